@@ -0,0 +1,95 @@
+//! Measures the memory used to fan messages out to many subscribers of a runner.
+//!
+//! `AsyncRunner::subscribe_all` copies every message for each subscriber,
+//! `AsyncRunner::subscribe_shared` gives each subscriber an `Arc` of the same one.
+//!
+//! Each case is printed as a line of JSON, so the report can be diffed or fed to other tools:
+//!
+//! ```text
+//! cargo run --release --example fanout --features testing -- 1000
+//! ```
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use twitchchat::{runner::Status, test::TestConnector, AsyncRunner, UserConfig};
+
+// counts every allocation made by the process
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const REGISTER: &str = ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                        :tmi.twitch.tv 376 justinfan1234 :>\r\n";
+
+const INPUT: &str = "@badge-info=subscriber/21;badges=broadcaster/1,subscriber/12,premium/1;color=#FF69B4;display-name=museun;emotes=25:0-4,12-16/1902:6-10;id=4a1e7a1f-0b9d-4b35-8a4d-3b4c3e5a0f1b;mod=0;room-id=23196011;tmi-sent-ts=1601079032426;user-id=23196011 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :Kappa Keepo Kappa test hell poop\r\n";
+
+async fn measure(api: &str, subscribers: usize, messages: usize) {
+    let connector = TestConnector::default();
+    let mut data = REGISTER.to_string();
+    data.push_str(&INPUT.repeat(messages));
+    connector.conn.write_data(data).await;
+
+    let config = UserConfig::builder().anonymous().build().unwrap();
+    let mut runner = AsyncRunner::connect(connector, &config).await.unwrap();
+
+    // the subscribers keep everything they were sent until the end
+    let (mut all, mut shared) = (vec![], vec![]);
+    for _ in 0..subscribers {
+        match api {
+            "subscribe_all" => all.push(runner.subscribe_all()),
+            _ => shared.push(runner.subscribe_shared()),
+        }
+    }
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = BYTES.load(Ordering::Relaxed);
+
+    let mut yielded = 0;
+    while let Ok(Status::Message(..)) = runner.next_message().await {
+        yielded += 1;
+    }
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let bytes = BYTES.load(Ordering::Relaxed) - bytes;
+
+    let report = serde_json::json!({
+        "api": api,
+        "subscribers": subscribers,
+        "messages": yielded,
+        "allocations_per_message": allocations as f64 / yielded as f64,
+        "bytes_per_message": bytes as f64 / yielded as f64,
+    });
+    println!("{}", report);
+}
+
+fn main() {
+    let messages = std::env::args()
+        .nth(1)
+        .map(|s| s.parse().expect("messages must be a number"))
+        .unwrap_or(100);
+
+    futures_lite::future::block_on(async {
+        for &subscribers in &[1, 10, 100, 500] {
+            measure("subscribe_all", subscribers, messages).await;
+            measure("subscribe_shared", subscribers, messages).await;
+        }
+    });
+}
@@ -0,0 +1,11 @@
+// `std::hint::black_box` needs Rust 1.66, this keeps the optimizer from
+// throwing `value` away on the versions before it too
+#[inline(never)]
+pub fn black_box<T>(value: T) -> T {
+    // SAFETY: `value` is forgotten, so the copy read from it is its only owner
+    unsafe {
+        let copy = std::ptr::read_volatile(&value);
+        std::mem::forget(value);
+        copy
+    }
+}
@@ -78,7 +78,7 @@ fn parse_demo() {
     // or it as a 'truthy' value
     assert_eq!(msg.tags().get_as_bool("key2"), true);
     // or as a FromStr parsed value
-    assert_eq!(msg.tags().get_parsed::<_, i32>("key3").unwrap(), 42);
+    assert_eq!(msg.tags().get_parsed::<_, i32>("key3").unwrap().unwrap(), 42);
 
     // you can convert a parsed message into an Commands easily by using From/Into;
     let all: messages::Commands<'_> = msg_b.into();
@@ -0,0 +1,130 @@
+//! Measures the allocations and time of the tag accessors of a `Privmsg`.
+//!
+//! The eager accessors (e.g. `Privmsg::badges()`) collect everything into an
+//! `AttributionVec`. The lazy variants parse each item on demand from the raw
+//! tag, which is what the iterator accessors do.
+//!
+//! Each case is printed as a line of JSON, so the report can be diffed or fed to other tools:
+//!
+//! ```text
+//! cargo run --release --example perf -- 100000
+//! ```
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+#[path = "include/black_box.rs"]
+mod black_box;
+use black_box::black_box;
+
+use twitchchat::{
+    messages::Privmsg,
+    twitch::{Badge, Emote, Flag},
+    FromIrcMessage as _,
+};
+
+// counts every allocation made by the process
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const INPUT: &str = "@badge-info=subscriber/21;badges=broadcaster/1,subscriber/12,premium/1;color=#FF69B4;display-name=museun;emotes=25:0-4,12-16/1902:6-10;flags=18-21:A.3/P.6,25-28:A.1;id=4a1e7a1f-0b9d-4b35-8a4d-3b4c3e5a0f1b;mod=0;room-id=23196011;tmi-sent-ts=1601079032426;user-id=23196011 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :Kappa Keepo Kappa test hell poop\r\n";
+
+fn measure(name: &str, api: &str, iterations: usize, mut f: impl FnMut() -> usize) {
+    // warm up, and make sure the case does something
+    let items = f();
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+
+    for _ in 0..iterations {
+        black_box(f());
+    }
+
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let bytes = BYTES.load(Ordering::Relaxed) - bytes;
+
+    let report = serde_json::json!({
+        "case": name,
+        "api": api,
+        "items": items,
+        "iterations": iterations,
+        "allocations_per_op": allocations as f64 / iterations as f64,
+        "bytes_per_op": bytes as f64 / iterations as f64,
+        "ns_per_op": elapsed.as_nanos() as f64 / iterations as f64,
+    });
+    println!("{}", report);
+}
+
+fn main() {
+    let iterations = std::env::args()
+        .nth(1)
+        .map(|s| s.parse().expect("iterations must be a number"))
+        .unwrap_or(10_000);
+
+    let msg = twitchchat::irc::parse(INPUT).next().unwrap().unwrap();
+    let pm = Privmsg::from_irc(msg).unwrap();
+
+    measure("badges", "eager", iterations, || pm.badges().len());
+    measure("badges", "lazy", iterations, || {
+        pm.tags()
+            .get("badges")
+            .into_iter()
+            .flat_map(|s| s.split(','))
+            .filter_map(|s| s.parse::<Badge>().ok())
+            .count()
+    });
+
+    measure("emotes", "eager", iterations, || pm.emotes().len());
+    measure("emotes", "lazy", iterations, || {
+        pm.tags()
+            .get("emotes")
+            .into_iter()
+            .flat_map(|s| s.split_terminator('/'))
+            .filter_map(|s| s.parse::<Emote>().ok())
+            .count()
+    });
+
+    measure("flags", "eager", iterations, || pm.flags().len());
+    measure("flags", "lazy", iterations, || {
+        pm.tags()
+            .get("flags")
+            .into_iter()
+            .flat_map(|s| s.split(','))
+            .filter_map(|s| s.parse::<Flag>().ok())
+            .count()
+    });
+
+    // looking for a single badge is where the lazy variant can stop early
+    measure("is_broadcaster", "eager", iterations, || {
+        pm.badges().contains(&Badge::Broadcaster) as usize
+    });
+    measure("is_broadcaster", "lazy", iterations, || {
+        pm.tags()
+            .get("badges")
+            .into_iter()
+            .flat_map(|s| s.split(','))
+            .filter_map(|s| s.parse::<Badge>().ok())
+            .any(|b| b == Badge::Broadcaster) as usize
+    });
+}
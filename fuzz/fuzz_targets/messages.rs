@@ -0,0 +1,23 @@
+//! Parse arbitrary input as messages, and use every part of them.
+//!
+//! Run with `cargo +nightly fuzz run messages` from the repository root.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use twitchchat::{messages::Commands, FromIrcMessage as _, IntoOwned as _};
+
+fuzz_target!(|data: &[u8]| {
+    let input = match std::str::from_utf8(data) {
+        Ok(input) => input,
+        Err(..) => return,
+    };
+
+    for msg in twitchchat::irc::parse(input).flatten() {
+        // the debug impls call every accessor of the message
+        let _ = format!("{:?}", msg);
+        if let Ok(msg) = Commands::from_irc(msg) {
+            let _ = format!("{:?}", msg);
+            let _ = msg.content_hash();
+            let _ = format!("{:?}", msg.into_owned());
+        }
+    }
+});
@@ -0,0 +1,321 @@
+/*!
+Alerts for the events of a [UserNotice], in the shape alert pipelines (overlays, bots, webhooks) expect.
+
+An [Alert] flattens the structured fields of a notice into one payload: the
+kind of event, the user, an amount and the message they sent with it.
+
+```
+# use twitchchat::{FromIrcMessage as _, alert::{Alert, AlertKind}, messages::UserNotice};
+let input = "@msg-id=resub;login=museun;display-name=Museun;user-id=23196011;msg-param-cumulative-months=12;msg-param-sub-plan=1000 :tmi.twitch.tv USERNOTICE #shaken_bot :one year!\r\n";
+let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+let msg = UserNotice::from_irc(msg).unwrap();
+
+let alert = Alert::from_user_notice(&msg).unwrap();
+assert_eq!(alert.kind, AlertKind::Resub);
+assert_eq!(alert.user.unwrap().display_name, "Museun");
+assert_eq!(alert.amount, Some(12));
+assert_eq!(alert.message.as_deref(), Some("one year!"));
+```
+
+With the `webhook` feature, an `AlertWebhook` POSTs them to an HTTP endpoint.
+*/
+
+use crate::messages::{NoticeType, UserNotice};
+use crate::twitch::MonetaryValue;
+
+#[cfg(feature = "webhook")]
+mod webhook;
+#[cfg(feature = "webhook")]
+#[cfg_attr(docsrs, doc(cfg(feature = "webhook")))]
+pub use webhook::{AlertWebhook, Error, PayloadFormat};
+
+/// The kind of event an [Alert] is for
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(::serde::Serialize, ::serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum AlertKind {
+    /// A new subscription
+    Sub,
+    /// A renewed subscription
+    Resub,
+    /// A gifted subscription, also for anonymous gifts
+    SubGift,
+    /// A number of subscriptions gifted to the chat
+    MysteryGift,
+    /// A gifted subscription that was continued, also for anonymous gifts
+    GiftUpgrade,
+    /// An incoming raid
+    Raid,
+    /// A new chatter, or another ritual
+    Ritual,
+    /// A user reached a new bits badge tier
+    BitsBadge,
+    /// A viewer reached a milestone, e.g. a watch streak
+    Milestone,
+    /// Any other kind of notice, see [Alert::msg_id]
+    Other,
+}
+
+impl AlertKind {
+    /// The kind of alert for this kind of notice
+    pub fn from_notice_type(kind: &NoticeType) -> Self {
+        match kind {
+            NoticeType::Sub => Self::Sub,
+            NoticeType::Resub => Self::Resub,
+            NoticeType::SubGift | NoticeType::AnonSubGift => Self::SubGift,
+            NoticeType::SubMysteryGift => Self::MysteryGift,
+            NoticeType::GiftPaidUpgrade | NoticeType::AnonGiftPaidUpgrade => Self::GiftUpgrade,
+            NoticeType::Raid => Self::Raid,
+            NoticeType::Ritual => Self::Ritual,
+            NoticeType::BitsBadgeTier => Self::BitsBadge,
+            NoticeType::ViewerMilestone => Self::Milestone,
+            _ => Self::Other,
+        }
+    }
+
+    /// The name of this kind, as it is serialized, e.g. `mystery_gift`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sub => "sub",
+            Self::Resub => "resub",
+            Self::SubGift => "sub_gift",
+            Self::MysteryGift => "mystery_gift",
+            Self::GiftUpgrade => "gift_upgrade",
+            Self::Raid => "raid",
+            Self::Ritual => "ritual",
+            Self::BitsBadge => "bits_badge",
+            Self::Milestone => "milestone",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl std::fmt::Display for AlertKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A user of an [Alert]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct AlertUser {
+    /// The login name of the user
+    pub login: String,
+    /// The display name of the user, the login name if they don't have one
+    pub display_name: String,
+    /// The user id, if it was sent
+    pub id: Option<u64>,
+}
+
+impl AlertUser {
+    fn new(login: Option<&str>, display_name: Option<&str>, id: Option<u64>) -> Option<Self> {
+        let login = login.filter(|login| !login.is_empty())?;
+        Some(Self {
+            login: login.to_string(),
+            display_name: display_name
+                .filter(|name| !name.is_empty())
+                .unwrap_or(login)
+                .to_string(),
+            id,
+        })
+    }
+}
+
+/// An alert for the event of a [UserNotice], see the [module docs](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Alert {
+    /// The kind of event
+    pub kind: AlertKind,
+    /// The `msg-id` of the notice, e.g. `anonsubgift`
+    pub msg_id: String,
+    /// The channel the event happened on
+    pub channel: String,
+    /// The user who caused the event (the raider for raids, the gifter for gifts)
+    pub user: Option<AlertUser>,
+    /// The user who received a gifted subscription
+    pub recipient: Option<AlertUser>,
+    /// The number the event is about:
+    /// * `sub`, `resub`: the total months subscribed
+    /// * `sub_gift`: the months gifted
+    /// * `mystery_gift`: the number of subscriptions gifted
+    /// * `raid`: the number of viewers
+    /// * `bits_badge`: the bits of the badge tier
+    /// * `milestone`: the value of the milestone
+    pub amount: Option<u64>,
+    /// The estimated value of the subscriptions, see [MonetaryValue::from_sub_plan]
+    pub value: Option<MonetaryValue>,
+    /// The message the user sent with the event
+    pub message: Option<String>,
+    /// The message Twitch prints in chat for the event
+    pub system_message: Option<String>,
+    /// When Twitch sent the notice, in milliseconds since the Unix epoch
+    pub timestamp: Option<u64>,
+}
+
+impl Alert {
+    /// Create an alert for this notice.
+    ///
+    /// The fields that are missing from the notice are `None`. This is only
+    /// `None` if the notice has no `msg-id` tag.
+    pub fn from_user_notice(msg: &UserNotice<'_>) -> Option<Self> {
+        let notice_type = msg.msg_id()?.ok()?;
+        let kind = AlertKind::from_notice_type(&notice_type);
+
+        let user = match notice_type {
+            NoticeType::AnonSubGift | NoticeType::AnonGiftPaidUpgrade => None,
+            _ => AlertUser::new(
+                msg.login(),
+                msg.display_name(),
+                msg.user_id().and_then(Result::ok),
+            ),
+        };
+
+        let recipient = match kind {
+            AlertKind::SubGift => AlertUser::new(
+                msg.msg_param_recipient_user_name(),
+                msg.msg_param_recipient_display_name(),
+                msg.msg_param_recipient_id().and_then(Result::ok),
+            ),
+            _ => None,
+        };
+
+        let (amount, value) = match kind {
+            AlertKind::Sub | AlertKind::Resub => (msg.months(), msg.sub_value()),
+            AlertKind::SubGift => (msg.gift_months(), msg.sub_value()),
+            AlertKind::MysteryGift => {
+                let count = msg
+                    .tags()
+                    .get_parsed::<_, u64>("msg-param-mass-gift-count")
+                    .and_then(Result::ok);
+                let value = msg.sub_value().and_then(|value| {
+                    let count = count?;
+                    Some(MonetaryValue::new(
+                        value.amount.checked_mul(count)?,
+                        value.exponent,
+                        value.currency,
+                    ))
+                });
+                (count, value)
+            }
+            AlertKind::Raid => (msg.msg_param_viewer_count().and_then(Result::ok), None),
+            AlertKind::BitsBadge => (msg.msg_param_threshold().and_then(Result::ok), None),
+            AlertKind::Milestone => (msg.msg_param_value().and_then(Result::ok), None),
+            _ => (None, None),
+        };
+
+        Some(Self {
+            kind,
+            msg_id: notice_type.to_string(),
+            channel: msg.channel().to_string(),
+            user,
+            recipient,
+            amount,
+            value,
+            message: msg.message().map(ToString::to_string),
+            system_message: msg.system_msg(),
+            timestamp: msg.tmi_sent_ts().and_then(Result::ok),
+        })
+    }
+
+    /// A line of text describing the alert, for chat-like destinations.
+    ///
+    /// This is the system message, or the kind and the user if there wasn't one.
+    pub fn text(&self) -> String {
+        if let Some(text) = &self.system_message {
+            return text.clone();
+        }
+        match &self.user {
+            Some(user) => format!("{}: {}", self.kind, user.display_name),
+            None => format!("{} on {}", self.kind, self.channel),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FromIrcMessage as _;
+
+    fn alert(input: &str) -> Option<Alert> {
+        let msg = crate::irc::parse(input).next().unwrap().unwrap();
+        Alert::from_user_notice(&UserNotice::from_irc(msg).unwrap())
+    }
+
+    #[test]
+    fn sub_gift() {
+        let alert = alert("@msg-id=subgift;login=museun;display-name=;user-id=1;msg-param-months=3;msg-param-gift-months=6;msg-param-recipient-user-name=shaken_bot;msg-param-recipient-display-name=Shaken_Bot;msg-param-recipient-id=2;msg-param-sub-plan=2000;tmi-sent-ts=1601079032426 :tmi.twitch.tv USERNOTICE #museun\r\n").unwrap();
+
+        assert_eq!(alert.kind, AlertKind::SubGift);
+        assert_eq!(alert.msg_id, "subgift");
+        assert_eq!(alert.channel, "#museun");
+        assert_eq!(
+            alert.user,
+            Some(AlertUser {
+                login: "museun".into(),
+                display_name: "museun".into(),
+                id: Some(1),
+            })
+        );
+        assert_eq!(alert.recipient.as_ref().unwrap().display_name, "Shaken_Bot");
+        assert_eq!(alert.amount, Some(6));
+        assert_eq!(alert.value, Some(MonetaryValue::new(999, 2, "USD")));
+        assert_eq!(alert.message, None);
+        assert_eq!(alert.timestamp, Some(1601079032426));
+        assert_eq!(alert.text(), "sub_gift: museun");
+    }
+
+    #[test]
+    fn anonymous_and_mystery_gifts() {
+        let alert_ = alert("@msg-id=anonsubgift;login=ananonymousgifter;msg-param-months=1;msg-param-recipient-user-name=museun;msg-param-sub-plan=1000 :tmi.twitch.tv USERNOTICE #museun\r\n").unwrap();
+        assert_eq!(alert_.kind, AlertKind::SubGift);
+        assert_eq!(alert_.user, None);
+        assert_eq!(alert_.recipient.as_ref().unwrap().login, "museun");
+        assert_eq!(alert_.amount, Some(1));
+        assert_eq!(alert_.text(), "sub_gift on #museun");
+
+        let alert_ = alert("@msg-id=submysterygift;login=museun;msg-param-mass-gift-count=5;msg-param-sub-plan=1000;system-msg=museun\\sis\\sgifting\\s5\\sTier\\s1\\sSubs! :tmi.twitch.tv USERNOTICE #museun\r\n").unwrap();
+        assert_eq!(alert_.kind, AlertKind::MysteryGift);
+        assert_eq!(alert_.amount, Some(5));
+        assert_eq!(alert_.value, Some(MonetaryValue::new(2495, 2, "USD")));
+        assert_eq!(alert_.text(), "museun is gifting 5 Tier 1 Subs!");
+    }
+
+    #[test]
+    fn other_kinds() {
+        let raid = alert("@msg-id=raid;login=museun;msg-param-viewerCount=42 :tmi.twitch.tv USERNOTICE #shaken_bot\r\n").unwrap();
+        assert_eq!(
+            (raid.kind, raid.amount, raid.value),
+            (AlertKind::Raid, Some(42), None)
+        );
+
+        let unknown =
+            alert("@msg-id=charitydonation;login=museun :tmi.twitch.tv USERNOTICE #museun\r\n")
+                .unwrap();
+        assert_eq!(unknown.kind, AlertKind::Other);
+        assert_eq!(unknown.msg_id, "charitydonation");
+        assert_eq!(unknown.amount, None);
+
+        assert!(alert(":tmi.twitch.tv USERNOTICE #museun\r\n").is_none());
+    }
+
+    #[test]
+    #[cfg(all(feature = "serde", feature = "serde_json"))]
+    fn json() {
+        let alert = alert("@msg-id=sub;login=museun;msg-param-cumulative-months=1;msg-param-sub-plan=Prime :tmi.twitch.tv USERNOTICE #museun :hi\r\n").unwrap();
+        let json = serde_json::to_value(&alert).unwrap();
+        assert_eq!(json["kind"], "sub");
+        assert_eq!(json["user"]["login"], "museun");
+        assert_eq!(json["amount"], 1);
+        assert_eq!(json["message"], "hi");
+        assert_eq!(json["value"]["amount"], 499);
+
+        let back: Alert = serde_json::from_value(json).unwrap();
+        assert_eq!(back, alert);
+    }
+}
@@ -0,0 +1,219 @@
+use super::Alert;
+use crate::connector::Connector;
+
+/// An error returned by the [AlertWebhook]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An I/O error occurred
+    Io(std::io::Error),
+    /// The response was not a valid HTTP response
+    InvalidResponse,
+    /// The endpoint responded with an unexpected HTTP status
+    Status(u16),
+    /// The alert could not be serialized
+    Json(serde_json::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io error: {}", err),
+            Self::InvalidResponse => write!(f, "invalid HTTP response"),
+            Self::Status(status) => write!(f, "unexpected HTTP status: {}", status),
+            Self::Json(err) => write!(f, "cannot serialize the alert: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+/// The shape of the JSON body an [AlertWebhook] sends
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PayloadFormat {
+    /// The [Alert] itself, e.g. `{"kind": "raid", "user": {..}, "amount": 42, ..}`
+    Json,
+    /// A Discord webhook message, `{"content": ".."}` with the [text](Alert::text) of the alert
+    Discord,
+    /// A Slack incoming webhook message, `{"text": ".."}` with the [text](Alert::text) of the alert
+    Slack,
+}
+
+impl Default for PayloadFormat {
+    fn default() -> Self {
+        Self::Json
+    }
+}
+
+impl PayloadFormat {
+    /// The body for this alert, in this format
+    ///
+    /// ```
+    /// # use twitchchat::{FromIrcMessage as _, alert::{Alert, PayloadFormat}, messages::UserNotice};
+    /// let input = "@msg-id=raid;login=museun;msg-param-viewerCount=42 :tmi.twitch.tv USERNOTICE #shaken_bot\r\n";
+    /// let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+    /// let alert = Alert::from_user_notice(&UserNotice::from_irc(msg).unwrap()).unwrap();
+    ///
+    /// let json = PayloadFormat::Json.payload(&alert)?;
+    /// assert_eq!(json["kind"], "raid");
+    /// assert_eq!(json["amount"], 42);
+    ///
+    /// let discord = PayloadFormat::Discord.payload(&alert)?;
+    /// assert_eq!(discord, serde_json::json!({ "content": "raid: museun" }));
+    /// # Ok::<_, serde_json::Error>(())
+    /// ```
+    pub fn payload(&self, alert: &Alert) -> Result<serde_json::Value, serde_json::Error> {
+        Ok(match self {
+            Self::Json => serde_json::to_value(alert)?,
+            Self::Discord => serde_json::json!({ "content": alert.text() }),
+            Self::Slack => serde_json::json!({ "text": alert.text() }),
+        })
+    }
+}
+
+/// Sends [Alert]s to a webhook with an HTTP POST.
+///
+/// This speaks plain HTTP over whatever stream the [Connector] returns, so
+/// for an `https://` webhook you want a TLS connector to its host.
+///
+/// ```no_run
+/// # use twitchchat::{connector::Connector, alert::{Alert, AlertWebhook, PayloadFormat}};
+/// # async fn demo(connector: impl Connector, alert: Alert) -> Result<(), twitchchat::alert::Error> {
+/// let mut webhook = AlertWebhook::new(connector, "discord.com", "/api/webhooks/1234/token")
+///     .with_format(PayloadFormat::Discord);
+/// webhook.send(&alert).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AlertWebhook<C> {
+    connector: C,
+    host: String,
+    path: String,
+    format: PayloadFormat,
+    headers: Vec<(String, String)>,
+}
+
+impl<C> AlertWebhook<C>
+where
+    C: Connector,
+{
+    /// Create a webhook that POSTs to this path, sending this `Host` header
+    pub fn new(connector: C, host: impl Into<String>, path: impl Into<String>) -> Self {
+        Self {
+            connector,
+            host: host.into(),
+            path: path.into(),
+            format: PayloadFormat::default(),
+            headers: vec![],
+        }
+    }
+
+    /// Send the alerts in this format, [PayloadFormat::Json] by default
+    pub fn with_format(mut self, format: PayloadFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Send this header with every request, e.g. a secret the endpoint checks
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// POST this alert to the webhook. Any `2xx` status is a success
+    pub async fn send(&mut self, alert: &Alert) -> Result<(), Error> {
+        let body = self.format.payload(alert)?.to_string();
+
+        let mut request = format!(
+            "POST {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: {}\r\nConnection: close\r\n",
+            self.path,
+            self.host,
+            crate::http::user_agent(),
+        );
+        for (name, value) in &self.headers {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        request.push_str(&format!(
+            "Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        ));
+
+        let response = crate::http::send(&mut self.connector, &request).await?;
+        let (status, _) = crate::http::split_response(&response).ok_or(Error::InvalidResponse)?;
+
+        if !(200..300).contains(&status) {
+            return Err(Error::Status(status));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::{messages::UserNotice, FromIrcMessage as _};
+
+    #[test]
+    fn send() {
+        let input = "@msg-id=raid;login=museun;msg-param-viewerCount=42 :tmi.twitch.tv USERNOTICE #shaken_bot\r\n";
+        let msg = crate::irc::parse(input).next().unwrap().unwrap();
+        let alert = Alert::from_user_notice(&UserNotice::from_irc(msg).unwrap()).unwrap();
+
+        let connector = crate::test::TestConnector::default();
+        let mut webhook = AlertWebhook::new(connector.clone(), "example.com", "/hooks/alerts")
+            .with_format(PayloadFormat::Slack)
+            .with_header("X-Secret", "hunter2");
+
+        futures_lite::future::block_on(async {
+            connector
+                .conn
+                .write_data("HTTP/1.1 204 No Content\r\n\r\n")
+                .await;
+            webhook.send(&alert).await
+        })
+        .unwrap();
+
+        let request = futures_lite::future::block_on(connector.conn.read_all_lines()).unwrap();
+        assert_eq!(request[0], "POST /hooks/alerts HTTP/1.0\r\n");
+        assert_eq!(request[1], "Host: example.com\r\n");
+        assert!(request.contains(&"X-Secret: hunter2\r\n".to_string()));
+        assert!(request
+            .last()
+            .unwrap()
+            .trim_end()
+            .ends_with(r#"{"text":"raid: museun"}"#));
+
+        let err = futures_lite::future::block_on(async {
+            connector
+                .conn
+                .write_data("HTTP/1.1 404 Not Found\r\n\r\n")
+                .await;
+            webhook.send(&alert).await
+        })
+        .unwrap_err();
+        assert!(matches!(err, Error::Status(404)));
+    }
+}
@@ -0,0 +1,372 @@
+/*!
+An audit log of the moderation of channels, written as JSON lines.
+
+An [AuditLog] observes every message the bot receives, and writes an
+[AuditRecord] for each ban, timeout, cleared chat, deleted message and change
+of the room settings. Twitch doesn't say what a ban removed, so the log keeps
+the recent chat of each channel in a [ContextBuffer] and records the messages
+the user sent before it.
+
+```
+# use twitchchat::{FromIrcMessage as _, audit::AuditLog, messages::{Commands, ContextBuffer}};
+# use std::time::Duration;
+let parse = |input| {
+    let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+    Commands::from_irc(msg).unwrap()
+};
+
+let mut log = AuditLog::new(vec![], ContextBuffer::new(100, Duration::from_secs(60 * 10)));
+
+log.observe(&parse("@id=1 :foo!foo@foo PRIVMSG #museun :buy followers\r\n"))?;
+log.observe(&parse("@tmi-sent-ts=1000 :tmi.twitch.tv CLEARCHAT #museun :foo\r\n"))?;
+
+let out = String::from_utf8(log.into_inner()).unwrap();
+assert!(out.starts_with(r##"{"timestamp":1000,"channel":"#museun","event":"ban","user":"foo""##));
+assert!(out.ends_with("\n"));
+# Ok::<_, std::io::Error>(())
+```
+*/
+
+use crate::messages::{
+    ChannelInfoCache, ChannelInfoChange, ChannelSettings, ClearChat, ClearMsg, Commands,
+    ContextBuffer, ContextEntry,
+};
+use std::io::Write;
+
+/// A chat message that was removed by a moderation event
+#[derive(Debug, Clone, PartialEq, Eq, ::serde::Serialize, ::serde::Deserialize)]
+pub struct RemovedMessage {
+    /// The login of the user who sent it
+    pub user: String,
+    /// The id of the message, from its `id` tag
+    pub msg_id: Option<String>,
+    /// When Twitch sent it, in milliseconds since the unix epoch
+    pub tmi_sent_ts: Option<u64>,
+    /// What the user said
+    pub data: String,
+}
+
+impl From<&ContextEntry> for RemovedMessage {
+    fn from(entry: &ContextEntry) -> Self {
+        let msg = &entry.message;
+        Self {
+            user: msg.name().to_string(),
+            msg_id: msg.tags().get("id").map(ToString::to_string),
+            tmi_sent_ts: msg.tmi_sent_ts().and_then(Result::ok),
+            data: msg.data().to_string(),
+        }
+    }
+}
+
+/// What happened on a channel, see [AuditRecord]
+#[derive(Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum AuditEvent {
+    /// A user was banned
+    Ban {
+        /// The login of the user
+        user: String,
+        /// The id of the user, from the `target-user-id` tag
+        user_id: Option<u64>,
+        /// The messages of the user that were in the context buffer
+        removed: Vec<RemovedMessage>,
+    },
+    /// A user was timed out
+    Timeout {
+        /// The login of the user
+        user: String,
+        /// The id of the user, from the `target-user-id` tag
+        user_id: Option<u64>,
+        /// How long the user was timed out for, in seconds
+        duration: u64,
+        /// The messages of the user that were in the context buffer
+        removed: Vec<RemovedMessage>,
+    },
+    /// The whole chat was cleared
+    ChatCleared,
+    /// A single message was deleted
+    MessageDeleted {
+        /// The login of the user who sent it
+        user: Option<String>,
+        /// The id of the message
+        msg_id: Option<String>,
+        /// The message, if it was in the context buffer
+        removed: Option<RemovedMessage>,
+    },
+    /// The chat settings changed
+    ///
+    /// Twitch sends all of the settings when a channel is joined, so there is
+    /// also one of these for each channel when the bot joins it
+    SettingsChanged {
+        /// The settings after the change
+        settings: ChannelSettings,
+    },
+}
+
+/// A line of the [AuditLog]
+#[derive(Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+pub struct AuditRecord {
+    /// When it happened, in milliseconds since the unix epoch
+    ///
+    /// This is the `tmi-sent-ts` tag of the message, or when it was received if it has none
+    pub timestamp: u64,
+    /// The channel it happened on
+    pub channel: String,
+    /// What happened
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+/// Writes an [AuditRecord] for each moderation event to a writer, one JSON object per line.
+///
+/// See the [module docs](self) for an example
+#[derive(Debug)]
+pub struct AuditLog<W> {
+    writer: W,
+    context: ContextBuffer,
+    channels: ChannelInfoCache,
+    written: u64,
+}
+
+impl<W: Write> AuditLog<W> {
+    /// Write the records to this writer, inferring removed messages from this context buffer
+    pub fn new(writer: W, context: ContextBuffer) -> Self {
+        Self {
+            writer,
+            context,
+            channels: ChannelInfoCache::default(),
+            written: 0,
+        }
+    }
+
+    /// Observe a message, writing the records for it.
+    ///
+    /// Chat messages are kept in the context buffer. This returns the records
+    /// that were written, which is empty for messages that aren't moderation events
+    pub fn observe(&mut self, msg: &Commands<'_>) -> std::io::Result<Vec<AuditRecord>> {
+        let records = self.records_for(msg);
+        for record in &records {
+            self.write(record)?;
+        }
+        self.context.observe(msg);
+        Ok(records)
+    }
+
+    /// Write a record, e.g. one from another source
+    pub fn write(&mut self, record: &AuditRecord) -> std::io::Result<()> {
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.writer.write_all(b"\n")?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Flush the writer
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// How many records were written
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Get the context buffer, e.g. to prune it
+    pub fn context(&mut self) -> &mut ContextBuffer {
+        &mut self.context
+    }
+
+    /// Get the writer back
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn records_for(&mut self, msg: &Commands<'_>) -> Vec<AuditRecord> {
+        let channel = match msg.channel() {
+            Some(channel) => channel.to_string(),
+            None => return vec![],
+        };
+
+        let events = match msg {
+            Commands::ClearChat(msg) => vec![self.clear_chat(msg)],
+            Commands::ClearMsg(msg) => vec![self.clear_msg(msg)],
+            Commands::RoomState(..) => self
+                .channels
+                .observe(msg)
+                .into_iter()
+                .filter_map(|change| match change {
+                    ChannelInfoChange::Settings(settings) => {
+                        Some(AuditEvent::SettingsChanged { settings })
+                    }
+                    _ => None,
+                })
+                .collect(),
+            _ => return vec![],
+        };
+
+        let timestamp = match msg {
+            Commands::ClearChat(msg) => msg.tags().get_parsed("tmi-sent-ts"),
+            Commands::ClearMsg(msg) => msg.tags().get_parsed("tmi-sent-ts"),
+            _ => None,
+        }
+        .and_then(Result::ok)
+        .unwrap_or_else(now_millis);
+
+        events
+            .into_iter()
+            .map(|event| AuditRecord {
+                timestamp,
+                channel: channel.clone(),
+                event,
+            })
+            .collect()
+    }
+
+    fn clear_chat(&self, msg: &ClearChat<'_>) -> AuditEvent {
+        let user = match msg.name() {
+            Some(user) => user,
+            None => return AuditEvent::ChatCleared,
+        };
+
+        let user_id = msg.tags().get_parsed("target-user-id").and_then(Result::ok);
+        let removed = self
+            .context
+            .by_user(msg.channel(), user)
+            .map(RemovedMessage::from)
+            .collect();
+
+        match msg.ban_duration().and_then(Result::ok) {
+            Some(duration) => AuditEvent::Timeout {
+                user: user.to_string(),
+                user_id,
+                duration,
+                removed,
+            },
+            None => AuditEvent::Ban {
+                user: user.to_string(),
+                user_id,
+                removed,
+            },
+        }
+    }
+
+    fn clear_msg(&self, msg: &ClearMsg<'_>) -> AuditEvent {
+        AuditEvent::MessageDeleted {
+            user: msg.login().map(ToString::to_string),
+            msg_id: msg.target_msg_id().map(ToString::to_string),
+            removed: msg
+                .target_msg_id()
+                .and_then(|id| self.context.by_msg_id(msg.channel(), id))
+                .map(RemovedMessage::from),
+        }
+    }
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|dur| dur.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{irc::parse, FromIrcMessage as _};
+    use std::time::Duration;
+
+    fn log(input: &str) -> (Vec<AuditRecord>, Vec<String>) {
+        let mut log = AuditLog::new(vec![], ContextBuffer::new(10, Duration::from_secs(60)));
+        let mut records = vec![];
+        for msg in parse(input) {
+            let msg = Commands::from_irc(msg.unwrap()).unwrap();
+            records.extend(log.observe(&msg).unwrap());
+        }
+        assert_eq!(log.written(), records.len() as u64);
+
+        let out = String::from_utf8(log.into_inner()).unwrap();
+        let lines = out.lines().map(ToString::to_string).collect();
+        (records, lines)
+    }
+
+    #[test]
+    fn bans_and_timeouts() {
+        let input = "@id=a;tmi-sent-ts=1 :foo!foo@foo PRIVMSG #museun :first\r\n\
+                     @id=b :bar!bar@bar PRIVMSG #museun :hello\r\n\
+                     @id=c :foo!foo@foo PRIVMSG #museun :second\r\n\
+                     @id=d :foo!foo@foo PRIVMSG #other :elsewhere\r\n\
+                     @ban-duration=600;target-user-id=42;tmi-sent-ts=100 :tmi.twitch.tv CLEARCHAT #museun :foo\r\n\
+                     @tmi-sent-ts=200 :tmi.twitch.tv CLEARCHAT #museun :bar\r\n\
+                     @tmi-sent-ts=300 :tmi.twitch.tv CLEARCHAT #museun\r\n";
+
+        let (records, lines) = log(input);
+        assert_eq!(records.len(), 3);
+        assert_eq!(lines.len(), 3);
+
+        match &records[0] {
+            AuditRecord {
+                timestamp: 100,
+                channel,
+                event:
+                    AuditEvent::Timeout {
+                        user,
+                        user_id: Some(42),
+                        duration: 600,
+                        removed,
+                    },
+            } if channel == "#museun" && user == "foo" => {
+                let data = removed.iter().map(|msg| &*msg.data).collect::<Vec<_>>();
+                assert_eq!(data, vec!["first", "second"]);
+                assert_eq!(removed[0].msg_id.as_deref(), Some("a"));
+                assert_eq!(removed[0].tmi_sent_ts, Some(1));
+            }
+            record => panic!("unexpected record: {:?}", record),
+        }
+
+        assert_eq!(
+            lines[1],
+            r##"{"timestamp":200,"channel":"#museun","event":"ban","user":"bar","user_id":null,"removed":[{"user":"bar","msg_id":"b","tmi_sent_ts":null,"data":"hello"}]}"##
+        );
+        assert_eq!(
+            lines[2],
+            r##"{"timestamp":300,"channel":"#museun","event":"chat_cleared"}"##
+        );
+
+        for (record, line) in records.iter().zip(&lines) {
+            assert_eq!(&serde_json::from_str::<AuditRecord>(line).unwrap(), record);
+        }
+    }
+
+    #[test]
+    fn deleted_messages() {
+        let input = "@id=a :foo!foo@foo PRIVMSG #museun :buy followers\r\n\
+                     @login=foo;target-msg-id=a;tmi-sent-ts=10 :tmi.twitch.tv CLEARMSG #museun :buy followers\r\n\
+                     @login=bar;target-msg-id=z;tmi-sent-ts=20 :tmi.twitch.tv CLEARMSG #museun :hello\r\n";
+
+        let (records, _) = log(input);
+        assert!(matches!(
+            &records[0].event,
+            AuditEvent::MessageDeleted { removed: Some(msg), .. } if msg.data == "buy followers"
+        ));
+        // it wasn't in the context buffer
+        assert!(matches!(
+            &records[1].event,
+            AuditEvent::MessageDeleted { msg_id: Some(id), removed: None, .. } if id == "z"
+        ));
+    }
+
+    #[test]
+    fn settings() {
+        let input = "@emote-only=0;followers-only=-1;r9k=0;room-id=1;slow=0;subs-only=0 :tmi.twitch.tv ROOMSTATE #museun\r\n\
+                     @room-id=1;slow=30 :tmi.twitch.tv ROOMSTATE #museun\r\n\
+                     @room-id=1;slow=30 :tmi.twitch.tv ROOMSTATE #museun\r\n";
+
+        let (records, lines) = log(input);
+        assert_eq!(records.len(), 2);
+        match &records[1].event {
+            AuditEvent::SettingsChanged { settings } => assert_eq!(settings.slow, 30),
+            event => panic!("unexpected event: {:?}", event),
+        }
+        assert!(lines[1].contains(r#""event":"settings_changed","settings":{"#));
+    }
+}
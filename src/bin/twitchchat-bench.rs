@@ -0,0 +1,105 @@
+//! Measures how fast a log file of raw lines is parsed into typed messages.
+//!
+//! This is built with the `bin-bench` feature:
+//!
+//! ```text
+//! cargo run --release --features bin-bench --bin twitchchat-bench -- chat.log 10
+//! ```
+//!
+//! The file is read once, and then parsed the given number of times (`1` by
+//! default). Each pass is printed as a line of JSON, so runs can be diffed or
+//! fed to other tools. Lines that aren't valid UTF-8 are read as Latin-1 or
+//! lossily, like [parse_bytes](twitchchat::irc::parse_bytes) does.
+use std::time::Instant;
+
+#[path = "../../examples/include/black_box.rs"]
+mod black_box;
+use black_box::black_box;
+use twitchchat::{messages::Commands, metrics::Metrics, FromIrcMessage as _};
+
+struct Pass {
+    lines: u64,
+    typed: u64,
+    invalid: u64,
+}
+
+// parse every line, and then into its message type
+fn parse(data: &[u8]) -> Pass {
+    let mut pass = Pass {
+        lines: 0,
+        typed: 0,
+        invalid: 0,
+    };
+    for msg in twitchchat::irc::parse_bytes(data).detect_latin1(true) {
+        pass.lines += 1;
+        let msg = match msg {
+            Ok(msg) => msg,
+            Err(..) => {
+                pass.invalid += 1;
+                continue;
+            }
+        };
+        match Commands::from_irc(msg) {
+            Ok(Commands::Raw(..)) => {}
+            Ok(msg) => {
+                black_box(msg);
+                pass.typed += 1;
+            }
+            Err(..) => pass.invalid += 1,
+        }
+    }
+    pass
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let path = match args.next() {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: twitchchat-bench <log file> [passes]");
+            std::process::exit(2)
+        }
+    };
+    let passes = match args.next() {
+        Some(passes) => passes.parse()?,
+        None => 1_u32,
+    };
+
+    let data = std::fs::read(&path)?;
+
+    for n in 0..passes {
+        let start = Instant::now();
+        let pass = parse(&data);
+        let secs = start.elapsed().as_secs_f64();
+
+        let report = serde_json::json!({
+            "pass": n + 1,
+            "lines": pass.lines,
+            "typed": pass.typed,
+            "invalid": pass.invalid,
+            "secs": secs,
+            "lines_per_sec": pass.lines as f64 / secs,
+            "mb_per_sec": data.len() as f64 / secs / (1024.0 * 1024.0),
+        });
+        println!("{}", report);
+    }
+
+    // what the log is like, outside of the timed passes
+    let metrics = Metrics::default();
+    for msg in twitchchat::irc::parse_bytes(&data)
+        .detect_latin1(true)
+        .flatten()
+    {
+        metrics.observe(&msg);
+    }
+    let metrics = metrics.snapshot();
+    let report = serde_json::json!({
+        "line_length_mean": metrics.line_length.mean(),
+        "line_length_max": metrics.line_length.max,
+        "tag_count_mean": metrics.tag_count.mean(),
+        "emote_count_max": metrics.emote_count.max,
+        "attribution_errors": metrics.attribution_errors,
+    });
+    println!("{}", report);
+    Ok(())
+}
@@ -0,0 +1,189 @@
+//! Pretty-prints the chat of some channels, until it is stopped or the connection ends.
+//!
+//! This is built with the `bin-tail` feature:
+//!
+//! ```text
+//! cargo run --features bin-tail --bin twitchchat-tail -- museun shaken_bot
+//! ```
+//!
+//! It reads anonymously, unless `TWITCH_NAME` and `TWITCH_TOKEN` are set.
+//! With `--limit <n>` it stops after `n` chat events, and then prints a summary
+//! of the session and the metrics of the messages it received.
+use twitchchat::{
+    connector::SmolConnector,
+    messages::{ChatEvent, ChatUser, ModAction, Segment},
+    AsyncRunner, Status, UserConfig,
+};
+
+struct Args {
+    channels: Vec<String>,
+    limit: Option<u64>,
+}
+
+fn usage() -> ! {
+    eprintln!("usage: twitchchat-tail [--limit <n>] <channel>...");
+    std::process::exit(2)
+}
+
+fn parse_args() -> Args {
+    let mut args = std::env::args().skip(1);
+    let mut channels = vec![];
+    let mut limit = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--limit" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => limit = Some(n),
+                None => usage(),
+            },
+            "-h" | "--help" => usage(),
+            _ => channels.push(arg),
+        }
+    }
+    if channels.is_empty() {
+        usage()
+    }
+    Args { channels, limit }
+}
+
+fn user_config() -> Result<UserConfig, Box<dyn std::error::Error>> {
+    let builder = match (std::env::var("TWITCH_NAME"), std::env::var("TWITCH_TOKEN")) {
+        (Ok(name), Ok(token)) => UserConfig::builder().name(name).token(token),
+        _ => UserConfig::builder().anonymous(),
+    };
+    Ok(builder.enable_all_capabilities().build()?)
+}
+
+// the name of the user, in their color
+fn name(user: &ChatUser) -> String {
+    match &user.color {
+        Some(color) => {
+            let rgb = color.rgb;
+            format!(
+                "\x1b[38;2;{};{};{}m{}\x1b[0m",
+                rgb.0, rgb.1, rgb.2, user.display_name
+            )
+        }
+        None => user.display_name.clone(),
+    }
+}
+
+// emotes are underlined
+fn body(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => text.clone(),
+            Segment::Emote { text, .. } => format!("\x1b[4m{}\x1b[0m", text),
+        })
+        .collect()
+}
+
+fn format(event: &ChatEvent) -> Option<String> {
+    let line = match event {
+        ChatEvent::Message {
+            channel,
+            user,
+            body: segments,
+            is_action: true,
+            ..
+        } => format!("{} * {} {}", channel, name(user), body(segments)),
+        ChatEvent::Message {
+            channel,
+            user,
+            body: segments,
+            bits,
+            ..
+        } => match bits {
+            Some(bits) => format!(
+                "{} <{}> ({} bits) {}",
+                channel,
+                name(user),
+                bits,
+                body(segments)
+            ),
+            None => format!("{} <{}> {}", channel, name(user), body(segments)),
+        },
+        ChatEvent::SystemNotice {
+            channel,
+            text,
+            body: segments,
+            ..
+        } if segments.is_empty() => format!("{} -!- {}", channel, text),
+        ChatEvent::SystemNotice {
+            channel,
+            text,
+            body: segments,
+            ..
+        } => format!("{} -!- {}: {}", channel, text, body(segments)),
+        ChatEvent::ModAction { channel, action } => {
+            let action = match action {
+                ModAction::ClearChat => "the chat was cleared".to_string(),
+                ModAction::Ban { login } => format!("{} was banned", login),
+                ModAction::Timeout { login, seconds } => {
+                    format!("{} was timed out for {}s", login, seconds)
+                }
+                ModAction::Delete {
+                    login: Some(login), ..
+                } => format!("a message of {} was deleted", login),
+                _ => "a message was deleted".to_string(),
+            };
+            format!("{} -!- {}", channel, action)
+        }
+        ChatEvent::Presence {
+            channel,
+            login,
+            joined,
+        } => format!(
+            "{} {} {}",
+            channel,
+            if *joined { "-->" } else { "<--" },
+            login
+        ),
+        _ => return None,
+    };
+    Some(line)
+}
+
+async fn tail(args: Args, user_config: &UserConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let connector = SmolConnector::twitch()?;
+    let mut runner = AsyncRunner::connect(connector, user_config).await?;
+    eprintln!("connected as {}", runner.identity.username());
+
+    for channel in &args.channels {
+        runner.join(channel).await?;
+    }
+
+    let mut seen = 0;
+    loop {
+        let msg = match runner.next_message().await? {
+            Status::Message(msg) => msg,
+            Status::Quit | Status::Eof => break,
+        };
+
+        if let Some(line) = ChatEvent::from_commands(&msg).as_ref().and_then(format) {
+            println!("{}", line);
+            seen += 1;
+        }
+
+        if matches!(args.limit, Some(limit) if seen >= limit) {
+            break;
+        }
+    }
+
+    let metrics = runner.metrics().snapshot();
+    eprintln!("{}", runner.session_summary());
+    eprintln!(
+        "{} lines, {:.1} bytes and {:.1} tags on average, {} parse errors",
+        metrics.line_length.count,
+        metrics.line_length.mean().unwrap_or_default(),
+        metrics.tag_count.mean().unwrap_or_default(),
+        metrics.parse_errors.values().sum::<u64>(),
+    );
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = parse_args();
+    let user_config = user_config()?;
+    smol::block_on(tail(args, &user_config))
+}
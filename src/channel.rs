@@ -49,6 +49,9 @@ impl<T> Sender<T> {
 
 pin_project_lite::pin_project! {
     /// Async and Sync MPMP Receiver.
+    ///
+    /// This is a `futures::Stream`, so the subscriptions of the runner work with
+    /// `StreamExt` combinators and `select!`.
     #[derive(Clone)]
     pub struct Receiver<T> {
         #[pin]
@@ -60,10 +60,12 @@ macro_rules! export_commands {
 
 export_commands! {
     ban             => Ban
+    cap_req         => CapReq
     clear           => Clear
     color           => Color
     command         => Command
     commercial      => Commercial
+    delete          => Delete
     disconnect      => Disconnect
     emote_only      => EmoteOnly
     emote_only_off  => EmoteOnlyOff
@@ -73,6 +75,7 @@ export_commands! {
     help            => Help
     host            => Host
     join            => Join
+    join_many       => JoinMany
     jtv_command     => JtvCommand
     marker          => Marker
     me              => Me
@@ -101,6 +104,7 @@ export_commands! {
     vip             => Vip
     vips            => Vips
     whisper         => Whisper
+    with_nonce      => WithNonce
 }
 
 macro_rules! serde_for_commands {
@@ -136,11 +140,13 @@ macro_rules! serde_for_commands {
 
 serde_for_commands! {
     Ban { channel, username, reason };
+    CapReq { caps };
     Clear { channel };
     Color { color };
     Command { channel, data };
     JtvCommand { data };
     Commercial { channel, length };
+    Delete { channel, msg_id };
     Disconnect { };
     EmoteOnly { channel };
     EmoteOnlyOff { channel };
@@ -150,6 +156,7 @@ serde_for_commands! {
     Help { channel };
     Host { source, target };
     Join { channel };
+    JoinMany { channels };
     Marker { channel, comment };
     Me { channel, msg };
     Mods { channel };
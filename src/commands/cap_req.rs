@@ -0,0 +1,64 @@
+use super::Encodable;
+use crate::twitch::Capability;
+use std::io::{Result, Write};
+
+/// Request many capabilities with a single `CAP REQ`.
+#[non_exhaustive]
+#[must_use = "commands must be encoded"]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Deserialize))]
+pub struct CapReq<'a> {
+    pub(crate) caps: Vec<Capability>,
+    #[cfg_attr(feature = "serde", serde(skip, default))]
+    _marker: std::marker::PhantomData<&'a ()>,
+}
+
+/// Request many capabilities with a single `CAP REQ`.
+///
+/// Twitch acknowledges or refuses all of them at once, so if one of them is
+/// refused, none of them are enabled. This writes nothing if `caps` is empty.
+pub fn cap_req(caps: &[Capability]) -> CapReq<'_> {
+    CapReq {
+        caps: caps.to_vec(),
+        _marker: std::marker::PhantomData,
+    }
+}
+
+impl<'a> Encodable for CapReq<'a> {
+    fn encode<W: Write + ?Sized>(&self, buf: &mut W) -> Result<()> {
+        if self.caps.is_empty() {
+            return Ok(());
+        }
+
+        write!(buf, "CAP REQ :")?;
+        for (i, cap) in self.caps.iter().enumerate() {
+            let sep = if i == 0 { "" } else { " " };
+            write!(buf, "{}{}", sep, cap.name())?;
+        }
+        write!(buf, "\r\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use super::*;
+
+    #[test]
+    fn cap_req_encode() {
+        test_encode(
+            cap_req(&[Capability::Tags, Capability::Commands]),
+            "CAP REQ :twitch.tv/tags twitch.tv/commands\r\n",
+        );
+        test_encode(cap_req(&[]), "");
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn cap_req_serde() {
+        test_serde(
+            cap_req(&[Capability::Membership]),
+            "CAP REQ :twitch.tv/membership\r\n",
+        );
+    }
+}
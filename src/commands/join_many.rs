@@ -0,0 +1,144 @@
+use super::{Channel, Encodable};
+use std::io::{Result, Write};
+
+/// Join many channels, using as few lines as possible. This handles prepending a leading '#' for you if you omit it.
+#[non_exhaustive]
+#[must_use = "commands must be encoded"]
+#[derive(Debug, Clone, PartialEq, Ord, PartialOrd, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Deserialize))]
+pub struct JoinMany<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub(crate) channels: Vec<&'a str>,
+}
+
+/// Join many channels, using as few lines as possible. This handles prepending a leading '#' for you if you omit it.
+///
+/// Channels are comma-separated, and a new `JOIN` line is started whenever a
+/// line would be longer than [JoinMany::MAX_LINE_LENGTH].
+///
+/// Twitch counts every channel against the join rate limit, not every line. If
+/// you're throttling joins, use [JoinMany::chunks] to split the channels into
+/// batches that fit your limit, and consume [JoinMany::len] tokens for each batch.
+pub fn join_many<'a, I>(channels: I) -> JoinMany<'a>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    JoinMany {
+        channels: channels.into_iter().collect(),
+    }
+}
+
+impl<'a> JoinMany<'a> {
+    /// The maximum length of an IRC line, including the trailing `\r\n`
+    pub const MAX_LINE_LENGTH: usize = 512;
+
+    /// The number of channels to join
+    pub fn len(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Determines whether there are no channels to join
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    /// Split this into batches of at most `max_channels` channels
+    ///
+    /// # Panics
+    /// If `max_channels` is zero
+    pub fn chunks(&self, max_channels: usize) -> impl Iterator<Item = JoinMany<'a>> + '_ {
+        self.channels.chunks(max_channels).map(|channels| JoinMany {
+            channels: channels.to_vec(),
+        })
+    }
+
+    /// The lines this will be encoded as, without the trailing `\r\n`
+    pub fn lines(&self) -> Vec<String> {
+        const PREFIX: &str = "JOIN ";
+
+        let mut lines = vec![];
+        let mut line = String::new();
+        for channel in &self.channels {
+            let channel = Channel(channel).to_string();
+            if !line.is_empty() && line.len() + 1 + channel.len() + 2 > Self::MAX_LINE_LENGTH {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push_str(if line.is_empty() { PREFIX } else { "," });
+            line.push_str(&channel);
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+        lines
+    }
+}
+
+impl<'a> Encodable for JoinMany<'a> {
+    fn encode<W: Write + ?Sized>(&self, buf: &mut W) -> Result<()> {
+        for line in self.lines() {
+            write_nl!(buf, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use super::*;
+
+    #[test]
+    fn join_many_encode() {
+        test_encode(
+            join_many(vec!["#museun", "SHAKEN_BOT", "#foo"]),
+            "JOIN #museun,#shaken_bot,#foo\r\n",
+        );
+        test_encode(join_many(vec![]), "");
+    }
+
+    #[test]
+    fn join_many_line_length() {
+        let names = (0..100)
+            .map(|i| format!("channel_{:020}", i))
+            .collect::<Vec<_>>();
+        let join = join_many(names.iter().map(|s| s.as_str()));
+
+        let mut data = vec![];
+        join.encode(&mut data).unwrap();
+        let data = std::str::from_utf8(&data).unwrap();
+
+        let mut channels = vec![];
+        for line in data.split_terminator("\r\n") {
+            assert!(line.len() + 2 <= JoinMany::MAX_LINE_LENGTH);
+            channels.extend(line.strip_prefix("JOIN ").unwrap().split(','));
+        }
+        assert!(data.lines().count() > 1);
+        assert_eq!(channels.len(), 100);
+        assert!(channels
+            .iter()
+            .zip(&names)
+            .all(|(left, right)| left[1..] == **right));
+    }
+
+    #[test]
+    fn join_many_chunks() {
+        let join = join_many(vec!["a", "b", "c", "d", "e"]);
+        assert_eq!(join.len(), 5);
+
+        let chunks = join.chunks(2).collect::<Vec<_>>();
+        assert_eq!(
+            chunks.iter().map(JoinMany::len).collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+        test_encode(chunks[2].clone(), "JOIN #e\r\n");
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn join_many_serde() {
+        test_serde(
+            join_many(vec!["#museun", "shaken_bot"]),
+            "JOIN #museun,#shaken_bot\r\n",
+        );
+    }
+}
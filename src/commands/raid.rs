@@ -1,4 +1,6 @@
 use super::{Channel, Encodable};
+#[cfg(feature = "usernotice")]
+use crate::messages::{NoticeType, UserNotice};
 use std::io::{Result, Write};
 
 /// Raid another channel.
@@ -20,6 +22,35 @@ pub const fn raid<'a>(source: &'a str, target: &'a str) -> Raid<'a> {
     Raid { source, target }
 }
 
+impl<'a> Raid<'a> {
+    /// Whether this `USERNOTICE` is the raid landing on the target channel.
+    ///
+    /// Twitch sends it to the target channel once the countdown is over, so
+    /// it is only seen when you've joined that channel.
+    ///
+    /// ```
+    /// # use twitchchat::{commands, messages::UserNotice, FromIrcMessage as _};
+    /// let input = "@msg-id=raid;msg-param-login=museun;msg-param-viewerCount=42 :tmi.twitch.tv USERNOTICE #shaken_bot\r\n";
+    /// let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+    /// let msg = UserNotice::from_irc(msg).unwrap();
+    ///
+    /// assert!(commands::raid("museun", "shaken_bot").is_confirmed_by(&msg));
+    /// assert!(!commands::raid("museun", "other").is_confirmed_by(&msg));
+    /// ```
+    #[cfg(feature = "usernotice")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "usernotice")))]
+    pub fn is_confirmed_by(&self, msg: &UserNotice<'_>) -> bool {
+        let is_raid = matches!(msg.msg_id(), Some(Ok(NoticeType::Raid)));
+        let source = self.source.trim_start_matches('#');
+        is_raid
+            && msg.channel().eq_ignore_ascii_case(&Channel(self.target).to_string())
+            && msg
+                .msg_param_login()
+                .filter(|login| login.eq_ignore_ascii_case(source))
+                .is_some()
+    }
+}
+
 impl<'a> Encodable for Raid<'a> {
     fn encode<W>(&self, buf: &mut W) -> Result<()>
     where
@@ -1,5 +1,5 @@
 use super::Encodable;
-use crate::twitch::UserConfig;
+use crate::twitch::{RegisterOrder, UserConfig};
 
 use std::io::Write;
 
@@ -32,16 +32,30 @@ impl<'a> Encodable for Register<'a> {
             name,
             token,
             capabilities,
+            handshake,
         } = &self.user_config;
 
-        // the caps have to be written first
-        for cap in capabilities {
-            let cap = cap.encode_as_str();
-            write!(buf, "{}\r\n", cap)?;
+        let write_caps = |buf: &mut W| {
+            if handshake.batch_capabilities {
+                return super::cap_req(capabilities).encode(buf);
+            }
+            for cap in capabilities {
+                write!(buf, "{}\r\n", cap.encode_as_str())?;
+            }
+            Ok(())
+        };
+
+        // the caps have to be written first, for Twitch to send GLOBALUSERSTATE
+        if handshake.order == RegisterOrder::CapabilitiesFirst {
+            write_caps(buf)?;
         }
 
         write!(buf, "PASS {}\r\n", token)?;
         write!(buf, "NICK {}\r\n", name)?;
+
+        if handshake.order == RegisterOrder::CredentialsFirst {
+            write_caps(buf)?;
+        }
         buf.flush()
     }
 }
@@ -69,6 +83,46 @@ mod tests {
         )
     }
 
+    #[test]
+    fn register_encode_credentials_first() {
+        let config = UserConfig::builder()
+            .anonymous()
+            .capabilities(&[crate::twitch::Capability::Tags])
+            .handshake(crate::twitch::HandshakeOptions {
+                order: RegisterOrder::CredentialsFirst,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+
+        test_encode(
+            register(&config),
+            "PASS justinfan1234\r\n\
+            NICK justinfan1234\r\n\
+            CAP REQ :twitch.tv/tags\r\n",
+        )
+    }
+
+    #[test]
+    fn register_encode_batched() {
+        let config = UserConfig::builder()
+            .anonymous()
+            .enable_all_capabilities()
+            .handshake(crate::twitch::HandshakeOptions {
+                batch_capabilities: true,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+
+        test_encode(
+            register(&config),
+            "CAP REQ :twitch.tv/membership twitch.tv/tags twitch.tv/commands\r\n\
+            PASS justinfan1234\r\n\
+            NICK justinfan1234\r\n",
+        )
+    }
+
     #[test]
     #[cfg(feature = "serde")]
     fn register_serde() {
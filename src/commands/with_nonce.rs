@@ -0,0 +1,70 @@
+use super::Encodable;
+use crate::irc::tags::escape_str;
+use std::io::{Result, Write};
+
+/// Attach a `client-nonce` tag to every line of a command
+#[non_exhaustive]
+#[must_use = "commands must be encoded"]
+#[derive(Debug, Copy, Clone, PartialEq, Ord, PartialOrd, Eq, Hash)]
+pub struct WithNonce<'a, E> {
+    pub(crate) nonce: &'a str,
+    pub(crate) cmd: E,
+}
+
+/// Attach a `client-nonce` tag to every line of a command.
+///
+/// Twitch echoes the nonce of a chat message in some replies, which can be
+/// used to tell which of your messages they are about. See
+/// [NonceMap](crate::runner::NonceMap) for keeping track of them.
+///
+/// ```
+/// # use twitchchat::{commands, Encodable as _};
+/// let mut out = vec![];
+/// commands::with_nonce("3f2a", commands::privmsg("#museun", "hello")).encode(&mut out)?;
+/// assert_eq!(out, b"@client-nonce=3f2a PRIVMSG #museun :hello\r\n");
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub fn with_nonce<E: Encodable>(nonce: &str, cmd: E) -> WithNonce<'_, E> {
+    WithNonce { nonce, cmd }
+}
+
+impl<'a, E: Encodable> Encodable for WithNonce<'a, E> {
+    fn encode<W: Write + ?Sized>(&self, buf: &mut W) -> Result<()> {
+        let mut data = vec![];
+        self.cmd.encode(&mut data)?;
+
+        let nonce = escape_str(self.nonce);
+        for line in data.split_inclusive(|&b| b == b'\n') {
+            write!(buf, "@client-nonce={}", nonce)?;
+            // merge with the tags the command already has
+            match line.strip_prefix(b"@") {
+                Some(rest) => buf.write_all(b";").and_then(|_| buf.write_all(rest))?,
+                None => buf.write_all(b" ").and_then(|_| buf.write_all(line))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use super::*;
+
+    #[test]
+    fn with_nonce_encode() {
+        test_encode(
+            with_nonce("abc", privmsg("#museun", "hello")),
+            "@client-nonce=abc PRIVMSG #museun :hello\r\n",
+        );
+        test_encode(
+            with_nonce("a b", reply("#museun", "1", "hello")),
+            "@client-nonce=a\\sb;reply-parent-msg-id=1 PRIVMSG #museun :hello\r\n",
+        );
+        test_encode(
+            with_nonce("abc", raw("PING :a\r\nPING :b")),
+            "@client-nonce=abc PING :a\r\n@client-nonce=abc PING :b\r\n",
+        );
+        test_encode(with_nonce("abc", cap_req(&[])), "");
+    }
+}
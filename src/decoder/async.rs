@@ -1,5 +1,5 @@
 cfg_async! {
-use crate::{irc::IrcMessage, IntoOwned,DecodeError};
+use crate::{irc::{IrcMessage, Limits}, IntoOwned,DecodeError};
 
 use std::{
     future::Future,
@@ -17,6 +17,9 @@ use futures_lite::{io::BufReader as AsyncBufReader, AsyncBufReadExt, AsyncRead,
 pub struct AsyncDecoder<R> {
     reader: AsyncBufReader<R>,
     buf: Vec<u8>,
+    limits: Option<Limits>,
+    // whether `buf` holds a line that was already read, rather than the start of one
+    consumed: bool,
 }
 
 impl<R> std::fmt::Debug for AsyncDecoder<R> {
@@ -31,6 +34,18 @@ impl<R: AsyncRead + Send + Sync + Unpin> AsyncDecoder<R> {
         Self {
             reader: AsyncBufReader::new(reader),
             buf: Vec::with_capacity(1024),
+            limits: None,
+            consumed: false,
+        }
+    }
+
+    /// Reject any message that doesn't fit in these `limits`.
+    ///
+    /// By default, no limits are checked.
+    pub fn with_limits(self, limits: Limits) -> Self {
+        Self {
+            limits: Some(limits),
+            ..self
         }
     }
 
@@ -39,24 +54,31 @@ impl<R: AsyncRead + Send + Sync + Unpin> AsyncDecoder<R> {
     /// This returns a borrowed [IrcMessage] which is valid until the next AsyncDecoder call is made.
     ///
     /// If you just want an owned one, use the [AsyncDecoder] as an stream. e.g. dec.next().
+    ///
+    /// This is cancellation safe: if the future is dropped before it completes
+    /// (e.g. it lost a `select!`), the part of the line that was read is kept
+    /// for the next call.
     pub async fn read_message(&mut self) -> Result<IrcMessage<'_>, DecodeError> {
-        self.buf.clear();
-        let n = self
-            .reader
-            .read_until(b'\n', &mut self.buf)
-            .await
-            .map_err(DecodeError::Io)?;
-        if n == 0 {
+        if std::mem::take(&mut self.consumed) {
+            self.buf.clear();
+        }
+
+        let read = self.reader.read_until(b'\n', &mut self.buf).await;
+        self.consumed = true;
+        read.map_err(DecodeError::Io)?;
+        if self.buf.is_empty() {
             return Err(DecodeError::Eof);
         }
 
-        let str = std::str::from_utf8(&self.buf[..n]).map_err(DecodeError::InvalidUtf8)?;
+        let str = std::str::from_utf8(&self.buf).map_err(DecodeError::InvalidUtf8)?;
         log::trace!("< {}", str.escape_debug());
 
         // this should only ever parse 1 message
-        crate::irc::parse_one(str)
-            .map_err(DecodeError::ParseError)
-            .map(|(_, msg)| msg)
+        let (_, msg) = crate::irc::parse_one(str).map_err(DecodeError::ParseError)?;
+        if let Some(limits) = &self.limits {
+            limits.check(&msg).map_err(DecodeError::ParseError)?;
+        }
+        Ok(msg)
     }
 
     /// Consume the decoder returning the inner Reader
@@ -121,5 +143,51 @@ mod tests {
 
         futures_lite::future::block_on(fut);
     }
+
+    // yields its chunks one at a time, and is pending before each one
+    struct Chunked(std::collections::VecDeque<&'static [u8]>, bool);
+
+    impl AsyncRead for Chunked {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            if !std::mem::replace(&mut self.1, false) {
+                self.1 = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let chunk = match self.0.pop_front() {
+                Some(chunk) => chunk,
+                None => return Poll::Ready(Ok(0)),
+            };
+            buf[..chunk.len()].copy_from_slice(chunk);
+            Poll::Ready(Ok(chunk.len()))
+        }
+    }
+
+    #[test]
+    fn read_cancelled() {
+        use futures_lite::future::{block_on, poll_once};
+
+        let chunks = vec![&b"PING :hel"[..], b"lo\r\nPING :wor", b"ld\r\n"];
+        let mut dec = AsyncDecoder::new(Chunked(chunks.into(), false));
+
+        let mut messages = vec![];
+        block_on(async {
+            loop {
+                // drop the read whenever it would wait
+                match poll_once(dec.read_message()).await {
+                    Some(Ok(msg)) => messages.push(msg.get_raw().to_string()),
+                    Some(Err(DecodeError::Eof)) => break,
+                    Some(Err(err)) => panic!("{}", err),
+                    None => continue,
+                }
+            }
+        });
+
+        assert_eq!(messages, vec!["PING :hello\r\n", "PING :world\r\n"]);
+    }
 }
 }
@@ -1,4 +1,4 @@
-use crate::{IntoOwned as _, IrcMessage, MessageError};
+use crate::{irc::Limits, IntoOwned as _, IrcMessage, MessageError};
 use std::io::{BufRead, BufReader, Read};
 
 /// An error produced by a Decoder.
@@ -45,6 +45,7 @@ impl std::error::Error for DecodeError {
 pub struct Decoder<R> {
     reader: BufReader<R>,
     buf: Vec<u8>,
+    limits: Option<Limits>,
 }
 
 impl<R> std::fmt::Debug for Decoder<R> {
@@ -62,6 +63,17 @@ where
         Self {
             reader: BufReader::new(reader),
             buf: Vec::with_capacity(1024),
+            limits: None,
+        }
+    }
+
+    /// Reject any message that doesn't fit in these `limits`.
+    ///
+    /// By default, no limits are checked.
+    pub fn with_limits(self, limits: Limits) -> Self {
+        Self {
+            limits: Some(limits),
+            ..self
         }
     }
 
@@ -80,12 +92,14 @@ where
             return Err(DecodeError::Eof);
         }
 
-        let str = std::str::from_utf8(&self.buf[..n]).map_err(DecodeError::InvalidUtf8)?;
+        let str = std::str::from_utf8(&self.buf).map_err(DecodeError::InvalidUtf8)?;
 
         // this should only ever parse 1 message
-        crate::irc::parse_one(str)
-            .map_err(DecodeError::ParseError)
-            .map(|(_, msg)| msg)
+        let (_, msg) = crate::irc::parse_one(str).map_err(DecodeError::ParseError)?;
+        if let Some(limits) = &self.limits {
+            limits.check(&msg).map_err(DecodeError::ParseError)?;
+        }
+        Ok(msg)
     }
 
     /// Returns an iterator over messages.
@@ -139,4 +153,15 @@ mod tests {
         }
         assert!(matches!(dec.read_message().unwrap_err(), DecodeError::Eof))
     }
+
+    #[test]
+    fn read_with_limits() {
+        let data = format!("PING :{}\r\nPING :hello\r\n", "a".repeat(600));
+        let mut dec = Decoder::new(data.as_bytes()).with_limits(Limits::TWITCH);
+        assert!(matches!(
+            dec.read_message().unwrap_err(),
+            DecodeError::ParseError(MessageError::LineTooLong { .. })
+        ));
+        assert_eq!(dec.read_message().unwrap().get_data(), Some("hello"));
+    }
 }
@@ -88,29 +88,37 @@ where
             return Ok(self.writer);
         }
 
-        let data = std::mem::take(&mut self.data);
-        self.writer.write_all(&data).await?;
-        self.writer.flush().await?;
+        self.write_buffered().await?;
         Ok(self.writer)
     }
 
     /// Encode this [Encodable](crate::Encodable) message to the writer.
     ///
-    /// This flushes the data before returning
+    /// This flushes the data before returning.
+    ///
+    /// This is cancellation safe: if the future is dropped before it completes,
+    /// the rest of the message is written by the next `encode`.
     pub async fn encode<M>(&mut self, msg: M) -> IoResult<()>
     where
         M: crate::Encodable + Send + Sync,
         W: Unpin,
     {
-        msg.encode(&mut self.data)?;
-        let data = &self.data[self.pos..];
+        self.buffer(msg)?;
+        self.write_buffered().await
+    }
 
-        self.writer.write_all(data).await?;
-        self.writer.flush().await?;
+    // encode the message without writing it, see `write_buffered`
+    pub(crate) fn buffer<M>(&mut self, msg: M) -> IoResult<()>
+    where
+        M: crate::Encodable + Send + Sync,
+    {
+        msg.encode(&mut self.data)
+    }
 
-        self.data.clear();
-        self.pos = 0;
-        Ok(())
+    // write and flush what's left of the buffered messages
+    pub(crate) async fn write_buffered(&mut self) -> IoResult<()> {
+        futures_lite::future::poll_fn(|ctx| self.poll_drain(ctx)).await?;
+        self.writer.flush().await
     }
 }
 
@@ -144,6 +152,57 @@ where
     }
 }
 
+impl<W> AsyncEncoder<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    // write out what is buffered, keeping track of it so a write can be resumed
+    fn poll_drain(&mut self, ctx: &mut Context<'_>) -> Poll<IoResult<()>> {
+        while self.pos < self.data.len() {
+            let data = &self.data[self.pos..];
+            let n = futures_lite::ready!(Pin::new(&mut self.writer).poll_write(ctx, data))?;
+            if n == 0 {
+                return Poll::Ready(Err(std::io::ErrorKind::WriteZero.into()));
+            }
+            self.pos += n;
+        }
+        self.data.clear();
+        self.pos = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Messages are buffered by `start_send` and written by the next `poll_ready` or `poll_flush`
+#[cfg(feature = "sink")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sink")))]
+impl<W, M> futures_sink::Sink<M> for AsyncEncoder<W>
+where
+    W: AsyncWrite + Unpin,
+    M: crate::Encodable,
+{
+    type Error = std::io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<IoResult<()>> {
+        self.get_mut().poll_drain(ctx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: M) -> IoResult<()> {
+        item.encode(&mut self.get_mut().data)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<IoResult<()>> {
+        let this = self.get_mut();
+        futures_lite::ready!(this.poll_drain(ctx))?;
+        Pin::new(&mut this.writer).poll_flush(ctx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<IoResult<()>> {
+        let this = self.get_mut();
+        futures_lite::ready!(this.poll_drain(ctx))?;
+        Pin::new(&mut this.writer).poll_close(ctx)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -165,5 +224,81 @@ mod tests {
         };
         futures_lite::future::block_on(fut);
     }
+
+    // takes 4 bytes at a time, and is pending before each write
+    #[derive(Default)]
+    struct Slow(Vec<u8>, bool);
+
+    impl AsyncWrite for Slow {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            ctx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<IoResult<usize>> {
+            if !std::mem::replace(&mut self.1, false) {
+                self.1 = true;
+                ctx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let n = buf.len().min(4);
+            self.0.extend_from_slice(&buf[..n]);
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<IoResult<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<IoResult<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn encoder_cancelled() {
+        use futures_lite::future::{block_on, poll_once};
+
+        let mut encoder = AsyncEncoder::new(Slow::default());
+        block_on(async {
+            // each of these is dropped after the first few bytes
+            for _ in 0..3 {
+                assert!(poll_once(encoder.encode(join("#museun"))).await.is_none());
+            }
+            encoder.encode(join("#shaken_bot")).await.unwrap();
+        });
+
+        let s = std::str::from_utf8(&encoder.writer.0).unwrap();
+        assert_eq!(
+            s,
+            "JOIN #museun\r\nJOIN #museun\r\nJOIN #museun\r\nJOIN #shaken_bot\r\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "sink")]
+    fn encoder_sink() {
+        use futures_sink::Sink;
+
+        let mut output = vec![];
+        let mut encoder = AsyncEncoder::new(&mut output);
+        let waker = futures_lite::future::block_on(futures_lite::future::poll_fn(|ctx| {
+            Poll::Ready(ctx.waker().clone())
+        }));
+        let mut ctx = Context::from_waker(&waker);
+
+        for msg in &["JOIN #museun\r\n", "JOIN #shaken_bot\r\n"] {
+            let mut encoder = Pin::new(&mut encoder);
+            assert!(Sink::<&&str>::poll_ready(encoder.as_mut(), &mut ctx).is_ready());
+            encoder.start_send(msg).unwrap();
+        }
+        assert!(matches!(
+            Sink::<&&str>::poll_flush(Pin::new(&mut encoder), &mut ctx),
+            Poll::Ready(Ok(()))
+        ));
+        drop(encoder);
+
+        let s = std::str::from_utf8(&output).unwrap();
+        assert_eq!(s, "JOIN #museun\r\nJOIN #shaken_bot\r\n");
+    }
 }
 }
@@ -0,0 +1,477 @@
+/*!
+Write chat in the log formats of other IRC software, so the tools made for those logs can read it.
+
+A [LogExporter] writes a line for each message, in one of the [LogFormat]s:
+* [LogFormat::Znc], like the `log` module of ZNC
+* [LogFormat::Weechat], like the logger of WeeChat
+* [LogFormat::Raw], the raw lines with an IRCv3 `time` tag
+
+Times are in UTC, from the `tmi-sent-ts` tag of a message or when it was exported.
+
+Those programs write a file per channel (and per day, for ZNC). This writes
+everything it is given, so filter the messages by their
+[channel](crate::messages::Commands::channel) to do the same.
+
+To keep a raw archive without running out of disk, write to a [RotatingWriter].
+A [DeltaEncoder] shrinks such an archive further, by writing the tags that
+repeat between messages only once.
+
+```
+# use twitchchat::{FromIrcMessage as _, export::{LogExporter, LogFormat}, messages::Commands};
+let parse = |input| {
+    let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+    Commands::from_irc(msg).unwrap()
+};
+
+let mut znc = LogExporter::new(vec![], LogFormat::Znc);
+let mut weechat = LogExporter::new(vec![], LogFormat::Weechat);
+
+let msg = parse("@tmi-sent-ts=1601079032426 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n");
+znc.export(&msg)?;
+weechat.export(&msg)?;
+
+assert_eq!(znc.into_inner(), b"[00:10:32] <museun> hello\n");
+assert_eq!(weechat.into_inner(), b"2020-09-26 00:10:32\tmuseun\thello\n");
+# Ok::<_, std::io::Error>(())
+```
+*/
+
+use crate::{irc::MessageRewriter, messages::Commands, IrcMessage, MaybeOwned};
+use std::io::Write;
+
+#[cfg(feature = "moderation")]
+use crate::messages::ClearChat;
+
+mod delta;
+pub use delta::{DeltaDecoder, DeltaEncoder};
+
+mod rotating;
+pub use rotating::RotatingWriter;
+
+/// A log format, see the [module docs](self)
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LogFormat {
+    /// `[HH:MM:SS] <nick> message`, like the `log` module of ZNC
+    Znc,
+    /// `YYYY-MM-DD HH:MM:SS<tab>nick<tab>message`, like the logger of WeeChat
+    Weechat,
+    /// The raw lines, with a `time` tag (if they didn't have one)
+    ///
+    /// This is every message, not just the chat, and the lines end with `\r\n`
+    /// so the log can be read again with a [Decoder](crate::Decoder)
+    Raw,
+}
+
+impl LogFormat {
+    /// Format this message, that happened at `timestamp` (in milliseconds since the unix epoch)
+    ///
+    /// This returns `None` for messages the format doesn't log. Only
+    /// [LogFormat::Raw] logs everything, the others log the chat, actions,
+    /// joins, parts, notices and bans (as kicks).
+    pub fn format(self, msg: &Commands<'_>, timestamp: u64) -> Option<String> {
+        let time = Utc::from_millis(timestamp);
+        if let Self::Raw = self {
+            return Some(raw(msg.raw(), &time));
+        }
+
+        let channel = msg.channel()?;
+        let event = Event::from_commands(msg)?;
+        let line = match self {
+            Self::Znc => znc(event, &time),
+            _ => weechat(event, channel, &time),
+        };
+        Some(line)
+    }
+}
+
+/// Writes messages to a log, see the [module docs](self)
+#[derive(Debug)]
+pub struct LogExporter<W> {
+    writer: W,
+    format: LogFormat,
+    written: u64,
+}
+
+impl<W: Write> LogExporter<W> {
+    /// Create an exporter writing this format to the writer
+    pub fn new(writer: W, format: LogFormat) -> Self {
+        Self {
+            writer,
+            format,
+            written: 0,
+        }
+    }
+
+    /// Write this message, at its `tmi-sent-ts` tag or now
+    ///
+    /// This returns whether anything was written
+    pub fn export(&mut self, msg: &Commands<'_>) -> std::io::Result<bool> {
+        let timestamp = msg.tmi_sent_ts().unwrap_or_else(now_millis);
+        self.export_at(msg, timestamp)
+    }
+
+    /// Write this message, that happened at `timestamp` (in milliseconds since the unix epoch)
+    ///
+    /// This returns whether anything was written
+    pub fn export_at(&mut self, msg: &Commands<'_>, timestamp: u64) -> std::io::Result<bool> {
+        let line = match self.format.format(msg, timestamp) {
+            Some(line) => line,
+            None => return Ok(false),
+        };
+        self.writer.write_all(line.as_bytes())?;
+        if self.format != LogFormat::Raw {
+            self.writer.write_all(b"\n")?;
+        }
+        self.written += 1;
+        Ok(true)
+    }
+
+    /// Flush the writer
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// How many lines were written
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// The format this writes
+    pub fn format(&self) -> LogFormat {
+        self.format
+    }
+
+    /// Get the writer back
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+// what the human-readable formats log
+enum Event<'a> {
+    Message {
+        nick: &'a str,
+        text: &'a str,
+    },
+    Action {
+        nick: &'a str,
+        text: &'a str,
+    },
+    Join {
+        nick: &'a str,
+        userhost: &'a str,
+    },
+    Part {
+        nick: &'a str,
+        userhost: &'a str,
+    },
+    Notice {
+        from: &'a str,
+        text: String,
+    },
+    #[cfg(feature = "moderation")]
+    Kick {
+        nick: &'a str,
+        reason: String,
+    },
+}
+
+impl<'a> Event<'a> {
+    fn from_commands(msg: &'a Commands<'a>) -> Option<Self> {
+        let event = match msg {
+            Commands::Privmsg(msg) if msg.is_action() => Self::Action {
+                nick: msg.name(),
+                text: msg.data(),
+            },
+            Commands::Privmsg(msg) => Self::Message {
+                nick: msg.name(),
+                text: msg.data(),
+            },
+            Commands::Join(msg) => Self::Join {
+                nick: msg.name(),
+                userhost: userhost(msg.raw()),
+            },
+            Commands::Part(msg) => Self::Part {
+                nick: msg.name(),
+                userhost: userhost(msg.raw()),
+            },
+            Commands::Notice(msg) => Self::Notice {
+                from: SERVER,
+                text: msg.message().to_string(),
+            },
+            #[cfg(feature = "usernotice")]
+            Commands::UserNotice(msg) => Self::Notice {
+                from: SERVER,
+                text: msg.system_msg()?,
+            },
+            #[cfg(feature = "moderation")]
+            Commands::ClearChat(msg) => Self::clear_chat(msg),
+            _ => return None,
+        };
+        Some(event)
+    }
+
+    #[cfg(feature = "moderation")]
+    fn clear_chat(msg: &'a ClearChat<'a>) -> Self {
+        let nick = match msg.name() {
+            Some(nick) => nick,
+            None => {
+                return Self::Notice {
+                    from: SERVER,
+                    text: "Chat was cleared".to_string(),
+                }
+            }
+        };
+        let reason = match msg.ban_duration().and_then(Result::ok) {
+            Some(duration) => format!("Timed out for {} seconds", duration),
+            None => "Banned".to_string(),
+        };
+        Self::Kick { nick, reason }
+    }
+}
+
+const SERVER: &str = "tmi.twitch.tv";
+
+fn znc(event: Event<'_>, time: &Utc) -> String {
+    let time = format!("[{:02}:{:02}:{:02}]", time.hour, time.minute, time.second);
+    match event {
+        Event::Message { nick, text } => format!("{} <{}> {}", time, nick, text),
+        Event::Action { nick, text } => format!("{} * {} {}", time, nick, text),
+        Event::Join { nick, userhost } => format!("{} *** Joins: {} ({})", time, nick, userhost),
+        Event::Part { nick, userhost } => {
+            format!("{} *** Parts: {} ({}) ()", time, nick, userhost)
+        }
+        Event::Notice { from, text } => format!("{} -{}- {}", time, from, text),
+        #[cfg(feature = "moderation")]
+        Event::Kick { nick, reason } => format!(
+            "{} *** {} was kicked by {} ({})",
+            time, nick, SERVER, reason
+        ),
+    }
+}
+
+fn weechat(event: Event<'_>, channel: &str, time: &Utc) -> String {
+    let time = format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        time.year, time.month, time.day, time.hour, time.minute, time.second
+    );
+    let (prefix, text) = match event {
+        Event::Message { nick, text } => (nick.to_string(), text.to_string()),
+        Event::Action { nick, text } => (" *".to_string(), format!("{} {}", nick, text)),
+        Event::Join { nick, userhost } => (
+            "-->".to_string(),
+            format!("{} ({}) has joined {}", nick, userhost, channel),
+        ),
+        Event::Part { nick, userhost } => (
+            "<--".to_string(),
+            format!("{} ({}) has left {}", nick, userhost, channel),
+        ),
+        Event::Notice { from, text } => (
+            "--".to_string(),
+            format!("Notice({}) -> {}: {}", from, channel, text),
+        ),
+        #[cfg(feature = "moderation")]
+        Event::Kick { nick, reason } => (
+            "<--".to_string(),
+            format!("{} has kicked {} ({})", SERVER, nick, reason),
+        ),
+    };
+    format!("{}\t{}\t{}", time, prefix, text)
+}
+
+fn raw(line: &str, time: &Utc) -> String {
+    let line = match line.ends_with("\r\n") {
+        true => line.to_string(),
+        false => format!("{}\r\n", line),
+    };
+    if has_time_tag(&line) {
+        return line;
+    }
+
+    let time = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        time.year, time.month, time.day, time.hour, time.minute, time.second, time.millis
+    );
+    // `time` is a valid key, so this only fails for lines that aren't messages
+    MessageRewriter::new(&line)
+        .set_tag("time", time)
+        .to_raw()
+        .unwrap_or(line)
+}
+
+fn has_time_tag(line: &str) -> bool {
+    IrcMessage::parse(MaybeOwned::Borrowed(line))
+        .ok()
+        .and_then(|msg| {
+            msg.get_tags().map(|tags| {
+                tags.trim_start_matches('@')
+                    .split(';')
+                    .any(|tag| tag.starts_with("time="))
+            })
+        })
+        .unwrap_or(false)
+}
+
+// the `user@host` of a message, e.g. `museun@museun.tmi.twitch.tv`
+fn userhost(raw: &str) -> &str {
+    raw.strip_prefix(':')
+        .and_then(|raw| raw.split(' ').next())
+        .and_then(|prefix| prefix.split_once('!'))
+        .map(|(_, userhost)| userhost)
+        .unwrap_or_default()
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|dur| dur.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+// a point in time, as a date and time in UTC
+#[derive(Debug, PartialEq)]
+struct Utc {
+    year: u64,
+    month: u64,
+    day: u64,
+    hour: u64,
+    minute: u64,
+    second: u64,
+    millis: u64,
+}
+
+impl Utc {
+    fn from_millis(timestamp: u64) -> Self {
+        let (secs, millis) = (timestamp / 1000, timestamp % 1000);
+        let (days, secs) = (secs / 86400, secs % 86400);
+
+        // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        // days are counted from 0000-03-01, so leap days are at the end of a year
+        let days = days + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + (month <= 2) as u64;
+
+        Self {
+            year,
+            month,
+            day,
+            hour: secs / 3600,
+            minute: secs % 3600 / 60,
+            second: secs % 60,
+            millis,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{irc::parse, FromIrcMessage as _};
+
+    fn commands(input: &str) -> Commands<'_> {
+        Commands::from_irc(parse(input).next().unwrap().unwrap()).unwrap()
+    }
+
+    const INPUT: &str = "\
+        :museun!museun@museun.tmi.twitch.tv JOIN #museun\r\n\
+        @id=1 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello world\r\n\
+        :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :\x01ACTION waves\x01\r\n\
+        @msg-id=slow_on :tmi.twitch.tv NOTICE #museun :This room is now in slow mode.\r\n\
+        @ban-duration=600 :tmi.twitch.tv CLEARCHAT #museun :foo\r\n\
+        :tmi.twitch.tv CLEARCHAT #museun :bar\r\n\
+        :museun!museun@museun.tmi.twitch.tv PART #museun\r\n\
+        PING :tmi.twitch.tv\r\n";
+
+    fn export(format: LogFormat) -> String {
+        let mut exporter = LogExporter::new(vec![], format);
+        for msg in parse(INPUT) {
+            let msg = Commands::from_irc(msg.unwrap()).unwrap();
+            exporter.export_at(&msg, 1_601_079_032_426).unwrap();
+        }
+        String::from_utf8(exporter.into_inner()).unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "moderation")]
+    fn znc() {
+        assert_eq!(
+            export(LogFormat::Znc),
+            "[00:10:32] *** Joins: museun (museun@museun.tmi.twitch.tv)\n\
+             [00:10:32] <museun> hello world\n\
+             [00:10:32] * museun waves\n\
+             [00:10:32] -tmi.twitch.tv- This room is now in slow mode.\n\
+             [00:10:32] *** foo was kicked by tmi.twitch.tv (Timed out for 600 seconds)\n\
+             [00:10:32] *** bar was kicked by tmi.twitch.tv (Banned)\n\
+             [00:10:32] *** Parts: museun (museun@museun.tmi.twitch.tv) ()\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "moderation")]
+    fn weechat() {
+        assert_eq!(
+            export(LogFormat::Weechat),
+            "2020-09-26 00:10:32\t-->\tmuseun (museun@museun.tmi.twitch.tv) has joined #museun\n\
+             2020-09-26 00:10:32\tmuseun\thello world\n\
+             2020-09-26 00:10:32\t *\tmuseun waves\n\
+             2020-09-26 00:10:32\t--\tNotice(tmi.twitch.tv) -> #museun: This room is now in slow mode.\n\
+             2020-09-26 00:10:32\t<--\ttmi.twitch.tv has kicked foo (Timed out for 600 seconds)\n\
+             2020-09-26 00:10:32\t<--\ttmi.twitch.tv has kicked bar (Banned)\n\
+             2020-09-26 00:10:32\t<--\tmuseun (museun@museun.tmi.twitch.tv) has left #museun\n"
+        );
+    }
+
+    #[test]
+    fn raw() {
+        let out = export(LogFormat::Raw);
+        let lines = out.split_terminator("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(
+            lines[0],
+            "@time=2020-09-26T00:10:32.426Z :museun!museun@museun.tmi.twitch.tv JOIN #museun"
+        );
+        assert_eq!(
+            lines[1],
+            "@id=1;time=2020-09-26T00:10:32.426Z :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello world"
+        );
+
+        // the log can be decoded again
+        let msgs = parse(&out).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(msgs.len(), 8);
+
+        // an existing time is kept
+        let msg = commands("@time=2011-10-19T16:40:51.620Z PING :tmi.twitch.tv\r\n");
+        assert_eq!(
+            LogFormat::Raw.format(&msg, 0).unwrap(),
+            "@time=2011-10-19T16:40:51.620Z PING :tmi.twitch.tv\r\n"
+        );
+    }
+
+    #[test]
+    fn timestamps() {
+        let mut exporter = LogExporter::new(vec![], LogFormat::Znc);
+        let msg = commands("@tmi-sent-ts=951782400000 :foo!foo@foo PRIVMSG #museun :hi\r\n");
+        assert!(exporter.export(&msg).unwrap());
+        assert!(!exporter.export(&commands("PING :foo\r\n")).unwrap());
+        assert_eq!(exporter.written(), 1);
+        assert_eq!(exporter.into_inner(), b"[00:00:00] <foo> hi\n");
+
+        let utc = |ts| {
+            let utc = Utc::from_millis(ts);
+            (
+                utc.year, utc.month, utc.day, utc.hour, utc.minute, utc.second,
+            )
+        };
+        assert_eq!(utc(0), (1970, 1, 1, 0, 0, 0));
+        assert_eq!(utc(951_782_400_000), (2000, 2, 29, 0, 0, 0));
+        assert_eq!(utc(4_107_542_399_000), (2100, 2, 28, 23, 59, 59));
+    }
+}
@@ -0,0 +1,347 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+// the tag keys seen so far, with the last value of each
+#[derive(Debug, Default)]
+struct Dictionary {
+    indices: HashMap<String, usize>,
+    // the key, and what followed it in the last message: `=value`, or nothing
+    tags: Vec<(String, String)>,
+}
+
+/// Writes raw lines in a compact delta encoding, for archiving lots of chat.
+///
+/// Twitch sends mostly the same tags with every message, and many of their
+/// values (`room-id`, `badges`, `color`, ..) repeat from one message to the
+/// next. This writes every tag key only once, and then refers to it by its
+/// number, and leaves out a value if it is the same as the last one of the
+/// key. What remains is smaller, and compresses better (e.g. with the `gzip`
+/// feature of the [RotatingWriter](super::RotatingWriter)) than the raw lines.
+///
+/// A [DeltaDecoder] gets the raw lines back, exactly as they were written. The
+/// encoding depends on every line before it, so a log has to be decoded from
+/// its start: write each file with a new encoder.
+///
+/// Each line of the log is a message, its tags are `;`-separated entries:
+/// * `+key=value` is a new key, numbered in the order they are seen
+/// * `3=value` is the key numbered `3` with a new value
+/// * `3` is the key numbered `3` with the same value as the last time
+/// * `3!` is the key numbered `3` without a value (`key` rather than `key=`)
+///
+/// ```
+/// # use twitchchat::export::{DeltaDecoder, DeltaEncoder};
+/// let lines = [
+///     "@badges=;color=#1E90FF;room-id=23196011 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n",
+///     "@badges=;color=#FF0000;room-id=23196011 :shaken!shaken@shaken.tmi.twitch.tv PRIVMSG #museun :hi\r\n",
+/// ];
+///
+/// let mut encoder = DeltaEncoder::new(vec![]);
+/// for line in &lines {
+///     encoder.encode(line)?;
+/// }
+/// let encoded = encoder.into_inner();
+/// assert_eq!(
+///     std::str::from_utf8(&encoded).unwrap().lines().nth(1),
+///     Some("@0;1=#FF0000;2 :shaken!shaken@shaken.tmi.twitch.tv PRIVMSG #museun :hi")
+/// );
+///
+/// let decoded = DeltaDecoder::new(&encoded[..]).collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(decoded, lines);
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct DeltaEncoder<W> {
+    writer: W,
+    dictionary: Dictionary,
+    written: u64,
+}
+
+impl<W: Write> DeltaEncoder<W> {
+    /// Create an encoder writing to the writer
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            dictionary: Dictionary::default(),
+            written: 0,
+        }
+    }
+
+    /// Write this raw line, with or without its `\r\n`.
+    ///
+    /// This fails with [InvalidInput](io::ErrorKind::InvalidInput) if the line
+    /// isn't a single message, which can't be encoded.
+    pub fn encode(&mut self, raw: &str) -> io::Result<()> {
+        let raw = raw.strip_suffix("\r\n").unwrap_or(raw);
+        if raw.contains(['\r', '\n']) {
+            return Err(invalid(io::ErrorKind::InvalidInput, "not a single line"));
+        }
+
+        let (tags, rest) = match raw.strip_prefix('@') {
+            Some(raw) => raw
+                .split_once(' ')
+                .ok_or_else(|| invalid(io::ErrorKind::InvalidInput, "a message has no command"))?,
+            None => {
+                writeln!(self.writer, "{}", raw)?;
+                self.written += 1;
+                return Ok(());
+            }
+        };
+
+        let mut line = String::with_capacity(raw.len());
+        line.push('@');
+        for (i, tag) in tags.split(';').enumerate() {
+            if i > 0 {
+                line.push(';');
+            }
+            let (key, value) = match tag.find('=') {
+                Some(pos) => tag.split_at(pos),
+                None => (tag, ""),
+            };
+
+            if key.ends_with('!') {
+                return Err(invalid(io::ErrorKind::InvalidInput, "invalid tag key"));
+            }
+
+            let dictionary = &mut self.dictionary;
+            match dictionary.indices.get(key) {
+                Some(&index) => {
+                    line.push_str(&index.to_string());
+                    if let Some((_, last)) = dictionary.tags.get_mut(index) {
+                        if last != value {
+                            push_value(&mut line, value);
+                            *last = value.to_string();
+                        }
+                    }
+                }
+                None => {
+                    line.push('+');
+                    line.push_str(key);
+                    push_value(&mut line, value);
+                    dictionary
+                        .indices
+                        .insert(key.to_string(), dictionary.tags.len());
+                    dictionary.tags.push((key.to_string(), value.to_string()));
+                }
+            }
+        }
+        line.push(' ');
+        line.push_str(rest);
+
+        writeln!(self.writer, "{}", line)?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Flush the writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// How many lines were written
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Get the writer back
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads the raw lines back from a log written by a [DeltaEncoder].
+///
+/// The lines end with `\r\n`, so they can be given to a [Decoder](crate::Decoder)
+/// or [parse](crate::irc::parse) as they are. Lines that can't be decoded are
+/// an [InvalidData](io::ErrorKind::InvalidData) error.
+#[derive(Debug)]
+pub struct DeltaDecoder<R> {
+    reader: R,
+    dictionary: Dictionary,
+    buf: String,
+}
+
+impl<R: BufRead> DeltaDecoder<R> {
+    /// Create a decoder reading from the start of a log
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            dictionary: Dictionary::default(),
+            buf: String::new(),
+        }
+    }
+
+    /// Read the next raw line, or None at the end of the log
+    pub fn read_line(&mut self) -> io::Result<Option<String>> {
+        self.buf.clear();
+        if self.reader.read_line(&mut self.buf)? == 0 {
+            return Ok(None);
+        }
+        let line = self.buf.strip_suffix('\n').unwrap_or(&self.buf);
+
+        let (entries, rest) = match line.strip_prefix('@') {
+            Some(line) => line
+                .split_once(' ')
+                .ok_or_else(|| invalid(io::ErrorKind::InvalidData, "a message has no command"))?,
+            None => return Ok(Some(format!("{}\r\n", line))),
+        };
+
+        let mut raw = String::with_capacity(line.len() * 2);
+        raw.push('@');
+        for (i, entry) in entries.split(';').enumerate() {
+            if i > 0 {
+                raw.push(';');
+            }
+            // None is a repeated value
+            let (head, value) = match entry.find('=') {
+                Some(pos) => {
+                    let (head, value) = entry.split_at(pos);
+                    (head, Some(value))
+                }
+                None => match entry.strip_suffix('!') {
+                    Some(head) => (head, Some("")),
+                    None => (entry, None),
+                },
+            };
+
+            let dictionary = &mut self.dictionary;
+            if let Some(key) = head.strip_prefix('+') {
+                let value = value
+                    .ok_or_else(|| invalid(io::ErrorKind::InvalidData, "a new key has no value"))?;
+                if dictionary.indices.contains_key(key) {
+                    return Err(invalid(io::ErrorKind::InvalidData, "a key was added twice"));
+                }
+                dictionary
+                    .indices
+                    .insert(key.to_string(), dictionary.tags.len());
+                dictionary.tags.push((key.to_string(), value.to_string()));
+                raw.push_str(key);
+                raw.push_str(value);
+                continue;
+            }
+
+            let (key, last) = head
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| dictionary.tags.get_mut(index))
+                .ok_or_else(|| invalid(io::ErrorKind::InvalidData, "unknown key"))?;
+            if let Some(value) = value {
+                *last = value.to_string();
+            }
+            raw.push_str(key);
+            raw.push_str(last);
+        }
+        raw.push(' ');
+        raw.push_str(rest);
+        raw.push_str("\r\n");
+        Ok(Some(raw))
+    }
+}
+
+impl<R: BufRead> Iterator for DeltaDecoder<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_line().transpose()
+    }
+}
+
+// a tag without a value is `key` rather than `key=`, which is written as `!`
+// so it isn't mistaken for a repeated value
+fn push_value(line: &mut String, value: &str) {
+    match value {
+        "" => line.push('!'),
+        value => line.push_str(value),
+    }
+}
+
+fn invalid(kind: io::ErrorKind, msg: &str) -> io::Error {
+    io::Error::new(kind, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &[&str] = &[
+        "@badge-info=;badges=broadcaster/1;color=#1E90FF;display-name=museun;emotes=;id=1;mod=0;room-id=23196011;subscriber=0;tmi-sent-ts=1601079032426;turbo=0;user-id=23196011;user-type= :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello world\r\n",
+        "@badge-info=;badges=;color=;display-name=shaken_bot;emotes=25:0-4;id=2;mod=0;room-id=23196011;subscriber=0;tmi-sent-ts=1601079032900;turbo=0;user-id=241015868;user-type= :shaken_bot!shaken_bot@shaken_bot.tmi.twitch.tv PRIVMSG #museun :Kappa\r\n",
+        ":museun!museun@museun.tmi.twitch.tv JOIN #museun\r\n",
+        "@valueless;room-id=23196011;+client-nonce=abc :tmi.twitch.tv ROOMSTATE #museun\r\n",
+        "@valueless=now;room-id=1;+client-nonce :tmi.twitch.tv ROOMSTATE #museun\r\n",
+        "@valueless;room-id=1;+client-nonce :tmi.twitch.tv ROOMSTATE #museun\r\n",
+        "PING :tmi.twitch.tv\r\n",
+    ];
+
+    fn encode(lines: &[&str]) -> Vec<u8> {
+        let mut encoder = DeltaEncoder::new(vec![]);
+        for line in lines {
+            encoder.encode(line).unwrap();
+        }
+        assert_eq!(encoder.written(), lines.len() as u64);
+        encoder.into_inner()
+    }
+
+    #[test]
+    fn round_trip() {
+        let encoded = encode(INPUT);
+        let decoded = DeltaDecoder::new(&encoded[..])
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(decoded, INPUT);
+
+        let encoded = String::from_utf8(encoded).unwrap();
+        let lines = encoded.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[1],
+            "@0;1=;2=;3=shaken_bot;4=25:0-4;5=2;6;7;8;9=1601079032900;10;11=241015868;12 :shaken_bot!shaken_bot@shaken_bot.tmi.twitch.tv PRIVMSG #museun :Kappa"
+        );
+        assert_eq!(
+            lines[3],
+            "@+valueless!;7;++client-nonce=abc :tmi.twitch.tv ROOMSTATE #museun"
+        );
+        assert_eq!(lines[4], "@13=now;7=1;14! :tmi.twitch.tv ROOMSTATE #museun");
+        assert_eq!(lines[5], "@13!;7;14 :tmi.twitch.tv ROOMSTATE #museun");
+    }
+
+    #[test]
+    fn smaller() {
+        let line = |i: usize| {
+            format!(
+                "@badge-info=subscriber/12;badges=subscriber/12,premium/1;color=#1E90FF;display-name=museun;emotes=;id={};mod=0;room-id=23196011;subscriber=1;tmi-sent-ts={};turbo=0;user-id=23196011;user-type= :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :message {}\r\n",
+                i,
+                1_601_079_032_426 + i,
+                i
+            )
+        };
+        let lines = (0..100).map(line).collect::<Vec<_>>();
+        let lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+
+        let raw = lines.iter().map(|line| line.len()).sum::<usize>();
+        assert!(encode(&lines).len() * 2 < raw);
+    }
+
+    #[test]
+    fn invalid_input() {
+        let mut encoder = DeltaEncoder::new(vec![]);
+        let err = encoder.encode("@id=1\r\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = encoder.encode("PING :a\r\nPING :b\r\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(encoder.into_inner().is_empty());
+
+        for log in &[
+            "@0 PING :a\n",
+            "@x=1 PING :a\n",
+            "@+a=1;+a=2 PING :a\n",
+            "@a=1\n",
+        ] {
+            let err = DeltaDecoder::new(log.as_bytes())
+                .next()
+                .unwrap()
+                .unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{}", log);
+        }
+    }
+}
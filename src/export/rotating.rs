@@ -0,0 +1,336 @@
+use std::{
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+// the writes to the current file are buffered up to this many bytes
+const BUFFER: usize = 64 * 1024;
+
+struct Current {
+    file: BufWriter<File>,
+    opened: Instant,
+    written: u64,
+}
+
+/// A [Write] to a log file that is rotated when it gets too large or too old.
+///
+/// The current file is `<prefix>.log` in the directory. When it is rotated,
+/// it is renamed to `<prefix>-<unix millis>.log` (and gzipped to
+/// `<prefix>-<unix millis>.log.gz` with the `gzip` feature) and a new one is
+/// started. Only a fixed size buffer is kept in memory, and with
+/// [RotatingWriter::keep] only that many rotated files are kept on disk.
+///
+/// Files are only rotated between lines, so a line is never split between two files.
+/// Use it with a [LogExporter](super::LogExporter) in the [Raw](super::LogFormat::Raw)
+/// format to archive every message a runner receives, e.g. from
+/// `AsyncRunner::subscribe_all`.
+///
+/// ```no_run
+/// # use twitchchat::export::{LogExporter, LogFormat, RotatingWriter};
+/// # use std::time::Duration;
+/// let writer = RotatingWriter::new("logs", "museun")?
+///     .max_bytes(64 * 1024 * 1024)
+///     .max_age(Duration::from_secs(24 * 60 * 60))
+///     .keep(30);
+/// let mut exporter = LogExporter::new(writer, LogFormat::Raw);
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub struct RotatingWriter {
+    dir: PathBuf,
+    prefix: String,
+    max_bytes: Option<u64>,
+    max_age: Option<Duration>,
+    keep: Option<usize>,
+    #[cfg(feature = "gzip")]
+    gzip: bool,
+    current: Option<Current>,
+    at_line_start: bool,
+    rotated: VecDeque<PathBuf>,
+}
+
+impl std::fmt::Debug for RotatingWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RotatingWriter")
+            .field("path", &self.path())
+            .field("max_bytes", &self.max_bytes)
+            .field("max_age", &self.max_age)
+            .field("keep", &self.keep)
+            .finish()
+    }
+}
+
+impl RotatingWriter {
+    /// Create a writer for `<prefix>.log` in `dir`, creating the directory if needed.
+    ///
+    /// An existing `<prefix>.log` is appended to. Without a limit, the file is never rotated.
+    pub fn new(dir: impl Into<PathBuf>, prefix: impl Into<String>) -> io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            prefix: prefix.into(),
+            max_bytes: None,
+            max_age: None,
+            keep: None,
+            #[cfg(feature = "gzip")]
+            gzip: false,
+            current: None,
+            at_line_start: true,
+            rotated: VecDeque::new(),
+        })
+    }
+
+    /// Rotate the file once it is at least this large
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes.replace(max_bytes);
+        self
+    }
+
+    /// Rotate the file once it has been written to for this long
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age.replace(max_age);
+        self
+    }
+
+    /// Only keep this many rotated files, deleting the oldest ones.
+    ///
+    /// Only the files rotated by this writer are counted.
+    pub fn keep(mut self, keep: usize) -> Self {
+        self.keep.replace(keep);
+        self
+    }
+
+    /// Gzip the files when they are rotated
+    #[cfg(feature = "gzip")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gzip")))]
+    pub fn gzip(mut self, gzip: bool) -> Self {
+        self.gzip = gzip;
+        self
+    }
+
+    /// The path of the current file
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(format!("{}.log", self.prefix))
+    }
+
+    /// The rotated files that are kept, oldest first
+    pub fn rotated(&self) -> impl Iterator<Item = &Path> + '_ {
+        self.rotated.iter().map(PathBuf::as_path)
+    }
+
+    /// Close the current file and start a new one
+    pub fn rotate(&mut self) -> io::Result<()> {
+        let mut current = match self.current.take() {
+            Some(current) => current,
+            None => return Ok(()),
+        };
+        current.file.flush()?;
+        drop(current);
+
+        let path = self.path();
+        let rotated = self.rotated_path();
+        std::fs::rename(&path, &rotated)?;
+        let rotated = self.compress(rotated)?;
+        self.rotated.push_back(rotated);
+
+        if let Some(keep) = self.keep {
+            while self.rotated.len() > keep {
+                if let Some(oldest) = self.rotated.pop_front() {
+                    std::fs::remove_file(oldest)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn rotated_path(&self) -> PathBuf {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |dur| dur.as_millis());
+        let name = format!("{}-{}", self.prefix, millis);
+
+        // a few files can be rotated in the same millisecond
+        let taken = |path: &Path| path.exists() || path.with_extension("log.gz").exists();
+        let mut path = self.dir.join(format!("{}.log", name));
+        let mut n = 0;
+        while taken(&path) {
+            n += 1;
+            path = self.dir.join(format!("{}-{}.log", name, n));
+        }
+        path
+    }
+
+    #[cfg(feature = "gzip")]
+    fn compress(&self, path: PathBuf) -> io::Result<PathBuf> {
+        use flate2::{write::GzEncoder, Compression};
+        if !self.gzip {
+            return Ok(path);
+        }
+
+        let gz = path.with_extension("log.gz");
+        let mut encoder = GzEncoder::new(File::create(&gz)?, Compression::default());
+        io::copy(&mut File::open(&path)?, &mut encoder)?;
+        encoder.finish()?.sync_all()?;
+        std::fs::remove_file(&path)?;
+        Ok(gz)
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    fn compress(&self, path: PathBuf) -> io::Result<PathBuf> {
+        Ok(path)
+    }
+
+    fn is_due(&self) -> bool {
+        let current = match &self.current {
+            Some(current) => current,
+            None => return false,
+        };
+        matches!(self.max_bytes, Some(max) if current.written >= max)
+            || matches!(self.max_age, Some(max) if current.opened.elapsed() >= max)
+    }
+
+    fn current(&mut self) -> io::Result<&mut Current> {
+        let current = match self.current.take() {
+            Some(current) => current,
+            None => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(self.path())?;
+                Current {
+                    written: file.metadata()?.len(),
+                    file: BufWriter::with_capacity(BUFFER, file),
+                    opened: Instant::now(),
+                }
+            }
+        };
+        Ok(self.current.get_or_insert(current))
+    }
+}
+
+impl Write for RotatingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.at_line_start && self.is_due() {
+            self.rotate()?;
+        }
+
+        let current = self.current()?;
+        let n = current.file.write(buf)?;
+        current.written += n as u64;
+        if n > 0 {
+            self.at_line_start = buf.get(n - 1) == Some(&b'\n');
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.current {
+            Some(current) => current.file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "twitchchat-rotating-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn rotate_by_size() {
+        let dir = temp_dir("size");
+        let mut writer = RotatingWriter::new(&dir, "museun")
+            .unwrap()
+            .max_bytes(10)
+            .keep(2);
+
+        // a line isn't split, even when it's over the limit
+        writer.write_all(b"PING :first line\r\n").unwrap();
+        writer.write_all(b"PING ").unwrap();
+        writer.write_all(b":second\r\n").unwrap();
+        writer.write_all(b"PING :third\r\n").unwrap();
+        writer.write_all(b"PING :fourth\r\n").unwrap();
+        writer.flush().unwrap();
+
+        let rotated = writer.rotated().map(Path::to_path_buf).collect::<Vec<_>>();
+        assert_eq!(rotated.len(), 2);
+        assert_eq!(
+            std::fs::read_to_string(&rotated[0]).unwrap(),
+            "PING :second\r\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&rotated[1]).unwrap(),
+            "PING :third\r\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(writer.path()).unwrap(),
+            "PING :fourth\r\n"
+        );
+
+        // the oldest one was deleted
+        let files = std::fs::read_dir(&dir).unwrap().count();
+        assert_eq!(files, 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rotate_by_age() {
+        let dir = temp_dir("age");
+        let mut writer = RotatingWriter::new(&dir, "museun")
+            .unwrap()
+            .max_age(Duration::from_secs(0));
+
+        writer.write_all(b"PING :first\r\n").unwrap();
+        writer.write_all(b"PING :second\r\n").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.rotated().count(), 1);
+
+        // without limits, nothing is rotated
+        let mut writer = RotatingWriter::new(&dir, "shaken_bot").unwrap();
+        for _ in 0..10 {
+            writer.write_all(b"PING :hello\r\n").unwrap();
+        }
+        assert_eq!(writer.rotated().count(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip() {
+        use flate2::read::GzDecoder;
+        use std::io::Read as _;
+
+        let dir = temp_dir("gzip");
+        let mut writer = RotatingWriter::new(&dir, "museun").unwrap().gzip(true);
+        writer.write_all(b"PING :first\r\n").unwrap();
+        writer.rotate().unwrap();
+
+        let rotated = writer.rotated().next().unwrap().to_path_buf();
+        assert_eq!(rotated.extension().unwrap(), "gz");
+
+        let mut data = String::new();
+        GzDecoder::new(File::open(rotated).unwrap())
+            .read_to_string(&mut data)
+            .unwrap();
+        assert_eq!(data, "PING :first\r\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
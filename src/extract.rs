@@ -0,0 +1,441 @@
+/*!
+Typed arguments for chat commands, like `!give @museun 5 for the raid`.
+
+A handler is a function whose parameters implement [FromArgs]. They are parsed
+from the words after the command, in order: any [FromStr] type takes a word,
+a [Mention] takes a user name (with or without the `@`) and [Rest] takes
+whatever is left. When an argument is missing or doesn't parse, the
+[UsageError] can be sent back to chat as is.
+
+```
+# use twitchchat::extract::{CommandRouter, Mention, Rest};
+let mut router = CommandRouter::new();
+router.command("!give", |user: Mention, count: u32, reason: Rest| {
+    format!("gave {} {} points {}", user, count, reason)
+});
+
+assert_eq!(
+    router.dispatch("!give @Museun 5 for the raid").unwrap().unwrap(),
+    "gave museun 5 points for the raid"
+);
+
+// not a command the router knows
+assert!(router.dispatch("hello world").is_none());
+
+let err = router.dispatch("!give museun five").unwrap().unwrap_err();
+assert_eq!(
+    err.to_string(),
+    "'five' is not a valid <u32> (argument 2). usage: !give <@user> <u32> [text...]"
+);
+```
+*/
+
+use std::{collections::HashMap, str::FromStr};
+
+/// The words after a command, which the [FromArgs] of a handler are parsed from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arguments<'a> {
+    rest: &'a str,
+    position: usize,
+}
+
+impl<'a> Arguments<'a> {
+    /// Create the arguments from the text after the command
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            rest: input.trim(),
+            position: 0,
+        }
+    }
+
+    /// The position of the last argument that was taken, starting at `1`
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Take the next word, if any is left
+    pub fn next_word(&mut self) -> Option<&'a str> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        self.position += 1;
+        let (word, rest) = self
+            .rest
+            .split_once(char::is_whitespace)
+            .unwrap_or((self.rest, ""));
+        self.rest = rest.trim_start();
+        Some(word)
+    }
+
+    /// Take the rest of the text, which may be empty
+    pub fn rest(&mut self) -> &'a str {
+        self.position += 1;
+        std::mem::take(&mut self.rest)
+    }
+
+    /// Make sure all of the arguments were taken
+    pub fn finish(&self) -> Result<(), ArgError> {
+        if self.rest.is_empty() {
+            return Ok(());
+        }
+        Err(ArgError::TooMany {
+            position: self.position + 1,
+            extra: self.rest.to_string(),
+        })
+    }
+}
+
+/// A type that can be taken from the [Arguments] of a command
+pub trait FromArgs: Sized {
+    /// How this argument is shown in a usage message, e.g. `<u32>`
+    fn usage() -> String;
+
+    /// Take this argument from the `args`
+    fn from_args(args: &mut Arguments<'_>) -> Result<Self, ArgError>;
+}
+
+impl<T: FromStr> FromArgs for T {
+    fn usage() -> String {
+        // e.g. `alloc::vec::Vec<u32>` is shown as `<Vec>`
+        let name = std::any::type_name::<T>();
+        let path = name.split('<').next().unwrap_or(name);
+        format!("<{}>", path.rsplit("::").next().unwrap_or(path))
+    }
+
+    fn from_args(args: &mut Arguments<'_>) -> Result<Self, ArgError> {
+        let word = next_word::<Self>(args)?;
+        word.parse().map_err(|_| ArgError::Invalid {
+            position: args.position(),
+            value: word.to_string(),
+            expected: Self::usage(),
+        })
+    }
+}
+
+fn next_word<'a, T: FromArgs>(args: &mut Arguments<'a>) -> Result<&'a str, ArgError> {
+    args.next_word().ok_or_else(|| ArgError::Missing {
+        position: args.position() + 1,
+        expected: T::usage(),
+    })
+}
+
+/// A user name, given as `@name` or just `name`. It is lowercased, like a login.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Mention(pub String);
+
+impl std::fmt::Display for Mention {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromArgs for Mention {
+    fn usage() -> String {
+        "<@user>".to_string()
+    }
+
+    fn from_args(args: &mut Arguments<'_>) -> Result<Self, ArgError> {
+        let word = next_word::<Self>(args)?;
+        let name = word.strip_prefix('@').unwrap_or(word);
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(ArgError::Invalid {
+                position: args.position(),
+                value: word.to_string(),
+                expected: Self::usage(),
+            });
+        }
+        Ok(Self(name.to_ascii_lowercase()))
+    }
+}
+
+/// The rest of the message after the other arguments, which may be empty
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Rest(pub String);
+
+impl std::fmt::Display for Rest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromArgs for Rest {
+    fn usage() -> String {
+        "[text...]".to_string()
+    }
+
+    fn from_args(args: &mut Arguments<'_>) -> Result<Self, ArgError> {
+        Ok(Self(args.rest().to_string()))
+    }
+}
+
+/// An argument of a command was missing or could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ArgError {
+    /// An argument wasn't given
+    Missing {
+        /// The position of the argument, starting at `1`
+        position: usize,
+        /// How the argument is shown in the usage
+        expected: String,
+    },
+    /// An argument could not be parsed
+    Invalid {
+        /// The position of the argument, starting at `1`
+        position: usize,
+        /// The word that was given
+        value: String,
+        /// How the argument is shown in the usage
+        expected: String,
+    },
+    /// More arguments were given than the command takes
+    TooMany {
+        /// The position of the first extra argument, starting at `1`
+        position: usize,
+        /// The extra arguments
+        extra: String,
+    },
+}
+
+impl std::fmt::Display for ArgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing { position, expected } => {
+                write!(f, "missing {} (argument {})", expected, position)
+            }
+            Self::Invalid {
+                position,
+                value,
+                expected,
+            } => write!(
+                f,
+                "'{}' is not a valid {} (argument {})",
+                value, expected, position
+            ),
+            Self::TooMany { extra, .. } => write!(f, "unexpected '{}'", extra),
+        }
+    }
+}
+
+impl std::error::Error for ArgError {}
+
+/// An [ArgError], with the usage of the command it happened in.
+///
+/// Its `Display` is meant to be sent back to chat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageError {
+    /// The command, e.g. `!give`
+    pub command: String,
+    /// The arguments the command takes, e.g. `<@user> <u32>`
+    pub usage: String,
+    /// What was wrong with the arguments
+    pub error: ArgError,
+}
+
+impl std::fmt::Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}. usage: {}", self.error, self.command)?;
+        if !self.usage.is_empty() {
+            write!(f, " {}", self.usage)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for UsageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// A function whose parameters are all [FromArgs]
+///
+/// This is implemented for functions of up to 8 parameters. `Args` is the
+/// tuple of the parameter types, so that it can be implemented for each arity.
+pub trait Handler<Args>: Send + Sync {
+    /// What the function returns
+    type Output;
+
+    /// Parse the arguments and call the function with them
+    fn call(&self, args: &mut Arguments<'_>) -> Result<Self::Output, ArgError>;
+
+    /// The usage of the parameters, e.g. `<@user> <u32> [text...]`
+    fn usage(&self) -> String;
+}
+
+macro_rules! handler {
+    ($($ty:ident),*) => {
+        impl<Func, Out, $($ty,)*> Handler<($($ty,)*)> for Func
+        where
+            Func: Fn($($ty),*) -> Out + Send + Sync,
+            $($ty: FromArgs,)*
+        {
+            type Output = Out;
+
+            #[allow(non_snake_case, unused_variables)]
+            fn call(&self, args: &mut Arguments<'_>) -> Result<Out, ArgError> {
+                $(let $ty = $ty::from_args(args)?;)*
+                args.finish()?;
+                Ok((self)($($ty),*))
+            }
+
+            fn usage(&self) -> String {
+                let usage: &[String] = &[$($ty::usage()),*];
+                usage.join(" ")
+            }
+        }
+    };
+}
+
+handler!();
+handler!(A);
+handler!(A, B);
+handler!(A, B, C);
+handler!(A, B, C, D);
+handler!(A, B, C, D, E);
+handler!(A, B, C, D, E, F);
+handler!(A, B, C, D, E, F, G);
+handler!(A, B, C, D, E, F, G, H);
+
+type BoxedHandler<T> = Box<dyn Fn(&mut Arguments<'_>) -> Result<T, ArgError> + Send + Sync>;
+
+struct Route<T> {
+    handler: BoxedHandler<T>,
+    usage: String,
+}
+
+/// Commands by their name, each with a [Handler] that returns a `T`
+pub struct CommandRouter<T> {
+    routes: HashMap<String, Route<T>>,
+}
+
+impl<T> std::fmt::Debug for CommandRouter<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CommandRouter")
+            .field("commands", &self.routes.keys())
+            .finish()
+    }
+}
+
+impl<T> Default for CommandRouter<T> {
+    fn default() -> Self {
+        Self {
+            routes: HashMap::new(),
+        }
+    }
+}
+
+impl<T> CommandRouter<T> {
+    /// Create an empty router
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a command, e.g. `!give`. This replaces a command with the same name.
+    pub fn command<H, Args>(&mut self, name: impl Into<String>, handler: H) -> &mut Self
+    where
+        H: Handler<Args, Output = T> + 'static,
+        Args: 'static,
+    {
+        let usage = handler.usage();
+        let route = Route {
+            handler: Box::new(move |args| handler.call(args)),
+            usage,
+        };
+        self.routes.insert(name.into(), route);
+        self
+    }
+
+    /// The usage of a command, e.g. `<@user> <u32>`, if it was added
+    pub fn usage(&self, name: &str) -> Option<&str> {
+        self.routes.get(name).map(|route| &*route.usage)
+    }
+
+    /// Call the handler of the command `data` starts with.
+    ///
+    /// This is `None` when `data` isn't one of the commands.
+    pub fn dispatch(&self, data: &str) -> Option<Result<T, UsageError>> {
+        let data = data.trim_start();
+        let (name, rest) = data.split_once(char::is_whitespace).unwrap_or((data, ""));
+        let route = self.routes.get(name)?;
+
+        let result = (route.handler)(&mut Arguments::new(rest)).map_err(|error| UsageError {
+            command: name.to_string(),
+            usage: route.usage.clone(),
+            error,
+        });
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arguments() {
+        let mut args = Arguments::new("  one  two\tthree four ");
+        assert_eq!(args.next_word(), Some("one"));
+        assert_eq!(args.next_word(), Some("two"));
+        assert_eq!(args.position(), 2);
+        assert_eq!(args.rest(), "three four");
+        assert_eq!(args.next_word(), None);
+        assert!(args.finish().is_ok());
+    }
+
+    #[test]
+    fn errors() {
+        let mut router = CommandRouter::new();
+        router
+            .command("!ping", || "pong")
+            .command("!so", |_: Mention| "shoutout")
+            .command("!roll", |_: u8, _: u8| "rolled");
+
+        assert_eq!(router.dispatch("!ping").unwrap(), Ok("pong"));
+        assert_eq!(router.usage("!roll"), Some("<u8> <u8>"));
+
+        let err = router.dispatch("!ping pong").unwrap().unwrap_err();
+        assert_eq!(
+            err.error,
+            ArgError::TooMany {
+                position: 1,
+                extra: "pong".to_string()
+            }
+        );
+        assert_eq!(err.to_string(), "unexpected 'pong'. usage: !ping");
+
+        let err = router.dispatch("!so").unwrap().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "missing <@user> (argument 1). usage: !so <@user>"
+        );
+
+        let err = router.dispatch("!so @").unwrap().unwrap_err();
+        assert!(matches!(err.error, ArgError::Invalid { position: 1, .. }));
+
+        let err = router.dispatch("!roll 1 300").unwrap().unwrap_err();
+        assert_eq!(
+            err.error,
+            ArgError::Invalid {
+                position: 2,
+                value: "300".to_string(),
+                expected: "<u8>".to_string()
+            }
+        );
+
+        assert!(router.dispatch("!unknown").is_none());
+        assert!(router.dispatch("").is_none());
+    }
+
+    #[test]
+    fn rest() {
+        let mut router = CommandRouter::new();
+        router.command("!say", |Rest(text): Rest| text);
+
+        assert_eq!(router.dispatch("!say").unwrap(), Ok(String::new()));
+        assert_eq!(
+            router.dispatch("!say  hello   world ").unwrap(),
+            Ok("hello   world".to_string())
+        );
+    }
+}
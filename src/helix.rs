@@ -0,0 +1,334 @@
+/*!
+A minimal client for the parts of the [Helix](https://dev.twitch.tv/docs/api/) API that chat bots need.
+
+Currently this is only sending whispers, which Twitch heavily restricts over IRC
+for unverified bots. See [whisper](crate::whisper) for choosing between the two.
+
+The token must have the `user:manage:whispers` scope, and must belong to the
+user with the `from_user_id` of the [HelixConfig].
+
+This speaks plain HTTP over whatever stream the [Connector] returns, so you
+want a TLS connector to the [ADDRESS] with the [DOMAIN], e.g.
+`SmolConnectorTls::custom(ADDRESS, DOMAIN)`.
+
+```no_run
+# use twitchchat::{connector::Connector, helix::{HelixConfig, HelixWhisper}};
+# async fn demo(connector: impl Connector) -> Result<(), twitchchat::helix::Error> {
+let config = HelixConfig {
+    client_id: "my-client-id".into(),
+    token: "my-oauth-token".into(),
+    from_user_id: "23196011".into(),
+};
+let mut client = HelixWhisper::new(connector, config);
+client.whisper("museun", "hello world").await?;
+# Ok(())
+# }
+```
+*/
+
+use crate::connector::Connector;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// The address of the Helix API
+pub const ADDRESS: &str = "api.twitch.tv:443";
+
+/// The TLS domain (and HTTP host) of the Helix API
+pub const DOMAIN: &str = "api.twitch.tv";
+
+/// An error returned by the [HelixWhisper] client
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An I/O error occurred
+    Io(std::io::Error),
+    /// The response was not a valid HTTP response
+    InvalidResponse,
+    /// The API responded with an unexpected HTTP status
+    Status {
+        /// The HTTP status
+        status: u16,
+        /// The error message of the API, if it sent one
+        message: Option<String>,
+    },
+    /// The response body was not the expected JSON
+    Json(serde_json::Error),
+    /// There is no user with this name
+    UnknownUser(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io error: {}", err),
+            Self::InvalidResponse => write!(f, "invalid HTTP response"),
+            Self::Status {
+                status,
+                message: Some(message),
+            } => write!(f, "unexpected HTTP status: {} ({})", status, message),
+            Self::Status {
+                status,
+                message: None,
+            } => write!(f, "unexpected HTTP status: {}", status),
+            Self::Json(err) => write!(f, "invalid response body: {}", err),
+            Self::UnknownUser(name) => write!(f, "unknown user: {}", name),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+/// The credentials used for the Helix API
+#[derive(Clone, PartialEq, Eq)]
+pub struct HelixConfig {
+    /// The client id of your application
+    pub client_id: String,
+    /// A user access token, without the `oauth:` prefix
+    pub token: String,
+    /// The user id of the owner of the token
+    pub from_user_id: String,
+}
+
+impl std::fmt::Debug for HelixConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HelixConfig")
+            .field("client_id", &self.client_id)
+            .field("token", &"{redacted}")
+            .field("from_user_id", &self.from_user_id)
+            .finish()
+    }
+}
+
+#[derive(::serde::Deserialize)]
+struct Users {
+    data: Vec<User>,
+}
+
+#[derive(::serde::Deserialize)]
+struct User {
+    id: String,
+    login: String,
+}
+
+#[derive(::serde::Deserialize)]
+struct ErrorBody {
+    message: Option<String>,
+}
+
+/// Sends whispers with the Helix API.
+///
+/// The user ids of the names are looked up once and cached, cloning this shares the cache.
+#[derive(Debug, Clone)]
+pub struct HelixWhisper<C> {
+    connector: C,
+    config: HelixConfig,
+    host: String,
+    user_ids: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl<C> HelixWhisper<C>
+where
+    C: Connector,
+{
+    /// Create a client that connects with this connector
+    pub fn new(connector: C, config: HelixConfig) -> Self {
+        Self::with_host(connector, config, DOMAIN)
+    }
+
+    /// Create a client that sends this `Host` header, e.g. for a mock of the API
+    pub fn with_host(connector: C, config: HelixConfig, host: impl Into<String>) -> Self {
+        Self {
+            connector,
+            config,
+            host: host.into(),
+            user_ids: Arc::default(),
+        }
+    }
+
+    /// Remember the user id of this name, e.g. from the `user-id` tag of a message
+    pub fn insert_user_id(&self, name: &str, user_id: impl Into<String>) {
+        self.user_ids
+            .lock()
+            .unwrap()
+            .insert(name.to_lowercase(), user_id.into());
+    }
+
+    /// Look up the user id of this name
+    pub async fn user_id(&mut self, name: &str) -> Result<String, Error> {
+        let name = name.to_lowercase();
+        if let Some(id) = self.user_ids.lock().unwrap().get(&name) {
+            return Ok(id.clone());
+        }
+
+        let path = format!("/helix/users?login={}", name);
+        let body = self.request("GET", &path, None).await?;
+
+        let users: Users = serde_json::from_str(&body)?;
+        let user = users
+            .data
+            .into_iter()
+            .find(|user| user.login.eq_ignore_ascii_case(&name))
+            .ok_or(Error::UnknownUser(name))?;
+
+        self.insert_user_id(&user.login, user.id.clone());
+        Ok(user.id)
+    }
+
+    /// Whisper this `message` to `username`
+    pub async fn whisper(&mut self, username: &str, message: &str) -> Result<(), Error> {
+        let to_user_id = self.user_id(username).await?;
+        let path = format!(
+            "/helix/whispers?from_user_id={}&to_user_id={}",
+            self.config.from_user_id, to_user_id
+        );
+        let body = serde_json::json!({ "message": message }).to_string();
+        self.request("POST", &path, Some(&body)).await.map(drop)
+    }
+
+    async fn request(
+        &mut self,
+        method: &str,
+        path: &str,
+        body: Option<&str>,
+    ) -> Result<String, Error> {
+        let mut request = format!(
+            "{} {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: {}\r\nClient-Id: {}\r\nAuthorization: Bearer {}\r\nAccept: application/json\r\nConnection: close\r\n",
+            method,
+            path,
+            self.host,
+            crate::http::user_agent(),
+            self.config.client_id,
+            self.config.token,
+        );
+        match body {
+            Some(body) => request.push_str(&format!(
+                "Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )),
+            None => request.push_str("\r\n"),
+        }
+
+        let response = crate::http::send(&mut self.connector, &request).await?;
+        let (status, body) =
+            crate::http::split_response(&response).ok_or(Error::InvalidResponse)?;
+
+        if !(200..300).contains(&status) {
+            let message = serde_json::from_str::<ErrorBody>(body)
+                .ok()
+                .and_then(|body| body.message);
+            return Err(Error::Status { status, message });
+        }
+
+        Ok(body.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> HelixConfig {
+        HelixConfig {
+            client_id: "client".into(),
+            token: "token".into(),
+            from_user_id: "1234".into(),
+        }
+    }
+
+    #[test]
+    fn config_debug_hides_token() {
+        assert!(!format!("{:?}", config()).contains("token\""));
+    }
+
+    #[test]
+    #[cfg(feature = "testing")]
+    fn whisper() {
+        let connector = crate::test::TestConnector::default();
+        let client = HelixWhisper::new(connector.clone(), config());
+        client.insert_user_id("Museun", "23196011");
+
+        futures_lite::future::block_on(async {
+            connector
+                .conn
+                .write_data("HTTP/1.1 204 No Content\r\n\r\n")
+                .await;
+            client.clone().whisper("museun", "hello \"world\"").await
+        })
+        .unwrap();
+
+        let request = futures_lite::future::block_on(connector.conn.read_all_lines()).unwrap();
+        assert_eq!(
+            request[0],
+            "POST /helix/whispers?from_user_id=1234&to_user_id=23196011 HTTP/1.0\r\n"
+        );
+        assert!(request.contains(&"Authorization: Bearer token\r\n".to_string()));
+        assert!(request
+            .last()
+            .unwrap()
+            .trim_end()
+            .ends_with(r#"{"message":"hello \"world\""}"#));
+    }
+
+    #[test]
+    #[cfg(feature = "testing")]
+    fn user_id() {
+        let connector = crate::test::TestConnector::default();
+        let mut client = HelixWhisper::new(connector.clone(), config());
+
+        let id = futures_lite::future::block_on(async {
+            connector
+                .conn
+                .write_data(concat!(
+                    "HTTP/1.1 200 OK\r\n\r\n",
+                    r#"{"data":[{"id":"23196011","login":"museun","display_name":"museun"}]}"#
+                ))
+                .await;
+            client.user_id("Museun").await
+        })
+        .unwrap();
+        assert_eq!(id, "23196011");
+
+        // the second lookup is cached
+        let id = futures_lite::future::block_on(client.user_id("museun")).unwrap();
+        assert_eq!(id, "23196011");
+
+        let err = futures_lite::future::block_on(async {
+            connector
+                .conn
+                .write_data(concat!(
+                    "HTTP/1.1 401 Unauthorized\r\n\r\n",
+                    r#"{"error":"Unauthorized","status":401,"message":"Invalid OAuth token"}"#
+                ))
+                .await;
+            client.user_id("shaken_bot").await
+        })
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Status { status: 401, message: Some(message) } if message == "Invalid OAuth token"
+        ));
+    }
+}
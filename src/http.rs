@@ -0,0 +1,34 @@
+//! Just enough HTTP/1.0 to talk to the web APIs some of the optional features use
+use crate::connector::Connector;
+use futures_lite::{AsyncReadExt as _, AsyncWriteExt as _};
+
+/// Send this complete request over a new connection, and read the whole response
+pub(crate) async fn send<C>(connector: &mut C, request: &str) -> std::io::Result<String>
+where
+    C: Connector,
+{
+    let mut stream = connector.connect().await?;
+    stream.write_all(request.as_bytes()).await?;
+    stream.flush().await?;
+
+    let mut response = vec![];
+    stream.read_to_end(&mut response).await?;
+    Ok(String::from_utf8_lossy(&response).into_owned())
+}
+
+/// Split a response into its status code and body
+pub(crate) fn split_response(response: &str) -> Option<(u16, &str)> {
+    let (head, body) = response.split_once("\r\n\r\n")?;
+    let status = head
+        .lines()
+        .next()
+        .filter(|line| line.starts_with("HTTP/"))
+        .and_then(|line| line.split(' ').nth(1))
+        .and_then(|status| status.parse().ok())?;
+    Some((status, body))
+}
+
+/// The `User-Agent` header sent with every request
+pub(crate) fn user_agent() -> String {
+    format!("twitchchat/{}", env!("CARGO_PKG_VERSION"))
+}
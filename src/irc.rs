@@ -50,6 +50,8 @@ impl<'a, T: 'a> IntoIrcMessage<'a> for T
 where
     T: FromIrcMessage<'a>,
 {
+    // the raw line was parsed when `self` was made, so it parses again
+    #[allow(clippy::expect_used)]
     fn into_irc(self) -> IrcMessage<'a> {
         IrcMessage::parse(self.into_inner()).expect("identity conversion")
     }
@@ -70,9 +72,24 @@ pub use tag_indices::TagIndices;
 mod error;
 pub use error::MessageError;
 
+mod limits;
+pub use limits::Limits;
+
+mod builder;
+pub use builder::{BuildError, IrcMessageBuilder};
+
+mod bridge;
+pub use bridge::{BridgeError, ForeignMessage};
+
+mod rewriter;
+pub use rewriter::MessageRewriter;
+
 mod parser;
 pub use parser::IrcParserIter;
 
+mod bytes;
+pub use bytes::{decode_bytes, BytesParserIter, Encoding};
+
 /// Parses a string and returns an iterator over the `IrcMessages` in it.
 ///
 /// This borrows from the input string.
@@ -80,6 +97,30 @@ pub fn parse(input: &str) -> IrcParserIter<'_> {
     IrcParserIter::new(input)
 }
 
+/// Parses bytes and returns an iterator over the `IrcMessages` in them, e.g. for importing old log files.
+///
+/// Each line is decoded on its own, see [decode_bytes]. The lines that are
+/// valid UTF-8 are borrowed from the input, the others are decoded lossily
+/// (or as Latin-1, with [BytesParserIter::detect_latin1]) into owned messages.
+///
+/// Unlike [parse], lines may end with just `\n`, the last line doesn't need a
+/// line ending, and empty lines are skipped.
+///
+/// ```
+/// # use twitchchat::irc::Encoding;
+/// let input = b"PING :tmi.twitch.tv\n:museun!museun@museun PRIVMSG #museun :caf\xe9\n";
+///
+/// let mut messages = twitchchat::irc::parse_bytes(input).detect_latin1(true);
+/// assert_eq!(messages.next().unwrap().unwrap().get_command(), "PING");
+///
+/// let msg = messages.next().unwrap().unwrap();
+/// assert_eq!(msg.get_data(), Some("café"));
+/// assert_eq!(messages.encoding(), Some(Encoding::Latin1));
+/// ```
+pub fn parse_bytes(input: &[u8]) -> BytesParserIter<'_> {
+    BytesParserIter::new(input)
+}
+
 /// Attempts to parse one message.
 ///
 /// This returns the index of the /next/ message (e.g, 0 for a single message) and the parsed message
@@ -91,10 +132,12 @@ pub fn parse_one(input: &str) -> Result<(usize, IrcMessage<'_>), MessageError> {
         .ok_or(MessageError::IncompleteMessage { pos: 0 })?
         + CRLF.len();
 
-    let next = &input[..pos];
+    let next = input
+        .get(..pos)
+        .ok_or(MessageError::IncompleteMessage { pos: 0 })?;
     let done = next.len() == input.len();
 
-    let msg = IrcMessage::parse(crate::MaybeOwned::Borrowed(next))?;
+    let msg = IrcMessage::parse(MaybeOwned::Borrowed(next))?;
     Ok((if done { 0 } else { pos }, msg))
 }
 
@@ -0,0 +1,251 @@
+use super::{BuildError, IrcMessage, IrcMessageBuilder};
+use crate::{messages::Commands, FromIrcMessage, MessageError};
+use std::borrow::Cow;
+
+#[cfg(feature = "irc-proto")]
+mod irc_proto;
+
+/// A message from another IRC library that can be converted into the types of this crate.
+///
+/// Implement this for the message type of your IRC stack and use
+/// [IrcMessage::from_foreign] or [Commands::from_foreign] to get the typed
+/// Twitch messages, without rendering and re-parsing the raw lines yourself.
+///
+/// This is implemented for the `Message` of [irc-proto](https://docs.rs/irc-proto)
+/// (and so for `irc::proto::Message`) with the `irc-proto` feature.
+///
+/// ```rust
+/// # use std::borrow::Cow;
+/// use twitchchat::{irc::ForeignMessage, messages::Commands};
+///
+/// // a message type from some other library
+/// struct Message {
+///     tags: Vec<(String, String)>,
+///     nick: Option<String>,
+///     command: String,
+///     params: Vec<String>,
+/// }
+///
+/// impl ForeignMessage for Message {
+///     fn tags(&self) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+///         self.tags.iter().map(|(k, v)| (k.into(), v.into())).collect()
+///     }
+///     fn prefix(&self) -> Option<Cow<'_, str>> {
+///         self.nick.as_deref().map(|nick| format!("{0}!{0}@{0}.tmi.twitch.tv", nick).into())
+///     }
+///     fn command(&self) -> Cow<'_, str> {
+///         self.command.as_str().into()
+///     }
+///     fn args(&self) -> Vec<Cow<'_, str>> {
+///         let n = self.params.len().saturating_sub(1);
+///         self.params[..n].iter().map(|s| s.into()).collect()
+///     }
+///     fn data(&self) -> Option<Cow<'_, str>> {
+///         self.params.last().map(|s| s.into())
+///     }
+/// }
+///
+/// let msg = Message {
+///     tags: vec![("display-name".into(), "museun".into())],
+///     nick: Some("museun".into()),
+///     command: "PRIVMSG".into(),
+///     params: vec!["#museun".into(), "hello world".into()],
+/// };
+///
+/// match Commands::from_foreign(&msg).unwrap() {
+///     Commands::Privmsg(pm) => assert_eq!(pm.data(), "hello world"),
+///     _ => unreachable!(),
+/// }
+/// ```
+pub trait ForeignMessage {
+    /// The tags of the message, with their values **unescaped**
+    fn tags(&self) -> Vec<(Cow<'_, str>, Cow<'_, str>)>;
+
+    /// The prefix of the message, without the leading `:`
+    fn prefix(&self) -> Option<Cow<'_, str>>;
+
+    /// The command of the message, e.g. `PRIVMSG`
+    fn command(&self) -> Cow<'_, str>;
+
+    /// The middle parameters of the message, e.g. the channel
+    fn args(&self) -> Vec<Cow<'_, str>>;
+
+    /// The trailing parameter of the message
+    ///
+    /// If your library doesn't distinguish the trailing parameter, return the
+    /// last parameter here for messages that carry data.
+    fn data(&self) -> Option<Cow<'_, str>>;
+}
+
+/// An error returned when converting a [ForeignMessage]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BridgeError {
+    /// The message could not be rendered as a valid IRC line
+    Build(BuildError),
+    /// The line was not a valid Twitch message
+    Message(MessageError),
+}
+
+impl std::fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Build(err) => write!(f, "cannot build the message: {}", err),
+            Self::Message(err) => write!(f, "invalid message: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BridgeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Build(err) => Some(err),
+            Self::Message(err) => Some(err),
+        }
+    }
+}
+
+impl From<BuildError> for BridgeError {
+    fn from(err: BuildError) -> Self {
+        Self::Build(err)
+    }
+}
+
+impl From<MessageError> for BridgeError {
+    fn from(err: MessageError) -> Self {
+        Self::Message(err)
+    }
+}
+
+impl IrcMessage<'static> {
+    /// Convert a message from another IRC library into an `IrcMessage`
+    ///
+    /// # Errors
+    /// If the message contains parts that cannot be put on the wire as-is
+    pub fn from_foreign<M>(msg: &M) -> Result<Self, BuildError>
+    where
+        M: ForeignMessage + ?Sized,
+    {
+        let mut builder = IrcMessageBuilder::new(msg.command()).tags(msg.tags());
+        if let Some(prefix) = msg.prefix() {
+            builder = builder.prefix(prefix);
+        }
+        for arg in msg.args() {
+            builder = builder.arg(arg);
+        }
+        if let Some(data) = msg.data() {
+            builder = builder.data(data);
+        }
+        builder.build()
+    }
+}
+
+impl Commands<'static> {
+    /// Convert a message from another IRC library into a typed Twitch message
+    ///
+    /// # Errors
+    /// If the message cannot be put on the wire as-is, or it isn't a valid Twitch message
+    pub fn from_foreign<M>(msg: &M) -> Result<Self, BridgeError>
+    where
+        M: ForeignMessage + ?Sized,
+    {
+        let msg = IrcMessage::from_foreign(msg)?;
+        Commands::from_irc(msg).map_err(Into::into)
+    }
+}
+
+impl<'a> ForeignMessage for IrcMessage<'a> {
+    fn tags(&self) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+        use crate::Validator as _;
+        let indices = self.parse_tags();
+        let tags = super::Tags::from_data_indices(&self.raw, &indices);
+        tags.iter()
+            .map(|(k, v)| {
+                let v = super::tags::unescape_str(v).to_string();
+                (Cow::Owned(k.to_string()), Cow::Owned(v))
+            })
+            .collect()
+    }
+
+    fn prefix(&self) -> Option<Cow<'_, str>> {
+        // get_prefix only returns the nick (or host), so take the whole prefix from the raw line
+        let raw = self.get_raw().trim_start();
+        let raw = match raw.strip_prefix('@') {
+            Some(tags) => tags.split_once(' ')?.1.trim_start(),
+            None => raw,
+        };
+        raw.strip_prefix(':')?.split(' ').next().map(Cow::Borrowed)
+    }
+
+    fn command(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self.get_command())
+    }
+
+    fn args(&self) -> Vec<Cow<'_, str>> {
+        self.get_args()
+            .map(|args| args.split_whitespace().map(Cow::Borrowed).collect())
+            .unwrap_or_default()
+    }
+
+    fn data(&self) -> Option<Cow<'_, str>> {
+        self.get_data().map(Cow::Borrowed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity() {
+        let inputs = &[
+            "@badge-info=;badges=broadcaster/1;color=#FF69B4;display-name=museun;emotes=25:0-4;system-msg=hello\\sworld :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :Kappa test\r\n",
+            ":tmi.twitch.tv CLEARCHAT #museun :shaken_bot\r\n",
+            "PING :1234567890\r\n",
+            ":museun!museun@museun.tmi.twitch.tv JOIN #museun\r\n",
+        ];
+
+        for input in inputs {
+            let msg = crate::irc::parse(input).next().unwrap().unwrap();
+            let bridged = IrcMessage::from_foreign(&msg).unwrap();
+            assert_eq!(bridged.get_raw(), *input);
+            assert_eq!(
+                Commands::from_irc(msg).unwrap(),
+                Commands::from_foreign(&bridged).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn errors() {
+        struct Bad(&'static str);
+        impl ForeignMessage for Bad {
+            fn tags(&self) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+                vec![]
+            }
+            fn prefix(&self) -> Option<Cow<'_, str>> {
+                None
+            }
+            fn command(&self) -> Cow<'_, str> {
+                self.0.into()
+            }
+            fn args(&self) -> Vec<Cow<'_, str>> {
+                vec![]
+            }
+            fn data(&self) -> Option<Cow<'_, str>> {
+                None
+            }
+        }
+
+        assert!(matches!(
+            Commands::from_foreign(&Bad("")).unwrap_err(),
+            BridgeError::Build(BuildError::InvalidCommand)
+        ));
+
+        // PRIVMSG without a channel
+        assert!(matches!(
+            Commands::from_foreign(&Bad("PRIVMSG")).unwrap_err(),
+            BridgeError::Message(..)
+        ));
+    }
+}
@@ -0,0 +1,157 @@
+use super::ForeignMessage;
+use irc_proto::{Command, Message};
+use std::borrow::Cow;
+
+// irc-proto doesn't keep which parameter was the trailing one, so this puts
+// them back the way Twitch sends them
+fn params(cmd: &Command) -> (Vec<Cow<'_, str>>, Option<Cow<'_, str>>) {
+    use Command::*;
+    fn borrowed(s: &str) -> Cow<'_, str> {
+        Cow::Borrowed(s)
+    }
+    fn all(params: &[String]) -> Vec<Cow<'_, str>> {
+        params.iter().map(|s| borrowed(s)).collect()
+    }
+
+    match cmd {
+        PRIVMSG(target, data) | NOTICE(target, data) => {
+            (vec![borrowed(target)], Some(borrowed(data)))
+        }
+        JOIN(channel, ..) => (vec![borrowed(channel)], None),
+        PART(channel, comment) => (vec![borrowed(channel)], comment.as_deref().map(borrowed)),
+        PING(token, None) | PONG(token, None) => (vec![], Some(borrowed(token))),
+        PING(server, Some(token)) | PONG(server, Some(token)) => {
+            (vec![borrowed(server)], Some(borrowed(token)))
+        }
+        // the capabilities can end up in any of the last two
+        CAP(target, sub, code, param) => {
+            let mut args = target
+                .as_deref()
+                .into_iter()
+                .chain(Some(sub.to_str()))
+                .chain(code.as_deref())
+                .chain(param.as_deref())
+                .map(borrowed)
+                .collect::<Vec<_>>();
+            let data = if code.is_some() || param.is_some() {
+                args.pop()
+            } else {
+                None
+            };
+            (args, data)
+        }
+        // e.g. CLEARCHAT, USERNOTICE, WHISPER and the numeric replies: the
+        // first parameter is the target, anything after it ends with the data
+        Response(_, params) | Raw(_, params) => match params.split_last() {
+            Some((data, args)) if !args.is_empty() => (all(args), Some(borrowed(data))),
+            _ => (all(params), None),
+        },
+        // Twitch doesn't send the other commands, so the line irc-proto would
+        // write is good enough
+        cmd => {
+            let line = String::from(cmd);
+            let (head, data) = match line.split_once(" :") {
+                Some((head, data)) => (head, Some(Cow::Owned(data.to_string()))),
+                None => (line.as_str(), None),
+            };
+            let args = head
+                .split(' ')
+                .skip(1)
+                .map(|arg| Cow::Owned(arg.to_string()))
+                .collect();
+            (args, data)
+        }
+    }
+}
+
+/// Requires the `irc-proto` feature. This also covers `irc::proto::Message` of the [irc](https://docs.rs/irc) crate.
+///
+/// ```rust
+/// use twitchchat::messages::Commands;
+///
+/// let msg: irc_proto::Message = "@display-name=museun :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello"
+///     .parse()
+///     .unwrap();
+///
+/// match Commands::from_foreign(&msg).unwrap() {
+///     Commands::Privmsg(pm) => assert_eq!(pm.data(), "hello"),
+///     _ => unreachable!(),
+/// }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "irc-proto")))]
+impl ForeignMessage for Message {
+    fn tags(&self) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+        self.tags
+            .iter()
+            .flatten()
+            .map(|tag| {
+                let value = tag.1.as_deref().unwrap_or_default();
+                (Cow::Borrowed(tag.0.as_str()), Cow::Borrowed(value))
+            })
+            .collect()
+    }
+
+    fn prefix(&self) -> Option<Cow<'_, str>> {
+        self.prefix
+            .as_ref()
+            .map(|prefix| Cow::Owned(prefix.to_string()))
+    }
+
+    fn command(&self) -> Cow<'_, str> {
+        match &self.command {
+            Command::Response(resp, ..) => Cow::Owned(format!("{:03}", *resp as u16)),
+            Command::Raw(cmd, ..) => Cow::Borrowed(cmd),
+            cmd => {
+                let line = String::from(cmd);
+                let cmd = line.split(' ').next().unwrap_or_default();
+                Cow::Owned(cmd.to_string())
+            }
+        }
+    }
+
+    fn args(&self) -> Vec<Cow<'_, str>> {
+        params(&self.command).0
+    }
+
+    fn data(&self) -> Option<Cow<'_, str>> {
+        params(&self.command).1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{irc::IrcMessage, messages::Commands, FromIrcMessage as _};
+
+    #[test]
+    fn twitch_messages() {
+        let inputs = &[
+            "@badge-info=;badges=broadcaster/1;color=#FF69B4;display-name=museun;emotes=25:0-4;system-msg=hello\\sworld :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :Kappa\r\n",
+            "@login=museun;target-msg-id=abc :tmi.twitch.tv CLEARMSG #museun :hello world\r\n",
+            ":tmi.twitch.tv CLEARCHAT #museun :shaken_bot\r\n",
+            ":tmi.twitch.tv CLEARCHAT #museun\r\n",
+            "@msg-id=raid :tmi.twitch.tv USERNOTICE #museun\r\n",
+            "@emote-only=0 :tmi.twitch.tv ROOMSTATE #museun\r\n",
+            ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n",
+            ":justinfan1234.tmi.twitch.tv 353 justinfan1234 = #museun :museun shaken_bot\r\n",
+            ":tmi.twitch.tv CAP * ACK :twitch.tv/membership\r\n",
+            ":museun!museun@museun.tmi.twitch.tv JOIN #museun\r\n",
+            ":museun!museun@museun.tmi.twitch.tv PART #museun\r\n",
+            "@msg-id=host_on :tmi.twitch.tv NOTICE #museun :Now hosting shaken_bot.\r\n",
+            "PING :tmi.twitch.tv\r\n",
+            ":tmi.twitch.tv RECONNECT\r\n",
+        ];
+
+        for input in inputs {
+            let msg: Message = input.parse().unwrap();
+            let bridged = IrcMessage::from_foreign(&msg).unwrap();
+            assert_eq!(bridged.get_raw(), *input);
+
+            let expected = crate::irc::parse(input).next().unwrap().unwrap();
+            assert_eq!(
+                Commands::from_irc(expected).unwrap(),
+                Commands::from_foreign(&msg).unwrap()
+            );
+        }
+    }
+}
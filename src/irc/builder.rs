@@ -0,0 +1,327 @@
+use super::{tags::escape_str, IrcMessage, MessageError};
+use crate::MaybeOwned;
+use std::borrow::Cow;
+
+/// An error returned by the [IrcMessageBuilder]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BuildError {
+    /// The command was empty or contained invalid characters
+    InvalidCommand,
+    /// A tag key was empty or contained invalid characters
+    InvalidTagKey(
+        /// The invalid key
+        String,
+    ),
+    /// The prefix was empty or contained invalid characters
+    InvalidPrefix,
+    /// An argument was empty or contained invalid characters
+    InvalidArg(
+        /// Position of the argument
+        usize,
+    ),
+    /// The data contained a line break
+    InvalidData,
+    /// The rendered line could not be parsed
+    Parse(MessageError),
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidCommand => f.write_str("invalid command"),
+            Self::InvalidTagKey(key) => write!(f, "invalid tag key: '{}'", key.escape_debug()),
+            Self::InvalidPrefix => f.write_str("invalid prefix"),
+            Self::InvalidArg(pos) => write!(f, "invalid argument at position {}", pos),
+            Self::InvalidData => f.write_str("data cannot contain line breaks"),
+            Self::Parse(err) => write!(f, "cannot parse the rendered line: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// A builder for raw IRC messages -- the inverse of the parser.
+///
+/// This is useful for synthesizing messages, e.g. for tests or for bridging
+/// events from other sources into the types of this crate.
+///
+/// Tag values are escaped, and rendered in the order they were first added.
+///
+/// ```rust
+/// # use twitchchat::{FromIrcMessage as _, irc::IrcMessageBuilder, messages::Privmsg};
+/// let msg = IrcMessageBuilder::new("PRIVMSG")
+///     .tag("display-name", "museun")
+///     .tag("system-msg", "hello world; this is a test")
+///     .user("museun")
+///     .arg("#museun")
+///     .data("Kappa 123")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(
+///     msg.get_raw(),
+///     "@display-name=museun;system-msg=hello\\sworld\\:\\sthis\\sis\\sa\\stest :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :Kappa 123\r\n"
+/// );
+///
+/// let pm = Privmsg::from_irc(msg).unwrap();
+/// assert_eq!(pm.name(), "museun");
+/// assert_eq!(pm.data(), "Kappa 123");
+/// assert_eq!(&*pm.tags().get_unescaped("system-msg").unwrap(), "hello world; this is a test");
+/// ```
+#[derive(Debug, Clone)]
+pub struct IrcMessageBuilder<'a> {
+    tags: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+    prefix: Option<Cow<'a, str>>,
+    command: Cow<'a, str>,
+    args: Vec<Cow<'a, str>>,
+    data: Option<Cow<'a, str>>,
+}
+
+impl<'a> IrcMessageBuilder<'a> {
+    /// Create a new builder for this `command`
+    pub fn new(command: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            tags: vec![],
+            prefix: None,
+            command: command.into(),
+            args: vec![],
+            data: None,
+        }
+    }
+
+    /// Add this tag. The `value` will be escaped.
+    ///
+    /// `key` will replace any previous keys, keeping its original position
+    pub fn tag(mut self, key: impl Into<Cow<'a, str>>, value: impl Into<Cow<'a, str>>) -> Self {
+        let (key, value) = (key.into(), value.into());
+        match self.tags.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.tags.push((key, value)),
+        }
+        self
+    }
+
+    /// Add all of these tags, e.g. from a map. The values will be escaped.
+    pub fn tags<I, K, V>(self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<Cow<'a, str>>,
+        V: Into<Cow<'a, str>>,
+    {
+        tags.into_iter().fold(self, |this, (k, v)| this.tag(k, v))
+    }
+
+    /// Set the raw prefix, e.g. `tmi.twitch.tv` or `museun!museun@museun.tmi.twitch.tv`
+    pub fn prefix(mut self, prefix: impl Into<Cow<'a, str>>) -> Self {
+        self.prefix.replace(prefix.into());
+        self
+    }
+
+    /// Set the prefix to the one Twitch uses for the user `name`
+    pub fn user(self, name: &str) -> Self {
+        self.prefix(format!("{name}!{name}@{name}.tmi.twitch.tv", name = name))
+    }
+
+    /// Set the prefix to the one Twitch uses for the server
+    pub fn server(self) -> Self {
+        self.prefix("tmi.twitch.tv")
+    }
+
+    /// Add an argument, e.g. the channel
+    pub fn arg(mut self, arg: impl Into<Cow<'a, str>>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Set the trailing data
+    pub fn data(mut self, data: impl Into<Cow<'a, str>>) -> Self {
+        self.data.replace(data.into());
+        self
+    }
+
+    /// Render the raw line, including the trailing `\r\n`
+    ///
+    /// # Errors
+    /// If any part contains characters that would change how the line is parsed
+    pub fn to_raw(&self) -> Result<String, BuildError> {
+        fn is_word(s: &str) -> bool {
+            !s.is_empty() && !s.contains(&[' ', '\r', '\n', '\0'][..])
+        }
+
+        let mut buf = String::new();
+
+        for (i, (key, value)) in self.tags.iter().enumerate() {
+            if !is_word(key) || key.contains(&['=', ';'][..]) {
+                return Err(BuildError::InvalidTagKey(key.to_string()));
+            }
+            buf.push(if i == 0 { '@' } else { ';' });
+            buf.push_str(key);
+            buf.push('=');
+            buf.push_str(&escape_str(value));
+        }
+        if !self.tags.is_empty() {
+            buf.push(' ');
+        }
+
+        if let Some(prefix) = &self.prefix {
+            if !is_word(prefix) {
+                return Err(BuildError::InvalidPrefix);
+            }
+            buf.push(':');
+            buf.push_str(prefix);
+            buf.push(' ');
+        }
+
+        if !is_word(&self.command) || self.command.starts_with(&[':', '@'][..]) {
+            return Err(BuildError::InvalidCommand);
+        }
+        buf.push_str(&self.command);
+
+        for (i, arg) in self.args.iter().enumerate() {
+            if !is_word(arg) || arg.starts_with(':') {
+                return Err(BuildError::InvalidArg(i));
+            }
+            buf.push(' ');
+            buf.push_str(arg);
+        }
+
+        if let Some(data) = &self.data {
+            if data.contains(&['\r', '\n', '\0'][..]) {
+                return Err(BuildError::InvalidData);
+            }
+            buf.push_str(" :");
+            buf.push_str(data);
+        }
+
+        buf.push_str("\r\n");
+        Ok(buf)
+    }
+
+    /// Build the message
+    ///
+    /// # Errors
+    /// If any part contains characters that would change how the line is parsed
+    pub fn build(&self) -> Result<IrcMessage<'static>, BuildError> {
+        let raw = self.to_raw()?;
+        IrcMessage::parse(MaybeOwned::Owned(raw.into())).map_err(BuildError::Parse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "usernotice")]
+    use crate::{messages::Commands, FromIrcMessage as _, Validator as _};
+    #[cfg(feature = "usernotice")]
+    use std::collections::BTreeMap;
+
+    #[test]
+    #[cfg(feature = "usernotice")]
+    fn round_trip() {
+        let mut tags = BTreeMap::new();
+        tags.insert("badges", "broadcaster/1");
+        tags.insert("empty", "");
+        tags.insert("system-msg", "a\\b;c d\re\nf");
+
+        let msg = IrcMessageBuilder::new("USERNOTICE")
+            .tags(tags.clone())
+            .server()
+            .arg("#museun")
+            .data(":with a leading colon")
+            .build()
+            .unwrap();
+
+        assert_eq!(msg.get_command(), "USERNOTICE");
+        assert_eq!(msg.get_prefix().unwrap(), "tmi.twitch.tv");
+        assert_eq!(msg.nth_arg(0).unwrap(), "#museun");
+        assert_eq!(msg.get_data().unwrap(), ":with a leading colon");
+
+        let parsed = msg.parse_tags();
+        let parsed = crate::irc::Tags::from_data_indices(&msg.raw, &parsed);
+        let parsed = parsed
+            .iter()
+            .map(|(k, _)| (k, parsed.get_unescaped(k).unwrap().to_string()))
+            .collect::<BTreeMap<_, _>>();
+        let expected = tags
+            .into_iter()
+            .map(|(k, v)| (k, v.to_string()))
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(parsed, expected);
+
+        // and back again
+        let raw = msg.get_raw().to_string();
+        let input = crate::irc::parse(&raw).next().unwrap().unwrap();
+        assert!(matches!(
+            Commands::from_irc(input).unwrap(),
+            Commands::UserNotice(..)
+        ));
+    }
+
+    #[test]
+    fn minimal() {
+        let msg = IrcMessageBuilder::new("PING").data("1234").build().unwrap();
+        assert_eq!(msg.get_raw(), "PING :1234\r\n");
+        assert!(msg.get_tags().is_none());
+        assert!(msg.get_prefix().is_none());
+
+        let msg = IrcMessageBuilder::new("RECONNECT")
+            .server()
+            .build()
+            .unwrap();
+        assert_eq!(msg.get_raw(), ":tmi.twitch.tv RECONNECT\r\n");
+    }
+
+    #[test]
+    fn replaces_tags() {
+        let raw = IrcMessageBuilder::new("USERSTATE")
+            .tag("color", "#FF0000")
+            .tag("mod", "1")
+            .tag("color", "#00FF00")
+            .arg("#museun")
+            .to_raw()
+            .unwrap();
+        assert_eq!(raw, "@color=#00FF00;mod=1 USERSTATE #museun\r\n");
+    }
+
+    #[test]
+    fn invalid() {
+        let build = |builder: IrcMessageBuilder<'_>| builder.build().unwrap_err();
+
+        assert!(matches!(
+            build(IrcMessageBuilder::new("")),
+            BuildError::InvalidCommand
+        ));
+        assert!(matches!(
+            build(IrcMessageBuilder::new("PRIVMSG #museun")),
+            BuildError::InvalidCommand
+        ));
+        assert!(matches!(
+            build(IrcMessageBuilder::new("PING").tag("a=b", "c")),
+            BuildError::InvalidTagKey(..)
+        ));
+        assert!(matches!(
+            build(IrcMessageBuilder::new("PING").tag("", "c")),
+            BuildError::InvalidTagKey(..)
+        ));
+        assert!(matches!(
+            build(IrcMessageBuilder::new("PING").prefix("tmi twitch")),
+            BuildError::InvalidPrefix
+        ));
+        assert!(matches!(
+            build(IrcMessageBuilder::new("JOIN").arg("#a").arg(":b")),
+            BuildError::InvalidArg(1)
+        ));
+        assert!(matches!(
+            build(IrcMessageBuilder::new("PRIVMSG").data("a\r\nPRIVMSG #b :c")),
+            BuildError::InvalidData
+        ));
+    }
+}
@@ -0,0 +1,138 @@
+use super::{IrcMessage, MessageError};
+use crate::MaybeOwned;
+use std::borrow::Cow;
+
+/// The encoding a line was read as, see [parse_bytes](super::parse_bytes)
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// The line was valid UTF-8
+    Utf8,
+    /// The line was Latin-1 (ISO 8859-1), where every byte is a char
+    Latin1,
+    /// The line was invalid UTF-8, the invalid bytes were replaced with `U+FFFD`
+    Utf8Lossy,
+}
+
+/// Decode a line of bytes as UTF-8, or as Latin-1 if `detect_latin1` is set and it looks like Latin-1.
+///
+/// A line looks like Latin-1 if it isn't valid UTF-8, and doesn't have any
+/// bytes in `0x80..=0x9F` (which are control characters in Latin-1, but text
+/// in e.g. Windows-1252). Everything else that isn't valid UTF-8 is decoded
+/// lossily. Only valid UTF-8 is borrowed.
+///
+/// ```
+/// # use twitchchat::irc::{decode_bytes, Encoding};
+/// assert_eq!(decode_bytes(b"caf\xc3\xa9", true), ("café".into(), Encoding::Utf8));
+/// assert_eq!(decode_bytes(b"caf\xe9", true), ("café".into(), Encoding::Latin1));
+/// assert_eq!(decode_bytes(b"caf\xe9", false), ("caf\u{fffd}".into(), Encoding::Utf8Lossy));
+/// ```
+pub fn decode_bytes(line: &[u8], detect_latin1: bool) -> (Cow<'_, str>, Encoding) {
+    if let Ok(line) = std::str::from_utf8(line) {
+        return (Cow::Borrowed(line), Encoding::Utf8);
+    }
+
+    if detect_latin1 && !line.iter().any(|byte| (0x80..=0x9f).contains(byte)) {
+        let line = line.iter().map(|&byte| char::from(byte)).collect();
+        return (Cow::Owned(line), Encoding::Latin1);
+    }
+
+    (String::from_utf8_lossy(line), Encoding::Utf8Lossy)
+}
+
+/// An iterator over a `&'a [u8]` that produces `IrcMessage<'a>`, see [parse_bytes](super::parse_bytes)
+#[derive(Copy, Clone, Debug)]
+pub struct BytesParserIter<'a> {
+    data: &'a [u8],
+    pos: usize,
+    detect_latin1: bool,
+    encoding: Option<Encoding>,
+}
+
+impl<'a> BytesParserIter<'a> {
+    pub(crate) const fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            detect_latin1: false,
+            encoding: None,
+        }
+    }
+
+    /// Read the lines that aren't valid UTF-8 as Latin-1, if they look like it.
+    ///
+    /// This is off by default, see [decode_bytes] for what looks like Latin-1.
+    pub const fn detect_latin1(mut self, detect: bool) -> Self {
+        self.detect_latin1 = detect;
+        self
+    }
+
+    /// The encoding of the line of the last message that was returned
+    pub const fn encoding(&self) -> Option<Encoding> {
+        self.encoding
+    }
+}
+
+impl<'a> Iterator for BytesParserIter<'a> {
+    type Item = Result<IrcMessage<'a>, MessageError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let rest = self.data.get(self.pos..).filter(|rest| !rest.is_empty())?;
+            let line = match rest.iter().position(|&byte| byte == b'\n') {
+                Some(end) => rest.get(..=end)?,
+                None => rest,
+            };
+            self.pos += line.len();
+
+            // logs often end with (or have) empty lines
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+
+            let (line, encoding) = decode_bytes(line, self.detect_latin1);
+            self.encoding.replace(encoding);
+            let msg = match line {
+                Cow::Borrowed(line) => IrcMessage::parse(MaybeOwned::Borrowed(line)),
+                Cow::Owned(line) => IrcMessage::parse(MaybeOwned::from(line)),
+            };
+            return Some(msg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines() {
+        let input = b"PING :a\r\n\r\n:tmi.twitch.tv NOTICE #museun :caf\xe9\n  \nPING :b";
+
+        let mut iter = crate::irc::parse_bytes(input);
+        let msg = iter.next().unwrap().unwrap();
+        assert_eq!(msg.get_raw(), "PING :a\r\n");
+        assert_eq!(iter.encoding(), Some(Encoding::Utf8));
+
+        let msg = iter.next().unwrap().unwrap();
+        assert_eq!(msg.get_data(), Some("caf\u{fffd}"));
+        assert_eq!(iter.encoding(), Some(Encoding::Utf8Lossy));
+
+        let msg = iter.next().unwrap().unwrap();
+        assert_eq!(msg.get_data(), Some("b"));
+        assert!(iter.next().is_none());
+
+        let mut iter = crate::irc::parse_bytes(input).detect_latin1(true);
+        let msg = iter.nth(1).unwrap().unwrap();
+        assert_eq!(msg.get_data(), Some("café"));
+        assert_eq!(iter.encoding(), Some(Encoding::Latin1));
+    }
+
+    #[test]
+    fn windows_1252_is_not_latin1() {
+        // a right single quote, in windows-1252
+        let (line, encoding) = decode_bytes(b"don\x92t", true);
+        assert_eq!(line, "don\u{fffd}t");
+        assert_eq!(encoding, Encoding::Utf8Lossy);
+    }
+}
@@ -59,6 +59,37 @@ pub enum MessageError {
     /// An empty message was provided
     EmptyMessage,
 
+    /// The message is longer than an index can point to
+    MessageTooLong {
+        /// The length of the message
+        len: usize,
+    },
+
+    /// The tag section is longer than the [Limits](super::Limits) allow
+    TagSectionTooLong {
+        /// The length of the tag section
+        len: usize,
+        /// The maximum length
+        max: usize,
+    },
+
+    /// The line (without its tag section) is longer than the [Limits](super::Limits) allow
+    LineTooLong {
+        /// The length of the line
+        len: usize,
+        /// The maximum length
+        max: usize,
+    },
+
+    /// An index into the message is out of bounds, or doesn't start and end on
+    /// character boundaries
+    InvalidIndex {
+        /// The start of the index
+        start: usize,
+        /// The end of the index
+        end: usize,
+    },
+
     /// A custom error message
     Custom {
         /// The inner error
@@ -81,6 +112,18 @@ impl std::fmt::Display for MessageError {
             Self::MissingTagValue(index) => write!(f, "missing tag value at pair index: {}", index),
             Self::IncompleteMessage { pos } => write!(f, "incomplete message starting at: {}", pos),
             Self::EmptyMessage => write!(f, "no message could be parsed"),
+            Self::MessageTooLong { len } => write!(f, "message is too long: {} bytes", len),
+            Self::TagSectionTooLong { len, max } => {
+                write!(f, "tag section is too long: {} bytes (max {})", len, max)
+            }
+            Self::LineTooLong { len, max } => {
+                write!(f, "line is too long: {} bytes (max {})", len, max)
+            }
+            Self::InvalidIndex { start, end } => write!(
+                f,
+                "index {}..{} is out of bounds, or not on a character boundary",
+                start, end
+            ),
             Self::Custom { error } => write!(f, "custom error: {}", error),
         }
     }
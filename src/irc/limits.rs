@@ -0,0 +1,125 @@
+use super::{IrcMessage, MessageError};
+
+/// Size limits of a message, checked with [Limits::check]
+///
+/// Twitch allows a tag section of up to 8191 bytes (the IRCv3 limit), while the
+/// rest of the line is limited to 512 bytes. Proxies and bridges may relax
+/// these, so they can be changed.
+///
+/// ```
+/// # use twitchchat::{irc::Limits, MessageError};
+/// let input = format!(":museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :{}\r\n", "a".repeat(600));
+/// let msg = twitchchat::irc::parse(&input).next().unwrap().unwrap();
+///
+/// let err = Limits::TWITCH.check(&msg).unwrap_err();
+/// assert!(matches!(err, MessageError::LineTooLong { max: 512, .. }));
+///
+/// let limits = Limits { max_line: 1024, ..Limits::TWITCH };
+/// assert!(limits.check(&msg).is_ok());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Limits {
+    /// The maximum length of the line without its tag section, including the trailing `\r\n`
+    pub max_line: usize,
+    /// The maximum length of the tag section, including the leading `@` and the trailing space
+    pub max_tags: usize,
+}
+
+impl Limits {
+    /// The limits Twitch uses
+    pub const TWITCH: Self = Self {
+        max_line: 512,
+        max_tags: 8191,
+    };
+
+    /// No limits at all
+    pub const NONE: Self = Self {
+        max_line: usize::MAX,
+        max_tags: usize::MAX,
+    };
+
+    /// Check the lengths of this message against these limits
+    pub fn check(&self, msg: &IrcMessage<'_>) -> Result<(), MessageError> {
+        let (tags, line) = lengths(msg);
+
+        if tags > self.max_tags {
+            return Err(MessageError::TagSectionTooLong {
+                len: tags,
+                max: self.max_tags,
+            });
+        }
+
+        if line > self.max_line {
+            return Err(MessageError::LineTooLong {
+                len: line,
+                max: self.max_line,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::TWITCH
+    }
+}
+
+// the length of the tag section and the rest of the line, as it was sent
+fn lengths(msg: &IrcMessage<'_>) -> (usize, usize) {
+    let raw = msg.get_raw().trim_end_matches("\r\n");
+    let (start, tags) = match msg.tags {
+        // the index covers the '@' and the tags, but not the space after them
+        Some(index) => (index.start as usize, index.as_range().len() + 1),
+        None => (0, 0),
+    };
+    let line = raw.len().saturating_sub(start + tags) + "\r\n".len();
+    (tags, line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(input: &str, limits: &Limits) -> Result<(), MessageError> {
+        let msg = crate::irc::parse(input).next().unwrap().unwrap();
+        limits.check(&msg)
+    }
+
+    #[test]
+    fn lengths() {
+        let input = "@a=b :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n";
+        let msg = crate::irc::parse(input).next().unwrap().unwrap();
+        assert_eq!(
+            super::lengths(&msg),
+            ("@a=b ".len(), input.len() - "@a=b ".len())
+        );
+
+        let input = "PING :1234\r\n";
+        let msg = crate::irc::parse(input).next().unwrap().unwrap();
+        assert_eq!(super::lengths(&msg), (0, input.len()));
+    }
+
+    #[test]
+    fn limits() {
+        let tags = format!("@emotes={}", "a".repeat(8200));
+        let input = format!("{} :tmi.twitch.tv USERSTATE #museun\r\n", tags);
+        assert!(matches!(
+            check(&input, &Limits::TWITCH),
+            Err(MessageError::TagSectionTooLong { len, max: 8191 }) if len == tags.len() + 1
+        ));
+        assert!(check(&input, &Limits::NONE).is_ok());
+
+        let input = format!("PRIVMSG #museun :{}\r\n", "a".repeat(510));
+        assert!(matches!(
+            check(&input, &Limits::TWITCH),
+            Err(MessageError::LineTooLong { len, max: 512 }) if len == input.len()
+        ));
+
+        // exactly at the limit
+        let input = format!("PRIVMSG #museun :{}\r\n", "a".repeat(512 - 19));
+        assert_eq!(input.len(), 512);
+        assert!(check(&input, &Limits::TWITCH).is_ok());
+    }
+}
@@ -21,21 +21,23 @@ pub struct IrcMessage<'a> {
 
 impl<'a> IrcMessage<'a> {
     pub(crate) fn parse(input: MaybeOwned<'a>) -> Result<Self, MessageError> {
+        if input.len() > MaybeOwnedIndex::MAX {
+            return Err(MessageError::MessageTooLong { len: input.len() });
+        }
+
         // trim any \r\n off incase this was directly called
-        let data = if input.ends_with("\r\n") {
-            &input.as_ref()[..input.len() - 2]
-        } else {
-            input.as_ref()
-        };
+        let data = input.strip_suffix("\r\n").unwrap_or(input.as_ref());
 
-        let data = data.trim();
-        if data.is_empty() {
-            return Err(super::MessageError::EmptyMessage);
+        // the indices are into `raw`, so the leading whitespace is skipped rather than trimmed
+        let data = data.trim_end();
+        let start = data.len() - data.trim_start().len();
+        if start == data.len() {
+            return Err(MessageError::EmptyMessage);
         }
 
         let mut p = Parser {
             input: data,
-            pos: 0,
+            pos: start,
         };
 
         let this = Self {
@@ -56,27 +58,29 @@ impl<'a> IrcMessage<'a> {
 
     /// Get the raw tags
     pub fn get_tags(&self) -> Option<&str> {
-        self.tags.map(|index| &self.raw[index])
+        self.tags?.get(&self.raw)
     }
 
     /// Get the raw prefix
     pub fn get_prefix(&self) -> Option<&str> {
-        self.prefix.map(|index| &self.raw[index.as_index()])
+        self.prefix?.as_index().get(&self.raw)
     }
 
     /// Get the raw command
+    ///
+    /// This is empty if the command index is invalid, e.g. if it was changed by hand
     pub fn get_command(&self) -> &str {
-        &self.raw[self.command]
+        self.command.get(&self.raw).unwrap_or_default()
     }
 
     /// Get the raw args
     pub fn get_args(&self) -> Option<&str> {
-        self.args.map(|index| &self.raw[index])
+        self.args?.get(&self.raw)
     }
 
     /// Get the raw data
     pub fn get_data(&self) -> Option<&str> {
-        self.data.map(|index| &self.raw[index])
+        self.data?.get(&self.raw)
     }
 
     /// Consumes this type returning the raw `MaybeOwned<'a>`
@@ -86,36 +90,33 @@ impl<'a> IrcMessage<'a> {
 
     /// Get the raw 'nth' argument
     pub fn nth_arg(&self, nth: usize) -> Option<&str> {
-        self.args
-            .map(|index| &self.raw[index])?
-            .split_ascii_whitespace()
-            .nth(nth)
+        self.get_args()?.split_ascii_whitespace().nth(nth)
     }
 
     /// Get the index of the 'nth' argumnet
     pub fn nth_arg_index(&self, nth: usize) -> Option<MaybeOwnedIndex> {
         let index = self.args?;
-        let args = &self.raw[index];
+        let args = index.get(&self.raw)?;
+        let start = index.start as usize;
 
         let mut seen = 0;
-        let (mut head, mut tail) = (index.start, index.start);
+        let mut head = start;
 
-        for ch in args.chars() {
+        // the positions are in bytes, so multi-byte characters don't shift them
+        for (pos, ch) in args.char_indices() {
             if ch.is_ascii_whitespace() {
                 if seen == nth {
-                    return Some(MaybeOwnedIndex::raw(head as _, tail as _));
+                    return Some(MaybeOwnedIndex::raw(head, start + pos));
                 }
 
                 // skip the space
-                head = tail + 1;
+                head = start + pos + 1;
                 seen += 1;
             }
-
-            tail += 1;
         }
 
         if seen == nth {
-            return Some(MaybeOwnedIndex::raw(head as _, tail as _));
+            return Some(MaybeOwnedIndex::raw(head, start + args.len()));
         }
 
         None
@@ -131,6 +132,12 @@ impl<'a> IrcMessage<'a> {
     ///
     /// This is sent by Twitch with your user information.
     pub const READY: &'static str = "376";
+    /// A chunk of the users on a channel -- `353`.
+    ///
+    /// This is sent after joining a channel, with the `MEMBERSHIP` capability.
+    pub const NAMES_REPLY: &'static str = "353";
+    /// The end of the users on a channel -- `366`.
+    pub const END_OF_NAMES: &'static str = "366";
     /// A capability response -- `CAP`.
     ///
     /// This is sent to acknowledge whether the capability requested is valid and applied to your connections.
@@ -244,6 +251,7 @@ impl<'de, 'a> ::serde::Deserialize<'de> for IrcMessage<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Validator as _;
 
     #[test]
     #[cfg(feature = "serde")]
@@ -261,4 +269,45 @@ mod tests {
             assert!(matches!(err, MessageError::EmptyMessage))
         }
     }
+
+    #[test]
+    fn parse_multibyte_and_leading_spaces() {
+        let input = "  :tmi.twitch.tv NOTICE #m\u{e9}\u{e9} \u{1f600}!x :hello\r\n";
+        let msg = IrcMessage::parse(MaybeOwned::Borrowed(input)).unwrap();
+        assert_eq!(msg.get_prefix(), Some("tmi.twitch.tv"));
+        assert_eq!(msg.get_command(), "NOTICE");
+        assert_eq!(msg.nth_arg(0), Some("#m\u{e9}\u{e9}"));
+        assert_eq!(msg.get_data(), Some("hello"));
+
+        for nth in 0..2 {
+            let index = msg.nth_arg_index(nth).unwrap();
+            assert_eq!(index.get(&msg.raw), msg.nth_arg(nth));
+        }
+
+        // indices changed by hand are checked
+        let mut msg = msg;
+        msg.data.replace(MaybeOwnedIndex::raw(29, 31));
+        assert!(matches!(
+            msg.expect_data_index(),
+            Err(MessageError::InvalidIndex { start: 29, end: 31 })
+        ));
+        assert_eq!(msg.get_data(), None);
+    }
+
+    #[test]
+    fn parse_past_u16_indices() {
+        // a tags section this large would wrap a 16-bit index
+        let value = "a".repeat(u16::MAX as usize + 10);
+        let input = format!(
+            "@key={} :test!test@test PRIVMSG #museun :this is a test\r\n",
+            value
+        );
+
+        let msg = IrcMessage::parse(MaybeOwned::Borrowed(&input)).unwrap();
+        assert_eq!(msg.get_tags().unwrap().len(), "@key=".len() + value.len());
+        assert_eq!(msg.get_prefix(), Some("test"));
+        assert_eq!(msg.get_command(), "PRIVMSG");
+        assert_eq!(msg.nth_arg(0), Some("#museun"));
+        assert_eq!(msg.get_data(), Some("this is a test"));
+    }
 }
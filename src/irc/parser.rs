@@ -28,7 +28,8 @@ impl<'a> Parser<'a> {
         if input.starts_with(':') {
             if let Some(pos) = input.find(' ') {
                 self.pos += 1;
-                let prefix = match input.find('!') {
+                // a `!` after the prefix belongs to the rest of the message
+                let prefix = match input.get(..pos).and_then(|prefix| prefix.find('!')) {
                     Some(bang) => PrefixIndex::User {
                         nick: self.mark_index(bang - 1, pos),
                     },
@@ -44,17 +45,17 @@ impl<'a> Parser<'a> {
     }
 
     pub(super) fn command(&mut self) -> MaybeOwnedIndex {
-        let input = &self.input[self.pos..];
+        let input = self.input.get(self.pos..).unwrap_or_default();
         let pos = input.find(' ').unwrap_or_else(|| input.len());
         self.mark_index(pos, pos + 1)
     }
 
     pub(super) fn args(&mut self) -> Option<MaybeOwnedIndex> {
-        if self.pos > self.input.len() || self.input[self.pos..].starts_with(':') {
+        let input = self.input.get(self.pos..)?;
+        if input.starts_with(':') {
             return None;
         }
 
-        let input = self.input.get(self.pos..)?;
         let pos = input.find(" :").unwrap_or_else(|| input.len());
         Some(self.mark_index(pos, pos))
     }
@@ -0,0 +1,257 @@
+use super::{tags::escape_str, BuildError, IrcMessage};
+use crate::MaybeOwned;
+use std::borrow::Cow;
+
+/// Rewrites the tags (and the data) of a raw message, e.g. to strip a tag before forwarding it.
+///
+/// The rest of the line is kept as it was, and the result is parsed again so
+/// its indices match the new line.
+///
+/// Tags keep their original position and escaping, added tags are appended in
+/// the order they were set and their values are escaped.
+///
+/// ```rust
+/// # use twitchchat::{FromIrcMessage as _, irc::MessageRewriter, messages::Privmsg};
+/// let input = "@badges=;color=#FF69B4;flags=0-4:P.3;id=1234 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello world\r\n";
+/// let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+/// let pm = Privmsg::from_irc(msg).unwrap();
+///
+/// let msg = MessageRewriter::new(pm.raw())
+///     .remove_tag("flags")
+///     .set_tag("color", "#00FF00")
+///     .set_tag("bridged-from", "some other chat")
+///     .rewrite()
+///     .unwrap();
+///
+/// assert_eq!(
+///     msg.get_raw(),
+///     "@badges=;color=#00FF00;id=1234;bridged-from=some\\sother\\schat :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello world\r\n"
+/// );
+///
+/// let pm = Privmsg::from_irc(msg).unwrap();
+/// assert!(pm.tags().get("flags").is_none());
+/// assert_eq!(pm.data(), "hello world");
+/// ```
+#[derive(Debug, Clone)]
+pub struct MessageRewriter<'a> {
+    raw: &'a str,
+    edits: Vec<(Cow<'a, str>, Option<Cow<'a, str>>)>,
+    data: Option<Cow<'a, str>>,
+}
+
+impl<'a> MessageRewriter<'a> {
+    /// Create a rewriter for this raw line, e.g. from `IrcMessage::get_raw` or the `raw()` of a message
+    pub fn new(raw: &'a str) -> Self {
+        Self {
+            raw,
+            edits: vec![],
+            data: None,
+        }
+    }
+
+    /// Replace the data (the trailing parameter) of the line, adding it if there is none
+    pub fn set_data(mut self, data: impl Into<Cow<'a, str>>) -> Self {
+        self.data.replace(data.into());
+        self
+    }
+
+    /// Set this tag, replacing its value if it exists. The `value` will be escaped.
+    pub fn set_tag(self, key: impl Into<Cow<'a, str>>, value: impl Into<Cow<'a, str>>) -> Self {
+        self.edit(key.into(), Some(value.into()))
+    }
+
+    /// Remove this tag, if it exists
+    pub fn remove_tag(self, key: impl Into<Cow<'a, str>>) -> Self {
+        self.edit(key.into(), None)
+    }
+
+    fn edit(mut self, key: Cow<'a, str>, value: Option<Cow<'a, str>>) -> Self {
+        match self.edits.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.edits.push((key, value)),
+        }
+        self
+    }
+
+    /// Render the rewritten raw line
+    ///
+    /// # Errors
+    /// If a tag key contains characters that would change how the line is parsed
+    pub fn to_raw(&self) -> Result<String, BuildError> {
+        for (key, _) in &self.edits {
+            if key.is_empty() || key.contains(&[' ', '\r', '\n', '\0', '=', ';'][..]) {
+                return Err(BuildError::InvalidTagKey(key.to_string()));
+            }
+        }
+        if let Some(data) = &self.data {
+            if data.contains(&['\r', '\n', '\0'][..]) {
+                return Err(BuildError::InvalidData);
+            }
+        }
+
+        let raw = self.raw.trim_start();
+        let (tags, rest) = match raw.strip_prefix('@') {
+            Some(tagged) => tagged.split_once(' ').unwrap_or((tagged, "")),
+            None => ("", raw),
+        };
+
+        let mut pairs = tags
+            .split(';')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+            .map(|(key, value)| (key, Cow::Borrowed(value)))
+            .collect::<Vec<_>>();
+
+        for (key, value) in &self.edits {
+            let pos = pairs.iter().position(|(k, _)| k == key);
+            match (pos, value) {
+                (Some(pos), Some(value)) => {
+                    if let Some(pair) = pairs.get_mut(pos) {
+                        pair.1 = escape_str(value)
+                    }
+                }
+                (Some(pos), None) => {
+                    pairs.remove(pos);
+                }
+                (None, Some(value)) => pairs.push((key, escape_str(value))),
+                (None, None) => {}
+            }
+        }
+
+        let mut buf = String::with_capacity(self.raw.len());
+        for (i, (key, value)) in pairs.iter().enumerate() {
+            buf.push(if i == 0 { '@' } else { ';' });
+            buf.push_str(key);
+            buf.push('=');
+            buf.push_str(value);
+        }
+        if !pairs.is_empty() {
+            buf.push(' ');
+        }
+        let rest = rest.trim_start_matches(' ');
+        match &self.data {
+            Some(data) => {
+                buf.push_str(without_data(rest));
+                buf.push_str(" :");
+                buf.push_str(data);
+                buf.push_str("\r\n");
+            }
+            None => buf.push_str(rest),
+        }
+        Ok(buf)
+    }
+
+    /// Rewrite the line, and parse it into a new message
+    ///
+    /// # Errors
+    /// If a tag key contains characters that would change how the line is parsed,
+    /// or if the line cannot be parsed
+    pub fn rewrite(&self) -> Result<IrcMessage<'static>, BuildError> {
+        let raw = self.to_raw()?;
+        IrcMessage::parse(MaybeOwned::Owned(raw.into())).map_err(BuildError::Parse)
+    }
+}
+
+// the line without its trailing parameter or line ending
+fn without_data(line: &str) -> &str {
+    let line = line.trim_end_matches(&['\r', '\n'][..]);
+    // the prefix can't have spaces, but it starts with a ':'
+    let start = match line.strip_prefix(':') {
+        Some(..) => line.find(' ').unwrap_or(line.len()),
+        None => 0,
+    };
+    let end = line
+        .get(start..)
+        .and_then(|rest| rest.find(" :"))
+        .map(|pos| start + pos);
+    end.and_then(|end| line.get(..end)).unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "usernotice")]
+    use crate::{messages::Commands, FromIrcMessage as _};
+
+    #[test]
+    #[cfg(feature = "usernotice")]
+    fn rewrite() {
+        let input = "@badges=;flags=;system-msg=a\\sb :tmi.twitch.tv USERNOTICE #museun :hi\r\n";
+        let msg = MessageRewriter::new(input)
+            .set_tag("badges", "broadcaster/1")
+            .remove_tag("flags")
+            .remove_tag("missing")
+            .rewrite()
+            .unwrap();
+        assert_eq!(
+            msg.get_raw(),
+            "@badges=broadcaster/1;system-msg=a\\sb :tmi.twitch.tv USERNOTICE #museun :hi\r\n"
+        );
+        assert!(matches!(
+            Commands::from_irc(msg).unwrap(),
+            Commands::UserNotice(..)
+        ));
+    }
+
+    #[test]
+    fn untagged() {
+        let input = ":tmi.twitch.tv RECONNECT\r\n";
+        assert_eq!(MessageRewriter::new(input).to_raw().unwrap(), input);
+
+        let msg = MessageRewriter::new(input)
+            .set_tag("note", "a;b")
+            .rewrite()
+            .unwrap();
+        assert_eq!(msg.get_raw(), "@note=a\\:b :tmi.twitch.tv RECONNECT\r\n");
+        assert_eq!(msg.get_prefix().unwrap(), "tmi.twitch.tv");
+        assert_eq!(msg.get_command(), "RECONNECT");
+    }
+
+    #[test]
+    fn remove_all_tags() {
+        let input = "@flags= PING :1234\r\n";
+        let msg = MessageRewriter::new(input)
+            .set_tag("flags", "x")
+            .remove_tag("flags")
+            .rewrite()
+            .unwrap();
+        assert_eq!(msg.get_raw(), "PING :1234\r\n");
+        assert!(msg.get_tags().is_none());
+        assert_eq!(msg.get_data().unwrap(), "1234");
+    }
+
+    #[test]
+    fn invalid_key() {
+        let err = MessageRewriter::new("PING :1234\r\n")
+            .set_tag("a b", "c")
+            .rewrite()
+            .unwrap_err();
+        assert!(matches!(err, BuildError::InvalidTagKey(key) if key == "a b"));
+    }
+
+    #[test]
+    fn data() {
+        let input = "@id=1 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hallo welt\r\n";
+        let msg = MessageRewriter::new(input)
+            .set_data("hello world")
+            .rewrite()
+            .unwrap();
+        assert_eq!(
+            msg.get_raw(),
+            "@id=1 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello world\r\n"
+        );
+        assert_eq!(msg.get_data().unwrap(), "hello world");
+
+        let msg = MessageRewriter::new(":tmi.twitch.tv RECONNECT\r\n")
+            .set_data("soon")
+            .rewrite()
+            .unwrap();
+        assert_eq!(msg.get_raw(), ":tmi.twitch.tv RECONNECT :soon\r\n");
+
+        let err = MessageRewriter::new(input)
+            .set_data("a\r\nQUIT")
+            .rewrite()
+            .unwrap_err();
+        assert!(matches!(err, BuildError::InvalidData));
+    }
+}
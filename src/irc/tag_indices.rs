@@ -24,11 +24,12 @@ impl TagIndices {
     ///
     /// The fragment should be in the form of `'@k1=v2;k2=v2'`    
     pub fn build_indices(input: &str) -> Result<Self, MessageError> {
-        if !input.starts_with('@') {
-            return Ok(Self::default());
-        }
+        let input = match input.strip_prefix('@') {
+            Some(input) => input,
+            None => return Ok(Self::default()),
+        };
 
-        input[1..]
+        input
             .split_terminator(';')
             .enumerate()
             .map(|(pos, input)| {
@@ -1,15 +1,10 @@
 use crate::maybe_owned::IntoOwned;
 use crate::{irc::TagIndices, MaybeOwned};
-use derive_more::Error;
-use derive_new::new;
-use getset::Getters;
 use std::fmt;
 use std::fmt::Debug;
-use std::{borrow::Borrow, str::FromStr}; // can't use derive_more here because it can't deal with the phantom data
+use std::{borrow::Borrow, str::FromStr};
 
 /// Error that occurs after we fail to parse a tag into its expected type.
-#[derive(Error, Getters, new)]
-#[getset(get = "pub")]
 pub struct TagParsingError<T>
 where
     T: FromStr,
@@ -24,6 +19,64 @@ where
     _phantom_data: std::marker::PhantomData<T>, // we need type T for Debug::fmt
 }
 
+impl<T> TagParsingError<T>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    pub(crate) fn new(
+        tag_name: String,
+        raw_value: String,
+        source_error: <T as FromStr>::Err,
+    ) -> Self {
+        Self {
+            tag_name,
+            raw_value,
+            source_error,
+            _phantom_data: std::marker::PhantomData,
+        }
+    }
+
+    /// Name of the tag.
+    pub fn tag_name(&self) -> &String {
+        &self.tag_name
+    }
+
+    /// Unparsed stored value.
+    pub fn raw_value(&self) -> &String {
+        &self.raw_value
+    }
+
+    /// Original error returned by the parser.
+    pub fn source_error(&self) -> &<T as FromStr>::Err {
+        &self.source_error
+    }
+}
+
+impl<T> fmt::Display for TagParsingError<T>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot parse tag '{}' with value '{}' as '{}': {:?}",
+            self.tag_name,
+            self.raw_value,
+            std::any::type_name::<T>(),
+            self.source_error
+        )
+    }
+}
+
+impl<T> std::error::Error for TagParsingError<T>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+}
+
 impl<T> Debug for TagParsingError<T>
 where
     T: FromStr,
@@ -51,7 +104,7 @@ pub struct Tags<'a> {
 }
 
 impl<'a> Debug for Tags<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_map().entries(self.iter()).finish()
     }
 }
@@ -99,6 +152,39 @@ impl<'a> Tags<'a> {
         self.indices.get(key.borrow())
     }
 
+    /** Tries to get this `key`, treating an empty value like a missing tag
+
+    Twitch sends some tags without a value (e.g. `color=` when the user never
+    picked a color), which [Tags::get] returns as `Some("")`. Use this when an
+    empty value means "not set", and [Tags::get] when the difference matters.
+
+    # NOTE: This does not unescape the tag value.
+
+    ```rust
+    # use twitchchat::irc::{TagIndices, Tags};
+    # use twitchchat::maybe_owned::MaybeOwned;
+    let input: MaybeOwned<'_> = "@color=;user-type=;display-name=museun".into();
+    let indices = TagIndices::build_indices(&*input).unwrap();
+    let tags = Tags::from_data_indices(&input, &indices);
+
+    assert_eq!(tags.get_non_empty("display-name"), Some("museun"));
+
+    // present, but empty
+    assert_eq!(tags.get("color"), Some(""));
+    assert_eq!(tags.get_non_empty("color"), None);
+
+    // missing
+    assert_eq!(tags.get("badges"), None);
+    assert_eq!(tags.get_non_empty("badges"), None);
+    ```
+    */
+    pub fn get_non_empty<K>(&self, key: &K) -> Option<&'a str>
+    where
+        K: ?Sized + Borrow<str>,
+    {
+        self.get(key).filter(|value| !value.is_empty())
+    }
+
     /**
      * Tries to get the tag as a parsable [std::str::FromStr] type.
 
@@ -188,12 +274,40 @@ impl<'a> Tags<'a> {
     }
 
     /// Get an iterator over all of the `key, value` pairs of tags
+    ///
+    /// The pairs are yielded in the order they appeared on the wire.
     pub fn iter(&self) -> TagsIter<'_> {
         TagsIter {
             inner: self,
             pos: 0,
         }
     }
+
+    /** Get all of the `key, value` pairs of tags, sorted by key
+
+    This is useful when the output needs to be independent of the order Twitch sent the tags in,
+    e.g. for snapshot tests or exports that get diffed.
+
+    ```rust
+    # use twitchchat::irc::{TagIndices, Tags};
+    # use twitchchat::maybe_owned::MaybeOwned;
+    let input: MaybeOwned<'_> = "@foo=1;bar=2;baz=3".into();
+    let indices = TagIndices::build_indices(&*input).unwrap();
+    let tags = Tags::from_data_indices(&input, &indices);
+
+    assert_eq!(tags.sorted(), vec![("bar", "2"), ("baz", "3"), ("foo", "1")]);
+    ```
+    */
+    pub fn sorted(&self) -> Vec<(&'a str, &'a str)> {
+        let mut pairs = self
+            .indices
+            .map
+            .iter()
+            .map(|(k, v)| (&**k, &**v))
+            .collect::<Vec<_>>();
+        pairs.sort_unstable();
+        pairs
+    }
 }
 
 impl<'a> IntoIterator for &'a Tags<'a> {
@@ -209,6 +323,8 @@ impl<'a> IntoIterator for &'a Tags<'a> {
 }
 
 /// An iterator over the [Tags]
+///
+/// This yields the tags in their original wire order.
 #[derive(Clone)]
 pub struct TagsIter<'a> {
     inner: &'a Tags<'a>,
@@ -236,6 +352,8 @@ impl<'a> Iterator for TagsIter<'a> {
     }
 }
 
+/// This serializes the tags in their original wire order. Use [Tags::sorted] if a
+/// key-ordered export is needed.
 #[cfg(feature = "serde")]
 impl<'a> ::serde::Serialize for Tags<'a> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -351,6 +469,20 @@ mod tests {
         assert_eq!(unescape_str(&*escape_str(s)), s);
     }
 
+    #[test]
+    fn non_empty() {
+        let data = MaybeOwned::Borrowed("@user-type=;mod=0;badges=");
+        let indices = TagIndices::build_indices(&data).unwrap();
+        let tags = Tags::from_data_indices(&data, &indices);
+
+        assert_eq!(tags.get("user-type"), Some(""));
+        assert_eq!(tags.get_non_empty("user-type"), None);
+        assert_eq!(tags.get_non_empty("mod"), Some("0"));
+        assert_eq!(tags.get_non_empty("badges"), None);
+        assert_eq!(tags.get("color"), None);
+        assert_eq!(tags.get_non_empty("color"), None);
+    }
+
     #[test]
     fn escaped_tag() {
         let s = escape_str(r"@hello;world=abc\ndef");
@@ -469,6 +601,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn tags_iter_wire_order() {
+        let input = "@user-type=;badges=broadcaster/1;color=#FF69B4;badge-info=;mod=0";
+        let data = MaybeOwned::Borrowed(input);
+        let indices = TagIndices::build_indices(input).unwrap();
+        let tags = Tags::from_data_indices(&data, &indices);
+
+        let keys = tags.iter().map(|(k, _)| k).collect::<Vec<_>>();
+        assert_eq!(keys, vec!["user-type", "badges", "color", "badge-info", "mod"]);
+
+        let keys = tags.sorted().into_iter().map(|(k, _)| k).collect::<Vec<_>>();
+        assert_eq!(keys, vec!["badge-info", "badges", "color", "mod", "user-type"]);
+    }
+
     #[test]
     fn parse() {
         let input = "@badges=broadcaster/1,subscriber/6;\
@@ -31,16 +31,27 @@ This crate provides a way to interface with [Twitch](https://dev.twitch.tv/docs/
 Along with the messages as Rust types, it provides methods for sending messages.
 ---
 
-By default, this crate depends on zero external crates -- but it makes it rather limited in scope.
+By default, this crate only depends on `twitchchat-macros` (the derives used by the twitch types)
+-- but it makes it rather limited in scope.
+Everything that needs a runtime, serialization or a storage backend is behind a feature.
 
 This allows parsing, and decoding/encoding to standard trait types (`std::io::{Read, Write}`).
 
 To use the [AsyncRunner] (an async-event loop) and related helpers, you must able the `async` feature.
 
+The message families are default features, so they can be compiled out when
+only some of the messages are needed:
+* `membership`: the `NAMES` replies and the `Presence` tracker
+* `moderation`: `CLEARCHAT`, `CLEARMSG` and the `audit` log
+* `usernotice`: `USERNOTICE` and its event fields
+* `whispers`: incoming `WHISPER`s
+
+Without one of them, its messages are [Commands::Raw](messages::Commands::Raw).
+
 ***NOTE*** This is a breaking change from `0.12` which had the async stuff enabled by default.
 
 ```toml
-twitchchat = { version = "0.14", features = ["async"] }
+twitchchat = { version = "0.15", features = ["async"] }
 ```
 ---
 
@@ -52,14 +63,37 @@ For twitch types:
 For the 'irc' types underneath it all:
 * [irc]
 ---
+For the common traits and types in one import:
+* [prelude]
+---
 For an event loop:
 * [runner]
 ---
+For ready-made connections, with the `async` feature:
+* `presets`
+---
+For an audit log of moderation events, with the `serde` and `serde_json` features:
+* `audit`
+---
+For signing the messages relayed between services, with the `signing` feature:
+* `runner::signing`
+---
+For logs in the formats of ZNC, WeeChat or raw IRCv3:
+* [export]
+---
+For chat commands with typed arguments:
+* [extract]
+---
 For just decoding messages:
 * [decoder]
 ---
 For just encoding messages:
 * [encoder]
+---
+Parsing, decoding and the accessors of the messages don't panic on any input,
+malformed input is an error (or a missing value) instead. Those modules deny
+the clippy lints for panics, indexing and slicing, and `fuzz/regressions.txt`
+holds inputs that are replayed by the tests.
 
 */
 
@@ -73,6 +107,25 @@ macro_rules! cfg_async {
     };
 }
 
+// the modules that parse untrusted input, which mustn't panic on any of it
+macro_rules! panic_free {
+    ($($item:item)*) => {
+        $(
+            #[cfg_attr(
+                not(test),
+                deny(
+                    clippy::panic,
+                    clippy::unwrap_used,
+                    clippy::expect_used,
+                    clippy::indexing_slicing,
+                    clippy::string_slice
+                )
+            )]
+            $item
+        )*
+    };
+}
+
 /// The Twitch IRC address for non-TLS connections
 pub const TWITCH_IRC_ADDRESS: &str = "irc.chat.twitch.tv:6667";
 
@@ -96,7 +149,7 @@ pub(crate) const JUSTINFAN1234: &str = "justinfan1234";
 #[allow(unused_macros)]
 mod macros;
 
-pub mod decoder;
+panic_free! { pub mod decoder; }
 pub use decoder::{DecodeError, Decoder};
 cfg_async! { pub use decoder::AsyncDecoder; }
 
@@ -109,11 +162,11 @@ pub type BoxedFuture<T> = std::pin::Pin<Box<dyn std::future::Future<Output = T>
 
 cfg_async! {
     /// An AsyncWriter over an MpscWriter
-    pub type Writer = crate::writer::AsyncWriter<crate::writer::MpscWriter>;
+    pub type Writer = writer::AsyncWriter<writer::MpscWriter>;
 }
 
 cfg_async! { pub mod connector; }
-cfg_async! { pub mod writer; }
+pub mod writer;
 cfg_async! { pub mod channel; }
 
 pub mod runner;
@@ -123,28 +176,66 @@ cfg_async! { pub use runner::AsyncRunner; }
 pub mod rate_limit;
 
 pub mod commands;
-pub mod messages;
+panic_free! { pub mod messages; }
 
-pub mod irc;
+panic_free! { pub mod irc; }
 pub use irc::{IrcMessage, MessageError};
 
+pub mod metrics;
+
+pub mod storage;
+
+pub mod maintenance;
+
+#[cfg(all(feature = "serde", feature = "serde_json", feature = "moderation"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "serde", feature = "serde_json", feature = "moderation")))
+)]
+pub mod audit;
+
+pub mod export;
+
+panic_free! { pub mod extract; }
+
+pub mod prelude;
+
+#[cfg(feature = "recent-messages")]
+#[cfg_attr(docsrs, doc(cfg(feature = "recent-messages")))]
+pub mod recent_messages;
+
+#[cfg(feature = "helix")]
+#[cfg_attr(docsrs, doc(cfg(feature = "helix")))]
+pub mod helix;
+
+#[cfg(feature = "usernotice")]
+#[cfg_attr(docsrs, doc(cfg(feature = "usernotice")))]
+pub mod alert;
+
+cfg_async! { pub mod whisper; }
+
+cfg_async! { pub mod presets; }
+
+#[cfg(any(feature = "recent-messages", feature = "helix", feature = "webhook"))]
+mod http;
+
 /// Helpful testing utilities
 pub mod test;
 
 #[doc(inline)]
 pub use irc::{FromIrcMessage, IntoIrcMessage};
 
-pub mod twitch;
+panic_free! { pub mod twitch; }
 pub use twitch::UserConfig;
 
 mod encodable;
 pub use encodable::Encodable;
 
-pub mod maybe_owned;
+panic_free! { pub mod maybe_owned; }
 pub use maybe_owned::IntoOwned;
 use maybe_owned::{MaybeOwned, MaybeOwnedIndex};
 
-mod validator;
+panic_free! { mod validator; }
 pub use validator::Validator;
 
 mod ext;
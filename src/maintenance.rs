@@ -0,0 +1,310 @@
+/*!
+Periodic cleanup of the caches a bot keeps, so they don't grow forever.
+
+Caches implementing [Expire] (like the [Presence](crate::messages::Presence), the [ContextBuffer] and
+the [Interner]) are registered with a [Maintenance], each with its own
+interval. Sync users call [Maintenance::tick] from their loop, with the
+`async` feature [Maintenance::run] does it in a background task.
+
+```
+# use twitchchat::{maintenance::Maintenance, messages::ContextBuffer};
+# use std::{sync::{Arc, Mutex}, time::Duration};
+let context = Arc::new(Mutex::new(ContextBuffer::new(100, Duration::from_secs(60))));
+
+let maintenance = Maintenance::new();
+maintenance.register("context", Duration::from_secs(10), Arc::clone(&context));
+
+// e.g. in the main loop, this only prunes the caches that are due
+maintenance.tick();
+
+let stats = maintenance.stats();
+assert_eq!(stats[0].name, "context");
+assert_eq!(stats[0].runs, 1);
+assert_eq!(maintenance.evictions(), 0);
+```
+*/
+
+use crate::messages::{ContextBuffer, Interner};
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// A cache with entries that expire
+pub trait Expire {
+    /// Drop the entries that are expired at `now`, returning how many were dropped
+    fn expire(&mut self, now: Instant) -> usize;
+}
+
+impl<E> Expire for &mut E
+where
+    E: Expire + ?Sized,
+{
+    fn expire(&mut self, now: Instant) -> usize {
+        (**self).expire(now)
+    }
+}
+
+#[cfg(feature = "membership")]
+impl Expire for crate::messages::Presence {
+    fn expire(&mut self, now: Instant) -> usize {
+        self.prune(now)
+    }
+}
+
+impl Expire for ContextBuffer {
+    fn expire(&mut self, now: Instant) -> usize {
+        self.prune(now)
+    }
+}
+
+impl Expire for Interner {
+    fn expire(&mut self, _now: Instant) -> usize {
+        self.prune()
+    }
+}
+
+#[cfg(feature = "async")]
+impl Expire for crate::runner::NonceMap {
+    fn expire(&mut self, now: Instant) -> usize {
+        self.prune(now)
+    }
+}
+
+/// The evictions of a cache registered with a [Maintenance]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The name it was registered with
+    pub name: String,
+    /// How often it is pruned
+    pub interval: Duration,
+    /// How many times it was pruned
+    pub runs: u64,
+    /// How many entries were evicted in total
+    pub evictions: u64,
+    /// How many entries were evicted the last time it was pruned
+    pub last_evictions: usize,
+    /// When it was last pruned
+    pub last_run: Option<Instant>,
+}
+
+struct Task {
+    stats: CacheStats,
+    next: Instant,
+    expire: Box<dyn FnMut(Instant) -> usize + Send>,
+}
+
+/// Prunes the registered caches on their intervals.
+///
+/// This is a cheap handle, clones share the same caches and stats.
+#[derive(Clone, Default)]
+pub struct Maintenance {
+    tasks: Arc<Mutex<Vec<Task>>>,
+}
+
+impl std::fmt::Debug for Maintenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Maintenance")
+            .field("caches", &self.stats())
+            .finish()
+    }
+}
+
+impl Maintenance {
+    /// How long [Maintenance::run] sleeps at most, so new caches are picked up
+    pub const IDLE_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Create a new maintenance without any caches
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prune this cache every `interval`, starting with the next tick.
+    ///
+    /// A cache already registered with this name is replaced
+    pub fn register<C>(&self, name: &str, interval: Duration, cache: Arc<Mutex<C>>)
+    where
+        C: Expire + Send + 'static,
+    {
+        let task = Task {
+            stats: CacheStats {
+                name: name.to_string(),
+                interval,
+                runs: 0,
+                evictions: 0,
+                last_evictions: 0,
+                last_run: None,
+            },
+            next: Instant::now(),
+            expire: Box::new(move |now| {
+                cache
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .expire(now)
+            }),
+        };
+
+        let mut tasks = self.lock();
+        tasks.retain(|task| task.stats.name != name);
+        tasks.push(task);
+    }
+
+    /// Stop pruning the cache with this name, returning whether it was registered
+    pub fn unregister(&self, name: &str) -> bool {
+        let mut tasks = self.lock();
+        let len = tasks.len();
+        tasks.retain(|task| task.stats.name != name);
+        tasks.len() != len
+    }
+
+    /// Prune the caches that are due now
+    ///
+    /// This returns how many entries were evicted
+    pub fn tick(&self) -> usize {
+        self.tick_at(Instant::now())
+    }
+
+    /// Prune the caches that are due at `now`
+    ///
+    /// This returns how many entries were evicted
+    pub fn tick_at(&self, now: Instant) -> usize {
+        let mut evicted = 0;
+        for task in self.lock().iter_mut().filter(|task| task.next <= now) {
+            let n = (task.expire)(now);
+            evicted += n;
+
+            task.next = now + task.stats.interval;
+            task.stats.runs += 1;
+            task.stats.evictions += n as u64;
+            task.stats.last_evictions = n;
+            task.stats.last_run = Some(now);
+        }
+        evicted
+    }
+
+    /// When the next cache is due, if any are registered
+    pub fn next_due(&self) -> Option<Instant> {
+        self.lock().iter().map(|task| task.next).min()
+    }
+
+    /// The stats of the registered caches, in the order they were registered
+    pub fn stats(&self) -> Vec<CacheStats> {
+        self.lock().iter().map(|task| task.stats.clone()).collect()
+    }
+
+    /// How many entries were evicted from all of the registered caches
+    pub fn evictions(&self) -> u64 {
+        self.lock().iter().map(|task| task.stats.evictions).sum()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Task>> {
+        // a task is only updated after its cache was pruned, so a poisoned lock is still usable
+        self.tasks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+cfg_async! {
+impl Maintenance {
+    /// Prune the registered caches when they are due, forever.
+    ///
+    /// Spawn this on your executor and drop it to stop. Caches can still be
+    /// (un)registered on clones of this handle while it is running
+    pub async fn run(self) {
+        loop {
+            self.tick();
+            let now = Instant::now();
+            let wait = self
+                .next_due()
+                .map(|next| next.saturating_duration_since(now))
+                .unwrap_or(Self::IDLE_INTERVAL)
+                .min(Self::IDLE_INTERVAL);
+            futures_timer::Delay::new(wait).await
+        }
+    }
+}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Ages(Vec<Instant>);
+
+    impl Expire for Ages {
+        fn expire(&mut self, now: Instant) -> usize {
+            let len = self.0.len();
+            self.0.retain(|&at| at > now);
+            len - self.0.len()
+        }
+    }
+
+    #[test]
+    fn intervals() {
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+
+        let fast = Arc::new(Mutex::new(Ages(vec![secs(5), secs(15), secs(25)])));
+        let slow = Arc::new(Mutex::new(Ages(vec![secs(5)])));
+
+        let maintenance = Maintenance::new();
+        maintenance.register("fast", Duration::from_secs(10), Arc::clone(&fast));
+        maintenance.register("slow", Duration::from_secs(60), Arc::clone(&slow));
+
+        assert_eq!(maintenance.tick_at(secs(10)), 2);
+        assert_eq!(maintenance.next_due(), Some(secs(20)));
+
+        // not due yet
+        assert_eq!(maintenance.tick_at(secs(19)), 0);
+        assert_eq!(maintenance.tick_at(secs(20)), 1);
+        assert_eq!(fast.lock().unwrap().0, vec![secs(25)]);
+
+        let stats = maintenance.stats();
+        assert_eq!(stats[0].runs, 2);
+        assert_eq!(stats[0].evictions, 2);
+        assert_eq!(stats[0].last_evictions, 1);
+        assert_eq!(stats[0].last_run, Some(secs(20)));
+        assert_eq!(stats[1].runs, 1);
+        assert_eq!(stats[1].evictions, 1);
+        assert_eq!(maintenance.evictions(), 3);
+
+        assert!(maintenance.unregister("fast"));
+        assert!(!maintenance.unregister("fast"));
+        assert_eq!(maintenance.tick_at(secs(100)), 0);
+        assert_eq!(maintenance.stats().len(), 1);
+    }
+
+    #[test]
+    fn replace() {
+        let maintenance = Maintenance::new();
+        let ages = Arc::new(Mutex::new(Ages::default()));
+        maintenance.register("ages", Duration::from_secs(10), Arc::clone(&ages));
+        maintenance.register("ages", Duration::from_secs(20), ages);
+
+        let stats = maintenance.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].interval, Duration::from_secs(20));
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn run() {
+        let start = Instant::now();
+        let ages = Arc::new(Mutex::new(Ages(vec![start])));
+
+        let maintenance = Maintenance::new();
+        maintenance.register("ages", Duration::from_secs(60), Arc::clone(&ages));
+
+        use futures_lite::future::{block_on, FutureExt as _};
+        block_on(
+            maintenance
+                .clone()
+                .run()
+                .or(futures_timer::Delay::new(Duration::from_millis(50))),
+        );
+        assert!(ages.lock().unwrap().0.is_empty());
+        assert_eq!(maintenance.evictions(), 1);
+    }
+}
@@ -2,6 +2,9 @@ use super::{MaybeOwned, MaybeOwnedIndex};
 use crate::twitch::{Color, UserConfig};
 
 /// Converts a **borrowed** type into an owned type. e.g. `'a` to `'static`
+///
+/// For messages, the raw line is kept byte-for-byte, so every tag (including
+/// ones this crate doesn't know about) survives the conversion.
 pub trait IntoOwned<'a> {
     /// The output type
     type Output: 'static;
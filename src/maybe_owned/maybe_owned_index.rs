@@ -1,7 +1,8 @@
 use super::MaybeOwned;
+use crate::MessageError;
 use std::ops::{Index, Range};
 
-type IndexWidth = u16;
+type IndexWidth = u32;
 
 /// An index into a [MaybeOwned].
 #[derive(Copy, Clone, Default, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
@@ -13,6 +14,11 @@ pub struct MaybeOwnedIndex {
 }
 
 impl MaybeOwnedIndex {
+    /// The largest position an index can point to.
+    ///
+    /// Messages longer than this are rejected by the parser, so an index can never wrap around.
+    pub const MAX: usize = IndexWidth::MAX as usize;
+
     /// Create a new index from this start and end point
     pub const fn raw(start: usize, end: usize) -> Self {
         Self {
@@ -71,32 +77,61 @@ impl MaybeOwnedIndex {
     pub const fn as_range(self) -> Range<usize> {
         (self.start as usize)..(self.end as usize)
     }
+
+    /// Get the part of `input` this index points to.
+    ///
+    /// Unlike indexing, this is `None` rather than a panic if the index is out
+    /// of bounds, or doesn't start and end on character boundaries.
+    ///
+    /// ```
+    /// # use twitchchat::maybe_owned::MaybeOwnedIndex;
+    /// assert_eq!(MaybeOwnedIndex::raw(0, 2).get("héllo"), None);
+    /// assert_eq!(MaybeOwnedIndex::raw(0, 3).get("héllo"), Some("hé"));
+    /// assert_eq!(MaybeOwnedIndex::raw(4, 8).get("héllo"), None);
+    /// ```
+    pub fn get(self, input: &str) -> Option<&str> {
+        input.get(self.as_range())
+    }
+
+    /// Check that this index can be used on `input`, see [MaybeOwnedIndex::get]
+    pub fn check(self, input: &str) -> Result<Self, MessageError> {
+        match self.get(input) {
+            Some(..) => Ok(self),
+            None => Err(MessageError::InvalidIndex {
+                start: self.start as usize,
+                end: self.end as usize,
+            }),
+        }
+    }
 }
 
+// the indices of a message are checked when it is parsed, so these can't be
+// out of bounds. Should one be anyway, it is an empty string rather than a panic
+
 impl<'a> Index<&MaybeOwnedIndex> for MaybeOwned<'a> {
     type Output = str;
     fn index(&self, index: &MaybeOwnedIndex) -> &Self::Output {
-        &self.as_ref()[index.as_range()]
+        index.get(self).unwrap_or_default()
     }
 }
 
 impl<'a> Index<MaybeOwnedIndex> for MaybeOwned<'a> {
     type Output = str;
     fn index(&self, index: MaybeOwnedIndex) -> &Self::Output {
-        &self.as_ref()[index.as_range()]
+        index.get(self).unwrap_or_default()
     }
 }
 
 impl<'a> Index<&MaybeOwnedIndex> for str {
     type Output = Self;
     fn index(&self, index: &MaybeOwnedIndex) -> &Self::Output {
-        &self[index.as_range()]
+        index.get(self).unwrap_or_default()
     }
 }
 
 impl<'a> Index<MaybeOwnedIndex> for str {
     type Output = Self;
     fn index(&self, index: MaybeOwnedIndex) -> &Self::Output {
-        &self[index.as_range()]
+        index.get(self).unwrap_or_default()
     }
 }
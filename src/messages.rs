@@ -42,12 +42,48 @@ pub use ready::Ready;
 mod cap;
 pub use cap::{Cap, Capability};
 
+mod channel_info;
+pub use channel_info::{
+    ChannelInfo, ChannelInfoCache, ChannelInfoChange, ChannelRaid, ChannelSettings,
+};
+
+#[cfg(feature = "moderation")]
 mod clear_chat;
+#[cfg(feature = "moderation")]
+#[cfg_attr(docsrs, doc(cfg(feature = "moderation")))]
 pub use clear_chat::ClearChat;
 
+mod chat_event;
+pub use chat_event::{ChatEvent, ChatUser, ModAction, RoomSettings, Segment};
+
+#[cfg(feature = "moderation")]
 mod clear_msg;
+#[cfg(feature = "moderation")]
+#[cfg_attr(docsrs, doc(cfg(feature = "moderation")))]
 pub use clear_msg::ClearMsg;
 
+mod content_hash;
+pub use content_hash::content_hash;
+pub(crate) use content_hash::Fnv;
+
+mod context;
+pub use context::{ContextBuffer, ContextEntry};
+
+#[cfg(feature = "usernotice")]
+mod event_fields;
+#[cfg(feature = "usernotice")]
+#[cfg_attr(docsrs, doc(cfg(feature = "usernotice")))]
+pub use event_fields::{
+    BitsBadgeTierFields, EventFields, EventFieldsError, GiftPaidUpgradeFields, RaidFields,
+    RitualFields, SubFields, SubGiftFields, SubMysteryGiftFields, ViewerMilestoneFields,
+};
+
+#[cfg(feature = "usernotice")]
+mod sub_milestone;
+#[cfg(feature = "usernotice")]
+#[cfg_attr(docsrs, doc(cfg(feature = "usernotice")))]
+pub use sub_milestone::{SubMilestone, SubMilestoneEvent};
+
 mod global_user_state;
 pub use global_user_state::GlobalUserState;
 
@@ -57,8 +93,20 @@ pub use host_target::{HostTarget, HostTargetKind};
 mod join;
 pub use join::Join;
 
+mod interner;
+pub use interner::{Interner, InternerStats};
+
+#[cfg(feature = "membership")]
+mod names;
+#[cfg(feature = "membership")]
+#[cfg_attr(docsrs, doc(cfg(feature = "membership")))]
+pub use names::{EndOfNames, Names, NamesCollector, NamesReply};
+
 mod notice;
-pub use notice::{MessageId, Notice};
+pub use notice::{MessageId, MessageIdCategory, Notice};
+
+mod notifications;
+pub use notifications::{Notification, Notifications};
 
 mod part;
 pub use part::Part;
@@ -69,6 +117,15 @@ pub use ping::Ping;
 mod pong;
 pub use pong::Pong;
 
+mod parts;
+pub use parts::{MessagePart, MessageParts};
+
+#[cfg(feature = "membership")]
+mod presence;
+#[cfg(feature = "membership")]
+#[cfg_attr(docsrs, doc(cfg(feature = "membership")))]
+pub use presence::Presence;
+
 mod privmsg;
 pub use privmsg::Ctcp;
 pub use privmsg::Privmsg;
@@ -76,18 +133,43 @@ pub use privmsg::Privmsg;
 mod reconnect;
 pub use reconnect::Reconnect;
 
+mod redemptions;
+pub use redemptions::{Redemption, RedemptionQueue, RedemptionStatus};
+
+mod registry;
+pub use registry::{AnyMessage, MessageRegistry};
+
+mod room_ids;
+pub use room_ids::RoomIds;
+
 mod room_state;
 pub use room_state::{FollowersOnly, RoomState};
 
+mod third_party;
+pub use third_party::{EmoteProvider, ThirdPartyEmotes};
+
+mod text;
+#[cfg(feature = "unicode-normalization")]
+#[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+pub use text::nfc;
+pub use text::{fold_width, mask, normalize, truncate, MaskOptions, MaskStyle, Script, Truncated};
+
+#[cfg(feature = "usernotice")]
 mod user_notice;
-pub use user_notice::{NoticeType, SubPlan, UserNotice};
+#[cfg(feature = "usernotice")]
+#[cfg_attr(docsrs, doc(cfg(feature = "usernotice")))]
+pub use user_notice::{MsgParamReport, NoticeType, SubPlan, UserNotice};
 
 mod user_state;
 pub use user_state::UserState;
 
+#[cfg(feature = "whispers")]
 mod whisper;
+#[cfg(feature = "whispers")]
+#[cfg_attr(docsrs, doc(cfg(feature = "whispers")))]
 pub use whisper::Whisper;
 
 mod tags;
+pub use tags::HasTags;
 
 pub use crate::irc::IrcMessage;
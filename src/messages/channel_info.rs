@@ -0,0 +1,315 @@
+use super::{Commands, FollowersOnly, RoomState};
+#[cfg(feature = "usernotice")]
+use super::{NoticeType, RaidFields, UserNotice};
+use std::collections::HashMap;
+
+/// The chat settings of a channel, from its `ROOMSTATE` messages
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct ChannelSettings {
+    /// Whether only emotes can be sent
+    pub emote_only: bool,
+    /// The followers-only mode, if Twitch has sent it
+    pub followers_only: Option<FollowersOnly>,
+    /// Whether r9k (unique chat) mode is on
+    pub r9k: bool,
+    /// The slow mode delay in seconds, `0` when slow mode is off
+    pub slow: u64,
+    /// Whether only subscribers can chat
+    pub subs_only: bool,
+}
+
+/// The most recent raid of a channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelRaid {
+    /// The login of the raiding channel
+    pub login: String,
+    /// The display name of the raiding channel
+    pub display_name: String,
+    /// The number of viewers of the raid
+    pub viewer_count: u64,
+    /// When Twitch sent the raid, in milliseconds since the unix epoch
+    pub tmi_sent_ts: Option<u64>,
+}
+
+/// Everything that is known about a channel, see [ChannelInfoCache]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChannelInfo {
+    /// The room id of the channel
+    pub room_id: Option<u64>,
+    /// The chat settings of the channel
+    pub settings: ChannelSettings,
+    /// The most recent raid of the channel
+    pub last_raid: Option<ChannelRaid>,
+    /// The number of subscription notices seen on the channel, including gifts
+    pub subs_seen: u64,
+    /// The language of the broadcaster, from the `broadcaster-lang` tag
+    ///
+    /// Twitch no longer sends this tag, so this is usually `None`
+    pub language: Option<String>,
+}
+
+/// A part of a [ChannelInfo] that was changed by a message
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ChannelInfoChange {
+    /// The room id was learned, or changed
+    RoomId(u64),
+    /// The chat settings changed
+    Settings(ChannelSettings),
+    /// The channel was raided
+    Raid(ChannelRaid),
+    /// A subscription notice was seen, with the new count
+    SubsSeen(u64),
+    /// The language changed
+    Language(Option<String>),
+}
+
+/// A cache of the [ChannelInfo] of every channel it has seen messages for
+///
+/// `ROOMSTATE` only has the settings that changed after the first one Twitch
+/// sends when joining a channel, so settings it doesn't mention are kept.
+///
+/// ```
+/// # use twitchchat::{FromIrcMessage as _, messages::{ChannelInfoCache, ChannelInfoChange, Commands}};
+/// let parse = |input| {
+///     let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+///     Commands::from_irc(msg).unwrap()
+/// };
+///
+/// let mut cache = ChannelInfoCache::default();
+///
+/// let msg = parse("@emote-only=0;followers-only=-1;r9k=0;room-id=23196011;slow=0;subs-only=0 :tmi.twitch.tv ROOMSTATE #museun\r\n");
+/// // the room id, and the followers-only mode
+/// assert_eq!(cache.observe(&msg).len(), 2);
+///
+/// let msg = parse("@emote-only=1;room-id=23196011 :tmi.twitch.tv ROOMSTATE #museun\r\n");
+/// match &*cache.observe(&msg) {
+///     [ChannelInfoChange::Settings(settings)] => assert!(settings.emote_only),
+///     changes => panic!("unexpected changes: {:?}", changes),
+/// }
+///
+/// let info = cache.get("#museun").unwrap();
+/// assert_eq!(info.room_id, Some(23196011));
+/// assert!(info.settings.emote_only);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChannelInfoCache {
+    channels: HashMap<String, ChannelInfo>,
+}
+
+impl ChannelInfoCache {
+    /// Update the info of a channel from this message.
+    ///
+    /// This returns what changed, which is empty if nothing did
+    pub fn observe(&mut self, msg: &Commands<'_>) -> Vec<ChannelInfoChange> {
+        let channel = match msg.channel() {
+            Some(channel) => channel,
+            None => return vec![],
+        };
+
+        let mut changes = vec![];
+        let room_id = msg.room_id();
+        let info = self.channel_mut(channel);
+
+        if let Some(room_id) = room_id {
+            if info.room_id.replace(room_id) != Some(room_id) {
+                changes.push(ChannelInfoChange::RoomId(room_id));
+            }
+        }
+
+        match msg {
+            Commands::RoomState(msg) => info.observe_room_state(msg, &mut changes),
+            #[cfg(feature = "usernotice")]
+            Commands::UserNotice(msg) => info.observe_user_notice(msg, &mut changes),
+            _ => {}
+        }
+
+        changes
+    }
+
+    /// Get the info of this channel
+    pub fn get(&self, channel: &str) -> Option<&ChannelInfo> {
+        self.channels.get(channel)
+    }
+
+    /// Get the info of every channel
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ChannelInfo)> + '_ {
+        self.channels
+            .iter()
+            .map(|(channel, info)| (channel.as_str(), info))
+    }
+
+    /// Forget the info of this channel, e.g. when you leave it
+    pub fn remove(&mut self, channel: &str) -> Option<ChannelInfo> {
+        self.channels.remove(channel)
+    }
+
+    // this only allocates the key for new channels, the lookup can't fail after inserting it
+    #[allow(clippy::expect_used)]
+    fn channel_mut(&mut self, channel: &str) -> &mut ChannelInfo {
+        if !self.channels.contains_key(channel) {
+            self.channels
+                .insert(channel.to_string(), ChannelInfo::default());
+        }
+        self.channels
+            .get_mut(channel)
+            .expect("channel should exist")
+    }
+}
+
+impl ChannelInfo {
+    fn observe_room_state(&mut self, msg: &RoomState<'_>, changes: &mut Vec<ChannelInfoChange>) {
+        let tags = msg.tags();
+        let flag = |key: &str| tags.get(key).map(|_| tags.get_as_bool(key));
+
+        let mut settings = self.settings;
+        if let Some(emote_only) = flag("emote-only") {
+            settings.emote_only = emote_only;
+        }
+        if let Some(Ok(followers_only)) = tags.get_parsed("followers-only") {
+            settings.followers_only.replace(followers_only);
+        }
+        if let Some(r9k) = flag("r9k") {
+            settings.r9k = r9k;
+        }
+        if let Some(Ok(slow)) = tags.get_parsed("slow") {
+            settings.slow = slow;
+        }
+        if let Some(subs_only) = flag("subs-only") {
+            settings.subs_only = subs_only;
+        }
+
+        if settings != self.settings {
+            self.settings = settings;
+            changes.push(ChannelInfoChange::Settings(settings));
+        }
+
+        if let Some(language) = tags.get("broadcaster-lang") {
+            let language = Some(language).filter(|s| !s.is_empty()).map(str::to_string);
+            if language != self.language {
+                self.language = language.clone();
+                changes.push(ChannelInfoChange::Language(language));
+            }
+        }
+    }
+
+    #[cfg(feature = "usernotice")]
+    fn observe_user_notice(&mut self, msg: &UserNotice<'_>, changes: &mut Vec<ChannelInfoChange>) {
+        let kind = match msg.msg_id() {
+            Some(Ok(kind)) => kind,
+            _ => return,
+        };
+
+        match kind {
+            NoticeType::Sub | NoticeType::Resub | NoticeType::SubGift | NoticeType::AnonSubGift => {
+                self.subs_seen += 1;
+                changes.push(ChannelInfoChange::SubsSeen(self.subs_seen));
+            }
+
+            NoticeType::Raid => {
+                let raid = match msg.try_event_fields::<RaidFields<'_>>() {
+                    Ok(raid) => raid,
+                    Err(..) => return,
+                };
+                let raid = ChannelRaid {
+                    login: raid.login.to_string(),
+                    display_name: raid.display_name.to_string(),
+                    viewer_count: raid.viewer_count,
+                    tmi_sent_ts: msg.tmi_sent_ts().and_then(Result::ok),
+                };
+                self.last_raid.replace(raid.clone());
+                changes.push(ChannelInfoChange::Raid(raid));
+            }
+
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irc::parse;
+    use crate::FromIrcMessage as _;
+
+    fn observe_all(cache: &mut ChannelInfoCache, input: &str) -> Vec<ChannelInfoChange> {
+        parse(input)
+            .map(|msg| Commands::from_irc(msg.unwrap()).unwrap())
+            .flat_map(|msg| cache.observe(&msg))
+            .collect()
+    }
+
+    #[test]
+    fn room_state() {
+        let mut cache = ChannelInfoCache::default();
+        let changes = observe_all(
+            &mut cache,
+            "@broadcaster-lang=;emote-only=0;followers-only=-1;r9k=0;room-id=23196011;slow=0;subs-only=0 :tmi.twitch.tv ROOMSTATE #museun\r\n\
+             @room-id=23196011;slow=30 :tmi.twitch.tv ROOMSTATE #museun\r\n\
+             @room-id=23196011;subs-only=0 :tmi.twitch.tv ROOMSTATE #museun\r\n\
+             @broadcaster-lang=en;room-id=23196011 :tmi.twitch.tv ROOMSTATE #museun\r\n",
+        );
+
+        let settings = ChannelSettings {
+            followers_only: Some(FollowersOnly::Disabled),
+            ..ChannelSettings::default()
+        };
+        assert_eq!(
+            changes,
+            vec![
+                ChannelInfoChange::RoomId(23196011),
+                ChannelInfoChange::Settings(settings),
+                ChannelInfoChange::Settings(ChannelSettings {
+                    slow: 30,
+                    ..settings
+                }),
+                ChannelInfoChange::Language(Some("en".to_string())),
+            ]
+        );
+
+        let info = cache.get("#museun").unwrap();
+        assert_eq!(info.settings.slow, 30);
+        assert_eq!(info.language.as_deref(), Some("en"));
+    }
+
+    #[test]
+    #[cfg(feature = "usernotice")]
+    fn user_notice() {
+        let mut cache = ChannelInfoCache::default();
+        let changes = observe_all(
+            &mut cache,
+            "@msg-id=sub;msg-param-cumulative-months=1;msg-param-sub-plan=1000 :tmi.twitch.tv USERNOTICE #museun\r\n\
+             @msg-id=subgift;msg-param-months=1;msg-param-recipient-user-name=foo :tmi.twitch.tv USERNOTICE #museun\r\n\
+             @msg-id=raid;msg-param-displayName=Shaken_Bot;msg-param-login=shaken_bot;msg-param-viewerCount=42;tmi-sent-ts=1234 :tmi.twitch.tv USERNOTICE #museun\r\n\
+             @msg-id=ritual;msg-param-ritual-name=new_chatter :tmi.twitch.tv USERNOTICE #museun\r\n\
+             :museun!museun@museun.tmi.twitch.tv JOIN #other\r\n\
+             PING :1234\r\n",
+        );
+
+        let raid = ChannelRaid {
+            login: "shaken_bot".to_string(),
+            display_name: "Shaken_Bot".to_string(),
+            viewer_count: 42,
+            tmi_sent_ts: Some(1234),
+        };
+        assert_eq!(
+            changes,
+            vec![
+                ChannelInfoChange::SubsSeen(1),
+                ChannelInfoChange::SubsSeen(2),
+                ChannelInfoChange::Raid(raid.clone()),
+            ]
+        );
+
+        let info = cache.get("#museun").unwrap();
+        assert_eq!(info.subs_seen, 2);
+        assert_eq!(info.last_raid, Some(raid));
+        assert_eq!(info.room_id, None);
+
+        assert_eq!(cache.get("#other"), Some(&ChannelInfo::default()));
+        assert_eq!(cache.iter().count(), 2);
+        assert!(cache.remove("#other").is_some());
+        assert_eq!(cache.iter().count(), 1);
+    }
+}
@@ -0,0 +1,449 @@
+#[cfg(feature = "usernotice")]
+use super::UserNotice;
+#[cfg(feature = "moderation")]
+use super::{ClearChat, ClearMsg};
+use super::{Commands, FollowersOnly, MessagePart, MessageParts, Notice, Privmsg, RoomState};
+use crate::twitch::{Badge, Color};
+
+/// The user an event is about, with the fields a UI shows for them
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct ChatUser {
+    /// The login name of the user
+    pub login: String,
+    /// The display name of the user, the login name if they don't have one
+    pub display_name: String,
+    /// The color of the user, if they set one
+    pub color: Option<Color>,
+    /// The badges of the user, in the order the official client shows them
+    pub badges: Vec<Badge>,
+}
+
+/// A segment of the body of a [ChatEvent]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub enum Segment {
+    /// Plain text
+    Text(String),
+    /// An emote
+    Emote {
+        /// The id of the emote
+        id: String,
+        /// The text the emote replaces
+        text: String,
+    },
+}
+
+impl Segment {
+    fn from_parts(parts: &MessageParts<'_>) -> Vec<Self> {
+        parts
+            .iter()
+            .map(|part| match part {
+                MessagePart::Text(text) => Self::Text(text.to_string()),
+                MessagePart::Emote { id, text } => Self::Emote {
+                    id: id.clone(),
+                    text: text.to_string(),
+                },
+            })
+            .collect()
+    }
+}
+
+/// What a moderator did, see [ChatEvent::ModAction]
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub enum ModAction {
+    /// The whole chat was cleared
+    ClearChat,
+    /// A user was banned
+    Ban {
+        /// The login of the user
+        login: String,
+    },
+    /// A user was timed out
+    Timeout {
+        /// The login of the user
+        login: String,
+        /// How long the timeout is, in seconds
+        seconds: u64,
+    },
+    /// A message was deleted
+    Delete {
+        /// The login of the user who sent it, if known
+        login: Option<String>,
+        /// The id of the message, if known
+        msg_id: Option<String>,
+        /// The text of the message
+        text: Option<String>,
+    },
+}
+
+/// The room settings a [ChatEvent::RoomChange] reports.
+///
+/// Only the settings that were in the message are set, a full `ROOMSTATE`
+/// (sent when joining) has all of them.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct RoomSettings {
+    /// Whether only emotes are allowed
+    pub emote_only: Option<bool>,
+    /// The follower-only mode
+    pub followers_only: Option<FollowersOnly>,
+    /// Whether messages have to be unique (r9k)
+    pub r9k: Option<bool>,
+    /// The slow mode delay in seconds, `0` is disabled
+    pub slow: Option<u64>,
+    /// Whether only subscribers can chat
+    pub subs_only: Option<bool>,
+}
+
+/// An event of a chat timeline, with its display fields resolved.
+///
+/// This flattens the messages a UI renders in a channel's timeline into one
+/// type. Use [ChatEvent::from_commands] to build it, messages that aren't part
+/// of a timeline (e.g. `PING`) are `None`.
+///
+/// ```
+/// # use twitchchat::{FromIrcMessage as _, messages::{ChatEvent, Commands, Segment}};
+/// let input = "@color=#1E90FF;display-name=Museun;badges=moderator/1;emotes=25:6-10 :museun!museun@museun PRIVMSG #museun :hello Kappa\r\n";
+/// let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+/// let msg = Commands::from_irc(msg).unwrap();
+///
+/// match ChatEvent::from_commands(&msg).unwrap() {
+///     ChatEvent::Message { user, body, .. } => {
+///         assert_eq!(user.display_name, "Museun");
+///         assert_eq!(body, vec![
+///             Segment::Text("hello ".into()),
+///             Segment::Emote { id: "25".into(), text: "Kappa".into() },
+///         ]);
+///     }
+///     event => panic!("unexpected event: {:?}", event),
+/// }
+/// ```
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub enum ChatEvent {
+    /// A chat message (`PRIVMSG`)
+    Message {
+        /// The channel
+        channel: String,
+        /// Who sent it
+        user: ChatUser,
+        /// The text and emotes of the message
+        body: Vec<Segment>,
+        /// Whether it was a `/me`
+        is_action: bool,
+        /// The id of the message, if known
+        id: Option<String>,
+        /// The bits cheered with it, if any
+        bits: Option<u64>,
+    },
+    /// A message from Twitch: a subscription, raid, etc. (`USERNOTICE`) or an
+    /// informational notice (`NOTICE`)
+    SystemNotice {
+        /// The channel
+        channel: String,
+        /// The `msg-id` of the notice, if known
+        kind: Option<String>,
+        /// The text Twitch shows for it
+        text: String,
+        /// The user it is about, if any
+        user: Option<ChatUser>,
+        /// The message the user attached to it, if any
+        body: Vec<Segment>,
+    },
+    /// A moderator cleared the chat, banned or timed out a user or deleted a
+    /// message (`CLEARCHAT`, `CLEARMSG`)
+    ModAction {
+        /// The channel
+        channel: String,
+        /// What they did
+        action: ModAction,
+    },
+    /// The settings of the room changed (`ROOMSTATE`)
+    RoomChange {
+        /// The channel
+        channel: String,
+        /// The settings that changed
+        settings: RoomSettings,
+    },
+    /// A user joined or left the channel (`JOIN`, `PART`)
+    Presence {
+        /// The channel
+        channel: String,
+        /// The login of the user
+        login: String,
+        /// Whether they joined, or left
+        joined: bool,
+    },
+}
+
+impl ChatEvent {
+    /// Build the event for this message, if it is part of a timeline
+    pub fn from_commands(msg: &Commands<'_>) -> Option<Self> {
+        let event = match msg {
+            Commands::Privmsg(msg) => Self::from_privmsg(msg),
+            #[cfg(feature = "usernotice")]
+            Commands::UserNotice(msg) => Self::from_user_notice(msg),
+            Commands::Notice(msg) => Self::from_notice(msg),
+            #[cfg(feature = "moderation")]
+            Commands::ClearChat(msg) => Self::from_clear_chat(msg),
+            #[cfg(feature = "moderation")]
+            Commands::ClearMsg(msg) => Self::from_clear_msg(msg),
+            Commands::RoomState(msg) => Self::from_room_state(msg),
+            Commands::Join(msg) => Self::Presence {
+                channel: msg.channel().to_string(),
+                login: msg.name().to_string(),
+                joined: true,
+            },
+            Commands::Part(msg) => Self::Presence {
+                channel: msg.channel().to_string(),
+                login: msg.name().to_string(),
+                joined: false,
+            },
+            _ => return None,
+        };
+        Some(event)
+    }
+
+    /// The channel of this event
+    pub fn channel(&self) -> &str {
+        match self {
+            Self::Message { channel, .. }
+            | Self::SystemNotice { channel, .. }
+            | Self::ModAction { channel, .. }
+            | Self::RoomChange { channel, .. }
+            | Self::Presence { channel, .. } => channel,
+        }
+    }
+
+    fn from_privmsg(msg: &Privmsg<'_>) -> Self {
+        let user = ChatUser {
+            login: msg.name().to_string(),
+            display_name: msg.display_name().unwrap_or_else(|| msg.name()).to_string(),
+            color: msg.color().and_then(Result::ok),
+            badges: msg.badges().display_order().into_iter().cloned().collect(),
+        };
+
+        Self::Message {
+            channel: msg.channel().to_string(),
+            user,
+            body: Segment::from_parts(&msg.parts()),
+            is_action: msg.is_action(),
+            id: msg.tags().get("id").map(ToString::to_string),
+            bits: msg.bits().and_then(Result::ok),
+        }
+    }
+
+    #[cfg(feature = "usernotice")]
+    fn from_user_notice(msg: &UserNotice<'_>) -> Self {
+        let user = msg.login().map(|login| ChatUser {
+            login: login.to_string(),
+            display_name: msg.display_name().unwrap_or(login).to_string(),
+            color: msg.color().and_then(Result::ok),
+            badges: msg.badges().display_order().into_iter().cloned().collect(),
+        });
+
+        let body = msg
+            .message()
+            .map(|data| Segment::from_parts(&MessageParts::new(data, &msg.emotes(), false)))
+            .unwrap_or_default();
+
+        Self::SystemNotice {
+            channel: msg.channel().to_string(),
+            kind: msg.tags().get("msg-id").map(ToString::to_string),
+            text: msg.system_msg().unwrap_or_default(),
+            user,
+            body,
+        }
+    }
+
+    fn from_notice(msg: &Notice<'_>) -> Self {
+        Self::SystemNotice {
+            channel: msg.channel().to_string(),
+            kind: msg.tags().get("msg-id").map(ToString::to_string),
+            text: msg.message().to_string(),
+            user: None,
+            body: vec![],
+        }
+    }
+
+    #[cfg(feature = "moderation")]
+    fn from_clear_chat(msg: &ClearChat<'_>) -> Self {
+        let action = match (msg.name(), msg.ban_duration().and_then(Result::ok)) {
+            (Some(login), Some(seconds)) => ModAction::Timeout {
+                login: login.to_string(),
+                seconds,
+            },
+            (Some(login), None) => ModAction::Ban {
+                login: login.to_string(),
+            },
+            (None, _) => ModAction::ClearChat,
+        };
+
+        Self::ModAction {
+            channel: msg.channel().to_string(),
+            action,
+        }
+    }
+
+    #[cfg(feature = "moderation")]
+    fn from_clear_msg(msg: &ClearMsg<'_>) -> Self {
+        Self::ModAction {
+            channel: msg.channel().to_string(),
+            action: ModAction::Delete {
+                login: msg.login().map(ToString::to_string),
+                msg_id: msg.target_msg_id().map(ToString::to_string),
+                text: msg.message().map(ToString::to_string),
+            },
+        }
+    }
+
+    fn from_room_state(msg: &RoomState<'_>) -> Self {
+        let tags = msg.tags();
+        let flag = |name| tags.get(name).map(|value| value == "1");
+        let settings = RoomSettings {
+            emote_only: flag("emote-only"),
+            followers_only: tags.get_parsed("followers-only").and_then(Result::ok),
+            r9k: flag("r9k"),
+            slow: tags.get_parsed("slow").and_then(Result::ok),
+            subs_only: flag("subs-only"),
+        };
+
+        Self::RoomChange {
+            channel: msg.channel().to_string(),
+            settings,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{irc::parse, FromIrcMessage as _};
+
+    fn event(input: &str) -> Option<ChatEvent> {
+        let msg = parse(input).next().unwrap().unwrap();
+        ChatEvent::from_commands(&Commands::from_irc(msg).unwrap())
+    }
+
+    #[test]
+    fn message() {
+        let input = "@badges=subscriber/12,broadcaster/1;bits=100;display-name=;id=abc :museun!museun@museun PRIVMSG #museun :\x01ACTION cheer100\x01\r\n";
+        assert_eq!(
+            event(input).unwrap(),
+            ChatEvent::Message {
+                channel: "#museun".into(),
+                user: ChatUser {
+                    login: "museun".into(),
+                    display_name: "museun".into(),
+                    color: None,
+                    badges: vec![Badge::Broadcaster, Badge::NoTierSubscriber(12)],
+                },
+                body: vec![Segment::Text("cheer100".into())],
+                is_action: true,
+                id: Some("abc".into()),
+                bits: Some(100),
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "usernotice")]
+    fn system_notices() {
+        let input = "@msg-id=resub;login=museun;display-name=Museun;system-msg=Museun\\ssubscribed\\sfor\\s3\\smonths! :tmi.twitch.tv USERNOTICE #museun :hi\r\n";
+        match event(input).unwrap() {
+            ChatEvent::SystemNotice {
+                kind,
+                text,
+                user,
+                body,
+                ..
+            } => {
+                assert_eq!(kind.as_deref(), Some("resub"));
+                assert_eq!(text, "Museun subscribed for 3 months!");
+                assert_eq!(user.unwrap().display_name, "Museun");
+                assert_eq!(body, vec![Segment::Text("hi".into())]);
+            }
+            event => panic!("unexpected event: {:?}", event),
+        }
+
+        let input =
+            "@msg-id=slow_on :tmi.twitch.tv NOTICE #museun :This room is now in slow mode.\r\n";
+        assert_eq!(
+            event(input).unwrap(),
+            ChatEvent::SystemNotice {
+                channel: "#museun".into(),
+                kind: Some("slow_on".into()),
+                text: "This room is now in slow mode.".into(),
+                user: None,
+                body: vec![],
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "moderation")]
+    fn mod_actions() {
+        let action = |input| match event(input).unwrap() {
+            ChatEvent::ModAction { action, .. } => action,
+            event => panic!("unexpected event: {:?}", event),
+        };
+
+        assert_eq!(
+            action("@ban-duration=60 :tmi.twitch.tv CLEARCHAT #museun :foo\r\n"),
+            ModAction::Timeout {
+                login: "foo".into(),
+                seconds: 60
+            }
+        );
+        assert_eq!(
+            action(":tmi.twitch.tv CLEARCHAT #museun :foo\r\n"),
+            ModAction::Ban {
+                login: "foo".into()
+            }
+        );
+        assert_eq!(
+            action(":tmi.twitch.tv CLEARCHAT #museun\r\n"),
+            ModAction::ClearChat
+        );
+        assert_eq!(
+            action("@login=foo;target-msg-id=abc :tmi.twitch.tv CLEARMSG #museun :bad\r\n"),
+            ModAction::Delete {
+                login: Some("foo".into()),
+                msg_id: Some("abc".into()),
+                text: Some("bad".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn room_and_presence() {
+        let input = "@slow=30;subs-only=1 :tmi.twitch.tv ROOMSTATE #museun\r\n";
+        assert_eq!(
+            event(input).unwrap(),
+            ChatEvent::RoomChange {
+                channel: "#museun".into(),
+                settings: RoomSettings {
+                    slow: Some(30),
+                    subs_only: Some(true),
+                    ..RoomSettings::default()
+                },
+            }
+        );
+
+        let input = ":museun!museun@museun.tmi.twitch.tv PART #museun\r\n";
+        assert_eq!(
+            event(input).unwrap(),
+            ChatEvent::Presence {
+                channel: "#museun".into(),
+                login: "museun".into(),
+                joined: false,
+            }
+        );
+
+        assert_eq!(event("PING :1234\r\n"), None);
+    }
+}
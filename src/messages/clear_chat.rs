@@ -33,6 +33,21 @@ impl<'a> ClearChat<'a> {
     pub fn room_id(&self) -> Option<&str> {
         self.tags().get("room-id")
     }
+
+    /// The room id this event happened on, as a number like the `room_id` of the other messages
+    pub fn parsed_room_id(&self) -> Option<ParsedTag<u64>> {
+        self.tags().get_parsed("room-id")
+    }
+
+    /// The user id of the user, if any, that was being purged
+    pub fn target_user_id(&self) -> Option<ParsedTag<u64>> {
+        self.tags().get_parsed("target-user-id")
+    }
+
+    /// The timestamp of when this event was sent by Twitch
+    pub fn tmi_sent_ts(&self) -> Option<ParsedTag<u64>> {
+        self.tags().get_parsed("tmi-sent-ts")
+    }
 }
 
 impl<'a> FromIrcMessage<'a> for ClearChat<'a> {
@@ -81,7 +96,6 @@ serde_struct!(ClearChat {
 mod tests {
     use super::*;
     use assert2::assert;
-    use pipe_trait::Pipe;
 
     #[test]
     #[cfg(feature = "serde")]
@@ -102,16 +116,24 @@ mod tests {
     /// Tests whether the parts of a full CLEARCHAT message can be accessed as expected.
     #[test]
     fn clear_chat_integrity() {
-        let input = "@ban-duration=60 :tmi.twitch.tv CLEARCHAT #dallas :ronni\r\n";
-        let msg = parse(input)
-            .next()
-            .unwrap()
-            .unwrap()
-            .pipe(ClearChat::from_irc)
-            .unwrap();
+        let input = "@ban-duration=60;room-id=1337 :tmi.twitch.tv CLEARCHAT #dallas :ronni\r\n";
+        let msg = ClearChat::from_irc(parse(input).next().unwrap().unwrap()).unwrap();
         assert!(msg.ban_duration().unwrap().unwrap() == 60);
+        assert!(msg.room_id().unwrap() == "1337");
+        assert!(msg.parsed_room_id().unwrap().unwrap() == 1337);
         assert!(msg.channel() == "#dallas");
         assert!(msg.name().unwrap() == "ronni");
+        assert!(msg.target_user_id().is_none());
+        assert!(msg.tmi_sent_ts().is_none());
+    }
+
+    #[test]
+    fn clear_chat_ban() {
+        let input = "@room-id=1337;target-user-id=42;tmi-sent-ts=1601079032426 :tmi.twitch.tv CLEARCHAT #dallas :ronni\r\n";
+        let msg = ClearChat::from_irc(parse(input).next().unwrap().unwrap()).unwrap();
+        assert!(msg.ban_duration().is_none());
+        assert!(msg.target_user_id().unwrap().unwrap() == 42);
+        assert!(msg.tmi_sent_ts().unwrap().unwrap() == 1_601_079_032_426);
     }
 
     #[test]
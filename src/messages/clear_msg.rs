@@ -1,3 +1,4 @@
+use crate::irc::tags::ParsedTag;
 use crate::{irc::*, MaybeOwned, MaybeOwnedIndex, Validator};
 
 /// When a single message has been removed from a channel.
@@ -32,6 +33,16 @@ impl<'a> ClearMsg<'a> {
     pub fn target_msg_id(&self) -> Option<&str> {
         self.tags().get("target-msg-id")
     }
+
+    /// The room id this event happened on
+    pub fn room_id(&self) -> Option<ParsedTag<u64>> {
+        self.tags().get_parsed("room-id")
+    }
+
+    /// The timestamp of when this event was sent by Twitch
+    pub fn tmi_sent_ts(&self) -> Option<ParsedTag<u64>> {
+        self.tags().get_parsed("tmi-sent-ts")
+    }
 }
 
 impl<'a> FromIrcMessage<'a> for ClearMsg<'a> {
@@ -112,14 +123,15 @@ mod tests {
 
     #[test]
     fn clear_msg_uuid() {
-        let input =
-            "@login=ronni;target-msg-id=abc-123-def :tmi.twitch.tv CLEARMSG #dallas :HeyGuys\r\n";
+        let input = "@login=ronni;room-id=1337;target-msg-id=abc-123-def;tmi-sent-ts=1601079032426 :tmi.twitch.tv CLEARMSG #dallas :HeyGuys\r\n";
         for msg in parse(input).map(|s| s.unwrap()) {
             let cm = ClearMsg::from_irc(msg).unwrap();
             assert_eq!(cm.channel(), "#dallas");
             assert_eq!(cm.message().unwrap(), "HeyGuys");
             assert_eq!(cm.target_msg_id().unwrap(), "abc-123-def");
             assert_eq!(cm.login().unwrap(), "ronni");
+            assert_eq!(cm.room_id().unwrap().unwrap(), 1337);
+            assert_eq!(cm.tmi_sent_ts().unwrap().unwrap(), 1_601_079_032_426);
         }
     }
 }
@@ -3,6 +3,10 @@ use {super::*, crate::*};
 /// An enum of all possible Twitch messages.
 ///
 /// This is useful if you just want to subscribe to ***all** messages.
+///
+/// The variants of the message families (`membership`, `moderation`,
+/// `usernotice` and `whispers`) only exist with their feature. Without it,
+/// those messages are parsed as [Commands::Raw].
 #[non_exhaustive]
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
@@ -15,8 +19,12 @@ pub enum Commands<'a> {
     Ready(Ready<'a>),
     /// A ClearMsg event occured
     Cap(Cap<'a>),
+    #[cfg(feature = "moderation")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "moderation")))]
     /// A GlobalUserState event occured
     ClearChat(ClearChat<'a>),
+    #[cfg(feature = "moderation")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "moderation")))]
     /// A HostTarget event occured
     ClearMsg(ClearMsg<'a>),
     /// A IrcReady event occured
@@ -39,12 +47,24 @@ pub enum Commands<'a> {
     Reconnect(Reconnect<'a>),
     /// A RoomState event occured
     RoomState(RoomState<'a>),
+    #[cfg(feature = "usernotice")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "usernotice")))]
     /// A UserNotice event occured
     UserNotice(UserNotice<'a>),
     /// A UserState event occured
     UserState(UserState<'a>),
+    #[cfg(feature = "whispers")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "whispers")))]
     /// A Whisper event occured
     Whisper(Whisper<'a>),
+    #[cfg(feature = "membership")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "membership")))]
+    /// A NamesReply event occured
+    NamesReply(NamesReply<'a>),
+    #[cfg(feature = "membership")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "membership")))]
+    /// A EndOfNames event occured
+    EndOfNames(EndOfNames<'a>),
 }
 
 impl<'a> Commands<'a> {
@@ -55,7 +75,9 @@ impl<'a> Commands<'a> {
             Self::IrcReady(msg) => msg.raw(),
             Self::Ready(msg) => msg.raw(),
             Self::Cap(msg) => msg.raw(),
+            #[cfg(feature = "moderation")]
             Self::ClearChat(msg) => msg.raw(),
+            #[cfg(feature = "moderation")]
             Self::ClearMsg(msg) => msg.raw(),
             Self::GlobalUserState(msg) => msg.raw(),
             Self::HostTarget(msg) => msg.raw(),
@@ -67,13 +89,83 @@ impl<'a> Commands<'a> {
             Self::Privmsg(msg) => msg.raw(),
             Self::Reconnect(msg) => msg.raw(),
             Self::RoomState(msg) => msg.raw(),
+            #[cfg(feature = "usernotice")]
             Self::UserNotice(msg) => msg.raw(),
             Self::UserState(msg) => msg.raw(),
+            #[cfg(feature = "whispers")]
             Self::Whisper(msg) => msg.raw(),
+            #[cfg(feature = "membership")]
+            Self::NamesReply(msg) => msg.raw(),
+            #[cfg(feature = "membership")]
+            Self::EndOfNames(msg) => msg.raw(),
         }
     }
 }
 
+impl<'a> Commands<'a> {
+    /// Get the channel this message happened on, if it is channel-scoped
+    pub fn channel(&self) -> Option<&str> {
+        let channel = match self {
+            #[cfg(feature = "moderation")]
+            Self::ClearChat(msg) => msg.channel(),
+            #[cfg(feature = "moderation")]
+            Self::ClearMsg(msg) => msg.channel(),
+            Self::HostTarget(msg) => msg.source(),
+            Self::Join(msg) => msg.channel(),
+            Self::Notice(msg) => msg.channel(),
+            Self::Part(msg) => msg.channel(),
+            Self::Privmsg(msg) => msg.channel(),
+            Self::RoomState(msg) => msg.channel(),
+            #[cfg(feature = "usernotice")]
+            Self::UserNotice(msg) => msg.channel(),
+            Self::UserState(msg) => msg.channel(),
+            #[cfg(feature = "membership")]
+            Self::NamesReply(msg) => msg.channel(),
+            #[cfg(feature = "membership")]
+            Self::EndOfNames(msg) => msg.channel(),
+            _ => return None,
+        };
+        Some(channel)
+    }
+
+    /// Get the room id from the `room-id` tag, if this message has a valid one
+    ///
+    /// See [RoomIds] for resolving the room id of messages that don't carry the tag.
+    pub fn room_id(&self) -> Option<u64> {
+        let room_id = match self {
+            #[cfg(feature = "moderation")]
+            Self::ClearChat(msg) => msg.parsed_room_id(),
+            #[cfg(feature = "moderation")]
+            Self::ClearMsg(msg) => msg.room_id(),
+            Self::Privmsg(msg) => msg.room_id(),
+            Self::RoomState(msg) => msg.room_id(),
+            #[cfg(feature = "usernotice")]
+            Self::UserNotice(msg) => msg.room_id(),
+            Self::UserState(msg) => msg.room_id(),
+            _ => None,
+        };
+        room_id?.ok()
+    }
+
+    /// Get when Twitch says this message was sent, from the `tmi-sent-ts` tag (in milliseconds)
+    pub fn tmi_sent_ts(&self) -> Option<u64> {
+        let tags = match self {
+            Self::Privmsg(msg) => msg.tags(),
+            #[cfg(feature = "usernotice")]
+            Self::UserNotice(msg) => msg.tags(),
+            #[cfg(feature = "moderation")]
+            Self::ClearChat(msg) => msg.tags(),
+            #[cfg(feature = "moderation")]
+            Self::ClearMsg(msg) => msg.tags(),
+            Self::RoomState(msg) => msg.tags(),
+            #[cfg(feature = "whispers")]
+            Self::Whisper(msg) => msg.tags(),
+            _ => return None,
+        };
+        tags.get_parsed("tmi-sent-ts").and_then(Result::ok)
+    }
+}
+
 impl<'a> IntoOwned<'a> for Commands<'a> {
     type Output = Commands<'static>;
 
@@ -83,7 +175,9 @@ impl<'a> IntoOwned<'a> for Commands<'a> {
             Self::IrcReady(s) => Commands::IrcReady(s.into_owned()),
             Self::Ready(s) => Commands::Ready(s.into_owned()),
             Self::Cap(s) => Commands::Cap(s.into_owned()),
+            #[cfg(feature = "moderation")]
             Self::ClearChat(s) => Commands::ClearChat(s.into_owned()),
+            #[cfg(feature = "moderation")]
             Self::ClearMsg(s) => Commands::ClearMsg(s.into_owned()),
             Self::GlobalUserState(s) => Commands::GlobalUserState(s.into_owned()),
             Self::HostTarget(s) => Commands::HostTarget(s.into_owned()),
@@ -95,9 +189,15 @@ impl<'a> IntoOwned<'a> for Commands<'a> {
             Self::Privmsg(s) => Commands::Privmsg(s.into_owned()),
             Self::Reconnect(s) => Commands::Reconnect(s.into_owned()),
             Self::RoomState(s) => Commands::RoomState(s.into_owned()),
+            #[cfg(feature = "usernotice")]
             Self::UserNotice(s) => Commands::UserNotice(s.into_owned()),
             Self::UserState(s) => Commands::UserState(s.into_owned()),
+            #[cfg(feature = "whispers")]
             Self::Whisper(s) => Commands::Whisper(s.into_owned()),
+            #[cfg(feature = "membership")]
+            Self::NamesReply(s) => Commands::NamesReply(s.into_owned()),
+            #[cfg(feature = "membership")]
+            Self::EndOfNames(s) => Commands::EndOfNames(s.into_owned()),
         }
     }
 }
@@ -117,7 +217,9 @@ impl<'a> FromIrcMessage<'a> for Commands<'a> {
             M::IRC_READY => map!(IrcReady),
             M::READY => map!(Ready),
             M::CAP => map!(Cap),
+            #[cfg(feature = "moderation")]
             M::CLEAR_CHAT => map!(ClearChat),
+            #[cfg(feature = "moderation")]
             M::CLEAR_MSG => map!(ClearMsg),
             M::GLOBAL_USER_STATE => map!(GlobalUserState),
             M::HOST_TARGET => map!(HostTarget),
@@ -129,9 +231,15 @@ impl<'a> FromIrcMessage<'a> for Commands<'a> {
             M::PRIVMSG => map!(Privmsg),
             M::RECONNECT => map!(Reconnect),
             M::ROOM_STATE => map!(RoomState),
+            #[cfg(feature = "usernotice")]
             M::USER_NOTICE => map!(UserNotice),
             M::USER_STATE => map!(UserState),
+            #[cfg(feature = "whispers")]
             M::WHISPER => map!(Whisper),
+            #[cfg(feature = "membership")]
+            M::NAMES_REPLY => map!(NamesReply),
+            #[cfg(feature = "membership")]
+            M::END_OF_NAMES => map!(EndOfNames),
             _ => Self::Raw(IrcMessage::from_irc(msg).expect("infallible conversion")),
         };
 
@@ -145,7 +253,9 @@ impl<'a> FromIrcMessage<'a> for Commands<'a> {
             Self::IrcReady(msg) => msg.into_inner(),
             Self::Ready(msg) => msg.into_inner(),
             Self::Cap(msg) => msg.into_inner(),
+            #[cfg(feature = "moderation")]
             Self::ClearChat(msg) => msg.into_inner(),
+            #[cfg(feature = "moderation")]
             Self::ClearMsg(msg) => msg.into_inner(),
             Self::GlobalUserState(msg) => msg.into_inner(),
             Self::HostTarget(msg) => msg.into_inner(),
@@ -157,16 +267,22 @@ impl<'a> FromIrcMessage<'a> for Commands<'a> {
             Self::Privmsg(msg) => msg.into_inner(),
             Self::Reconnect(msg) => msg.into_inner(),
             Self::RoomState(msg) => msg.into_inner(),
+            #[cfg(feature = "usernotice")]
             Self::UserNotice(msg) => msg.into_inner(),
             Self::UserState(msg) => msg.into_inner(),
+            #[cfg(feature = "whispers")]
             Self::Whisper(msg) => msg.into_inner(),
+            #[cfg(feature = "membership")]
+            Self::NamesReply(msg) => msg.into_inner(),
+            #[cfg(feature = "membership")]
+            Self::EndOfNames(msg) => msg.into_inner(),
         }
     }
 }
 
 macro_rules! from_other {
-    ($($ident:tt)*) => {
-        $(impl<'a> From<$ident<'a>> for Commands<'a> {
+    ($($(#[$meta:meta])* $ident:ident)*) => {
+        $($(#[$meta])* impl<'a> From<$ident<'a>> for Commands<'a> {
             fn from(msg: $ident<'a>) -> Self {
                 Self::$ident(msg)
             }
@@ -181,7 +297,9 @@ from_other! {
     IrcReady
     Ready
     Cap
+    #[cfg(feature = "moderation")]
     ClearChat
+    #[cfg(feature = "moderation")]
     ClearMsg
     GlobalUserState
     HostTarget
@@ -193,9 +311,15 @@ from_other! {
     Privmsg
     Reconnect
     RoomState
+    #[cfg(feature = "usernotice")]
     UserNotice
     UserState
+    #[cfg(feature = "whispers")]
     Whisper
+    #[cfg(feature = "membership")]
+    NamesReply
+    #[cfg(feature = "membership")]
+    EndOfNames
 }
 
 #[cfg(test)]
@@ -206,8 +330,166 @@ mod tests {
     #[cfg(feature = "serde")]
     fn commands_serde() {
         let input = ":test!test@test PRIVMSG #museun :this is a test\r\n";
-        crate::serde::round_trip_json::<Commands>(input);
-        crate::serde::round_trip_rmp::<Commands>(input);
+        serde::round_trip_json::<Commands>(input);
+        serde::round_trip_rmp::<Commands>(input);
+    }
+
+    // tags this crate doesn't know about yet (and odd spacing) must survive the owned conversion
+    const UNKNOWN_TAGS: &[&str] = &[
+        "@badge-info=;badges=;x-future-tag=hello\\sworld;flags=;client-nonce=abc :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :this is a test\r\n",
+        "@x-future-tag=1;msg-id=some_new_notice :tmi.twitch.tv NOTICE #museun :something new\r\n",
+        "@room-id=23196011;x-future-tag=;emote-only=0 :tmi.twitch.tv ROOMSTATE #museun\r\n",
+        "@msg-id=some_new_event;x-future-tag=\\s\\:\\\\;login=museun :tmi.twitch.tv USERNOTICE #museun :hello\r\n",
+        "@x-future-tag=1 :tmi.twitch.tv SOMETHING_NEW #museun :with data\r\n",
+    ];
+
+    #[test]
+    fn into_owned_preserves_raw() {
+        for input in UNKNOWN_TAGS {
+            let msg = IrcMessage::parse(MaybeOwned::Borrowed(input)).unwrap();
+            let borrowed = Commands::from_irc(msg).unwrap();
+            let owned = borrowed.clone().into_owned();
+            assert_eq!(owned.raw(), *input);
+            assert_eq!(owned, borrowed);
+
+            let msg = IrcMessage::parse(MaybeOwned::Borrowed(owned.raw())).unwrap();
+            let indices = msg.parse_tags();
+            let tags = irc::Tags::from_data_indices(&msg.raw, &indices);
+            assert!(tags.get("x-future-tag").is_some(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn into_owned_unknown_tag_values() {
+        let input = UNKNOWN_TAGS[0];
+        let msg = IrcMessage::parse(MaybeOwned::Borrowed(input)).unwrap();
+        let msg = Privmsg::from_irc(msg).unwrap().into_owned();
+        assert_eq!(msg.tags().get("x-future-tag").unwrap(), "hello\\sworld");
+        assert_eq!(
+            &*msg.tags().get_unescaped("x-future-tag").unwrap(),
+            "hello world"
+        );
+        assert_eq!(msg.tags().get("client-nonce").unwrap(), "abc");
+        assert_eq!(msg.tags().get("flags").unwrap(), "");
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_preserves_unknown_tags() {
+        for input in UNKNOWN_TAGS {
+            serde::round_trip_json::<Commands>(input);
+            serde::round_trip_rmp::<Commands>(input);
+
+            let msg = IrcMessage::parse(MaybeOwned::Borrowed(input)).unwrap();
+            let msg = Commands::from_irc(msg).unwrap().into_owned();
+            let json = serde_json::to_string(&msg).unwrap();
+            let msg = serde_json::from_str::<Commands>(&json).unwrap();
+            assert_eq!(msg.raw(), *input);
+        }
+    }
+
+    // a small xorshift generator, so the messages are the same on every run
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+
+        fn word(&mut self) -> String {
+            const CHARS: &[char] = &[
+                'a', 'Z', '0', '#', '@', '!', ':', ';', '=', ' ', '\\', '\x01', '\t', 'é', 'ß',
+                '中', '😀', '\u{200b}',
+            ];
+            (0..self.next(8))
+                .map(|_| CHARS[self.next(CHARS.len())])
+                .collect()
+        }
+
+        fn message(&mut self) -> String {
+            const COMMANDS: &[&str] = &[
+                "PRIVMSG",
+                "NOTICE",
+                "USERNOTICE",
+                "WHISPER",
+                "JOIN",
+                "PART",
+                "CLEARCHAT",
+                "CLEARMSG",
+                "ROOMSTATE",
+                "USERSTATE",
+                "GLOBALUSERSTATE",
+                "HOSTTARGET",
+                "CAP",
+                "PING",
+                "PONG",
+                "RECONNECT",
+                "001",
+                "353",
+                "366",
+                "376",
+                "é",
+            ];
+
+            let mut msg = String::new();
+            if self.next(2) == 0 {
+                msg.push('@');
+                for _ in 0..self.next(4) {
+                    msg.push_str(&format!("{}={};", self.word(), self.word()));
+                }
+                msg.push(' ');
+            }
+            if self.next(2) == 0 {
+                msg.push_str(&format!(":{}!{} ", self.word(), self.word()));
+            }
+            msg.push_str(COMMANDS[self.next(COMMANDS.len())]);
+            for _ in 0..self.next(3) {
+                msg.push_str(&format!(" {}", self.word()));
+            }
+            if self.next(2) == 0 {
+                msg.push_str(&format!(" :{}", self.word()));
+            }
+            msg.push_str("\r\n");
+            msg
+        }
+    }
+
+    // see the `messages` fuzz target for the open-ended version of this
+    #[test]
+    fn random_unicode_messages() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..20_000 {
+            let input = rng.message();
+            let msg = match IrcMessage::parse(MaybeOwned::Borrowed(&input)) {
+                Ok(msg) => msg,
+                Err(..) => continue,
+            };
+            let _ = format!("{:?}", msg);
+            if let Ok(msg) = Commands::from_irc(msg) {
+                let _ = format!("{:?}", msg);
+                let _ = msg.content_hash();
+                assert_eq!(msg.into_owned().raw(), input);
+            }
+        }
+    }
+
+    #[test]
+    fn fuzz_regressions() {
+        let corpus = include_str!("../../fuzz/regressions.txt");
+        for line in corpus.lines().filter(|line| !line.starts_with('#')) {
+            let input = format!("{}\r\n", line);
+            for msg in irc::parse(&input).flatten() {
+                let _ = format!("{:?}", msg);
+                if let Ok(msg) = Commands::from_irc(msg) {
+                    let _ = format!("{:?}", msg);
+                    let _ = msg.content_hash();
+                    let _ = format!("{:?}", msg.into_owned());
+                }
+            }
+        }
     }
 
     #[test]
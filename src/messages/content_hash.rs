@@ -0,0 +1,142 @@
+use super::{Commands, Privmsg};
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// 64-bit FNV-1a, which unlike `DefaultHasher` is the same in every process and release
+pub(crate) struct Fnv(pub(crate) u64);
+
+impl Fnv {
+    pub(crate) const fn new() -> Self {
+        Self(FNV_OFFSET)
+    }
+
+    pub(crate) fn write(&mut self, bytes: impl IntoIterator<Item = u8>) -> &mut Self {
+        for byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+        // 0xFF never appears in utf-8, so fields can't run into each other
+        self.0 ^= 0xFF;
+        self.0 = self.0.wrapping_mul(FNV_PRIME);
+        self
+    }
+}
+
+/// A stable hash of the content of a message, for use as a cache key or to
+/// find duplicates across processes.
+///
+/// The channel and user are compared case-insensitively (and the channel with
+/// or without its `#`), and whitespace around the data is ignored. The hash
+/// doesn't change between processes or versions of this crate.
+///
+/// ```
+/// # use twitchchat::messages::content_hash;
+/// assert_eq!(
+///     content_hash("#Museun", "Foo", "hello ", Some(1234)),
+///     content_hash("museun", "foo", "hello", Some(1234)),
+/// );
+/// assert_ne!(
+///     content_hash("#museun", "foo", "hello", Some(1234)),
+///     content_hash("#museun", "foo", "hello", Some(1235)),
+/// );
+/// ```
+pub fn content_hash(channel: &str, user: &str, data: &str, tmi_sent_ts: Option<u64>) -> u64 {
+    let lower = |s: &str| {
+        s.bytes()
+            .map(|b| b.to_ascii_lowercase())
+            .collect::<Vec<_>>()
+    };
+
+    let ts = tmi_sent_ts.map(|ts| ts.to_string()).unwrap_or_default();
+    Fnv::new()
+        .write(lower(channel.trim_start_matches('#')))
+        .write(lower(user))
+        .write(data.trim().bytes())
+        .write(ts.bytes())
+        .0
+}
+
+impl<'a> Privmsg<'a> {
+    /// A stable hash of the channel, sender, data and `tmi-sent-ts` of this message, see [content_hash]
+    pub fn content_hash(&self) -> u64 {
+        content_hash(
+            self.channel(),
+            self.name(),
+            self.data(),
+            self.tmi_sent_ts().and_then(Result::ok),
+        )
+    }
+}
+
+impl<'a> Commands<'a> {
+    /// A stable hash of the content of this message, see [content_hash]
+    ///
+    /// Chat messages, whispers and user notices are hashed by their channel,
+    /// sender, data and `tmi-sent-ts`. Other messages are hashed by their
+    /// channel and their raw line without tags.
+    pub fn content_hash(&self) -> u64 {
+        match self {
+            Self::Privmsg(msg) => msg.content_hash(),
+            #[cfg(feature = "whispers")]
+            Self::Whisper(msg) => content_hash(
+                "",
+                msg.name(),
+                msg.data(),
+                msg.tmi_sent_ts().and_then(Result::ok),
+            ),
+            #[cfg(feature = "usernotice")]
+            Self::UserNotice(msg) => content_hash(
+                msg.channel(),
+                msg.login().unwrap_or_default(),
+                msg.message().unwrap_or_default(),
+                msg.tmi_sent_ts().and_then(Result::ok),
+            ),
+            msg => {
+                let raw = msg.raw();
+                let untagged = match raw.strip_prefix('@') {
+                    Some(tagged) => tagged.split_once(' ').map_or("", |(_, rest)| rest),
+                    None => raw,
+                };
+                content_hash(msg.channel().unwrap_or_default(), "", untagged, None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{irc::parse, FromIrcMessage as _};
+
+    fn hashes(input: &str) -> Vec<u64> {
+        parse(input)
+            .map(|msg| Commands::from_irc(msg.unwrap()).unwrap().content_hash())
+            .collect()
+    }
+
+    #[test]
+    fn stable() {
+        // if this changes, hashes stored by other processes are invalidated
+        assert_eq!(
+            content_hash("#museun", "foo", "hello", Some(1234)),
+            0xed73_4307_3865_0a52
+        );
+    }
+
+    #[test]
+    fn normalized() {
+        let hashes = hashes(
+            "@id=1;tmi-sent-ts=1234 :foo!foo@foo PRIVMSG #museun :hello\r\n\
+             @id=2;tmi-sent-ts=1234 :FOO!foo@foo PRIVMSG #Museun :hello \r\n\
+             @id=3;tmi-sent-ts=1235 :foo!foo@foo PRIVMSG #museun :hello\r\n\
+             @room-id=1 :tmi.twitch.tv CLEARCHAT #museun\r\n\
+             @room-id=2 :tmi.twitch.tv CLEARCHAT #museun\r\n\
+             :tmi.twitch.tv CLEARCHAT #other\r\n",
+        );
+        assert_eq!(hashes[0], hashes[1]);
+        assert_ne!(hashes[0], hashes[2]);
+        assert_eq!(hashes[3], hashes[4]);
+        assert_ne!(hashes[3], hashes[5]);
+    }
+}
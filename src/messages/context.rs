@@ -0,0 +1,299 @@
+use super::{Commands, Privmsg};
+use crate::IntoOwned as _;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// A chat message kept by the [ContextBuffer]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextEntry {
+    /// When the message was received
+    pub received: Instant,
+    /// The message
+    pub message: Privmsg<'static>,
+}
+
+/// A per-channel buffer of recent chat messages.
+///
+/// This keeps, for each channel, at most `max_messages` messages that are no
+/// older than `max_age`. When a moderation event arrives, it can be used to
+/// look up what the user recently said (e.g. for logs or appeals).
+///
+/// ```
+/// # use twitchchat::{FromIrcMessage as _, messages::{Commands, ContextBuffer}};
+/// # use std::time::Duration;
+/// let parse = |input| {
+///     let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+///     Commands::from_irc(msg).unwrap()
+/// };
+///
+/// let mut context = ContextBuffer::new(100, Duration::from_secs(60 * 5));
+///
+/// context.observe(&parse("@id=1 :foo!foo@foo PRIVMSG #museun :buy followers\r\n"));
+/// context.observe(&parse("@id=2 :bar!bar@bar PRIVMSG #museun :hello\r\n"));
+///
+/// // `CLEARCHAT` is parsed with the `moderation` feature
+/// # #[cfg(feature = "moderation")] {
+/// let ban = parse("@ban-duration=600 :tmi.twitch.tv CLEARCHAT #museun :foo\r\n");
+/// let said = context.for_event(&ban);
+/// assert_eq!(said.len(), 1);
+/// assert_eq!(said[0].message.data(), "buy followers");
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContextBuffer {
+    max_messages: usize,
+    max_age: Duration,
+    channels: HashMap<String, VecDeque<ContextEntry>>,
+}
+
+impl ContextBuffer {
+    /// Create a new buffer keeping up to `max_messages` messages per channel, for up to `max_age`
+    pub fn new(max_messages: usize, max_age: Duration) -> Self {
+        Self {
+            max_messages,
+            max_age,
+            channels: HashMap::new(),
+        }
+    }
+
+    /// Remember this message if it is a chat message.
+    ///
+    /// Other messages are ignored
+    pub fn observe(&mut self, msg: &Commands<'_>) {
+        if let Commands::Privmsg(msg) = msg {
+            self.push(msg)
+        }
+    }
+
+    /// Remember this chat message, received now
+    pub fn push(&mut self, msg: &Privmsg<'_>) {
+        self.push_at(msg, Instant::now())
+    }
+
+    /// Remember this chat message, received at `received`
+    pub fn push_at(&mut self, msg: &Privmsg<'_>, received: Instant) {
+        if self.max_messages == 0 {
+            return;
+        }
+
+        let entries = match self.channels.get_mut(msg.channel()) {
+            Some(entries) => entries,
+            None => self.channels.entry(msg.channel().to_string()).or_default(),
+        };
+
+        while entries.len() >= self.max_messages {
+            entries.pop_front();
+        }
+        entries.push_back(ContextEntry {
+            received,
+            message: msg.clone().into_owned(),
+        });
+
+        Self::expire(entries, self.max_age, received);
+    }
+
+    /// Drop all messages that are older than `max_age` at `now`
+    ///
+    /// This returns how many messages were dropped
+    pub fn prune(&mut self, now: Instant) -> usize {
+        let max_age = self.max_age;
+        let mut pruned = 0;
+        self.channels.retain(|_, entries| {
+            let len = entries.len();
+            Self::expire(entries, max_age, now);
+            pruned += len - entries.len();
+            !entries.is_empty()
+        });
+        pruned
+    }
+
+    /// All of the messages for this channel, oldest first
+    pub fn channel<'a>(&'a self, channel: &str) -> impl Iterator<Item = &'a ContextEntry> + 'a {
+        self.channels.get(channel).into_iter().flatten()
+    }
+
+    /// The messages sent by the user `name` (their login) on this channel, oldest first
+    pub fn by_user<'a>(
+        &'a self,
+        channel: &str,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a ContextEntry> + 'a {
+        self.channel(channel)
+            .filter(move |entry| entry.message.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The messages sent by the user `name` (their login) on any channel, oldest first
+    pub fn by_user_anywhere(&self, name: &str) -> impl Iterator<Item = &ContextEntry> + '_ {
+        let mut entries = self
+            .channels
+            .values()
+            .flatten()
+            .filter(|entry| entry.message.name().eq_ignore_ascii_case(name))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.received);
+        entries.into_iter()
+    }
+
+    /// The messages sent by the user with this id on this channel, oldest first
+    pub fn by_user_id<'a>(
+        &'a self,
+        channel: &str,
+        user_id: u64,
+    ) -> impl Iterator<Item = &'a ContextEntry> + 'a {
+        self.channel(channel)
+            .filter(move |entry| matches!(entry.message.user_id(), Some(Ok(id)) if id == user_id))
+    }
+
+    /// The message with this id (the `id` tag) on this channel
+    pub fn by_msg_id(&self, channel: &str, msg_id: &str) -> Option<&ContextEntry> {
+        self.channel(channel)
+            .find(|entry| entry.message.tags().get("id") == Some(msg_id))
+    }
+
+    /// The messages a moderation event is about.
+    ///
+    /// For a `CLEARCHAT` targeting a user, this is what the user said on that
+    /// channel. For a `CLEARMSG`, this is the deleted message. Otherwise this is empty.
+    pub fn for_event(&self, msg: &Commands<'_>) -> Vec<&ContextEntry> {
+        match msg {
+            #[cfg(feature = "moderation")]
+            Commands::ClearChat(msg) => match msg.name() {
+                Some(name) => self
+                    .channel(msg.channel())
+                    .filter(|entry| entry.message.name().eq_ignore_ascii_case(name))
+                    .collect(),
+                None => vec![],
+            },
+            #[cfg(feature = "moderation")]
+            Commands::ClearMsg(msg) => msg
+                .target_msg_id()
+                .and_then(|id| self.by_msg_id(msg.channel(), id))
+                .into_iter()
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Forget all of the messages for this channel
+    pub fn remove_channel(&mut self, channel: &str) {
+        self.channels.remove(channel);
+    }
+
+    fn expire(entries: &mut VecDeque<ContextEntry>, max_age: Duration, now: Instant) {
+        while let Some(entry) = entries.front() {
+            if now.saturating_duration_since(entry.received) <= max_age {
+                break;
+            }
+            entries.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irc::parse;
+    use crate::FromIrcMessage as _;
+
+    fn privmsg(input: &str) -> Privmsg<'_> {
+        let msg = parse(input).next().unwrap().unwrap();
+        Privmsg::from_irc(msg).unwrap()
+    }
+
+    #[cfg(feature = "moderation")]
+    fn commands(input: &str) -> Commands<'_> {
+        let msg = parse(input).next().unwrap().unwrap();
+        Commands::from_irc(msg).unwrap()
+    }
+
+    #[test]
+    fn max_messages() {
+        let mut context = ContextBuffer::new(2, Duration::from_secs(60));
+        for i in 0..3 {
+            let input = format!(":foo!foo@foo PRIVMSG #museun :{}\r\n", i);
+            context.push(&privmsg(&input));
+        }
+        context.push(&privmsg(":foo!foo@foo PRIVMSG #other :hello\r\n"));
+
+        let data = context
+            .channel("#museun")
+            .map(|entry| entry.message.data())
+            .collect::<Vec<_>>();
+        assert_eq!(data, vec!["1", "2"]);
+        assert_eq!(context.channel("#other").count(), 1);
+
+        context.remove_channel("#other");
+        assert_eq!(context.channel("#other").count(), 0);
+    }
+
+    #[test]
+    fn max_age() {
+        let start = Instant::now();
+        let mut context = ContextBuffer::new(10, Duration::from_secs(60));
+
+        context.push_at(&privmsg(":foo!foo@foo PRIVMSG #museun :a\r\n"), start);
+        context.push_at(
+            &privmsg(":foo!foo@foo PRIVMSG #museun :b\r\n"),
+            start + Duration::from_secs(30),
+        );
+        context.push_at(
+            &privmsg(":foo!foo@foo PRIVMSG #museun :c\r\n"),
+            start + Duration::from_secs(61),
+        );
+
+        let data = context
+            .channel("#museun")
+            .map(|entry| entry.message.data())
+            .collect::<Vec<_>>();
+        assert_eq!(data, vec!["b", "c"]);
+
+        assert_eq!(context.prune(start + Duration::from_secs(200)), 2);
+        assert_eq!(context.channel("#museun").count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "moderation")]
+    fn queries() {
+        let mut context = ContextBuffer::new(10, Duration::from_secs(60));
+        for input in &[
+            "@id=a;user-id=1 :foo!foo@foo PRIVMSG #museun :first\r\n",
+            "@id=b;user-id=2 :bar!bar@bar PRIVMSG #museun :hello\r\n",
+            "@id=c;user-id=1 :foo!foo@foo PRIVMSG #museun :second\r\n",
+            "@id=d;user-id=1 :foo!foo@foo PRIVMSG #other :elsewhere\r\n",
+        ] {
+            context.observe(&commands(input));
+        }
+        context.observe(&commands(":tmi.twitch.tv CLEARCHAT #museun\r\n"));
+
+        let data = |entries: Vec<&ContextEntry>| {
+            entries
+                .into_iter()
+                .map(|entry| entry.message.data().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            data(context.by_user("#museun", "FOO").collect()),
+            vec!["first", "second"]
+        );
+        assert_eq!(
+            data(context.by_user_id("#museun", 2).collect()),
+            vec!["hello"]
+        );
+        assert_eq!(
+            context.by_msg_id("#museun", "c").unwrap().message.data(),
+            "second"
+        );
+        assert!(context.by_msg_id("#museun", "d").is_none());
+
+        let timeout = commands("@ban-duration=1 :tmi.twitch.tv CLEARCHAT #museun :foo\r\n");
+        assert_eq!(data(context.for_event(&timeout)), vec!["first", "second"]);
+
+        let delete =
+            commands("@login=bar;target-msg-id=b :tmi.twitch.tv CLEARMSG #museun :hello\r\n");
+        assert_eq!(data(context.for_event(&delete)), vec!["hello"]);
+
+        let clear = commands(":tmi.twitch.tv CLEARCHAT #museun\r\n");
+        assert!(context.for_event(&clear).is_empty());
+    }
+}
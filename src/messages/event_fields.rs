@@ -0,0 +1,432 @@
+use super::{NoticeType, SubPlan, UserNotice};
+
+/// An error returned by [UserNotice::try_event_fields()]
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum EventFieldsError {
+    /// The notice had no (or an unparsable) `msg-id` tag
+    MissingMsgId,
+    /// The fields were requested from the wrong kind of notice
+    WrongNoticeType {
+        /// The kinds of notices the fields are sent with
+        expected: Vec<NoticeType>,
+        /// The kind of this notice
+        found: NoticeType,
+    },
+    /// A tag that is always sent with this kind of notice was missing
+    MissingField {
+        /// The kind of this notice
+        kind: NoticeType,
+        /// The name of the tag
+        name: &'static str,
+    },
+    /// A tag could not be parsed
+    InvalidField {
+        /// The kind of this notice
+        kind: NoticeType,
+        /// The name of the tag
+        name: &'static str,
+        /// The raw value of the tag
+        value: String,
+    },
+}
+
+impl std::fmt::Display for EventFieldsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingMsgId => write!(f, "the notice has no valid 'msg-id' tag"),
+            Self::WrongNoticeType { expected, found } => {
+                let expected = expected
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("' or '");
+                write!(f, "expected a '{}' notice, got '{}'", expected, found)
+            }
+            Self::MissingField { kind, name } => {
+                write!(f, "the '{}' notice is missing '{}'", kind, name)
+            }
+            Self::InvalidField { kind, name, value } => write!(
+                f,
+                "the '{}' notice has an invalid '{}': '{}'",
+                kind, name, value
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EventFieldsError {}
+
+/// The `msg-param-*` fields of a specific kind of [UserNotice].
+///
+/// Use these with [UserNotice::try_event_fields()]
+pub trait EventFields<'a>: Sized {
+    /// The kinds of notices these fields are sent with
+    fn expected() -> Vec<NoticeType>;
+
+    /// Read the fields from this notice. The kind of the notice has already been checked
+    fn from_notice(msg: &'a UserNotice<'_>, kind: NoticeType) -> Result<Self, EventFieldsError>;
+}
+
+/// Reads the required fields of one notice, in the same error-reporting way
+struct Fields<'a, 'b> {
+    msg: &'a UserNotice<'b>,
+    kind: NoticeType,
+}
+
+impl<'a, 'b> Fields<'a, 'b> {
+    fn str(&self, name: &'static str) -> Result<&'a str, EventFieldsError> {
+        self.msg
+            .tags()
+            .get(name)
+            .ok_or_else(|| EventFieldsError::MissingField {
+                kind: self.kind.clone(),
+                name,
+            })
+    }
+
+    fn u64(&self, name: &'static str) -> Result<u64, EventFieldsError> {
+        let value = self.str(name)?;
+        value.parse().map_err(|_| EventFieldsError::InvalidField {
+            kind: self.kind.clone(),
+            name,
+            value: value.to_string(),
+        })
+    }
+
+    fn opt_u64(&self, name: &'static str) -> Result<Option<u64>, EventFieldsError> {
+        match self.msg.tags().get(name) {
+            Some(..) => self.u64(name).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn sub_plan(&self) -> Result<SubPlan, EventFieldsError> {
+        let name = "msg-param-sub-plan";
+        self.str(name)?;
+        self.msg
+            .msg_param_sub_plan()
+            .ok_or_else(|| EventFieldsError::MissingField {
+                kind: self.kind.clone(),
+                name,
+            })
+    }
+}
+
+/// The fields of a `sub` or `resub` notice
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubFields<'a> {
+    /// The total number of months the user has subscribed
+    pub cumulative_months: u64,
+    /// The number of consecutive months, if the user shared their streak
+    pub streak_months: Option<u64>,
+    /// The subscription plan
+    pub sub_plan: SubPlan,
+    /// The display name of the subscription plan
+    pub sub_plan_name: &'a str,
+}
+
+impl<'a> EventFields<'a> for SubFields<'a> {
+    fn expected() -> Vec<NoticeType> {
+        vec![NoticeType::Sub, NoticeType::Resub]
+    }
+
+    fn from_notice(msg: &'a UserNotice<'_>, kind: NoticeType) -> Result<Self, EventFieldsError> {
+        let fields = Fields { msg, kind };
+        Ok(Self {
+            cumulative_months: fields.u64("msg-param-cumulative-months")?,
+            streak_months: msg.streak_months(),
+            sub_plan: fields.sub_plan()?,
+            sub_plan_name: fields.str("msg-param-sub-plan-name")?,
+        })
+    }
+}
+
+/// The fields of a `subgift` or `anonsubgift` notice
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubGiftFields<'a> {
+    /// The total number of months the recipient has subscribed
+    pub months: u64,
+    /// The number of months that were gifted
+    pub gift_months: u64,
+    /// The display name of the recipient
+    pub recipient_display_name: &'a str,
+    /// The user id of the recipient
+    pub recipient_id: u64,
+    /// The login of the recipient
+    pub recipient_user_name: &'a str,
+    /// The subscription plan
+    pub sub_plan: SubPlan,
+    /// The display name of the subscription plan
+    pub sub_plan_name: &'a str,
+}
+
+impl<'a> EventFields<'a> for SubGiftFields<'a> {
+    fn expected() -> Vec<NoticeType> {
+        vec![NoticeType::SubGift, NoticeType::AnonSubGift]
+    }
+
+    fn from_notice(msg: &'a UserNotice<'_>, kind: NoticeType) -> Result<Self, EventFieldsError> {
+        let fields = Fields { msg, kind };
+        Ok(Self {
+            months: fields.u64("msg-param-months")?,
+            gift_months: fields.opt_u64("msg-param-gift-months")?.unwrap_or(1),
+            recipient_display_name: fields.str("msg-param-recipient-display-name")?,
+            recipient_id: fields.u64("msg-param-recipient-id")?,
+            recipient_user_name: fields.str("msg-param-recipient-user-name")?,
+            sub_plan: fields.sub_plan()?,
+            sub_plan_name: fields.str("msg-param-sub-plan-name")?,
+        })
+    }
+}
+
+/// The fields of a `submysterygift` notice
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubMysteryGiftFields {
+    /// The number of subscriptions that were gifted
+    pub mass_gift_count: u64,
+    /// The subscription plan
+    pub sub_plan: SubPlan,
+}
+
+impl<'a> EventFields<'a> for SubMysteryGiftFields {
+    fn expected() -> Vec<NoticeType> {
+        vec![NoticeType::SubMysteryGift]
+    }
+
+    fn from_notice(msg: &'a UserNotice<'_>, kind: NoticeType) -> Result<Self, EventFieldsError> {
+        let fields = Fields { msg, kind };
+        Ok(Self {
+            mass_gift_count: fields.u64("msg-param-mass-gift-count")?,
+            sub_plan: fields.sub_plan()?,
+        })
+    }
+}
+
+/// The fields of a `giftpaidupgrade` notice
+#[derive(Debug, Clone, PartialEq)]
+pub struct GiftPaidUpgradeFields<'a> {
+    /// The login of the user who gifted the original subscription
+    pub sender_login: &'a str,
+    /// The display name of the user who gifted the original subscription
+    pub sender_name: &'a str,
+}
+
+impl<'a> EventFields<'a> for GiftPaidUpgradeFields<'a> {
+    fn expected() -> Vec<NoticeType> {
+        vec![NoticeType::GiftPaidUpgrade]
+    }
+
+    fn from_notice(msg: &'a UserNotice<'_>, kind: NoticeType) -> Result<Self, EventFieldsError> {
+        let fields = Fields { msg, kind };
+        Ok(Self {
+            sender_login: fields.str("msg-param-sender-login")?,
+            sender_name: fields.str("msg-param-sender-name")?,
+        })
+    }
+}
+
+/// The fields of a `raid` notice
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaidFields<'a> {
+    /// The display name of the raiding channel
+    pub display_name: &'a str,
+    /// The login of the raiding channel
+    pub login: &'a str,
+    /// The number of viewers of the raid
+    pub viewer_count: u64,
+}
+
+impl<'a> EventFields<'a> for RaidFields<'a> {
+    fn expected() -> Vec<NoticeType> {
+        vec![NoticeType::Raid]
+    }
+
+    fn from_notice(msg: &'a UserNotice<'_>, kind: NoticeType) -> Result<Self, EventFieldsError> {
+        let fields = Fields { msg, kind };
+        Ok(Self {
+            display_name: fields.str("msg-param-displayName")?,
+            login: fields.str("msg-param-login")?,
+            viewer_count: fields.u64("msg-param-viewerCount")?,
+        })
+    }
+}
+
+/// The fields of a `ritual` notice
+#[derive(Debug, Clone, PartialEq)]
+pub struct RitualFields<'a> {
+    /// The name of the ritual, e.g. `new_chatter`
+    pub ritual_name: &'a str,
+}
+
+impl<'a> EventFields<'a> for RitualFields<'a> {
+    fn expected() -> Vec<NoticeType> {
+        vec![NoticeType::Ritual]
+    }
+
+    fn from_notice(msg: &'a UserNotice<'_>, kind: NoticeType) -> Result<Self, EventFieldsError> {
+        let fields = Fields { msg, kind };
+        Ok(Self {
+            ritual_name: fields.str("msg-param-ritual-name")?,
+        })
+    }
+}
+
+/// The fields of a `bitsbadgetier` notice
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BitsBadgeTierFields {
+    /// The tier of the bits badge that was earned, e.g. `1000`
+    pub threshold: u64,
+}
+
+impl<'a> EventFields<'a> for BitsBadgeTierFields {
+    fn expected() -> Vec<NoticeType> {
+        vec![NoticeType::BitsBadgeTier]
+    }
+
+    fn from_notice(msg: &'a UserNotice<'_>, kind: NoticeType) -> Result<Self, EventFieldsError> {
+        let fields = Fields { msg, kind };
+        Ok(Self {
+            threshold: fields.u64("msg-param-threshold")?,
+        })
+    }
+}
+
+/// The fields of a `viewermilestone` notice
+#[derive(Debug, Clone, PartialEq)]
+pub struct ViewerMilestoneFields<'a> {
+    /// The kind of milestone, e.g. `watch-streak`
+    pub category: &'a str,
+    /// The value of the milestone, e.g. the number of streams watched in a row
+    pub value: u64,
+    /// The channel points the viewer was rewarded with, if any
+    pub copo_reward: Option<u64>,
+}
+
+impl<'a> ViewerMilestoneFields<'a> {
+    /// Whether this is a watch streak
+    pub fn is_watch_streak(&self) -> bool {
+        self.category == "watch-streak"
+    }
+}
+
+impl<'a> EventFields<'a> for ViewerMilestoneFields<'a> {
+    fn expected() -> Vec<NoticeType> {
+        vec![NoticeType::ViewerMilestone]
+    }
+
+    fn from_notice(msg: &'a UserNotice<'_>, kind: NoticeType) -> Result<Self, EventFieldsError> {
+        let fields = Fields { msg, kind };
+        Ok(Self {
+            category: fields.str("msg-param-category")?,
+            value: fields.u64("msg-param-value")?,
+            copo_reward: fields.opt_u64("msg-param-copoReward")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irc::parse;
+    use crate::FromIrcMessage as _;
+
+    fn notice(input: &str) -> UserNotice<'_> {
+        let msg = parse(input).next().unwrap().unwrap();
+        UserNotice::from_irc(msg).unwrap()
+    }
+
+    #[test]
+    fn raid() {
+        let input = "@msg-id=raid;msg-param-displayName=Museun;msg-param-login=museun;msg-param-viewerCount=42 :tmi.twitch.tv USERNOTICE #shaken_bot\r\n";
+        let msg = notice(input);
+
+        let raid = msg.try_event_fields::<RaidFields<'_>>().unwrap();
+        assert_eq!(
+            raid,
+            RaidFields {
+                display_name: "Museun",
+                login: "museun",
+                viewer_count: 42,
+            }
+        );
+
+        let err = msg.try_event_fields::<SubFields<'_>>().unwrap_err();
+        assert_eq!(
+            err,
+            EventFieldsError::WrongNoticeType {
+                expected: vec![NoticeType::Sub, NoticeType::Resub],
+                found: NoticeType::Raid,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "expected a 'sub' or 'resub' notice, got 'raid'"
+        );
+    }
+
+    #[test]
+    fn sub_gift() {
+        let input = "@msg-id=subgift;msg-param-months=3;msg-param-recipient-display-name=Museun;msg-param-recipient-id=23196011;msg-param-recipient-user-name=museun;msg-param-sub-plan=1000;msg-param-sub-plan-name=Channel\\sSubscription :tmi.twitch.tv USERNOTICE #shaken_bot\r\n";
+        let gift = notice(input);
+        let fields = gift.try_event_fields::<SubGiftFields<'_>>().unwrap();
+        assert_eq!(fields.months, 3);
+        assert_eq!(fields.gift_months, 1);
+        assert_eq!(fields.recipient_id, 23196011);
+        assert_eq!(fields.sub_plan, SubPlan::Tier1);
+    }
+
+    #[test]
+    fn viewer_milestone() {
+        let input = "@msg-id=viewermilestone;msg-param-category=watch-streak;msg-param-copoReward=450;msg-param-id=b9e4f7a2;msg-param-value=3 :tmi.twitch.tv USERNOTICE #shaken_bot :third stream in a row\r\n";
+        let msg = notice(input);
+        assert_eq!(msg.msg_id().unwrap().unwrap(), NoticeType::ViewerMilestone);
+
+        let milestone = msg.try_event_fields::<ViewerMilestoneFields<'_>>().unwrap();
+        assert_eq!(
+            milestone,
+            ViewerMilestoneFields {
+                category: "watch-streak",
+                value: 3,
+                copo_reward: Some(450),
+            }
+        );
+        assert!(milestone.is_watch_streak());
+        assert!(msg.validate_for_type().unwrap().is_valid());
+
+        let msg = notice("@msg-id=viewermilestone;msg-param-category=watch-streak;msg-param-value=5 :tmi.twitch.tv USERNOTICE #shaken_bot\r\n");
+        let milestone = msg.try_event_fields::<ViewerMilestoneFields<'_>>().unwrap();
+        assert_eq!(milestone.copo_reward, None);
+    }
+
+    #[test]
+    fn errors() {
+        let msg = notice(":tmi.twitch.tv USERNOTICE #museun\r\n");
+        assert_eq!(
+            msg.try_event_fields::<RitualFields<'_>>().unwrap_err(),
+            EventFieldsError::MissingMsgId
+        );
+
+        let msg =
+            notice("@msg-id=raid;msg-param-login=museun :tmi.twitch.tv USERNOTICE #museun\r\n");
+        assert_eq!(
+            msg.try_event_fields::<RaidFields<'_>>().unwrap_err(),
+            EventFieldsError::MissingField {
+                kind: NoticeType::Raid,
+                name: "msg-param-displayName"
+            }
+        );
+
+        let msg = notice(
+            "@msg-id=bitsbadgetier;msg-param-threshold=lots :tmi.twitch.tv USERNOTICE #museun\r\n",
+        );
+        assert_eq!(
+            msg.try_event_fields::<BitsBadgeTierFields>().unwrap_err(),
+            EventFieldsError::InvalidField {
+                kind: NoticeType::BitsBadgeTier,
+                name: "msg-param-threshold",
+                value: "lots".into()
+            }
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::twitch::{Attribution, AttributionVec, BadgeVec};
+use crate::twitch::{Attribution, AttributionVec, BadgeVec, UserType};
 use crate::{irc::*, twitch::*, IntoOwned, MaybeOwned, Validator};
 use std::str::FromStr;
 
@@ -73,6 +73,13 @@ impl<'a> GlobalUserState<'a> {
         self.display_name.as_deref()
     }
 
+    /// Your `user-type`, `Some(UserType::Normal)` for the empty tag of an ordinary user.
+    ///
+    /// This is `None` if the tag is missing
+    pub fn user_type(&self) -> Option<UserType> {
+        self.tags().get("user-type").map(UserType::from_tag)
+    }
+
     /// Your color, if set. Defaults to `white`
     pub fn color(&self) -> Color {
         self.color
@@ -97,14 +104,14 @@ impl<'a> FromIrcMessage<'a> for GlobalUserState<'a> {
             .map(MaybeOwned::into_owned);
 
         let display_name = tags
-            .get("display-name")
+            .get_non_empty("display-name")
             .map(MaybeOwned::from)
             .map(MaybeOwned::into_owned);
 
         let color = tags
             .get("color")
             .filter(|s| !s.is_empty())
-            .map(std::str::FromStr::from_str)
+            .map(FromStr::from_str)
             .transpose()
             .map_err(|err| MessageError::CannotParseTag {
                 name: "color".into(),
@@ -158,7 +165,7 @@ serde_struct!(GlobalUserState {
 mod tests {
     use super::*;
     use assert2::assert;
-    use maplit::hashset;
+    use maplit::btreeset;
 
     #[test]
     #[cfg(feature = "serde")]
@@ -170,23 +177,22 @@ mod tests {
 
     #[test]
     fn global_user_state_integrity() {
-        let input = "@badge-info=subscriber/8;badges=subscriber/6;color=#0D4200;display-name=dallas;emote-sets=0,33,50,237,793,2126,3517,4578,5569,9400,10337,12239;turbo=0;user-id=1337;user-type=admin :tmi.twitch.tv GLOBALUSERSTATE
-        \r\n";
+        let input = "@badge-info=subscriber/8;badges=subscriber/6;color=#0D4200;display-name=dallas;emote-sets=0,33,50,237,793,2126,3517,4578,5569,9400,10337,12239;turbo=0;user-id=1337;user-type=admin :tmi.twitch.tv GLOBALUSERSTATE\r\n";
         for msg in parse(input).map(|s| s.unwrap()) {
             let msg = GlobalUserState::from_irc(msg).unwrap();
-            assert!(msg.badge_info().unwrap().unwrap() == vec![BadgeInfo::NoTierSubscriber(8)]);
-            assert!(msg.badges().unwrap().unwrap() == vec![Badge::NoTierSubscriber(6)]);
+            let badge_info = BadgeVec::from_str(msg.tags().get("badge-info").unwrap()).unwrap();
+            assert!(*badge_info == vec![BadgeInfo::NoTierSubscriber(8)]);
+            assert!(*msg.badges() == vec![Badge::NoTierSubscriber(6)]);
             let color = "#0D4200".parse().unwrap();
             assert!(msg.color == color);
-            assert!(msg.color().unwrap().unwrap() == color);
+            assert!(msg.color() == color);
             assert!(msg.display_name().unwrap() == "dallas");
 
-            assert!(
-                msg.emote_sets().unwrap().unwrap()
-                    == hashset! {0,33,50,237,793,2126,3517,4578,5569,9400,10337,12239});
-            assert!(msg.turbo().unwrap().unwrap() == false);
-            assert!(msg.user_id().unwrap().unwrap() == 1337);
-            assert!(msg.user_type().unwrap() == "admin");
+            let emote_sets = msg.tags().get_parsed::<_, EmoteSet>("emote-sets").unwrap().unwrap();
+            assert!(*emote_sets == btreeset! {0,33,50,237,793,2126,3517,4578,5569,9400,10337,12239});
+            assert!(msg.tags().get_as_bool("turbo") == false);
+            assert!(msg.user_id().unwrap() == "1337");
+            assert!(msg.tags().get("user-type").unwrap() == "admin");
         }
     }
 
@@ -197,8 +203,9 @@ mod tests {
             let msg = GlobalUserState::from_irc(msg).unwrap();
             assert!(msg.user_id().is_none());
             assert!(msg.display_name().is_none());
-            assert_eq!(msg.color(), crate::twitch::Color::default());
-            assert_eq!(msg.emote_sets().unwrap(), hashset! {0});
+            assert!(msg.user_type().is_none());
+            assert_eq!(msg.color(), Color::default());
+            assert_eq!(msg.emote_sets(), vec!["0"]);
         }
     }
 
@@ -209,7 +216,8 @@ mod tests {
             let msg = GlobalUserState::from_irc(msg).unwrap();
             assert_eq!(msg.user_id().unwrap(), "241015868");
             assert_eq!(msg.display_name().unwrap(), "shaken_bot");
-            assert_eq!(msg.color(), crate::twitch::Color::default());
+            assert_eq!(msg.user_type(), Some(UserType::Normal));
+            assert_eq!(msg.color(), Color::default());
             assert_eq!(msg.emote_sets(), vec!["0"]);
         }
     }
@@ -0,0 +1,184 @@
+use super::Commands;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Hit and miss counts of an [Interner]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct InternerStats {
+    /// How many lookups returned an existing name
+    pub hits: u64,
+    /// How many lookups had to allocate a new name
+    pub misses: u64,
+}
+
+impl InternerStats {
+    /// The fraction of lookups that returned an existing name, from `0.0` to `1.0`
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            total => self.hits as f64 / total as f64,
+        }
+    }
+}
+
+/// An interner for channel and user names.
+///
+/// Long-running archivers see the same few channel and user names millions of
+/// times. This hands out a shared `Arc<str>` for each distinct name, so keeping
+/// a name around costs a reference count instead of a new `String`.
+///
+/// ```
+/// # use twitchchat::{FromIrcMessage as _, messages::{Commands, Interner}};
+/// # use std::sync::Arc;
+/// let parse = |input| {
+///     let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+///     Commands::from_irc(msg).unwrap()
+/// };
+///
+/// let mut interner = Interner::default();
+///
+/// let msg = parse(":museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n");
+/// let channel = interner.channel(&msg).unwrap();
+/// let user = interner.user(&msg).unwrap();
+/// assert_eq!(&*channel, "#museun");
+/// assert_eq!(&*user, "museun");
+///
+/// let msg = parse(":museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :world\r\n");
+/// assert!(Arc::ptr_eq(&channel, &interner.channel(&msg).unwrap()));
+///
+/// assert_eq!(interner.stats().hits, 1);
+/// assert_eq!(interner.stats().misses, 2);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Interner {
+    names: HashSet<Arc<str>>,
+    stats: InternerStats,
+}
+
+impl Interner {
+    /// Get the shared handle for this name, adding it if it hasn't been seen yet
+    pub fn intern(&mut self, name: &str) -> Arc<str> {
+        if let Some(name) = self.names.get(name) {
+            self.stats.hits += 1;
+            return Arc::clone(name);
+        }
+
+        self.stats.misses += 1;
+        let name: Arc<str> = name.into();
+        self.names.insert(Arc::clone(&name));
+        name
+    }
+
+    /// Get the shared handle for this name, without adding it
+    pub fn get(&self, name: &str) -> Option<Arc<str>> {
+        self.names.get(name).cloned()
+    }
+
+    /// The interned channel of this message, if it is channel-scoped
+    pub fn channel(&mut self, msg: &Commands<'_>) -> Option<Arc<str>> {
+        msg.channel().map(|channel| self.intern(channel))
+    }
+
+    /// The interned name of the user this message is from (or about, for a `CLEARCHAT` or `CLEARMSG`)
+    pub fn user(&mut self, msg: &Commands<'_>) -> Option<Arc<str>> {
+        let name = match msg {
+            Commands::Privmsg(msg) => msg.name(),
+            #[cfg(feature = "whispers")]
+            Commands::Whisper(msg) => msg.name(),
+            Commands::Join(msg) => msg.name(),
+            Commands::Part(msg) => msg.name(),
+            #[cfg(feature = "usernotice")]
+            Commands::UserNotice(msg) => msg.login()?,
+            #[cfg(feature = "moderation")]
+            Commands::ClearChat(msg) => msg.name()?,
+            #[cfg(feature = "moderation")]
+            Commands::ClearMsg(msg) => msg.login()?,
+            _ => return None,
+        };
+        Some(self.intern(name))
+    }
+
+    /// The hit and miss counts of the lookups so far
+    pub fn stats(&self) -> InternerStats {
+        self.stats
+    }
+
+    /// The number of distinct names
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Determines whether no names have been interned
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Drop the names that are no longer used outside of the interner.
+    ///
+    /// This returns how many names were dropped
+    pub fn prune(&mut self) -> usize {
+        let len = self.names.len();
+        self.names.retain(|name| Arc::strong_count(name) > 1);
+        len - self.names.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "moderation")]
+    fn commands(input: &str) -> Commands<'_> {
+        use crate::FromIrcMessage as _;
+        let msg = crate::irc::parse(input).next().unwrap().unwrap();
+        Commands::from_irc(msg).unwrap()
+    }
+
+    #[test]
+    fn intern() {
+        let mut interner = Interner::default();
+        assert_eq!(interner.stats().hit_rate(), 0.0);
+
+        let museun = interner.intern("museun");
+        let shaken_bot = interner.intern("shaken_bot");
+        assert!(Arc::ptr_eq(&museun, &interner.intern("museun")));
+        assert!(!Arc::ptr_eq(&museun, &shaken_bot));
+        assert_eq!(interner.len(), 2);
+
+        assert_eq!(interner.stats(), InternerStats { hits: 1, misses: 2 });
+
+        // lookups without adding aren't counted
+        assert!(interner.get("foo").is_none());
+        assert!(Arc::ptr_eq(&museun, &interner.get("museun").unwrap()));
+        assert_eq!(interner.stats().hits + interner.stats().misses, 3);
+
+        drop(shaken_bot);
+        assert_eq!(interner.prune(), 1);
+        assert_eq!(interner.len(), 1);
+        assert!(interner.get("shaken_bot").is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "moderation")]
+    fn messages() {
+        let mut interner = Interner::default();
+
+        let join = commands(":museun!museun@museun.tmi.twitch.tv JOIN #museun\r\n");
+        assert_eq!(interner.user(&join).as_deref(), Some("museun"));
+        assert_eq!(interner.channel(&join).as_deref(), Some("#museun"));
+
+        let clear = commands(":tmi.twitch.tv CLEARCHAT #museun :shaken_bot\r\n");
+        assert_eq!(interner.user(&clear).as_deref(), Some("shaken_bot"));
+        assert_eq!(interner.channel(&clear).as_deref(), Some("#museun"));
+
+        let clear = commands(":tmi.twitch.tv CLEARCHAT #museun\r\n");
+        assert!(interner.user(&clear).is_none());
+
+        let ping = commands("PING :1234567890\r\n");
+        assert!(interner.user(&ping).is_none());
+        assert!(interner.channel(&ping).is_none());
+
+        assert_eq!(interner.len(), 3);
+        assert_eq!(interner.stats().hits, 1);
+    }
+}
@@ -0,0 +1,231 @@
+use super::Commands;
+use crate::{irc::*, MaybeOwned, MaybeOwnedIndex, Validator};
+use std::collections::HashMap;
+
+/// A chunk of the users on a channel -- `353`
+///
+/// With the membership capability, Twitch sends these after you join a
+/// channel. A large list is split over several of these, and is finished by an
+/// [EndOfNames]. See [NamesCollector] for putting them back together.
+#[derive(Clone, PartialEq)]
+pub struct NamesReply<'a> {
+    raw: MaybeOwned<'a>,
+    channel: MaybeOwnedIndex,
+    data: Option<MaybeOwnedIndex>,
+}
+
+impl<'a> NamesReply<'a> {
+    raw!();
+    str_field!(
+        /// The channel the users are on
+        channel
+    );
+
+    opt_str_field!(
+        /// The space-separated users in this chunk
+        data
+    );
+
+    /// The users in this chunk
+    pub fn users(&self) -> impl Iterator<Item = &str> + '_ {
+        self.data().into_iter().flat_map(str::split_whitespace)
+    }
+}
+
+impl<'a> FromIrcMessage<'a> for NamesReply<'a> {
+    type Error = MessageError;
+
+    fn from_irc(msg: IrcMessage<'a>) -> Result<Self, Self::Error> {
+        msg.expect_command(IrcMessage::NAMES_REPLY)?;
+
+        let this = Self {
+            // the args are: our name, the channel type ('=') and the channel
+            channel: msg.expect_arg_index(2)?,
+            data: msg.data,
+            raw: msg.raw,
+        };
+
+        Ok(this)
+    }
+
+    into_inner_raw!();
+}
+
+into_owned!(NamesReply { raw, channel, data });
+impl_custom_debug!(NamesReply { raw, channel, data });
+serde_struct!(NamesReply { raw, channel, data });
+
+/// The end of the user list of a channel -- `366`
+#[derive(Clone, PartialEq)]
+pub struct EndOfNames<'a> {
+    raw: MaybeOwned<'a>,
+    channel: MaybeOwnedIndex,
+}
+
+impl<'a> EndOfNames<'a> {
+    raw!();
+    str_field!(
+        /// The channel the list was for
+        channel
+    );
+}
+
+impl<'a> FromIrcMessage<'a> for EndOfNames<'a> {
+    type Error = MessageError;
+
+    fn from_irc(msg: IrcMessage<'a>) -> Result<Self, Self::Error> {
+        msg.expect_command(IrcMessage::END_OF_NAMES)?;
+
+        let this = Self {
+            // the args are: our name and the channel
+            channel: msg.expect_arg_index(1)?,
+            raw: msg.raw,
+        };
+
+        Ok(this)
+    }
+
+    into_inner_raw!();
+}
+
+into_owned!(EndOfNames { raw, channel });
+impl_custom_debug!(EndOfNames { raw, channel });
+serde_struct!(EndOfNames { raw, channel });
+
+/// The complete list of users on a channel, put together by a [NamesCollector]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Names {
+    /// The channel the users are on
+    pub channel: String,
+    /// The users, in the order Twitch sent them
+    pub users: Vec<String>,
+}
+
+/// Collects the [NamesReply] chunks of each channel until the [EndOfNames]
+///
+/// ```
+/// # use twitchchat::{FromIrcMessage as _, messages::{Commands, NamesCollector}};
+/// let parse = |input| {
+///     let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+///     Commands::from_irc(msg).unwrap()
+/// };
+///
+/// let mut collector = NamesCollector::default();
+/// let msg = parse(":museun.tmi.twitch.tv 353 museun = #museun :museun shaken_bot\r\n");
+/// assert!(collector.observe(&msg).is_none());
+///
+/// let msg = parse(":museun.tmi.twitch.tv 353 museun = #museun :foo\r\n");
+/// assert!(collector.observe(&msg).is_none());
+///
+/// let msg = parse(":museun.tmi.twitch.tv 366 museun #museun :End of /NAMES list\r\n");
+/// let names = collector.observe(&msg).unwrap();
+/// assert_eq!(names.channel, "#museun");
+/// assert_eq!(names.users, vec!["museun", "shaken_bot", "foo"]);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NamesCollector {
+    pending: HashMap<String, Vec<String>>,
+}
+
+impl NamesCollector {
+    /// Add this message to the list of its channel.
+    ///
+    /// This returns the complete list when the message was an [EndOfNames]
+    pub fn observe(&mut self, msg: &Commands<'_>) -> Option<Names> {
+        match msg {
+            Commands::NamesReply(msg) => {
+                let users = msg.users().map(ToString::to_string);
+                match self.pending.get_mut(msg.channel()) {
+                    Some(pending) => pending.extend(users),
+                    None => {
+                        self.pending
+                            .insert(msg.channel().to_string(), users.collect());
+                    }
+                }
+                None
+            }
+            Commands::EndOfNames(msg) => Some(Names {
+                channel: msg.channel().to_string(),
+                users: self.pending.remove(msg.channel()).unwrap_or_default(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn names_serde() {
+        let input = ":museun.tmi.twitch.tv 353 museun = #museun :museun shaken_bot\r\n";
+        crate::serde::round_trip_json::<NamesReply>(input);
+        crate::serde::round_trip_rmp::<NamesReply>(input);
+
+        let input = ":museun.tmi.twitch.tv 366 museun #museun :End of /NAMES list\r\n";
+        crate::serde::round_trip_json::<EndOfNames>(input);
+        crate::serde::round_trip_rmp::<EndOfNames>(input);
+    }
+
+    #[test]
+    fn names_reply() {
+        let input = ":museun.tmi.twitch.tv 353 museun = #museun :museun shaken_bot\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = NamesReply::from_irc(msg).unwrap();
+            assert_eq!(msg.channel(), "#museun");
+            assert_eq!(
+                msg.users().collect::<Vec<_>>(),
+                vec!["museun", "shaken_bot"]
+            );
+        }
+
+        let input = ":museun.tmi.twitch.tv 353 museun = #museun\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = NamesReply::from_irc(msg).unwrap();
+            assert_eq!(msg.users().count(), 0);
+        }
+    }
+
+    #[test]
+    fn end_of_names() {
+        let input = ":museun.tmi.twitch.tv 366 museun #museun :End of /NAMES list\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = EndOfNames::from_irc(msg).unwrap();
+            assert_eq!(msg.channel(), "#museun");
+        }
+    }
+
+    #[test]
+    fn collector() {
+        let input = ":museun.tmi.twitch.tv 353 museun = #museun :museun\r\n\
+                     :museun.tmi.twitch.tv 353 museun = #other :foo\r\n\
+                     :museun.tmi.twitch.tv 353 museun = #museun :shaken_bot\r\n\
+                     :museun.tmi.twitch.tv 366 museun #museun :End of /NAMES list\r\n\
+                     :museun.tmi.twitch.tv 366 museun #empty :End of /NAMES list\r\n";
+
+        let mut collector = NamesCollector::default();
+        let names = parse(input)
+            .map(|s| s.unwrap())
+            .map(|msg| Commands::from_irc(msg).unwrap())
+            .filter_map(|msg| collector.observe(&msg))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            names,
+            vec![
+                Names {
+                    channel: "#museun".into(),
+                    users: vec!["museun".into(), "shaken_bot".into()]
+                },
+                Names {
+                    channel: "#empty".into(),
+                    users: vec![]
+                },
+            ]
+        );
+        assert_eq!(collector.pending.len(), 1);
+    }
+}
@@ -75,7 +75,7 @@ serde_struct!(Notice {
 
 /// These tags apply to both the NOTICE (Twitch Commands) and NOTICE (Twitch Chat Rooms) commands.
 #[non_exhaustive]
-#[derive(Debug, Clone, PartialEq, Hash)]
+#[derive(Debug, Clone, PartialEq, Hash, Eq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub enum MessageId<'a> {
     /// <user> is already banned in this channel.
@@ -584,6 +584,491 @@ impl<'a> MessageId<'a> {
     }
 }
 
+/// A coarse category of a [MessageId], e.g. for routing notices to different parts of a UI
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum MessageIdCategory {
+    /// A command was successful
+    Success,
+    /// A room mode was changed, or was already set
+    RoomMode,
+    /// A moderation command could not be applied to its target
+    ModerationError,
+    /// A chat message was not sent
+    MessageRejected,
+    /// You are sending messages (or commands) too quickly
+    RateLimited,
+    /// You, or the channel, are banned or suspended
+    Banned,
+    /// You don't have permission to do that
+    PermissionDenied,
+    /// A command was used incorrectly
+    Usage,
+    /// Something about host mode
+    Host,
+    /// Something about a raid
+    Raid,
+    /// Something about whispers
+    Whisper,
+    /// Informational, e.g. the list of moderators
+    Info,
+    /// The message id is not known
+    Unknown,
+}
+
+impl MessageIdCategory {
+    /// A short, stable, machine-readable name for this category
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::RoomMode => "room_mode",
+            Self::ModerationError => "moderation_error",
+            Self::MessageRejected => "message_rejected",
+            Self::RateLimited => "rate_limited",
+            Self::Banned => "banned",
+            Self::PermissionDenied => "permission_denied",
+            Self::Usage => "usage",
+            Self::Host => "host",
+            Self::Raid => "raid",
+            Self::Whisper => "whisper",
+            Self::Info => "info",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    /// Whether notices of this category are errors that should be shown to the user
+    pub fn is_error(self) -> bool {
+        matches!(
+            self,
+            Self::ModerationError
+                | Self::MessageRejected
+                | Self::RateLimited
+                | Self::Banned
+                | Self::PermissionDenied
+                | Self::Usage
+        )
+    }
+}
+
+impl<'a> MessageId<'a> {
+    /// The category of this message id
+    ///
+    /// ```
+    /// # use twitchchat::messages::{MessageId, MessageIdCategory};
+    /// assert_eq!(MessageId::MsgRatelimit.category(), MessageIdCategory::RateLimited);
+    /// assert_eq!(MessageId::SlowOn.category(), MessageIdCategory::RoomMode);
+    /// assert_eq!(MessageId::Unknown("foo").category(), MessageIdCategory::Unknown);
+    /// ```
+    pub fn category(&self) -> MessageIdCategory {
+        use MessageId::*;
+        use MessageIdCategory as C;
+        match self {
+            BanSuccess | ColorChanged | CommercialSuccess | DeleteMessageSuccess | ModSuccess
+            | TimeoutSuccess | UnbanSuccess | UnmodSuccess | UntimeoutSuccess => C::Success,
+
+            AlreadyEmoteOnlyOff | AlreadyEmoteOnlyOn | AlreadyR9kOff | AlreadyR9kOn
+            | AlreadySubsOff | AlreadySubsOn | EmoteOnlyOff | EmoteOnlyOn | FollowersOff
+            | FollowersOn | FollowersOnZero | R9kOff | R9kOn | SlowOff | SlowOn | SubsOff
+            | SubsOn => C::RoomMode,
+
+            AlreadyBanned
+            | BadBanAdmin
+            | BadBanAnon
+            | BadBanBroadcaster
+            | BadBanGlobalMod
+            | BadBanMod
+            | BadBanSelf
+            | BadBanStaff
+            | BadCommercialError
+            | BadDeleteMessageBroadcaster
+            | BadDeleteMessageMod
+            | BadMarkerClient
+            | BadModBanned
+            | BadModMod
+            | BadSlowDuration
+            | BadTimeoutAdmin
+            | BadTimeoutAnon
+            | BadTimeoutBroadcaster
+            | BadTimeoutDuration
+            | BadTimeoutGlobalMod
+            | BadTimeoutMod
+            | BadTimeoutSelf
+            | BadTimeoutStaff
+            | BadUnbanNoBan
+            | BadUnmodMod
+            | InvalidUser
+            | TimeoutNoTimeout
+            | UntimeoutBanned => C::ModerationError,
+
+            MsgBadCharacters
+            | MsgChannelBlocked
+            | MsgDuplicate
+            | MsgEmoteonly
+            | MsgFacebook
+            | MsgFollowersonly
+            | MsgFollowersonlyFollowed
+            | MsgFollowersonlyZero
+            | MsgR9k
+            | MsgRejected
+            | MsgRejectedMandatory
+            | MsgRoomNotFound
+            | MsgSlowmode
+            | MsgSubsonly
+            | MsgVerifiedEmail => C::MessageRejected,
+
+            MsgRatelimit | BadHostRateExceeded => C::RateLimited,
+
+            MsgBanned | MsgChannelSuspended | MsgSuspended | MsgTimedout | TosBan => C::Banned,
+
+            NoPermission | TurboOnlyColor | UnsupportedChatroomsCmd => C::PermissionDenied,
+
+            UnrecognizedCmd | UsageBan | UsageClear | UsageColor | UsageCommercial
+            | UsageDisconnect | UsageEmoteOnlyOff | UsageEmoteOnlyOn | UsageFollowersOff
+            | UsageFollowersOn | UsageHelp | UsageHost | UsageMarker | UsageMe | UsageMod
+            | UsageMods | UsageR9kOff | UsageR9kOn | UsageRaid | UsageSlowOff | UsageSlowOn
+            | UsageSubsOff | UsageSubsOn | UsageTimeout | UsageUnban | UsageUnhost | UsageUnmod
+            | UsageUnraid | UsageUntimeout => C::Usage,
+
+            BadHostError
+            | BadHostHosting
+            | BadHostRejected
+            | BadHostSelf
+            | BadUnhostError
+            | HostOff
+            | HostOn
+            | HostSuccess
+            | HostSuccessViewers
+            | HostTargetWentOffline
+            | HostsRemaining
+            | NotHosting => C::Host,
+
+            RaidErrorAlreadyRaiding
+            | RaidErrorForbidden
+            | RaidErrorSelf
+            | RaidErrorTooManyViewers
+            | RaidErrorUnexpected
+            | RaidNoticeMature
+            | RaidNoticeRestrictedChat
+            | UnraidErrorNoActiveRaid
+            | UnraidErrorUnexpected
+            | UnraidSuccess => C::Raid,
+
+            WhisperBanned
+            | WhisperBannedRecipient
+            | WhisperInvalidArgs
+            | WhisperInvalidLogin
+            | WhisperInvalidSelf
+            | WhisperLimitPerMin
+            | WhisperLimitPerSec
+            | WhisperRestricted
+            | WhisperRestrictedRecipient => C::Whisper,
+
+            CmdsAvailable | NoHelp | NoMods | RoomMods => C::Info,
+
+            Unknown(..) => C::Unknown,
+        }
+    }
+
+    /// A short, human-friendly description of this message id
+    ///
+    /// Unlike the message of the [Notice], this doesn't contain any user names,
+    /// channels or durations, so it can be used as-is (or as a key for translations).
+    ///
+    /// ```
+    /// # use twitchchat::messages::MessageId;
+    /// assert_eq!(
+    ///     MessageId::MsgRatelimit.description(),
+    ///     "You are sending messages too quickly."
+    /// );
+    /// ```
+    pub fn description(&self) -> &'static str {
+        use MessageId::*;
+        match self {
+            AlreadyBanned => "That user is already banned.",
+            AlreadyEmoteOnlyOff => "This room is not in emote-only mode.",
+            AlreadyEmoteOnlyOn => "This room is already in emote-only mode.",
+            AlreadyR9kOff => "This room is not in unique-chat mode.",
+            AlreadyR9kOn => "This room is already in unique-chat mode.",
+            AlreadySubsOff => "This room is not in subscribers-only mode.",
+            AlreadySubsOn => "This room is already in subscribers-only mode.",
+            BadBanAdmin => "You cannot ban an admin.",
+            BadBanAnon => "You cannot ban anonymous users.",
+            BadBanBroadcaster => "You cannot ban the broadcaster.",
+            BadBanGlobalMod => "You cannot ban a global moderator.",
+            BadBanMod => "You cannot ban a moderator.",
+            BadBanSelf => "You cannot ban yourself.",
+            BadBanStaff => "You cannot ban a staff member.",
+            BadCommercialError => "The commercial could not be started.",
+            BadDeleteMessageBroadcaster => "You cannot delete the broadcaster's messages.",
+            BadDeleteMessageMod => "You cannot delete a moderator's messages.",
+            BadHostError => "The channel could not be hosted.",
+            BadHostHosting => "You are already hosting that channel.",
+            BadHostRateExceeded => "You are changing the hosted channel too often.",
+            BadHostRejected => "That channel cannot be hosted.",
+            BadHostSelf => "A channel cannot host itself.",
+            BadMarkerClient => "Stream markers cannot be added from this client.",
+            BadModBanned => "A banned user cannot be made a moderator.",
+            BadModMod => "That user is already a moderator.",
+            BadSlowDuration => "That slow mode delay is too long.",
+            BadTimeoutAdmin => "You cannot timeout an admin.",
+            BadTimeoutAnon => "You cannot timeout anonymous users.",
+            BadTimeoutBroadcaster => "You cannot timeout the broadcaster.",
+            BadTimeoutDuration => "That timeout is too long.",
+            BadTimeoutGlobalMod => "You cannot timeout a global moderator.",
+            BadTimeoutMod => "You cannot timeout a moderator.",
+            BadTimeoutSelf => "You cannot timeout yourself.",
+            BadTimeoutStaff => "You cannot timeout a staff member.",
+            BadUnbanNoBan => "That user is not banned.",
+            BadUnhostError => "Host mode could not be exited.",
+            BadUnmodMod => "That user is not a moderator.",
+            BanSuccess => "The user has been banned.",
+            CmdsAvailable => "These are the commands available to you.",
+            ColorChanged => "Your color has been changed.",
+            CommercialSuccess => "The commercial has been started.",
+            DeleteMessageSuccess => "The message has been deleted.",
+            EmoteOnlyOff => "This room is no longer in emote-only mode.",
+            EmoteOnlyOn => "This room is now in emote-only mode.",
+            FollowersOff => "This room is no longer in followers-only mode.",
+            FollowersOn | FollowersOnZero => "This room is now in followers-only mode.",
+            HostOff => "Exited host mode.",
+            HostOn => "Now hosting a channel.",
+            HostSuccess | HostSuccessViewers => "Someone is now hosting you.",
+            HostTargetWentOffline => "The hosted channel went offline.",
+            HostsRemaining => "These are the host commands remaining this half hour.",
+            InvalidUser => "That user does not exist.",
+            ModSuccess => "The user is now a moderator.",
+            MsgBanned => "You are banned from this channel.",
+            MsgBadCharacters => "Your message contains characters that cannot be sent.",
+            MsgChannelBlocked => "Your account is not in good standing in this channel.",
+            MsgChannelSuspended => "This channel has been suspended.",
+            MsgDuplicate => "Your message is identical to the previous one.",
+            MsgEmoteonly => "This room is in emote-only mode.",
+            MsgFacebook => "This channel requires a connected Facebook account.",
+            MsgFollowersonly | MsgFollowersonlyFollowed | MsgFollowersonlyZero => {
+                "This room is in followers-only mode."
+            }
+            MsgR9k => "This room is in unique-chat mode and your message is not unique.",
+            MsgRatelimit => "You are sending messages too quickly.",
+            MsgRejected => "Your message is being checked by the moderators.",
+            MsgRejectedMandatory => {
+                "Your message conflicts with the channel's moderation settings."
+            }
+            MsgRoomNotFound => "The room was not found.",
+            MsgSlowmode => "This room is in slow mode.",
+            MsgSubsonly => "This room is in subscribers-only mode.",
+            MsgSuspended => "Your account has been suspended.",
+            MsgTimedout => "You are timed out in this channel.",
+            MsgVerifiedEmail => "This room requires a verified email address.",
+            NoHelp => "No help is available.",
+            NoMods => "This channel has no moderators.",
+            NotHosting => "No channel is being hosted.",
+            NoPermission => "You don't have permission to do that.",
+            R9kOff => "This room is no longer in unique-chat mode.",
+            R9kOn => "This room is now in unique-chat mode.",
+            RaidErrorAlreadyRaiding => "You already have a raid in progress.",
+            RaidErrorForbidden => "You cannot raid this channel.",
+            RaidErrorSelf => "A channel cannot raid itself.",
+            RaidErrorTooManyViewers => "You have too many viewers to raid.",
+            RaidErrorUnexpected => "The raid could not be started.",
+            RaidNoticeMature => "The raided channel is intended for mature audiences.",
+            RaidNoticeRestrictedChat => "The raided channel has restricted chat.",
+            RoomMods => "These are the moderators of this channel.",
+            SlowOff => "This room is no longer in slow mode.",
+            SlowOn => "This room is now in slow mode.",
+            SubsOff => "This room is no longer in subscribers-only mode.",
+            SubsOn => "This room is now in subscribers-only mode.",
+            TimeoutNoTimeout => "That user is not timed out.",
+            TimeoutSuccess => "The user has been timed out.",
+            TosBan => "This channel has been closed for Terms of Service violations.",
+            TurboOnlyColor => "Only Turbo users can use custom colors.",
+            UnbanSuccess => "The user is no longer banned.",
+            UnmodSuccess => "The user is no longer a moderator.",
+            UnraidErrorNoActiveRaid => "You don't have an active raid.",
+            UnraidErrorUnexpected => "The raid could not be cancelled.",
+            UnraidSuccess => "The raid has been cancelled.",
+            UnrecognizedCmd => "That command is not recognized.",
+            UnsupportedChatroomsCmd => "That command cannot be used in a chat room.",
+            UntimeoutBanned => "That user is permanently banned.",
+            UntimeoutSuccess => "The user is no longer timed out.",
+            UsageBan | UsageClear | UsageColor | UsageCommercial | UsageDisconnect
+            | UsageEmoteOnlyOff | UsageEmoteOnlyOn | UsageFollowersOff | UsageFollowersOn
+            | UsageHelp | UsageHost | UsageMarker | UsageMe | UsageMod | UsageMods
+            | UsageR9kOff | UsageR9kOn | UsageRaid | UsageSlowOff | UsageSlowOn | UsageSubsOff
+            | UsageSubsOn | UsageTimeout | UsageUnban | UsageUnhost | UsageUnmod | UsageUnraid
+            | UsageUntimeout => "That command was used incorrectly.",
+            WhisperBanned => "You are banned from sending whispers.",
+            WhisperBannedRecipient => "That user is banned from receiving whispers.",
+            WhisperInvalidArgs => "That whisper is missing a user or a message.",
+            WhisperInvalidLogin => "That user does not exist.",
+            WhisperInvalidSelf => "You cannot whisper yourself.",
+            WhisperLimitPerMin | WhisperLimitPerSec => "You are sending whispers too quickly.",
+            WhisperRestricted => "Your settings prevent you from sending this whisper.",
+            WhisperRestrictedRecipient => {
+                "That user's settings prevent them from receiving whispers."
+            }
+            Unknown(..) => "Unknown notice.",
+        }
+    }
+
+    /// The `msg-id` this was parsed from
+    ///
+    /// ```
+    /// # use twitchchat::messages::MessageId;
+    /// assert_eq!(MessageId::MsgBanned.as_str(), "msg_banned");
+    /// assert_eq!(MessageId::Unknown("foo").as_str(), "foo");
+    /// ```
+    pub fn as_str(&self) -> &'a str {
+        use MessageId::*;
+        match self {
+            AlreadyBanned => "already_banned",
+            AlreadyEmoteOnlyOff => "already_emote_only_off",
+            AlreadyEmoteOnlyOn => "already_emote_only_on",
+            AlreadyR9kOff => "already_r9k_off",
+            AlreadyR9kOn => "already_r9k_on",
+            AlreadySubsOff => "already_subs_off",
+            AlreadySubsOn => "already_subs_on",
+            BadBanAdmin => "bad_ban_admin",
+            BadBanAnon => "bad_ban_anon",
+            BadBanBroadcaster => "bad_ban_broadcaster",
+            BadBanGlobalMod => "bad_ban_global_mod",
+            BadBanMod => "bad_ban_mod",
+            BadBanSelf => "bad_ban_self",
+            BadBanStaff => "bad_ban_staff",
+            BadCommercialError => "bad_commercial_error",
+            BadDeleteMessageBroadcaster => "bad_delete_message_broadcaster",
+            BadDeleteMessageMod => "bad_delete_message_mod",
+            BadHostError => "bad_host_error",
+            BadHostHosting => "bad_host_hosting",
+            BadHostRateExceeded => "bad_host_rate_exceeded",
+            BadHostRejected => "bad_host_rejected",
+            BadHostSelf => "bad_host_self",
+            BadMarkerClient => "bad_marker_client",
+            BadModBanned => "bad_mod_banned",
+            BadModMod => "bad_mod_mod",
+            BadSlowDuration => "bad_slow_duration",
+            BadTimeoutAdmin => "bad_timeout_admin",
+            BadTimeoutAnon => "bad_timeout_anon",
+            BadTimeoutBroadcaster => "bad_timeout_broadcaster",
+            BadTimeoutDuration => "bad_timeout_duration",
+            BadTimeoutGlobalMod => "bad_timeout_global_mod",
+            BadTimeoutMod => "bad_timeout_mod",
+            BadTimeoutSelf => "bad_timeout_self",
+            BadTimeoutStaff => "bad_timeout_staff",
+            BadUnbanNoBan => "bad_unban_no_ban",
+            BadUnhostError => "bad_unhost_error",
+            BadUnmodMod => "bad_unmod_mod",
+            BanSuccess => "ban_success",
+            CmdsAvailable => "cmds_available",
+            ColorChanged => "color_changed",
+            CommercialSuccess => "commercial_success",
+            DeleteMessageSuccess => "delete_message_success",
+            EmoteOnlyOff => "emote_only_off",
+            EmoteOnlyOn => "emote_only_on",
+            FollowersOff => "followers_off",
+            FollowersOn => "followers_on",
+            FollowersOnZero => "followers_on_zero",
+            HostOff => "host_off",
+            HostOn => "host_on",
+            HostSuccess => "host_success",
+            HostSuccessViewers => "host_success_viewers",
+            HostTargetWentOffline => "host_target_went_offline",
+            HostsRemaining => "hosts_remaining",
+            InvalidUser => "invalid_user",
+            ModSuccess => "mod_success",
+            MsgBanned => "msg_banned",
+            MsgBadCharacters => "msg_bad_characters",
+            MsgChannelBlocked => "msg_channel_blocked",
+            MsgChannelSuspended => "msg_channel_suspended",
+            MsgDuplicate => "msg_duplicate",
+            MsgEmoteonly => "msg_emoteonly",
+            MsgFacebook => "msg_facebook",
+            MsgFollowersonly => "msg_followersonly",
+            MsgFollowersonlyFollowed => "msg_followersonly_followed",
+            MsgFollowersonlyZero => "msg_followersonly_zero",
+            MsgR9k => "msg_r9k",
+            MsgRatelimit => "msg_ratelimit",
+            MsgRejected => "msg_rejected",
+            MsgRejectedMandatory => "msg_rejected_mandatory",
+            MsgRoomNotFound => "msg_room_not_found",
+            MsgSlowmode => "msg_slowmode",
+            MsgSubsonly => "msg_subsonly",
+            MsgSuspended => "msg_suspended",
+            MsgTimedout => "msg_timedout",
+            MsgVerifiedEmail => "msg_verified_email",
+            NoHelp => "no_help",
+            NoMods => "no_mods",
+            NotHosting => "not_hosting",
+            NoPermission => "no_permission",
+            R9kOff => "r9k_off",
+            R9kOn => "r9k_on",
+            RaidErrorAlreadyRaiding => "raid_error_already_raiding",
+            RaidErrorForbidden => "raid_error_forbidden",
+            RaidErrorSelf => "raid_error_self",
+            RaidErrorTooManyViewers => "raid_error_too_many_viewers",
+            RaidErrorUnexpected => "raid_error_unexpected",
+            RaidNoticeMature => "raid_notice_mature",
+            RaidNoticeRestrictedChat => "raid_notice_restricted_chat",
+            RoomMods => "room_mods",
+            SlowOff => "slow_off",
+            SlowOn => "slow_on",
+            SubsOff => "subs_off",
+            SubsOn => "subs_on",
+            TimeoutNoTimeout => "timeout_no_timeout",
+            TimeoutSuccess => "timeout_success",
+            TosBan => "tos_ban",
+            TurboOnlyColor => "turbo_only_color",
+            UnbanSuccess => "unban_success",
+            UnmodSuccess => "unmod_success",
+            UnraidErrorNoActiveRaid => "unraid_error_no_active_raid",
+            UnraidErrorUnexpected => "unraid_error_unexpected",
+            UnraidSuccess => "unraid_success",
+            UnrecognizedCmd => "unrecognized_cmd",
+            UnsupportedChatroomsCmd => "unsupported_chatrooms_cmd",
+            UntimeoutBanned => "untimeout_banned",
+            UntimeoutSuccess => "untimeout_success",
+            UsageBan => "usage_ban",
+            UsageClear => "usage_clear",
+            UsageColor => "usage_color",
+            UsageCommercial => "usage_commercial",
+            UsageDisconnect => "usage_disconnect",
+            UsageEmoteOnlyOff => "usage_emote_only_off",
+            UsageEmoteOnlyOn => "usage_emote_only_on",
+            UsageFollowersOff => "usage_followers_off",
+            UsageFollowersOn => "usage_followers_on",
+            UsageHelp => "usage_help",
+            UsageHost => "usage_host",
+            UsageMarker => "usage_marker",
+            UsageMe => "usage_me",
+            UsageMod => "usage_mod",
+            UsageMods => "usage_mods",
+            UsageR9kOff => "usage_r9k_off",
+            UsageR9kOn => "usage_r9k_on",
+            UsageRaid => "usage_raid",
+            UsageSlowOff => "usage_slow_off",
+            UsageSlowOn => "usage_slow_on",
+            UsageSubsOff => "usage_subs_off",
+            UsageSubsOn => "usage_subs_on",
+            UsageTimeout => "usage_timeout",
+            UsageUnban => "usage_unban",
+            UsageUnhost => "usage_unhost",
+            UsageUnmod => "usage_unmod",
+            UsageUnraid => "usage_unraid",
+            UsageUntimeout => "usage_untimeout",
+            WhisperBanned => "whisper_banned",
+            WhisperBannedRecipient => "whisper_banned_recipient",
+            WhisperInvalidArgs => "whisper_invalid_args",
+            WhisperInvalidLogin => "whisper_invalid_login",
+            WhisperInvalidSelf => "whisper_invalid_self",
+            WhisperLimitPerMin => "whisper_limit_per_min",
+            WhisperLimitPerSec => "whisper_limit_per_sec",
+            WhisperRestricted => "whisper_restricted",
+            WhisperRestrictedRecipient => "whisper_restricted_recipient",
+            Unknown(id) => id,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -617,4 +1102,54 @@ mod tests {
             assert!(msg.msg_id().unwrap() == MessageId::SlowOff);
         }
     }
+
+    #[test]
+    fn notice_description() {
+        let input = "@msg-id=msg_ratelimit :tmi.twitch.tv NOTICE #museun :Your message was not sent because you are sending messages too quickly.\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Notice::from_irc(msg).unwrap();
+            let id = msg.msg_id().unwrap();
+            assert!(id.category() == MessageIdCategory::RateLimited);
+            assert!(id.category().is_error());
+            assert!(id.category().as_str() == "rate_limited");
+            assert!(id.description() == "You are sending messages too quickly.");
+        }
+
+        let tests = &[
+            ("ban_success", MessageIdCategory::Success),
+            ("already_r9k_on", MessageIdCategory::RoomMode),
+            ("bad_timeout_self", MessageIdCategory::ModerationError),
+            ("msg_subsonly", MessageIdCategory::MessageRejected),
+            ("msg_timedout", MessageIdCategory::Banned),
+            ("no_permission", MessageIdCategory::PermissionDenied),
+            ("usage_me", MessageIdCategory::Usage),
+            ("host_on", MessageIdCategory::Host),
+            ("unraid_success", MessageIdCategory::Raid),
+            ("whisper_invalid_self", MessageIdCategory::Whisper),
+            ("room_mods", MessageIdCategory::Info),
+            ("something_new", MessageIdCategory::Unknown),
+        ];
+        for (input, category) in tests {
+            let id = MessageId::parse(input);
+            assert!(id.category() == *category, "{}", input);
+            assert!(!id.description().is_empty());
+        }
+        assert!(!MessageIdCategory::Success.is_error());
+    }
+
+    #[test]
+    fn notice_msg_id_as_str() {
+        for input in &[
+            "msg_banned",
+            "slow_on",
+            "host_on",
+            "msg_rejected",
+            "unrecognized_cmd",
+        ] {
+            let id = MessageId::parse(input);
+            assert!(!matches!(id, MessageId::Unknown(..)), "{}", input);
+            assert!(id.as_str() == *input);
+        }
+        assert!(MessageId::parse("something_new").as_str() == "something_new");
+    }
 }
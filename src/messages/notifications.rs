@@ -0,0 +1,314 @@
+use super::{Commands, ContextBuffer, ContextEntry, Privmsg};
+use crate::IntoOwned as _;
+
+#[cfg(feature = "whispers")]
+use super::Whisper;
+
+/// Something that should alert you, see [Notifications]
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notification {
+    /// Someone mentioned one of your names in a channel
+    Mention {
+        /// The message with the mention
+        message: Privmsg<'static>,
+        /// The messages on the channel before it, oldest first
+        context: Vec<ContextEntry>,
+    },
+    /// Someone whispered you
+    #[cfg(feature = "whispers")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "whispers")))]
+    Whisper {
+        /// The whisper
+        message: Whisper<'static>,
+        /// What the user recently said on the channels, oldest first
+        context: Vec<ContextEntry>,
+    },
+}
+
+impl Notification {
+    /// The login of the user who caused this notification
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Mention { message, .. } => message.name(),
+            #[cfg(feature = "whispers")]
+            Self::Whisper { message, .. } => message.name(),
+        }
+    }
+
+    /// What the user said
+    pub fn data(&self) -> &str {
+        match self {
+            Self::Mention { message, .. } => message.data(),
+            #[cfg(feature = "whispers")]
+            Self::Whisper { message, .. } => message.data(),
+        }
+    }
+
+    /// The channel of a mention. Whispers don't have one
+    pub fn channel(&self) -> Option<&str> {
+        match self {
+            Self::Mention { message, .. } => Some(message.channel()),
+            #[cfg(feature = "whispers")]
+            Self::Whisper { .. } => None,
+        }
+    }
+
+    /// The messages that were sent before this one
+    pub fn context(&self) -> &[ContextEntry] {
+        match self {
+            Self::Mention { context, .. } => context,
+            #[cfg(feature = "whispers")]
+            Self::Whisper { context, .. } => context,
+        }
+    }
+}
+
+/// Finds the messages that mention you, and the whispers you get.
+///
+/// A name is mentioned when it is a whole word of a chat message, ignoring
+/// case and with or without an `@`. Your own messages are not mentions.
+///
+/// The chat of every channel is kept in a [ContextBuffer], so a notification
+/// carries what was said before it.
+///
+/// ```
+/// # use twitchchat::{FromIrcMessage as _, messages::{Commands, ContextBuffer, Notification, Notifications}};
+/// # use std::time::Duration;
+/// let parse = |input| {
+///     let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+///     Commands::from_irc(msg).unwrap()
+/// };
+///
+/// let mut notifications = Notifications::new("shaken_bot", ContextBuffer::new(10, Duration::from_secs(60)));
+///
+/// assert!(notifications.observe(&parse(":foo!foo@foo PRIVMSG #museun :is the bot here?\r\n")).is_none());
+/// assert!(notifications.observe(&parse(":foo!foo@foo PRIVMSG #museun :shaken_bots are great\r\n")).is_none());
+///
+/// let mention = notifications.observe(&parse(":foo!foo@foo PRIVMSG #museun :hey @Shaken_Bot\r\n")).unwrap();
+/// assert!(matches!(mention, Notification::Mention { .. }));
+/// assert_eq!(mention.channel(), Some("#museun"));
+/// assert_eq!(mention.context().len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Notifications {
+    login: String,
+    names: Vec<String>,
+    context: ContextBuffer,
+}
+
+impl Notifications {
+    /// Notify about mentions of this login (your username), keeping the chat in this buffer
+    pub fn new(login: impl Into<String>, context: ContextBuffer) -> Self {
+        let login = login.into();
+        Self {
+            names: vec![login.to_ascii_lowercase()],
+            login,
+            context,
+        }
+    }
+
+    /// Also notify about mentions of this name, e.g. your display name or a nickname
+    pub fn with_name(mut self, name: impl AsRef<str>) -> Self {
+        let name = name.as_ref().trim_start_matches('@').to_ascii_lowercase();
+        if !name.is_empty() && !self.names.contains(&name) {
+            self.names.push(name)
+        }
+        self
+    }
+
+    /// Whether this text mentions any of the names
+    pub fn is_mention(&self, data: &str) -> bool {
+        self.names.iter().any(|name| mentions(data, name))
+    }
+
+    /// Observe a message, returning a notification if it is a mention or a whisper.
+    ///
+    /// Chat messages are kept in the context buffer.
+    pub fn observe(&mut self, msg: &Commands<'_>) -> Option<Notification> {
+        match msg {
+            Commands::Privmsg(msg) => {
+                let notification = self.mention(msg);
+                self.context.push(msg);
+                notification
+            }
+            #[cfg(feature = "whispers")]
+            Commands::Whisper(msg) => Some(Notification::Whisper {
+                message: msg.clone().into_owned(),
+                context: self.context.by_user_anywhere(msg.name()).cloned().collect(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Get the context buffer, e.g. to prune it
+    pub fn context(&mut self) -> &mut ContextBuffer {
+        &mut self.context
+    }
+
+    /// Get the context buffer back
+    pub fn into_context(self) -> ContextBuffer {
+        self.context
+    }
+
+    fn mention(&self, msg: &Privmsg<'_>) -> Option<Notification> {
+        if msg.name().eq_ignore_ascii_case(&self.login) || !self.is_mention(msg.data()) {
+            return None;
+        }
+
+        Some(Notification::Mention {
+            message: msg.clone().into_owned(),
+            context: self.context.channel(msg.channel()).cloned().collect(),
+        })
+    }
+}
+
+// whether `name` (lowercase) is a whole word of `data`
+fn mentions(data: &str, name: &str) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let data = data.to_ascii_lowercase();
+    data.match_indices(name).any(|(start, found)| {
+        let before = data.get(..start).and_then(|s| s.chars().next_back());
+        let after = data
+            .get(start + found.len()..)
+            .and_then(|s| s.chars().next());
+        !matches!(before, Some(c) if is_word(c)) && !matches!(after, Some(c) if is_word(c))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irc::parse;
+    use crate::FromIrcMessage as _;
+    use std::time::Duration;
+
+    fn commands(input: &str) -> Commands<'_> {
+        let msg = parse(input).next().unwrap().unwrap();
+        Commands::from_irc(msg).unwrap()
+    }
+
+    #[test]
+    fn names() {
+        let notifications =
+            Notifications::new("shaken_bot", ContextBuffer::new(1, Duration::from_secs(0)))
+                .with_name("@ShakenBot");
+
+        for data in &[
+            "shaken_bot",
+            "hello @shaken_bot!",
+            "SHAKEN_BOT: hi",
+            "hi shakenbot",
+            "(shaken_bot)",
+        ] {
+            assert!(notifications.is_mention(data), "{}", data);
+        }
+
+        for data in &[
+            "shaken_bots",
+            "@shaken_bot_",
+            "notshakenbot",
+            "shaken bot",
+            "",
+        ] {
+            assert!(!notifications.is_mention(data), "{}", data);
+        }
+    }
+
+    #[test]
+    fn observe() {
+        let mut notifications =
+            Notifications::new("shaken_bot", ContextBuffer::new(2, Duration::from_secs(60)));
+
+        for input in &[
+            ":foo!foo@foo PRIVMSG #museun :first\r\n",
+            ":bar!bar@bar PRIVMSG #other :elsewhere\r\n",
+            ":foo!foo@foo PRIVMSG #museun :second\r\n",
+            ":foo!foo@foo PRIVMSG #museun :third\r\n",
+            ":shaken_bot!shaken_bot@shaken_bot PRIVMSG #museun :I am shaken_bot\r\n",
+        ] {
+            assert!(notifications.observe(&commands(input)).is_none());
+        }
+
+        let mention = notifications
+            .observe(&commands(":foo!foo@foo PRIVMSG #museun :shaken_bot?\r\n"))
+            .unwrap();
+        assert_eq!(mention.name(), "foo");
+        assert_eq!(mention.data(), "shaken_bot?");
+        let context = mention
+            .context()
+            .iter()
+            .map(|entry| entry.message.data())
+            .collect::<Vec<_>>();
+        assert_eq!(context, vec!["third", "I am shaken_bot"]);
+
+        assert!(notifications
+            .observe(&commands(":tmi.twitch.tv CLEARCHAT #museun\r\n"))
+            .is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "whispers")]
+    fn whisper() {
+        let mut notifications = Notifications::new(
+            "shaken_bot",
+            ContextBuffer::new(10, Duration::from_secs(60)),
+        );
+
+        for input in &[
+            ":bar!bar@bar PRIVMSG #museun :hello\r\n",
+            ":foo!foo@foo PRIVMSG #museun :first\r\n",
+            ":foo!foo@foo PRIVMSG #other :second\r\n",
+        ] {
+            notifications.observe(&commands(input));
+        }
+
+        let whisper = notifications
+            .observe(&commands(
+                ":foo!foo@foo.tmi.twitch.tv WHISPER shaken_bot :psst\r\n",
+            ))
+            .unwrap();
+        assert!(matches!(whisper, Notification::Whisper { .. }));
+        assert_eq!(whisper.channel(), None);
+        assert_eq!(whisper.data(), "psst");
+        let context = whisper
+            .context()
+            .iter()
+            .map(|entry| entry.message.data())
+            .collect::<Vec<_>>();
+        assert_eq!(context, vec!["first", "second"]);
+    }
+
+    #[test]
+    #[cfg(feature = "testing")]
+    fn runner() {
+        use crate::{runner::Status, test::TestConnector, AsyncRunner, UserConfig};
+
+        let connector = TestConnector::default();
+        let config = UserConfig::builder().anonymous().build().unwrap();
+
+        futures_lite::future::block_on(async {
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     :foo!foo@foo PRIVMSG #museun :hello\r\n\
+                     :foo!foo@foo PRIVMSG #museun :hi justinfan1234\r\n\
+                     :foo!foo@foo PRIVMSG #museun :bye\r\n",
+                )
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector, &config).await.unwrap();
+            let notifications = runner.notifications();
+
+            while let Status::Message(..) = runner.next_message().await.unwrap() {}
+            drop(runner);
+
+            let mention = notifications.recv().await.unwrap();
+            assert_eq!(mention.data(), "hi justinfan1234");
+            assert_eq!(mention.context().len(), 1);
+            assert!(notifications.recv().await.is_none());
+        });
+    }
+}
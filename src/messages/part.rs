@@ -56,6 +56,33 @@ mod tests {
         crate::serde::round_trip_rmp::<Part>(input);
     }
 
+    #[test]
+    fn part_bad_command() {
+        let input = ":tmi.twitch.tv NOT_PART #foo\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let err = Part::from_irc(msg).unwrap_err();
+            assert!(matches!(err, MessageError::InvalidCommand { .. }))
+        }
+    }
+
+    #[test]
+    fn part_bad_nick() {
+        let input = ":tmi.twitch.tv PART #foo\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let err = Part::from_irc(msg).unwrap_err();
+            assert!(matches!(err, MessageError::ExpectedNick))
+        }
+    }
+
+    #[test]
+    fn part_bad_channel() {
+        let input = ":tmi.twitch.tv PART\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let err = Part::from_irc(msg).unwrap_err();
+            assert!(matches!(err, MessageError::ExpectedArg { pos: 0 }))
+        }
+    }
+
     #[test]
     fn part() {
         let input = ":test!test@test PART #museun\r\n";
@@ -0,0 +1,241 @@
+use crate::twitch::Emote;
+
+/// A segment of a chat message
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub enum MessagePart<'a> {
+    /// Plain text
+    Text(&'a str),
+    /// An emote
+    Emote {
+        /// The id of the emote
+        id: String,
+        /// The text the emote replaces
+        text: &'a str,
+    },
+}
+
+impl<'a> MessagePart<'a> {
+    /// The text of this part, as it was written in the message
+    pub fn as_str(&self) -> &'a str {
+        match self {
+            Self::Text(text) | Self::Emote { text, .. } => text,
+        }
+    }
+}
+
+/// A chat message split into text and emote parts.
+///
+/// If the message was a CTCP `ACTION` (a `/me`), the framing is already
+/// stripped from the parts and [MessageParts::is_action] is set, so the whole
+/// message should be rendered as an action (e.g. in italics, with the user's color).
+///
+/// ```
+/// # use twitchchat::{FromIrcMessage as _, messages::{MessagePart, Privmsg}};
+/// let input = "@emotes=25:0-4 :museun!museun@museun PRIVMSG #museun :\x01ACTION Kappa hello\x01\r\n";
+/// let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+/// let msg = Privmsg::from_irc(msg).unwrap();
+///
+/// let parts = msg.parts();
+/// assert!(parts.is_action);
+/// assert_eq!(parts.parts, vec![
+///     MessagePart::Emote { id: "25".into(), text: "Kappa" },
+///     MessagePart::Text(" hello"),
+/// ]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct MessageParts<'a> {
+    /// Whether the message was a CTCP `ACTION`
+    pub is_action: bool,
+    /// The parts, in message order
+    pub parts: Vec<MessagePart<'a>>,
+}
+
+impl<'a> MessageParts<'a> {
+    /// Split `data` using the `emotes` ranges.
+    ///
+    /// Ranges that are out of bounds or overlap a previous emote are ignored.
+    pub fn new(data: &'a str, emotes: &[Emote], is_action: bool) -> Self {
+        // twitch uses inclusive char (not byte) ranges
+        let offsets = data
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(data.len()))
+            .collect::<Vec<_>>();
+
+        let mut ranges = emotes
+            .iter()
+            .flat_map(|emote| {
+                emote
+                    .ranges
+                    .iter()
+                    .map(move |range| (range.start as usize, range.end as usize + 1, emote))
+            })
+            .filter(|&(start, end, _)| start < end && end < offsets.len())
+            .collect::<Vec<_>>();
+        ranges.sort_by_key(|&(start, ..)| start);
+
+        // the ranges were checked above, but this mustn't panic on a weird message
+        let text = |start: usize, end: usize| {
+            let (&start, &end) = (offsets.get(start)?, offsets.get(end)?);
+            data.get(start..end)
+        };
+
+        let mut parts = vec![];
+        let mut pos = 0;
+        for (start, end, emote) in ranges {
+            if start < pos {
+                continue;
+            }
+            let (before, text) = match (text(pos, start), text(start, end)) {
+                (Some(before), Some(text)) => (before, text),
+                _ => continue,
+            };
+            if !before.is_empty() {
+                parts.push(MessagePart::Text(before));
+            }
+            parts.push(MessagePart::Emote {
+                id: emote.id.clone(),
+                text,
+            });
+            pos = end;
+        }
+
+        match text(pos, offsets.len().saturating_sub(1)) {
+            Some(rest) if !rest.is_empty() => parts.push(MessagePart::Text(rest)),
+            _ => {}
+        }
+
+        Self { is_action, parts }
+    }
+
+    /// Iterate over the parts
+    pub fn iter(&self) -> impl Iterator<Item = &MessagePart<'a>> + '_ {
+        self.parts.iter()
+    }
+
+    /// The fraction of the visible (non-whitespace) characters that are emotes,
+    /// from `0.0` to `1.0`. This is `0.0` for a message without visible characters
+    pub fn emote_coverage(&self) -> f64 {
+        let visible = |text: &str| text.chars().filter(|c| !c.is_whitespace()).count();
+        let (emotes, total) = self.parts.iter().fold((0, 0), |(emotes, total), part| {
+            let n = visible(part.as_str());
+            match part {
+                MessagePart::Emote { .. } => (emotes + n, total + n),
+                MessagePart::Text(..) => (emotes, total + n),
+            }
+        });
+        match total {
+            0 => 0.0,
+            total => emotes as f64 / total as f64,
+        }
+    }
+
+    /// Whether the message is only emotes (and whitespace between them)
+    pub fn is_emote_only(&self) -> bool {
+        let mut has_emote = false;
+        for part in &self.parts {
+            match part {
+                MessagePart::Emote { .. } => has_emote = true,
+                MessagePart::Text(text) if !text.trim().is_empty() => return false,
+                MessagePart::Text(..) => {}
+            }
+        }
+        has_emote
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::twitch::EmoteVec;
+    use std::str::FromStr;
+
+    #[test]
+    fn split() {
+        let emotes = EmoteVec::from_str("25:0-4,12-16/1902:6-10").unwrap();
+        let parts = MessageParts::new("Kappa Keepo Kappa!", &emotes, false);
+        assert!(!parts.is_action);
+        assert_eq!(
+            parts.parts,
+            vec![
+                MessagePart::Emote {
+                    id: "25".into(),
+                    text: "Kappa"
+                },
+                MessagePart::Text(" "),
+                MessagePart::Emote {
+                    id: "1902".into(),
+                    text: "Keepo"
+                },
+                MessagePart::Text(" "),
+                MessagePart::Emote {
+                    id: "25".into(),
+                    text: "Kappa"
+                },
+                MessagePart::Text("!"),
+            ]
+        );
+
+        let text = parts.iter().map(MessagePart::as_str).collect::<String>();
+        assert_eq!(text, "Kappa Keepo Kappa!");
+    }
+
+    #[test]
+    fn char_ranges() {
+        let emotes = EmoteVec::from_str("25:2-6").unwrap();
+        let parts = MessageParts::new("\u{1F468}\u{FFFD}Kappa", &emotes, false);
+        assert_eq!(
+            parts.parts,
+            vec![
+                MessagePart::Text("\u{1F468}\u{FFFD}"),
+                MessagePart::Emote {
+                    id: "25".into(),
+                    text: "Kappa"
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_ranges() {
+        // out of bounds, and overlapping
+        let emotes = EmoteVec::from_str("25:0-4/33:2-6/1902:6-100").unwrap();
+        let parts = MessageParts::new("Kappa test", &emotes, true);
+        assert!(parts.is_action);
+        assert_eq!(
+            parts.parts,
+            vec![
+                MessagePart::Emote {
+                    id: "25".into(),
+                    text: "Kappa"
+                },
+                MessagePart::Text(" test"),
+            ]
+        );
+
+        let parts = MessageParts::new("", &[], false);
+        assert!(parts.parts.is_empty());
+    }
+
+    #[test]
+    fn coverage() {
+        let emotes = EmoteVec::from_str("25:0-4,12-16/1902:6-10").unwrap();
+        let parts = MessageParts::new("Kappa Keepo Kappa", &emotes, false);
+        assert!(parts.is_emote_only());
+        assert_eq!(parts.emote_coverage(), 1.0);
+
+        let parts = MessageParts::new("Kappa Keepo Kappa!!!", &emotes, false);
+        assert!(!parts.is_emote_only());
+        assert_eq!(parts.emote_coverage(), 15.0 / 18.0);
+
+        let parts = MessageParts::new("hello world", &[], false);
+        assert!(!parts.is_emote_only());
+        assert_eq!(parts.emote_coverage(), 0.0);
+
+        let parts = MessageParts::new("   ", &[], false);
+        assert!(!parts.is_emote_only());
+        assert_eq!(parts.emote_coverage(), 0.0);
+    }
+}
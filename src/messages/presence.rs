@@ -0,0 +1,273 @@
+use super::{Commands, Names, NamesCollector};
+use crate::twitch::SpecialSenderPolicy;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+/// Tracks which users are on which channels.
+///
+/// This needs the membership capability. It is seeded from the `NAMES` list
+/// Twitch sends after joining a channel, and kept up to date with `JOIN` and
+/// `PART` messages.
+///
+/// ***NOTE*** Twitch batches `JOIN`s and `PART`s, and only lists the moderators
+/// of channels with more than 1000 users, so this is an approximation.
+///
+/// The [special senders](crate::twitch::SpecialSender) (e.g. `jtv`) are
+/// skipped, unless configured otherwise with [Presence::with_special_senders].
+///
+/// As a missed `PART` leaves a user present forever, a time-to-live can be set
+/// with [Presence::with_ttl]. Then [Presence::prune] forgets the users that
+/// haven't joined or chatted for that long.
+///
+/// ```
+/// # use twitchchat::{FromIrcMessage as _, messages::{Commands, Presence}};
+/// let parse = |input| {
+///     let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+///     Commands::from_irc(msg).unwrap()
+/// };
+///
+/// let mut presence = Presence::default();
+/// presence.observe(&parse(":museun.tmi.twitch.tv 353 museun = #museun :museun shaken_bot\r\n"));
+/// presence.observe(&parse(":museun.tmi.twitch.tv 366 museun #museun :End of /NAMES list\r\n"));
+/// presence.observe(&parse(":foo!foo@foo.tmi.twitch.tv JOIN #museun\r\n"));
+/// presence.observe(&parse(":shaken_bot!shaken_bot@shaken_bot.tmi.twitch.tv PART #museun\r\n"));
+///
+/// assert_eq!(presence.users("#museun").collect::<Vec<_>>(), vec!["foo", "museun"]);
+/// assert!(presence.is_present("#museun", "foo"));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Presence {
+    channels: HashMap<String, BTreeMap<String, Instant>>,
+    names: NamesCollector,
+    special_senders: SpecialSenderPolicy,
+    ttl: Option<Duration>,
+}
+
+impl Presence {
+    /// Create an empty cache that handles the special senders with this policy
+    pub fn with_special_senders(special_senders: SpecialSenderPolicy) -> Self {
+        Self {
+            special_senders,
+            ..Self::default()
+        }
+    }
+
+    /// Forget users that haven't been seen for `ttl` when [pruning](Presence::prune)
+    pub fn with_ttl(self, ttl: Duration) -> Self {
+        Self {
+            ttl: Some(ttl),
+            ..self
+        }
+    }
+
+    /// Update the users of a channel from this message.
+    ///
+    /// A chat message refreshes when its sender was last seen, if they are
+    /// present. Other messages are ignored
+    pub fn observe(&mut self, msg: &Commands<'_>) {
+        match msg {
+            Commands::Join(msg) => self.insert(msg.channel(), msg.name()),
+            Commands::Part(msg) => self.remove(msg.channel(), msg.name()),
+            Commands::Privmsg(msg) => self.touch(msg.channel(), msg.name(), Instant::now()),
+            Commands::NamesReply(..) | Commands::EndOfNames(..) => {
+                if let Some(names) = self.names.observe(msg) {
+                    self.extend(&names)
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Add all of the users of a `NAMES` list
+    pub fn extend(&mut self, names: &Names) {
+        // JOINs may have arrived before the list was finished, so this adds to the channel
+        let policy = self.special_senders;
+        let now = Instant::now();
+        let users = self.channel_mut(&names.channel);
+        users.extend(
+            names
+                .users
+                .iter()
+                .filter(|user| policy.allows(user))
+                .map(|user| (user.to_lowercase(), now)),
+        );
+    }
+
+    /// Mark `user` as being on `channel`
+    pub fn insert(&mut self, channel: &str, user: &str) {
+        self.insert_at(channel, user, Instant::now())
+    }
+
+    /// Mark `user` as being on `channel`, last seen at `seen`
+    pub fn insert_at(&mut self, channel: &str, user: &str, seen: Instant) {
+        if !self.special_senders.allows(user) {
+            return;
+        }
+        self.channel_mut(channel).insert(user.to_lowercase(), seen);
+    }
+
+    /// Mark `user` as having left `channel`
+    pub fn remove(&mut self, channel: &str, user: &str) {
+        if let Some(users) = self.channels.get_mut(channel) {
+            users.remove(&user.to_lowercase());
+        }
+    }
+
+    /// Forget everything about this channel, e.g. when you leave it
+    pub fn remove_channel(&mut self, channel: &str) {
+        self.channels.remove(channel);
+    }
+
+    /// Forget the users that weren't seen for longer than the ttl at `now`.
+    ///
+    /// This returns how many users were forgotten, and does nothing without a ttl
+    pub fn prune(&mut self, now: Instant) -> usize {
+        let ttl = match self.ttl {
+            Some(ttl) => ttl,
+            None => return 0,
+        };
+
+        let mut pruned = 0;
+        self.channels.retain(|_, users| {
+            let len = users.len();
+            users.retain(|_, seen| now.saturating_duration_since(*seen) <= ttl);
+            pruned += len - users.len();
+            !users.is_empty()
+        });
+        pruned
+    }
+
+    /// The users on this channel, sorted by name
+    pub fn users<'a>(&'a self, channel: &str) -> impl Iterator<Item = &'a str> + 'a {
+        self.channels
+            .get(channel)
+            .into_iter()
+            .flat_map(|users| users.keys())
+            .map(String::as_str)
+    }
+
+    /// Whether `user` is on `channel`
+    pub fn is_present(&self, channel: &str, user: &str) -> bool {
+        self.channels
+            .get(channel)
+            .map_or(false, |users| users.contains_key(&user.to_lowercase()))
+    }
+
+    /// The channels `user` is on
+    pub fn channels_of<'a>(&'a self, user: &str) -> impl Iterator<Item = &'a str> + 'a {
+        let user = user.to_lowercase();
+        self.channels
+            .iter()
+            .filter(move |(_, users)| users.contains_key(&user))
+            .map(|(channel, _)| channel.as_str())
+    }
+
+    fn touch(&mut self, channel: &str, user: &str, seen: Instant) {
+        let user = user.to_lowercase();
+        if let Some(last) = self
+            .channels
+            .get_mut(channel)
+            .and_then(|users| users.get_mut(&user))
+        {
+            *last = seen;
+        }
+    }
+
+    // this only allocates the key for new channels, the lookup can't fail after inserting it
+    #[allow(clippy::expect_used)]
+    fn channel_mut(&mut self, channel: &str) -> &mut BTreeMap<String, Instant> {
+        if !self.channels.contains_key(channel) {
+            self.channels.insert(channel.to_string(), BTreeMap::new());
+        }
+        self.channels
+            .get_mut(channel)
+            .expect("channel should exist")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irc::parse;
+    use crate::FromIrcMessage as _;
+
+    fn observe_all(presence: &mut Presence, input: &str) {
+        for msg in parse(input).map(|s| s.unwrap()) {
+            presence.observe(&Commands::from_irc(msg).unwrap());
+        }
+    }
+
+    #[test]
+    fn presence() {
+        let mut presence = Presence::default();
+        observe_all(
+            &mut presence,
+            ":museun.tmi.twitch.tv 353 museun = #museun :museun Shaken_Bot\r\n\
+             :foo!foo@foo.tmi.twitch.tv JOIN #museun\r\n\
+             :museun.tmi.twitch.tv 366 museun #museun :End of /NAMES list\r\n\
+             :foo!foo@foo.tmi.twitch.tv JOIN #other\r\n\
+             :museun!museun@museun.tmi.twitch.tv PART #museun\r\n",
+        );
+
+        assert_eq!(
+            presence.users("#museun").collect::<Vec<_>>(),
+            vec!["foo", "shaken_bot"]
+        );
+        assert!(presence.is_present("#museun", "SHAKEN_BOT"));
+        assert!(!presence.is_present("#museun", "museun"));
+
+        let mut channels = presence.channels_of("foo").collect::<Vec<_>>();
+        channels.sort_unstable();
+        assert_eq!(channels, vec!["#museun", "#other"]);
+
+        presence.remove_channel("#museun");
+        assert_eq!(presence.users("#museun").count(), 0);
+        assert_eq!(
+            presence.channels_of("foo").collect::<Vec<_>>(),
+            vec!["#other"]
+        );
+    }
+
+    #[test]
+    fn special_senders() {
+        let input = ":museun.tmi.twitch.tv 353 museun = #museun :jtv museun\r\n\
+                     :museun.tmi.twitch.tv 366 museun #museun :End of /NAMES list\r\n\
+                     :jtv!jtv@jtv.tmi.twitch.tv JOIN #museun\r\n";
+
+        let mut presence = Presence::default();
+        observe_all(&mut presence, input);
+        assert_eq!(
+            presence.users("#museun").collect::<Vec<_>>(),
+            vec!["museun"]
+        );
+
+        let mut presence = Presence::with_special_senders(SpecialSenderPolicy::Include);
+        observe_all(&mut presence, input);
+        assert_eq!(
+            presence.users("#museun").collect::<Vec<_>>(),
+            vec!["jtv", "museun"]
+        );
+    }
+
+    #[test]
+    fn ttl() {
+        let start = Instant::now();
+        let mut presence = Presence::default();
+        presence.insert_at("#museun", "foo", start);
+        assert_eq!(presence.prune(start + Duration::from_secs(3600)), 0);
+
+        let mut presence = Presence::default().with_ttl(Duration::from_secs(60));
+        presence.insert_at("#museun", "foo", start);
+        presence.insert_at("#museun", "bar", start);
+        presence.insert_at("#other", "foo", start + Duration::from_secs(30));
+        presence.touch("#museun", "bar", start + Duration::from_secs(40));
+
+        assert_eq!(presence.prune(start + Duration::from_secs(60)), 0);
+        assert_eq!(presence.prune(start + Duration::from_secs(61)), 1);
+        assert_eq!(presence.users("#museun").collect::<Vec<_>>(), vec!["bar"]);
+        assert!(presence.is_present("#other", "foo"));
+
+        assert_eq!(presence.prune(start + Duration::from_secs(120)), 2);
+        assert_eq!(presence.channels_of("foo").count(), 0);
+    }
+}
@@ -1,8 +1,11 @@
 use crate::irc::tags::ParsedTag;
+use crate::messages::{mask, truncate, MaskOptions, MessageParts, ThirdPartyEmotes, Truncated};
 use crate::twitch::attributes::{Attribution, AttributionVec};
-use crate::twitch::{Badge, BadgeVec, Color, EmoteVec, FlagVec};
+use crate::twitch::{
+    Badge, BadgeVec, Cheer, Color, Emote, EmoteVec, FlagVec, MonetaryValue, Spanned, UserType,
+};
 use crate::{irc::*, MaybeOwned, MaybeOwnedIndex, Validator};
-use std::str::FromStr;
+use std::{borrow::Cow, str::FromStr};
 
 // IDEA: Use tendril crate for parsing
 
@@ -65,7 +68,7 @@ impl<'a> Privmsg<'a> {
     /// Gets the 'CTCP' kind associated with this message, if any
     pub fn ctcp(&self) -> Option<Ctcp<'_>> {
         const ACTION: &str = "ACTION";
-        let command = &self.raw[self.ctcp?];
+        let command = self.ctcp?.get(&self.raw)?;
         if command == ACTION {
             Some(Ctcp::Action)
         } else {
@@ -78,6 +81,50 @@ impl<'a> Privmsg<'a> {
         matches!(self.ctcp(), Some(Ctcp::Action))
     }
 
+    /// Split this message into text and emote parts
+    ///
+    /// For actions the `ACTION` framing is stripped and [MessageParts::is_action] is set.
+    pub fn parts(&self) -> MessageParts<'_> {
+        MessageParts::new(self.data(), &self.emotes(), self.is_action())
+    }
+
+    /// Split this message into text and emote parts, with the third-party emotes of its channel.
+    ///
+    /// Where a third-party emote overlaps a Twitch emote, the Twitch one is used.
+    pub fn parts_with(&self, emotes: &ThirdPartyEmotes) -> MessageParts<'_> {
+        let mut all = self.emotes().to_vec();
+        all.extend(emotes.annotate(self.channel(), self.data()));
+        MessageParts::new(self.data(), &all, self.is_action())
+    }
+
+    /// Whether this message is only emotes, e.g. for spam scoring or an "emote wall"
+    ///
+    /// This uses the `emote-only` tag when Twitch sent it, otherwise it is
+    /// worked out from the `emotes` tag, see [MessageParts::is_emote_only]
+    pub fn is_emote_only_message(&self) -> bool {
+        match self.tags().get("emote-only") {
+            Some(flag) => flag == "1",
+            None => self.parts().is_emote_only(),
+        }
+    }
+
+    /// The fraction of the visible characters of this message covered by
+    /// emotes, see [MessageParts::emote_coverage]
+    pub fn emote_coverage(&self) -> f64 {
+        self.parts().emote_coverage()
+    }
+
+    /// This message shortened to at most `max_chars` characters for display,
+    /// with the emotes and flags that are still visible, see [truncate]
+    pub fn truncated(&self, max_chars: usize) -> Truncated<'_> {
+        truncate(self.data(), max_chars, &self.emotes(), &self.flags())
+    }
+
+    /// This message with the terms automod flagged hidden, see [mask]
+    pub fn masked(&self, options: &MaskOptions) -> Cow<'_, str> {
+        mask(self.data(), &self.flags(), options)
+    }
+
     /// Helper function to return information that can be parsed as AttributionVec.
     fn tag_to_attribution_vec<Ref, Attr, T>(
         &'a self,
@@ -103,19 +150,65 @@ impl<'a> Privmsg<'a> {
         self.tag_to_attribution_vec("badge-info")
     }
 
+    /// How many months the user has been subscribed to the channel, from the `badge-info` tag
+    pub fn subscriber_months(&self) -> Option<u32> {
+        self.tags()
+            .get("badge-info")
+            .and_then(crate::twitch::subscriber_months)
+    }
+
     /// Badges attached to this message
     pub fn badges(&'a self) -> BadgeVec {
         self.tag_to_attribution_vec("badges")
     }
 
+    /// Badges attached to this message, with where each one is in the `badges` tag
+    pub fn badge_spans(&self) -> Vec<Spanned<Badge>> {
+        self.tags()
+            .get("badges")
+            .map(BadgeVec::parse_spanned)
+            .unwrap_or_default()
+    }
+
     /// How many bits were attached to this message
     pub fn bits(&self) -> Option<ParsedTag<u64>> {
         self.tags().get_parsed("bits")
     }
 
+    /// The cheermotes in this message, e.g. `Cheer100`
+    ///
+    /// This is empty if no bits were attached to this message
+    pub fn cheers(&self) -> Vec<Cheer> {
+        if self.tags().get("bits").is_none() {
+            return vec![];
+        }
+        crate::twitch::parse_cheers(self.data()).collect()
+    }
+
+    /// The amount paid for this message, if it is a Hype Chat
+    pub fn hype_chat(&self) -> Option<MonetaryValue> {
+        let tags = self.tags();
+        let amount = tags.get("pinned-chat-paid-amount")?.parse().ok()?;
+        let exponent = tags.get("pinned-chat-paid-exponent")?.parse().ok()?;
+        let currency = tags.get("pinned-chat-paid-currency")?;
+        Some(MonetaryValue::new(amount, exponent, currency))
+    }
+
+    /// The estimated value of this message: the bits attached to it, or the amount paid for a Hype Chat
+    pub fn value(&self) -> Option<MonetaryValue> {
+        match self.bits() {
+            Some(Ok(bits)) if bits > 0 => Some(MonetaryValue::from_bits(bits)),
+            _ => self.hype_chat(),
+        }
+    }
+
     /// The color of the user who sent this message, if set
+    ///
+    /// This is `None` if the tag is missing, or empty because the user never picked a color
     pub fn color(&self) -> Option<ParsedTag<Color>> {
-        self.tags().get_parsed("color")
+        let tags = self.tags();
+        tags.get_non_empty("color")?;
+        tags.get_parsed("color")
     }
 
     /// Returns the display name of the user, if set.
@@ -128,7 +221,15 @@ impl<'a> Privmsg<'a> {
     ///
     /// Otherwise it'll return `None`.
     pub fn display_name(&'a self) -> Option<&str> {
-        self.tags().get("display-name")
+        self.tags().get_non_empty("display-name")
+    }
+
+    /// The `user-type` of the user who sent this message.
+    ///
+    /// Ordinary users have an empty tag, which is `Some(UserType::Normal)`, while
+    /// `None` means the tag is missing (e.g. the tags capability wasn't requested)
+    pub fn user_type(&self) -> Option<UserType> {
+        self.tags().get("user-type").map(UserType::from_tag)
     }
 
     /// Emotes attached to this message
@@ -136,6 +237,14 @@ impl<'a> Privmsg<'a> {
         self.tag_to_attribution_vec("emotes")
     }
 
+    /// Emotes attached to this message, with where each one is in the `emotes` tag
+    pub fn emote_spans(&self) -> Vec<Spanned<Emote>> {
+        self.tags()
+            .get("emotes")
+            .map(EmoteVec::parse_spanned)
+            .unwrap_or_default()
+    }
+
     /// Flags attached to this message
     pub fn flags(&self) -> FlagVec {
         self.tag_to_attribution_vec("flags")
@@ -209,6 +318,13 @@ impl<'a> Privmsg<'a> {
     pub fn msg_id(&self) -> Option<&str> {
         self.tags().get("msg-id")
     }
+
+    /// The `client-nonce` the sender attached to this message, if Twitch echoed it.
+    ///
+    /// See [with_nonce](crate::commands::with_nonce) for attaching one to sent messages
+    pub fn client_nonce(&self) -> Option<&str> {
+        self.tags().get("client-nonce")
+    }
 }
 
 impl<'a> FromIrcMessage<'a> for Privmsg<'a> {
@@ -223,18 +339,19 @@ impl<'a> FromIrcMessage<'a> for Privmsg<'a> {
 
         let data = &msg.raw[index];
         if data.starts_with(CTCP_MARKER) && data.ends_with(CTCP_MARKER) {
-            let len = data.chars().map(char::len_utf8).sum::<usize>();
-            match data[1..len - 1].find(' ') {
+            // a lone marker both starts and ends the data
+            let inner = data
+                .get(1..data.len() - 1)
+                .ok_or(MessageError::ExpectedData)?;
+            match inner.find(' ') {
                 Some(pos) => {
-                    // TODO refactor this so the casting is done in 1 canonical place
-                    //
                     // skip the first byte
-                    let head = index.start + 1;
-                    let ctcp_index = MaybeOwnedIndex::raw(head as usize, (head as usize) + pos);
+                    let head = index.as_range().start + 1;
+                    let ctcp_index = MaybeOwnedIndex::raw(head, head + pos);
 
-                    // for the byte + space
-                    index.start += (pos as u16) + 2;
-                    index.end -= 1;
+                    // for the byte + space, and the trailing byte
+                    let range = index.as_range();
+                    index = MaybeOwnedIndex::raw(range.start + pos + 2, range.end - 1);
                     ctcp.replace(ctcp_index);
                 }
                 None => return Err(MessageError::ExpectedData),
@@ -304,16 +421,44 @@ serde_struct!(Privmsg {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::twitch::Emote;
+    use assert2::assert;
 
     macro_rules! emote {
         ($id:expr, $($r:expr),* $(,)?) => {
             Emote {
-                id: $id,
+                id: $id.to_string(),
                 ranges: vec![$($r.into()),*]
             }
         };
     }
 
+    #[test]
+    fn privmsg_empty_and_missing_tags() {
+        let input = "@badges=;color=;display-name=;user-type= :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+            assert!(msg.tags().get("color") == Some(""));
+            assert!(msg.color().is_none());
+            assert!(msg.display_name().is_none());
+            assert!(msg.user_type() == Some(UserType::Normal));
+        }
+
+        let input = "@badges=;user-type=mod :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+            assert!(msg.color().is_none());
+            assert!(msg.display_name().is_none());
+            assert!(msg.user_type() == Some(UserType::Moderator));
+        }
+
+        let input = ":museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+            assert!(msg.user_type().is_none());
+        }
+    }
+
     #[test]
     #[cfg(feature = "serde")]
     fn privmsg_serde() {
@@ -338,9 +483,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn privmsg_subscriber_months() {
+        let input = "@badge-info=subscriber/1012;badges=subscriber/3012 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+            assert_eq!(msg.subscriber_months(), Some(1012));
+        }
+    }
+
     #[test]
     fn privmsg_integrity() {
-        let input = "@badge-info=;badges=global_mod/1,turbo/1;color=#0D4200;display-name=ronni;emotes=25:0-4,12-16/1902:6-10;id=b34ccfc7-4977-403a-8a94-33c6bac34fb8;mod=0;room-id=1337;subscriber=0;tmi-sent-ts=1507246572675;turbo=1;user-id=1337;user-type=global_mod :ronni!ronni@ronni.tmi.twitch.tv PRIVMSG #ronni :Kappa Keepo Kappa";
+        let input = "@badge-info=;badges=global_mod/1,turbo/1;color=#0D4200;display-name=ronni;emotes=25:0-4,12-16/1902:6-10;id=b34ccfc7-4977-403a-8a94-33c6bac34fb8;mod=0;room-id=1337;subscriber=0;tmi-sent-ts=1507246572675;turbo=1;user-id=1337;user-type=global_mod :ronni!ronni@ronni.tmi.twitch.tv PRIVMSG #ronni :Kappa Keepo Kappa\r\n";
         for msg in parse(input).map(|s| s.unwrap()) {
             let msg = Privmsg::from_irc(msg).unwrap();
 
@@ -349,21 +503,71 @@ mod tests {
             assert!(msg.data() == "Kappa Keepo Kappa");
             assert!(msg.ctcp() == None);
 
-            assert!(msg.badge_info().unwrap().unwrap() == vec![]);
-            assert!(msg.badges().unwrap().unwrap() == vec![Badge::GlobalMod, Badge::Turbo]);
+            assert!(*msg.badge_info() == vec![]);
+            assert!(msg.subscriber_months() == None);
+            assert!(*msg.badges() == vec![Badge::GlobalMod, Badge::Turbo]);
             assert!(msg.color().unwrap().unwrap() == "#0D4200".parse().unwrap());
             assert!(msg.display_name().unwrap() == "ronni");
             assert!(
-                msg.emotes().unwrap().unwrap() == vec![emote!(25, (0..4), (12..16)), emote!(1902, (6..10))]
+                *msg.emotes() == vec![emote!(25, (0..4), (12..16)), emote!(1902, (6..10))]
             );
-            assert!(msg.id().unwrap() == "b34ccfc7-4977-403a-8a94-33c6bac34fb8");
-            assert!(msg.r#mod().unwrap().unwrap() == true);
+            assert!(msg.tags().get("id").unwrap() == "b34ccfc7-4977-403a-8a94-33c6bac34fb8");
+            assert!(msg.is_moderator() == false);
             assert!(msg.room_id().unwrap().unwrap() == 1337);
-            assert!(msg.subscriber().unwrap().unwrap() == false);
+            assert!(msg.tags().get_as_bool("subscriber") == false);
             assert!(msg.tmi_sent_ts().unwrap().unwrap() == 1507246572675);
-            assert!(msg.turbo().unwrap().unwrap() == true);
+            assert!(msg.tags().get_as_bool("turbo") == true);
             assert!(msg.user_id().unwrap().unwrap() == 1337);
-            assert!(msg.user_type().unwrap() == "global_mod");
+            assert!(msg.tags().get("user-type").unwrap() == "global_mod");
+            assert!(msg.user_type() == Some(UserType::GlobalModerator));
+        }
+    }
+
+    #[test]
+    fn privmsg_cheers() {
+        use crate::twitch::CheerTier;
+
+        let input = "@bits=1100;display-name=museun :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :Cheer100 great stream Kappa1000 Cheer0\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+            let cheers = msg.cheers();
+            assert!(cheers.len() == 2);
+            assert!(cheers[0].prefix == "Cheer");
+            assert!(cheers[0].tier == CheerTier::Purple);
+            assert!(cheers[1].amount == 1000);
+            assert!(cheers[1].tier == CheerTier::Green);
+        }
+
+        // without bits these are just words
+        let input = ":museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :Cheer100\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+            assert!(msg.cheers().is_empty());
+        }
+    }
+
+    #[test]
+    fn privmsg_value() {
+        use crate::twitch::MonetaryValue;
+
+        let input = "@bits=250 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :Cheer250\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+            assert!(msg.hype_chat().is_none());
+            assert!(msg.value() == Some(MonetaryValue::new(250, 2, "USD")));
+        }
+
+        let input = "@pinned-chat-paid-amount=1400;pinned-chat-paid-currency=EUR;pinned-chat-paid-exponent=2;pinned-chat-paid-level=TWO :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+            assert!(msg.hype_chat() == Some(MonetaryValue::new(1400, 2, "EUR")));
+            assert!(msg.value() == msg.hype_chat());
+        }
+
+        let input = ":museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+            assert!(msg.value().is_none());
         }
     }
 
@@ -390,6 +594,30 @@ mod tests {
             assert_eq!(msg.channel(), "#museun");
             assert_eq!(msg.data(), "this is a test");
             assert_eq!(msg.ctcp().unwrap(), Ctcp::Action);
+
+            let parts = msg.parts();
+            assert!(parts.is_action);
+            assert_eq!(
+                parts.parts,
+                vec![crate::messages::MessagePart::Text("this is a test")]
+            );
+        }
+    }
+
+    #[test]
+    fn privmsg_action_long_tags() {
+        let value = "a".repeat(u16::MAX as usize);
+        let input = format!(
+            "@key={} :test!user@host PRIVMSG #museun :\x01ACTION this is a test\x01\r\n",
+            value
+        );
+        for msg in parse(&input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+
+            assert_eq!(msg.tags().get("key"), Some(&*value));
+            assert_eq!(msg.channel(), "#museun");
+            assert_eq!(msg.data(), "this is a test");
+            assert_eq!(msg.ctcp().unwrap(), Ctcp::Action);
         }
     }
 
@@ -406,6 +634,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn privmsg_lone_ctcp_marker() {
+        let input = ":test!user@host PRIVMSG #museun :\x01\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let err = Privmsg::from_irc(msg).unwrap_err();
+            assert!(matches!(err, MessageError::ExpectedData));
+        }
+    }
+
     #[test]
     fn privmsg_community_rewards() {
         let input = "@custom-reward-id=abc-123-foo;msg-id=highlighted-message :test!user@host PRIVMSG #museun :Notice me!\r\n";
@@ -419,6 +656,67 @@ mod tests {
         }
     }
 
+    #[test]
+    fn privmsg_client_nonce() {
+        let input = "@client-nonce=3f2a;id=1 :museun!museun@museun PRIVMSG #museun :hello\r\n\
+                     @id=2 :museun!museun@museun PRIVMSG #museun :hello\r\n";
+        let nonces = parse(input)
+            .map(|msg| Privmsg::from_irc(msg.unwrap()).unwrap())
+            .map(|msg| msg.client_nonce().map(ToString::to_string))
+            .collect::<Vec<_>>();
+        assert_eq!(nonces, vec![Some("3f2a".to_string()), None]);
+    }
+
+    #[test]
+    fn privmsg_spans() {
+        let input = "@badges=broadcaster/1,bogus,subscriber/3012;emotes=25:0-4,6-10/81274:12-17 :museun!museun@museun PRIVMSG #museun :Kappa Kappa VoHiYo\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+
+            let badges = msg.badge_spans();
+            assert_eq!(badges.len(), 2);
+            assert_eq!(*badges[0], Badge::Broadcaster);
+            assert_eq!(badges[0].raw_span(), ("broadcaster/1", 0..13));
+            assert_eq!(*badges[1], Badge::TierSubscriber(3, 12));
+            assert_eq!(badges[1].raw_span(), ("subscriber/3012", 20..35));
+
+            let tag = msg.tags().get("emotes").unwrap();
+            for emote in msg.emote_spans() {
+                let (raw, span) = emote.raw_span();
+                assert_eq!(&tag[span], raw);
+            }
+            assert_eq!(msg.emote_spans()[1].raw_span(), ("81274:12-17", 12..23));
+            assert_eq!(msg.emote_spans()[1].id, "81274");
+        }
+
+        let input = ":museun!museun@museun PRIVMSG #museun :Kappa\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+            assert!(msg.badge_spans().is_empty());
+            assert!(msg.emote_spans().is_empty());
+        }
+    }
+
+    #[test]
+    fn emote_only() {
+        let input = "@emote-only=1;emotes=25:0-4,6-10/81274:12-17 :museun!museun@museun PRIVMSG #museun :Kappa Kappa VoHiYo\r\n\
+                     @emotes=25:0-4,6-10 :museun!museun@museun PRIVMSG #museun :Kappa Kappa\r\n\
+                     @emotes=25:0-4 :museun!museun@museun PRIVMSG #museun :Kappa hi\r\n\
+                     :museun!museun@museun PRIVMSG #museun :hello\r\n";
+        let msgs = parse(input)
+            .map(|msg| Privmsg::from_irc(msg.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+
+        let emote_only = msgs
+            .iter()
+            .map(Privmsg::is_emote_only_message)
+            .collect::<Vec<_>>();
+        assert_eq!(emote_only, vec![true, true, false, false]);
+
+        let coverage = msgs.iter().map(Privmsg::emote_coverage).collect::<Vec<_>>();
+        assert_eq!(coverage, vec![1.0, 1.0, 5.0 / 7.0, 0.0]);
+    }
+
     // #[test]
     // fn privmsg_badges_iter() {
     //     let input = "@badge-info=;badges=broadcaster/1;color=#FF69B4;display-name=museun;emote-only=1;emotes=25:0-4,6-10/81274:12-17;flags=;id=4e160a53-5482-4764-ba28-f224cd59a51f;mod=0;room-id=23196011;subscriber=0;tmi-sent-ts=1601079032426;turbo=0;user-id=23196011;user-type= :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :Kappa Kappa VoHiYo\r\n";
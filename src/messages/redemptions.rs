@@ -0,0 +1,263 @@
+use super::{Commands, Privmsg};
+use std::collections::{BTreeMap, VecDeque};
+
+/// What has been done with a [Redemption], see [RedemptionQueue]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum RedemptionStatus {
+    /// It hasn't been handled yet
+    Pending,
+    /// It was handled
+    Fulfilled,
+    /// It was rejected, e.g. because the input was invalid
+    Canceled,
+}
+
+/// A channel points reward redeemed with a message
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Redemption {
+    /// The id of the message, from its `id` tag
+    pub id: String,
+    /// The id of the reward, from the `custom-reward-id` tag
+    pub reward_id: String,
+    /// The channel the reward was redeemed on
+    pub channel: String,
+    /// The login of the user who redeemed it
+    pub login: String,
+    /// The display name of the user who redeemed it, if set
+    pub display_name: Option<String>,
+    /// The id of the user who redeemed it
+    pub user_id: Option<u64>,
+    /// The text the user entered
+    pub input: String,
+    /// When Twitch sent the message, in milliseconds since the unix epoch
+    pub tmi_sent_ts: Option<u64>,
+    /// What has been done with it
+    pub status: RedemptionStatus,
+}
+
+impl Redemption {
+    /// Get the redemption from this message, if it redeemed a reward.
+    ///
+    /// This is `None` if the message doesn't have a `custom-reward-id` or an `id`
+    pub fn from_privmsg(msg: &Privmsg<'_>) -> Option<Self> {
+        let reward_id = msg.custom_reward_id().filter(|s| !s.is_empty())?;
+        let id = msg.tags().get_non_empty("id")?;
+
+        Some(Self {
+            id: id.to_string(),
+            reward_id: reward_id.to_string(),
+            channel: msg.channel().to_string(),
+            login: msg.name().to_string(),
+            display_name: msg.display_name().map(str::to_string),
+            user_id: msg.user_id().and_then(Result::ok),
+            input: msg.data().to_string(),
+            tmi_sent_ts: msg.tmi_sent_ts().and_then(Result::ok),
+            status: RedemptionStatus::Pending,
+        })
+    }
+
+    /// Whether this hasn't been handled yet
+    pub fn is_pending(&self) -> bool {
+        self.status == RedemptionStatus::Pending
+    }
+}
+
+/// A work queue of channel points redemptions, in the order they were received.
+///
+/// Twitch doesn't tell chat when a redemption is fulfilled or canceled, so the
+/// queue only keeps track of what you [marked](RedemptionQueue::fulfill) them as.
+/// With the `serde` feature, it can be saved and loaded to survive restarts.
+///
+/// ```
+/// # use twitchchat::{FromIrcMessage as _, messages::{Commands, RedemptionQueue}};
+/// let parse = |input| {
+///     let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+///     Commands::from_irc(msg).unwrap()
+/// };
+///
+/// let mut queue = RedemptionQueue::default();
+/// queue.observe(&parse("@custom-reward-id=song;id=1 :foo!foo@foo PRIVMSG #museun :never gonna give you up\r\n"));
+/// queue.observe(&parse("@custom-reward-id=hydrate;id=2 :bar!bar@bar PRIVMSG #museun :drink water\r\n"));
+/// queue.observe(&parse("@id=3 :baz!baz@baz PRIVMSG #museun :just chatting\r\n"));
+///
+/// let next = queue.next_pending().unwrap();
+/// assert_eq!(next.reward_id, "song");
+/// assert!(queue.fulfill("1"));
+///
+/// assert_eq!(queue.next_pending().unwrap().input, "drink water");
+/// assert_eq!(queue.pending_for("song").count(), 0);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct RedemptionQueue {
+    redemptions: VecDeque<Redemption>,
+}
+
+impl RedemptionQueue {
+    /// Queue the redemption of this message, if it redeemed a reward.
+    ///
+    /// Other messages are ignored
+    pub fn observe(&mut self, msg: &Commands<'_>) -> Option<&Redemption> {
+        match msg {
+            Commands::Privmsg(msg) => self.push(msg),
+            _ => None,
+        }
+    }
+
+    /// Queue the redemption of this chat message, if it redeemed a reward.
+    ///
+    /// A message that was already queued (e.g. when replaying a log) isn't queued again
+    pub fn push(&mut self, msg: &Privmsg<'_>) -> Option<&Redemption> {
+        let redemption = Redemption::from_privmsg(msg)?;
+        if self.get(&redemption.id).is_some() {
+            return None;
+        }
+        self.redemptions.push_back(redemption);
+        self.redemptions.back()
+    }
+
+    /// Get the redemption of the message with this id
+    pub fn get(&self, id: &str) -> Option<&Redemption> {
+        self.redemptions.iter().find(|r| r.id == id)
+    }
+
+    /// The oldest redemption that hasn't been handled
+    pub fn next_pending(&self) -> Option<&Redemption> {
+        self.pending().next()
+    }
+
+    /// The redemptions that haven't been handled, oldest first
+    pub fn pending(&self) -> impl Iterator<Item = &Redemption> + '_ {
+        self.redemptions.iter().filter(|r| r.is_pending())
+    }
+
+    /// The redemptions of this reward that haven't been handled, oldest first
+    pub fn pending_for<'a>(
+        &'a self,
+        reward_id: &'a str,
+    ) -> impl Iterator<Item = &'a Redemption> + 'a {
+        self.pending().filter(move |r| r.reward_id == reward_id)
+    }
+
+    /// Every redemption, grouped by reward id, oldest first
+    pub fn by_reward(&self) -> BTreeMap<&str, Vec<&Redemption>> {
+        let mut rewards = BTreeMap::<_, Vec<_>>::new();
+        for redemption in &self.redemptions {
+            rewards
+                .entry(redemption.reward_id.as_str())
+                .or_default()
+                .push(redemption);
+        }
+        rewards
+    }
+
+    /// Every redemption, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &Redemption> + '_ {
+        self.redemptions.iter()
+    }
+
+    /// Mark the redemption with this id as fulfilled, returning whether it was queued
+    pub fn fulfill(&mut self, id: &str) -> bool {
+        self.set_status(id, RedemptionStatus::Fulfilled).is_some()
+    }
+
+    /// Mark the redemption with this id as canceled, returning whether it was queued
+    pub fn cancel(&mut self, id: &str) -> bool {
+        self.set_status(id, RedemptionStatus::Canceled).is_some()
+    }
+
+    /// Set the status of the redemption with this id, returning its previous status
+    pub fn set_status(&mut self, id: &str, status: RedemptionStatus) -> Option<RedemptionStatus> {
+        let redemption = self.redemptions.iter_mut().find(|r| r.id == id)?;
+        Some(std::mem::replace(&mut redemption.status, status))
+    }
+
+    /// Forget every redemption that was fulfilled or canceled, returning how many were removed
+    pub fn remove_handled(&mut self) -> usize {
+        let before = self.redemptions.len();
+        self.redemptions.retain(Redemption::is_pending);
+        before - self.redemptions.len()
+    }
+
+    /// The number of queued redemptions, including handled ones
+    pub fn len(&self) -> usize {
+        self.redemptions.len()
+    }
+
+    /// Whether nothing is queued
+    pub fn is_empty(&self) -> bool {
+        self.redemptions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{irc::parse, FromIrcMessage as _};
+
+    fn queue(input: &str) -> RedemptionQueue {
+        let mut queue = RedemptionQueue::default();
+        for msg in parse(input).map(|msg| Commands::from_irc(msg.unwrap()).unwrap()) {
+            queue.observe(&msg);
+        }
+        queue
+    }
+
+    const INPUT: &str = "@custom-reward-id=song;display-name=Foo;id=1;tmi-sent-ts=10;user-id=42 :foo!foo@foo PRIVMSG #museun :a song\r\n\
+                         @custom-reward-id=hydrate;id=2 :bar!bar@bar PRIVMSG #museun :water\r\n\
+                         @custom-reward-id=song;id=3 :baz!baz@baz PRIVMSG #museun :another song\r\n\
+                         @custom-reward-id=song;id=1 :foo!foo@foo PRIVMSG #museun :a song\r\n\
+                         @custom-reward-id=song :foo!foo@foo PRIVMSG #museun :no id\r\n\
+                         :foo!foo@foo PRIVMSG #museun :not a redemption\r\n";
+
+    #[test]
+    fn queue_and_acknowledge() {
+        let mut queue = queue(INPUT);
+        assert_eq!(queue.len(), 3);
+
+        let first = queue.get("1").unwrap();
+        assert_eq!(first.display_name.as_deref(), Some("Foo"));
+        assert_eq!(first.user_id, Some(42));
+        assert_eq!(first.tmi_sent_ts, Some(10));
+        assert_eq!(first.input, "a song");
+
+        let rewards = queue
+            .by_reward()
+            .into_iter()
+            .map(|(reward, redemptions)| (reward, redemptions.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(rewards, vec![("hydrate", 1), ("song", 2)]);
+
+        assert!(queue.fulfill("1"));
+        assert!(queue.cancel("2"));
+        assert!(!queue.fulfill("4"));
+        assert_eq!(
+            queue.set_status("1", RedemptionStatus::Pending),
+            Some(RedemptionStatus::Fulfilled)
+        );
+        assert!(queue.fulfill("1"));
+
+        let pending = queue
+            .pending_for("song")
+            .map(|r| &*r.id)
+            .collect::<Vec<_>>();
+        assert_eq!(pending, vec!["3"]);
+
+        assert_eq!(queue.remove_handled(), 2);
+        assert_eq!(queue.next_pending().unwrap().id, "3");
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn persistence() {
+        let mut queue = queue(INPUT);
+        queue.cancel("2");
+
+        let json = serde_json::to_string(&queue).unwrap();
+        let loaded: RedemptionQueue = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, queue);
+        assert_eq!(loaded.get("2").unwrap().status, RedemptionStatus::Canceled);
+    }
+}
@@ -0,0 +1,261 @@
+use super::*;
+use crate::{FromIrcMessage, IntoIrcMessage, IntoOwned as _, MessageError};
+use std::{any::Any, collections::HashMap, fmt::Debug};
+
+/// A message of any type, see [MessageRegistry]
+///
+/// This is implemented for every `'static` type that is `Debug + Send + Sync`,
+/// and can be downcast to the concrete type.
+pub trait AnyMessage: Any + Debug + Send + Sync {
+    #[doc(hidden)]
+    fn as_any(&self) -> &dyn Any;
+    #[doc(hidden)]
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T> AnyMessage for T
+where
+    T: Any + Debug + Send + Sync,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl dyn AnyMessage {
+    /// Is this message a `T`?
+    pub fn is<T: AnyMessage>(&self) -> bool {
+        self.as_any().is::<T>()
+    }
+
+    /// Get a reference to the `T` this message is
+    pub fn downcast_ref<T: AnyMessage>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+
+    /// Get the `T` this message is, or the message back if it isn't one
+    // the message can't be given back after `into_any`, so the type is checked first
+    #[allow(clippy::expect_used)]
+    pub fn downcast<T: AnyMessage>(self: Box<Self>) -> Result<Box<T>, Box<Self>> {
+        if self.is::<T>() {
+            return Ok(self.into_any().downcast().expect("type was checked"));
+        }
+        Err(self)
+    }
+}
+
+type ParseFn =
+    Box<dyn Fn(IrcMessage<'static>) -> Result<Box<dyn AnyMessage>, MessageError> + Send + Sync>;
+
+/// A registry of the types commands are parsed into, for plugin systems.
+///
+/// By default, every command that [Commands] knows is parsed into its message
+/// type (e.g. `PRIVMSG` into a `Privmsg<'static>`), and any other command
+/// into an `IrcMessage<'static>`. Plugins can register parsers for their own
+/// commands, or replace the built-in ones.
+///
+/// ```rust
+/// # use twitchchat::{MessageError, messages::{MessageRegistry, Privmsg}};
+/// // a command this crate doesn't know about
+/// #[derive(Debug)]
+/// struct Usernotice2 {
+///     channel: String,
+/// }
+///
+/// let mut registry = MessageRegistry::default();
+/// registry.register_fn("USERNOTICE2", |msg| {
+///     let channel = msg.nth_arg(0).ok_or(MessageError::ExpectedArg { pos: 0 })?;
+///     Ok(Usernotice2 { channel: channel.to_string() })
+/// });
+///
+/// let input = ":tmi.twitch.tv USERNOTICE2 #museun\r\n\
+///              :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n";
+/// let mut messages = twitchchat::irc::parse(input).map(|msg| registry.parse(msg.unwrap()).unwrap());
+///
+/// let msg = messages.next().unwrap();
+/// assert_eq!(msg.downcast_ref::<Usernotice2>().unwrap().channel, "#museun");
+///
+/// let msg = messages.next().unwrap();
+/// assert_eq!(msg.downcast::<Privmsg<'static>>().unwrap().data(), "hello");
+/// ```
+pub struct MessageRegistry {
+    parsers: HashMap<String, ParseFn>,
+}
+
+impl Debug for MessageRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageRegistry")
+            .field("commands", &self.parsers.keys())
+            .finish()
+    }
+}
+
+impl Default for MessageRegistry {
+    fn default() -> Self {
+        use IrcMessage as M;
+
+        let mut this = Self::empty();
+        this.register::<IrcReady<'static>>(M::IRC_READY);
+        this.register::<Ready<'static>>(M::READY);
+        this.register::<Cap<'static>>(M::CAP);
+        #[cfg(feature = "moderation")]
+        this.register::<ClearChat<'static>>(M::CLEAR_CHAT);
+        #[cfg(feature = "moderation")]
+        this.register::<ClearMsg<'static>>(M::CLEAR_MSG);
+        this.register::<GlobalUserState<'static>>(M::GLOBAL_USER_STATE);
+        this.register::<HostTarget<'static>>(M::HOST_TARGET);
+        this.register::<Join<'static>>(M::JOIN);
+        this.register::<Notice<'static>>(M::NOTICE);
+        this.register::<Part<'static>>(M::PART);
+        this.register::<Ping<'static>>(M::PING);
+        this.register::<Pong<'static>>(M::PONG);
+        this.register::<Privmsg<'static>>(M::PRIVMSG);
+        this.register::<Reconnect<'static>>(M::RECONNECT);
+        this.register::<RoomState<'static>>(M::ROOM_STATE);
+        #[cfg(feature = "usernotice")]
+        this.register::<UserNotice<'static>>(M::USER_NOTICE);
+        this.register::<UserState<'static>>(M::USER_STATE);
+        #[cfg(feature = "whispers")]
+        this.register::<Whisper<'static>>(M::WHISPER);
+        #[cfg(feature = "membership")]
+        this.register::<NamesReply<'static>>(M::NAMES_REPLY);
+        #[cfg(feature = "membership")]
+        this.register::<EndOfNames<'static>>(M::END_OF_NAMES);
+        this
+    }
+}
+
+impl MessageRegistry {
+    /// Create a registry without any parsers, so every command is parsed into an `IrcMessage<'static>`
+    pub fn empty() -> Self {
+        Self {
+            parsers: HashMap::new(),
+        }
+    }
+
+    /// Parse `command` into a `T`, replacing any previous parser for it
+    pub fn register<T>(&mut self, command: &str) -> &mut Self
+    where
+        T: FromIrcMessage<'static> + AnyMessage,
+        T::Error: Into<MessageError>,
+    {
+        self.register_fn(command, |msg| T::from_irc(msg).map_err(Into::into))
+    }
+
+    /// Parse `command` with this function, replacing any previous parser for it
+    pub fn register_fn<F, T>(&mut self, command: impl Into<String>, parse: F) -> &mut Self
+    where
+        F: Fn(IrcMessage<'static>) -> Result<T, MessageError> + Send + Sync + 'static,
+        T: AnyMessage,
+    {
+        let parse: ParseFn = Box::new(move |msg| {
+            let msg: Box<dyn AnyMessage> = Box::new(parse(msg)?);
+            Ok(msg)
+        });
+        self.parsers.insert(command.into(), parse);
+        self
+    }
+
+    /// Remove the parser for `command`, returning whether there was one
+    pub fn unregister(&mut self, command: &str) -> bool {
+        self.parsers.remove(command).is_some()
+    }
+
+    /// Is there a parser for `command`?
+    pub fn is_registered(&self, command: &str) -> bool {
+        self.parsers.contains_key(command)
+    }
+
+    /// Parse this message with the parser registered for its command.
+    ///
+    /// This accepts any message, e.g. the [Commands] yielded by a runner.
+    pub fn parse<'a, M>(&self, msg: M) -> Result<Box<dyn AnyMessage>, MessageError>
+    where
+        M: IntoIrcMessage<'a>,
+    {
+        let msg = msg.into_irc().into_owned();
+        match self.parsers.get(msg.get_command()) {
+            Some(parse) => parse(msg),
+            None => Ok(Box::new(msg)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(all(feature = "membership", feature = "moderation"))]
+    fn builtins() {
+        let registry = MessageRegistry::default();
+        let input = ":museun!museun@museun.tmi.twitch.tv JOIN #museun\r\n\
+                     :tmi.twitch.tv 353 justinfan1234 = #museun :museun\r\n\
+                     :tmi.twitch.tv CLEARCHAT #museun\r\n\
+                     :tmi.twitch.tv FOOBAR #museun\r\n";
+
+        let messages = crate::irc::parse(input)
+            .map(|msg| registry.parse(msg.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(messages[0].is::<Join<'static>>());
+        assert!(messages[1].is::<NamesReply<'static>>());
+        assert!(messages[2].is::<ClearChat<'static>>());
+        assert_eq!(
+            messages[3]
+                .downcast_ref::<IrcMessage<'static>>()
+                .unwrap()
+                .get_command(),
+            "FOOBAR"
+        );
+        assert!(messages[3].downcast_ref::<Join<'static>>().is_none());
+    }
+
+    #[test]
+    fn from_commands() {
+        let registry = MessageRegistry::default();
+        let input = ":museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n";
+        let msg = crate::irc::parse(input).next().unwrap().unwrap();
+        let msg = Commands::from_irc(msg).unwrap();
+
+        let msg = registry.parse(msg).unwrap();
+        let msg = msg.downcast::<Privmsg<'static>>().unwrap();
+        assert_eq!(msg.data(), "hello");
+    }
+
+    #[test]
+    fn replace_and_unregister() {
+        let input = "PING :1234\r\n";
+        let mut registry = MessageRegistry::default();
+        let msg = crate::irc::parse(input).next().unwrap().unwrap();
+        assert!(registry.parse(msg).unwrap().is::<Ping<'static>>());
+
+        registry.register_fn("PING", |msg| {
+            msg.get_data()
+                .map(|data| data.to_string())
+                .ok_or(MessageError::ExpectedData)
+        });
+        let msg = crate::irc::parse(input).next().unwrap().unwrap();
+        let msg = registry.parse(msg).unwrap();
+        assert_eq!(msg.downcast_ref::<String>().unwrap(), "1234");
+
+        assert!(registry.unregister("PING"));
+        assert!(!registry.is_registered("PING"));
+        let msg = crate::irc::parse(input).next().unwrap().unwrap();
+        assert!(registry.parse(msg).unwrap().is::<IrcMessage<'static>>());
+
+        let err = MessageRegistry::default()
+            .parse(
+                crate::irc::parse(":tmi.twitch.tv PRIVMSG\r\n")
+                    .next()
+                    .unwrap()
+                    .unwrap(),
+            )
+            .unwrap_err();
+        assert!(matches!(err, MessageError::ExpectedData));
+    }
+}
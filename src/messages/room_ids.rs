@@ -0,0 +1,125 @@
+use super::Commands;
+use std::collections::HashMap;
+
+/// A cache of channel names to room ids
+///
+/// Most channel-scoped messages carry a `room-id` tag, but some (e.g. `JOIN`,
+/// `PART`, `NOTICE` and often `USERSTATE`) don't. This remembers the room id of
+/// every channel it has seen a tagged message for, so every message can be
+/// keyed by room id.
+///
+/// ```
+/// # use twitchchat::{FromIrcMessage as _, messages::{Commands, RoomIds}};
+/// let parse = |input| {
+///     let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+///     Commands::from_irc(msg).unwrap()
+/// };
+///
+/// let mut room_ids = RoomIds::default();
+///
+/// let msg = parse("@room-id=23196011 :tmi.twitch.tv ROOMSTATE #museun\r\n");
+/// assert_eq!(room_ids.observe(&msg), Some(23196011));
+///
+/// let msg = parse(":museun!museun@museun.tmi.twitch.tv JOIN #museun\r\n");
+/// assert_eq!(msg.room_id(), None);
+/// assert_eq!(room_ids.resolve(&msg), Some(23196011));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RoomIds {
+    map: HashMap<String, u64>,
+}
+
+impl RoomIds {
+    /// Remember the room id of this message, if it has one.
+    ///
+    /// This returns the resolved room id of the message
+    pub fn observe(&mut self, msg: &Commands<'_>) -> Option<u64> {
+        let channel = msg.channel()?;
+        match msg.room_id() {
+            Some(room_id) => {
+                self.insert(channel, room_id);
+                Some(room_id)
+            }
+            None => self.get(channel),
+        }
+    }
+
+    /// Get the room id for this message, either from its tags or from the cache
+    pub fn resolve(&self, msg: &Commands<'_>) -> Option<u64> {
+        msg.room_id().or_else(|| self.get(msg.channel()?))
+    }
+
+    /// Get the room id for this channel
+    pub fn get(&self, channel: &str) -> Option<u64> {
+        self.map.get(channel).copied()
+    }
+
+    /// Set the room id for this channel
+    pub fn insert(&mut self, channel: &str, room_id: u64) {
+        match self.map.get_mut(channel) {
+            Some(old) => *old = room_id,
+            None => {
+                self.map.insert(channel.to_string(), room_id);
+            }
+        }
+    }
+
+    /// Forget the room id for this channel
+    pub fn remove(&mut self, channel: &str) -> Option<u64> {
+        self.map.remove(channel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irc::parse;
+    use crate::FromIrcMessage as _;
+
+    fn commands(input: &str) -> Commands<'_> {
+        let msg = parse(input).next().unwrap().unwrap();
+        Commands::from_irc(msg).unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "moderation")]
+    fn resolve() {
+        let mut room_ids = RoomIds::default();
+
+        let join = commands(":museun!museun@museun.tmi.twitch.tv JOIN #museun\r\n");
+        assert_eq!(room_ids.observe(&join), None);
+        assert_eq!(room_ids.resolve(&join), None);
+
+        let user_state = commands("@mod=0 :tmi.twitch.tv USERSTATE #museun\r\n");
+        assert_eq!(room_ids.observe(&user_state), None);
+
+        let pm = commands(
+            "@room-id=23196011 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n",
+        );
+        assert_eq!(room_ids.observe(&pm), Some(23196011));
+
+        assert_eq!(room_ids.resolve(&join), Some(23196011));
+        assert_eq!(room_ids.resolve(&user_state), Some(23196011));
+
+        // the tag always wins over the cache
+        let clear = commands("@room-id=1337 :tmi.twitch.tv CLEARCHAT #museun\r\n");
+        assert_eq!(room_ids.resolve(&clear), Some(1337));
+
+        // messages that aren't channel-scoped are never resolved
+        let ping = commands("PING :1234567890\r\n");
+        assert_eq!(room_ids.observe(&ping), None);
+
+        assert_eq!(room_ids.remove("#museun"), Some(23196011));
+        assert_eq!(room_ids.resolve(&join), None);
+    }
+
+    #[test]
+    fn invalid_room_id() {
+        let mut room_ids = RoomIds::default();
+        room_ids.insert("#museun", 23196011);
+
+        let msg = commands("@room-id=foo :tmi.twitch.tv ROOMSTATE #museun\r\n");
+        assert_eq!(msg.room_id(), None);
+        assert_eq!(room_ids.observe(&msg), Some(23196011));
+    }
+}
@@ -1,13 +1,11 @@
 use crate::irc::tags::ParsedTag;
 use crate::messages::tags::HasTags;
 use crate::{irc::*, MaybeOwned, MaybeOwnedIndex, Validator};
-use pipe_trait::Pipe;
 use std::time::Duration;
 use twitchchat_macros::irc_tags;
-use wrap_result::WrapOk;
 
 /// The parameters for a room being in follower-only mode
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub enum FollowersOnly {
     /// The mode is disabled
@@ -18,19 +16,16 @@ pub enum FollowersOnly {
     Limit(Duration),
 }
 
-// std::time::Duration doesn't implement FromStr, so we can't use parse_display::FromStr
 impl std::str::FromStr for FollowersOnly {
     type Err = std::num::ParseIntError;
     fn from_str(s: &str) -> Result<FollowersOnly, Self::Err> {
-        let minutes_to_duration = |x| Duration::from_secs(x * 86400);
-        let duration_to_limit = |x| FollowersOnly::Limit(x);
         match s {
-            "-1" => FollowersOnly::Disabled.wrap_ok(),
-            "0" => FollowersOnly::All.wrap_ok(),
-            s => u64::from_str(s)?
-                .pipe(minutes_to_duration)
-                .pipe(duration_to_limit)
-                .wrap_ok(),
+            "-1" => Ok(FollowersOnly::Disabled),
+            "0" => Ok(FollowersOnly::All),
+            s => {
+                let minutes = u64::from_str(s)?;
+                Ok(FollowersOnly::Limit(Duration::from_secs(minutes * 86400)))
+            }
         }
     }
 }
@@ -137,7 +132,6 @@ serde_struct!(RoomState { raw, tags, channel });
 mod tests {
     use super::*;
     use std::str::FromStr;
-    //use pipe_trait::Pipe;
     use assert2::assert;
     use crate::messages::tags::*;
 
@@ -163,18 +157,13 @@ mod tests {
     #[test]
     fn room_state_integrity() {
         let input = "@emote-only=0;followers-only=0;r9k=0;slow=0;subs-only=0 :tmi.twitch.tv ROOMSTATE #dallas\r\n";
-        let msg = parse(input)
-            .next()
-            .unwrap()
-            .unwrap()
-            .pipe(RoomState::from_irc)
-            .unwrap();
-
-        assert!(msg.emote_only().unwrap().unwrap() == false);
+        let msg = RoomState::from_irc(parse(input).next().unwrap().unwrap()).unwrap();
+
+        assert!(msg.is_emote_only() == false);
         assert!(msg.followers_only().unwrap().unwrap() == FollowersOnly::All);
-        assert!(msg.r9k().unwrap().unwrap() == true);
+        assert!(msg.is_r9k() == false);
         assert!(msg.slow().unwrap().unwrap() == 0);
-        assert!(msg.subs_only().unwrap().unwrap() == false);
+        assert!(msg.is_subs_only() == false);
         assert!(msg.channel() == "#dallas");
     }
 
@@ -0,0 +1,177 @@
+use super::{NoticeType, UserNotice};
+
+/// A subscription anniversary that alerts usually call out
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum SubMilestone {
+    /// 6 months
+    SixMonths,
+    /// 12 months
+    OneYear,
+    /// 24 months
+    TwoYears,
+    /// Every full year after the second, e.g. `Years(3)` for 36 months
+    Years(u64),
+}
+
+impl SubMilestone {
+    /// The milestone reached at exactly this many months, if any
+    ///
+    /// ```
+    /// # use twitchchat::messages::SubMilestone;
+    /// assert_eq!(SubMilestone::from_months(12), Some(SubMilestone::OneYear));
+    /// assert_eq!(SubMilestone::from_months(48), Some(SubMilestone::Years(4)));
+    /// assert_eq!(SubMilestone::from_months(13), None);
+    /// ```
+    pub fn from_months(months: u64) -> Option<Self> {
+        match months {
+            6 => Some(Self::SixMonths),
+            12 => Some(Self::OneYear),
+            24 => Some(Self::TwoYears),
+            months if months > 24 && months % 12 == 0 => Some(Self::Years(months / 12)),
+            _ => None,
+        }
+    }
+
+    /// The number of months of this milestone
+    pub fn months(&self) -> u64 {
+        match self {
+            Self::SixMonths => 6,
+            Self::OneYear => 12,
+            Self::TwoYears => 24,
+            Self::Years(years) => years.saturating_mul(12),
+        }
+    }
+
+    /// A label for this milestone, like `6 months` or `1 year`
+    pub fn label(&self) -> String {
+        match self {
+            Self::SixMonths => "6 months".to_string(),
+            Self::OneYear => "1 year".to_string(),
+            Self::TwoYears => "2 years".to_string(),
+            Self::Years(years) => format!("{} years", years),
+        }
+    }
+}
+
+impl std::fmt::Display for SubMilestone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.label())
+    }
+}
+
+/// A `sub` or `resub` notice that reached a [SubMilestone], see [UserNotice::sub_milestone]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubMilestoneEvent<'a> {
+    /// The channel
+    pub channel: &'a str,
+    /// The login of the subscriber
+    pub login: Option<&'a str>,
+    /// The name to show for the subscriber, their display name if they have one
+    pub name: Option<&'a str>,
+    /// The milestone that was reached
+    pub milestone: SubMilestone,
+    /// Whether it is the streak (consecutive months) that reached it,
+    /// rather than the total months
+    pub streak: bool,
+    /// A label for the alert, like `museun has been subscribed for 1 year`
+    pub label: String,
+}
+
+impl<'a> UserNotice<'a> {
+    /// The [SubMilestone] this `sub` or `resub` reached, if any
+    ///
+    /// The total months are checked first, then the streak if the user shared it.
+    ///
+    /// ```
+    /// # use twitchchat::{FromIrcMessage as _, messages::{UserNotice, SubMilestone}};
+    /// let input = "@display-name=Museun;login=museun;msg-id=resub;msg-param-cumulative-months=14;msg-param-should-share-streak=1;msg-param-streak-months=12 :tmi.twitch.tv USERNOTICE #shaken_bot\r\n";
+    /// let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+    /// let msg = UserNotice::from_irc(msg).unwrap();
+    ///
+    /// let event = msg.sub_milestone().unwrap();
+    /// assert_eq!(event.milestone, SubMilestone::OneYear);
+    /// assert!(event.streak);
+    /// assert_eq!(event.label, "Museun has been subscribed for 1 year in a row");
+    /// ```
+    pub fn sub_milestone(&'a self) -> Option<SubMilestoneEvent<'a>> {
+        // the months of a gift are the recipient's
+        match self.msg_id()?.ok()? {
+            NoticeType::Sub | NoticeType::Resub => {}
+            _ => return None,
+        }
+
+        let total = self
+            .months()
+            .and_then(SubMilestone::from_months)
+            .map(|milestone| (milestone, false));
+        let streak = || {
+            self.streak_months()
+                .and_then(SubMilestone::from_months)
+                .map(|milestone| (milestone, true))
+        };
+        let (milestone, streak) = total.or_else(streak)?;
+
+        let name = self.display_name().or_else(|| self.login());
+        let label = format!(
+            "{} has been subscribed for {}{}",
+            name.unwrap_or("someone"),
+            milestone,
+            if streak { " in a row" } else { "" }
+        );
+
+        Some(SubMilestoneEvent {
+            channel: self.channel(),
+            login: self.login(),
+            name,
+            milestone,
+            streak,
+            label,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irc::parse;
+    use crate::FromIrcMessage as _;
+
+    fn notice(input: &str) -> UserNotice<'_> {
+        let msg = parse(input).next().unwrap().unwrap();
+        UserNotice::from_irc(msg).unwrap()
+    }
+
+    #[test]
+    fn milestones() {
+        let msg = notice("@login=museun;msg-id=sub;msg-param-cumulative-months=6 :tmi.twitch.tv USERNOTICE #shaken_bot\r\n");
+        let event = msg.sub_milestone().unwrap();
+        assert_eq!(event.channel, "#shaken_bot");
+        assert_eq!(event.login, Some("museun"));
+        assert_eq!(event.milestone, SubMilestone::SixMonths);
+        assert!(!event.streak);
+        assert_eq!(event.label, "museun has been subscribed for 6 months");
+
+        // the total months win over the streak
+        let msg = notice("@login=museun;msg-id=resub;msg-param-cumulative-months=24;msg-param-should-share-streak=1;msg-param-streak-months=12 :tmi.twitch.tv USERNOTICE #shaken_bot\r\n");
+        let event = msg.sub_milestone().unwrap();
+        assert_eq!(event.milestone, SubMilestone::TwoYears);
+        assert!(!event.streak);
+
+        // the streak only counts when it was shared
+        let msg = notice("@msg-id=resub;msg-param-cumulative-months=13;msg-param-should-share-streak=0;msg-param-streak-months=12 :tmi.twitch.tv USERNOTICE #shaken_bot\r\n");
+        assert_eq!(msg.sub_milestone(), None);
+
+        let msg = notice("@msg-id=resub;msg-param-cumulative-months=36 :tmi.twitch.tv USERNOTICE #shaken_bot\r\n");
+        let event = msg.sub_milestone().unwrap();
+        assert_eq!(event.milestone, SubMilestone::Years(3));
+        assert_eq!(event.milestone.months(), 36);
+        assert_eq!(event.label, "someone has been subscribed for 3 years");
+
+        // gifts aren't milestones of the gifter
+        let msg =
+            notice("@msg-id=subgift;msg-param-months=12 :tmi.twitch.tv USERNOTICE #shaken_bot\r\n");
+        assert_eq!(msg.sub_milestone(), None);
+    }
+}
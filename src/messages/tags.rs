@@ -10,13 +10,15 @@ ban-duration: u64 to enum BanDuration{ Timeout(BoundedU32<1,1_209_600>) or Perma
 target-msg-id: &str to UUID
 emote-sets: Vec<&str> to Vec<u32>
 room-id: u64 to &str */
-use crate::messages::SubPlan;
-use crate::messages::{FollowersOnly, NoticeType};
+use crate::messages::FollowersOnly;
+#[cfg(feature = "usernotice")]
+use crate::messages::{NoticeType, SubPlan};
 use crate::twitch::{BadgeVec, Color, EmoteSet, EmoteVec, FlagVec};
 use twitchchat_macros::generate_tag_traits as init_tags;
 
 /// Trait that should be applied to all message struct that can contain tags.
 pub trait HasTags<'a> {
+    /// Get the tags of this message
     fn tags(&'a self) -> crate::irc::Tags<'a>;
 }
 
@@ -35,7 +37,6 @@ init_tags![
     "id",
     "login",
     "mod" as bool,
-    "msg-id" as NoticeType,
     "r9k" as bool,
     "room-id" as u64,
     "slow" as u64,
@@ -60,10 +61,13 @@ init_tags![
     "msg-param-sender-name",
     "msg-param-should-share-streak" as bool,
     "msg-param-streak-months" as u64,
-    "msg-param-sub-plan" as SubPlan,
     "msg-param-sub-plan-name",
     "msg-param-viewerCount" as u64,
     "msg-param-ritual-name",
     "msg-param-threshold" as u64,
     "msg-param-gift-months" as u64,
 ];
+
+// the tags whose types are only there with the `usernotice` feature
+#[cfg(feature = "usernotice")]
+init_tags!["msg-id" as NoticeType, "msg-param-sub-plan" as SubPlan];
@@ -0,0 +1,500 @@
+use crate::twitch::{Emote, EmoteVec, Flag, FlagVec};
+use std::borrow::Cow;
+
+/// The writing system of a letter, as far as chat in different languages needs to be told apart
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Script {
+    /// The Latin alphabet, with its accented letters
+    Latin,
+    /// The Cyrillic alphabet
+    Cyrillic,
+    /// Chinese, Japanese and Korean: Han characters, kana and Hangul
+    Cjk,
+}
+
+impl Script {
+    /// The script of this character, if it is a letter of one of the scripts
+    pub fn of(ch: char) -> Option<Self> {
+        let script = match ch {
+            'a'..='z'
+            | 'A'..='Z'
+            | '\u{00C0}'..='\u{00D6}'
+            | '\u{00D8}'..='\u{00F6}'
+            | '\u{00F8}'..='\u{024F}'
+            | '\u{1E00}'..='\u{1EFF}'
+            | '\u{FF21}'..='\u{FF3A}'
+            | '\u{FF41}'..='\u{FF5A}' => Self::Latin,
+            '\u{0400}'..='\u{052F}'
+            | '\u{1C80}'..='\u{1C8F}'
+            | '\u{2DE0}'..='\u{2DFF}'
+            | '\u{A640}'..='\u{A69F}' => Self::Cyrillic,
+            '\u{1100}'..='\u{11FF}'
+            | '\u{3040}'..='\u{30FF}'
+            | '\u{3130}'..='\u{318F}'
+            | '\u{31F0}'..='\u{31FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{AC00}'..='\u{D7AF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{FF66}'..='\u{FF9D}'
+            | '\u{FFA0}'..='\u{FFDC}'
+            | '\u{20000}'..='\u{2FA1F}' => Self::Cjk,
+            _ => return None,
+        };
+        Some(script)
+    }
+
+    /// The script most of the letters of this text are in.
+    ///
+    /// Digits, punctuation, emoji and letters of other scripts are ignored, so
+    /// this is `None` if there are no letters of these scripts
+    ///
+    /// ```
+    /// # use twitchchat::messages::Script;
+    /// assert_eq!(Script::detect("привет chat"), Some(Script::Cyrillic));
+    /// assert_eq!(Script::detect("こんにちは、みなさん Kappa"), Some(Script::Cjk));
+    /// assert_eq!(Script::detect("123 <3"), None);
+    /// ```
+    pub fn detect(text: &str) -> Option<Self> {
+        let mut counts = [0_usize; 3];
+        for script in text.chars().filter_map(Self::of) {
+            if let Some(count) = counts.get_mut(script as usize) {
+                *count += 1;
+            }
+        }
+        // ties go to the first script, so this is stable
+        let (index, &count) = counts
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|&(_, count)| count)?;
+        if count == 0 {
+            return None;
+        }
+        [Self::Latin, Self::Cyrillic, Self::Cjk].get(index).copied()
+    }
+}
+
+// the fullwidth forms of the halfwidth katakana from U+FF61, these are all in the BMP
+const HALFWIDTH_KATAKANA: [u16; 63] = [
+    0x3002, 0x300C, 0x300D, 0x3001, 0x30FB, 0x30F2, 0x30A1, 0x30A3, 0x30A5, 0x30A7, 0x30A9, 0x30E3,
+    0x30E5, 0x30E7, 0x30C3, 0x30FC, 0x30A2, 0x30A4, 0x30A6, 0x30A8, 0x30AA, 0x30AB, 0x30AD, 0x30AF,
+    0x30B1, 0x30B3, 0x30B5, 0x30B7, 0x30B9, 0x30BB, 0x30BD, 0x30BF, 0x30C1, 0x30C4, 0x30C6, 0x30C8,
+    0x30CA, 0x30CB, 0x30CC, 0x30CD, 0x30CE, 0x30CF, 0x30D2, 0x30D5, 0x30D8, 0x30DB, 0x30DE, 0x30DF,
+    0x30E0, 0x30E1, 0x30E2, 0x30E4, 0x30E6, 0x30E8, 0x30E9, 0x30EA, 0x30EB, 0x30EC, 0x30ED, 0x30EF,
+    0x30F3, 0x3099, 0x309A,
+];
+
+fn fold_char(ch: char) -> Option<char> {
+    let folded = match ch {
+        '\u{3000}' => ' ',
+        '\u{FF01}'..='\u{FF5E}' => std::char::from_u32(ch as u32 - 0xFEE0)?,
+        '\u{FF61}'..='\u{FF9F}' => {
+            std::char::from_u32((*HALFWIDTH_KATAKANA.get(ch as usize - 0xFF61)?).into())?
+        }
+        '\u{FFE0}' => '\u{00A2}',
+        '\u{FFE1}' => '\u{00A3}',
+        '\u{FFE2}' => '\u{00AC}',
+        '\u{FFE3}' => '\u{00AF}',
+        '\u{FFE4}' => '\u{00A6}',
+        '\u{FFE5}' => '\u{00A5}',
+        '\u{FFE6}' => '\u{20A9}',
+        _ => return None,
+    };
+    Some(folded)
+}
+
+// the katakana that have a voiced form (with a dakuten) right after them,
+// and the ones that also have a semi-voiced form (with a handakuten) after that
+const VOICED: &str = "カキクケコサシスセソタチツテトハヒフヘホ";
+const SEMI_VOICED: &str = "ハヒフヘホ";
+
+// a katakana with a (semi-)voiced sound mark after it, as a single character
+fn compose_voiced(base: char, mark: char) -> Option<char> {
+    let offset = match mark {
+        '\u{3099}' if base == 'ウ' => return Some('ヴ'),
+        '\u{3099}' if VOICED.contains(base) => 1,
+        '\u{309A}' if SEMI_VOICED.contains(base) => 2,
+        _ => return None,
+    };
+    std::char::from_u32(base as u32 + offset)
+}
+
+/// Replace the fullwidth forms of ASCII (e.g. `ｈｅｌｌｏ`) and the halfwidth
+/// forms of katakana (e.g. `ｶﾞ`) with their usual forms.
+///
+/// This is what compatibility normalization does for these forms, without
+/// changing anything else. The text is only copied if something was replaced
+///
+/// ```
+/// # use twitchchat::messages::fold_width;
+/// assert_eq!(fold_width("ＦＲＥＥ　ｂｉｔｓ！"), "FREE bits!");
+/// assert_eq!(fold_width("ｶﾞﾝﾊﾞﾚ"), "ガンバレ");
+/// ```
+pub fn fold_width(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(|ch| fold_char(ch).is_some()) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        let ch = fold_char(ch).unwrap_or(ch);
+        if let Some(composed) = out.chars().last().and_then(|base| compose_voiced(base, ch)) {
+            out.pop();
+            out.push(composed);
+        } else {
+            out.push(ch);
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Normalize this text to Unicode Normalization Form C, so that e.g. an `é`
+/// written as `e` and a combining accent is the same as a single `é`.
+///
+/// The text is only copied if it wasn't normalized already
+#[cfg(feature = "unicode-normalization")]
+#[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+pub fn nfc(text: &str) -> Cow<'_, str> {
+    use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization as _};
+    match is_nfc_quick(text.chars()) {
+        IsNormalized::Yes => Cow::Borrowed(text),
+        _ => Cow::Owned(text.nfc().collect()),
+    }
+}
+
+/// Normalize this text before comparing it with other messages, e.g. for
+/// finding duplicates or spam across channels in different languages.
+///
+/// This [folds the width](fold_width) of the text, and with the
+/// `unicode-normalization` feature also normalizes it to [NFC](nfc).
+/// Case and whitespace are left alone.
+///
+/// ```
+/// # use twitchchat::messages::{content_hash, normalize};
+/// let hash = |data| content_hash("#museun", "foo", &normalize(data), None);
+/// assert_eq!(hash("ｆｏｌｌｏｗ　ｍｅ"), hash("follow me"));
+/// ```
+pub fn normalize(text: &str) -> Cow<'_, str> {
+    let folded = fold_width(text);
+    #[cfg(feature = "unicode-normalization")]
+    {
+        if let Cow::Owned(normalized) = nfc(&folded) {
+            return Cow::Owned(normalized);
+        }
+    }
+    folded
+}
+
+/// A message shortened by [truncate], with its emotes and flags adjusted to
+/// the shorter text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Truncated<'a> {
+    /// The visible text
+    pub data: &'a str,
+    /// The emotes that are fully visible
+    pub emotes: EmoteVec,
+    /// The flags on the visible text, cut off where the text ends
+    pub flags: FlagVec,
+    /// Whether any text was cut off, e.g. to add an ellipsis
+    pub is_truncated: bool,
+}
+
+/// Shorten `data` to at most `max_chars` characters for display, keeping only
+/// the `emotes` and `flags` that are still in range.
+///
+/// An emote is never cut in half: if the limit falls inside one, the text ends
+/// before it. A flag that runs past the end is shortened to the visible text.
+/// Ranges that were out of bounds to begin with are dropped too, so the result
+/// can be rendered without checking them again.
+///
+/// ```
+/// # use twitchchat::{messages::truncate, twitch::{EmoteVec, FlagVec}};
+/// let emotes: EmoteVec = "25:0-4,12-16".parse().unwrap();
+/// let flags: FlagVec = "6-10:P.6".parse().unwrap();
+///
+/// let short = truncate("Kappa frick Kappa", 13, &emotes, &flags);
+/// assert_eq!(short.data, "Kappa frick ");
+/// assert!(short.is_truncated);
+/// assert_eq!(*short.emotes, *"25:0-4".parse::<EmoteVec>().unwrap());
+/// assert_eq!(short.flags, flags);
+/// ```
+pub fn truncate<'a>(
+    data: &'a str,
+    max_chars: usize,
+    emotes: &[Emote],
+    flags: &[Flag],
+) -> Truncated<'a> {
+    // twitch uses inclusive char (not byte) ranges
+    let len = data.chars().count();
+    let mut end = len.min(max_chars);
+
+    let emote_ranges = || emotes.iter().flat_map(|emote| &emote.ranges);
+    while let Some(range) =
+        emote_ranges().find(|range| (range.start as usize) < end && range.end as usize >= end)
+    {
+        end = range.start as usize;
+    }
+
+    let emotes = emotes
+        .iter()
+        .filter_map(|emote| {
+            let ranges = emote
+                .ranges
+                .iter()
+                .filter(|range| range.start <= range.end && (range.end as usize) < end)
+                .cloned()
+                .collect::<Vec<_>>();
+            if ranges.is_empty() {
+                return None;
+            }
+            Some(Emote {
+                id: emote.id.clone(),
+                ranges,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let flags = flags
+        .iter()
+        .filter_map(|flag| {
+            let range = flag.range();
+            if range.start > range.end || range.start as usize >= end {
+                return None;
+            }
+            let last = range.end.min((end - 1) as u16);
+            let scores = flag.scores().to_vec();
+            Some(Flag::new((range.start..last).into(), scores))
+        })
+        .collect::<Vec<_>>();
+
+    let cut = data
+        .char_indices()
+        .nth(end)
+        .map_or(data.len(), |(index, _)| index);
+
+    Truncated {
+        data: data.get(..cut).unwrap_or(data),
+        emotes: emotes.into(),
+        flags: flags.into(),
+        is_truncated: end < len,
+    }
+}
+
+/// How [mask] hides a flagged term
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum MaskStyle {
+    /// Replace each character with an `*`, keeping the whitespace.
+    ///
+    /// The length of the text doesn't change, so the emote ranges still fit
+    Asterisks,
+    /// Remove the term from the text
+    Remove,
+}
+
+/// Which flagged terms [mask] hides, and how
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MaskOptions {
+    /// Terms with a score of at least this severity are hidden
+    pub min_severity: u8,
+    /// Whether to hide terms that were flagged without a score, e.g. links
+    pub unscored: bool,
+    /// How to hide them
+    pub style: MaskStyle,
+}
+
+impl Default for MaskOptions {
+    /// Hide every term that automod scored, with asterisks
+    fn default() -> Self {
+        Self {
+            min_severity: 1,
+            unscored: false,
+            style: MaskStyle::Asterisks,
+        }
+    }
+}
+
+impl MaskOptions {
+    /// Whether this flag is hidden with these options
+    pub fn is_masked(&self, flag: &Flag) -> bool {
+        match flag.scores() {
+            [] => self.unscored,
+            scores => scores
+                .iter()
+                .any(|score| score.severity() >= self.min_severity),
+        }
+    }
+}
+
+/// Hide the terms automod flagged in `data`, e.g. for a family friendly overlay.
+///
+/// Only the `flags` that are [masked](MaskOptions::is_masked) by the `options`
+/// are hidden. The text is only copied if anything was hidden.
+///
+/// ```
+/// # use twitchchat::{messages::{mask, MaskOptions, MaskStyle}, twitch::FlagVec};
+/// let flags: FlagVec = "0-3:P.6,10-12:P.3".parse().unwrap();
+///
+/// let options = MaskOptions { min_severity: 5, ..MaskOptions::default() };
+/// assert_eq!(mask("LMAO Poki wtf", &flags, &options), "**** Poki wtf");
+///
+/// let options = MaskOptions { style: MaskStyle::Remove, ..MaskOptions::default() };
+/// assert_eq!(mask("LMAO Poki wtf", &flags, &options), " Poki ");
+/// ```
+pub fn mask<'a>(data: &'a str, flags: &[Flag], options: &MaskOptions) -> Cow<'a, str> {
+    // twitch uses inclusive char (not byte) ranges
+    let ranges = flags
+        .iter()
+        .filter(|flag| options.is_masked(flag))
+        .map(|flag| flag.range().start as usize..=flag.range().end as usize)
+        .collect::<Vec<_>>();
+
+    let is_masked = |index| ranges.iter().any(|range| range.contains(&index));
+    if !data.chars().enumerate().any(|(index, _)| is_masked(index)) {
+        return Cow::Borrowed(data);
+    }
+
+    let mut out = String::with_capacity(data.len());
+    for (index, ch) in data.chars().enumerate() {
+        match options.style {
+            _ if !is_masked(index) => out.push(ch),
+            MaskStyle::Asterisks if ch.is_whitespace() => out.push(ch),
+            MaskStyle::Asterisks => out.push('*'),
+            MaskStyle::Remove => {}
+        }
+    }
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_of() {
+        assert_eq!(Script::of('é'), Some(Script::Latin));
+        assert_eq!(Script::of('×'), None);
+        assert_eq!(Script::of('ж'), Some(Script::Cyrillic));
+        assert_eq!(Script::of('漢'), Some(Script::Cjk));
+        assert_eq!(Script::of('한'), Some(Script::Cjk));
+        assert_eq!(Script::of('ｶ'), Some(Script::Cjk));
+        assert_eq!(Script::of('1'), None);
+        assert_eq!(Script::of('😀'), None);
+    }
+
+    #[test]
+    fn detect() {
+        assert_eq!(Script::detect(""), None);
+        assert_eq!(Script::detect("ab жжж"), Some(Script::Cyrillic));
+        // ties go to the first script
+        assert_eq!(Script::detect("ab жж 漢字"), Some(Script::Latin));
+        assert_eq!(Script::detect("Kappa 你好你好你好"), Some(Script::Cjk));
+    }
+
+    #[test]
+    fn width() {
+        assert!(matches!(fold_width("hello ガ"), Cow::Borrowed(..)));
+        assert_eq!(fold_width("～ｚ０￥"), "~z0¥");
+        assert_eq!(fold_width("ｳﾞｨ ﾊﾟﾊﾞ ｹﾞｰﾑ"), "ヴィ パバ ゲーム");
+        // a mark that can't be composed is kept
+        assert_eq!(fold_width("ｱﾞ"), "ア\u{3099}");
+        assert_eq!(fold_width("ﾞ"), "\u{3099}");
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-normalization")]
+    fn normalization() {
+        assert!(matches!(nfc("caf\u{e9}"), Cow::Borrowed(..)));
+        assert_eq!(nfc("cafe\u{301}"), "caf\u{e9}");
+        assert_eq!(normalize("ｃａｆｅ\u{301}"), "caf\u{e9}");
+        assert_eq!(normalize("ｱﾞ"), "ア\u{3099}");
+    }
+
+    #[test]
+    fn masking() {
+        let flags: FlagVec = "9-12:A.6/I.6,15-18:S.3,21-31".parse().unwrap();
+        let data = "I have a spaz, sexy! example.com";
+
+        let default = MaskOptions::default();
+        assert_eq!(
+            mask(data, &flags, &default),
+            "I have a ****, ****! example.com"
+        );
+
+        let severe = MaskOptions {
+            min_severity: 5,
+            ..default
+        };
+        assert_eq!(
+            mask(data, &flags, &severe),
+            "I have a ****, sexy! example.com"
+        );
+
+        let links = MaskOptions {
+            unscored: true,
+            style: MaskStyle::Remove,
+            ..default
+        };
+        assert_eq!(mask(data, &flags, &links), "I have a , ! ");
+
+        // nothing to hide, so nothing is copied
+        let severe = MaskOptions {
+            min_severity: 7,
+            ..default
+        };
+        assert!(matches!(mask(data, &flags, &severe), Cow::Borrowed(..)));
+        assert!(matches!(mask(data, &[], &default), Cow::Borrowed(..)));
+
+        // ranges are in chars, and out of bounds ranges are ignored
+        let flags: FlagVec = "3-4:P.6,20-25:P.6".parse().unwrap();
+        assert_eq!(mask("日本語ab c", &flags, &default), "日本語** c");
+    }
+
+    #[test]
+    fn truncation() {
+        let emotes: EmoteVec = "25:0-4,12-16/1902:6-10".parse().unwrap();
+        let flags: FlagVec = "6-10:P.6,13-14:A.3".parse().unwrap();
+        let data = "Kappa Keepo Kappa";
+
+        // everything fits
+        let all = truncate(data, 17, &emotes, &flags);
+        assert_eq!(all.data, data);
+        assert!(!all.is_truncated);
+        assert_eq!(all.emotes, emotes);
+        assert_eq!(all.flags, flags);
+
+        // an emote isn't cut in half, and the rest of it is dropped
+        let short = truncate(data, 13, &emotes, &flags);
+        assert_eq!(short.data, "Kappa Keepo ");
+        assert!(short.is_truncated);
+        assert_eq!(short.emotes, "25:0-4/1902:6-10".parse().unwrap());
+        assert_eq!(short.flags, "6-10:P.6".parse().unwrap());
+
+        // a flag is cut off with the text
+        let flags: FlagVec = "3-8:P.6".parse().unwrap();
+        let short = truncate("frick off", 5, &[], &flags);
+        assert_eq!(short.data, "frick");
+        assert_eq!(short.flags, "3-4:P.6".parse().unwrap());
+
+        // ranges are in chars, not bytes
+        let emotes: EmoteVec = "25:3-7".parse().unwrap();
+        let short = truncate("日本語Kappa日本語", 8, &emotes, &[]);
+        assert_eq!(short.data, "日本語Kappa");
+        assert_eq!(short.emotes, emotes);
+        assert_eq!(truncate("日本語Kappa", 5, &emotes, &[]).data, "日本語");
+
+        // out of bounds ranges are dropped even if nothing was cut
+        let emotes: EmoteVec = "25:0-4,10-14".parse().unwrap();
+        let short = truncate("Kappa", 10, &emotes, &[]);
+        assert_eq!(short.data, "Kappa");
+        assert!(!short.is_truncated);
+        assert_eq!(short.emotes, "25:0-4".parse().unwrap());
+
+        assert_eq!(truncate("Kappa", 0, &emotes, &[]).data, "");
+        assert_eq!(truncate("", 10, &emotes, &[]).emotes.len(), 0);
+    }
+}
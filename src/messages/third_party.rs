@@ -0,0 +1,255 @@
+use crate::twitch::{Emote, MsgRange};
+use std::{collections::HashMap, convert::TryFrom};
+
+/// A third-party emote service
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum EmoteProvider {
+    /// BetterTTV
+    Bttv,
+    /// FrankerFaceZ
+    Ffz,
+    /// 7TV
+    SevenTv,
+    /// Any other service, by the prefix of its emote ids
+    Other(String),
+}
+
+impl EmoteProvider {
+    /// The prefix of the ids of this service's emotes, e.g. `bttv` for `bttv:54fa8f1401e468494b85b537`
+    pub fn prefix(&self) -> &str {
+        match self {
+            Self::Bttv => "bttv",
+            Self::Ffz => "ffz",
+            Self::SevenTv => "7tv",
+            Self::Other(prefix) => prefix,
+        }
+    }
+
+    /// Split the id of a third-party emote into its service and the id on that service.
+    ///
+    /// Twitch's emote ids don't have a `:`, so this is `None` for them.
+    ///
+    /// ```
+    /// # use twitchchat::messages::EmoteProvider;
+    /// assert_eq!(
+    ///     EmoteProvider::split_id("7tv:60ae3e54259ac5a73e56a426"),
+    ///     Some((EmoteProvider::SevenTv, "60ae3e54259ac5a73e56a426"))
+    /// );
+    /// assert_eq!(EmoteProvider::split_id("25"), None);
+    /// ```
+    pub fn split_id(id: &str) -> Option<(Self, &str)> {
+        let (prefix, id) = id.split_once(':')?;
+        let provider = match prefix {
+            "bttv" => Self::Bttv,
+            "ffz" => Self::Ffz,
+            "7tv" => Self::SevenTv,
+            prefix => Self::Other(prefix.to_string()),
+        };
+        Some((provider, id))
+    }
+
+    fn emote_id(&self, id: &str) -> String {
+        format!("{}:{}", self.prefix(), id)
+    }
+}
+
+/// The emotes of third-party services (e.g. BTTV, FFZ and 7TV), by the word that shows them.
+///
+/// Twitch doesn't know about these emotes, so they aren't in the `emotes`
+/// tag. This finds them in the text of a message, as [Emote]s with the id
+/// `prefix:id` (see [EmoteProvider::split_id]). They can be rendered with
+/// the Twitch emotes, see [Privmsg::parts_with](super::Privmsg::parts_with).
+///
+/// An emote is a whole word of the message, and is case-sensitive. The
+/// emotes of a channel take precedence over the global ones.
+///
+/// ```
+/// # use twitchchat::{FromIrcMessage as _, messages::{EmoteProvider, MessagePart, Privmsg, ThirdPartyEmotes}};
+/// let mut emotes = ThirdPartyEmotes::default();
+/// emotes.insert_global(EmoteProvider::Bttv, "catJAM", "5f1b0186cf6d2144653d2970");
+/// emotes.insert("#museun", EmoteProvider::SevenTv, "peepoHey", "60ae3e54259ac5a73e56a426");
+///
+/// let input = "@emotes=25:0-4 :museun!museun@museun PRIVMSG #museun :Kappa peepoHey catJAM\r\n";
+/// let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+/// let msg = Privmsg::from_irc(msg).unwrap();
+///
+/// assert_eq!(msg.parts_with(&emotes).parts, vec![
+///     MessagePart::Emote { id: "25".into(), text: "Kappa" },
+///     MessagePart::Text(" "),
+///     MessagePart::Emote { id: "7tv:60ae3e54259ac5a73e56a426".into(), text: "peepoHey" },
+///     MessagePart::Text(" "),
+///     MessagePart::Emote { id: "bttv:5f1b0186cf6d2144653d2970".into(), text: "catJAM" },
+/// ]);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ThirdPartyEmotes {
+    global: HashMap<String, String>,
+    channels: HashMap<String, HashMap<String, String>>,
+}
+
+impl ThirdPartyEmotes {
+    /// Show this emote for the word `code` on every channel, replacing any previous one
+    pub fn insert_global(&mut self, provider: EmoteProvider, code: impl Into<String>, id: &str) {
+        self.global.insert(code.into(), provider.emote_id(id));
+    }
+
+    /// Show this emote for the word `code` on this channel, replacing any previous one
+    pub fn insert(
+        &mut self,
+        channel: &str,
+        provider: EmoteProvider,
+        code: impl Into<String>,
+        id: &str,
+    ) {
+        self.channels
+            .entry(channel.to_string())
+            .or_default()
+            .insert(code.into(), provider.emote_id(id));
+    }
+
+    /// Forget the emotes of this channel, e.g. before inserting its new ones
+    pub fn remove_channel(&mut self, channel: &str) {
+        self.channels.remove(channel);
+    }
+
+    /// The id of the emote shown for the word `code` on this channel
+    pub fn get(&self, channel: &str, code: &str) -> Option<&str> {
+        self.channels
+            .get(channel)
+            .and_then(|emotes| emotes.get(code))
+            .or_else(|| self.global.get(code))
+            .map(String::as_str)
+    }
+
+    /// Find the emotes in the text of a message on this channel.
+    ///
+    /// The ranges are in characters, like the ones of the `emotes` tag.
+    pub fn annotate(&self, channel: &str, data: &str) -> Vec<Emote> {
+        let mut emotes = Vec::<Emote>::new();
+        for (start, word) in words(data) {
+            let id = match self.get(channel, word) {
+                Some(id) => id,
+                None => continue,
+            };
+
+            let end = start + word.chars().count() - 1;
+            let range = match (u16::try_from(start), u16::try_from(end)) {
+                (Ok(start), Ok(end)) => MsgRange::from(start..end),
+                _ => break,
+            };
+
+            match emotes.iter_mut().find(|emote| emote.id == id) {
+                Some(emote) => emote.ranges.push(range),
+                None => emotes.push(Emote {
+                    id: id.to_string(),
+                    ranges: vec![range],
+                }),
+            }
+        }
+        emotes
+    }
+}
+
+// the whitespace separated words, with the char index they start at
+fn words(data: &str) -> impl Iterator<Item = (usize, &str)> + '_ {
+    let mut start = None;
+    data.char_indices()
+        .chain(std::iter::once((data.len(), ' ')))
+        .enumerate()
+        .filter_map(move |(pos, (i, ch))| match (start, ch.is_whitespace()) {
+            (None, false) => {
+                start.replace((pos, i));
+                None
+            }
+            (Some((pos, begin)), true) => {
+                start.take();
+                data.get(begin..i).map(|word| (pos, word))
+            }
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::{MessagePart, Privmsg};
+    use crate::FromIrcMessage as _;
+
+    fn emotes() -> ThirdPartyEmotes {
+        let mut emotes = ThirdPartyEmotes::default();
+        emotes.insert_global(EmoteProvider::Bttv, "catJAM", "cat");
+        emotes.insert_global(EmoteProvider::Ffz, "OMEGALUL", "omega");
+        emotes.insert("#museun", EmoteProvider::SevenTv, "catJAM", "seven");
+        emotes.insert("#museun", EmoteProvider::Other("x".into()), "Kappa", "k");
+        emotes
+    }
+
+    #[test]
+    fn words() {
+        let words = super::words("  héllo  wörld\tx ").collect::<Vec<_>>();
+        assert_eq!(words, vec![(2, "héllo"), (9, "wörld"), (15, "x")]);
+        assert_eq!(super::words("").count(), 0);
+    }
+
+    #[test]
+    fn annotate() {
+        let emotes = emotes();
+        assert_eq!(emotes.get("#museun", "catJAM"), Some("7tv:seven"));
+        assert_eq!(emotes.get("#other", "catJAM"), Some("bttv:cat"));
+        assert_eq!(emotes.get("#other", "catjam"), None);
+
+        let found = emotes.annotate("#other", "ñ catJAM OMEGALUL catJAM catJAMs");
+        assert_eq!(
+            found,
+            vec![
+                Emote {
+                    id: "bttv:cat".into(),
+                    ranges: vec![(2..7).into(), (18..23).into()],
+                },
+                Emote {
+                    id: "ffz:omega".into(),
+                    ranges: vec![(9..16).into()],
+                },
+            ]
+        );
+
+        assert!(emotes.annotate("#other", "catJAM!").is_empty());
+
+        let mut emotes = emotes;
+        emotes.remove_channel("#museun");
+        assert_eq!(emotes.get("#museun", "catJAM"), Some("bttv:cat"));
+    }
+
+    #[test]
+    fn parts_with() {
+        let input = "@emotes=25:0-4 :museun!museun@museun PRIVMSG #museun :\x01ACTION Kappa catJAM OMEGALUL\x01\r\n";
+        let msg = crate::irc::parse(input).next().unwrap().unwrap();
+        let msg = Privmsg::from_irc(msg).unwrap();
+
+        // twitch's emote wins over the channel's `Kappa`
+        let parts = msg.parts_with(&emotes());
+        assert!(parts.is_action);
+        assert_eq!(
+            parts.parts,
+            vec![
+                MessagePart::Emote {
+                    id: "25".into(),
+                    text: "Kappa"
+                },
+                MessagePart::Text(" "),
+                MessagePart::Emote {
+                    id: "7tv:seven".into(),
+                    text: "catJAM"
+                },
+                MessagePart::Text(" "),
+                MessagePart::Emote {
+                    id: "ffz:omega".into(),
+                    text: "OMEGALUL"
+                },
+            ]
+        );
+        assert!(parts.is_emote_only());
+    }
+}
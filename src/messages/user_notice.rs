@@ -1,38 +1,54 @@
+use super::{EventFields, EventFieldsError};
 use crate::irc::tags::ParsedTag;
-use crate::twitch::{Attribution, AttributionVec, BadgeVec, Color, EmoteVec, FlagVec};
-use crate::{irc::*, MaybeOwned, MaybeOwnedIndex, Validator};
-use parse_display::FromStr;
+use crate::twitch::{
+    Attribution, AttributionVec, BadgeVec, Color, EmoteVec, FlagVec, MonetaryValue, UserType,
+};
+use crate::{irc::*, twitch::ParseError, MaybeOwned, MaybeOwnedIndex, Validator};
 use std::str::FromStr;
 
 /// A paid subscription ot the channel
 #[non_exhaustive]
-#[derive(Clone, Debug, PartialEq, Hash, FromStr)]
+#[derive(Clone, Debug, PartialEq, Hash, Eq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub enum SubPlan {
     /// A `Prime` subscription
     Prime,
 
     /// A Tier-1 subscription (currently $4.99)
-    #[from_str(regex = "1000")]
     Tier1,
 
     /// A Tier-2 subscription (currently $9.99)
-    #[from_str(regex = "2000")]
     Tier2,
 
     /// A Tier-3 subscription (currently $24.99)
-    #[from_str(regex = "3000")]
     Tier3,
 
     /// An unknown tier -- this will catch and future tiers if they are added.
-    #[display("{0}")]
     Unknown(String),
 }
 
+/// Parses the `msg-param-sub-plan` tag, `Prime`, `1000`, `2000` or `3000`.
+///
+/// The `Tier1` to `Tier3` names are also read. Anything else is
+/// [SubPlan::Unknown], so this never fails.
+impl FromStr for SubPlan {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let plan = match input {
+            "Prime" => Self::Prime,
+            "1000" | "Tier1" => Self::Tier1,
+            "2000" | "Tier2" => Self::Tier2,
+            "3000" | "Tier3" => Self::Tier3,
+            plan => Self::Unknown(plan.to_string()),
+        };
+        Ok(plan)
+    }
+}
+
 /// The kind of notice it was, retrieved via [UserNotice::msg_id()]
 #[non_exhaustive]
-#[derive(Clone, Debug, PartialEq, Hash, FromStr)]
-#[display(style = "lowercase")]
+#[derive(Clone, Debug, PartialEq, Hash, Eq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub enum NoticeType {
     /// This was a subscription notice
@@ -59,11 +75,168 @@ pub enum NoticeType {
     Ritual,
     /// A the tier that the bits were part of
     BitsBadgeTier,
+    /// A viewer reached a milestone, e.g. a watch streak
+    ViewerMilestone,
     /// An unknown notice type (a catch-all)
-    #[display("{0}")]
     Unknown(String),
 }
 
+impl std::fmt::Display for NoticeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg_id = match self {
+            Self::Sub => "sub",
+            Self::Resub => "resub",
+            Self::SubGift => "subgift",
+            Self::AnonSubGift => "anonsubgift",
+            Self::SubMysteryGift => "submysterygift",
+            Self::GiftPaidUpgrade => "giftpaidupgrade",
+            Self::RewardGift => "rewardgift",
+            Self::AnonGiftPaidUpgrade => "anongiftpaidupgrade",
+            Self::Raid => "raid",
+            Self::Unraid => "unraid",
+            Self::Ritual => "ritual",
+            Self::BitsBadgeTier => "bitsbadgetier",
+            Self::ViewerMilestone => "viewermilestone",
+            Self::Unknown(msg_id) => msg_id,
+        };
+        f.write_str(msg_id)
+    }
+}
+
+/// Anything that isn't a known `msg-id` is [NoticeType::Unknown], so this never fails.
+impl FromStr for NoticeType {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let kind = match input {
+            "sub" => Self::Sub,
+            "resub" => Self::Resub,
+            "subgift" => Self::SubGift,
+            "anonsubgift" => Self::AnonSubGift,
+            "submysterygift" => Self::SubMysteryGift,
+            "giftpaidupgrade" => Self::GiftPaidUpgrade,
+            "rewardgift" => Self::RewardGift,
+            "anongiftpaidupgrade" => Self::AnonGiftPaidUpgrade,
+            "raid" => Self::Raid,
+            "unraid" => Self::Unraid,
+            "ritual" => Self::Ritual,
+            "bitsbadgetier" => Self::BitsBadgeTier,
+            "viewermilestone" => Self::ViewerMilestone,
+            msg_id => Self::Unknown(msg_id.to_string()),
+        };
+        Ok(kind)
+    }
+}
+
+impl NoticeType {
+    /// The `msg-param-*` tags Twitch documents as always being sent with this kind of notice.
+    ///
+    /// This is empty for [NoticeType::Unknown].
+    pub fn expected_msg_params(&self) -> &'static [&'static str] {
+        match self {
+            Self::Sub | Self::Resub => &[
+                "msg-param-cumulative-months",
+                "msg-param-should-share-streak",
+                "msg-param-sub-plan",
+                "msg-param-sub-plan-name",
+            ],
+            Self::SubGift | Self::AnonSubGift => &[
+                "msg-param-months",
+                "msg-param-recipient-display-name",
+                "msg-param-recipient-id",
+                "msg-param-recipient-user-name",
+                "msg-param-sub-plan",
+                "msg-param-sub-plan-name",
+            ],
+            Self::SubMysteryGift => &["msg-param-mass-gift-count", "msg-param-sub-plan"],
+            Self::GiftPaidUpgrade => &["msg-param-sender-login", "msg-param-sender-name"],
+            Self::Raid => &[
+                "msg-param-displayName",
+                "msg-param-login",
+                "msg-param-viewerCount",
+            ],
+            Self::Ritual => &["msg-param-ritual-name"],
+            Self::BitsBadgeTier => &["msg-param-threshold"],
+            Self::ViewerMilestone => &["msg-param-category", "msg-param-value"],
+            Self::AnonGiftPaidUpgrade | Self::RewardGift | Self::Unraid | Self::Unknown(_) => &[],
+        }
+    }
+
+    /// The `msg-param-*` tags that Twitch may additionally send with this kind of notice.
+    ///
+    /// This is empty for [NoticeType::Unknown].
+    pub fn optional_msg_params(&self) -> &'static [&'static str] {
+        match self {
+            Self::Sub | Self::Resub => &[
+                "msg-param-anon-gift",
+                "msg-param-gift-month-being-redeemed",
+                "msg-param-gift-months",
+                "msg-param-gifter-id",
+                "msg-param-gifter-login",
+                "msg-param-gifter-name",
+                "msg-param-months",
+                "msg-param-multimonth-duration",
+                "msg-param-multimonth-tenure",
+                "msg-param-streak-months",
+                "msg-param-was-gifted",
+            ],
+            Self::SubGift | Self::AnonSubGift => &[
+                "msg-param-community-gift-id",
+                "msg-param-fun-string",
+                "msg-param-gift-months",
+                "msg-param-goal-contribution-type",
+                "msg-param-goal-current-contributions",
+                "msg-param-goal-description",
+                "msg-param-goal-target-contributions",
+                "msg-param-goal-user-contributions",
+                "msg-param-origin-id",
+                "msg-param-sender-count",
+            ],
+            Self::SubMysteryGift => &[
+                "msg-param-community-gift-id",
+                "msg-param-goal-contribution-type",
+                "msg-param-goal-current-contributions",
+                "msg-param-goal-description",
+                "msg-param-goal-target-contributions",
+                "msg-param-goal-user-contributions",
+                "msg-param-origin-id",
+                "msg-param-sender-count",
+            ],
+            Self::GiftPaidUpgrade | Self::AnonGiftPaidUpgrade => {
+                &["msg-param-promo-gift-total", "msg-param-promo-name"]
+            }
+            Self::RewardGift => &[
+                "msg-param-domain",
+                "msg-param-selected-count",
+                "msg-param-total-reward-count",
+                "msg-param-trigger-amount",
+                "msg-param-trigger-type",
+            ],
+            Self::Raid => &["msg-param-profileImageURL"],
+            Self::ViewerMilestone => &["msg-param-copoReward", "msg-param-id"],
+            Self::Ritual | Self::BitsBadgeTier | Self::Unraid | Self::Unknown(_) => &[],
+        }
+    }
+}
+
+/// The result of checking the `msg-param-*` tags of a [UserNotice] against its [NoticeType].
+///
+/// Retrieved via [UserNotice::validate_for_type()]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MsgParamReport<'a> {
+    /// Expected tags that weren't found on the message
+    pub missing: Vec<&'static str>,
+    /// `msg-param-*` tags that were found, but aren't known for this notice type (sorted by name)
+    pub extra: Vec<&'a str>,
+}
+
+impl<'a> MsgParamReport<'a> {
+    /// Whether the message had exactly the tags that were expected for its type
+    pub fn is_valid(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty()
+    }
+}
+
 /// Announces Twitch-specific events to the channel (e.g., a user's subscription notification).
 #[derive(Clone, PartialEq)]
 pub struct UserNotice<'a> {
@@ -110,19 +283,38 @@ impl<'a> UserNotice<'a> {
         self.tag_to_attribution_vec("badge-info")
     }
 
+    /// How many months the user has been subscribed to the channel, from the `badge-info` tag
+    pub fn subscriber_months(&self) -> Option<u32> {
+        self.tags()
+            .get("badge-info")
+            .and_then(crate::twitch::subscriber_months)
+    }
+
     /// Badges attached to this message
     pub fn badges(&'a self) -> BadgeVec {
         self.tag_to_attribution_vec("badges")
     }
 
     /// The user's color, if set
+    ///
+    /// An empty `color=` tag is treated like a missing one
     pub fn color(&self) -> Option<ParsedTag<Color>> {
-        self.tags().get_parsed("color")
+        let tags = self.tags();
+        tags.get_non_empty("color")?;
+        tags.get_parsed("color")
     }
 
     /// The user's display name, if set
     pub fn display_name(&self) -> Option<&str> {
-        self.tags().get("display-name")
+        self.tags().get_non_empty("display-name")
+    }
+
+    /// The `user-type` of the user.
+    ///
+    /// Ordinary users have an empty tag, which is `Some(UserType::Normal)`, while
+    /// `None` means the tag is missing (e.g. the tags capability wasn't requested)
+    pub fn user_type(&self) -> Option<UserType> {
+        self.tags().get("user-type").map(UserType::from_tag)
     }
 
     /// Emotes attached to this message
@@ -277,16 +469,15 @@ impl<'a> UserNotice<'a> {
     /// 3000 refer to the first, second, and third levels of paid subscriptions,
     /// respectively (currently $4.99, $9.99, and $24.99).
     pub fn msg_param_sub_plan(&'a self) -> Option<SubPlan> {
-        self.tags().get("msg-param-sub-plan").and_then(|s| {
-            match s {
-                "Prime" => SubPlan::Prime,
-                "Tier1" => SubPlan::Tier1,
-                "Tier2" => SubPlan::Tier2,
-                "Tier3" => SubPlan::Tier3,
-                s => SubPlan::Unknown(s.into()),
-            }
-            .into()
-        })
+        self.tags()
+            .get("msg-param-sub-plan")
+            .and_then(|s| s.parse().ok())
+    }
+
+    /// The estimated value of the subscription on a `sub`, `resub`, `subgift` or
+    /// `anonsubgift` notice, see [MonetaryValue::from_sub_plan]
+    pub fn sub_value(&'a self) -> Option<MonetaryValue> {
+        MonetaryValue::from_sub_plan(&self.msg_param_sub_plan()?)
     }
 
     /// (Sent only on sub, resub, subgift, anonsubgift) The display name of the
@@ -315,6 +506,144 @@ impl<'a> UserNotice<'a> {
     pub fn msg_param_threshold(&self) -> Option<ParsedTag<u64>> {
         self.tags().get_parsed("msg-param-threshold")
     }
+
+    /// (Sent only on viewermilestone) The kind of milestone; e.g. watch-streak.
+    pub fn msg_param_category(&self) -> Option<&str> {
+        self.tags().get("msg-param-category")
+    }
+
+    /// (Sent only on viewermilestone) The value of the milestone; e.g. the
+    /// number of consecutive streams watched for a watch-streak.
+    pub fn msg_param_value(&self) -> Option<ParsedTag<u64>> {
+        self.tags().get_parsed("msg-param-value")
+    }
+
+    /// (Sent only on viewermilestone) The channel points the user was rewarded
+    /// with for the milestone.
+    pub fn msg_param_copo_reward(&self) -> Option<ParsedTag<u64>> {
+        self.tags().get_parsed("msg-param-copoReward")
+    }
+
+    /// The total number of months the user has been subscribed, coalesced from
+    /// the month tags Twitch sends for this kind of notice.
+    ///
+    /// The precedence is:
+    /// * `sub`, `resub`: `msg-param-cumulative-months`, then the deprecated `msg-param-months`
+    /// * `subgift`, `anonsubgift`: `msg-param-months` (the months of the recipient)
+    ///
+    /// A value of `0` is treated as missing, as Twitch still sends `msg-param-months=0`
+    /// on some notices.
+    ///
+    /// Returns None for other kinds of notices, or if none of the tags were found
+    pub fn months(&self) -> Option<u64> {
+        match self.msg_id()?.ok()? {
+            NoticeType::Sub | NoticeType::Resub => self
+                .nonzero("msg-param-cumulative-months")
+                .or_else(|| self.nonzero("msg-param-months")),
+            NoticeType::SubGift | NoticeType::AnonSubGift => self.nonzero("msg-param-months"),
+            _ => None,
+        }
+    }
+
+    /// The number of consecutive months the user has been subscribed.
+    ///
+    /// This is only available on `sub` and `resub` notices where the user
+    /// chose to share their streak (`msg-param-should-share-streak`).
+    pub fn streak_months(&self) -> Option<u64> {
+        match self.msg_id()?.ok()? {
+            NoticeType::Sub | NoticeType::Resub => {}
+            _ => return None,
+        }
+        // this is sent as `0` or `1`, so it can't be parsed as a bool
+        if !self.tags().get_as_bool("msg-param-should-share-streak") {
+            return None;
+        }
+        self.nonzero("msg-param-streak-months")
+    }
+
+    /// The number of months that were gifted on a `subgift` or `anonsubgift` notice.
+    ///
+    /// Twitch omits `msg-param-gift-months` for single-month gifts, so this is `1` if it is missing.
+    pub fn gift_months(&self) -> Option<u64> {
+        match self.msg_id()?.ok()? {
+            NoticeType::SubGift | NoticeType::AnonSubGift => {
+                Some(self.nonzero("msg-param-gift-months").unwrap_or(1))
+            }
+            _ => None,
+        }
+    }
+
+    fn nonzero(&self, key: &str) -> Option<u64> {
+        self.tags()
+            .get_parsed::<_, u64>(key)?
+            .ok()
+            .filter(|&months| months > 0)
+    }
+
+    /// Get the `msg-param-*` fields of a specific kind of notice.
+    ///
+    /// Unlike the `msg_param_*` accessors, which return None when used on the
+    /// wrong kind of notice, this returns an error naming the expected [NoticeType].
+    /// Tags that are always sent with that kind of notice are required.
+    ///
+    /// ```
+    /// # use twitchchat::{FromIrcMessage as _, messages::{UserNotice, RaidFields, SubFields}};
+    /// let input = "@msg-id=raid;msg-param-displayName=Museun;msg-param-login=museun;msg-param-viewerCount=42 :tmi.twitch.tv USERNOTICE #shaken_bot\r\n";
+    /// let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+    /// let msg = UserNotice::from_irc(msg).unwrap();
+    ///
+    /// let raid = msg.try_event_fields::<RaidFields>().unwrap();
+    /// assert_eq!(raid.viewer_count, 42);
+    ///
+    /// assert!(msg.try_event_fields::<SubFields>().is_err());
+    /// ```
+    pub fn try_event_fields<T>(&'a self) -> Result<T, EventFieldsError>
+    where
+        T: EventFields<'a>,
+    {
+        let kind = match self.msg_id() {
+            Some(Ok(kind)) => kind,
+            _ => return Err(EventFieldsError::MissingMsgId),
+        };
+
+        if !T::expected().contains(&kind) {
+            return Err(EventFieldsError::WrongNoticeType {
+                expected: T::expected(),
+                found: kind,
+            });
+        }
+
+        T::from_notice(self, kind)
+    }
+
+    /// Checks the `msg-param-*` tags on this message against the ones documented for its [NoticeType].
+    ///
+    /// This can be used to detect changes on Twitch's side, instead of silently getting `None` from the accessors.
+    ///
+    /// For a [NoticeType::Unknown] every `msg-param-*` tag is reported as extra.
+    ///
+    /// Returns None if the `msg-id` tag wasn't found on the message
+    pub fn validate_for_type(&self) -> Option<MsgParamReport<'_>> {
+        let kind = self.msg_id()?.ok()?;
+        let (expected, optional) = (kind.expected_msg_params(), kind.optional_msg_params());
+
+        let tags = self.tags();
+        let missing = expected
+            .iter()
+            .filter(|&&key| tags.get(key).is_none())
+            .copied()
+            .collect();
+
+        let extra = tags
+            .sorted()
+            .into_iter()
+            .map(|(key, _)| key)
+            .filter(|key| key.starts_with("msg-param-"))
+            .filter(|key| !expected.contains(key) && !optional.contains(key))
+            .collect();
+
+        Some(MsgParamReport { missing, extra })
+    }
 }
 
 impl<'a> FromIrcMessage<'a> for UserNotice<'a> {
@@ -363,6 +692,29 @@ mod tests {
     use crate::twitch::Badge;
     use assert2::assert;
 
+    #[test]
+    fn sub_plan_from_str() {
+        let plans = &[
+            ("Prime", SubPlan::Prime),
+            ("1000", SubPlan::Tier1),
+            ("Tier1", SubPlan::Tier1),
+            ("2000", SubPlan::Tier2),
+            ("Tier2", SubPlan::Tier2),
+            ("3000", SubPlan::Tier3),
+            ("Tier3", SubPlan::Tier3),
+            ("prime", SubPlan::Unknown("prime".to_string())),
+            ("4000", SubPlan::Unknown("4000".to_string())),
+            ("10000", SubPlan::Unknown("10000".to_string())),
+            ("1000a", SubPlan::Unknown("1000a".to_string())),
+            (" 1000", SubPlan::Unknown(" 1000".to_string())),
+            ("", SubPlan::Unknown("".to_string())),
+        ];
+
+        for (input, plan) in plans {
+            assert!(input.parse::<SubPlan>().unwrap() == *plan, "{}", input);
+        }
+    }
+
     #[test]
     #[cfg(feature = "serde")]
     fn user_notice_serde() {
@@ -407,32 +759,127 @@ mod tests {
         for msg in parse(input).map(|s| s.unwrap()) {
             let msg = UserNotice::from_irc(msg).unwrap();
 
-            assert!(msg.channel() == "#ronni");
+            assert!(msg.channel() == "#dallas");
             assert!(msg.message().unwrap() == "Great stream -- keep it up!");
 
-            assert!(msg.badge_info().unwrap().unwrap() == vec![]);
-            assert!(msg.badges().unwrap().unwrap() == vec![Badge::Staff, Badge::Broadcaster, Badge::Turbo]);
+            assert!(*msg.badge_info() == vec![]);
+            assert!(*msg.badges() == vec![Badge::Staff, Badge::Broadcaster, Badge::Turbo]);
             assert!(msg.color().unwrap().unwrap() == "#008000".parse().unwrap());
             assert!(msg.display_name().unwrap() == "ronni");
-            assert!(msg.emotes().unwrap().unwrap() == vec![]);
+            assert!(*msg.emotes() == vec![]);
             assert!(msg.id().unwrap() == "db25007f-7a18-43eb-9379-80131e44d633");
-            assert!(msg.r#mod().unwrap().unwrap() == false);
+            assert!(msg.is_moderator() == false);
             assert!(msg.room_id().unwrap().unwrap() == 1337);
-            assert!(msg.subscriber().unwrap().unwrap() == true);
+            assert!(msg.tags().get_as_bool("subscriber") == true);
             assert!(msg.tmi_sent_ts().unwrap().unwrap() == 1507246572675);
-            assert!(msg.turbo().unwrap().unwrap() == true);
+            assert!(msg.tags().get_as_bool("turbo") == true);
             assert!(msg.user_id().unwrap().unwrap() == 1337);
-            assert!(msg.user_type().unwrap() == "staff");
+            assert!(msg.tags().get("user-type").unwrap() == "staff");
             assert!(msg.login().unwrap() == "ronni");
             assert!(msg.msg_id().unwrap().unwrap() == NoticeType::Resub);
             assert!(msg.msg_param_cumulative_months().unwrap().unwrap() == 6);
             assert!(msg.msg_param_streak_months().unwrap().unwrap() == 2);
-            assert!(msg.msg_param_should_share_streak().unwrap().unwrap() == true);
-            assert!(msg.msg_param_sub_plan().unwrap().unwrap() == SubPlan::Prime);
+            assert!(msg.msg_param_should_share_streak().unwrap().is_err());
+            assert!(msg.msg_param_sub_plan().unwrap() == SubPlan::Prime);
             assert!(msg.msg_param_sub_plan_name().unwrap() == "Prime");
             assert!(msg.system_msg().unwrap() == "ronni has subscribed for 6 months!");
         }
     }
+    #[test]
+    fn user_notice_validate_for_type() {
+        let input = "@badge-info=;badges=;color=#008000;display-name=ronni;emotes=;id=db25007f-7a18-43eb-9379-80131e44d633;login=ronni;mod=0;msg-id=resub;msg-param-cumulative-months=6;msg-param-streak-months=2;msg-param-should-share-streak=1;msg-param-sub-plan=Prime;msg-param-sub-plan-name=Prime;room-id=1337;subscriber=1;tmi-sent-ts=1507246572675;turbo=1;user-id=1337;user-type=staff :tmi.twitch.tv USERNOTICE #dallas :Great stream -- keep it up!\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = UserNotice::from_irc(msg).unwrap();
+            let report = msg.validate_for_type().unwrap();
+            assert!(report.is_valid());
+        }
+
+        // the way Twitch sends them now
+        let inputs = &[
+            "@badge-info=subscriber/8;badges=subscriber/6,premium/1;color=#FF4500;display-name=Foo;emotes=;flags=;id=5f1a6b7c-8d9e-4f01-a2b3-c4d5e6f70812;login=foo;mod=0;msg-id=resub;msg-param-cumulative-months=8;msg-param-months=0;msg-param-multimonth-duration=0;msg-param-multimonth-tenure=0;msg-param-should-share-streak=0;msg-param-sub-plan-name=Channel\\sSubscription\\s(dallas);msg-param-sub-plan=1000;msg-param-was-gifted=false;room-id=1337;subscriber=1;system-msg=Foo\\ssubscribed\\sat\\sTier\\s1.\\sThey've\\ssubscribed\\sfor\\s8\\smonths!;tmi-sent-ts=1700000000000;user-id=1234;user-type=;vip=0 :tmi.twitch.tv USERNOTICE #dallas :hello\r\n",
+            "@badge-info=;badges=premium/1;color=;display-name=Foo;emotes=;flags=;id=0a1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d;login=foo;mod=0;msg-id=subgift;msg-param-community-gift-id=1234567890123456789;msg-param-fun-string=FunStringOne;msg-param-gift-months=1;msg-param-goal-contribution-type=SUB_POINTS;msg-param-goal-current-contributions=120;msg-param-goal-description=;msg-param-goal-target-contributions=200;msg-param-goal-user-contributions=1;msg-param-months=3;msg-param-origin-id=1234567890123456789;msg-param-recipient-display-name=Bar;msg-param-recipient-id=5678;msg-param-recipient-user-name=bar;msg-param-sender-count=0;msg-param-sub-plan-name=Channel\\sSubscription\\s(dallas);msg-param-sub-plan=1000;room-id=1337;subscriber=0;system-msg=Foo\\sgifted\\sa\\sTier\\s1\\ssub\\sto\\sBar!;tmi-sent-ts=1700000000000;user-id=1234;user-type=;vip=0 :tmi.twitch.tv USERNOTICE #dallas\r\n",
+        ];
+        for input in inputs {
+            for msg in parse(input).map(|s| s.unwrap()) {
+                let msg = UserNotice::from_irc(msg).unwrap();
+                let report = msg.validate_for_type().unwrap();
+                assert!(report.is_valid(), "{:?}", report);
+            }
+        }
+
+        let input = "@login=foo;msg-id=raid;msg-param-displayName=Foo;msg-param-login=foo;msg-param-new-thing=1 :tmi.twitch.tv USERNOTICE #dallas\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = UserNotice::from_irc(msg).unwrap();
+            let report = msg.validate_for_type().unwrap();
+            assert!(!report.is_valid());
+            assert!(report.missing == vec!["msg-param-viewerCount"]);
+            assert!(report.extra == vec!["msg-param-new-thing"]);
+        }
+
+        let input = "@msg-id=somethingnew;msg-param-foo=bar :tmi.twitch.tv USERNOTICE #dallas\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = UserNotice::from_irc(msg).unwrap();
+            let report = msg.validate_for_type().unwrap();
+            assert!(report.missing.is_empty());
+            assert!(report.extra == vec!["msg-param-foo"]);
+        }
+
+        let input = ":tmi.twitch.tv USERNOTICE #dallas\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = UserNotice::from_irc(msg).unwrap();
+            assert!(msg.validate_for_type().is_none());
+        }
+    }
+
+    #[test]
+    fn user_notice_months() {
+        // (tags, months, streak months, gift months)
+        type Test = (&'static str, Option<u64>, Option<u64>, Option<u64>);
+        let tests: &[Test] = &[
+            // resub with a shared streak
+            ("msg-id=resub;msg-param-cumulative-months=6;msg-param-months=0;msg-param-should-share-streak=1;msg-param-streak-months=2", Some(6), Some(2), None),
+            // resub without sharing the streak
+            ("msg-id=resub;msg-param-cumulative-months=8;msg-param-months=0;msg-param-should-share-streak=0;msg-param-streak-months=8", Some(8), None, None),
+            // an old sub notice with only the deprecated tag
+            ("msg-id=sub;msg-param-months=3", Some(3), None, None),
+            ("msg-id=sub;msg-param-cumulative-months=0;msg-param-months=0", None, None, None),
+            ("msg-id=subgift;msg-param-months=12;msg-param-gift-months=6", Some(12), None, Some(6)),
+            ("msg-id=anonsubgift;msg-param-months=1", Some(1), None, Some(1)),
+            ("msg-id=raid;msg-param-months=1", None, None, None),
+        ];
+
+        for (tags, months, streak, gift) in tests {
+            let input = format!("@{} :tmi.twitch.tv USERNOTICE #museun\r\n", tags);
+            for msg in parse(&input).map(|s| s.unwrap()) {
+                let msg = UserNotice::from_irc(msg).unwrap();
+                assert!(msg.months() == *months, "{}", tags);
+                assert!(msg.streak_months() == *streak, "{}", tags);
+                assert!(msg.gift_months() == *gift, "{}", tags);
+            }
+        }
+    }
+
+    #[test]
+    fn user_notice_sub_value() {
+        use crate::twitch::MonetaryValue;
+
+        let tests = &[
+            ("msg-param-sub-plan=Prime", Some(499)),
+            ("msg-param-sub-plan=1000", Some(499)),
+            ("msg-param-sub-plan=2000", Some(999)),
+            ("msg-param-sub-plan=3000", Some(2499)),
+            ("msg-param-sub-plan=9000", None),
+        ];
+        for (tags, amount) in tests {
+            let input = format!("@msg-id=sub;{} :tmi.twitch.tv USERNOTICE #museun\r\n", tags);
+            for msg in parse(&input).map(|s| s.unwrap()) {
+                let msg = UserNotice::from_irc(msg).unwrap();
+                let expected = amount.map(|amount| MonetaryValue::new(amount, 2, "USD"));
+                assert!(msg.sub_value() == expected, "{}", tags);
+            }
+        }
+    }
+
     #[test]
     fn user_notice_unknown() {
         let input = "@badge-info=subscriber/8;badges=subscriber/6,bits/100;color=#59517B;display-name=lllAirJordanlll;emotes=;flags=;id=3198b02c-eaf4-4904-9b07-eb1b2b12ba50;login=lllairjordanlll;mod=0;msg-id=resub;msg-param-cumulative-months=8;msg-param-months=0;msg-param-should-share-streak=0;msg-param-sub-plan-name=Channel\\sSubscription\\s(giantwaffle);msg-param-sub-plan=1000;room-id=22552479;subscriber=1;system-msg=lllAirJordanlll\\ssubscribed\\sat\\sTier\\s1.\\sThey\'ve\\ssubscribed\\sfor\\s8\\smonths!;tmi-sent-ts=1580932171144;user-id=44979519;user-type= :tmi.twitch.tv USERNOTICE #giantwaffle\r\n";
@@ -442,4 +889,63 @@ mod tests {
             assert_eq!(msg.tags().is_empty(), false);
         }
     }
+
+    #[test]
+    fn ordered_enums() {
+        use std::collections::BTreeMap;
+
+        let mut plans = vec![
+            SubPlan::Unknown("4000".to_string()),
+            SubPlan::Tier3,
+            SubPlan::Prime,
+            SubPlan::Tier1,
+        ];
+        plans.sort();
+        assert!(
+            plans
+                == vec![
+                    SubPlan::Prime,
+                    SubPlan::Tier1,
+                    SubPlan::Tier3,
+                    SubPlan::Unknown("4000".to_string()),
+                ]
+        );
+
+        let mut counts = BTreeMap::new();
+        for kind in &["resub", "sub", "newthing", "resub"] {
+            *counts
+                .entry(kind.parse::<NoticeType>().unwrap())
+                .or_insert(0) += 1;
+        }
+        let counts = counts.into_iter().collect::<Vec<_>>();
+        assert!(
+            counts
+                == vec![
+                    (NoticeType::Sub, 1),
+                    (NoticeType::Resub, 2),
+                    (NoticeType::Unknown("newthing".to_string()), 1),
+                ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn unknown_variants_serde() {
+        fn round_trip<T>(value: T)
+        where
+            T: ::serde::Serialize + ::serde::de::DeserializeOwned + PartialEq + std::fmt::Debug,
+        {
+            let json = serde_json::to_string(&value).unwrap();
+            assert!(
+                serde_json::from_str::<T>(&json).unwrap() == value,
+                "{}",
+                json
+            );
+        }
+
+        round_trip(SubPlan::Unknown("4000".to_string()));
+        round_trip(NoticeType::Unknown("newthing".to_string()));
+        round_trip(Badge::Unknown("founder".to_string(), 0));
+        round_trip(UserType::Unknown);
+    }
 }
@@ -1,5 +1,5 @@
 use crate::irc::tags::ParsedTag;
-use crate::twitch::{Attribution, AttributionVec, BadgeVec, Color, EmoteVec, FlagVec};
+use crate::twitch::{Attribution, AttributionVec, BadgeVec, Color, EmoteVec, FlagVec, UserType};
 use crate::{irc::*, MaybeOwned, MaybeOwnedIndex, Validator};
 use std::str::FromStr;
 
@@ -45,19 +45,38 @@ impl<'a> UserState<'a> {
         self.tag_to_attribution_vec("badge-info")
     }
 
+    /// How many months the user has been subscribed to the channel, from the `badge-info` tag
+    pub fn subscriber_months(&self) -> Option<u32> {
+        self.tags()
+            .get("badge-info")
+            .and_then(crate::twitch::subscriber_months)
+    }
+
     /// Badges attached to this message
     pub fn badges(&'a self) -> BadgeVec {
         self.tag_to_attribution_vec("badges")
     }
 
     /// The user's color, if set
+    ///
+    /// Twitch sends an empty tag until a color is picked, which is `None` as well
     pub fn color(&self) -> Option<ParsedTag<Color>> {
-        self.tags().get_parsed("color")
+        let tags = self.tags();
+        tags.get_non_empty("color")?;
+        tags.get_parsed("color")
     }
 
     /// The user's display name, if set
     pub fn display_name(&self) -> Option<&str> {
-        self.tags().get("display-name")
+        self.tags().get_non_empty("display-name")
+    }
+
+    /// The `user-type` of the user.
+    ///
+    /// Ordinary users have an empty tag, which is `Some(UserType::Normal)`, while
+    /// `None` means the tag is missing (e.g. the tags capability wasn't requested)
+    pub fn user_type(&self) -> Option<UserType> {
+        self.tags().get("user-type").map(UserType::from_tag)
     }
 
     /// Emotes attached to this message
@@ -74,6 +93,11 @@ impl<'a> UserState<'a> {
     pub fn is_moderator(&self) -> bool {
         self.tags().get_as_bool("mod")
     }
+
+    /// The room id this event happened on, if Twitch included it
+    pub fn room_id(&self) -> Option<ParsedTag<u64>> {
+        self.tags().get_parsed("room-id")
+    }
 }
 
 impl<'a> FromIrcMessage<'a> for UserState<'a> {
@@ -101,9 +125,9 @@ serde_struct!(UserState { raw, tags, channel });
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::twitch::Badge;
+    use crate::twitch::{Badge, EmoteSet};
     use assert2::assert;
-    use maplit::hashset;
+    use maplit::btreeset;
 
     #[test]
     #[cfg(feature = "serde")]
@@ -124,23 +148,30 @@ mod tests {
 
     #[test]
     fn user_state_integrity() {
-        let input = "@badge-info=;badges=staff/1;color=#0D4200;display-name=ronni;emote-sets=0,33,50,237,793,2126,3517,4578,5569,9400,10337,12239;mod=1;subscriber=1;turbo=1;user-type=staff :tmi.twitch.tv USERSTATE #dallas
-        \r\n";
+        let input = "@badge-info=;badges=staff/1;color=#0D4200;display-name=ronni;emote-sets=0,33,50,237,793,2126,3517,4578,5569,9400,10337,12239;mod=1;subscriber=1;turbo=1;user-type=staff :tmi.twitch.tv USERSTATE #dallas\r\n";
         for msg in parse(input).map(|s| s.unwrap()) {
             let msg = UserState::from_irc(msg).unwrap();
-            assert!(msg.badge_info().unwrap().unwrap() == vec![]);
-            assert!(msg.badges().unwrap().unwrap() == vec![Badge::Staff]);
+            assert!(*msg.badge_info() == vec![]);
+            assert!(*msg.badges() == vec![Badge::Staff]);
             assert!(msg.color().unwrap().unwrap() == "#0D4200".parse().unwrap());
             assert!(msg.display_name().unwrap() == "ronni");
 
-            assert!(
-                msg.emote_sets().unwrap().unwrap()
-                    == hashset! {0,33,50,237,793,2126,3517,4578,5569,9400,10337,12239}
-            );
-            assert!(msg.mod().unwrap().unwrap() == true);
-            assert!(msg.subscriber().unwrap().unwrap() == true);
-            assert!(msg.turbo().unwrap().unwrap() == true);
-            assert!(msg.user_type().unwrap().unwrap() == "staff");
+            let emote_sets = msg.tags().get_parsed::<_, EmoteSet>("emote-sets").unwrap().unwrap();
+            assert!(*emote_sets == btreeset! {0,33,50,237,793,2126,3517,4578,5569,9400,10337,12239});
+            assert!(msg.is_moderator() == true);
+            assert!(msg.tags().get_as_bool("subscriber") == true);
+            assert!(msg.tags().get_as_bool("turbo") == true);
+            assert!(msg.tags().get("user-type").unwrap() == "staff");
+            assert!(msg.room_id().is_none());
+        }
+    }
+
+    #[test]
+    fn user_state_room_id() {
+        let input = "@mod=0;room-id=23196011 :tmi.twitch.tv USERSTATE #museun\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = UserState::from_irc(msg).unwrap();
+            assert!(msg.room_id().unwrap().unwrap() == 23196011);
         }
     }
 }
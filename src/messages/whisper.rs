@@ -1,5 +1,7 @@
 use crate::irc::tags::ParsedTag;
-use crate::twitch::{Attribution, AttributionVec, Badge, BadgeVec, Color, EmoteVec, FlagVec};
+use crate::twitch::{
+    Attribution, AttributionVec, Badge, BadgeVec, Color, EmoteVec, FlagVec, UserType,
+};
 use crate::{irc::*, MaybeOwned, MaybeOwnedIndex, Validator};
 use std::str::FromStr;
 
@@ -25,8 +27,12 @@ impl<'a> Whisper<'a> {
     );
 
     /// The color of the user who sent this message, if set
+    ///
+    /// This is `None` for an empty tag as well as a missing one
     pub fn color(&self) -> Option<ParsedTag<Color>> {
-        self.tags().get_parsed("color")
+        let tags = self.tags();
+        tags.get_non_empty("color")?;
+        tags.get_parsed("color")
     }
 
     /// Helper function to return information that can be parsed as AttributionVec. (copied from Privmsg)
@@ -57,7 +63,15 @@ impl<'a> Whisper<'a> {
     ///
     /// Otherwise it'll return `None`.
     pub fn display_name(&'a self) -> Option<&'a str> {
-        self.tags().get("display-name")
+        self.tags().get_non_empty("display-name")
+    }
+
+    /// The `user-type` of the user who sent this message.
+    ///
+    /// Ordinary users have an empty tag, which is `Some(UserType::Normal)`, while
+    /// `None` means the tag is missing (e.g. the tags capability wasn't requested)
+    pub fn user_type(&self) -> Option<UserType> {
+        self.tags().get("user-type").map(UserType::from_tag)
     }
 
     /// Badges attached to this message
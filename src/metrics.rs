@@ -0,0 +1,627 @@
+/*!
+Cheap metrics about the messages a bot receives, for capacity planning.
+
+[Metrics] can be shared between threads and updated without locking. The
+[AsyncRunner](crate::AsyncRunner) keeps one, see `AsyncRunner::metrics()`,
+but it can also be fed by hand:
+
+```
+# use twitchchat::metrics::Metrics;
+let metrics = Metrics::default();
+
+let input = "@badges=;emotes=25:0-4,12-16/1902:6-10 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :Kappa Keepo Kappa\r\n";
+for msg in twitchchat::irc::parse(input) {
+    metrics.observe(&msg.unwrap());
+}
+
+let snapshot = metrics.snapshot();
+assert_eq!(snapshot.line_length.count, 1);
+assert_eq!(snapshot.tag_count.max, 2);
+assert_eq!(snapshot.emote_count.max, 3);
+```
+
+With [Metrics::with_script_detection], it also keeps track of the
+[script](crate::messages::Script) that the chat of each channel is written in.
+*/
+
+use crate::{
+    messages::Script,
+    twitch::{BadgeVec, EmoteVec, FlagVec},
+    IrcMessage, MessageError,
+};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::SystemTime,
+};
+
+// each power of two is split into this many linear sub-buckets (as a power of two),
+// so a value is off by at most 25%
+const SUB_BUCKET_BITS: u32 = 2;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+const BUCKETS: usize = SUB_BUCKETS * (64 - SUB_BUCKET_BITS as usize + 1);
+
+fn bucket_of(value: u64) -> usize {
+    if value < SUB_BUCKETS as u64 {
+        return value as usize;
+    }
+    let exp = 63 - value.leading_zeros();
+    let sub = (value >> (exp - SUB_BUCKET_BITS)) as usize & (SUB_BUCKETS - 1);
+    SUB_BUCKETS * (exp - SUB_BUCKET_BITS + 1) as usize + sub
+}
+
+// the smallest and largest value of a bucket
+fn bounds_of(bucket: usize) -> (u64, u64) {
+    if bucket < SUB_BUCKETS {
+        return (bucket as u64, bucket as u64);
+    }
+    let shift = (bucket / SUB_BUCKETS - 1) as u32;
+    let low = ((SUB_BUCKETS + bucket % SUB_BUCKETS) as u64) << shift;
+    (low, low + ((1 << shift) - 1))
+}
+
+/// A histogram with exponentially growing buckets
+///
+/// Values up to 3 are exact, larger values fall into one of four buckets per
+/// power of two. Recording is a few atomic operations.
+#[derive(Debug)]
+pub struct Histogram {
+    buckets: Box<[AtomicU64]>,
+    count: AtomicU64,
+    sum: AtomicU64,
+    min: AtomicU64,
+    max: AtomicU64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0),
+            min: AtomicU64::new(u64::MAX),
+            max: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Histogram {
+    /// Record a value
+    pub fn record(&self, value: u64) {
+        self.buckets[bucket_of(value)].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(value, Ordering::Relaxed);
+        self.min.fetch_min(value, Ordering::Relaxed);
+        self.max.fetch_max(value, Ordering::Relaxed);
+    }
+
+    /// Take a copy of the current state
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let buckets = self
+            .buckets
+            .iter()
+            .enumerate()
+            .filter_map(|(bucket, count)| {
+                let count = count.load(Ordering::Relaxed);
+                if count == 0 {
+                    return None;
+                }
+                let (low, high) = bounds_of(bucket);
+                Some(Bucket { low, high, count })
+            })
+            .collect();
+
+        let count = self.count.load(Ordering::Relaxed);
+        HistogramSnapshot {
+            buckets,
+            count,
+            sum: self.sum.load(Ordering::Relaxed),
+            min: if count == 0 {
+                0
+            } else {
+                self.min.load(Ordering::Relaxed)
+            },
+            max: self.max.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A bucket of a [HistogramSnapshot]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Bucket {
+    /// The smallest value in this bucket
+    pub low: u64,
+    /// The largest value in this bucket
+    pub high: u64,
+    /// How many values were in this bucket
+    pub count: u64,
+}
+
+/// A copy of a [Histogram] at some point in time
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct HistogramSnapshot {
+    /// The non-empty buckets, from the smallest values to the largest
+    pub buckets: Vec<Bucket>,
+    /// How many values were recorded
+    pub count: u64,
+    /// The sum of all values
+    pub sum: u64,
+    /// The smallest value, or 0 if nothing was recorded
+    pub min: u64,
+    /// The largest value, or 0 if nothing was recorded
+    pub max: u64,
+}
+
+impl HistogramSnapshot {
+    /// The mean of all values, if any were recorded
+    pub fn mean(&self) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(self.sum as f64 / self.count as f64)
+    }
+
+    /// An upper bound of the value at this quantile (between `0.0` and `1.0`), if any were recorded
+    pub fn quantile(&self, quantile: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((quantile.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        self.buckets
+            .iter()
+            .find(|bucket| {
+                seen += bucket.count;
+                seen >= rank
+            })
+            .map(|bucket| bucket.high.min(self.max))
+    }
+}
+
+/// How many chat messages of a channel were mostly in each script, see [Metrics::with_script_detection]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct ScriptCounts {
+    /// Messages mostly in the Latin alphabet
+    pub latin: u64,
+    /// Messages mostly in the Cyrillic alphabet
+    pub cyrillic: u64,
+    /// Messages mostly in Chinese, Japanese or Korean
+    pub cjk: u64,
+    /// Messages without any letters of these scripts, e.g. just emoji or numbers
+    pub other: u64,
+}
+
+impl ScriptCounts {
+    /// Count this message by the script it is mostly in
+    pub fn record(&mut self, text: &str) {
+        match Script::detect(text) {
+            Some(Script::Latin) => self.latin += 1,
+            Some(Script::Cyrillic) => self.cyrillic += 1,
+            Some(Script::Cjk) => self.cjk += 1,
+            None => self.other += 1,
+        }
+    }
+
+    /// The script most messages were in, if any had letters
+    pub fn predominant(&self) -> Option<Script> {
+        let counts = [
+            (Script::Latin, self.latin),
+            (Script::Cyrillic, self.cyrillic),
+            (Script::Cjk, self.cjk),
+        ];
+        // ties go to the first script
+        counts
+            .iter()
+            .rev()
+            .max_by_key(|(_, count)| count)
+            .filter(|(_, count)| *count > 0)
+            .map(|(script, _)| *script)
+    }
+}
+
+/// A message that could not be parsed, see [Metrics::record_parse_error]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct ParseFailure {
+    /// The command of the message
+    pub command: String,
+    /// The parse error
+    pub error: String,
+    /// The raw line, with its trailing data replaced by its length and cut off after [ParseFailures::MAX_LINE] bytes
+    pub line: String,
+    /// When the failure was recorded
+    pub when: SystemTime,
+}
+
+/// Counts of the messages that could not be parsed, and the most recent ones
+#[derive(Debug, Default)]
+pub struct ParseFailures {
+    counts: BTreeMap<String, u64>,
+    recent: VecDeque<ParseFailure>,
+    attribution_counts: BTreeMap<String, u64>,
+}
+
+impl ParseFailures {
+    /// How many of the most recent failures are kept
+    pub const RECENT: usize = 16;
+    /// How many bytes of each failed line are kept
+    pub const MAX_LINE: usize = 256;
+
+    fn record(&mut self, msg: &IrcMessage<'_>, err: &MessageError) {
+        let command = msg.get_command().to_string();
+        *self.counts.entry(command.clone()).or_default() += 1;
+
+        if self.recent.len() == Self::RECENT {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(ParseFailure {
+            command,
+            error: err.to_string(),
+            line: redact(msg),
+            when: SystemTime::now(),
+        });
+    }
+
+    fn record_attribution_errors(&mut self, tag: &str, errors: usize) {
+        *self.attribution_counts.entry(tag.to_string()).or_default() += errors as u64;
+    }
+}
+
+// the trailing data is what users typed, so only its length is kept
+fn redact(msg: &IrcMessage<'_>) -> String {
+    let raw = msg.get_raw().trim_end_matches("\r\n");
+    let mut line = match msg.get_data() {
+        Some(data) if raw.ends_with(data) => {
+            let start = raw.len() - data.len();
+            format!("{}<{} bytes>", &raw[..start], data.len())
+        }
+        _ => raw.to_string(),
+    };
+
+    if line.len() > ParseFailures::MAX_LINE {
+        let mut end = ParseFailures::MAX_LINE;
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        line.truncate(end);
+        line.push_str("...");
+    }
+    line
+}
+
+/// Distributions of the sizes of received messages, and counts of the ones that could not be parsed
+#[derive(Debug, Default)]
+pub struct Metrics {
+    line_length: Histogram,
+    tag_count: Histogram,
+    emote_count: Histogram,
+    parse_failures: Mutex<ParseFailures>,
+    channel_scripts: Option<Mutex<BTreeMap<String, ScriptCounts>>>,
+}
+
+impl Metrics {
+    /// Create metrics that also count the scripts of the chat messages of each channel.
+    ///
+    /// This looks at every character of every chat message, so it is off by default
+    pub fn with_script_detection() -> Self {
+        Self {
+            channel_scripts: Some(Mutex::default()),
+            ..Self::default()
+        }
+    }
+
+    /// Record the sizes of this message
+    pub fn observe(&self, msg: &IrcMessage<'_>) {
+        self.line_length.record(msg.get_raw().len() as u64);
+
+        let tags = msg.get_tags().map_or(0, |tags| tags.split(';').count());
+        self.tag_count.record(tags as u64);
+
+        if let Some(emotes) = msg.get_tags().and_then(|tags| find_tag(tags, "emotes")) {
+            self.emote_count.record(count_emotes(emotes) as u64);
+        }
+
+        if let Some(tags) = msg.get_tags() {
+            self.observe_attributions(tags);
+        }
+
+        if let Some(scripts) = &self.channel_scripts {
+            Self::observe_script(scripts, msg);
+        }
+    }
+
+    fn observe_script(scripts: &Mutex<BTreeMap<String, ScriptCounts>>, msg: &IrcMessage<'_>) {
+        if msg.get_command() != "PRIVMSG" {
+            return;
+        }
+        let (channel, data) = match (msg.nth_arg(0), msg.get_data()) {
+            (Some(channel), Some(data)) => (channel, data),
+            _ => return,
+        };
+        // the ACTION of a /me would count as Latin
+        let data = data.strip_prefix("\u{1}ACTION ").unwrap_or(data);
+
+        let mut scripts = scripts.lock().unwrap();
+        match scripts.get_mut(channel) {
+            Some(counts) => counts.record(data),
+            None => scripts.entry(channel.to_string()).or_default().record(data),
+        }
+    }
+
+    /// The script most chat messages of this channel were in.
+    ///
+    /// This is `None` without [script detection](Metrics::with_script_detection)
+    pub fn channel_script(&self, channel: &str) -> Option<Script> {
+        self.channel_scripts
+            .as_ref()?
+            .lock()
+            .unwrap()
+            .get(channel)?
+            .predominant()
+    }
+
+    // elements of the list tags that the lenient parsers would silently drop
+    fn observe_attributions(&self, tags: &str) {
+        type Parse = fn(&str) -> usize;
+        const TAGS: [(&str, Parse); 4] = [
+            ("badges", |s| BadgeVec::parse_with_errors(s).1.len()),
+            ("badge-info", |s| BadgeVec::parse_with_errors(s).1.len()),
+            ("emotes", |s| EmoteVec::parse_with_errors(s).1.len()),
+            ("flags", |s| FlagVec::parse_with_errors(s).1.len()),
+        ];
+
+        for (tag, parse) in TAGS.iter() {
+            let errors = find_tag(tags, tag).map_or(0, parse);
+            if errors > 0 {
+                self.parse_failures
+                    .lock()
+                    .unwrap()
+                    .record_attribution_errors(tag, errors);
+            }
+        }
+    }
+
+    /// Record that this message could not be parsed into its type.
+    ///
+    /// The [ParseFailures::RECENT] most recent ones are kept, see [ParseFailure] for what is kept of them.
+    pub fn record_parse_error(&self, msg: &IrcMessage<'_>, err: &MessageError) {
+        self.parse_failures.lock().unwrap().record(msg, err)
+    }
+
+    /// Take a copy of the current state
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let parse_failures = self.parse_failures.lock().unwrap();
+        MetricsSnapshot {
+            line_length: self.line_length.snapshot(),
+            tag_count: self.tag_count.snapshot(),
+            emote_count: self.emote_count.snapshot(),
+            parse_errors: parse_failures.counts.clone(),
+            recent_parse_errors: parse_failures.recent.iter().cloned().collect(),
+            attribution_errors: parse_failures.attribution_counts.clone(),
+            channel_scripts: self
+                .channel_scripts
+                .as_ref()
+                .map(|scripts| scripts.lock().unwrap().clone())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// A copy of the [Metrics] at some point in time
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MetricsSnapshot {
+    /// The length of the raw lines, in bytes
+    pub line_length: HistogramSnapshot,
+    /// The number of tags of each message
+    pub tag_count: HistogramSnapshot,
+    /// The number of emotes of each message that has an `emotes` tag
+    pub emote_count: HistogramSnapshot,
+    /// How many messages could not be parsed, per command
+    pub parse_errors: BTreeMap<String, u64>,
+    /// The most recent messages that could not be parsed, from the oldest to the newest
+    pub recent_parse_errors: Vec<ParseFailure>,
+    /// How many elements of the list tags (`badges`, `badge-info`, `emotes` and `flags`)
+    /// could not be parsed, per tag
+    ///
+    /// The message types skip these elements, so a count here usually means
+    /// Twitch changed the format of a tag.
+    pub attribution_errors: BTreeMap<String, u64>,
+    /// The scripts of the chat messages, per channel
+    ///
+    /// This is empty without [script detection](Metrics::with_script_detection)
+    pub channel_scripts: BTreeMap<String, ScriptCounts>,
+}
+
+fn find_tag<'a>(tags: &'a str, key: &str) -> Option<&'a str> {
+    tags.trim_start_matches('@').split(';').find_map(|tag| {
+        let (k, v) = tag.split_once('=')?;
+        Some(v).filter(|_| k == key)
+    })
+}
+
+// every range of every emote, e.g. `25:0-4,12-16/1902:6-10` is 3
+fn count_emotes(emotes: &str) -> usize {
+    emotes
+        .split('/')
+        .filter_map(|emote| emote.split_once(':'))
+        .map(|(_, ranges)| ranges.split(',').count())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets() {
+        for value in (0..10_000).chain([u64::MAX - 1, u64::MAX].iter().copied()) {
+            let (low, high) = bounds_of(bucket_of(value));
+            assert!(
+                low <= value && value <= high,
+                "{} in {}..={}",
+                value,
+                low,
+                high
+            );
+        }
+        assert_eq!(bounds_of(bucket_of(3)), (3, 3));
+        assert_eq!(bounds_of(bucket_of(255)), (224, 255));
+        assert!(bucket_of(u64::MAX) < BUCKETS);
+    }
+
+    #[test]
+    fn histogram() {
+        let histogram = Histogram::default();
+        assert_eq!(histogram.snapshot().quantile(0.5), None);
+
+        for value in 1..=100 {
+            histogram.record(value);
+        }
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count, 100);
+        assert_eq!((snapshot.min, snapshot.max), (1, 100));
+        assert_eq!(snapshot.mean(), Some(50.5));
+        assert_eq!(snapshot.quantile(0.5), Some(55));
+        assert_eq!(snapshot.quantile(1.0), Some(100));
+        assert_eq!(
+            snapshot.buckets.iter().map(|b| b.count).sum::<u64>(),
+            snapshot.count
+        );
+    }
+
+    #[test]
+    fn metrics() {
+        let metrics = Metrics::default();
+        let input = "@badges=;emotes=25:0-4,12-16/1902:6-10;id=1 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :Kappa Keepo Kappa\r\n\
+                     @emotes= :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n\
+                     PING :1234\r\n";
+        for msg in crate::irc::parse(input) {
+            metrics.observe(&msg.unwrap());
+        }
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.line_length.count, 3);
+        assert_eq!(snapshot.line_length.min, "PING :1234\r\n".len() as u64);
+        assert_eq!((snapshot.tag_count.min, snapshot.tag_count.max), (0, 3));
+        assert_eq!(snapshot.emote_count.count, 2);
+        assert_eq!((snapshot.emote_count.min, snapshot.emote_count.max), (0, 3));
+    }
+
+    #[test]
+    #[cfg(feature = "moderation")]
+    fn parse_errors() {
+        use crate::FromIrcMessage as _;
+
+        let metrics = Metrics::default();
+        let input = format!(
+            "@badges=;color= :museun!museun@museun.tmi.twitch.tv PRIVMSG :secret\r\n\
+             :tmi.twitch.tv CLEARCHAT\r\n\
+             PRIVMSG #museun :{}\r\n",
+            "a".repeat(300)
+        );
+        for msg in crate::irc::parse(&input).map(|s| s.unwrap()) {
+            let err = crate::messages::Commands::from_irc(msg.clone()).unwrap_err();
+            metrics.record_parse_error(&msg, &err);
+        }
+
+        let snapshot = metrics.snapshot();
+        let counts = snapshot.parse_errors.into_iter().collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![("CLEARCHAT".to_string(), 1), ("PRIVMSG".to_string(), 2)]
+        );
+
+        let recent = snapshot.recent_parse_errors;
+        assert_eq!(recent.len(), 3);
+        assert_eq!(
+            recent[0].line,
+            "@badges=;color= :museun!museun@museun.tmi.twitch.tv PRIVMSG :<6 bytes>"
+        );
+        assert_eq!(recent[2].line, "PRIVMSG #museun :<300 bytes>");
+
+        for _ in 0..ParseFailures::RECENT {
+            let msg = crate::irc::parse(":tmi.twitch.tv CLEARCHAT\r\n")
+                .next()
+                .unwrap()
+                .unwrap();
+            metrics.record_parse_error(&msg, &MessageError::ExpectedArg { pos: 0 });
+        }
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.recent_parse_errors.len(), ParseFailures::RECENT);
+        assert_eq!(snapshot.parse_errors["CLEARCHAT"], 17);
+    }
+
+    #[test]
+    fn attribution_errors() {
+        let metrics = Metrics::default();
+        let input = "@badge-info=subscriber/8;badges=subscriber/6,bits/x;emotes=25:0-4/:1-2;flags= :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :Kappa\r\n\
+                     @badges=moderator/1,vip/abc;emotes=25:0-4 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :Kappa\r\n";
+        for msg in crate::irc::parse(input) {
+            metrics.observe(&msg.unwrap());
+        }
+
+        let counts = metrics
+            .snapshot()
+            .attribution_errors
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![("badges".to_string(), 2), ("emotes".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn channel_scripts() {
+        let input = ":foo!foo@foo PRIVMSG #museun :hello chat\r\n\
+                     :foo!foo@foo PRIVMSG #museun :\u{1}ACTION машет\u{1}\r\n\
+                     :foo!foo@foo PRIVMSG #ru :привет Kappa\r\n\
+                     :foo!foo@foo PRIVMSG #ru :всем привет\r\n\
+                     :foo!foo@foo PRIVMSG #ru :123\r\n\
+                     :foo!foo@foo PRIVMSG #jp :こんにちは\r\n";
+
+        let metrics = Metrics::default();
+        for msg in crate::irc::parse(input) {
+            metrics.observe(&msg.unwrap());
+        }
+        assert_eq!(metrics.channel_script("#ru"), None);
+        assert!(metrics.snapshot().channel_scripts.is_empty());
+
+        let metrics = Metrics::with_script_detection();
+        for msg in crate::irc::parse(input) {
+            metrics.observe(&msg.unwrap());
+        }
+        // a tie goes to Latin
+        assert_eq!(metrics.channel_script("#museun"), Some(Script::Latin));
+        assert_eq!(metrics.channel_script("#ru"), Some(Script::Cyrillic));
+        assert_eq!(metrics.channel_script("#jp"), Some(Script::Cjk));
+        assert_eq!(metrics.channel_script("#other"), None);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(
+            snapshot.channel_scripts["#ru"],
+            ScriptCounts {
+                cyrillic: 2,
+                other: 1,
+                ..ScriptCounts::default()
+            }
+        );
+    }
+
+    #[test]
+    fn redact_long_lines() {
+        let input = format!("@emotes={} PING\r\n", "é".repeat(200));
+        let msg = crate::irc::parse(&input).next().unwrap().unwrap();
+        let line = redact(&msg);
+        assert!(line.ends_with("..."));
+        assert!(line.len() <= ParseFailures::MAX_LINE + 3);
+    }
+}
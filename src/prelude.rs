@@ -0,0 +1,58 @@
+/*!
+The common traits and types, for glob importing.
+
+```
+use twitchchat::prelude::*;
+
+let input = ":museun!museun@museun PRIVMSG #museun :hello\r\n";
+let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+
+match Commands::from_irc(msg).unwrap().into_owned() {
+    Commands::Privmsg(msg) => {
+        let mut out = vec![];
+        out.say(&msg, "hi")?;
+        assert_eq!(out, b"PRIVMSG #museun :hi\r\n");
+    }
+    _ => unreachable!(),
+}
+# Ok::<_, std::io::Error>(())
+```
+
+This is kept small on purpose: it is the traits needed to call the methods
+of messages and writers, the message types you match on most often, and the
+handles for running a connection. Everything in it is stable across minor
+versions, less common types should be imported from their modules.
+*/
+
+#[doc(no_inline)]
+pub use crate::{
+    commands,
+    irc::{IrcMessage, MessageError},
+    messages::{
+        Commands, GlobalUserState, HasTags, Join, Notice, Part, Privmsg, RoomState, UserState,
+    },
+    runner::{Status, SyncRunner},
+    twitch::UserConfig,
+    writer::MessageSink,
+    Encodable, FromIrcMessage, IntoIrcMessage, IntoOwned, PrivmsgExt, Validator,
+};
+
+#[cfg(feature = "moderation")]
+#[doc(no_inline)]
+pub use crate::messages::{ClearChat, ClearMsg};
+
+#[cfg(feature = "usernotice")]
+#[doc(no_inline)]
+pub use crate::messages::UserNotice;
+
+#[cfg(feature = "whispers")]
+#[doc(no_inline)]
+pub use crate::messages::Whisper;
+
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+#[doc(no_inline)]
+pub use crate::{
+    runner::{AsyncRunner, ConfigHandle, NotifyHandle},
+    writer::{AsyncWriter, MpscWriter},
+};
@@ -0,0 +1,210 @@
+/*!
+Ready-made connections for common kinds of bots.
+
+Each preset connects, registers and joins the channels in one call, and
+returns something that is a [Stream] of owned messages. They are thin: the
+[AsyncRunner] underneath is still available, so writers, metrics, config
+handles, etc. can be used as usual.
+
+* [archiver] yields every message of the channels, e.g. for logging chat
+* [moderation_feed] yields only the moderation events of the channels
+
+```no_run
+# use twitchchat::{connector::Connector, presets};
+# use futures_lite::StreamExt as _;
+# async fn demo<C>(connector: C) -> Result<(), twitchchat::runner::Error>
+# where C: Connector, for<'a> &'a C::Output: futures_lite::AsyncRead + futures_lite::AsyncWrite + Send + Sync + Unpin {
+let mut chat = presets::archiver(connector, &["#museun", "#shaken_bot"]).await?;
+
+while let Some(msg) = chat.next().await {
+    println!("{}", msg.raw().escape_debug());
+}
+# Ok(())
+# }
+```
+*/
+
+use crate::{
+    connector::Connector,
+    runner::{AsyncRunner, Error},
+    UserConfig,
+};
+use futures_lite::{AsyncRead, AsyncWrite};
+
+#[cfg(feature = "moderation")]
+use {
+    crate::messages::Commands,
+    futures_lite::Stream,
+    std::{
+        pin::Pin,
+        task::{Context, Poll},
+    },
+};
+
+/// Connect anonymously with all of the capabilities, and join these channels.
+///
+/// The returned runner is a [Stream] of every message it receives. It ends
+/// when the connection is closed or the runner quits.
+pub async fn archiver<C, I>(connector: C, channels: I) -> Result<AsyncRunner, Error>
+where
+    C: Connector,
+    for<'a> &'a C::Output: AsyncRead + AsyncWrite + Send + Sync + Unpin,
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let user_config = UserConfig::builder()
+        .anonymous()
+        .enable_all_capabilities()
+        .build()
+        .expect("an anonymous config is always valid");
+
+    let mut runner = AsyncRunner::connect(connector, &user_config).await?;
+    for channel in channels {
+        runner.join(channel.as_ref()).await?;
+    }
+    Ok(runner)
+}
+
+#[cfg(feature = "moderation")]
+#[cfg_attr(docsrs, doc(cfg(feature = "moderation")))]
+/// Connect anonymously and join these channels, yielding only their moderation events.
+///
+/// See [ModerationFeed] for which messages are yielded.
+pub async fn moderation_feed<C, I>(connector: C, channels: I) -> Result<ModerationFeed, Error>
+where
+    C: Connector,
+    for<'a> &'a C::Output: AsyncRead + AsyncWrite + Send + Sync + Unpin,
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    archiver(connector, channels)
+        .await
+        .map(|runner| ModerationFeed { runner })
+}
+
+#[cfg(feature = "moderation")]
+#[cfg_attr(docsrs, doc(cfg(feature = "moderation")))]
+/// A [Stream] of the moderation events an [AsyncRunner] receives.
+///
+/// These are bans and timeouts (and cleared chats) as [ClearChat](crate::messages::ClearChat),
+/// deleted messages as [ClearMsg](crate::messages::ClearMsg), and changes of
+/// the room modes (e.g. slow mode) as [RoomState](crate::messages::RoomState).
+/// Twitch also sends the modes of a room when it is joined.
+///
+/// Every other message is still handled by the runner, then dropped.
+#[derive(Debug)]
+pub struct ModerationFeed {
+    runner: AsyncRunner,
+}
+
+#[cfg(feature = "moderation")]
+impl ModerationFeed {
+    /// Yield the moderation events of this runner
+    pub fn new(runner: AsyncRunner) -> Self {
+        Self { runner }
+    }
+
+    /// Whether this message is a moderation event
+    pub fn is_moderation(msg: &Commands<'_>) -> bool {
+        matches!(
+            msg,
+            Commands::ClearChat(..) | Commands::ClearMsg(..) | Commands::RoomState(..)
+        )
+    }
+
+    /// Get the runner, e.g. to join more channels
+    pub fn runner(&mut self) -> &mut AsyncRunner {
+        &mut self.runner
+    }
+
+    /// Get the runner back
+    pub fn into_runner(self) -> AsyncRunner {
+        self.runner
+    }
+}
+
+#[cfg(feature = "moderation")]
+impl Stream for ModerationFeed {
+    type Item = Commands<'static>;
+
+    fn poll_next(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let runner = &mut self.get_mut().runner;
+        loop {
+            match futures_lite::ready!(Pin::new(&mut *runner).poll_next(ctx)) {
+                Some(msg) if Self::is_moderation(&msg) => break Poll::Ready(Some(msg)),
+                Some(..) => continue,
+                None => break Poll::Ready(None),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::test::TestConnector;
+    use futures_lite::StreamExt as _;
+
+    const REGISTER: &str = ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                            :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                            :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #museun\r\n\
+                            :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #shaken_bot\r\n";
+
+    const CHAT: &str = ":foo!foo@foo PRIVMSG #museun :buy followers\r\n\
+                        @ban-duration=600 :tmi.twitch.tv CLEARCHAT #museun :foo\r\n\
+                        :bar!bar@bar PRIVMSG #shaken_bot :hello\r\n\
+                        @login=bar;target-msg-id=1 :tmi.twitch.tv CLEARMSG #shaken_bot :hello\r\n";
+
+    #[test]
+    fn archiver() {
+        futures_lite::future::block_on(async {
+            let connector = TestConnector::default();
+            connector.conn.write_data(format!("{}{}", REGISTER, CHAT)).await;
+
+            let runner = super::archiver(connector.clone(), &["#museun", "shaken_bot"])
+                .await
+                .unwrap();
+            assert!(runner.is_on_channel("#museun"));
+            assert!(runner.is_on_channel("#shaken_bot"));
+
+            let chat = runner
+                .filter(|msg| matches!(msg, Commands::Privmsg(..)))
+                .map(|msg| msg.raw().to_string())
+                .collect::<Vec<_>>()
+                .await;
+            assert_eq!(
+                chat,
+                vec![
+                    ":foo!foo@foo PRIVMSG #museun :buy followers\r\n",
+                    ":bar!bar@bar PRIVMSG #shaken_bot :hello\r\n",
+                ]
+            );
+
+            let sent = connector.conn.read_all_lines().await.unwrap();
+            for line in &[
+                "NICK justinfan1234\r\n",
+                "JOIN #museun\r\n",
+                "JOIN #shaken_bot\r\n",
+            ] {
+                assert!(sent.iter().any(|sent| sent == line), "{}", line);
+            }
+        });
+    }
+
+    #[test]
+    fn moderation_feed() {
+        futures_lite::future::block_on(async {
+            let connector = TestConnector::default();
+            connector.conn.write_data(format!("{}{}", REGISTER, CHAT)).await;
+
+            let feed = super::moderation_feed(connector, &["#museun", "#shaken_bot"])
+                .await
+                .unwrap();
+
+            let events = feed.collect::<Vec<_>>().await;
+            assert_eq!(events.len(), 2);
+            assert!(matches!(&events[0], Commands::ClearChat(msg) if msg.name() == Some("foo")));
+            assert!(matches!(&events[1], Commands::ClearMsg(msg) if msg.login() == Some("bar")));
+        });
+    }
+}
@@ -8,7 +8,8 @@ use std::time::{Duration, Instant};
 
 /// A preset number of tokens as described by Twitch
 #[non_exhaustive]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub enum RateClass {
     /// `20` per `30` seconds
     Regular,
@@ -144,9 +145,16 @@ impl RateLimit {
     /// * Failure to consume (e.g. out of tokens) will return a Duration of when
     ///   the bucket will be refilled
     pub fn consume(&mut self, tokens: u64) -> Result<u64, Duration> {
+        self.consume_at(tokens, Instant::now())
+    }
+
+    /// Consume a specific ammount of tokens, as if it was `now`
+    ///
+    /// See [RateLimit::consume]. The periods start at the first consumption,
+    /// so `now` should come from the same clock every time.
+    pub fn consume_at(&mut self, tokens: u64, now: Instant) -> Result<u64, Duration> {
         let Self { bucket, .. } = self;
 
-        let now = Instant::now();
         if let Some(n) = bucket.refill(now) {
             bucket.tokens = std::cmp::min(bucket.tokens + n, self.cap);
         }
@@ -166,39 +174,38 @@ impl RateLimit {
 struct Bucket {
     tokens: u64,
     backoff: u32,
-    next: Instant,
-    last: Instant,
+    // the start of the current period, set by the first refill
+    last: Option<Instant>,
     quantum: u64,
     period: Duration,
 }
 
 impl Bucket {
     fn new(tokens: u64, initial: u64, period: Duration) -> Self {
-        let now = Instant::now();
         Self {
             tokens: initial,
             backoff: 0,
-            next: now + period,
-            last: now,
+            last: None,
             quantum: tokens,
             period,
         }
     }
 
     fn refill(&mut self, now: Instant) -> Option<u64> {
-        if now < self.next {
+        let last = *self.last.get_or_insert(now);
+        if now < last + self.period {
             return None;
         }
 
-        let last = now.duration_since(self.last);
-        let periods = last.as_nanos().checked_div(self.period.as_nanos())? as u64;
-        self.last += self.period * (periods as u32);
-        self.next = self.last + self.period;
+        let elapsed = now.duration_since(last);
+        let periods = elapsed.as_nanos().checked_div(self.period.as_nanos())? as u64;
+        self.last = Some(last + self.period * (periods as u32));
         (periods * self.quantum).into()
     }
 
     fn estimate(&mut self, tokens: u64, now: Instant) -> Duration {
-        let until = self.next.duration_since(now);
+        let next = self.last.unwrap_or(now) + self.period;
+        let until = next.saturating_duration_since(now);
         let periods = (tokens.checked_add(self.quantum).unwrap() - 1) / self.quantum;
         until + self.period * (periods as u32 - 1)
     }
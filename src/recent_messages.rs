@@ -0,0 +1,274 @@
+/*!
+A client for the [recent-messages](https://recent-messages.robotty.de/) service.
+
+Twitch doesn't send any chat history when you join a channel. This community
+service keeps the last few hundred lines of a channel, which can be used to
+show some context from before the connection was opened.
+
+The lines are parsed as the same [Commands] the [AsyncRunner](crate::AsyncRunner)
+yields. The service adds a `historical=1` tag and a `rm-received-ts` tag (the
+unix timestamp in milliseconds of when it received the line) to each of them.
+
+This speaks plain HTTP over whatever stream the [Connector] returns, so you
+want a TLS connector to the [ADDRESS] with the [DOMAIN], e.g.
+`SmolConnectorTls::custom(ADDRESS, DOMAIN)`.
+
+```no_run
+# use twitchchat::{connector::Connector, recent_messages::RecentMessages};
+# async fn demo(connector: impl Connector) -> Result<(), twitchchat::recent_messages::Error> {
+let mut client = RecentMessages::new(connector);
+for msg in client.fetch("#museun", Some(50)).await? {
+    println!("{}", msg.raw().escape_debug());
+}
+# Ok(())
+# }
+```
+*/
+
+use crate::{connector::Connector, messages::Commands, FromIrcMessage as _, IntoOwned as _};
+
+/// The address of the recent-messages service
+pub const ADDRESS: &str = "recent-messages.robotty.de:443";
+
+/// The TLS domain (and HTTP host) of the recent-messages service
+pub const DOMAIN: &str = "recent-messages.robotty.de";
+
+/// An error returned by the [RecentMessages] client
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An I/O error occurred
+    Io(std::io::Error),
+    /// The response was not a valid HTTP response
+    InvalidResponse,
+    /// The service responded with an unexpected HTTP status
+    Status(u16),
+    /// The response body was not the expected JSON
+    Json(serde_json::Error),
+    /// The service returned an error
+    Service {
+        /// The error code, e.g. `channel_not_joined`
+        code: Option<String>,
+        /// The error message
+        message: String,
+    },
+    /// A line was not a valid Twitch message
+    Message(crate::MessageError),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io error: {}", err),
+            Self::InvalidResponse => write!(f, "invalid HTTP response"),
+            Self::Status(status) => write!(f, "unexpected HTTP status: {}", status),
+            Self::Json(err) => write!(f, "invalid response body: {}", err),
+            Self::Service {
+                code: Some(code),
+                message,
+            } => {
+                write!(f, "service error ({}): {}", code, message)
+            }
+            Self::Service {
+                code: None,
+                message,
+            } => write!(f, "service error: {}", message),
+            Self::Message(err) => write!(f, "invalid message: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Json(err) => Some(err),
+            Self::Message(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<crate::MessageError> for Error {
+    fn from(err: crate::MessageError) -> Self {
+        Self::Message(err)
+    }
+}
+
+#[derive(::serde::Deserialize)]
+struct Response {
+    #[serde(default)]
+    messages: Vec<String>,
+    error: Option<String>,
+    error_code: Option<String>,
+}
+
+/// A client for the recent-messages service
+#[derive(Debug, Clone)]
+pub struct RecentMessages<C> {
+    connector: C,
+    host: String,
+}
+
+impl<C> RecentMessages<C>
+where
+    C: Connector,
+{
+    /// Create a client that connects with this connector
+    pub fn new(connector: C) -> Self {
+        Self::with_host(connector, DOMAIN)
+    }
+
+    /// Create a client for a self-hosted instance of the service, sending this `Host` header
+    pub fn with_host(connector: C, host: impl Into<String>) -> Self {
+        Self {
+            connector,
+            host: host.into(),
+        }
+    }
+
+    /// Fetch the recent messages of this `channel`, oldest first.
+    ///
+    /// `limit` is the maximum number of messages, the service's own limit is used if this is None.
+    pub async fn fetch(
+        &mut self,
+        channel: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<Commands<'static>>, Error> {
+        let request = format!(
+            "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: {}\r\nAccept: application/json\r\nConnection: close\r\n\r\n",
+            request_path(channel, limit),
+            self.host,
+            crate::http::user_agent(),
+        );
+
+        let response = crate::http::send(&mut self.connector, &request).await?;
+        let (status, body) =
+            crate::http::split_response(&response).ok_or(Error::InvalidResponse)?;
+
+        match parse_response(body) {
+            Err(Error::Json(..)) if status != 200 => Err(Error::Status(status)),
+            Ok(..) if status != 200 => Err(Error::Status(status)),
+            res => res,
+        }
+    }
+}
+
+/// The path of the request for the recent messages of this `channel`
+pub fn request_path(channel: &str, limit: Option<usize>) -> String {
+    let channel = channel.strip_prefix('#').unwrap_or(channel).to_lowercase();
+    match limit {
+        Some(limit) => format!("/api/v2/recent-messages/{}?limit={}", channel, limit),
+        None => format!("/api/v2/recent-messages/{}", channel),
+    }
+}
+
+/// Parse a JSON response body of the service into messages, oldest first
+pub fn parse_response(body: &str) -> Result<Vec<Commands<'static>>, Error> {
+    let response: Response = serde_json::from_str(body)?;
+    if let Some(message) = response.error {
+        return Err(Error::Service {
+            code: response.error_code,
+            message,
+        });
+    }
+
+    response
+        .messages
+        .into_iter()
+        .map(|mut line| {
+            if !line.ends_with("\r\n") {
+                line.push_str("\r\n");
+            }
+            let (_, msg) = crate::irc::parse_one(&line)?;
+            Ok(Commands::from_irc(msg)?.into_owned())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &str = r#"{
+        "messages": [
+            "@historical=1;rm-received-ts=1617292812345 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello world",
+            "@historical=1;rm-received-ts=1617292812346 :tmi.twitch.tv CLEARCHAT #museun :shaken_bot\r\n"
+        ],
+        "error": null,
+        "error_code": null
+    }"#;
+
+    #[test]
+    fn path() {
+        assert_eq!(
+            request_path("#Museun", Some(10)),
+            "/api/v2/recent-messages/museun?limit=10"
+        );
+        assert_eq!(
+            request_path("museun", None),
+            "/api/v2/recent-messages/museun"
+        );
+    }
+
+    #[test]
+    fn parse() {
+        let messages = parse_response(BODY).unwrap();
+        assert_eq!(messages.len(), 2);
+        match &messages[0] {
+            Commands::Privmsg(msg) => {
+                assert_eq!(msg.data(), "hello world");
+                assert_eq!(msg.tags().get("historical"), Some("1"));
+            }
+            msg => panic!("unexpected message: {:?}", msg),
+        }
+        assert!(matches!(messages[1], Commands::ClearChat(..)));
+
+        let err = parse_response(
+            r#"{"messages": [], "error": "channel is suspended", "error_code": "channel_suspended"}"#,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Service { code: Some(code), .. } if code == "channel_suspended"
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "testing")]
+    fn fetch() {
+        let connector = crate::test::TestConnector::default();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{}",
+            BODY
+        );
+
+        let messages = futures_lite::future::block_on(async {
+            connector.conn.write_data(response).await;
+            RecentMessages::new(connector.clone())
+                .fetch("#museun", Some(2))
+                .await
+        })
+        .unwrap();
+        assert_eq!(messages.len(), 2);
+
+        let request = futures_lite::future::block_on(connector.conn.read_all_lines()).unwrap();
+        assert_eq!(
+            request[0],
+            "GET /api/v2/recent-messages/museun?limit=2 HTTP/1.0\r\n"
+        );
+        assert_eq!(request[1], "Host: recent-messages.robotty.de\r\n");
+    }
+}
@@ -1,32 +1,63 @@
 cfg_async! {
 use crate::{
-    channel::Receiver,
+    channel::{Receiver, Sender, TrySendError},
     commands,
     connector::Connector,
     encoder::AsyncEncoder,
-    messages::{Capability, Commands, MessageId},
+    metrics::Metrics,
+    messages::{
+        Commands, ContextBuffer, MessageId, Notice, Notification, Notifications, UserState,
+    },
     rate_limit::{RateClass, RateLimit},
-    twitch::UserConfig,
+    twitch::{Deprecation, UserConfig},
     util::{Notify, NotifyHandle},
     writer::{AsyncWriter, MpscWriter},
-    AsyncDecoder, DecodeError, Encodable, FromIrcMessage, IrcMessage,
+    AsyncDecoder, BoxedFuture, DecodeError, Encodable, FromIrcMessage, IrcMessage,
 };
 
 use super::{
     channel::Channels,
+    config::{ConfigChange, ConfigHandle, RuntimeConfig},
+    replay::{Clock, Recorder, Replay},
     timeout::{TimeoutState, RATE_LIMIT_WINDOW, TIMEOUT, WINDOW},
-    Capabilities, Channel, Error, Identity, Status, StepResult,
+    rejection::REJECTION_WINDOW,
+    join_manager::{JoinManager, JOIN_TIMEOUT},
+    envelope::next_connection_id,
+    handshake::Handshake,
+    session::Session,
+    Channel, DuplicateEcho, Envelope, Error, Health, Identity, JoinEvent, JoinFailure,
+    ModerationCommand, ModerationOutcome, RaidOutcome, RoleChange, Roles, SendRejected,
+    SessionSummary, Status, StepResult, Transform,
 };
 
+#[cfg(feature = "usernotice")]
+use super::raid::RAID_WINDOW;
+
 use futures_lite::{AsyncRead, AsyncWrite, AsyncWriteExt, Stream};
 use std::{
     collections::{HashSet, VecDeque},
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
+    time::Duration,
     iter::Iterator
 };
 
+type BoxedRead = Box<dyn AsyncRead + Send + Sync + Unpin>;
+type BoxedWrite = Box<dyn AsyncWrite + Send + Sync + Unpin>;
+
+// a registered connection that isn't driven by a runner yet
+struct Connection {
+    decoder: AsyncDecoder<BoxedRead>,
+    encoder: AsyncEncoder<BoxedWrite>,
+    identity: Identity,
+    missed_messages: VecDeque<Commands<'static>>,
+    id: u64,
+}
+
 /// An asynchronous runner
+///
+/// This is a `futures::Stream` of the messages it receives, see `AsyncRunner::next_message`
 pub struct AsyncRunner {
     /// You identity that Twitch gives when you connected
     pub identity: Identity,
@@ -41,14 +72,49 @@ pub struct AsyncRunner {
     notify_handle: NotifyHandle,
 
     timeout_state: TimeoutState,
+    clock: Clock,
 
-    decoder: AsyncDecoder<Box<dyn AsyncRead + Send + Sync + Unpin>>,
-    encoder: AsyncEncoder<Box<dyn AsyncWrite + Send + Sync + Unpin>>,
+    decoder: AsyncDecoder<BoxedRead>,
+    encoder: AsyncEncoder<BoxedWrite>,
 
     writer: AsyncWriter<MpscWriter>,
     global_rate_limit: RateLimit,
 
+    config: ConfigHandle,
+    config_rx: Receiver<ConfigChange>,
+
+    deprecations: Vec<Sender<Deprecation>>,
+    reported_tags: HashSet<Deprecation>,
+    send_rejections: Vec<Sender<SendRejected>>,
+
+    joins: JoinManager,
+    join_events: Vec<Sender<JoinEvent>>,
+    duplicate_echoes: Vec<Sender<DuplicateEcho>>,
+
+    role_changes: Vec<Sender<RoleChange>>,
+
+    notifier: Option<Notifications>,
+    notifications: Vec<Sender<Notification>>,
+
+    envelopes: Vec<Sender<Envelope>>,
+    shared_envelopes: Vec<Sender<Arc<Envelope>>>,
+    transform: Option<Box<dyn Transform>>,
+    connection_id: u64,
+    sequence: u64,
+
+    metrics: Arc<Metrics>,
+    health: Arc<Health>,
+
+    session: Session,
+    session_summaries: Vec<Sender<SessionSummary>>,
+
     missed_messages: VecDeque<Commands<'static>>,
+    // messages a wait (e.g. in `join`) got from `step`. they were transformed
+    // and published then, so they are yielded as they are
+    delivered: VecDeque<Commands<'static>>,
+    // the message being yielded, and its transform. these are kept until the
+    // message is returned, so a cancelled `next_message` doesn't lose them
+    pending: Option<(Commands<'static>, Option<BoxedFuture<Commands<'static>>>)>,
 }
 
 impl std::fmt::Debug for AsyncRunner {
@@ -66,35 +132,13 @@ impl AsyncRunner {
         C: Connector,
         for<'a> &'a C::Output: AsyncRead + AsyncWrite + Send + Sync + Unpin,
     {
-        log::debug!("connecting");
-        let mut stream = { connector }.connect().await?;
-        log::debug!("connection established");
-
-        log::debug!("registering");
-        let mut buf = vec![];
-        commands::register(user_config).encode(&mut buf)?;
-        stream.write_all(&buf).await?;
-        log::debug!("registered");
-
-        let read = async_dup::Arc::new(stream);
-        let write = read.clone();
-
-        let read: Box<dyn AsyncRead + Send + Sync + Unpin> = Box::new(read);
-        let write: Box<dyn AsyncWrite + Send + Sync + Unpin> = Box::new(write);
-
-        let mut decoder = AsyncDecoder::new(read);
-        let mut encoder = AsyncEncoder::new(write);
-
-        log::debug!("waiting for the connection to be ready");
-        let mut missed_messages = VecDeque::new();
-        let identity = Self::wait_for_ready(
-            &mut decoder,
-            &mut encoder,
-            user_config,
-            &mut missed_messages,
-        )
-        .await?;
-        log::debug!("connection is ready: {:?}", identity);
+        let Connection {
+            decoder,
+            encoder,
+            identity,
+            missed_messages,
+            id: connection_id,
+        } = Self::open(connector, user_config).await?;
 
         let (writer_tx, writer_rx) = crate::channel::unbounded();
         let (notify, notify_handle) = Notify::new();
@@ -107,6 +151,12 @@ impl AsyncRunner {
 
         let global_rate_limit = RateLimit::from_class(RateClass::Regular);
 
+        let config = ConfigHandle::new(RuntimeConfig {
+            capabilities: user_config.capabilities.iter().copied().collect(),
+            ..RuntimeConfig::default()
+        });
+        let config_rx = config.subscribe();
+
         Ok(Self {
             identity,
             channels,
@@ -118,6 +168,7 @@ impl AsyncRunner {
             notify_handle,
 
             timeout_state,
+            clock: Clock::System,
 
             decoder,
             encoder,
@@ -125,10 +176,281 @@ impl AsyncRunner {
             writer,
             global_rate_limit,
 
+            config,
+            config_rx,
+
+            deprecations: vec![],
+            reported_tags: HashSet::new(),
+            send_rejections: vec![],
+
+            joins: JoinManager::default(),
+            join_events: vec![],
+            duplicate_echoes: vec![],
+
+            role_changes: vec![],
+
+            notifier: None,
+            notifications: vec![],
+
+            envelopes: vec![],
+            shared_envelopes: vec![],
+            transform: None,
+            connection_id,
+            sequence: 0,
+
+            metrics: Arc::default(),
+            health: Arc::new(Health::new(connection_id)),
+
+            session: Session::default(),
+            session_summaries: vec![],
+
             missed_messages,
+            delivered: VecDeque::new(),
+            pending: None,
         })
     }
 
+    /// Connect like [AsyncRunner::connect], recording the session with the [Recorder].
+    ///
+    /// The lines that are read and written, and the idle timer firing, are
+    /// recorded. See [Replay] for running the session again.
+    pub async fn connect_recorded<C>(
+        connector: C,
+        user_config: &UserConfig,
+        recorder: &Recorder,
+    ) -> Result<Self, Error>
+    where
+        C: Connector,
+    {
+        let mut this = Self::connect(recorder.connector(connector), user_config).await?;
+        this.clock = Clock::Recording(recorder.clone());
+        Ok(this)
+    }
+
+    /// Connect to a [Replay] of a recorded session, instead of to Twitch.
+    ///
+    /// The `user_config` should be the one the session was recorded with,
+    /// or the lines sent when registering differ from the recording.
+    pub async fn connect_replay(replay: &Replay, user_config: &UserConfig) -> Result<Self, Error> {
+        let mut this = Self::connect(replay.connector(), user_config).await?;
+        this.clock = Clock::Replay(replay.clone());
+        Ok(this)
+    }
+
+    /// Reconnect with a new `user_config` (e.g. a rotated OAuth token) without missing any messages.
+    ///
+    /// This opens a second connection, joins all of the channels you're on
+    /// with it, and only then switches over to it and quits the old connection.
+    /// Until then, messages from the old connection are handled as usual.
+    ///
+    /// Messages received on both connections while switching are only yielded
+    /// once. Your writers, channel state, rate limits and config are kept.
+    ///
+    /// A channel that Twitch doesn't let the new connection join (e.g. because
+    /// it was suspended), or that isn't rejoined within 10 seconds, is left to
+    /// the [JoinManager] as a failed join: it is retried or given up on as
+    /// usual, see [AsyncRunner::join_events].
+    ///
+    /// If the new connection cannot be established, the old one is kept and the error is returned.
+    pub async fn reauthenticate<C>(
+        &mut self,
+        connector: C,
+        user_config: &UserConfig,
+    ) -> Result<(), Error>
+    where
+        C: Connector,
+        for<'a> &'a C::Output: AsyncRead + AsyncWrite + Send + Sync + Unpin,
+    {
+        use crate::util::*;
+        use crate::IntoOwned as _;
+
+        log::debug!("reauthenticating");
+        let Connection {
+            mut decoder,
+            mut encoder,
+            identity,
+            id: connection_id,
+            ..
+        } = Self::open(connector, user_config).await?;
+
+        // the registration messages are dropped, the caller has already seen them once
+        let mut buffered = VecDeque::new();
+
+        let mut pending = HashSet::new();
+        for channel in self.channels.map.keys() {
+            log::debug!("rejoining '{}' on the new connection", channel);
+            encoder.encode(commands::join(channel)).await?;
+            pending.insert(channel.clone());
+        }
+
+        // raw lines read from the old connection while both are open
+        let mut seen = HashSet::new();
+        let mut old_eof = false;
+
+        // the channels that weren't rejoined, and why
+        let mut failed = vec![];
+        let deadline = self.clock.now() + JOIN_TIMEOUT;
+
+        while !pending.is_empty() {
+            let left = deadline.saturating_duration_since(self.clock.now());
+            let timer = self.clock.wait(Some(left));
+            let select = if old_eof {
+                match decoder.read_message().either(timer).await {
+                    Left(msg) => Some(Right(msg)),
+                    Right(()) => None,
+                }
+            } else {
+                match self
+                    .decoder
+                    .read_message()
+                    .either(decoder.read_message())
+                    .either(timer)
+                    .await
+                {
+                    Left(select) => Some(select),
+                    Right(()) => None,
+                }
+            };
+
+            let select = match select {
+                Some(select) => select,
+                None => {
+                    for channel in pending.drain() {
+                        log::warn!("'{}' wasn't rejoined in time", channel);
+                        failed.push((channel, JoinFailure::NoResponse));
+                    }
+                    break;
+                }
+            };
+
+            match select {
+                Left(Err(DecodeError::Eof)) => {
+                    log::info!("the old connection was closed while reauthenticating");
+                    old_eof = true;
+                }
+
+                Left(msg) => {
+                    let msg = msg?;
+                    self.timeout_state = TimeoutState::Activity(self.clock.now());
+                    self.metrics.observe(&msg);
+                    self.health.message_received();
+                    self.session.messages_in += 1;
+                    Self::check_deprecations(
+                        &mut self.deprecations,
+                        &mut self.reported_tags,
+                        &msg,
+                    );
+
+                    let ignored = self.config.load().is_ignored_message(&msg);
+                    if ignored && !Self::is_tracked(&msg) {
+                        self.session.dropped_messages += 1;
+                        continue;
+                    }
+
+                    let all = Self::parse_commands(&self.metrics, msg).into_owned();
+                    seen.insert(all.raw().to_string());
+
+                    if let Some(echo) = self.duplicate_echo(&all) {
+                        self.drop_duplicate_echo(echo);
+                        continue;
+                    }
+
+                    self.check_messages(&all)?;
+                    self.encoder.write_buffered().await?;
+
+                    if ignored {
+                        self.session.dropped_messages += 1;
+                    } else {
+                        self.missed_messages.push_back(all);
+                    }
+                }
+
+                Right(msg) => {
+                    let msg = msg?;
+                    self.metrics.observe(&msg);
+                    self.health.message_received();
+                    self.session.messages_in += 1;
+                    let ignored = self.config.load().is_ignored_message(&msg);
+
+                    let all = Self::parse_commands(&self.metrics, msg).into_owned();
+
+                    match &all {
+                        Commands::Ping(msg) => {
+                            encoder.encode(commands::pong(msg.token())).await?;
+                            continue;
+                        }
+
+                        Commands::Join(msg) if msg.name() == identity.username() => {
+                            log::debug!("rejoined '{}'", msg.channel());
+                            // you never left the channel, so this isn't yielded
+                            if pending.remove(msg.channel()) {
+                                self.drop_duplicate_echo(DuplicateEcho::Join {
+                                    channel: msg.channel().to_string(),
+                                });
+                                continue;
+                            }
+                        }
+
+                        Commands::Notice(msg) if pending.contains(msg.channel()) => {
+                            let failure = msg.msg_id().as_ref().and_then(JoinFailure::from_msg_id);
+                            if let Some(failure) = failure {
+                                log::warn!("cannot rejoin '{}' ({})", msg.channel(), failure);
+                                pending.remove(msg.channel());
+                                failed.push((msg.channel().to_string(), failure));
+                            }
+                        }
+
+                        _ => {}
+                    }
+
+                    if ignored {
+                        self.session.dropped_messages += 1;
+                    } else {
+                        buffered.push_back(all);
+                    }
+                }
+            }
+        }
+
+        log::debug!("switching to the new connection");
+        if !old_eof {
+            // the old connection is going away, so an error here doesn't matter
+            let _ = self.encoder.encode(commands::raw("QUIT\r\n")).await;
+        }
+
+        self.decoder = decoder;
+        self.encoder = encoder;
+        self.identity = identity;
+        if let Some(notifier) = self.notifier.take() {
+            let context = notifier.into_context();
+            self.notifier.replace(Self::notifier(&self.identity, context));
+        }
+        self.connection_id = connection_id;
+        self.reported_tags.clear();
+        self.timeout_state = TimeoutState::Activity(self.clock.now());
+        self.session.reconnects += 1;
+        self.health.reconnected(connection_id);
+
+        // these are retried on the new connection as if their JOIN failed
+        let now = self.clock.now();
+        for (channel, failure) in failed {
+            self.channels.remove(&channel);
+            self.joins.sent(&channel, now);
+            if let Some(event) = self.joins.failed(&channel, failure, now) {
+                if let JoinEvent::GaveUp { .. } = event {
+                    self.sync_config_channel(&channel, false);
+                }
+                self.send_join_event(event);
+            }
+        }
+
+        self.missed_messages
+            .extend(buffered.into_iter().filter(|msg| !seen.contains(msg.raw())));
+
+        log::debug!("reauthenticated: {:?}", self.identity);
+        Ok(())
+    }
+
     /// Check whether you're on this channel
     pub fn is_on_channel(&self, channel: &str) -> bool {
         self.channels.is_on(channel)
@@ -151,6 +473,171 @@ impl AsyncRunner {
         self.writer.clone()
     }
 
+    /// Get a handle that you can use to change the configuration of this runner
+    /// without reconnecting.
+    ///
+    /// Changes are applied the next time the runner is stepped:
+    /// * a new [RateClass] replaces the rate limit of the connection and every channel
+    /// * added or removed channels are joined or parted, without waiting for a response
+    /// * messages from ignored users, on ignored channels or with ignored
+    ///   commands are dropped before they are parsed
+    /// * added capabilities are requested. Removed capabilities only take
+    ///   effect once you reconnect
+    pub fn config_handle(&self) -> ConfigHandle {
+        self.config.clone()
+    }
+
+    /// Subscribe to the deprecated Twitch features used by the messages this
+    /// runner receives or sends, e.g. `HOSTTARGET` or `/host`.
+    ///
+    /// Messages are only checked while there is at least one subscriber. A
+    /// deprecated tag (which is on almost every message) is only reported the
+    /// first time it is seen on each command, once per connection.
+    pub fn deprecations(&mut self) -> Receiver<Deprecation> {
+        let (tx, rx) = crate::channel::unbounded();
+        self.deprecations.push(tx);
+        rx
+    }
+
+    /// Subscribe to the chat messages that Twitch rejected, e.g. because the
+    /// room is in emote-only mode, or you sent messages too quickly in slow mode.
+    ///
+    /// When the rejected message was sent by this runner shortly before, it is
+    /// included so it can be fixed and sent again.
+    pub fn send_rejections(&mut self) -> Receiver<SendRejected> {
+        let (tx, rx) = crate::channel::unbounded();
+        self.send_rejections.push(tx);
+        rx
+    }
+
+    /// Subscribe to the retries of failed joins, and the joins that were given up on.
+    ///
+    /// See [AsyncRunner::join_manager] for how joins are retried.
+    pub fn join_events(&mut self) -> Receiver<JoinEvent> {
+        let (tx, rx) = crate::channel::unbounded();
+        self.join_events.push(tx);
+        rx
+    }
+
+    /// Subscribe to the JOINs and PARTs of your own that Twitch sent again.
+    ///
+    /// Twitch sometimes echoes a JOIN of a channel you are already on (e.g.
+    /// when rejoining after [reauthenticating](AsyncRunner::reauthenticate)),
+    /// or a PART of a channel you already left. These aren't yielded as
+    /// messages, so a presence cache or the other subscribers don't see them
+    /// twice. They are counted as dropped in the [SessionSummary].
+    pub fn duplicate_echoes(&mut self) -> Receiver<DuplicateEcho> {
+        let (tx, rx) = crate::channel::unbounded();
+        self.duplicate_echoes.push(tx);
+        rx
+    }
+
+    /// Subscribe to the changes of your roles in the channels you're on, e.g. being modded.
+    ///
+    /// The roles are read from the badges of the `USERSTATE` Twitch sends when
+    /// joining and after each message you send. When you become (or stop being)
+    /// a moderator or VIP, the [RateClass] of the channel is changed to match,
+    /// see [Roles::rate_class].
+    pub fn role_changes(&mut self) -> Receiver<RoleChange> {
+        let (tx, rx) = crate::channel::unbounded();
+        self.role_changes.push(tx);
+        rx
+    }
+
+    /// Subscribe to the messages that mention you on the channels you're on, and the whispers you get.
+    ///
+    /// Your username and display name are looked for, see [Notifications]. The
+    /// last 20 messages of each channel, from the last 10 minutes, are kept as
+    /// the context of a notification, starting with the first subscriber.
+    pub fn notifications(&mut self) -> Receiver<Notification> {
+        if self.notifier.is_none() {
+            let context = ContextBuffer::new(20, Duration::from_secs(10 * 60));
+            self.notifier.replace(Self::notifier(&self.identity, context));
+        }
+
+        let (tx, rx) = crate::channel::unbounded();
+        self.notifications.push(tx);
+        rx
+    }
+
+    /// Get the [JoinManager], to change how the failed joins of a channel are retried.
+    ///
+    /// When a JOIN fails with a transient error (the channel is suspended, or
+    /// Twitch didn't answer it, e.g. because of the join rate limit) it is sent
+    /// again later, with a growing delay. Other failures, or running out of
+    /// retries, are reported as a [JoinEvent::GaveUp].
+    pub fn join_manager(&mut self) -> &mut JoinManager {
+        &mut self.joins
+    }
+
+    /// Subscribe to every message this runner yields, wrapped in an [Envelope]
+    /// with when it was received, the connection id and its sequence number.
+    ///
+    /// This is useful for audit logs, or forwarding the messages to a message queue.
+    /// Messages are still yielded by [AsyncRunner::next_message] as usual.
+    pub fn subscribe_all(&mut self) -> Receiver<Envelope> {
+        let (tx, rx) = crate::channel::unbounded();
+        self.envelopes.push(tx);
+        rx
+    }
+
+    /// Subscribe to every message this runner yields, like [AsyncRunner::subscribe_all],
+    /// but with every subscriber sharing the same [Envelope].
+    ///
+    /// [AsyncRunner::subscribe_all] copies the message for each subscriber,
+    /// this only copies an `Arc`, which is cheaper with many subscribers.
+    pub fn subscribe_shared(&mut self) -> Receiver<Arc<Envelope>> {
+        let (tx, rx) = crate::channel::unbounded();
+        self.shared_envelopes.push(tx);
+        rx
+    }
+
+    /// Subscribe to the [SessionSummary] sent when the connection of this runner ends.
+    ///
+    /// It is sent once, when [AsyncRunner::next_message] returns an error,
+    /// `Status::Quit` or `Status::Eof`, and is also logged at the `info` level.
+    pub fn subscribe_session_summary(&mut self) -> Receiver<SessionSummary> {
+        let (tx, rx) = crate::channel::unbounded();
+        self.session_summaries.push(tx);
+        rx
+    }
+
+    /// Get the [SessionSummary] of the connection so far
+    pub fn session_summary(&self) -> SessionSummary {
+        self.session.summary(None)
+    }
+
+    /// Rewrite messages with this [Transform] before they are yielded, replacing any previous one.
+    ///
+    /// The original message is kept in the [Envelope] of [AsyncRunner::subscribe_all].
+    pub fn set_transform<T>(&mut self, transform: T)
+    where
+        T: Transform + 'static,
+    {
+        self.transform.replace(Box::new(transform));
+    }
+
+    /// Stop rewriting messages
+    pub fn clear_transform(&mut self) {
+        self.transform.take();
+    }
+
+    /// Get the [Metrics] of the messages this runner receives.
+    ///
+    /// Every received message is recorded, including ignored ones, as well as
+    /// the messages that could not be parsed. Use `Metrics::snapshot` to read them.
+    pub fn metrics(&self) -> Arc<Metrics> {
+        self.metrics.clone()
+    }
+
+    /// Get the [Health] of this runner, e.g. for the health check of a HTTP server.
+    ///
+    /// This is updated as the runner runs, so it can be kept around and read
+    /// from another thread. See [HealthReport](super::HealthReport) for combining several runners.
+    pub fn health(&self) -> Arc<Health> {
+        self.health.clone()
+    }
+
     /// Get a handle that you can trigger a normal 'quit'.
     ///
     /// You can also do `AsyncWriter::quit`.
@@ -159,6 +646,9 @@ impl AsyncRunner {
     }
 
     /// Join `channel` and wait for it to complete
+    ///
+    /// A failed join is retried as the [JoinManager] says, this waits until it
+    /// succeeded or was given up on.
     pub async fn join(&mut self, channel: &str) -> Result<(), Error> {
         if self.is_on_channel(channel) {
             return Err(Error::AlreadyOnChannel {
@@ -168,44 +658,225 @@ impl AsyncRunner {
 
         log::debug!("joining '{}'", channel);
         self.encoder.encode(commands::join(channel)).await?;
+        self.joins.sent(channel, self.clock.now());
 
-        let channel = crate::commands::Channel::new(channel).to_string();
+        let channel = commands::Channel::new(channel).to_string();
         log::debug!("waiting for a response");
 
         let mut queue = VecDeque::new();
 
-        let status = self
-            .wait_for(&mut queue, |msg, this| match msg {
-                // check to see if it was us that joined the channel
-                Commands::Join(msg) => {
-                    Ok(msg.channel() == channel && msg.name() == this.identity.username())
-                }
+        loop {
+            match self.step().await? {
+                StepResult::Status(Status::Message(msg)) => {
+                    match &msg {
+                        // check to see if it was us that joined the channel
+                        Commands::Join(msg)
+                            if msg.channel() == channel
+                                && msg.name() == self.identity.username() =>
+                        {
+                            break
+                        }
 
-                // check to see if we were banned
-                Commands::Notice(msg) if matches!(msg.msg_id(), Some(MessageId::MsgBanned)) => {
-                    Err(Error::BannedFromChannel {
-                        channel: msg.channel().to_string(),
-                    })
-                }
+                        // check to see if we were banned
+                        Commands::Notice(msg)
+                            if matches!(msg.msg_id(), Some(MessageId::MsgBanned)) =>
+                        {
+                            return Err(Error::BannedFromChannel {
+                                channel: msg.channel().to_string(),
+                            });
+                        }
 
-                _ => Ok(false),
-            })
-            .await?;
+                        _ => {}
+                    }
+                    queue.push_back(msg);
+                }
+                StepResult::Status(..) => return Err(Error::UnexpectedEof),
+                StepResult::Nothing => {}
+            }
 
-        if let Some(status) = status {
-            match status {
-                Status::Quit | Status::Eof => return Err(Error::UnexpectedEof),
-                _ => unimplemented!(),
+            // check to see if the join was given up on
+            if let Some(failure) = self.joins.gave_up(&channel) {
+                self.requeue(queue);
+                return Err(Error::JoinFailed { channel, failure });
             }
         }
 
-        self.missed_messages.extend(queue);
+        self.requeue(queue);
 
         log::debug!("joined '{}'", channel);
 
         Ok(())
     }
 
+    /// Send a moderation command (e.g. a [ban](commands::ban)) and wait for
+    /// Twitch to reply whether it worked.
+    ///
+    /// The reply is the next `NOTICE` on the channel about this kind of
+    /// command. If there isn't one within 10 seconds, this is
+    /// [ModerationOutcome::NoReply]. Messages read while waiting are yielded
+    /// afterwards, as usual.
+    pub async fn moderate<M>(&mut self, cmd: M) -> Result<ModerationOutcome, Error>
+    where
+        M: ModerationCommand,
+    {
+        use crate::util::{Either::*, FutExt as _};
+
+        let channel = commands::Channel::new(cmd.channel()).to_string();
+        let action = cmd.action();
+        log::debug!("sending {:?} to '{}'", action, channel);
+        self.encoder.encode(cmd).await?;
+
+        // the timer fires on time, and the deadline does in a replay
+        let deadline = self.clock.now() + REJECTION_WINDOW;
+        let clock = self.clock.clone();
+
+        let mut queue = VecDeque::new();
+        let wait = async {
+            loop {
+                if self.clock.now() >= deadline {
+                    break Ok(ModerationOutcome::NoReply);
+                }
+                match self.step().await? {
+                    StepResult::Status(Status::Message(msg)) => {
+                        if let Commands::Notice(notice) = &msg {
+                            if notice.channel() == channel {
+                                if let Some(outcome) = action.outcome_of(notice) {
+                                    break Ok(outcome);
+                                }
+                            }
+                        }
+                        queue.push_back(msg);
+                    }
+                    StepResult::Status(..) => break Err(Error::UnexpectedEof),
+                    StepResult::Nothing => continue,
+                }
+            }
+        };
+
+        let outcome = match wait.first(clock.wait(Some(REJECTION_WINDOW))).await {
+            Left(outcome) => outcome,
+            Right(..) => Ok(ModerationOutcome::NoReply),
+        };
+        self.requeue(queue);
+
+        log::debug!("{:?} on '{}': {:?}", action, channel, outcome);
+        outcome
+    }
+
+    /// Start a [raid](commands::raid) and wait for it to land.
+    ///
+    /// If Twitch rejects the raid, that's the outcome. Otherwise, when you've
+    /// joined the target channel, this waits for the raid to land there, which
+    /// takes up to 90 seconds. When you haven't, or it doesn't land in time,
+    /// this is [RaidOutcome::Pending]. Messages read while waiting are yielded
+    /// afterwards, as usual.
+    #[cfg(feature = "usernotice")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "usernotice")))]
+    pub async fn raid(&mut self, cmd: commands::types::Raid<'_>) -> Result<RaidOutcome, Error> {
+        use crate::util::{Either::*, FutExt as _};
+
+        let channel = commands::Channel::new(cmd.source).to_string();
+        let window = if self.is_on_channel(cmd.target) {
+            RAID_WINDOW
+        } else {
+            REJECTION_WINDOW
+        };
+        log::debug!("raiding '{}' from '{}'", cmd.target, channel);
+        self.encoder.encode(cmd).await?;
+
+        let deadline = self.clock.now() + window;
+        let clock = self.clock.clone();
+
+        let mut queue = VecDeque::new();
+        let wait = async {
+            loop {
+                if self.clock.now() >= deadline {
+                    break Ok(RaidOutcome::Pending);
+                }
+                match self.step().await? {
+                    StepResult::Status(Status::Message(msg)) => {
+                        match &msg {
+                            Commands::Notice(notice) if notice.channel() == channel => {
+                                let outcome =
+                                    notice.msg_id().and_then(|id| RaidOutcome::of_raid(&id));
+                                if let Some(outcome) = outcome {
+                                    break Ok(outcome);
+                                }
+                            }
+                            Commands::UserNotice(notice) if cmd.is_confirmed_by(notice) => {
+                                queue.push_back(msg);
+                                break Ok(RaidOutcome::Landed);
+                            }
+                            _ => {}
+                        }
+                        queue.push_back(msg);
+                    }
+                    StepResult::Status(..) => break Err(Error::UnexpectedEof),
+                    StepResult::Nothing => continue,
+                }
+            }
+        };
+
+        let outcome = match wait.first(clock.wait(Some(window))).await {
+            Left(outcome) => outcome,
+            Right(..) => Ok(RaidOutcome::Pending),
+        };
+        self.requeue(queue);
+
+        log::debug!("raid on '{}': {:?}", channel, outcome);
+        outcome
+    }
+
+    /// Cancel a raid with [unraid](commands::unraid) and wait for Twitch to
+    /// reply whether it worked.
+    ///
+    /// If there isn't a reply within 10 seconds, this is [RaidOutcome::NoReply].
+    /// Messages read while waiting are yielded afterwards, as usual.
+    pub async fn unraid(&mut self, cmd: commands::types::Unraid<'_>) -> Result<RaidOutcome, Error> {
+        use crate::util::{Either::*, FutExt as _};
+
+        let channel = commands::Channel::new(cmd.channel).to_string();
+        log::debug!("cancelling the raid from '{}'", channel);
+        self.encoder.encode(cmd).await?;
+
+        let deadline = self.clock.now() + REJECTION_WINDOW;
+        let clock = self.clock.clone();
+
+        let mut queue = VecDeque::new();
+        let wait = async {
+            loop {
+                if self.clock.now() >= deadline {
+                    break Ok(RaidOutcome::NoReply);
+                }
+                match self.step().await? {
+                    StepResult::Status(Status::Message(msg)) => {
+                        if let Commands::Notice(notice) = &msg {
+                            if notice.channel() == channel {
+                                let outcome =
+                                    notice.msg_id().and_then(|id| RaidOutcome::of_unraid(&id));
+                                if let Some(outcome) = outcome {
+                                    break Ok(outcome);
+                                }
+                            }
+                        }
+                        queue.push_back(msg);
+                    }
+                    StepResult::Status(..) => break Err(Error::UnexpectedEof),
+                    StepResult::Nothing => continue,
+                }
+            }
+        };
+
+        let outcome = match wait.first(clock.wait(Some(REJECTION_WINDOW))).await {
+            Left(outcome) => outcome,
+            Right(..) => Ok(RaidOutcome::NoReply),
+        };
+        self.requeue(queue);
+
+        log::debug!("unraid on '{}': {:?}", channel, outcome);
+        outcome
+    }
+
     /// Part `channel` and wait for it to complete
     pub async fn part(&mut self, channel: &str) -> Result<(), Error> {
         if !self.is_on_channel(channel) {
@@ -217,7 +888,7 @@ impl AsyncRunner {
         log::debug!("leaving '{}'", channel);
         self.encoder.encode(commands::part(channel)).await?;
 
-        let channel = crate::commands::Channel::new(channel).to_string();
+        let channel = commands::Channel::new(channel).to_string();
         log::debug!("waiting for a response");
 
         let mut queue = VecDeque::new();
@@ -240,13 +911,31 @@ impl AsyncRunner {
         }
         log::debug!("left '{}'", channel);
 
-        self.missed_messages.extend(queue);
+        self.requeue(queue);
 
         Ok(())
     }
 
     /// Get the next message. You'll usually want to call this in a loop
+    ///
+    /// This is cancellation safe, so it can be used in a `select!`: if the
+    /// future is dropped before it completes, no message is lost. A message
+    /// that was already read is returned by the next call, and one that was
+    /// partly read is finished then.
+    ///
+    /// A [Transform] that was started for a message is kept, and continues on
+    /// the next call.
     pub async fn next_message(&mut self) -> Result<Status<'static>, Error> {
+        let status = self.next_status().await;
+        match &status {
+            Ok(Status::Message(..)) => {}
+            Ok(..) => self.end_session(None),
+            Err(err) => self.end_session(Some(err.to_string())),
+        }
+        status
+    }
+
+    async fn next_status(&mut self) -> Result<Status<'static>, Error> {
         use crate::util::{Either::*, FutExt as _};
 
         loop {
@@ -276,14 +965,52 @@ impl AsyncRunner {
         }
     }
 
+    /// Poll for the next message once, without waiting.
+    ///
+    /// This returns `Ok(None)` if there is no message yet. It's meant for
+    /// loops that can't await, e.g. a game loop calling it once per frame.
+    /// As with [next_message](Self::next_message), nothing is lost when there
+    /// is no message yet.
+    ///
+    /// The connector's runtime has to be available on this thread, e.g. in a
+    /// `tokio` runtime context.
+    pub fn try_next_now(&mut self) -> Result<Option<Status<'static>>, Error> {
+        use futures_lite::future::{block_on, poll_once};
+        block_on(poll_once(self.next_message())).transpose()
+    }
+
     /// Single step the loop. This is useful for testing.
     pub async fn step(&mut self) -> Result<StepResult<'static>, Error> {
         use crate::util::*;
         use crate::IntoOwned as _;
 
-        if let Some(msg) = self.missed_messages.pop_front() {
+        // finish what a cancelled step started
+        self.encoder.write_buffered().await?;
+        // these are older than a message a cancelled step read
+        if let Some(msg) = self.delivered.pop_front() {
             return Ok(StepResult::Status(Status::Message(msg)));
         }
+        if self.pending.is_none() {
+            self.pending = self.missed_messages.pop_front().map(|msg| (msg, None));
+        }
+        if let Some(msg) = self.deliver_pending().await {
+            return Ok(StepResult::Status(Status::Message(msg)));
+        }
+
+        self.retry_joins().await?;
+
+        // writes made since the last step are sent before reading on, so a
+        // reply doesn't race the next message (or the end of the connection)
+        let mut wrote = false;
+        while let Some(write_data) = self.writer_rx.try_recv() {
+            self.enqueue_write(write_data)?;
+            wrote = true;
+        }
+        if wrote {
+            self.drain_queued_messages().await?;
+        }
+        self.health
+            .set_queues(self.available_queued_messages(), self.channels.map.len());
 
         let select = self
             .decoder
@@ -291,11 +1018,16 @@ impl AsyncRunner {
             .either(self.activity_rx.recv())
             .either(self.writer_rx.recv())
             .either(self.notify.wait())
-            .either(super::timeout::next_delay())
+            .either(self.config_rx.recv())
+            .either(
+                self.clock
+                    .idle()
+                    .either(self.clock.wait(self.joins.next_wakeup(self.clock.now()))),
+            )
             .await;
 
         match select {
-            Left(Left(Left(Left(msg)))) => {
+            Left(Left(Left(Left(Left(msg))))) => {
                 let msg = match msg {
                     Err(DecodeError::Eof) => {
                         log::info!("got an EOF, exiting main loop");
@@ -308,51 +1040,67 @@ impl AsyncRunner {
                     Ok(msg) => msg,
                 };
 
-                self.timeout_state = TimeoutState::activity();
+                self.timeout_state = TimeoutState::Activity(self.clock.now());
+                self.metrics.observe(&msg);
+                self.health.message_received();
+                self.session.messages_in += 1;
+                Self::check_deprecations(
+                    &mut self.deprecations,
+                    &mut self.reported_tags,
+                    &msg,
+                );
 
-                let all = Commands::from_irc(msg) //
-                    .expect("msg identity conversion should be upheld")
-                    .into_owned();
+                // ignored messages are dropped before they are parsed, unless
+                // the runner needs them to keep track of its own state
+                let ignored = self.config.load().is_ignored_message(&msg);
+                if ignored && !Self::is_tracked(&msg) {
+                    self.session.dropped_messages += 1;
+                    return Ok(StepResult::Nothing);
+                }
 
-                self.check_messages(&all).await?;
+                let all = Self::parse_commands(&self.metrics, msg).into_owned();
 
-                return Ok(StepResult::Status(Status::Message(all)));
-            }
+                if let Some(echo) = self.duplicate_echo(&all) {
+                    self.drop_duplicate_echo(echo);
+                    return Ok(StepResult::Nothing);
+                }
 
-            Left(Left(Left(Right(Some(_activity))))) => {
-                self.timeout_state = TimeoutState::activity();
-            }
+                self.check_messages(&all)?;
 
-            Left(Left(Right(Some(write_data)))) => {
-                // TODO provide a 'bytes' flavored parser
-                let msg = std::str::from_utf8(&*write_data).map_err(Error::InvalidUtf8)?;
-                let res = crate::irc::parse_one(msg) //
-                    .expect("encoder should produce valid IRC messages");
-                let msg = res.1;
+                if ignored {
+                    self.session.dropped_messages += 1;
+                } else {
+                    self.pending.replace((all, None));
+                }
 
-                if let crate::irc::IrcMessage::PRIVMSG = msg.get_command() {
-                    if let Some(ch) = msg.nth_arg(0) {
-                        if !self.channels.is_on(ch) {
-                            self.channels.add(ch)
-                        }
+                // replies (e.g. a PONG) are sent before the message is yielded
+                self.encoder.write_buffered().await?;
 
-                        let ch = self.channels.get_mut(ch).unwrap();
-                        if ch.rated_limited_at.map(|s| s.elapsed()) > Some(RATE_LIMIT_WINDOW) {
-                            ch.reset_rate_limit();
-                        }
+                return Ok(match self.deliver_pending().await {
+                    Some(msg) => StepResult::Status(Status::Message(msg)),
+                    None => StepResult::Nothing,
+                });
+            }
 
-                        ch.rate_limited.enqueue(write_data)
-                    }
-                }
+            Left(Left(Left(Left(Right(Some(_activity)))))) => {
+                self.timeout_state = TimeoutState::Activity(self.clock.now());
             }
 
-            Left(Right(_notified)) => return Ok(StepResult::Status(Status::Quit)),
+            Left(Left(Left(Right(Some(write_data))))) => self.enqueue_write(write_data)?,
+
+            Left(Left(Right(_notified))) => return Ok(StepResult::Status(Status::Quit)),
 
-            Right(_timeout) => {
+            Left(Right(Some(change))) => self.apply_config_change(change).await?,
+
+            // a join has to be retried or timed out, that's done on the next step
+            Right(Right(_join)) => {}
+
+            Right(Left(_timeout)) => {
                 log::info!("idle connection detected, sending a ping");
-                let ts = timestamp().to_string();
+                self.clock.idle_fired();
+                let ts = self.clock.unix_timestamp().to_string();
                 self.encoder.encode(commands::ping(&ts)).await?;
-                self.timeout_state = TimeoutState::waiting_for_pong();
+                self.timeout_state = TimeoutState::WaitingForPong(self.clock.now());
             }
 
             _ => {
@@ -360,19 +1108,20 @@ impl AsyncRunner {
             }
         }
 
+        let now = self.clock.now();
         match self.timeout_state {
             TimeoutState::WaitingForPong(dt) => {
-                if dt.elapsed() > TIMEOUT {
+                if now.saturating_duration_since(dt) > TIMEOUT {
                     log::warn!("PING timeout detected, exiting");
                     return Err(Error::TimedOut);
                 }
             }
             TimeoutState::Activity(dt) => {
-                if dt.elapsed() > WINDOW {
+                if now.saturating_duration_since(dt) > WINDOW {
                     log::warn!("idle connectiond detected, sending a PING");
-                    let ts = timestamp().to_string();
-                    self.encoder.encode(crate::commands::ping(&ts)).await?;
-                    self.timeout_state = TimeoutState::waiting_for_pong();
+                    let ts = self.clock.unix_timestamp().to_string();
+                    self.encoder.encode(commands::ping(&ts)).await?;
+                    self.timeout_state = TimeoutState::WaitingForPong(self.clock.now());
                 }
             }
             TimeoutState::Start => {}
@@ -384,10 +1133,12 @@ impl AsyncRunner {
         Ok(StepResult::Nothing)
     }
 
-    async fn check_messages(&mut self, all: &Commands<'static>) -> Result<(), Error> {
+    // replies are only buffered, they are written with `write_buffered`
+    fn check_messages(&mut self, all: &Commands<'static>) -> Result<(), Error> {
         use {Commands::*, TimeoutState::*};
 
         log::trace!("< {}", all.raw().escape_debug());
+        self.check_notifications(all);
 
         match &all {
             Ping(msg) => {
@@ -396,22 +1147,28 @@ impl AsyncRunner {
                     "got a ping from the server. responding with token '{}'",
                     token
                 );
-                self.encoder.encode(commands::pong(token)).await?;
-                self.timeout_state = TimeoutState::activity();
+                self.encoder.buffer(commands::pong(token))?;
+                self.timeout_state = Activity(self.clock.now());
             }
 
             Pong(..) if matches!(self.timeout_state, WaitingForPong {..}) => {
-                self.timeout_state = TimeoutState::activity()
+                self.timeout_state = Activity(self.clock.now())
             }
 
             Join(msg) if msg.name() == self.identity.username() => {
                 log::debug!("starting tracking channel for '{}'", msg.channel());
                 self.channels.add(msg.channel());
+                self.sync_config_channel(msg.channel(), true);
+                self.session.channels_joined += 1;
+                if let Some(event) = self.joins.joined(msg.channel()) {
+                    self.send_join_event(event);
+                }
             }
 
             Part(msg) if msg.name() == self.identity.username() => {
                 log::debug!("stopping tracking of channel '{}'", msg.channel());
                 self.channels.remove(msg.channel());
+                self.sync_config_channel(msg.channel(), false);
             }
 
             RoomState(msg) => {
@@ -422,7 +1179,19 @@ impl AsyncRunner {
                 }
             }
 
+            UserState(msg) => self.check_roles(msg),
+
             Notice(msg) => {
+                self.check_rejection(msg);
+
+                let now = self.clock.now();
+                let failure = msg.msg_id().as_ref().and_then(JoinFailure::from_msg_id);
+                if let Some(failure) = failure {
+                    if let Some(event) = self.joins.failed(msg.channel(), failure, now) {
+                        self.send_join_event(event);
+                    }
+                }
+
                 let ch = self.channels.get_mut(msg.channel());
                 match (msg.msg_id(), ch) {
                     // we should enable slow mode
@@ -430,9 +1199,12 @@ impl AsyncRunner {
                     // we should disable slow mode
                     (Some(MessageId::SlowOff), Some(ch)) => ch.disable_slow_mode(),
                     // we've been rate limited on the channel
-                    (Some(MessageId::MsgRatelimit), Some(ch)) => ch.set_rate_limited(),
+                    (Some(MessageId::MsgRatelimit), Some(ch)) => ch.set_rate_limited_at(now),
                     // we cannot join/send to the channel because we're banned
-                    (Some(MessageId::MsgBanned), ..) => self.channels.remove(msg.channel()),
+                    (Some(MessageId::MsgBanned), ..) => {
+                        self.channels.remove(msg.channel());
+                        self.sync_config_channel(msg.channel(), false);
+                    }
                     _ => {}
                 }
             }
@@ -447,6 +1219,388 @@ impl AsyncRunner {
 }
 
 impl AsyncRunner {
+    // this doesn't take `self` because the message may still borrow the decoder
+    fn check_deprecations(
+        subscribers: &mut Vec<Sender<Deprecation>>,
+        reported_tags: &mut HashSet<Deprecation>,
+        msg: &IrcMessage<'_>,
+    ) {
+        if subscribers.is_empty() {
+            return;
+        }
+
+        for deprecation in Deprecation::check(msg) {
+            // the deprecated tags are on almost every message
+            if matches!(deprecation, Deprecation::Tag { .. })
+                && !reported_tags.insert(deprecation.clone())
+            {
+                continue;
+            }
+
+            log::debug!("{}", deprecation);
+            subscribers.retain(|tx| {
+                !matches!(
+                    tx.try_send(deprecation.clone()),
+                    Err(TrySendError::Closed(..))
+                )
+            });
+        }
+    }
+
+    fn enqueue_write(&mut self, write_data: Box<[u8]>) -> Result<(), Error> {
+        // TODO provide a 'bytes' flavored parser
+        let msg = std::str::from_utf8(&write_data).map_err(Error::InvalidUtf8)?;
+        let res = crate::irc::parse_one(msg) //
+            .expect("encoder should produce valid IRC messages");
+        let msg = res.1;
+        Self::check_deprecations(
+            &mut self.deprecations,
+            &mut self.reported_tags,
+            &msg,
+        );
+
+        if let IrcMessage::PRIVMSG = msg.get_command() {
+            if let Some(ch) = msg.nth_arg(0) {
+                if !self.channels.is_on(ch) {
+                    self.channels.add(ch)
+                }
+
+                let now = self.clock.now();
+                let ch = self.channels.get_mut(ch).unwrap();
+                if ch.rated_limited_at.map(|s| now.saturating_duration_since(s))
+                    > Some(RATE_LIMIT_WINDOW)
+                {
+                    ch.reset_rate_limit();
+                }
+
+                ch.rate_limited.enqueue(write_data)
+            }
+        }
+        Ok(())
+    }
+
+    async fn retry_joins(&mut self) -> Result<(), Error> {
+        let now = self.clock.now();
+        for event in self.joins.timed_out(now) {
+            self.send_join_event(event);
+        }
+
+        for channel in self.joins.due(now) {
+            log::debug!("joining '{}' again", channel);
+            self.encoder.encode(commands::join(&channel)).await?;
+            self.joins.sent(&channel, now);
+        }
+        Ok(())
+    }
+
+    // the messages a wait took are older than the ones it left in `delivered`
+    fn requeue(&mut self, queue: VecDeque<Commands<'static>>) {
+        for msg in queue.into_iter().rev() {
+            self.delivered.push_front(msg);
+        }
+    }
+
+    fn send_join_event(&mut self, event: JoinEvent) {
+        match &event {
+            JoinEvent::Retrying { channel, failure, delay, .. } => {
+                log::info!("cannot join '{}' ({}), retrying in {:?}", channel, failure, delay)
+            }
+            JoinEvent::GaveUp { channel, failure, .. } => {
+                log::warn!("cannot join '{}' ({}), giving up", channel, failure)
+            }
+            JoinEvent::Joined { .. } => {}
+        }
+
+        self.join_events.retain(|tx| {
+            !matches!(
+                tx.try_send(event.clone()),
+                Err(TrySendError::Closed(..))
+            )
+        });
+    }
+
+    // a JOIN of a channel you're on that you didn't ask for, or a PART of one you're not on
+    fn duplicate_echo(&self, msg: &Commands<'_>) -> Option<DuplicateEcho> {
+        match msg {
+            Commands::Join(msg)
+                if msg.name() == self.identity.username()
+                    && self.channels.is_on(msg.channel())
+                    && !self.joins.is_pending(msg.channel()) =>
+            {
+                Some(DuplicateEcho::Join {
+                    channel: msg.channel().to_string(),
+                })
+            }
+            Commands::Part(msg)
+                if msg.name() == self.identity.username() && !self.channels.is_on(msg.channel()) =>
+            {
+                Some(DuplicateEcho::Part {
+                    channel: msg.channel().to_string(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn drop_duplicate_echo(&mut self, echo: DuplicateEcho) {
+        log::debug!("dropping a duplicate {:?}", echo);
+        self.session.dropped_messages += 1;
+        self.duplicate_echoes.retain(|tx| {
+            !matches!(
+                tx.try_send(echo.clone()),
+                Err(TrySendError::Closed(..))
+            )
+        });
+    }
+
+    fn check_roles(&mut self, msg: &UserState<'_>) {
+        let roles = Roles::from_badges(&msg.badges());
+        let default = self.channels.rate_class;
+        let ch = match self.channels.get_mut(msg.channel()) {
+            Some(ch) => ch,
+            None => return,
+        };
+
+        let previous = ch.roles.replace(roles);
+        let before = previous.unwrap_or_default().rate_class(default);
+        let rate_class = roles.rate_class(default);
+        if before != rate_class {
+            log::debug!("using {:?} for '{}'", rate_class, msg.channel());
+            ch.set_rate_class(rate_class);
+        }
+
+        // the first USERSTATE of a channel is where we start from
+        let previous = match previous {
+            Some(previous) if previous != roles => previous,
+            _ => return,
+        };
+
+        log::info!("our roles in '{}' changed to {:?}", msg.channel(), roles);
+        let change = RoleChange {
+            channel: msg.channel().to_string(),
+            previous,
+            current: roles,
+            rate_class,
+        };
+        self.role_changes.retain(|tx| {
+            !matches!(
+                tx.try_send(change.clone()),
+                Err(TrySendError::Closed(..))
+            )
+        });
+    }
+
+    fn notifier(identity: &Identity, context: ContextBuffer) -> Notifications {
+        let notifier = Notifications::new(identity.username(), context);
+        match identity {
+            Identity::Full {
+                display_name: Some(display_name),
+                ..
+            } => notifier.with_name(display_name),
+            _ => notifier,
+        }
+    }
+
+    fn check_notifications(&mut self, msg: &Commands<'_>) {
+        let notification = match self.notifier.as_mut().and_then(|n| n.observe(msg)) {
+            Some(notification) => notification,
+            None => return,
+        };
+
+        log::debug!("notifying about a message from '{}'", notification.name());
+        self.notifications.retain(|tx| {
+            !matches!(
+                tx.try_send(notification.clone()),
+                Err(TrySendError::Closed(..))
+            )
+        });
+
+        // stop looking when nobody is listening
+        if self.notifications.is_empty() {
+            self.notifier.take();
+        }
+    }
+
+    fn check_rejection(&mut self, msg: &Notice<'_>) {
+        if self.send_rejections.is_empty() {
+            return;
+        }
+
+        let mut rejected = match SendRejected::from_notice(msg) {
+            Some(rejected) => rejected,
+            None => return,
+        };
+
+        // only the newest message is kept, so it can only be rejected once
+        let last_sent = self
+            .channels
+            .get_mut(msg.channel())
+            .and_then(|ch| ch.rate_limited.last_sent.take());
+        if let Some((data, sent)) = last_sent {
+            if self.clock.now().saturating_duration_since(sent) < REJECTION_WINDOW {
+                let line = String::from_utf8_lossy(&data);
+                rejected.message = Some(line.trim_end_matches("\r\n").to_string());
+            }
+        }
+
+        log::debug!("message to '{}' was rejected: {:?}", rejected.channel, rejected.reason);
+        self.send_rejections.retain(|tx| {
+            !matches!(
+                tx.try_send(rejected.clone()),
+                Err(TrySendError::Closed(..))
+            )
+        });
+    }
+
+    // a message that Twitch changed the format of is counted in the metrics
+    // and yielded as a raw message, rather than taking the runner down
+    fn parse_commands<'a>(metrics: &Metrics, msg: IrcMessage<'a>) -> Commands<'a> {
+        Commands::from_irc(msg.clone()).unwrap_or_else(|err| {
+            log::warn!("cannot parse {}: {}", msg.get_command(), err);
+            metrics.record_parse_error(&msg, &err);
+            Commands::Raw(msg)
+        })
+    }
+
+    // transform the pending message, and publish it
+    async fn deliver_pending(&mut self) -> Option<Commands<'static>> {
+        let (msg, transform) = self.pending.as_mut()?;
+        if transform.is_none() {
+            *transform = self.transform.as_ref().and_then(|t| t.transform(msg));
+        }
+        let transformed = match transform {
+            Some(fut) => Some(fut.await),
+            None => None,
+        };
+
+        let (msg, _) = self.pending.take()?;
+        let (msg, original) = match transformed {
+            Some(transformed) => (transformed, Some(msg)),
+            None => (msg, None),
+        };
+        self.publish(&msg, original);
+        Some(msg)
+    }
+
+    fn end_session(&mut self, error: Option<String>) {
+        if std::mem::replace(&mut self.session.ended, true) {
+            return;
+        }
+
+        self.health.disconnected();
+
+        let summary = self.session.summary(error);
+        log::info!("{}", summary);
+        for tx in self.session_summaries.drain(..) {
+            let _ = tx.try_send(summary.clone());
+        }
+    }
+
+    fn publish(&mut self, msg: &Commands<'static>, original: Option<Commands<'static>>) {
+        let sequence = self.sequence;
+        self.sequence += 1;
+
+        if self.envelopes.is_empty() && self.shared_envelopes.is_empty() {
+            return;
+        }
+
+        let envelope = Envelope::new(msg.clone(), original, self.connection_id, sequence);
+        self.envelopes.retain(|tx| {
+            !matches!(
+                tx.try_send(envelope.clone()),
+                Err(TrySendError::Closed(..))
+            )
+        });
+
+        if self.shared_envelopes.is_empty() {
+            return;
+        }
+        let envelope = Arc::new(envelope);
+        self.shared_envelopes.retain(|tx| {
+            !matches!(
+                tx.try_send(Arc::clone(&envelope)),
+                Err(TrySendError::Closed(..))
+            )
+        });
+    }
+
+    // these are the messages `check_messages` acts on
+    fn is_tracked(msg: &IrcMessage<'_>) -> bool {
+        matches!(
+            msg.get_command(),
+            IrcMessage::PING
+                | IrcMessage::PONG
+                | IrcMessage::JOIN
+                | IrcMessage::PART
+                | IrcMessage::ROOM_STATE
+                | IrcMessage::NOTICE
+                | IrcMessage::RECONNECT
+        )
+    }
+
+    // keeps the channels of the config in line with the ones we're on. The
+    // change this makes is a no-op once it gets back to `apply_config_change`
+    fn sync_config_channel(&self, channel: &str, joined: bool) {
+        let channel = channel.to_string();
+        self.config.update(|config| {
+            if joined {
+                config.channels.insert(channel);
+            } else {
+                config.channels.remove(&channel);
+            }
+        });
+    }
+
+    async fn apply_config_change(&mut self, change: ConfigChange) -> Result<(), Error> {
+        log::debug!("applying config change: {:?}", change);
+
+        // a change can be older than the config, e.g. the JOIN of a channel
+        // that was parted since. only the channels it has now are followed
+        let channels = &self.config.load().channels;
+        let change = match change {
+            ConfigChange::Joined(channel) if !channels.contains(&channel) => return Ok(()),
+            ConfigChange::Parted(channel) if channels.contains(&channel) => return Ok(()),
+            change => change,
+        };
+
+        match change {
+            ConfigChange::RateClass(rate_class) => {
+                self.global_rate_limit = RateLimit::from_class(rate_class);
+                self.channels.set_rate_class(rate_class);
+            }
+
+            // a join that's being retried is already on its way
+            ConfigChange::Joined(channel)
+                if !self.is_on_channel(&channel) && !self.joins.is_pending(&channel) =>
+            {
+                self.encoder.encode(commands::join(&channel)).await?;
+                self.joins.sent(&channel, self.clock.now());
+            }
+
+            ConfigChange::Parted(channel) if self.is_on_channel(&channel) => {
+                self.encoder.encode(commands::part(&channel)).await?
+            }
+
+            ConfigChange::CapabilityAdded(cap) => {
+                self.encoder
+                    .encode(commands::raw(&format!("{}\r\n", cap.encode_as_str())))
+                    .await?
+            }
+
+            ConfigChange::CapabilityRemoved(cap) => {
+                log::warn!(
+                    "the {:?} capability will stay enabled until the next reconnect",
+                    cap
+                );
+            }
+
+            // ignore lists are checked against the current config for each message
+            _ => {}
+        }
+
+        Ok(())
+    }
+
     async fn wait_for<F>(
         &mut self,
         missed: &mut VecDeque<Commands<'static>>,
@@ -480,20 +1634,25 @@ impl AsyncRunner {
     async fn drain_queued_messages(&mut self) -> std::io::Result<()> {
         let enc = &mut self.encoder;
         let limit = &mut self.global_rate_limit.get_available_tokens();
+        let now = self.clock.now();
 
         let start = *limit;
 
         // for each channel, try to take up to 'limit' tokens
         for channel in self.channels.map.values_mut() {
-            if channel.rated_limited_at.map(|s| s.elapsed()) > Some(RATE_LIMIT_WINDOW) {
+            if channel.rated_limited_at.map(|s| now.saturating_duration_since(s))
+                > Some(RATE_LIMIT_WINDOW)
+            {
                 channel.reset_rate_limit();
             }
 
             // drain until we're out of messages, or tokens
+            let before = *limit;
             channel
                 .rate_limited
-                .drain_until_blocked(&channel.name, limit, enc)
+                .drain_until_blocked(&channel.name, limit, enc, now)
                 .await?;
+            self.session.messages_out += before - *limit;
 
             let left = std::cmp::max(start, *limit);
             let right = std::cmp::min(start, *limit);
@@ -506,7 +1665,7 @@ impl AsyncRunner {
             }
 
             // and throttle the global one
-            match self.global_rate_limit.consume(diff) {
+            match self.global_rate_limit.consume_at(diff, now) {
                 // use the new remaining amount of tokens
                 Ok(rem) => *limit = rem,
 
@@ -521,6 +1680,50 @@ impl AsyncRunner {
         Ok(())
     }
 
+    async fn open<C>(connector: C, user_config: &UserConfig) -> Result<Connection, Error>
+    where
+        C: Connector,
+        for<'a> &'a C::Output: AsyncRead + AsyncWrite + Send + Sync + Unpin,
+    {
+        log::debug!("connecting");
+        let mut stream = { connector }.connect().await?;
+        log::debug!("connection established");
+
+        log::debug!("registering");
+        let mut buf = vec![];
+        commands::register(user_config).encode(&mut buf)?;
+        stream.write_all(&buf).await?;
+        log::debug!("registered");
+
+        let read = async_dup::Arc::new(stream);
+        let write = read.clone();
+
+        let read: BoxedRead = Box::new(read);
+        let write: BoxedWrite = Box::new(write);
+
+        let mut decoder = AsyncDecoder::new(read);
+        let mut encoder = AsyncEncoder::new(write);
+
+        log::debug!("waiting for the connection to be ready");
+        let mut missed_messages = VecDeque::new();
+        let identity = Self::wait_for_ready(
+            &mut decoder,
+            &mut encoder,
+            user_config,
+            &mut missed_messages,
+        )
+        .await?;
+        log::debug!("connection is ready: {:?}", identity);
+
+        Ok(Connection {
+            decoder,
+            encoder,
+            identity,
+            missed_messages,
+            id: next_connection_id(),
+        })
+    }
+
     async fn wait_for_ready<R, W>(
         decoder: &mut AsyncDecoder<R>,
         encoder: &mut AsyncEncoder<W>,
@@ -533,137 +1736,47 @@ impl AsyncRunner {
     {
         use crate::IntoOwned as _;
 
-        let is_anonymous = user_config.is_anonymous();
-
-        let mut looking_for: HashSet<_> = user_config.capabilities.iter().collect();
-        let mut caps = Capabilities::default();
-        let mut our_name = None;
-
-        use crate::twitch::Capability as TwitchCap;
-        // Twitch says we'll be getting a GlobalUserState if we just send the
-        // Tags capability
-        //
-        // This is false. Twitch will only send GlobalUserState if we've sent
-        // the Commands capability and atleast 1 other capability.
-        //
-        // That other capability doesn't have to be Tags, interestingly enough.
-        // So a combination of both 'Commands' and 'Membership' will produce an
-        // empty GlobalUserState
-        //
-        // We'll check for both Tags and Commands
-        //
-        let will_be_getting_global_user_state_hopefully =
-            user_config.capabilities.contains(&TwitchCap::Tags) &&
-            user_config.capabilities.contains(&TwitchCap::Commands);
-
-        let identity = loop {
-            let msg: IrcMessage<'_> = decoder.read_message().await?;
+        let mut handshake = Handshake::new(user_config);
+
+        loop {
+            let msg: IrcMessage<'_> = match handshake.time_left() {
+                (_, None) => decoder.read_message().await?,
+                (phase, Some(left)) => {
+                    use crate::util::{Either::*, FutExt as _};
+                    match decoder
+                        .read_message()
+                        .either(futures_timer::Delay::new(left))
+                        .await
+                    {
+                        Left(msg) => msg?,
+                        Right(..) => return Err(Error::HandshakeTimedOut { phase }),
+                    }
+                }
+            };
 
             // this should always be infallible. its not marked infallible
             // because of the 'non-exhaustive' attribute
-            use Commands::*;
             let commands = Commands::from_irc(msg)?;
 
             // this is the simpliest way. and this'll only clone like 9 messages
             missed_messages.push_back(commands.clone().into_owned());
 
-            match commands {
-                Ready(msg) => {
-                    our_name.replace(msg.username().to_string());
-
-                    // if we aren't going to be receiving tags, then we
-                    // won't be looking for any more messages
-
-                    // if we're anonymous, we won't get GLOBALUSERSTATE even
-                    // if we do send Tags
-                    if is_anonymous {
-                        break Identity::Anonymous { caps };
-                    }
-
-                    // if we're not looking for any more caps and we won't be
-                    // getting a GlobalUserState just give them the basic
-                    // Identity
-                    if looking_for.is_empty() && !will_be_getting_global_user_state_hopefully {
-                        break Identity::Basic {
-                            name: our_name.take().unwrap(),
-                            caps,
-                        };
-                    }
-                }
-
-                Cap(msg) => match msg.capability() {
-                    Capability::Acknowledged(name) => {
-                        use crate::twitch::Capability as Cap;
-
-                        let cap = match Cap::maybe_from_str(name) {
-                            Some(cap) => cap,
-                            // Twitch sent us an unknown capability
-                            None => {
-                                caps.unknown.insert(name.to_string());
-                                continue;
-                            }
-                        };
-
-                        *match cap {
-                            Cap::Tags => &mut caps.tags,
-                            Cap::Membership => &mut caps.membership,
-                            Cap::Commands => &mut caps.commands,
-                        } = true;
-
-                        looking_for.remove(&cap);
-                    }
-
-                    Capability::NotAcknowledged(name) => {
-                        return Err(Error::InvalidCap {
-                            cap: name.to_string(),
-                        })
-                    }
-                },
-
-                // NOTE: This will only be sent when there's both Commands and atleast one other CAP requested
-                GlobalUserState(msg) => {
-                    // TODO: this is so shitty.
-                    let id = match msg.user_id {
-                        Some(id) => id.parse().unwrap(),
-                        // XXX: we can get this message without any tags
-                        None => {
-                            break Identity::Basic {
-                                name: our_name.take().unwrap(),
-                                caps,
-                            };
-                        }
-                    };
-
-                    break Identity::Full {
-                        // these unwraps should be safe because we'll have all of the TAGs here
-                        name: our_name.unwrap(),
-                        user_id: id,
-                        display_name: msg.display_name.map(|s| s.to_string()),
-                        color: msg.color,
-                        caps,
-                    };
-
-                }
-
-                // Reply to any PINGs while waiting. Although Twitch doesn't
-                // currently send a PING for spoof detection on initial
-                // handshake, one day they may. Most IRC servers do this
-                // already
-                Ping(msg) => encoder.encode(commands::pong(msg.token())).await?,
-
-                _ => {
-                    // we have our name, but we won't be getting GlobalUserState and we've got all of our Caps
-                    if our_name.is_some() && !will_be_getting_global_user_state_hopefully && looking_for.is_empty() {
-                        break Identity::Basic {
-                            name: our_name.take().unwrap(),
-                            caps,
-                        };
-                    }
-                }
-            };
-        };
+            // Reply to any PINGs while waiting. Although Twitch doesn't
+            // currently send a PING for spoof detection on initial
+            // handshake, one day they may. Most IRC servers do this
+            // already
+            if let Commands::Ping(msg) = &commands {
+                encoder.encode(commands::pong(msg.token())).await?;
+            }
 
-        Ok(identity)
+            let identity = handshake.observe(&commands)?;
+            for cap in handshake.take_requests() {
+                encoder.encode(commands::raw(cap.encode_as_str())).await?;
+            }
+            if let Some(identity) = identity {
+                break Ok(identity);
+            }
+        }
     }
 }
 
@@ -1,3 +1,4 @@
+use crate::twitch::Capability;
 use std::collections::HashSet;
 
 /// Capabiltiies Twitch acknowledged.
@@ -11,4 +12,7 @@ pub struct Capabilities {
     pub tags: bool,
     /// A set of unknown capabilities Twitch sent to use
     pub unknown: HashSet<String>,
+    /// The capabilities Twitch refused when they were requested on their own,
+    /// after a [batched request](crate::twitch::HandshakeOptions::batch_capabilities) was refused
+    pub refused: HashSet<Capability>,
 }
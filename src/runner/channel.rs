@@ -1,5 +1,8 @@
 cfg_async! {
-use super::rate_limit::{PreviousRate, RateLimitedEncoder};
+use super::{
+    rate_limit::{PreviousRate, RateLimitedEncoder},
+    Roles,
+};
 use crate::rate_limit::{RateClass, RateLimit};
 use std::{
     collections::{HashMap, VecDeque},
@@ -21,6 +24,7 @@ pub struct Channel {
     pub(crate) rate_limited: RateLimitedEncoder,
     pub(crate) previous: Option<PreviousRate>,
     pub(crate) rated_limited_at: Option<std::time::Instant>,
+    pub(crate) roles: Option<Roles>,
 }
 
 impl std::fmt::Debug for Channel {
@@ -30,20 +34,27 @@ impl std::fmt::Debug for Channel {
 }
 
 impl Channel {
-    pub(crate) fn new(name: String) -> Self {
-        let rate_limit = RateLimit::from_class(RateClass::Regular);
+    pub(crate) fn new(name: String, rate_class: RateClass) -> Self {
+        let rate_limit = RateLimit::from_class(rate_class);
         let rate_limited = RateLimitedEncoder {
             rate_limit,
             queue: VecDeque::new(),
+            last_sent: None,
         };
         Self {
             name,
             rate_limited,
             previous: None,
             rated_limited_at: None,
+            roles: None,
         }
     }
 
+    /// Your roles in this channel, once Twitch sent a `USERSTATE` for it
+    pub fn roles(&self) -> Option<Roles> {
+        self.roles
+    }
+
     /// Set the [RateClass] for this channel
     pub fn set_rate_class(&mut self, rate_class: RateClass) {
         self.rate_limited.rate_limit = RateLimit::from_class(rate_class);
@@ -71,8 +82,12 @@ impl Channel {
 
     /// Mark that you've been rate limited on this channel
     pub fn set_rate_limited(&mut self) {
+        self.set_rate_limited_at(std::time::Instant::now())
+    }
+
+    pub(crate) fn set_rate_limited_at(&mut self, now: std::time::Instant) {
         self.rate_limited.rate_limit.set_cap(1);
-        self.rated_limited_at.replace(std::time::Instant::now());
+        self.rated_limited_at.replace(now);
     }
 
     /// Reset to the default rate class
@@ -86,9 +101,18 @@ impl Channel {
 #[derive(Debug, Default)]
 pub struct Channels {
     pub map: HashMap<String, Channel>,
+    pub rate_class: RateClass,
 }
 
 impl Channels {
+    pub fn set_rate_class(&mut self, rate_class: RateClass) {
+        self.rate_class = rate_class;
+        for channel in self.map.values_mut() {
+            let roles = channel.roles.unwrap_or_default();
+            channel.set_rate_class(roles.rate_class(rate_class))
+        }
+    }
+
     pub fn is_on(&self, name: &str) -> bool {
         self.map.contains_key(name)
     }
@@ -107,7 +131,7 @@ impl Channels {
             return;
         }
 
-        let channel = Channel::new(name.to_string());
+        let channel = Channel::new(name.to_string(), self.rate_class);
         self.map.insert(name.to_string(), channel);
     }
 
@@ -0,0 +1,491 @@
+cfg_async! {
+use crate::{
+    channel::{Receiver, Sender, TrySendError},
+    irc::IrcMessage,
+    rate_limit::RateClass,
+    twitch::Capability,
+};
+
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// Settings of a running connection that can be changed without reconnecting.
+///
+/// A snapshot of this is handed out by [ConfigHandle::load]. To change it, use
+/// one of the methods on the [ConfigHandle].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuntimeConfig {
+    /// The rate class applied to the connection and all of its channels
+    pub rate_class: RateClass,
+    /// Channels that should be joined.
+    ///
+    /// The runner keeps this in line with the channels it is on, so the joins
+    /// and parts made with e.g. [AsyncRunner::join](crate::AsyncRunner::join)
+    /// show up here (and as a [ConfigChange]) too.
+    pub channels: BTreeSet<String>,
+    /// Users whose messages should be dropped by the runner
+    pub ignored_users: BTreeSet<String>,
+    /// Channels whose messages should be dropped by the runner
+    pub ignored_channels: BTreeSet<String>,
+    /// Commands (e.g. `USERNOTICE`) that should be dropped by the runner
+    pub ignored_commands: BTreeSet<String>,
+    /// Capabilities that should be requested
+    pub capabilities: BTreeSet<Capability>,
+}
+
+impl RuntimeConfig {
+    /// Determines whether messages from `name` should be dropped
+    pub fn is_ignored(&self, name: &str) -> bool {
+        self.ignored_users.contains(&name.to_ascii_lowercase())
+    }
+
+    /// Determines whether this message should be dropped, before it is parsed
+    /// into a typed message.
+    ///
+    /// This only looks at the command, the first argument (the channel) and the
+    /// prefix (the user), so it is cheap enough to run on every message.
+    pub fn is_ignored_message(&self, msg: &IrcMessage<'_>) -> bool {
+        if !self.ignored_commands.is_empty() && self.ignored_commands.contains(msg.get_command()) {
+            return true;
+        }
+
+        if !self.ignored_channels.is_empty() {
+            if let Some(channel) = msg.nth_arg(0).filter(|arg| arg.starts_with('#')) {
+                if self.ignored_channels.contains(channel) {
+                    return true;
+                }
+            }
+        }
+
+        if !self.ignored_users.is_empty() {
+            if let Some(name) = msg.get_prefix() {
+                return self.ignored_users.contains(name);
+            }
+        }
+
+        false
+    }
+
+    fn diff(&self, new: &Self) -> Vec<ConfigChange> {
+        use ConfigChange::*;
+
+        let mut changes = vec![];
+        if self.rate_class != new.rate_class {
+            changes.push(RateClass(new.rate_class));
+        }
+
+        let added = |new: &BTreeSet<String>, old: &BTreeSet<String>| {
+            new.difference(old).cloned().collect::<Vec<_>>()
+        };
+
+        changes.extend(added(&new.channels, &self.channels).into_iter().map(Joined));
+        changes.extend(added(&self.channels, &new.channels).into_iter().map(Parted));
+
+        changes.extend(added(&new.ignored_users, &self.ignored_users).into_iter().map(Ignored));
+        changes.extend(added(&self.ignored_users, &new.ignored_users).into_iter().map(Unignored));
+
+        changes.extend(
+            added(&new.ignored_channels, &self.ignored_channels)
+                .into_iter()
+                .map(IgnoredChannel),
+        );
+        changes.extend(
+            added(&self.ignored_channels, &new.ignored_channels)
+                .into_iter()
+                .map(UnignoredChannel),
+        );
+
+        changes.extend(
+            added(&new.ignored_commands, &self.ignored_commands)
+                .into_iter()
+                .map(IgnoredCommand),
+        );
+        changes.extend(
+            added(&self.ignored_commands, &new.ignored_commands)
+                .into_iter()
+                .map(UnignoredCommand),
+        );
+
+        changes.extend(
+            new.capabilities
+                .difference(&self.capabilities)
+                .copied()
+                .map(CapabilityAdded),
+        );
+        changes.extend(
+            self.capabilities
+                .difference(&new.capabilities)
+                .copied()
+                .map(CapabilityRemoved),
+        );
+
+        changes
+    }
+}
+
+/// A change made to the [RuntimeConfig]
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigChange {
+    /// The rate class was changed
+    RateClass(RateClass),
+    /// A channel was added
+    Joined(String),
+    /// A channel was removed
+    Parted(String),
+    /// A user was added to the ignore list
+    Ignored(String),
+    /// A user was removed from the ignore list
+    Unignored(String),
+    /// A channel was added to the ignore list
+    IgnoredChannel(String),
+    /// A channel was removed from the ignore list
+    UnignoredChannel(String),
+    /// A command was added to the ignore list
+    IgnoredCommand(String),
+    /// A command was removed from the ignore list
+    UnignoredCommand(String),
+    /// A capability was added
+    CapabilityAdded(Capability),
+    /// A capability was removed
+    CapabilityRemoved(Capability),
+}
+
+struct Shared {
+    current: Arc<RuntimeConfig>,
+    subscribers: Vec<Sender<ConfigChange>>,
+}
+
+impl Shared {
+    fn replace(&mut self, config: RuntimeConfig) -> Arc<RuntimeConfig> {
+        let changes = self.current.diff(&config);
+        let old = std::mem::replace(&mut self.current, Arc::new(config));
+
+        for change in changes {
+            self.subscribers.retain(|tx| {
+                !matches!(tx.try_send(change.clone()), Err(TrySendError::Closed(..)))
+            });
+        }
+
+        old
+    }
+}
+
+/// A clonable handle for changing the [RuntimeConfig] of a running connection.
+///
+/// Every change swaps out the whole config at once, so a snapshot from
+/// [ConfigHandle::load] is never partially updated. Each swap produces a
+/// [ConfigChange] for every difference, which is delivered to all subscribers.
+///
+/// ```rust
+/// # use twitchchat::runner::{ConfigChange, ConfigHandle, RuntimeConfig};
+/// use twitchchat::rate_limit::RateClass;
+///
+/// let handle = ConfigHandle::new(RuntimeConfig::default());
+/// let events = handle.subscribe();
+///
+/// handle.set_rate_class(RateClass::Moderator);
+/// handle.join("#museun");
+///
+/// assert!(handle.load().channels.contains("#museun"));
+/// assert_eq!(events.try_recv(), Some(ConfigChange::RateClass(RateClass::Moderator)));
+/// assert_eq!(events.try_recv(), Some(ConfigChange::Joined("#museun".into())));
+/// ```
+#[derive(Clone)]
+pub struct ConfigHandle {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl std::fmt::Debug for ConfigHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigHandle")
+            .field("current", &self.load())
+            .finish()
+    }
+}
+
+impl ConfigHandle {
+    /// Create a new handle starting with this `config`
+    pub fn new(config: RuntimeConfig) -> Self {
+        let shared = Shared {
+            current: Arc::new(config),
+            subscribers: vec![],
+        };
+        Self {
+            shared: Arc::new(Mutex::new(shared)),
+        }
+    }
+
+    /// Get a snapshot of the current config
+    pub fn load(&self) -> Arc<RuntimeConfig> {
+        Arc::clone(&self.lock().current)
+    }
+
+    /// Subscribe to the changes made after this call
+    pub fn subscribe(&self) -> Receiver<ConfigChange> {
+        let (tx, rx) = crate::channel::unbounded();
+        self.lock().subscribers.push(tx);
+        rx
+    }
+
+    /// Replace the config with `config`, returning the previous one
+    pub fn swap(&self, config: RuntimeConfig) -> Arc<RuntimeConfig> {
+        self.lock().replace(config)
+    }
+
+    /// Modify a copy of the current config with `func` and swap it in
+    ///
+    /// This returns the previous config
+    pub fn update<F>(&self, func: F) -> Arc<RuntimeConfig>
+    where
+        F: FnOnce(&mut RuntimeConfig),
+    {
+        // hold the lock for the entire read-modify-write so concurrent updates aren't lost
+        let mut shared = self.lock();
+        let mut config = RuntimeConfig::clone(&shared.current);
+        func(&mut config);
+        shared.replace(config)
+    }
+
+    /// Set the [RateClass] used for the connection
+    pub fn set_rate_class(&self, rate_class: RateClass) {
+        self.update(|config| config.rate_class = rate_class);
+    }
+
+    /// Join `channel`
+    pub fn join(&self, channel: &str) {
+        let channel = crate::commands::Channel::new(channel).to_string();
+        self.update(|config| {
+            config.channels.insert(channel);
+        });
+    }
+
+    /// Part `channel`
+    pub fn part(&self, channel: &str) {
+        let channel = crate::commands::Channel::new(channel).to_string();
+        self.update(|config| {
+            config.channels.remove(&channel);
+        });
+    }
+
+    /// Drop any messages from `name`
+    pub fn ignore_user(&self, name: &str) {
+        let name = name.to_ascii_lowercase();
+        self.update(|config| {
+            config.ignored_users.insert(name);
+        });
+    }
+
+    /// Stop dropping messages from `name`
+    pub fn unignore_user(&self, name: &str) {
+        let name = name.to_ascii_lowercase();
+        self.update(|config| {
+            config.ignored_users.remove(&name);
+        });
+    }
+
+    /// Drop any messages on `channel`
+    pub fn ignore_channel(&self, channel: &str) {
+        let channel = crate::commands::Channel::new(channel).to_string();
+        self.update(|config| {
+            config.ignored_channels.insert(channel);
+        });
+    }
+
+    /// Stop dropping messages on `channel`
+    pub fn unignore_channel(&self, channel: &str) {
+        let channel = crate::commands::Channel::new(channel).to_string();
+        self.update(|config| {
+            config.ignored_channels.remove(&channel);
+        });
+    }
+
+    /// Drop any messages with this `command`, e.g. `USERNOTICE`
+    pub fn ignore_command(&self, command: &str) {
+        let command = command.to_ascii_uppercase();
+        self.update(|config| {
+            config.ignored_commands.insert(command);
+        });
+    }
+
+    /// Stop dropping messages with this `command`
+    pub fn unignore_command(&self, command: &str) {
+        let command = command.to_ascii_uppercase();
+        self.update(|config| {
+            config.ignored_commands.remove(&command);
+        });
+    }
+
+    /// Replace the requested capabilities with this preset
+    pub fn set_capabilities(&self, caps: &[Capability]) {
+        self.update(|config| config.capabilities = caps.iter().copied().collect());
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Shared> {
+        // the shared state is always left consistent, so a poisoned lock is still usable
+        self.shared
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swap_emits_changes() {
+        let handle = ConfigHandle::new(RuntimeConfig {
+            channels: vec!["#foo".to_string()].into_iter().collect(),
+            capabilities: vec![Capability::Tags].into_iter().collect(),
+            ..RuntimeConfig::default()
+        });
+        let events = handle.subscribe();
+
+        let old = handle.swap(RuntimeConfig {
+            rate_class: RateClass::Known,
+            channels: vec!["#bar".to_string()].into_iter().collect(),
+            ignored_users: vec!["nightbot".to_string()].into_iter().collect(),
+            capabilities: vec![Capability::Commands].into_iter().collect(),
+            ..RuntimeConfig::default()
+        });
+        assert!(old.channels.contains("#foo"));
+
+        let changes = std::iter::from_fn(|| events.try_recv()).collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                ConfigChange::RateClass(RateClass::Known),
+                ConfigChange::Joined("#bar".into()),
+                ConfigChange::Parted("#foo".into()),
+                ConfigChange::Ignored("nightbot".into()),
+                ConfigChange::CapabilityAdded(Capability::Commands),
+                ConfigChange::CapabilityRemoved(Capability::Tags),
+            ]
+        );
+
+        // nothing changed, so nothing is sent
+        handle.update(|_| {});
+        assert!(events.try_recv().is_none());
+    }
+
+    #[test]
+    fn snapshots_are_not_modified() {
+        let handle = ConfigHandle::new(RuntimeConfig::default());
+        let before = handle.load();
+
+        handle.join("museun");
+        handle.ignore_user("nightbot");
+
+        assert!(before.channels.is_empty());
+        let after = handle.load();
+        assert!(after.channels.contains("#museun"));
+        assert!(after.is_ignored("nightbot"));
+
+        handle.unignore_user("nightbot");
+        handle.part("#museun");
+        assert_eq!(*handle.load(), *before);
+    }
+
+    #[test]
+    fn closed_subscribers_are_dropped() {
+        let handle = ConfigHandle::new(RuntimeConfig::default());
+        let events = handle.subscribe();
+        drop(handle.subscribe());
+
+        handle.ignore_user("nightbot");
+        assert_eq!(handle.lock().subscribers.len(), 1);
+        assert_eq!(events.try_recv(), Some(ConfigChange::Ignored("nightbot".into())));
+    }
+
+    #[test]
+    fn ignored_messages() {
+        let handle = ConfigHandle::new(RuntimeConfig::default());
+        let events = handle.subscribe();
+
+        handle.ignore_user("NightBot");
+        handle.ignore_channel("spam");
+        handle.ignore_command("usernotice");
+
+        let changes = std::iter::from_fn(|| events.try_recv()).collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                ConfigChange::Ignored("nightbot".into()),
+                ConfigChange::IgnoredChannel("#spam".into()),
+                ConfigChange::IgnoredCommand("USERNOTICE".into()),
+            ]
+        );
+
+        let config = handle.load();
+        let is_ignored = |input: &str| {
+            let msg = crate::irc::parse(input).next().unwrap().unwrap();
+            config.is_ignored_message(&msg)
+        };
+
+        assert!(is_ignored(":nightbot!nightbot@nightbot.tmi.twitch.tv PRIVMSG #museun :hi\r\n"));
+        assert!(is_ignored(":museun!museun@museun.tmi.twitch.tv PRIVMSG #spam :hi\r\n"));
+        assert!(is_ignored("@msg-id=raid :tmi.twitch.tv USERNOTICE #museun\r\n"));
+
+        assert!(!is_ignored(":museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hi\r\n"));
+        assert!(!is_ignored(":tmi.twitch.tv ROOMSTATE #museun\r\n"));
+        assert!(!is_ignored("PING :1234567890\r\n"));
+
+        handle.unignore_user("nightbot");
+        handle.unignore_channel("#spam");
+        handle.unignore_command("USERNOTICE");
+        assert_eq!(*handle.load(), RuntimeConfig::default());
+    }
+
+    #[test]
+    #[cfg(feature = "testing")]
+    fn follows_the_runner() {
+        use crate::{runner::Status, test::TestConnector, AsyncRunner, UserConfig};
+
+        let config = UserConfig::builder().anonymous().build().unwrap();
+        futures_lite::future::block_on(async {
+            let connector = TestConnector::default();
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #museun\r\n\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv PART #museun\r\n",
+                )
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector.clone(), &config)
+                .await
+                .unwrap();
+            let handle = runner.config_handle();
+            let events = handle.subscribe();
+
+            runner.join("#museun").await.unwrap();
+            assert!(handle.load().channels.contains("#museun"));
+
+            runner.part("#museun").await.unwrap();
+            assert!(handle.load().channels.is_empty());
+
+            while let Status::Message(..) = runner.next_message().await.unwrap() {}
+
+            let changes = std::iter::from_fn(|| events.try_recv()).collect::<Vec<_>>();
+            assert_eq!(
+                changes,
+                vec![
+                    ConfigChange::Joined("#museun".into()),
+                    ConfigChange::Parted("#museun".into()),
+                ]
+            );
+
+            // the changes didn't make the runner join or part again
+            let lines = connector.conn.read_all_lines().await.unwrap();
+            let sent = |cmd: &str| lines.iter().filter(|line| line.starts_with(cmd)).count();
+            assert_eq!(sent("JOIN"), 1);
+            assert_eq!(sent("PART"), 1);
+        });
+    }
+}
+}
@@ -0,0 +1,242 @@
+use crate::messages::Commands;
+use std::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// A message yielded by a runner, with where and when it was received.
+///
+/// See [AsyncRunner::subscribe_all](crate::AsyncRunner::subscribe_all)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Envelope {
+    /// The message
+    pub message: Commands<'static>,
+    /// When the message was received.
+    ///
+    /// Messages that were buffered by the runner (e.g. while connecting) are stamped when they are yielded
+    pub received: SystemTime,
+    /// When Twitch says the message was sent, from its `tmi-sent-ts` tag.
+    ///
+    /// This is by the clock of Twitch, so it can be skewed from `received`
+    pub sent: Option<SystemTime>,
+    /// The id of the connection the runner was using, unique for this process.
+    ///
+    /// This changes when the runner reauthenticates, and differs between runners (e.g. shards)
+    pub connection: u64,
+    /// The position of this message in everything the runner has yielded, starting at 0
+    pub sequence: u64,
+    /// The message as it was received, if it was rewritten by the [Transform](super::Transform) of the runner
+    pub original: Option<Commands<'static>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) content_hash: LazyHash,
+}
+
+impl Envelope {
+    pub(crate) fn new(
+        message: Commands<'static>,
+        original: Option<Commands<'static>>,
+        connection: u64,
+        sequence: u64,
+    ) -> Self {
+        let sent = message
+            .tmi_sent_ts()
+            .and_then(|ts| UNIX_EPOCH.checked_add(Duration::from_millis(ts)));
+        Self {
+            message,
+            received: SystemTime::now(),
+            sent,
+            connection,
+            sequence,
+            original,
+            content_hash: LazyHash::default(),
+        }
+    }
+
+    /// How long the message took from Twitch to the runner.
+    ///
+    /// This is None when Twitch didn't send a timestamp, or when it is after
+    /// the local receive time (when the clocks are skewed)
+    pub fn latency(&self) -> Option<Duration> {
+        self.received.duration_since(self.sent?).ok()
+    }
+
+    /// When the message happened, for ordering messages.
+    ///
+    /// This is the `sent` time, unless it is missing or after the local
+    /// receive time, then it is the `received` time.
+    pub fn timestamp(&self) -> SystemTime {
+        self.sent
+            .filter(|sent| *sent <= self.received)
+            .unwrap_or(self.received)
+    }
+
+    /// A stable hash of the content of the message, see [content_hash](crate::messages::content_hash).
+    ///
+    /// This is computed the first time it is needed, and shared by clones made after that.
+    pub fn content_hash(&self) -> u64 {
+        self.content_hash
+            .get_or_init(|| self.message.content_hash())
+    }
+}
+
+// a cache of `Envelope::content_hash`, which doesn't take part in comparisons
+//
+// the hash is the same no matter who computes it, so a race only computes it twice
+#[derive(Debug, Default)]
+pub(crate) struct LazyHash {
+    hash: AtomicU64,
+    set: AtomicBool,
+}
+
+impl LazyHash {
+    fn get_or_init(&self, init: impl FnOnce() -> u64) -> u64 {
+        if self.set.load(Ordering::Acquire) {
+            return self.hash.load(Ordering::Relaxed);
+        }
+        let hash = init();
+        self.hash.store(hash, Ordering::Relaxed);
+        self.set.store(true, Ordering::Release);
+        hash
+    }
+}
+
+impl Clone for LazyHash {
+    fn clone(&self) -> Self {
+        let set = self.set.load(Ordering::Acquire);
+        Self {
+            hash: AtomicU64::new(self.hash.load(Ordering::Relaxed)),
+            set: AtomicBool::new(set),
+        }
+    }
+}
+
+impl PartialEq for LazyHash {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+pub(crate) fn next_connection_id() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::{
+        runner::Status, test::TestConnector, AsyncRunner, FromIrcMessage as _, IntoOwned as _,
+        UserConfig,
+    };
+    use std::sync::Arc;
+
+    #[test]
+    fn shared() {
+        let connector = TestConnector::default();
+        let config = UserConfig::builder().anonymous().build().unwrap();
+
+        futures_lite::future::block_on(async {
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n",
+                )
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector, &config).await.unwrap();
+            let all = runner.subscribe_all();
+            let shared = (0..3)
+                .map(|_| runner.subscribe_shared())
+                .collect::<Vec<_>>();
+            drop(runner.subscribe_shared());
+
+            while let Ok(Status::Message(..)) = runner.next_message().await {}
+            drop(runner);
+
+            let expected = all.recv().await.unwrap();
+            let first = shared[0].recv().await.unwrap();
+            assert_eq!(*first, expected);
+            for rx in &shared[1..] {
+                assert!(Arc::ptr_eq(&first, &rx.recv().await.unwrap()));
+            }
+
+            // the hash is computed once for all of them
+            assert_eq!(first.content_hash(), expected.content_hash());
+        });
+    }
+
+    #[test]
+    fn published_once() {
+        let connector = TestConnector::default();
+        let config = UserConfig::builder().anonymous().build().unwrap();
+
+        futures_lite::future::block_on(async {
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #museun\r\n\
+                     :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :world\r\n",
+                )
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector, &config).await.unwrap();
+            let all = runner.subscribe_all();
+
+            // the messages read while joining are yielded afterwards
+            runner.join("#museun").await.unwrap();
+            let mut yielded = vec![];
+            while let Ok(Status::Message(msg)) = runner.next_message().await {
+                yielded.push(msg.raw().to_string());
+            }
+            drop(runner);
+
+            let mut published = vec![];
+            while let Some(envelope) = all.try_recv() {
+                assert_eq!(envelope.sequence, published.len() as u64);
+                published.push(envelope.message.raw().to_string());
+            }
+            assert_eq!(published.len(), 5);
+
+            // the JOIN was taken by `join`
+            published.retain(|raw| !raw.contains(" JOIN "));
+            assert_eq!(published, yielded);
+        });
+    }
+
+    #[test]
+    fn timestamps() {
+        let envelope = |input: &str| {
+            let msg = crate::irc::parse(input).next().unwrap().unwrap();
+            let msg = Commands::from_irc(msg).unwrap().into_owned();
+            Envelope::new(msg, None, 0, 0)
+        };
+
+        let sent =
+            envelope("@tmi-sent-ts=1601079032426 :museun!museun@museun PRIVMSG #museun :hi\r\n");
+        assert_eq!(
+            sent.sent,
+            Some(UNIX_EPOCH + Duration::from_millis(1_601_079_032_426))
+        );
+        assert!(sent.latency().unwrap() > Duration::from_secs(60));
+        assert_eq!(sent.timestamp(), sent.sent.unwrap());
+
+        // missing
+        let missing = envelope(":museun!museun@museun PRIVMSG #museun :hi\r\n");
+        assert_eq!(missing.sent, None);
+        assert_eq!(missing.latency(), None);
+        assert_eq!(missing.timestamp(), missing.received);
+
+        // in the future, by a skewed clock
+        let skewed =
+            envelope("@tmi-sent-ts=99999999999999 :museun!museun@museun PRIVMSG #museun :hi\r\n");
+        assert!(skewed.sent.is_some());
+        assert_eq!(skewed.latency(), None);
+        assert_eq!(skewed.timestamp(), skewed.received);
+    }
+}
@@ -1,6 +1,12 @@
-use crate::{DecodeError, MessageError};
+use super::JoinFailure;
+use crate::{twitch::HandshakePhase, DecodeError, MessageError};
 
 /// An error returned by a Runner
+///
+/// ***NOTE*** This is a breaking change from `0.14.8`: `JoinFailed` and
+/// `HandshakeTimedOut` were added, and this is `#[non_exhaustive]` now, so
+/// matches need a wildcard arm.
+#[non_exhaustive]
 #[derive(Debug)]
 pub enum Error {
     /// An I/O error occured
@@ -29,8 +35,23 @@ pub enum Error {
         /// The channel name
         channel: String,
     },
+    /// Joining the channel failed, and wasn't retried (anymore).
+    ///
+    /// See [AsyncRunner::join_manager](crate::AsyncRunner::join_manager)
+    JoinFailed {
+        /// The channel name
+        channel: String,
+        /// Why it failed the last time
+        failure: JoinFailure,
+    },
     /// Your connection timed out.
     TimedOut,
+    /// A phase of the handshake took longer than its timeout in the
+    /// [HandshakeOptions](crate::twitch::HandshakeOptions)
+    HandshakeTimedOut {
+        /// The phase that timed out
+        phase: HandshakePhase,
+    },
     /// Twitch restarted the server, you should reconnect.
     ShouldReconnect,
     /// An unexpected EOF was found -- this means the connectionc losed abnormally.
@@ -49,7 +70,13 @@ impl std::fmt::Display for Error {
             Self::AlreadyOnChannel { channel } => write!(f, "already on channel '{}'", channel),
             Self::NotOnChannel { channel } => write!(f, "not on channel '{}'", channel),
             Self::BannedFromChannel { channel } => write!(f, "banned from channel '{}'", channel),
+            Self::JoinFailed { channel, failure } => {
+                write!(f, "cannot join channel '{}': {}", channel, failure)
+            }
             Self::TimedOut => write!(f, "your connection timed out"),
+            Self::HandshakeTimedOut { phase } => {
+                write!(f, "the handshake timed out waiting for {}", phase)
+            }
             Self::ShouldReconnect => write!(f, "you should reconnect. Twitch restarted the server"),
             Self::UnexpectedEof => write!(f, "reached an unexpected EOF"),
         }
@@ -0,0 +1,412 @@
+use super::{Capabilities, Error, Identity};
+use crate::{
+    messages::{Capability, Commands},
+    twitch::{Capability as TwitchCap, HandshakeOptions, HandshakePhase, ReadyOn, RegisterOrder},
+    UserConfig,
+};
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+/// Builds the [Identity] from the messages Twitch sends after registering.
+///
+/// This doesn't do any I/O, so the runners feed it the messages they read
+/// (and answer the PINGs themselves).
+pub(crate) struct Handshake {
+    is_anonymous: bool,
+    looking_for: HashSet<TwitchCap>,
+    caps: Capabilities,
+    our_name: Option<String>,
+    will_be_getting_global_user_state_hopefully: bool,
+    options: HandshakeOptions,
+    phase: HandshakePhase,
+    phase_started: Instant,
+    requests: Vec<TwitchCap>,
+}
+
+impl Handshake {
+    pub(crate) fn new(user_config: &UserConfig) -> Self {
+        // Twitch says we'll be getting a GlobalUserState if we just send the
+        // Tags capability
+        //
+        // This is false. Twitch will only send GlobalUserState if we've sent
+        // the Commands capability and atleast 1 other capability.
+        //
+        // That other capability doesn't have to be Tags, interestingly enough.
+        // So a combination of both 'Commands' and 'Membership' will produce an
+        // empty GlobalUserState
+        //
+        // We'll check for both Tags and Commands
+        //
+        // It also won't be sent if the capabilities are requested after registering
+        //
+        let options = user_config.handshake;
+        let will_be_getting_global_user_state_hopefully = match options.ready_on {
+            ReadyOn::GlobalUserState => true,
+            _ => {
+                options.order == RegisterOrder::CapabilitiesFirst
+                    && user_config.capabilities.contains(&TwitchCap::Tags)
+                    && user_config.capabilities.contains(&TwitchCap::Commands)
+            }
+        };
+
+        Self {
+            is_anonymous: user_config.is_anonymous(),
+            looking_for: user_config.capabilities.iter().copied().collect(),
+            caps: Capabilities::default(),
+            our_name: None,
+            will_be_getting_global_user_state_hopefully,
+            options,
+            phase: HandshakePhase::Welcome,
+            phase_started: Instant::now(),
+            requests: vec![],
+        }
+    }
+
+    /// The phase the handshake is in, and how long is left until it times out
+    pub(crate) fn time_left(&mut self) -> (HandshakePhase, Option<Duration>) {
+        let phase = match () {
+            _ if self.our_name.is_none() => HandshakePhase::Welcome,
+            _ if !self.looking_for.is_empty() => HandshakePhase::Capabilities,
+            _ => HandshakePhase::GlobalUserState,
+        };
+        if phase != self.phase {
+            self.phase = phase;
+            self.phase_started = Instant::now();
+        }
+
+        let left = self
+            .options
+            .timeout(phase)
+            .map(|timeout| timeout.saturating_sub(self.phase_started.elapsed()));
+        (phase, left)
+    }
+
+    /// Fail if the current phase has taken longer than its timeout
+    pub(crate) fn check_timeout(&mut self) -> Result<(), Error> {
+        match self.time_left() {
+            (phase, Some(left)) if left == Duration::ZERO => {
+                Err(Error::HandshakeTimedOut { phase })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Look at the next message, returning the identity once it is known
+    pub(crate) fn observe(&mut self, msg: &Commands<'_>) -> Result<Option<Identity>, Error> {
+        use Commands::*;
+
+        match msg {
+            IrcReady(msg) if self.options.ready_on == ReadyOn::Welcome => {
+                self.our_name.replace(msg.username().to_string());
+                if self.is_anonymous {
+                    return Ok(Some(Identity::Anonymous {
+                        name: msg.username().to_string(),
+                        caps: self.take_caps(),
+                    }));
+                }
+                return Ok(Some(self.basic()));
+            }
+
+            Ready(msg) => {
+                self.our_name.replace(msg.username().to_string());
+
+                // if we aren't going to be receiving tags, then we
+                // won't be looking for any more messages
+
+                // if we're anonymous, we won't get GLOBALUSERSTATE even
+                // if we do send Tags
+                if self.is_anonymous {
+                    return Ok(Some(Identity::Anonymous {
+                        name: msg.username().to_string(),
+                        caps: self.take_caps(),
+                    }));
+                }
+
+                // if we're not looking for any more caps and we won't be
+                // getting a GlobalUserState just give them the basic
+                // Identity
+                if self.looking_for.is_empty() && !self.will_be_getting_global_user_state_hopefully
+                {
+                    return Ok(Some(self.basic()));
+                }
+            }
+
+            Cap(msg) => match msg.capability() {
+                // a batched request is answered with all of its capabilities at once
+                Capability::Acknowledged(names) => {
+                    for name in names.split_whitespace() {
+                        self.acknowledge(name)
+                    }
+                }
+
+                Capability::NotAcknowledged(names) if self.options.batch_capabilities => {
+                    let names = names.split_whitespace().collect::<Vec<_>>();
+                    if let [name] = names[..] {
+                        self.refuse(name);
+                        if self.is_ready_without_global_user_state() {
+                            return Ok(Some(self.basic()));
+                        }
+                    } else {
+                        // ask for each of them again, to find out which one was refused
+                        let caps = names.into_iter().filter_map(TwitchCap::maybe_from_str);
+                        self.requests.extend(caps);
+                    }
+                }
+
+                Capability::NotAcknowledged(name) => {
+                    return Err(Error::InvalidCap {
+                        cap: name.to_string(),
+                    })
+                }
+            },
+
+            // NOTE: This will only be sent when there's both Commands and atleast one other CAP requested
+            GlobalUserState(msg) => {
+                // TODO: this is so shitty.
+                let id = match &msg.user_id {
+                    Some(id) => id.parse().unwrap(),
+                    // XXX: we can get this message without any tags
+                    None => return Ok(Some(self.basic())),
+                };
+
+                return Ok(Some(Identity::Full {
+                    // these unwraps should be safe because we'll have all of the TAGs here
+                    name: self.our_name.take().unwrap(),
+                    user_id: id,
+                    display_name: msg.display_name.as_ref().map(|s| s.to_string()),
+                    color: msg.color,
+                    caps: self.take_caps(),
+                }));
+            }
+
+            Ping(..) => {}
+
+            _ => {
+                // we have our name, but we won't be getting GlobalUserState and we've got all of our Caps
+                if self.is_ready_without_global_user_state() {
+                    return Ok(Some(self.basic()));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Take the capabilities that have to be requested again, each with their own `CAP REQ`
+    pub(crate) fn take_requests(&mut self) -> Vec<TwitchCap> {
+        std::mem::take(&mut self.requests)
+    }
+
+    fn is_ready_without_global_user_state(&self) -> bool {
+        self.our_name.is_some()
+            && !self.will_be_getting_global_user_state_hopefully
+            && self.looking_for.is_empty()
+    }
+
+    fn acknowledge(&mut self, name: &str) {
+        let cap = match TwitchCap::maybe_from_str(name) {
+            Some(cap) => cap,
+            // Twitch sent us an unknown capability
+            None => {
+                self.caps.unknown.insert(name.to_string());
+                return;
+            }
+        };
+
+        *match cap {
+            TwitchCap::Tags => &mut self.caps.tags,
+            TwitchCap::Membership => &mut self.caps.membership,
+            TwitchCap::Commands => &mut self.caps.commands,
+        } = true;
+
+        self.looking_for.remove(&cap);
+    }
+
+    fn refuse(&mut self, name: &str) {
+        let cap = match TwitchCap::maybe_from_str(name) {
+            Some(cap) => cap,
+            None => return,
+        };
+        self.looking_for.remove(&cap);
+        self.caps.refused.insert(cap);
+
+        // Twitch only sends GLOBALUSERSTATE with both of these
+        if matches!(cap, TwitchCap::Tags | TwitchCap::Commands)
+            && self.options.ready_on == ReadyOn::Auto
+        {
+            self.will_be_getting_global_user_state_hopefully = false;
+        }
+    }
+
+    fn basic(&mut self) -> Identity {
+        Identity::Basic {
+            name: self.our_name.take().unwrap(),
+            caps: self.take_caps(),
+        }
+    }
+
+    fn take_caps(&mut self) -> Capabilities {
+        std::mem::take(&mut self.caps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{irc::parse, FromIrcMessage as _};
+
+    const WELCOME: &str = ":tmi.twitch.tv CAP * ACK :twitch.tv/tags\r\n\
+                           :tmi.twitch.tv 001 museun :Welcome, GLHF!\r\n\
+                           :tmi.twitch.tv 376 museun :>\r\n\
+                           @user-id=23196011 :tmi.twitch.tv GLOBALUSERSTATE\r\n";
+
+    // the number of messages observed before the identity was known
+    fn ready_after(handshake: &mut Handshake) -> Option<(usize, Identity)> {
+        parse(WELCOME)
+            .map(|msg| Commands::from_irc(msg.unwrap()).unwrap())
+            .enumerate()
+            .find_map(|(i, msg)| Some((i + 1, handshake.observe(&msg).unwrap()?)))
+    }
+
+    fn config(handshake: HandshakeOptions) -> UserConfig {
+        UserConfig::builder()
+            .name("museun")
+            .token(format!("oauth:{}", "a".repeat(30)))
+            .capabilities(&[TwitchCap::Tags])
+            .handshake(handshake)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn ready_on() {
+        let mut handshake = Handshake::new(&config(HandshakeOptions::default()));
+        let (n, identity) = ready_after(&mut handshake).unwrap();
+        assert_eq!(n, 3);
+        assert!(matches!(identity, Identity::Basic { .. }));
+
+        let mut handshake = Handshake::new(&config(HandshakeOptions {
+            ready_on: ReadyOn::Welcome,
+            ..HandshakeOptions::default()
+        }));
+        let (n, identity) = ready_after(&mut handshake).unwrap();
+        assert_eq!(n, 2);
+        assert!(matches!(identity, Identity::Basic { caps, .. } if caps.tags));
+
+        let mut handshake = Handshake::new(&config(HandshakeOptions {
+            ready_on: ReadyOn::GlobalUserState,
+            ..HandshakeOptions::default()
+        }));
+        let (n, identity) = ready_after(&mut handshake).unwrap();
+        assert_eq!(n, 4);
+        assert!(matches!(
+            identity,
+            Identity::Full {
+                user_id: 23196011,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn timeouts() {
+        let mut handshake = Handshake::new(&config(HandshakeOptions {
+            welcome_timeout: Some(Duration::from_secs(60)),
+            capabilities_timeout: Some(Duration::ZERO),
+            ..HandshakeOptions::default()
+        }));
+        assert!(handshake.check_timeout().is_ok());
+
+        // the name is known, but the capability isn't
+        handshake.our_name.replace("museun".to_string());
+        assert!(matches!(
+            handshake.check_timeout(),
+            Err(Error::HandshakeTimedOut {
+                phase: HandshakePhase::Capabilities
+            })
+        ));
+
+        handshake.looking_for.clear();
+        assert_eq!(
+            handshake.time_left(),
+            (HandshakePhase::GlobalUserState, None)
+        );
+    }
+
+    #[test]
+    fn batched_capabilities() {
+        let observe = |handshake: &mut Handshake, input: &str| {
+            parse(input)
+                .map(|msg| Commands::from_irc(msg.unwrap()).unwrap())
+                .map(|msg| handshake.observe(&msg).unwrap())
+                .last()
+                .unwrap()
+        };
+
+        let config = UserConfig::builder()
+            .name("museun")
+            .token(format!("oauth:{}", "a".repeat(30)))
+            .capabilities(&[TwitchCap::Tags, TwitchCap::Commands, TwitchCap::Membership])
+            .handshake(HandshakeOptions {
+                batch_capabilities: true,
+                ..HandshakeOptions::default()
+            })
+            .build()
+            .unwrap();
+
+        let mut handshake = Handshake::new(&config);
+        let nak =
+            ":tmi.twitch.tv CAP * NAK :twitch.tv/membership twitch.tv/tags twitch.tv/commands\r\n";
+        assert!(observe(&mut handshake, nak).is_none());
+
+        let mut requests = handshake.take_requests();
+        requests.sort();
+        assert_eq!(
+            requests,
+            vec![TwitchCap::Membership, TwitchCap::Tags, TwitchCap::Commands]
+        );
+        assert!(handshake.take_requests().is_empty());
+
+        let input = ":tmi.twitch.tv 001 museun :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 museun :>\r\n\
+                     :tmi.twitch.tv CAP * ACK :twitch.tv/membership\r\n\
+                     :tmi.twitch.tv CAP * ACK :twitch.tv/tags\r\n";
+        assert!(observe(&mut handshake, input).is_none());
+
+        // without commands, there won't be a GLOBALUSERSTATE
+        let nak = ":tmi.twitch.tv CAP * NAK :twitch.tv/commands\r\n";
+        match observe(&mut handshake, nak) {
+            Some(Identity::Basic { caps, .. }) => {
+                assert!(caps.membership && caps.tags && !caps.commands);
+                assert_eq!(
+                    caps.refused.into_iter().collect::<Vec<_>>(),
+                    vec![TwitchCap::Commands]
+                );
+            }
+            identity => panic!("unexpected identity: {:?}", identity),
+        }
+
+        // a batch acknowledged at once
+        let mut handshake = Handshake::new(&config);
+        let ack =
+            ":tmi.twitch.tv CAP * ACK :twitch.tv/membership twitch.tv/tags twitch.tv/commands\r\n";
+        observe(&mut handshake, ack);
+        assert!(handshake.looking_for.is_empty());
+        assert!(handshake.caps.membership && handshake.caps.tags && handshake.caps.commands);
+    }
+
+    #[test]
+    fn refused_without_batching() {
+        let mut handshake = Handshake::new(&config(HandshakeOptions::default()));
+        let msg = parse(":tmi.twitch.tv CAP * NAK :twitch.tv/tags\r\n")
+            .next()
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            handshake.observe(&Commands::from_irc(msg).unwrap()),
+            Err(Error::InvalidCap { cap }) if cap == "twitch.tv/tags"
+        ));
+    }
+}
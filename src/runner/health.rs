@@ -0,0 +1,233 @@
+use std::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+// stored in `last_message` until the first message is received
+const NEVER: u64 = u64::MAX;
+
+/// The health of a runner, shared with whatever reports on it.
+///
+/// The runner updates this as it goes, reading it is a handful of atomic loads,
+/// so it can be called from the health check of a HTTP server.
+///
+/// See [AsyncRunner::health](crate::AsyncRunner::health)
+#[derive(Debug)]
+pub struct Health {
+    started: Instant,
+    connected: AtomicBool,
+    connection_id: AtomicU64,
+    // milliseconds since `started`
+    last_message: AtomicU64,
+    queue_depth: AtomicU64,
+    reconnects: AtomicU64,
+    channels: AtomicU64,
+}
+
+impl Health {
+    pub(crate) fn new(connection_id: u64) -> Self {
+        Self {
+            started: Instant::now(),
+            connected: AtomicBool::new(true),
+            connection_id: AtomicU64::new(connection_id),
+            last_message: AtomicU64::new(NEVER),
+            queue_depth: AtomicU64::new(0),
+            reconnects: AtomicU64::new(0),
+            channels: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn message_received(&self) {
+        let elapsed = self.started.elapsed().as_millis() as u64;
+        self.last_message.store(elapsed, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_queues(&self, queue_depth: usize, channels: usize) {
+        self.queue_depth.store(queue_depth as u64, Ordering::Relaxed);
+        self.channels.store(channels as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn reconnected(&self, connection_id: u64) {
+        self.connection_id.store(connection_id, Ordering::Relaxed);
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn disconnected(&self) {
+        self.connected.store(false, Ordering::Relaxed);
+    }
+
+    /// Get the current state of the runner
+    pub fn shard(&self) -> ShardHealth {
+        let last_message = match self.last_message.load(Ordering::Relaxed) {
+            NEVER => None,
+            ms => Some(
+                self.started
+                    .elapsed()
+                    .saturating_sub(Duration::from_millis(ms)),
+            ),
+        };
+
+        ShardHealth {
+            connection_id: self.connection_id.load(Ordering::Relaxed),
+            connected: self.connected.load(Ordering::Relaxed),
+            last_message_age: last_message,
+            queue_depth: self.queue_depth.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+            channels: self.channels.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The state of a single runner, see [Health::shard]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct ShardHealth {
+    /// The id of the connection the runner is using, see [Envelope](crate::runner::Envelope)
+    pub connection_id: u64,
+    /// Whether the runner is still connected
+    pub connected: bool,
+    /// How long ago the last message was received, if any was
+    pub last_message_age: Option<Duration>,
+    /// How many messages are waiting for the rate limit of their channel
+    pub queue_depth: u64,
+    /// How many times the runner switched to a new connection, see
+    /// [AsyncRunner::reauthenticate](crate::AsyncRunner::reauthenticate)
+    pub reconnects: u64,
+    /// How many channels the runner is on
+    pub channels: u64,
+}
+
+impl ShardHealth {
+    /// Whether the runner is connected and received a message in the last `max_age`
+    ///
+    /// Twitch sends a `PING` every five minutes, so a `max_age` a bit longer
+    /// than that works even for quiet channels.
+    pub fn is_healthy(&self, max_age: Duration) -> bool {
+        self.connected && matches!(self.last_message_age, Some(age) if age <= max_age)
+    }
+}
+
+/// The health of a set of runners (e.g. shards), collected in one call.
+///
+/// ```
+/// # use twitchchat::runner::{HealthReport, ShardHealth};
+/// # use std::time::Duration;
+/// # let shard = ShardHealth {
+/// #     connection_id: 0, connected: true, last_message_age: Some(Duration::from_secs(1)),
+/// #     queue_depth: 2, reconnects: 0, channels: 3,
+/// # };
+/// # let shards = vec![shard, ShardHealth { connected: false, ..shard }];
+/// let report: HealthReport = shards.into_iter().collect();
+/// assert_eq!(report.connected_shards(), 1);
+/// assert_eq!(report.queue_depth(), 4);
+/// assert!(!report.is_healthy(Duration::from_secs(330)));
+/// ```
+///
+/// With the runners' [Health] handles (see [AsyncRunner::health](crate::AsyncRunner::health)),
+/// it's `HealthReport::new(&handles)`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct HealthReport {
+    /// The state of each runner, in the order they were given
+    pub shards: Vec<ShardHealth>,
+}
+
+impl HealthReport {
+    /// Collect the state of each of these runners
+    pub fn new<'a, I, H>(shards: I) -> Self
+    where
+        I: IntoIterator<Item = &'a H>,
+        H: AsRef<Health> + 'a,
+    {
+        shards
+            .into_iter()
+            .map(|health| health.as_ref().shard())
+            .collect()
+    }
+
+    /// How many of the runners are still connected
+    pub fn connected_shards(&self) -> usize {
+        self.shards.iter().filter(|shard| shard.connected).count()
+    }
+
+    /// How many messages are waiting to be sent, over all runners
+    pub fn queue_depth(&self) -> u64 {
+        self.shards.iter().map(|shard| shard.queue_depth).sum()
+    }
+
+    /// How many times the runners reconnected in total
+    pub fn reconnects(&self) -> u64 {
+        self.shards.iter().map(|shard| shard.reconnects).sum()
+    }
+
+    /// The age of the most stale runner's last message, if all of them received one
+    pub fn oldest_message_age(&self) -> Option<Duration> {
+        self.shards
+            .iter()
+            .map(|shard| shard.last_message_age)
+            .try_fold(Duration::default(), |max, age| Some(max.max(age?)))
+    }
+
+    /// Whether every runner is healthy, see [ShardHealth::is_healthy]
+    ///
+    /// An empty report isn't healthy.
+    pub fn is_healthy(&self, max_age: Duration) -> bool {
+        !self.shards.is_empty() && self.shards.iter().all(|shard| shard.is_healthy(max_age))
+    }
+}
+
+impl std::iter::FromIterator<ShardHealth> for HealthReport {
+    fn from_iter<I: IntoIterator<Item = ShardHealth>>(iter: I) -> Self {
+        Self {
+            shards: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::{runner::Status, test::TestConnector, AsyncRunner, UserConfig};
+    use std::sync::Arc;
+
+    #[test]
+    fn report() {
+        let connector = TestConnector::default();
+        let config = UserConfig::builder().anonymous().build().unwrap();
+
+        futures_lite::future::block_on(async {
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #museun\r\n\
+                     :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n",
+                )
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector, &config).await.unwrap();
+            let health = runner.health();
+            let idle = Arc::new(Health::new(42));
+
+            let report = HealthReport::new(&[health.clone(), idle.clone()]);
+            assert_eq!(report.connected_shards(), 2);
+            assert_eq!(report.oldest_message_age(), None);
+            assert!(!report.is_healthy(Duration::from_secs(60)));
+
+            while let Status::Message(..) = runner.next_message().await.unwrap() {}
+
+            let shard = health.shard();
+            assert!(!shard.connected);
+            assert_eq!(shard.channels, 1);
+            assert_eq!(shard.reconnects, 0);
+            assert!(shard.last_message_age.is_some());
+
+            idle.message_received();
+            let report = HealthReport::new(&[health, idle]);
+            assert_eq!(report.connected_shards(), 1);
+            assert!(report.oldest_message_age().is_some());
+            assert!(!report.is_healthy(Duration::from_secs(60)));
+        });
+    }
+}
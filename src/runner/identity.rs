@@ -3,10 +3,18 @@ use crate::{runner::Capabilities, twitch::Color};
 /// Your identity on Twitch.
 ///
 /// Currently this is only updated when you connect.
+///
+/// ***NOTE*** This is a breaking change from `0.14.8`: `Anonymous` has the
+/// `name` it connected with, so patterns like `Identity::Anonymous { caps }`
+/// need a `..`. Use [Identity::username] to get the name of any identity. This
+/// is also `#[non_exhaustive]` now, so matches need a wildcard arm.
+#[non_exhaustive]
 #[derive(Debug, Clone)]
 pub enum Identity {
     /// An anonymous identity.
     Anonymous {
+        /// Your `justinfan` nick
+        name: String,
         /// The capabilities you'll have
         caps: Capabilities,
     },
@@ -41,12 +49,13 @@ pub enum Identity {
 impl Identity {
     /// Get your username from this identity
     ///
-    /// If its anonymous, it'll be `justinfan1234`
+    /// If its anonymous, it'll be the `justinfan` nick you connected with,
+    /// e.g. `justinfan1234` or one from [anonymous_nick](crate::twitch::anonymous_nick)
     pub fn username(&self) -> &str {
-        let (nick, _) = crate::ANONYMOUS_LOGIN;
         match self {
-            Self::Anonymous { .. } => nick,
-            Self::Basic { name, .. } | Self::Full { name, .. } => &*name,
+            Self::Anonymous { name, .. } | Self::Basic { name, .. } | Self::Full { name, .. } => {
+                name
+            }
         }
     }
 }
@@ -0,0 +1,45 @@
+use crate::messages::MessageId;
+
+/// Why joining a channel failed
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum JoinFailure {
+    /// The channel is suspended (`msg_channel_suspended`)
+    Suspended,
+    /// The channel doesn't exist (`msg_room_not_found`)
+    NotFound,
+    /// You are banned from the channel (`msg_banned`)
+    Banned,
+    /// Twitch didn't answer the JOIN, e.g. because joins were rate limited
+    NoResponse,
+}
+
+impl JoinFailure {
+    /// The failure a notice with this message id reports, if it is about joins
+    pub fn from_msg_id(msg_id: &MessageId<'_>) -> Option<Self> {
+        let failure = match msg_id {
+            MessageId::MsgChannelSuspended => Self::Suspended,
+            MessageId::MsgRoomNotFound => Self::NotFound,
+            MessageId::MsgBanned => Self::Banned,
+            _ => return None,
+        };
+        Some(failure)
+    }
+
+    /// Whether trying again later can help
+    pub fn is_transient(self) -> bool {
+        matches!(self, Self::Suspended | Self::NoResponse)
+    }
+}
+
+impl std::fmt::Display for JoinFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Suspended => f.write_str("the channel is suspended"),
+            Self::NotFound => f.write_str("the channel does not exist"),
+            Self::Banned => f.write_str("you are banned from the channel"),
+            Self::NoResponse => f.write_str("twitch did not answer the join"),
+        }
+    }
+}
@@ -0,0 +1,585 @@
+use super::JoinFailure;
+use crate::commands::Channel;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+// how long Twitch has to answer a JOIN before it is assumed to have been dropped
+pub(crate) const JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How the failed joins of a channel are retried, see [JoinManager]
+///
+/// The delay doubles after each failed attempt, up to `max_delay`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct JoinRetry {
+    /// The delay before the first retry
+    pub initial_delay: Duration,
+    /// The longest delay between two attempts
+    pub max_delay: Duration,
+    /// How many times the join is retried before giving up
+    pub max_retries: u32,
+}
+
+impl Default for JoinRetry {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(5),
+            max_delay: Duration::from_secs(5 * 60),
+            max_retries: 8,
+        }
+    }
+}
+
+impl JoinRetry {
+    /// Never retry, a failed join is given up on right away
+    pub const fn disabled() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(0),
+            max_delay: Duration::from_secs(0),
+            max_retries: 0,
+        }
+    }
+
+    /// The delay before the `retry`th retry, starting at `1`
+    ///
+    /// ```
+    /// # use twitchchat::runner::JoinRetry;
+    /// # use std::time::Duration;
+    /// let retry = JoinRetry::default();
+    /// assert_eq!(retry.delay(1), Duration::from_secs(5));
+    /// assert_eq!(retry.delay(3), Duration::from_secs(20));
+    /// assert_eq!(retry.delay(20), Duration::from_secs(5 * 60));
+    /// ```
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 1_u32
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+}
+
+/// What happened to a join, see [AsyncRunner::join_events](crate::AsyncRunner::join_events)
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum JoinEvent {
+    /// The channel was joined after retrying
+    Joined {
+        /// The channel
+        channel: String,
+        /// How many times the join was retried
+        retries: u32,
+    },
+    /// The join failed and will be tried again
+    Retrying {
+        /// The channel
+        channel: String,
+        /// Why it failed
+        failure: JoinFailure,
+        /// Which retry this is, starting at `1`
+        retry: u32,
+        /// How long until the JOIN is sent again
+        delay: Duration,
+    },
+    /// The join failed and won't be tried again
+    GaveUp {
+        /// The channel
+        channel: String,
+        /// Why it failed the last time
+        failure: JoinFailure,
+        /// How many times the join was retried
+        retries: u32,
+    },
+}
+
+/// A JOIN or PART of your own that Twitch sent again, see
+/// [AsyncRunner::duplicate_echoes](crate::AsyncRunner::duplicate_echoes)
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DuplicateEcho {
+    /// A JOIN of a channel you were already on, e.g. when rejoining after reconnecting
+    Join {
+        /// The channel
+        channel: String,
+    },
+    /// A PART of a channel you had already left
+    Part {
+        /// The channel
+        channel: String,
+    },
+}
+
+impl DuplicateEcho {
+    /// The channel of the JOIN or PART
+    pub fn channel(&self) -> &str {
+        match self {
+            Self::Join { channel } | Self::Part { channel } => channel,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Pending {
+    retries: u32,
+    // when the JOIN was sent, while waiting for an answer
+    sent: Option<Instant>,
+    // when the JOIN should be sent again, while waiting to retry
+    retry_at: Option<Instant>,
+}
+
+/// Keeps track of the JOINs the runner sent, and retries the ones that fail.
+///
+/// Get it with [AsyncRunner::join_manager](crate::AsyncRunner::join_manager)
+/// and follow what it does with [AsyncRunner::join_events](crate::AsyncRunner::join_events).
+///
+/// Every channel uses the default [JoinRetry] unless it has its own.
+#[derive(Debug, Default)]
+pub struct JoinManager {
+    default: JoinRetry,
+    retries: HashMap<String, JoinRetry>,
+    pending: HashMap<String, Pending>,
+    // the last failure of the joins that were given up on
+    gave_up: HashMap<String, JoinFailure>,
+}
+
+impl JoinManager {
+    /// Set the [JoinRetry] of the channels without their own
+    pub fn set_default_retry(&mut self, retry: JoinRetry) {
+        self.default = retry;
+    }
+
+    /// Set the [JoinRetry] of this channel
+    pub fn set_retry(&mut self, channel: &str, retry: JoinRetry) {
+        self.retries
+            .insert(Channel::new(channel).to_string(), retry);
+    }
+
+    /// Let this channel use the default [JoinRetry] again
+    pub fn reset_retry(&mut self, channel: &str) {
+        self.retries.remove(&Channel::new(channel).to_string());
+    }
+
+    /// The [JoinRetry] of this channel
+    pub fn retry_for(&self, channel: &str) -> JoinRetry {
+        self.retries
+            .get(&Channel::new(channel).to_string())
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// Whether a join of this channel is waiting for an answer or to be retried
+    pub fn is_pending(&self, channel: &str) -> bool {
+        self.pending
+            .contains_key(&Channel::new(channel).to_string())
+    }
+
+    /// The channels with a join waiting for an answer or to be retried
+    pub fn pending(&self) -> impl Iterator<Item = &str> + '_ {
+        self.pending.keys().map(String::as_str)
+    }
+
+    /// Stop retrying to join this channel.
+    ///
+    /// Returns whether a join was pending
+    pub fn cancel(&mut self, channel: &str) -> bool {
+        self.pending
+            .remove(&Channel::new(channel).to_string())
+            .is_some()
+    }
+
+    pub(crate) fn sent(&mut self, channel: &str, now: Instant) {
+        let pending = self
+            .pending
+            .entry(Channel::new(channel).to_string())
+            .or_insert(Pending {
+                retries: 0,
+                sent: None,
+                retry_at: None,
+            });
+        pending.sent = Some(now);
+        self.gave_up.remove(&Channel::new(channel).to_string());
+        pending.retry_at = None;
+    }
+
+    pub(crate) fn joined(&mut self, channel: &str) -> Option<JoinEvent> {
+        let channel = Channel::new(channel).to_string();
+        let pending = self.pending.remove(&channel)?;
+        if pending.retries == 0 {
+            return None;
+        }
+        Some(JoinEvent::Joined {
+            channel,
+            retries: pending.retries,
+        })
+    }
+
+    pub(crate) fn failed(
+        &mut self,
+        channel: &str,
+        failure: JoinFailure,
+        now: Instant,
+    ) -> Option<JoinEvent> {
+        let channel = Channel::new(channel).to_string();
+        let retry = self.retry_for(&channel);
+        let pending = self.pending.get_mut(&channel)?;
+        // only a JOIN that was sent can fail
+        pending.sent.take()?;
+
+        if !failure.is_transient() || pending.retries >= retry.max_retries {
+            let retries = pending.retries;
+            self.pending.remove(&channel);
+            self.gave_up.insert(channel.clone(), failure);
+            return Some(JoinEvent::GaveUp {
+                channel,
+                failure,
+                retries,
+            });
+        }
+
+        pending.retries += 1;
+        let delay = retry.delay(pending.retries);
+        pending.retry_at = Some(now + delay);
+        Some(JoinEvent::Retrying {
+            channel,
+            failure,
+            retry: pending.retries,
+            delay,
+        })
+    }
+
+    pub(crate) fn gave_up(&self, channel: &str) -> Option<JoinFailure> {
+        self.gave_up.get(channel).copied()
+    }
+
+    // the JOINs that weren't answered in time
+    pub(crate) fn timed_out(&mut self, now: Instant) -> Vec<JoinEvent> {
+        let channels = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| {
+                matches!(pending.sent, Some(sent) if now.saturating_duration_since(sent) >= JOIN_TIMEOUT)
+            })
+            .map(|(channel, _)| channel.clone())
+            .collect::<Vec<_>>();
+
+        channels
+            .into_iter()
+            .filter_map(|channel| self.failed(&channel, JoinFailure::NoResponse, now))
+            .collect()
+    }
+
+    // the channels that should be joined again now
+    pub(crate) fn due(&self, now: Instant) -> Vec<String> {
+        self.pending
+            .iter()
+            .filter(|(_, pending)| matches!(pending.retry_at, Some(at) if at <= now))
+            .map(|(channel, _)| channel.clone())
+            .collect()
+    }
+
+    // how long until something has to be done
+    pub(crate) fn next_wakeup(&self, now: Instant) -> Option<Duration> {
+        self.pending
+            .values()
+            .filter_map(|pending| match (pending.sent, pending.retry_at) {
+                (Some(sent), _) => Some(sent + JOIN_TIMEOUT),
+                (None, at) => at,
+            })
+            .min()
+            .map(|at| at.saturating_duration_since(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff() {
+        let retry = JoinRetry {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10),
+            max_retries: 3,
+        };
+        let mut joins = JoinManager::default();
+        joins.set_retry("museun", retry);
+
+        let start = Instant::now();
+        joins.sent("#museun", start);
+        assert!(joins.is_pending("museun"));
+
+        let mut delays = vec![];
+        let mut now = start;
+        loop {
+            match joins.failed("#museun", JoinFailure::Suspended, now) {
+                Some(JoinEvent::Retrying { delay, .. }) => delays.push(delay),
+                Some(JoinEvent::GaveUp { retries, .. }) => {
+                    assert_eq!(retries, 3);
+                    break;
+                }
+                event => panic!("unexpected event: {:?}", event),
+            }
+
+            assert!(joins.due(now).is_empty());
+            now += *delays.last().unwrap();
+            assert_eq!(joins.due(now), vec!["#museun".to_string()]);
+            joins.sent("#museun", now);
+        }
+
+        let secs = |s| Duration::from_secs(s);
+        assert_eq!(delays, vec![secs(1), secs(2), secs(4)]);
+        assert!(!joins.is_pending("#museun"));
+    }
+
+    #[test]
+    fn timeouts_and_terminal_failures() {
+        let mut joins = JoinManager::default();
+        let start = Instant::now();
+
+        joins.sent("#museun", start);
+        joins.sent("#shaken_bot", start);
+        assert_eq!(joins.next_wakeup(start), Some(JOIN_TIMEOUT));
+        assert!(joins.timed_out(start).is_empty());
+
+        // a join that wasn't answered is retried
+        let events = joins.timed_out(start + JOIN_TIMEOUT);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            &events[0],
+            JoinEvent::Retrying {
+                failure: JoinFailure::NoResponse,
+                retry: 1,
+                ..
+            }
+        ));
+
+        // and a successful retry is reported
+        joins.sent("#museun", start + JOIN_TIMEOUT * 2);
+        assert_eq!(
+            joins.joined("#museun"),
+            Some(JoinEvent::Joined {
+                channel: "#museun".into(),
+                retries: 1
+            })
+        );
+
+        // a ban isn't retried
+        joins.sent("#shaken_bot", start);
+        assert_eq!(
+            joins.failed("#shaken_bot", JoinFailure::Banned, start),
+            Some(JoinEvent::GaveUp {
+                channel: "#shaken_bot".into(),
+                failure: JoinFailure::Banned,
+                retries: 1
+            })
+        );
+
+        // neither are joins of channels without a retry
+        joins.set_retry("#foo", JoinRetry::disabled());
+        joins.sent("#foo", start);
+        assert!(matches!(
+            joins.failed("#foo", JoinFailure::Suspended, start),
+            Some(JoinEvent::GaveUp { .. })
+        ));
+
+        // and a join that isn't pending can't fail
+        assert_eq!(joins.failed("#bar", JoinFailure::Suspended, start), None);
+        assert_eq!(joins.pending().count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "testing")]
+    fn runner_retries_joins() {
+        use crate::{runner::Error, test::TestConnector, AsyncRunner, UserConfig};
+
+        let config = UserConfig::builder().anonymous().build().unwrap();
+        futures_lite::future::block_on(async {
+            let connector = TestConnector::default();
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     @msg-id=msg_channel_suspended :tmi.twitch.tv NOTICE #museun :This channel has been suspended.\r\n\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #museun\r\n\
+                     @msg-id=msg_channel_suspended :tmi.twitch.tv NOTICE #shaken_bot :This channel has been suspended.\r\n",
+                )
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector.clone(), &config)
+                .await
+                .unwrap();
+            let events = runner.join_events();
+
+            let retry = JoinRetry {
+                initial_delay: Duration::from_secs(0),
+                max_delay: Duration::from_secs(0),
+                max_retries: 1,
+            };
+            runner.join_manager().set_default_retry(retry);
+            runner
+                .join_manager()
+                .set_retry("#shaken_bot", JoinRetry::disabled());
+
+            runner.join("#museun").await.unwrap();
+            assert!(matches!(
+                runner.join("#shaken_bot").await,
+                Err(Error::JoinFailed {
+                    failure: JoinFailure::Suspended,
+                    ..
+                })
+            ));
+
+            let lines = connector.conn.read_all_lines().await.unwrap();
+            let joins = lines.iter().filter(|l| *l == "JOIN #museun\r\n").count();
+            assert_eq!(joins, 2);
+
+            let events = std::iter::from_fn(|| events.try_recv()).collect::<Vec<_>>();
+            assert_eq!(
+                events,
+                vec![
+                    JoinEvent::Retrying {
+                        channel: "#museun".into(),
+                        failure: JoinFailure::Suspended,
+                        retry: 1,
+                        delay: Duration::from_secs(0),
+                    },
+                    JoinEvent::Joined {
+                        channel: "#museun".into(),
+                        retries: 1,
+                    },
+                    JoinEvent::GaveUp {
+                        channel: "#shaken_bot".into(),
+                        failure: JoinFailure::Suspended,
+                        retries: 0,
+                    },
+                ]
+            );
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "testing")]
+    fn runner_retries_failed_rejoins() {
+        use crate::{runner::Status, test::TestConnector, AsyncRunner, UserConfig};
+
+        let config = UserConfig::builder().anonymous().build().unwrap();
+        futures_lite::future::block_on(async {
+            let connector = TestConnector::default();
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #museun\r\n\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #shaken_bot\r\n",
+                )
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector, &config).await.unwrap();
+            runner.join("#museun").await.unwrap();
+            runner.join("#shaken_bot").await.unwrap();
+
+            let events = runner.join_events();
+            runner.join_manager().set_default_retry(JoinRetry {
+                initial_delay: Duration::from_secs(0),
+                max_delay: Duration::from_secs(0),
+                max_retries: 1,
+            });
+
+            let connector = TestConnector::default();
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #museun\r\n\
+                     @msg-id=msg_channel_suspended :tmi.twitch.tv NOTICE #shaken_bot :This channel has been suspended.\r\n",
+                )
+                .await;
+
+            runner
+                .reauthenticate(connector.clone(), &config)
+                .await
+                .unwrap();
+            assert!(runner.is_on_channel("#museun"));
+            assert!(!runner.is_on_channel("#shaken_bot"));
+
+            // the rejoin is retried on the new connection
+            while let Status::Message(..) = runner.next_message().await.unwrap() {}
+            let lines = connector.conn.read_all_lines().await.unwrap();
+            let joins = lines.iter().filter(|l| *l == "JOIN #shaken_bot\r\n").count();
+            assert_eq!(joins, 2);
+
+            assert_eq!(
+                events.try_recv(),
+                Some(JoinEvent::Retrying {
+                    channel: "#shaken_bot".into(),
+                    failure: JoinFailure::Suspended,
+                    retry: 1,
+                    delay: Duration::from_secs(0),
+                })
+            );
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "testing")]
+    fn runner_drops_duplicate_echoes() {
+        use crate::{
+            messages::Commands,
+            runner::Status,
+            test::TestConnector,
+            AsyncRunner, UserConfig,
+        };
+
+        let config = UserConfig::builder().anonymous().build().unwrap();
+        futures_lite::future::block_on(async {
+            let connector = TestConnector::default();
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #museun\r\n\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #museun\r\n\
+                     :foo!foo@foo.tmi.twitch.tv JOIN #museun\r\n\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv PART #museun\r\n\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv PART #museun\r\n",
+                )
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector, &config).await.unwrap();
+            let echoes = runner.duplicate_echoes();
+
+            let mut membership = vec![];
+            while let Status::Message(msg) = runner.next_message().await.unwrap() {
+                match msg {
+                    Commands::Join(msg) => membership.push(format!("JOIN {}", msg.name())),
+                    Commands::Part(msg) => membership.push(format!("PART {}", msg.name())),
+                    _ => {}
+                }
+            }
+            assert_eq!(
+                membership,
+                vec!["JOIN justinfan1234", "JOIN foo", "PART justinfan1234"]
+            );
+
+            let echoes = std::iter::from_fn(|| echoes.try_recv()).collect::<Vec<_>>();
+            assert_eq!(
+                echoes,
+                vec![
+                    DuplicateEcho::Join {
+                        channel: "#museun".into()
+                    },
+                    DuplicateEcho::Part {
+                        channel: "#museun".into()
+                    },
+                ]
+            );
+            assert_eq!(runner.session_summary().dropped_messages, 2);
+        });
+    }
+}
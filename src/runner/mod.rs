@@ -8,6 +8,14 @@
 //!     1. join a channel with: [AsyncRunner::join()],
 //!     1. write messages with the [AsyncWriter](crate::writer::AsyncWriter) provided by [AsyncRunner::writer()].
 //!     1. signal you want to quit with the [AsyncRunner::quit_handle()]
+//!     1. change the rate limit, channels or ignored users at runtime with the [AsyncRunner::config_handle()]
+//!
+//! To reproduce a bug without a connection to Twitch, record the session with
+//! [AsyncRunner::connect_recorded()] and run the same code against it again with
+//! [AsyncRunner::connect_replay()].
+//!
+//! For small tools that don't want an async runtime, the blocking [SyncRunner]
+//! works with any [std::io::Read] and [std::io::Write] pair, e.g. a `TcpStream`.
 //!
 
 mod status;
@@ -22,6 +30,14 @@ pub use identity::Identity;
 mod error;
 pub use error::Error;
 
+mod join_failure;
+pub use join_failure::JoinFailure;
+
+mod handshake;
+
+mod sync_runner;
+pub use sync_runner::SyncRunner;
+
 #[allow(dead_code)]
 mod timeout;
 
@@ -29,11 +45,81 @@ cfg_async! {
     mod rate_limit;
 }
 
+cfg_async! {
+    mod config;
+    pub use config::{ConfigChange, ConfigHandle, RuntimeConfig};
+}
+
 cfg_async! {
     mod channel;
     pub use channel::Channel;
 }
 
+cfg_async! {
+    mod envelope;
+    pub use envelope::Envelope;
+}
+
+cfg_async! {
+    mod session;
+    pub use session::SessionSummary;
+}
+
+cfg_async! {
+    mod health;
+    pub use health::{Health, HealthReport, ShardHealth};
+}
+
+cfg_async! {
+    mod rejection;
+    pub use rejection::{RejectReason, SendRejected};
+}
+
+cfg_async! {
+    mod moderation;
+    pub use moderation::{ModerationAction, ModerationCommand, ModerationOutcome};
+}
+
+cfg_async! {
+    mod join_manager;
+    pub use join_manager::{DuplicateEcho, JoinEvent, JoinManager, JoinRetry};
+}
+
+cfg_async! {
+    mod roles;
+    pub use roles::{RoleChange, Roles};
+}
+
+cfg_async! {
+    mod raid;
+    pub use raid::RaidOutcome;
+}
+
+cfg_async! {
+    mod nonce;
+    pub use nonce::{Correlation, NonceMap, PendingSend, SendOutcome};
+}
+
+cfg_async! {
+    mod replay;
+    pub use replay::{
+        Divergence, EventKind, RecordedEvent, Recorder, Recording, RecordingConnector,
+        RecordingError, RecordingStream, Replay, ReplayConnector, ReplayStream,
+    };
+}
+
+#[cfg(feature = "signing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "signing")))]
+pub mod signing;
+#[cfg(feature = "signing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "signing")))]
+pub use signing::{EnvelopeSigner, SignedEnvelope, SigningError};
+
+cfg_async! {
+    mod transform;
+    pub use transform::Transform;
+}
+
 cfg_async! {
     mod async_runner;
     pub use async_runner::AsyncRunner;
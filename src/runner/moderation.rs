@@ -0,0 +1,311 @@
+use crate::{
+    commands::types::{Ban, Delete, Timeout},
+    messages::{MessageId, Notice},
+    Encodable,
+};
+
+/// What a moderation command does, see [ModerationCommand]
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum ModerationAction {
+    /// `/ban`
+    Ban,
+    /// `/timeout`
+    Timeout,
+    /// `/delete`
+    Delete,
+}
+
+impl ModerationAction {
+    /// The outcome of this action a notice with this message id reports, if it is about this action
+    ///
+    /// ```
+    /// # use twitchchat::{messages::MessageId, runner::{ModerationAction, ModerationOutcome}};
+    /// let outcome = ModerationAction::Ban.outcome(&MessageId::BadBanMod);
+    /// assert_eq!(outcome, Some(ModerationOutcome::Protected));
+    ///
+    /// // that's about timeouts
+    /// assert_eq!(ModerationAction::Ban.outcome(&MessageId::TimeoutSuccess), None);
+    /// ```
+    pub fn outcome(self, msg_id: &MessageId<'_>) -> Option<ModerationOutcome> {
+        use {MessageId as Id, ModerationAction as Action, ModerationOutcome as Outcome};
+
+        let outcome = match (self, msg_id) {
+            (Action::Ban, Id::BanSuccess)
+            | (Action::Timeout, Id::TimeoutSuccess)
+            | (Action::Delete, Id::DeleteMessageSuccess) => Outcome::Success,
+
+            (Action::Ban, Id::AlreadyBanned) => Outcome::AlreadyBanned,
+
+            (Action::Ban, Id::BadBanAdmin)
+            | (Action::Ban, Id::BadBanAnon)
+            | (Action::Ban, Id::BadBanBroadcaster)
+            | (Action::Ban, Id::BadBanGlobalMod)
+            | (Action::Ban, Id::BadBanMod)
+            | (Action::Ban, Id::BadBanSelf)
+            | (Action::Ban, Id::BadBanStaff)
+            | (Action::Timeout, Id::BadTimeoutAdmin)
+            | (Action::Timeout, Id::BadTimeoutAnon)
+            | (Action::Timeout, Id::BadTimeoutBroadcaster)
+            | (Action::Timeout, Id::BadTimeoutGlobalMod)
+            | (Action::Timeout, Id::BadTimeoutMod)
+            | (Action::Timeout, Id::BadTimeoutSelf)
+            | (Action::Timeout, Id::BadTimeoutStaff)
+            | (Action::Delete, Id::BadDeleteMessageBroadcaster)
+            | (Action::Delete, Id::BadDeleteMessageMod) => Outcome::Protected,
+
+            (Action::Ban, Id::UsageBan)
+            | (Action::Timeout, Id::UsageTimeout)
+            | (Action::Timeout, Id::BadTimeoutDuration) => Outcome::InvalidArguments,
+
+            (_, Id::NoPermission) => Outcome::NoPermission,
+            (_, Id::InvalidUser) => Outcome::InvalidUser,
+            (_, Id::UnrecognizedCmd) => Outcome::Unsupported,
+            _ => return None,
+        };
+        Some(outcome)
+    }
+
+    /// The outcome of this action a notice reports, if it is about this action
+    pub fn outcome_of(self, msg: &Notice<'_>) -> Option<ModerationOutcome> {
+        self.outcome(&msg.msg_id()?)
+    }
+}
+
+/// What Twitch replied to a moderation command.
+///
+/// See [AsyncRunner::moderate](crate::AsyncRunner::moderate)
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum ModerationOutcome {
+    /// It worked (`ban_success`, `timeout_success`, `delete_message_success`)
+    Success,
+    /// The user was already banned (`already_banned`)
+    AlreadyBanned,
+    /// The user (or their message) can't be moderated by you, e.g. the
+    /// broadcaster or another moderator (`bad_ban_*`, `bad_timeout_*`, `bad_delete_message_*`)
+    Protected,
+    /// The command was malformed, e.g. an invalid duration (`usage_*`, `bad_timeout_duration`)
+    InvalidArguments,
+    /// You aren't a moderator of the channel (`no_permission`)
+    NoPermission,
+    /// The user doesn't exist (`invalid_user`)
+    InvalidUser,
+    /// Twitch doesn't support the command (`unrecognized_cmd`)
+    Unsupported,
+    /// Twitch didn't reply in time
+    NoReply,
+}
+
+impl ModerationOutcome {
+    /// Whether the command worked
+    pub fn is_success(self) -> bool {
+        matches!(self, Self::Success)
+    }
+}
+
+/// A command whose result Twitch replies to with a `NOTICE`.
+///
+/// See [AsyncRunner::moderate](crate::AsyncRunner::moderate)
+pub trait ModerationCommand: Encodable + Send + Sync {
+    /// The channel it is sent to
+    fn channel(&self) -> &str;
+    /// What it does
+    fn action(&self) -> ModerationAction;
+}
+
+impl<'a> ModerationCommand for Ban<'a> {
+    fn channel(&self) -> &str {
+        self.channel
+    }
+
+    fn action(&self) -> ModerationAction {
+        ModerationAction::Ban
+    }
+}
+
+impl<'a> ModerationCommand for Timeout<'a> {
+    fn channel(&self) -> &str {
+        self.channel
+    }
+
+    fn action(&self) -> ModerationAction {
+        ModerationAction::Timeout
+    }
+}
+
+impl<'a> ModerationCommand for Delete<'a> {
+    fn channel(&self) -> &str {
+        self.channel
+    }
+
+    fn action(&self) -> ModerationAction {
+        ModerationAction::Delete
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::{commands, test::TestConnector, AsyncRunner, UserConfig};
+
+    const REGISTER: &str = ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                            :tmi.twitch.tv 376 justinfan1234 :>\r\n";
+
+    #[test]
+    fn moderate() {
+        let config = UserConfig::builder().anonymous().build().unwrap();
+        futures_lite::future::block_on(async {
+            let connector = TestConnector::default();
+            connector
+                .conn
+                .write_data(format!(
+                    "{}\
+                     :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n\
+                     @msg-id=timeout_success :tmi.twitch.tv NOTICE #other :foo has been timed out for 10 minutes.\r\n\
+                     @msg-id=bad_ban_mod :tmi.twitch.tv NOTICE #museun :You cannot ban moderator bar unless you are the owner of this channel.\r\n\
+                     @msg-id=delete_message_success :tmi.twitch.tv NOTICE #museun :The message from baz is now deleted.\r\n",
+                    REGISTER
+                ))
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector.clone(), &config)
+                .await
+                .unwrap();
+
+            // the notices of other channels and actions are skipped
+            let outcome = runner
+                .moderate(commands::ban("museun", "bar", None))
+                .await
+                .unwrap();
+            assert_eq!(outcome, ModerationOutcome::Protected);
+
+            let outcome = runner
+                .moderate(commands::delete("#museun", "abc"))
+                .await
+                .unwrap();
+            assert!(outcome.is_success());
+
+            let lines = connector.conn.read_all_lines().await.unwrap();
+            assert!(lines.contains(&"PRIVMSG #museun :/ban bar\r\n".to_string()));
+            assert!(lines.contains(&"PRIVMSG #museun :/delete abc\r\n".to_string()));
+
+            // and the messages read while waiting aren't lost
+            loop {
+                match runner.next_message().await.unwrap() {
+                    crate::Status::Message(crate::messages::Commands::Privmsg(..)) => break,
+                    crate::Status::Message(..) => continue,
+                    status => panic!("unexpected status: {:?}", status),
+                }
+            }
+
+            // the connection ended before a reply
+            assert!(runner
+                .moderate(commands::timeout("#museun", "foo", None, None))
+                .await
+                .is_err());
+        });
+    }
+
+    #[test]
+    fn keeps_the_order() {
+        let config = UserConfig::builder().anonymous().build().unwrap();
+        futures_lite::future::block_on(async {
+            let connector = TestConnector::default();
+            connector
+                .conn
+                .write_data(format!(
+                    "{}\
+                     :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n\
+                     @msg-id=ban_success :tmi.twitch.tv NOTICE #museun :bar is now banned from this channel.\r\n\
+                     :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :world\r\n\
+                     @msg-id=delete_message_success :tmi.twitch.tv NOTICE #museun :The message from baz is now deleted.\r\n",
+                    REGISTER
+                ))
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector, &config).await.unwrap();
+            let outcome = runner
+                .moderate(commands::delete("#museun", "abc"))
+                .await
+                .unwrap();
+            assert!(outcome.is_success());
+
+            // the reply is one of the messages read while deleting
+            let outcome = runner
+                .moderate(commands::ban("#museun", "bar", None))
+                .await
+                .unwrap();
+            assert!(outcome.is_success());
+
+            let mut seen = vec![];
+            while let Ok(crate::Status::Message(msg)) = runner.next_message().await {
+                if let crate::messages::Commands::Privmsg(msg) = msg {
+                    seen.push(msg.data().to_string());
+                }
+            }
+            assert_eq!(seen, vec!["hello", "world"]);
+        });
+    }
+
+    #[test]
+    fn replayed_no_reply() {
+        use super::super::replay::{EventKind, RecordedEvent, Recorder, Replay};
+        use std::time::Duration;
+
+        let config = UserConfig::builder().anonymous().build().unwrap();
+        futures_lite::future::block_on(async {
+            // the registration, from a real session
+            let connector = TestConnector::default();
+            connector.conn.write_data(REGISTER).await;
+            let recorder = Recorder::new();
+            let mut runner = AsyncRunner::connect_recorded(connector, &config, &recorder)
+                .await
+                .unwrap();
+            while let Ok(crate::Status::Message(..)) = runner.next_message().await {}
+
+            // and then a reply that came too late
+            let mut recording = recorder.recording();
+            let events = vec![
+                (1_000, EventKind::Sent("PRIVMSG #museun :/ban bar".to_string())),
+                (
+                    12_000,
+                    EventKind::Received(
+                        ":museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello".to_string(),
+                    ),
+                ),
+                (
+                    12_100,
+                    EventKind::Received(
+                        "@msg-id=ban_success :tmi.twitch.tv NOTICE #museun :bar is now banned from this channel.".to_string(),
+                    ),
+                ),
+            ];
+            recording
+                .events
+                .extend(events.into_iter().map(|(at, kind)| RecordedEvent {
+                    at: Duration::from_millis(at),
+                    kind,
+                }));
+
+            // the replay doesn't take 10 seconds, but its clock does
+            let replay = Replay::new(&recording);
+            let mut runner = AsyncRunner::connect_replay(&replay, &config).await.unwrap();
+            let outcome = runner
+                .moderate(commands::ban("museun", "bar", None))
+                .await
+                .unwrap();
+            assert_eq!(outcome, ModerationOutcome::NoReply);
+
+            let mut seen = vec![];
+            while let Ok(crate::Status::Message(msg)) = runner.next_message().await {
+                seen.push(msg.raw().to_string());
+            }
+            assert!(seen[seen.len() - 2].ends_with(":hello\r\n"));
+            assert!(seen[seen.len() - 1].contains("ban_success"));
+            assert_eq!(replay.divergences(), vec![]);
+        });
+    }
+}
@@ -0,0 +1,231 @@
+use super::{rejection::REJECTION_WINDOW, SendRejected};
+use crate::messages::Commands;
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// A chat message sent with a `client-nonce`, see [NonceMap]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingSend {
+    /// The nonce it was sent with
+    pub nonce: String,
+    /// The channel it was sent to
+    pub channel: String,
+    /// When it was sent
+    pub sent: Instant,
+}
+
+/// What Twitch replied to a sent message, see [Correlation]
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SendOutcome {
+    /// Twitch echoed the message with its nonce
+    Echoed,
+    /// Twitch rejected the message with a `NOTICE`
+    Rejected(SendRejected),
+}
+
+/// A reply from Twitch matched with the message it is about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Correlation {
+    /// The nonce the message was sent with
+    pub nonce: String,
+    /// The channel it was sent to
+    pub channel: String,
+    /// How long after sending the message the reply arrived
+    pub elapsed: Duration,
+    /// What Twitch replied
+    pub outcome: SendOutcome,
+}
+
+/// Matches sent chat messages with the replies of Twitch, by their `client-nonce`.
+///
+/// Messages sent [with a nonce](crate::commands::with_nonce) are recorded with
+/// [NonceMap::sent]. An echo of the message carries its nonce, but the notices
+/// rejecting a message don't, so those are matched with the newest pending
+/// message of their channel that was sent within the window.
+///
+/// ```
+/// # use twitchchat::{FromIrcMessage as _, commands, messages::Commands, runner::{NonceMap, SendOutcome}};
+/// let parse = |input| {
+///     let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+///     Commands::from_irc(msg).unwrap()
+/// };
+///
+/// let mut nonces = NonceMap::new();
+/// let nonce = nonces.next_nonce();
+///
+/// let mut out = vec![];
+/// twitchchat::Encoder::new(&mut out).encode(commands::with_nonce(&nonce, commands::privmsg("#museun", "hello")))?;
+/// nonces.sent(&nonce, "#museun");
+///
+/// let notice = parse("@msg-id=msg_emoteonly :tmi.twitch.tv NOTICE #museun :This room is in emote-only mode.\r\n");
+/// let correlation = nonces.observe(&notice).unwrap();
+/// assert_eq!(correlation.nonce, nonce);
+/// assert!(matches!(correlation.outcome, SendOutcome::Rejected(..)));
+/// assert_eq!(nonces.pending().count(), 0);
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct NonceMap {
+    window: Duration,
+    pending: VecDeque<PendingSend>,
+}
+
+impl Default for NonceMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NonceMap {
+    /// Create a new map, correlating rejections with messages sent up to 10 seconds before
+    pub fn new() -> Self {
+        Self::with_window(REJECTION_WINDOW)
+    }
+
+    /// Create a new map, correlating rejections with messages sent up to `window` before
+    pub fn with_window(window: Duration) -> Self {
+        Self {
+            window,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Make a nonce that is unique in this process, and unlikely to be used by another one
+    pub fn next_nonce(&self) -> String {
+        crate::util::nonce()
+    }
+
+    /// Record a message sent now with this nonce to this channel
+    pub fn sent(&mut self, nonce: &str, channel: &str) {
+        self.sent_at(nonce, channel, Instant::now())
+    }
+
+    /// Record a message sent at `sent` with this nonce to this channel
+    pub fn sent_at(&mut self, nonce: &str, channel: &str, sent: Instant) {
+        self.pending.push_back(PendingSend {
+            nonce: nonce.to_string(),
+            channel: normalize(channel),
+            sent,
+        });
+    }
+
+    /// Match this message with a pending send, now.
+    ///
+    /// A matched send is no longer pending
+    pub fn observe(&mut self, msg: &Commands<'_>) -> Option<Correlation> {
+        self.observe_at(msg, Instant::now())
+    }
+
+    /// Match this message, received at `now`, with a pending send.
+    ///
+    /// A matched send is no longer pending
+    pub fn observe_at(&mut self, msg: &Commands<'_>, now: Instant) -> Option<Correlation> {
+        let (index, outcome) = match msg {
+            Commands::Privmsg(msg) => {
+                let nonce = msg.client_nonce()?;
+                let index = self.pending.iter().position(|send| send.nonce == nonce)?;
+                (index, SendOutcome::Echoed)
+            }
+            Commands::Notice(msg) => {
+                let rejected = SendRejected::from_notice(msg)?;
+                let channel = normalize(&rejected.channel);
+                let window = self.window;
+                let index = self.pending.iter().rposition(|send| {
+                    send.channel == channel && now.saturating_duration_since(send.sent) < window
+                })?;
+                (index, SendOutcome::Rejected(rejected))
+            }
+            _ => return None,
+        };
+
+        let send = self.pending.remove(index)?;
+        Some(Correlation {
+            elapsed: now.saturating_duration_since(send.sent),
+            nonce: send.nonce,
+            channel: send.channel,
+            outcome,
+        })
+    }
+
+    /// The sends that haven't been matched yet, oldest first
+    pub fn pending(&self) -> impl Iterator<Item = &PendingSend> + '_ {
+        self.pending.iter()
+    }
+
+    /// Forget the sends that are older than the window at `now`
+    ///
+    /// This returns how many were forgotten
+    pub fn prune(&mut self, now: Instant) -> usize {
+        let len = self.pending.len();
+        let window = self.window;
+        self.pending
+            .retain(|send| now.saturating_duration_since(send.sent) < window);
+        len - self.pending.len()
+    }
+}
+
+fn normalize(channel: &str) -> String {
+    let channel = channel.to_ascii_lowercase();
+    match channel.starts_with('#') {
+        true => channel,
+        false => format!("#{}", channel),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{irc::parse, FromIrcMessage as _};
+
+    fn commands(input: &str) -> Commands<'_> {
+        Commands::from_irc(parse(input).next().unwrap().unwrap()).unwrap()
+    }
+
+    const SLOW: &str = "@msg-id=msg_slowmode :tmi.twitch.tv NOTICE #museun :This room is in slow mode \
+                        and you are sending messages too quickly. You will be able to talk again in 7 seconds.\r\n";
+
+    #[test]
+    fn correlation() {
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+
+        let mut nonces = NonceMap::new();
+        nonces.sent_at("a", "museun", secs(0));
+        nonces.sent_at("b", "#museun", secs(1));
+        nonces.sent_at("c", "#other", secs(2));
+
+        // the newest one on the channel
+        let correlation = nonces.observe_at(&commands(SLOW), secs(3)).unwrap();
+        assert_eq!(correlation.nonce, "b");
+        assert_eq!(correlation.elapsed, Duration::from_secs(2));
+        match correlation.outcome {
+            SendOutcome::Rejected(rejected) => {
+                assert_eq!(rejected.retry_after, Some(Duration::from_secs(7)))
+            }
+            outcome => panic!("unexpected outcome: {:?}", outcome),
+        }
+
+        let echo = commands("@client-nonce=c :foo!foo@foo PRIVMSG #other :hello\r\n");
+        let correlation = nonces.observe_at(&echo, secs(3)).unwrap();
+        assert_eq!(correlation.channel, "#other");
+        assert_eq!(correlation.outcome, SendOutcome::Echoed);
+        assert!(nonces.observe_at(&echo, secs(3)).is_none());
+
+        // too late for "a"
+        assert!(nonces.observe_at(&commands(SLOW), secs(10)).is_none());
+        assert_eq!(nonces.pending().count(), 1);
+        assert_eq!(nonces.prune(secs(10)), 1);
+
+        let other = commands(":foo!foo@foo PRIVMSG #museun :no nonce\r\n");
+        assert!(nonces.observe(&other).is_none());
+    }
+
+    #[test]
+    fn unique_nonces() {
+        let nonces = NonceMap::new();
+        assert_ne!(nonces.next_nonce(), nonces.next_nonce());
+    }
+}
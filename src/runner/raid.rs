@@ -0,0 +1,201 @@
+use crate::messages::MessageId;
+#[cfg(feature = "usernotice")]
+use std::time::Duration;
+
+// how long Twitch counts down before a raid lands, with some slack
+#[cfg(feature = "usernotice")]
+pub(crate) const RAID_WINDOW: Duration = Duration::from_secs(100);
+
+/// What came of a [raid](crate::commands::raid) or an [unraid](crate::commands::unraid).
+///
+/// See [AsyncRunner::raid](crate::AsyncRunner::raid) and [AsyncRunner::unraid](crate::AsyncRunner::unraid)
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum RaidOutcome {
+    /// The raid landed on the target channel (the `raid` `USERNOTICE` was seen there)
+    Landed,
+    /// Twitch didn't reject the raid, but it hasn't been seen landing.
+    ///
+    /// This is the usual outcome when you haven't joined the target channel.
+    Pending,
+    /// The raid was cancelled (`unraid_success`)
+    Cancelled,
+    /// A raid is already in progress (`raid_error_already_raiding`)
+    AlreadyRaiding,
+    /// The target channel can't be raided (`raid_error_forbidden`, `raid_error_self`)
+    Forbidden,
+    /// The target channel can't take that many viewers (`raid_error_too_many_viewers`)
+    TooManyViewers,
+    /// There is no raid to cancel (`unraid_error_no_active_raid`)
+    NoActiveRaid,
+    /// Twitch failed, it says to try again in a minute (`raid_error_unexpected`, `unraid_error_unexpected`)
+    Failed,
+    /// You aren't allowed to raid from the channel (`no_permission`)
+    NoPermission,
+    /// Twitch doesn't support the command (`unrecognized_cmd`)
+    Unsupported,
+    /// Twitch didn't reply in time
+    NoReply,
+}
+
+impl RaidOutcome {
+    /// The outcome a notice with this message id reports, if it is about raids
+    ///
+    /// `raid_notice_mature` and `raid_notice_restricted_chat` only warn about the
+    /// target channel, so they aren't an outcome.
+    ///
+    /// ```
+    /// # use twitchchat::{messages::MessageId, runner::RaidOutcome};
+    /// let outcome = RaidOutcome::from_msg_id(&MessageId::RaidErrorSelf);
+    /// assert_eq!(outcome, Some(RaidOutcome::Forbidden));
+    ///
+    /// assert_eq!(RaidOutcome::from_msg_id(&MessageId::RaidNoticeMature), None);
+    /// ```
+    pub fn from_msg_id(msg_id: &MessageId<'_>) -> Option<Self> {
+        let outcome = match msg_id {
+            MessageId::UnraidSuccess => Self::Cancelled,
+            MessageId::RaidErrorAlreadyRaiding => Self::AlreadyRaiding,
+            MessageId::RaidErrorForbidden | MessageId::RaidErrorSelf => Self::Forbidden,
+            MessageId::RaidErrorTooManyViewers => Self::TooManyViewers,
+            MessageId::UnraidErrorNoActiveRaid => Self::NoActiveRaid,
+            MessageId::RaidErrorUnexpected | MessageId::UnraidErrorUnexpected => Self::Failed,
+            MessageId::NoPermission => Self::NoPermission,
+            MessageId::UnrecognizedCmd => Self::Unsupported,
+            _ => return None,
+        };
+        Some(outcome)
+    }
+
+    /// Whether the raid landed, or was cancelled for an unraid
+    pub fn is_success(self) -> bool {
+        matches!(self, Self::Landed | Self::Cancelled)
+    }
+
+    // the outcome of a `/raid` this reply reports
+    #[cfg(feature = "usernotice")]
+    pub(crate) fn of_raid(msg_id: &MessageId<'_>) -> Option<Self> {
+        match msg_id {
+            MessageId::UnraidSuccess
+            | MessageId::UnraidErrorNoActiveRaid
+            | MessageId::UnraidErrorUnexpected => None,
+            msg_id => Self::from_msg_id(msg_id),
+        }
+    }
+
+    // the outcome of an `/unraid` this reply reports
+    pub(crate) fn of_unraid(msg_id: &MessageId<'_>) -> Option<Self> {
+        match msg_id {
+            MessageId::UnraidSuccess
+            | MessageId::UnraidErrorNoActiveRaid
+            | MessageId::UnraidErrorUnexpected
+            | MessageId::NoPermission
+            | MessageId::UnrecognizedCmd => Self::from_msg_id(msg_id),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::{
+        commands, messages::Commands, test::TestConnector, AsyncRunner, Status, UserConfig,
+    };
+
+    const REGISTER: &str = ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                            :tmi.twitch.tv 376 justinfan1234 :>\r\n";
+
+    #[test]
+    fn raid_and_unraid() {
+        let config = UserConfig::builder().anonymous().build().unwrap();
+        futures_lite::future::block_on(async {
+            let connector = TestConnector::default();
+            connector
+                .conn
+                .write_data(format!(
+                    "{}\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #shaken_bot\r\n\
+                     @msg-id=raid_notice_mature :tmi.twitch.tv NOTICE #museun :This channel is intended for mature audiences.\r\n\
+                     @msg-id=raid;msg-param-login=someone :tmi.twitch.tv USERNOTICE #shaken_bot\r\n\
+                     @msg-id=raid;msg-param-login=museun;msg-param-viewerCount=42 :tmi.twitch.tv USERNOTICE #shaken_bot\r\n\
+                     @msg-id=unraid_error_no_active_raid :tmi.twitch.tv NOTICE #museun :You do not have an active raid.\r\n\
+                     @msg-id=raid_error_self :tmi.twitch.tv NOTICE #museun :A channel cannot raid itself.\r\n",
+                    REGISTER
+                ))
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector.clone(), &config)
+                .await
+                .unwrap();
+            runner.join("#shaken_bot").await.unwrap();
+
+            // the warning and the raid from another channel are skipped
+            let outcome = runner
+                .raid(commands::raid("museun", "shaken_bot"))
+                .await
+                .unwrap();
+            assert_eq!(outcome, RaidOutcome::Landed);
+
+            let outcome = runner.unraid(commands::unraid("museun")).await.unwrap();
+            assert_eq!(outcome, RaidOutcome::NoActiveRaid);
+
+            let outcome = runner
+                .raid(commands::raid("museun", "museun"))
+                .await
+                .unwrap();
+            assert_eq!(outcome, RaidOutcome::Forbidden);
+
+            let lines = connector.conn.read_all_lines().await.unwrap();
+            assert!(lines.contains(&"PRIVMSG #museun :/raid #shaken_bot\r\n".to_string()));
+            assert!(lines.contains(&"PRIVMSG #museun :/unraid\r\n".to_string()));
+
+            // the messages read while waiting aren't lost
+            let mut raids = 0;
+            while let Ok(Status::Message(msg)) = runner.next_message().await {
+                if let Commands::UserNotice(..) = msg {
+                    raids += 1;
+                }
+            }
+            assert_eq!(raids, 2);
+        });
+    }
+
+    #[test]
+    fn keeps_the_order() {
+        let config = UserConfig::builder().anonymous().build().unwrap();
+        futures_lite::future::block_on(async {
+            let connector = TestConnector::default();
+            connector
+                .conn
+                .write_data(format!(
+                    "{}\
+                     :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n\
+                     @msg-id=unraid_error_no_active_raid :tmi.twitch.tv NOTICE #museun :You do not have an active raid.\r\n\
+                     :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :world\r\n\
+                     @msg-id=raid_error_self :tmi.twitch.tv NOTICE #museun :A channel cannot raid itself.\r\n",
+                    REGISTER
+                ))
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector, &config).await.unwrap();
+            let outcome = runner
+                .raid(commands::raid("museun", "museun"))
+                .await
+                .unwrap();
+            assert_eq!(outcome, RaidOutcome::Forbidden);
+
+            // the reply is one of the messages read while raiding
+            let outcome = runner.unraid(commands::unraid("museun")).await.unwrap();
+            assert_eq!(outcome, RaidOutcome::NoActiveRaid);
+
+            let mut seen = vec![];
+            while let Ok(Status::Message(msg)) = runner.next_message().await {
+                if let Commands::Privmsg(msg) = msg {
+                    seen.push(msg.data().to_string());
+                }
+            }
+            assert_eq!(seen, vec!["hello", "world"]);
+        });
+    }
+}
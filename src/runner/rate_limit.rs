@@ -1,10 +1,15 @@
 use crate::rate_limit::{RateClass, RateLimit};
 use futures_lite::{AsyncWrite, AsyncWriteExt};
-use std::{collections::VecDeque, time::Duration};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
 
 pub struct RateLimitedEncoder {
     pub(crate) rate_limit: RateLimit,
     pub(crate) queue: VecDeque<Box<[u8]>>,
+    // the last message written, to correlate rejections with
+    pub(crate) last_sent: Option<(Box<[u8]>, Instant)>,
 }
 
 impl RateLimitedEncoder {
@@ -13,12 +18,13 @@ impl RateLimitedEncoder {
         name: &str,
         limit: &mut u64,
         sink: &mut W,
+        now: Instant,
     ) -> std::io::Result<()>
     where
         W: AsyncWrite + Send + Sync + Unpin + ?Sized,
     {
         while let Some(data) = self.queue.pop_front() {
-            match self.rate_limit.consume(1) {
+            match self.rate_limit.consume_at(1, now) {
                 Ok(..) => {
                     *limit = limit.saturating_sub(1);
                     log::trace!(
@@ -27,6 +33,7 @@ impl RateLimitedEncoder {
                         std::str::from_utf8(&*data).unwrap().escape_debug()
                     );
                     sink.write_all(&*data).await?;
+                    self.last_sent.replace((data, now));
                 }
                 Err(..) => {
                     log::warn!(
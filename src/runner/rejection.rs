@@ -0,0 +1,148 @@
+use crate::messages::{MessageId, Notice};
+use std::time::Duration;
+
+// how long after a message was sent a rejection is still correlated with it
+pub(crate) const REJECTION_WINDOW: Duration = Duration::from_secs(10);
+
+/// Why Twitch didn't send a chat message, see [SendRejected]
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum RejectReason {
+    /// The room is in emote-only mode (`msg_emoteonly`)
+    EmoteOnly,
+    /// The room is in followers-only mode, and you don't follow the channel,
+    /// or haven't followed it for long enough (`msg_followersonly*`)
+    FollowersOnly,
+    /// The room is in subscribers-only mode (`msg_subsonly`)
+    SubsOnly,
+    /// The room is in slow mode and you sent messages too quickly (`msg_slowmode`)
+    SlowMode,
+    /// You are banned from talking in the room (`msg_banned`)
+    Banned,
+}
+
+impl RejectReason {
+    /// The reason of a notice with this message id, if it rejected a message
+    pub fn from_msg_id(msg_id: &MessageId<'_>) -> Option<Self> {
+        let reason = match msg_id {
+            MessageId::MsgEmoteonly => Self::EmoteOnly,
+            MessageId::MsgFollowersonly
+            | MessageId::MsgFollowersonlyFollowed
+            | MessageId::MsgFollowersonlyZero => Self::FollowersOnly,
+            MessageId::MsgSubsonly => Self::SubsOnly,
+            MessageId::MsgSlowmode => Self::SlowMode,
+            MessageId::MsgBanned => Self::Banned,
+            _ => return None,
+        };
+        Some(reason)
+    }
+
+    /// Whether sending the same message again can work without any changes to
+    /// the room or your account, after [SendRejected::retry_after]
+    pub fn is_retryable(self) -> bool {
+        matches!(self, Self::SlowMode)
+    }
+}
+
+/// A chat message that Twitch didn't send, from the `NOTICE` it replied with.
+///
+/// See [AsyncRunner::send_rejections](crate::AsyncRunner::send_rejections)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct SendRejected {
+    /// The channel the message was sent to
+    pub channel: String,
+    /// Why it was rejected
+    pub reason: RejectReason,
+    /// The text of the notice
+    pub notice: String,
+    /// How long until you can send a message again, if the notice says so
+    pub retry_after: Option<Duration>,
+    /// The line that was rejected, without its `\r\n`, if it was sent recently
+    /// enough to be correlated with the notice
+    pub message: Option<String>,
+}
+
+impl SendRejected {
+    /// Get the rejection from this notice, if it rejected a message.
+    ///
+    /// This isn't correlated with a sent message
+    ///
+    /// ```
+    /// # use twitchchat::{FromIrcMessage as _, messages::Notice, runner::{RejectReason, SendRejected}};
+    /// # use std::time::Duration;
+    /// let input = "@msg-id=msg_slowmode :tmi.twitch.tv NOTICE #museun :This room is in slow mode \
+    ///              and you are sending messages too quickly. You will be able to talk again in 7 seconds.\r\n";
+    /// let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+    ///
+    /// let rejected = SendRejected::from_notice(&Notice::from_irc(msg).unwrap()).unwrap();
+    /// assert_eq!(rejected.reason, RejectReason::SlowMode);
+    /// assert_eq!(rejected.retry_after, Some(Duration::from_secs(7)));
+    /// ```
+    pub fn from_notice(msg: &Notice<'_>) -> Option<Self> {
+        let reason = RejectReason::from_msg_id(&msg.msg_id()?)?;
+        Some(Self {
+            channel: msg.channel().to_string(),
+            reason,
+            notice: msg.message().to_string(),
+            retry_after: find_seconds(msg.message()),
+            message: None,
+        })
+    }
+}
+
+// e.g. "You will be able to talk again in 7 seconds."
+fn find_seconds(text: &str) -> Option<Duration> {
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    words.windows(2).find_map(|pair| match pair {
+        [n, unit] if unit.starts_with("second") => n.parse().ok().map(Duration::from_secs),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{irc::parse, FromIrcMessage as _};
+
+    fn rejections(input: &str) -> Vec<Option<SendRejected>> {
+        parse(input)
+            .map(|msg| SendRejected::from_notice(&Notice::from_irc(msg.unwrap()).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn reasons() {
+        let rejections = rejections(
+            "@msg-id=msg_emoteonly :tmi.twitch.tv NOTICE #museun :This room is in emote-only mode.\r\n\
+             @msg-id=msg_followersonly_zero :tmi.twitch.tv NOTICE #museun :This room is in followers-only mode.\r\n\
+             @msg-id=msg_subsonly :tmi.twitch.tv NOTICE #museun :This room is in subscribers-only mode.\r\n\
+             @msg-id=msg_banned :tmi.twitch.tv NOTICE #museun :You are permanently banned from talking in museun.\r\n\
+             @msg-id=slow_on :tmi.twitch.tv NOTICE #museun :This room is now in slow mode.\r\n\
+             :tmi.twitch.tv NOTICE * :Login unsuccessful\r\n",
+        );
+
+        let reasons = rejections
+            .iter()
+            .map(|r| r.as_ref().map(|r| r.reason))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            vec![
+                Some(RejectReason::EmoteOnly),
+                Some(RejectReason::FollowersOnly),
+                Some(RejectReason::SubsOnly),
+                Some(RejectReason::Banned),
+                None,
+                None,
+            ]
+        );
+
+        let emote_only = rejections[0].as_ref().unwrap();
+        assert_eq!(emote_only.channel, "#museun");
+        assert_eq!(emote_only.notice, "This room is in emote-only mode.");
+        assert_eq!(emote_only.retry_after, None);
+        assert!(!emote_only.reason.is_retryable());
+    }
+}
@@ -0,0 +1,792 @@
+use crate::connector::Connector;
+use futures_lite::{AsyncRead, AsyncWrite};
+use std::{
+    future::Future,
+    io::Result as IoResult,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const HEADER: &str = "twitchchat-recording 1";
+
+/// Something that happened on a recorded connection, see [Recording]
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventKind {
+    /// A line was received, without its `\r\n`
+    Received(String),
+    /// A line was sent, without its `\r\n`
+    Sent(String),
+    /// The idle timer of the runner fired
+    Timer,
+}
+
+/// An event of a [Recording]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedEvent {
+    /// When it happened, since the recording started (in whole milliseconds)
+    pub at: Duration,
+    /// What happened
+    pub kind: EventKind,
+}
+
+/// Everything that happened on the connection of a runner, in order.
+///
+/// This is a single text artifact: use its `Display` impl to save it and its
+/// `FromStr` impl to load it again. A bug report can attach it, and the bug
+/// can be reproduced with a [Replay] without a connection to Twitch.
+///
+/// ```
+/// # use twitchchat::runner::{EventKind, Recording};
+/// # use std::time::Duration;
+/// let recording: Recording = "twitchchat-recording 1 1601079032426\n\
+///                             0 > NICK justinfan1234\n\
+///                             15 < :tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\n\
+///                             45015 ! timer\n"
+///     .parse()?;
+///
+/// assert_eq!(recording.started, 1601079032426);
+/// assert_eq!(recording.events[2].at, Duration::from_secs(45) + Duration::from_millis(15));
+/// assert_eq!(recording.events[2].kind, EventKind::Timer);
+/// # Ok::<_, twitchchat::runner::RecordingError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    /// When the recording started, in milliseconds since the unix epoch
+    pub started: u64,
+    /// What happened, in order
+    pub events: Vec<RecordedEvent>,
+}
+
+impl std::fmt::Display for Recording {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} {}", HEADER, self.started)?;
+        for event in &self.events {
+            let at = event.at.as_millis();
+            match &event.kind {
+                EventKind::Received(line) => writeln!(f, "{} < {}", at, line)?,
+                EventKind::Sent(line) => writeln!(f, "{} > {}", at, line)?,
+                EventKind::Timer => writeln!(f, "{} ! timer", at)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Recording {
+    type Err = RecordingError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut lines = input.lines();
+        let started = lines
+            .next()
+            .and_then(|line| line.strip_prefix(HEADER))
+            .and_then(|started| started.trim().parse().ok())
+            .ok_or(RecordingError::InvalidHeader)?;
+
+        let events = lines
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(pos, line)| {
+                parse_event(line).ok_or(RecordingError::InvalidEvent { line: pos + 2 })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { started, events })
+    }
+}
+
+fn parse_event(line: &str) -> Option<RecordedEvent> {
+    let (at, rest) = line.split_once(' ')?;
+    let at = Duration::from_millis(at.parse().ok()?);
+    let kind = match rest.split_once(' ')? {
+        ("<", line) => EventKind::Received(line.to_string()),
+        (">", line) => EventKind::Sent(line.to_string()),
+        ("!", "timer") => EventKind::Timer,
+        _ => return None,
+    };
+    Some(RecordedEvent { at, kind })
+}
+
+/// An error returned when parsing a [Recording]
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordingError {
+    /// The first line isn't a recording header
+    InvalidHeader,
+    /// This line isn't an event
+    InvalidEvent {
+        /// The line number, starting at 1
+        line: usize,
+    },
+}
+
+impl std::fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidHeader => write!(f, "missing the recording header"),
+            Self::InvalidEvent { line } => write!(f, "invalid event on line {}", line),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+// the clock of a recording or a replay only advances with the events that the
+// runner reacts to, so its timeouts happen at the same events both times
+#[derive(Debug)]
+struct Timeline {
+    started: u64,
+    base: Instant,
+    now: Duration,
+}
+
+impl Timeline {
+    fn now(&self) -> Instant {
+        self.base + self.now
+    }
+
+    fn unix_timestamp(&self) -> u64 {
+        (self.started + self.now.as_millis() as u64) / 1000
+    }
+}
+
+#[derive(Debug)]
+struct RecorderState {
+    recording: Recording,
+    timeline: Timeline,
+    received: Vec<u8>,
+    sent: Vec<u8>,
+}
+
+impl RecorderState {
+    fn push(&mut self, kind: EventKind) {
+        let at = self.timeline.base.elapsed();
+        // events are saved in whole milliseconds, so the replay sees the same times
+        let at = Duration::from_millis(at.as_millis() as u64);
+        if !matches!(kind, EventKind::Sent(..)) {
+            self.timeline.now = at;
+        }
+        self.recording.events.push(RecordedEvent { at, kind });
+    }
+}
+
+/// Records a runner session, see [AsyncRunner::connect_recorded](crate::AsyncRunner::connect_recorded).
+///
+/// This is a cheap handle, clones of it record into the same [Recording].
+/// Only the first connection is recorded, so don't use it to reauthenticate.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    state: Arc<Mutex<RecorderState>>,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Recorder {
+    /// Start a new recording
+    pub fn new() -> Self {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|dur| dur.as_millis() as u64)
+            .unwrap_or_default();
+
+        let state = RecorderState {
+            recording: Recording {
+                started,
+                events: vec![],
+            },
+            timeline: Timeline {
+                started,
+                base: Instant::now(),
+                now: Duration::default(),
+            },
+            received: vec![],
+            sent: vec![],
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Wrap this connector, so everything read or written on its connection is recorded
+    pub fn connector<C>(&self, connector: C) -> RecordingConnector<C> {
+        RecordingConnector {
+            connector,
+            recorder: self.clone(),
+        }
+    }
+
+    /// Get what was recorded so far
+    pub fn recording(&self) -> Recording {
+        self.lock().recording.clone()
+    }
+
+    pub(crate) fn timer(&self) {
+        self.lock().push(EventKind::Timer)
+    }
+
+    pub(crate) fn now(&self) -> Instant {
+        self.lock().timeline.now()
+    }
+
+    pub(crate) fn unix_timestamp(&self) -> u64 {
+        self.lock().timeline.unix_timestamp()
+    }
+
+    fn received(&self, data: &[u8]) {
+        let mut state = self.lock();
+        state.received.extend_from_slice(data);
+        while let Some(line) = take_line(&mut state.received) {
+            state.push(EventKind::Received(line))
+        }
+    }
+
+    fn sent(&self, data: &[u8]) {
+        let mut state = self.lock();
+        state.sent.extend_from_slice(data);
+        while let Some(line) = take_line(&mut state.sent) {
+            state.push(EventKind::Sent(line))
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RecorderState> {
+        // every event is pushed whole, so a poisoned lock is still usable
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// removes the first complete line from the buffer, without its `\r\n`
+fn take_line(buf: &mut Vec<u8>) -> Option<String> {
+    let pos = buf.windows(2).position(|w| w == b"\r\n")?;
+    let line = String::from_utf8_lossy(&buf[..pos]).into_owned();
+    buf.drain(..pos + 2);
+    Some(line)
+}
+
+/// A [Connector] that records its connection, see [Recorder]
+#[derive(Debug, Clone)]
+pub struct RecordingConnector<C> {
+    connector: C,
+    recorder: Recorder,
+}
+
+impl<C: Connector> Connector for RecordingConnector<C> {
+    type Output = RecordingStream<C::Output>;
+
+    fn connect(&mut self) -> crate::BoxedFuture<IoResult<Self::Output>> {
+        let fut = self.connector.connect();
+        let recorder = self.recorder.clone();
+        Box::pin(async move {
+            let stream = fut.await?;
+            Ok(RecordingStream {
+                stream: async_dup::Mutex::new(stream),
+                recorder,
+            })
+        })
+    }
+}
+
+/// The connection of a [RecordingConnector]
+#[derive(Debug)]
+pub struct RecordingStream<S> {
+    // locked, because the runner reads and writes through shared references
+    stream: async_dup::Mutex<S>,
+    recorder: Recorder,
+}
+
+macro_rules! recording_stream {
+    ($($ty:ty)*) => {
+        $(
+        impl<S> AsyncRead for $ty
+        where
+            S: AsyncRead + Unpin,
+        {
+            fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<IoResult<usize>> {
+                let this = &*self;
+                let n = futures_lite::ready!(Pin::new(&mut &this.stream).poll_read(cx, buf))?;
+                this.recorder.received(&buf[..n]);
+                Poll::Ready(Ok(n))
+            }
+        }
+
+        impl<S> AsyncWrite for $ty
+        where
+            S: AsyncWrite + Unpin,
+        {
+            fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<IoResult<usize>> {
+                let this = &*self;
+                let n = futures_lite::ready!(Pin::new(&mut &this.stream).poll_write(cx, buf))?;
+                this.recorder.sent(&buf[..n]);
+                Poll::Ready(Ok(n))
+            }
+
+            fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+                Pin::new(&mut &self.stream).poll_flush(cx)
+            }
+
+            fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+                Pin::new(&mut &self.stream).poll_close(cx)
+            }
+        }
+        )*
+    };
+}
+
+recording_stream! {
+    RecordingStream<S>
+    &RecordingStream<S>
+}
+
+/// A line the runner sent during a [Replay] that isn't the one it sent in the [Recording]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Which sent line this is, starting at 0
+    pub index: usize,
+    /// The line that was recorded, if there was one
+    pub expected: Option<String>,
+    /// The line that was sent now
+    pub actual: String,
+}
+
+#[derive(Debug)]
+struct ReplayState {
+    events: Vec<RecordedEvent>,
+    // the next received line or timer event
+    cursor: usize,
+    // how much of the current line was read
+    offset: usize,
+    expected: Vec<String>,
+    sent: Vec<u8>,
+    sent_lines: usize,
+    divergences: Vec<Divergence>,
+    timeline: Timeline,
+    read_waker: Option<Waker>,
+    timer_waker: Option<Waker>,
+}
+
+impl ReplayState {
+    // skips over the sent lines, those are checked when the runner writes
+    fn peek(&mut self) -> Option<&RecordedEvent> {
+        while let Some(RecordedEvent {
+            kind: EventKind::Sent(..),
+            ..
+        }) = self.events.get(self.cursor)
+        {
+            self.cursor += 1;
+        }
+        self.events.get(self.cursor)
+    }
+
+    fn advance(&mut self, at: Duration) {
+        self.cursor += 1;
+        self.offset = 0;
+        self.timeline.now = at;
+    }
+
+    fn poll_read(&mut self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<IoResult<usize>> {
+        let (at, line) = match self.peek() {
+            None => return Poll::Ready(Ok(0)),
+            Some(RecordedEvent {
+                at,
+                kind: EventKind::Received(line),
+            }) => (*at, format!("{}\r\n", line)),
+            Some(..) => {
+                // a timer comes first
+                self.read_waker.replace(cx.waker().clone());
+                if let Some(waker) = self.timer_waker.take() {
+                    waker.wake()
+                }
+                return Poll::Pending;
+            }
+        };
+
+        let rest = &line.as_bytes()[self.offset..];
+        let n = rest.len().min(buf.len());
+        buf[..n].copy_from_slice(&rest[..n]);
+        self.offset += n;
+        if self.offset == line.len() {
+            self.advance(at);
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_timer(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        match self.peek() {
+            Some(RecordedEvent {
+                at,
+                kind: EventKind::Timer,
+            }) => {
+                let at = *at;
+                self.advance(at);
+                if let Some(waker) = self.read_waker.take() {
+                    waker.wake()
+                }
+                Poll::Ready(())
+            }
+            _ => {
+                self.timer_waker.replace(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        self.sent.extend_from_slice(data);
+        while let Some(actual) = take_line(&mut self.sent) {
+            let index = self.sent_lines;
+            let expected = self.expected.get(index);
+            if expected != Some(&actual) {
+                self.divergences.push(Divergence {
+                    index,
+                    expected: expected.cloned(),
+                    actual,
+                })
+            }
+            self.sent_lines += 1;
+        }
+    }
+}
+
+/// Replays a [Recording] to a runner, see [AsyncRunner::connect_replay](crate::AsyncRunner::connect_replay).
+///
+/// The runner reads the received lines in order, and its idle timer fires
+/// where it fired in the recording instead of after a delay. Its clock only
+/// moves with those events, so its timeouts happen at the same events as
+/// when it was recorded. The lines it sends are compared with the recorded
+/// ones, and the differences are its [divergences](Replay::divergences).
+///
+/// The replay is deterministic as long as the code using the runner is.
+/// Rate limiting still uses the real clock, so a session that was rate
+/// limited can send its messages earlier in the replay.
+///
+/// This is a cheap handle, clones of it share the same replay.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    state: Arc<Mutex<ReplayState>>,
+}
+
+impl Replay {
+    /// Create a replay of this recording
+    pub fn new(recording: &Recording) -> Self {
+        let expected = recording
+            .events
+            .iter()
+            .filter_map(|event| match &event.kind {
+                EventKind::Sent(line) => Some(line.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let state = ReplayState {
+            events: recording.events.clone(),
+            cursor: 0,
+            offset: 0,
+            expected,
+            sent: vec![],
+            sent_lines: 0,
+            divergences: vec![],
+            timeline: Timeline {
+                started: recording.started,
+                base: Instant::now(),
+                now: Duration::default(),
+            },
+            read_waker: None,
+            timer_waker: None,
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// A connector for the recorded connection, it can only be connected once
+    pub fn connector(&self) -> ReplayConnector {
+        ReplayConnector {
+            replay: self.clone(),
+        }
+    }
+
+    /// The sent lines that didn't match the recording, so far
+    pub fn divergences(&self) -> Vec<Divergence> {
+        self.lock().divergences.clone()
+    }
+
+    /// Whether every recorded event was replayed, and every recorded line was sent
+    pub fn is_finished(&self) -> bool {
+        let mut state = self.lock();
+        state.peek().is_none() && state.sent_lines >= state.expected.len()
+    }
+
+    pub(crate) fn timer(&self) -> ReplayTimer<'_> {
+        ReplayTimer { replay: self }
+    }
+
+    pub(crate) fn now(&self) -> Instant {
+        self.lock().timeline.now()
+    }
+
+    pub(crate) fn unix_timestamp(&self) -> u64 {
+        self.lock().timeline.unix_timestamp()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ReplayState> {
+        // the state is only changed by whole events, so a poisoned lock is still usable
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// resolves when the next event of the replay is the idle timer
+pub(crate) struct ReplayTimer<'a> {
+    replay: &'a Replay,
+}
+
+impl<'a> Future for ReplayTimer<'a> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.replay.lock().poll_timer(cx)
+    }
+}
+
+/// A [Connector] for a [Replay]
+#[derive(Debug, Clone)]
+pub struct ReplayConnector {
+    replay: Replay,
+}
+
+impl Connector for ReplayConnector {
+    type Output = ReplayStream;
+
+    fn connect(&mut self) -> crate::BoxedFuture<IoResult<Self::Output>> {
+        let replay = self.replay.clone();
+        Box::pin(async move { Ok(ReplayStream { replay }) })
+    }
+}
+
+/// The connection of a [ReplayConnector]
+#[derive(Debug)]
+pub struct ReplayStream {
+    replay: Replay,
+}
+
+macro_rules! replay_stream {
+    ($($ty:ty)*) => {
+        $(
+        impl AsyncRead for $ty {
+            fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<IoResult<usize>> {
+                self.replay.lock().poll_read(cx, buf)
+            }
+        }
+
+        impl AsyncWrite for $ty {
+            fn poll_write(self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<IoResult<usize>> {
+                self.replay.lock().write(buf);
+                Poll::Ready(Ok(buf.len()))
+            }
+
+            fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+                Poll::Ready(Ok(()))
+            }
+        }
+        )*
+    };
+}
+
+replay_stream! {
+    ReplayStream
+    &ReplayStream
+}
+
+// where the runner gets the time and its idle timer from
+#[derive(Debug, Clone)]
+pub(crate) enum Clock {
+    System,
+    Recording(Recorder),
+    Replay(Replay),
+}
+
+impl Clock {
+    pub(crate) fn now(&self) -> Instant {
+        match self {
+            Self::System => Instant::now(),
+            Self::Recording(recorder) => recorder.now(),
+            Self::Replay(replay) => replay.now(),
+        }
+    }
+
+    // seconds since the unix epoch, the token of the runner's PINGs
+    pub(crate) fn unix_timestamp(&self) -> u64 {
+        match self {
+            Self::System => crate::util::timestamp(),
+            Self::Recording(recorder) => recorder.unix_timestamp(),
+            Self::Replay(replay) => replay.unix_timestamp(),
+        }
+    }
+
+    pub(crate) async fn idle(&self) {
+        match self {
+            Self::Replay(replay) => replay.timer().await,
+            _ => super::timeout::next_delay().await,
+        }
+    }
+
+    // waits for `delay`, forever without one. a replay only advances on
+    // recorded events, so it never wakes up
+    pub(crate) async fn wait(&self, delay: Option<Duration>) {
+        match (self, delay) {
+            (Self::Replay(..), _) | (_, None) => futures_lite::future::pending().await,
+            (_, Some(delay)) => futures_timer::Delay::new(delay).await,
+        }
+    }
+
+    pub(crate) fn idle_fired(&self) {
+        if let Self::Recording(recorder) = self {
+            recorder.timer()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::{runner::Status, test::TestConnector, AsyncRunner, UserConfig};
+
+    const SESSION: &str = ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                           :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                           PING :1234\r\n\
+                           :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :!hello\r\n\
+                           :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :bye\r\n";
+
+    // a small bot: it answers `!hello`
+    async fn run(mut runner: AsyncRunner) -> Vec<String> {
+        let mut writer = runner.writer();
+        let mut seen = vec![];
+        while let Ok(Status::Message(msg)) = runner.next_message().await {
+            if let crate::messages::Commands::Privmsg(msg) = &msg {
+                if msg.data() == "!hello" {
+                    writer
+                        .encode(crate::commands::privmsg(msg.channel(), "hi!"))
+                        .await
+                        .unwrap();
+                }
+            }
+            seen.push(msg.raw().to_string());
+        }
+        seen
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let config = UserConfig::builder().anonymous().build().unwrap();
+        futures_lite::future::block_on(async {
+            let connector = TestConnector::default();
+            connector.conn.write_data(SESSION).await;
+
+            let recorder = Recorder::new();
+            let runner = AsyncRunner::connect_recorded(connector, &config, &recorder)
+                .await
+                .unwrap();
+            let seen = run(runner).await;
+
+            let recording = recorder.recording();
+            let sent = recording
+                .events
+                .iter()
+                .filter(|event| matches!(event.kind, EventKind::Sent(..)))
+                .count();
+            assert!(sent >= 3, "{}", recording);
+
+            // it survives being saved
+            let recording: Recording = recording.to_string().parse().unwrap();
+            assert_eq!(recording, recorder.recording());
+
+            let replay = Replay::new(&recording);
+            let runner = AsyncRunner::connect_replay(&replay, &config).await.unwrap();
+            assert_eq!(run(runner).await, seen);
+            assert_eq!(replay.divergences(), vec![]);
+            assert!(replay.is_finished());
+        });
+    }
+
+    #[test]
+    fn replay_timer() {
+        let config = UserConfig::builder().anonymous().build().unwrap();
+        futures_lite::future::block_on(async {
+            // the registration, from a real session
+            let connector = TestConnector::default();
+            connector
+                .conn
+                .write_data(&SESSION[..SESSION.find("PING").unwrap()])
+                .await;
+            let recorder = Recorder::new();
+            let runner = AsyncRunner::connect_recorded(connector, &config, &recorder)
+                .await
+                .unwrap();
+            run(runner).await;
+
+            // and then an idle connection
+            let mut recording = recorder.recording();
+            let token = |at: u64| (recording.started + at) / 1000;
+            let events = vec![
+                (45_000, EventKind::Timer),
+                (45_000, EventKind::Sent(format!("PING {}", token(45_000)))),
+                (
+                    45_100,
+                    EventKind::Received(format!(
+                        ":tmi.twitch.tv PONG tmi.twitch.tv :{}",
+                        token(45_000)
+                    )),
+                ),
+                (90_100, EventKind::Timer),
+                (90_100, EventKind::Sent(format!("PING {}", token(90_100)))),
+            ];
+            recording
+                .events
+                .extend(events.into_iter().map(|(at, kind)| RecordedEvent {
+                    at: Duration::from_millis(at),
+                    kind,
+                }));
+
+            let replay = Replay::new(&recording);
+            let runner = AsyncRunner::connect_replay(&replay, &config).await.unwrap();
+            run(runner).await;
+            assert_eq!(replay.divergences(), vec![]);
+            assert!(replay.is_finished());
+
+            // a different token is a divergence
+            recording.events.pop();
+            recording.events.push(RecordedEvent {
+                at: Duration::from_millis(90_100),
+                kind: EventKind::Sent("PING 0".to_string()),
+            });
+            let replay = Replay::new(&recording);
+            let runner = AsyncRunner::connect_replay(&replay, &config).await.unwrap();
+            run(runner).await;
+            let divergences = replay.divergences();
+            assert_eq!(divergences.len(), 1);
+            assert_eq!(divergences[0].expected.as_deref(), Some("PING 0"));
+        });
+    }
+
+    #[test]
+    fn invalid_recording() {
+        assert_eq!("".parse::<Recording>(), Err(RecordingError::InvalidHeader));
+        assert_eq!(
+            "twitchchat-recording 1 0\n0 < a\nfoo\n".parse::<Recording>(),
+            Err(RecordingError::InvalidEvent { line: 3 })
+        );
+    }
+}
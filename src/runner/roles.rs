@@ -0,0 +1,154 @@
+use crate::{rate_limit::RateClass, twitch::Badge};
+
+/// The roles you have in a channel, from the badges of its `USERSTATE`
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Roles {
+    /// It is your channel
+    pub broadcaster: bool,
+    /// You are a moderator
+    pub moderator: bool,
+    /// You are a VIP
+    pub vip: bool,
+}
+
+impl Roles {
+    /// Get the roles from a set of badges
+    ///
+    /// ```
+    /// # use twitchchat::{runner::Roles, twitch::BadgeVec};
+    /// let badges: BadgeVec = "moderator/1,subscriber/12".parse().unwrap();
+    /// let roles = Roles::from_badges(&badges);
+    /// assert!(roles.moderator);
+    /// assert!(!roles.vip);
+    /// ```
+    pub fn from_badges(badges: &[Badge]) -> Self {
+        badges.iter().fold(Self::default(), |roles, badge| match badge {
+            Badge::Broadcaster => Self {
+                broadcaster: true,
+                ..roles
+            },
+            Badge::Moderator => Self {
+                moderator: true,
+                ..roles
+            },
+            Badge::Vip => Self { vip: true, ..roles },
+            _ => roles,
+        })
+    }
+
+    /// Whether these roles get the moderator rate limit, which the broadcaster,
+    /// moderators and VIPs do
+    pub fn is_privileged(&self) -> bool {
+        self.broadcaster || self.moderator || self.vip
+    }
+
+    /// The [RateClass] to use with these roles, when `default` is used otherwise
+    ///
+    /// The larger of the two is used, so a verified bot stays verified after being modded.
+    pub fn rate_class(&self, default: RateClass) -> RateClass {
+        if self.is_privileged() && RateClass::Moderator.tickets() > default.tickets() {
+            RateClass::Moderator
+        } else {
+            default
+        }
+    }
+}
+
+/// Your roles in a channel changed, e.g. you were modded while connected.
+///
+/// See [AsyncRunner::role_changes](crate::AsyncRunner::role_changes)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct RoleChange {
+    /// The channel
+    pub channel: String,
+    /// The roles before the change
+    pub previous: Roles,
+    /// The roles now
+    pub current: Roles,
+    /// The rate class the channel uses now
+    pub rate_class: RateClass,
+}
+
+impl RoleChange {
+    /// You were made a moderator
+    pub fn gained_moderator(&self) -> bool {
+        !self.previous.moderator && self.current.moderator
+    }
+
+    /// You are no longer a moderator
+    pub fn lost_moderator(&self) -> bool {
+        self.previous.moderator && !self.current.moderator
+    }
+
+    /// You were made a VIP
+    pub fn gained_vip(&self) -> bool {
+        !self.previous.vip && self.current.vip
+    }
+
+    /// You are no longer a VIP
+    pub fn lost_vip(&self) -> bool {
+        self.previous.vip && !self.current.vip
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::{runner::Status, test::TestConnector, AsyncRunner, UserConfig};
+
+    #[test]
+    fn modded_mid_session() {
+        let connector = TestConnector::default();
+        let config = UserConfig::builder().anonymous().build().unwrap();
+
+        futures_lite::future::block_on(async {
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #museun\r\n\
+                     @badges=subscriber/12 :tmi.twitch.tv USERSTATE #museun\r\n\
+                     @badges=subscriber/12 :tmi.twitch.tv USERSTATE #museun\r\n\
+                     @badges=moderator/1,subscriber/12 :tmi.twitch.tv USERSTATE #museun\r\n\
+                     @badges=vip/1 :tmi.twitch.tv USERSTATE #museun\r\n",
+                )
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector, &config).await.unwrap();
+            let changes = runner.role_changes();
+
+            while let Status::Message(..) = runner.next_message().await.unwrap() {}
+            drop(runner);
+
+            // the first USERSTATE and the unchanged one aren't changes
+            let modded = changes.recv().await.unwrap();
+            assert_eq!(modded.channel, "#museun");
+            assert!(modded.gained_moderator());
+            assert_eq!(modded.rate_class, RateClass::Moderator);
+
+            let vip = changes.recv().await.unwrap();
+            assert!(vip.lost_moderator());
+            assert!(vip.gained_vip());
+            assert_eq!(vip.rate_class, RateClass::Moderator);
+
+            assert!(changes.recv().await.is_none());
+        });
+    }
+
+    #[test]
+    fn rate_class() {
+        let modded = Roles {
+            moderator: true,
+            ..Roles::default()
+        };
+        assert_eq!(modded.rate_class(RateClass::Regular), RateClass::Moderator);
+        assert_eq!(modded.rate_class(RateClass::Verified), RateClass::Verified);
+        assert_eq!(
+            Roles::default().rate_class(RateClass::Known),
+            RateClass::Known
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::time::{Duration, Instant, SystemTime};
+
+/// Counters for everything that happened while a runner was connected, sent
+/// once when its connection ends.
+///
+/// See [AsyncRunner::session_summary](crate::AsyncRunner::session_summary)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct SessionSummary {
+    /// When the runner connected
+    pub connected: SystemTime,
+    /// How long the runner was connected
+    pub uptime: Duration,
+    /// How many messages were received, after the handshake
+    pub messages_in: u64,
+    /// How many messages from the writers were sent
+    pub messages_out: u64,
+    /// How many times the runner switched to a new connection, see
+    /// [AsyncRunner::reauthenticate](crate::AsyncRunner::reauthenticate)
+    pub reconnects: u64,
+    /// How many received messages were dropped because they were ignored
+    pub dropped_messages: u64,
+    /// How many times a channel was joined, not counting rejoins when reconnecting
+    pub channels_joined: u64,
+    /// Why the connection ended, if it was an error
+    pub error: Option<String>,
+}
+
+impl std::fmt::Display for SessionSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "session ended after {:?}: {} in, {} out, {} dropped, {} reconnects, {} channels joined",
+            self.uptime,
+            self.messages_in,
+            self.messages_out,
+            self.dropped_messages,
+            self.reconnects,
+            self.channels_joined
+        )?;
+        if let Some(error) = &self.error {
+            write!(f, " ({})", error)?;
+        }
+        Ok(())
+    }
+}
+
+// the counters a runner keeps for its summary
+#[derive(Debug)]
+pub(crate) struct Session {
+    connected: SystemTime,
+    started: Instant,
+    pub(crate) messages_in: u64,
+    pub(crate) messages_out: u64,
+    pub(crate) reconnects: u64,
+    pub(crate) dropped_messages: u64,
+    pub(crate) channels_joined: u64,
+    pub(crate) ended: bool,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            connected: SystemTime::now(),
+            started: Instant::now(),
+            messages_in: 0,
+            messages_out: 0,
+            reconnects: 0,
+            dropped_messages: 0,
+            channels_joined: 0,
+            ended: false,
+        }
+    }
+}
+
+impl Session {
+    pub(crate) fn summary(&self, error: Option<String>) -> SessionSummary {
+        SessionSummary {
+            connected: self.connected,
+            uptime: self.started.elapsed(),
+            messages_in: self.messages_in,
+            messages_out: self.messages_out,
+            reconnects: self.reconnects,
+            dropped_messages: self.dropped_messages,
+            channels_joined: self.channels_joined,
+            error,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use crate::{runner::Status, test::TestConnector, AsyncRunner, UserConfig};
+
+    #[test]
+    fn summary_on_eof() {
+        let connector = TestConnector::default();
+        let config = UserConfig::builder().anonymous().build().unwrap();
+
+        futures_lite::future::block_on(async {
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #museun\r\n\
+                     :nightbot!nightbot@nightbot.tmi.twitch.tv PRIVMSG #museun :!uptime\r\n\
+                     :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n",
+                )
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector, &config).await.unwrap();
+            runner.config_handle().ignore_user("nightbot");
+            let summaries = runner.subscribe_session_summary();
+
+            while let Status::Message(..) = runner.next_message().await.unwrap() {}
+            assert!(matches!(runner.next_message().await, Ok(Status::Eof)));
+
+            let summary = summaries.recv().await.unwrap();
+            assert_eq!(summary.messages_in, 3);
+            assert_eq!(summary.dropped_messages, 1);
+            assert_eq!(summary.channels_joined, 1);
+            assert_eq!(summary.reconnects, 0);
+            assert_eq!(summary.error, None);
+
+            // only one summary is sent
+            assert!(summaries.recv().await.is_none());
+        });
+    }
+}
@@ -0,0 +1,207 @@
+/*!
+HMAC signing of [Envelope]s relayed between services.
+
+The sending side serializes the envelope to JSON and signs it with a key the
+services share. The receiving side checks the signature before it parses
+anything, so a forwarded chat event can be trusted to come from a service
+holding the key, unchanged.
+
+```
+# use twitchchat::{runner::{Envelope, EnvelopeSigner, SignedEnvelope}};
+# fn relay(envelope: &Envelope) -> Result<(), Box<dyn std::error::Error>> {
+let signer = EnvelopeSigner::new(b"shared secret");
+
+// on the relaying service
+let signed = signer.sign(envelope)?;
+let wire = serde_json::to_string(&signed)?;
+
+// on the consuming service
+let signed = serde_json::from_str::<SignedEnvelope>(&wire)?;
+let envelope = signer.verify(&signed)?;
+# Ok(())
+# }
+```
+*/
+
+use super::Envelope;
+use hmac::{Hmac, Mac as _};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// A serialized [Envelope] with its signature, see [EnvelopeSigner]
+#[derive(Debug, Clone, PartialEq, Eq, ::serde::Serialize, ::serde::Deserialize)]
+pub struct SignedEnvelope {
+    /// The envelope, as JSON
+    pub payload: String,
+    /// The HMAC-SHA256 of the payload, as lowercase hex
+    pub signature: String,
+}
+
+/// An error returned by [EnvelopeSigner]
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum SigningError {
+    /// The envelope couldn't be serialized, or the payload couldn't be parsed
+    Serialization(serde_json::Error),
+    /// The signature doesn't match the payload (or isn't hex)
+    InvalidSignature,
+}
+
+impl std::fmt::Display for SigningError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Serialization(err) => write!(f, "cannot serialize the envelope: {}", err),
+            Self::InvalidSignature => f.write_str("the signature doesn't match the envelope"),
+        }
+    }
+}
+
+impl std::error::Error for SigningError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Serialization(err) => Some(err),
+            Self::InvalidSignature => None,
+        }
+    }
+}
+
+/// Signs [Envelope]s, and verifies them on ingest, with a shared key.
+///
+/// The signature is an HMAC-SHA256 of the serialized envelope, and it is
+/// compared in constant time.
+#[derive(Clone)]
+pub struct EnvelopeSigner {
+    mac: HmacSha256,
+}
+
+impl std::fmt::Debug for EnvelopeSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // the key stays out of the logs
+        f.debug_struct("EnvelopeSigner").finish()
+    }
+}
+
+impl EnvelopeSigner {
+    /// Create a signer with this key. It can be of any length
+    pub fn new(key: impl AsRef<[u8]>) -> Self {
+        let mac = HmacSha256::new_from_slice(key.as_ref()).expect("HMAC takes keys of any length");
+        Self { mac }
+    }
+
+    /// Serialize and sign this envelope
+    pub fn sign(&self, envelope: &Envelope) -> Result<SignedEnvelope, SigningError> {
+        let payload = serde_json::to_string(envelope).map_err(SigningError::Serialization)?;
+        let mut mac = self.mac.clone();
+        mac.update(payload.as_bytes());
+        let signature = mac
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        Ok(SignedEnvelope { payload, signature })
+    }
+
+    /// Check the signature of this envelope, then parse it
+    pub fn verify(&self, signed: &SignedEnvelope) -> Result<Envelope, SigningError> {
+        let signature = decode_hex(&signed.signature).ok_or(SigningError::InvalidSignature)?;
+        let mut mac = self.mac.clone();
+        mac.update(signed.payload.as_bytes());
+        mac.verify_slice(&signature)
+            .map_err(|_| SigningError::InvalidSignature)?;
+        serde_json::from_str(&signed.payload).map_err(SigningError::Serialization)
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair)
+                .ok()
+                .filter(|pair| pair.len() == 2)?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::{messages::Commands, runner::Status, test::TestConnector, AsyncRunner, UserConfig};
+
+    fn envelope() -> Envelope {
+        let connector = TestConnector::default();
+        let config = UserConfig::builder().anonymous().build().unwrap();
+
+        futures_lite::future::block_on(async {
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     @id=abc :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n",
+                )
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector, &config).await.unwrap();
+            let all = runner.subscribe_all();
+            while let Ok(Status::Message(..)) = runner.next_message().await {}
+            drop(runner);
+            loop {
+                let envelope = all.recv().await.unwrap();
+                if let Commands::Privmsg(..) = envelope.message {
+                    break envelope;
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn round_trip() {
+        let envelope = envelope();
+        let signer = EnvelopeSigner::new(b"secret");
+
+        let signed = signer.sign(&envelope).unwrap();
+        assert_eq!(signed.signature.len(), 64);
+        assert_eq!(signer.verify(&signed).unwrap(), envelope);
+
+        // and across the wire
+        let wire = serde_json::to_string(&signed).unwrap();
+        let signed = serde_json::from_str::<SignedEnvelope>(&wire).unwrap();
+        assert_eq!(signer.verify(&signed).unwrap(), envelope);
+    }
+
+    #[test]
+    fn rejected() {
+        let signer = EnvelopeSigner::new(b"secret");
+        let signed = signer.sign(&envelope()).unwrap();
+
+        // another key
+        let other = EnvelopeSigner::new(b"other secret");
+        assert!(matches!(
+            other.verify(&signed),
+            Err(SigningError::InvalidSignature)
+        ));
+
+        // a tampered payload
+        let mut tampered = signed.clone();
+        tampered.payload = tampered.payload.replace("hello", "hellO");
+        assert_ne!(tampered, signed);
+        assert!(matches!(
+            signer.verify(&tampered),
+            Err(SigningError::InvalidSignature)
+        ));
+
+        // a malformed signature
+        for signature in &["", "abc", "zz", "é"] {
+            let mut malformed = signed.clone();
+            malformed.signature = signature.to_string();
+            assert!(matches!(
+                signer.verify(&malformed),
+                Err(SigningError::InvalidSignature)
+            ));
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use super::{handshake::Handshake, Error, Identity, Status};
+use crate::{
+    commands,
+    decoder::Decoder,
+    encoder::Encoder,
+    messages::{Commands, MessageId},
+    DecodeError, FromIrcMessage as _, IntoOwned as _, UserConfig,
+};
+use std::{
+    collections::{HashSet, VecDeque},
+    io::{Read, Write},
+    net::TcpStream,
+};
+
+/// A blocking runner, for small tools where an async runtime is overkill.
+///
+/// This registers and builds your [Identity] the same way the `AsyncRunner`
+/// does, answers PINGs, and keeps track of the channels you're on. It doesn't
+/// rate limit or time out, and it only does anything while you call its methods.
+///
+/// ```no_run
+/// # use twitchchat::{runner::{Status, SyncRunner}, commands, UserConfig};
+/// # fn demo() -> Result<(), twitchchat::RunnerError> {
+/// let config = UserConfig::builder().anonymous().build().unwrap();
+/// let mut runner = SyncRunner::connect_tcp(&config)?;
+/// runner.join("#museun")?;
+///
+/// while let Status::Message(msg) = runner.next_message()? {
+///     println!("{}", msg.raw().trim_end());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct SyncRunner<R, W> {
+    decoder: Decoder<R>,
+    encoder: Encoder<W>,
+    identity: Identity,
+    channels: HashSet<String>,
+    missed_messages: VecDeque<Commands<'static>>,
+}
+
+impl<R, W> std::fmt::Debug for SyncRunner<R, W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SyncRunner { .. }").finish()
+    }
+}
+
+impl SyncRunner<TcpStream, TcpStream> {
+    /// Connect to Twitch over plain TCP (without TLS) with the provided UserConfig
+    pub fn connect_tcp(user_config: &UserConfig) -> Result<Self, Error> {
+        let stream = TcpStream::connect(crate::TWITCH_IRC_ADDRESS)?;
+        Self::connect(stream.try_clone()?, stream, user_config)
+    }
+}
+
+impl<R, W> SyncRunner<R, W>
+where
+    R: Read,
+    W: Write,
+{
+    /// Register on this connection with the provided UserConfig.
+    ///
+    /// This blocks until Twitch has sent your identity.
+    pub fn connect(reader: R, writer: W, user_config: &UserConfig) -> Result<Self, Error> {
+        let mut decoder = Decoder::new(reader);
+        let mut encoder = Encoder::new(writer);
+        encoder.encode(commands::register(user_config))?;
+
+        let mut handshake = Handshake::new(user_config);
+        let mut missed_messages = VecDeque::new();
+
+        let identity = loop {
+            // reads block, so the timeouts are only checked when a message arrives
+            handshake.check_timeout()?;
+            let msg = Commands::from_irc(decoder.read_message()?)?.into_owned();
+            if let Commands::Ping(msg) = &msg {
+                encoder.encode(commands::pong(msg.token()))?;
+            }
+
+            let identity = handshake.observe(&msg)?;
+            for cap in handshake.take_requests() {
+                encoder.encode(commands::raw(cap.encode_as_str()))?;
+            }
+            missed_messages.push_back(msg);
+            if let Some(identity) = identity {
+                break identity;
+            }
+        };
+
+        Ok(Self {
+            decoder,
+            encoder,
+            identity,
+            channels: HashSet::new(),
+            missed_messages,
+        })
+    }
+
+    /// Get your identity
+    pub fn identity(&self) -> &Identity {
+        &self.identity
+    }
+
+    /// Check whether you're on this channel
+    pub fn is_on_channel(&self, channel: &str) -> bool {
+        self.channels
+            .contains(&commands::Channel::new(channel).to_string())
+    }
+
+    /// Get the writer, to send messages with
+    pub fn writer(&mut self) -> &mut Encoder<W> {
+        &mut self.encoder
+    }
+
+    /// Join `channel` and wait for it to complete
+    pub fn join(&mut self, channel: &str) -> Result<(), Error> {
+        if self.is_on_channel(channel) {
+            return Err(Error::AlreadyOnChannel {
+                channel: channel.to_string(),
+            });
+        }
+
+        self.encoder.encode(commands::join(channel))?;
+        let channel = commands::Channel::new(channel).to_string();
+
+        self.wait_for(|msg, name| match msg {
+            // check to see if it was us that joined the channel
+            Commands::Join(msg) => Ok(msg.channel() == channel && msg.name() == name),
+
+            // check to see if we were banned
+            Commands::Notice(msg) if matches!(msg.msg_id(), Some(MessageId::MsgBanned)) => {
+                Err(Error::BannedFromChannel {
+                    channel: msg.channel().to_string(),
+                })
+            }
+
+            _ => Ok(false),
+        })
+    }
+
+    /// Part `channel` and wait for it to complete
+    pub fn part(&mut self, channel: &str) -> Result<(), Error> {
+        if !self.is_on_channel(channel) {
+            return Err(Error::NotOnChannel {
+                channel: channel.to_string(),
+            });
+        }
+
+        self.encoder.encode(commands::part(channel))?;
+        let channel = commands::Channel::new(channel).to_string();
+
+        self.wait_for(|msg, name| match msg {
+            // check to see if it was us that left the channel
+            Commands::Part(msg) => Ok(msg.channel() == channel && msg.name() == name),
+            _ => Ok(false),
+        })
+    }
+
+    /// Get the next message, blocking until there is one.
+    ///
+    /// This returns `Status::Eof` when the connection was closed.
+    pub fn next_message(&mut self) -> Result<Status<'static>, Error> {
+        if let Some(msg) = self.missed_messages.pop_front() {
+            return Ok(Status::Message(msg));
+        }
+
+        let msg = match self.decoder.read_message() {
+            Err(DecodeError::Eof) => return Ok(Status::Eof),
+            msg => Commands::from_irc(msg?)?.into_owned(),
+        };
+        self.check_message(&msg)?;
+        Ok(Status::Message(msg))
+    }
+
+    /// Send a `QUIT` and close this runner
+    pub fn quit(mut self) -> Result<(), Error> {
+        self.encoder.encode(commands::raw("QUIT"))?;
+        Ok(())
+    }
+
+    // read until `func` returns true, keeping the messages for next_message
+    fn wait_for<F>(&mut self, func: F) -> Result<(), Error>
+    where
+        F: Fn(&Commands<'static>, &str) -> Result<bool, Error>,
+    {
+        let mut missed = VecDeque::new();
+        let result = loop {
+            let msg = match self.decoder.read_message() {
+                Err(DecodeError::Eof) => break Err(Error::UnexpectedEof),
+                msg => Commands::from_irc(msg?)?.into_owned(),
+            };
+            self.check_message(&msg)?;
+
+            match func(&msg, self.identity.username()) {
+                Ok(true) => break Ok(()),
+                Ok(false) => missed.push_back(msg),
+                Err(err) => break Err(err),
+            }
+        };
+
+        self.missed_messages.extend(missed);
+        result
+    }
+
+    fn check_message(&mut self, msg: &Commands<'_>) -> Result<(), Error> {
+        use Commands::*;
+
+        match msg {
+            Ping(msg) => self.encoder.encode(commands::pong(msg.token()))?,
+
+            Join(msg) if msg.name() == self.identity.username() => {
+                self.channels.insert(msg.channel().to_string());
+            }
+
+            Part(msg) if msg.name() == self.identity.username() => {
+                self.channels.remove(msg.channel());
+            }
+
+            Notice(msg) if matches!(msg.msg_id(), Some(MessageId::MsgBanned)) => {
+                self.channels.remove(msg.channel());
+            }
+
+            Reconnect(_) => return Err(Error::ShouldReconnect),
+
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> UserConfig {
+        UserConfig::builder()
+            .anonymous()
+            .enable_all_capabilities()
+            .build()
+            .unwrap()
+    }
+
+    const HANDSHAKE: &str = ":tmi.twitch.tv CAP * ACK :twitch.tv/membership\r\n\
+        :tmi.twitch.tv CAP * ACK :twitch.tv/tags\r\n\
+        :tmi.twitch.tv CAP * ACK :twitch.tv/commands\r\n\
+        :tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+        :tmi.twitch.tv 376 justinfan1234 :>\r\n";
+
+    #[test]
+    fn connect_and_read() {
+        let input = format!(
+            "{}PING :1234\r\n\
+             :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #museun\r\n\
+             :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n\
+             :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv PART #museun\r\n",
+            HANDSHAKE
+        );
+
+        let mut output = vec![];
+        let mut runner = SyncRunner::connect(input.as_bytes(), &mut output, &config()).unwrap();
+        assert!(
+            matches!(runner.identity(), Identity::Anonymous { name, caps } if caps.tags && name == "justinfan1234")
+        );
+
+        // the handshake is yielded first
+        for _ in 0..5 {
+            assert!(matches!(
+                runner.next_message().unwrap(),
+                Status::Message(..)
+            ));
+        }
+
+        assert!(matches!(
+            runner.next_message().unwrap(),
+            Status::Message(Commands::Ping(..))
+        ));
+
+        runner.join("museun").unwrap();
+        assert!(runner.is_on_channel("#museun"));
+        assert!(matches!(
+            runner.join("#museun"),
+            Err(Error::AlreadyOnChannel { .. })
+        ));
+
+        runner.part("#museun").unwrap();
+        assert!(!runner.is_on_channel("#museun"));
+
+        // the message read while parting is kept
+        match runner.next_message().unwrap() {
+            Status::Message(Commands::Privmsg(msg)) => assert_eq!(msg.data(), "hello"),
+            msg => panic!("unexpected message: {:?}", msg),
+        }
+        assert!(matches!(runner.next_message().unwrap(), Status::Eof));
+
+        runner.quit().unwrap();
+        let output = std::str::from_utf8(&output).unwrap();
+        assert!(output.starts_with("CAP REQ :twitch.tv/membership\r\n"));
+        assert!(
+            output.ends_with(
+                "NICK justinfan1234\r\nPONG :1234\r\nJOIN #museun\r\nPART #museun\r\nQUIT\r\n"
+            ),
+            "{}",
+            output.escape_debug()
+        );
+    }
+
+    #[test]
+    fn banned_and_reconnect() {
+        let input = format!(
+            "{}@msg-id=msg_banned :tmi.twitch.tv NOTICE #museun :You are permanently banned from talking in museun.\r\n\
+             :tmi.twitch.tv RECONNECT\r\n",
+            HANDSHAKE
+        );
+
+        let mut runner = SyncRunner::connect(input.as_bytes(), vec![], &config()).unwrap();
+        assert!(matches!(
+            runner.join("#museun"),
+            Err(Error::BannedFromChannel { channel }) if channel == "#museun"
+        ));
+
+        for _ in 0..5 {
+            assert!(matches!(
+                runner.next_message().unwrap(),
+                Status::Message(..)
+            ));
+        }
+        assert!(matches!(runner.next_message(), Err(Error::ShouldReconnect)));
+        assert!(matches!(runner.next_message(), Ok(Status::Eof)));
+    }
+
+    #[test]
+    fn invalid_cap() {
+        let input = ":tmi.twitch.tv CAP * NAK :twitch.tv/tags\r\n";
+        let err = SyncRunner::connect(input.as_bytes(), vec![], &config()).unwrap_err();
+        assert!(matches!(err, Error::InvalidCap { cap } if cap == "twitch.tv/tags"));
+    }
+}
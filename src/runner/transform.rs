@@ -0,0 +1,209 @@
+use crate::{messages::Commands, BoxedFuture};
+
+/// Rewrites messages before an [AsyncRunner](crate::AsyncRunner) yields them,
+/// e.g. to machine translate the data of `PRIVMSG`s.
+///
+/// The runner keeps track of its own state (channels, rate limits, etc) with
+/// the original message, and puts it in the [Envelope](super::Envelope) of the
+/// rewritten one.
+///
+/// This is implemented for closures. Use
+/// [MessageRewriter::set_data](crate::irc::MessageRewriter::set_data) to
+/// replace the data of a message.
+///
+/// ```no_run
+/// # use twitchchat::{irc::MessageRewriter, messages::Commands, AsyncRunner, FromIrcMessage as _};
+/// # async fn translate(text: &str) -> String { text.to_string() }
+/// # fn demo(runner: &mut AsyncRunner) {
+/// runner.set_transform(|msg: &Commands<'static>| {
+///     let msg = match msg {
+///         Commands::Privmsg(msg) => msg.clone(),
+///         _ => return None,
+///     };
+///     Some(Box::pin(async move {
+///         let data = translate(msg.data()).await;
+///         MessageRewriter::new(msg.raw())
+///             .set_data(data)
+///             .rewrite()
+///             .ok()
+///             .and_then(|msg| Commands::from_irc(msg).ok())
+///             .unwrap_or(Commands::Privmsg(msg))
+///     }) as _)
+/// });
+/// # }
+/// ```
+pub trait Transform: Send + Sync {
+    /// Start rewriting this message, or return `None` to yield it unchanged.
+    ///
+    /// The runner doesn't read anything else until the future completes.
+    ///
+    /// This is called once per message. The messages read while e.g.
+    /// [join](crate::AsyncRunner::join) waits are yielded afterwards, already rewritten.
+    fn transform(&self, msg: &Commands<'static>) -> Option<BoxedFuture<Commands<'static>>>;
+}
+
+impl<F> Transform for F
+where
+    F: Fn(&Commands<'static>) -> Option<BoxedFuture<Commands<'static>>> + Send + Sync,
+{
+    fn transform(&self, msg: &Commands<'static>) -> Option<BoxedFuture<Commands<'static>>> {
+        (self)(msg)
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::{
+        irc::MessageRewriter, runner::Status, test::TestConnector, AsyncRunner,
+        FromIrcMessage as _, UserConfig,
+    };
+
+    fn shout(msg: &Commands<'static>) -> Option<BoxedFuture<Commands<'static>>> {
+        let msg = match msg {
+            Commands::Privmsg(msg) => msg.clone(),
+            _ => return None,
+        };
+        Some(Box::pin(async move {
+            let msg = MessageRewriter::new(msg.raw())
+                .set_data(msg.data().to_uppercase())
+                .rewrite()
+                .unwrap();
+            Commands::from_irc(msg).unwrap()
+        }))
+    }
+
+    #[test]
+    fn transform() {
+        let connector = TestConnector::default();
+        let config = UserConfig::builder().anonymous().build().unwrap();
+
+        futures_lite::future::block_on(async {
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n",
+                )
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector, &config).await.unwrap();
+            let envelopes = runner.subscribe_all();
+            runner.set_transform(shout);
+
+            let msg = loop {
+                match runner.next_message().await.unwrap() {
+                    Status::Message(Commands::Privmsg(msg)) => break msg,
+                    Status::Message(..) => continue,
+                    status => panic!("unexpected status: {:?}", status),
+                }
+            };
+            assert_eq!(msg.data(), "HELLO");
+
+            let envelope = loop {
+                let envelope = envelopes.recv().await.unwrap();
+                if envelope.original.is_some() {
+                    break envelope;
+                }
+            };
+            assert!(matches!(envelope.message, Commands::Privmsg(msg) if msg.data() == "HELLO"));
+            assert!(
+                matches!(envelope.original, Some(Commands::Privmsg(msg)) if msg.data() == "hello")
+            );
+        });
+    }
+
+    #[test]
+    fn transform_cancelled() {
+        let connector = TestConnector::default();
+        let config = UserConfig::builder().anonymous().build().unwrap();
+
+        let mut runner = futures_lite::future::block_on(async {
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n",
+                )
+                .await;
+            AsyncRunner::connect(connector, &config).await.unwrap()
+        });
+
+        let envelopes = runner.subscribe_all();
+        // this isn't done on the first poll, so `try_next_now` drops it
+        runner.set_transform(|msg: &Commands<'static>| -> Option<BoxedFuture<_>> {
+            let fut = shout(msg)?;
+            Some(Box::pin(async move {
+                futures_lite::future::yield_now().await;
+                fut.await
+            }))
+        });
+
+        let mut cancelled = 0;
+        let msg = loop {
+            match runner.try_next_now().unwrap() {
+                Some(Status::Message(Commands::Privmsg(msg))) => break msg,
+                Some(Status::Message(..)) => continue,
+                Some(status) => panic!("unexpected status: {:?}", status),
+                None => cancelled += 1,
+            }
+        };
+        assert!(cancelled > 0);
+        assert_eq!(msg.data(), "HELLO");
+
+        // and it was only published once
+        let envelopes = std::iter::from_fn(|| envelopes.try_recv())
+            .filter(|envelope| envelope.original.is_some())
+            .count();
+        assert_eq!(envelopes, 1);
+    }
+
+    #[test]
+    fn transformed_once() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let connector = TestConnector::default();
+        let config = UserConfig::builder().anonymous().build().unwrap();
+
+        futures_lite::future::block_on(async {
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n\
+                     :justinfan1234!justinfan1234@justinfan1234.tmi.twitch.tv JOIN #museun\r\n",
+                )
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector, &config).await.unwrap();
+            let calls = Arc::new(AtomicUsize::new(0));
+            runner.set_transform({
+                let calls = Arc::clone(&calls);
+                move |msg: &Commands<'static>| {
+                    if let Commands::Privmsg(..) = msg {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                    }
+                    shout(msg)
+                }
+            });
+
+            // the PRIVMSG is read while joining, and yielded afterwards
+            runner.join("#museun").await.unwrap();
+            let msg = loop {
+                match runner.next_message().await.unwrap() {
+                    Status::Message(Commands::Privmsg(msg)) => break msg,
+                    Status::Message(..) => continue,
+                    status => panic!("unexpected status: {:?}", status),
+                }
+            };
+            assert_eq!(msg.data(), "HELLO");
+            assert_eq!(calls.load(Ordering::SeqCst), 1);
+        });
+    }
+}
@@ -0,0 +1,261 @@
+/*!
+A small key-value storage interface, for persisting state across restarts.
+
+Caches and queues like the [RedemptionQueue](crate::messages::RedemptionQueue),
+or snapshots of [Metrics](crate::metrics::Metrics), can be saved to a [Storage]
+and loaded again when the bot starts. Keys are strings, so related values can
+share a prefix (e.g. `presence/#museun`) and be [scanned](Storage::scan) together.
+
+These storages are available:
+* [MemoryStorage], which doesn't persist anything, e.g. for tests
+* `SledStorage`, a [sled](https://docs.rs/sled) tree, with the `sled` feature
+* `SqliteStorage`, a table in a [SQLite](https://docs.rs/rusqlite) database, with the `sqlite` feature
+
+With the `serde` and `serde_json` features, [StorageExt] saves and loads any
+serializable value as JSON.
+
+An [Outbox] keeps outgoing messages in a storage, so queued messages are
+still sent after a restart.
+*/
+
+use std::collections::BTreeMap;
+
+mod outbox;
+pub use self::outbox::{Outbox, OutboxEntry};
+
+#[cfg(feature = "sled")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sled")))]
+mod sled;
+#[cfg(feature = "sled")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sled")))]
+pub use self::sled::SledStorage;
+
+#[cfg(feature = "sqlite")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+pub use self::sqlite::SqliteStorage;
+
+/// An error returned by a [Storage]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum StorageError {
+    /// The backend of the storage failed
+    Backend(Box<dyn std::error::Error + Send + Sync>),
+    /// A value could not be encoded, or decoded
+    Encoding(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl std::fmt::Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Backend(err) => write!(f, "storage error: {}", err),
+            Self::Encoding(err) => write!(f, "cannot encode or decode a value: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Backend(err) | Self::Encoding(err) => Some(&**err),
+        }
+    }
+}
+
+impl From<std::io::Error> for StorageError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Backend(Box::new(err))
+    }
+}
+
+/// A key-value store for persisting state
+pub trait Storage {
+    /// Get the value of this key, if it is stored
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError>;
+
+    /// Store this value for this key, replacing any previous value
+    fn put(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError>;
+
+    /// Remove this key, returning its value if it was stored
+    fn remove(&mut self, key: &str) -> Result<Option<Vec<u8>>, StorageError>;
+
+    /// Get every key that starts with this prefix and its value, ordered by key
+    fn scan(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>, StorageError>;
+}
+
+impl<S> Storage for &mut S
+where
+    S: Storage + ?Sized,
+{
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        (**self).get(key)
+    }
+
+    fn put(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        (**self).put(key, value)
+    }
+
+    fn remove(&mut self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        (**self).remove(key)
+    }
+
+    fn scan(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>, StorageError> {
+        (**self).scan(prefix)
+    }
+}
+
+impl<S> Storage for Box<S>
+where
+    S: Storage + ?Sized,
+{
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        (**self).get(key)
+    }
+
+    fn put(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        (**self).put(key, value)
+    }
+
+    fn remove(&mut self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        (**self).remove(key)
+    }
+
+    fn scan(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>, StorageError> {
+        (**self).scan(prefix)
+    }
+}
+
+/// A [Storage] in memory, which is lost when it is dropped
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MemoryStorage {
+    map: BTreeMap<String, Vec<u8>>,
+}
+
+impl MemoryStorage {
+    /// Create a new, empty storage
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of stored keys
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether nothing is stored
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        Ok(self.map.get(key).cloned())
+    }
+
+    fn put(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        self.map.insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        Ok(self.map.remove(key))
+    }
+
+    fn scan(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>, StorageError> {
+        Ok(self
+            .map
+            .range(prefix.to_string()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect())
+    }
+}
+
+/// Save and load serializable values as JSON, for every [Storage]
+///
+/// ```
+/// # use twitchchat::{FromIrcMessage as _, messages::{Commands, RedemptionQueue}};
+/// use twitchchat::storage::{MemoryStorage, StorageExt as _};
+///
+/// let input = "@custom-reward-id=song;id=1 :foo!foo@foo PRIVMSG #museun :a song\r\n";
+/// let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+///
+/// let mut queue = RedemptionQueue::default();
+/// queue.observe(&Commands::from_irc(msg).unwrap());
+///
+/// let mut storage = MemoryStorage::new();
+/// storage.save("redemptions/#museun", &queue).unwrap();
+///
+/// // e.g. after a restart
+/// let loaded: RedemptionQueue = storage.load("redemptions/#museun").unwrap().unwrap();
+/// assert_eq!(loaded, queue);
+/// ```
+#[cfg(all(feature = "serde", feature = "serde_json"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "serde", feature = "serde_json"))))]
+pub trait StorageExt: Storage {
+    /// Serialize this value and store it for this key
+    fn save<T>(&mut self, key: &str, value: &T) -> Result<(), StorageError>
+    where
+        T: ::serde::Serialize + ?Sized,
+    {
+        let data = serde_json::to_vec(value).map_err(|err| StorageError::Encoding(err.into()))?;
+        self.put(key, &data)
+    }
+
+    /// Load the value of this key, if it is stored
+    fn load<T>(&self, key: &str) -> Result<Option<T>, StorageError>
+    where
+        T: ::serde::de::DeserializeOwned,
+    {
+        match self.get(key)? {
+            Some(data) => serde_json::from_slice(&data)
+                .map(Some)
+                .map_err(|err| StorageError::Encoding(err.into())),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "serde_json"))]
+impl<S> StorageExt for S where S: Storage + ?Sized {}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // the behavior every storage should have
+    pub(crate) fn check_storage(storage: &mut dyn Storage) {
+        assert_eq!(storage.get("presence/#museun").unwrap(), None);
+
+        storage.put("presence/#museun", b"1").unwrap();
+        storage.put("presence/#foo", b"2").unwrap();
+        storage.put("presence", b"3").unwrap();
+        storage.put("stats/#museun", b"4").unwrap();
+        storage.put("presence/#foo", b"5").unwrap();
+
+        assert_eq!(storage.get("presence/#foo").unwrap(), Some(b"5".to_vec()));
+        assert_eq!(
+            storage.scan("presence/").unwrap(),
+            vec![
+                ("presence/#foo".to_string(), b"5".to_vec()),
+                ("presence/#museun".to_string(), b"1".to_vec()),
+            ]
+        );
+        assert_eq!(storage.scan("").unwrap().len(), 4);
+        assert!(storage.scan("redemptions/").unwrap().is_empty());
+
+        assert_eq!(storage.remove("presence").unwrap(), Some(b"3".to_vec()));
+        assert_eq!(storage.remove("presence").unwrap(), None);
+        assert_eq!(storage.get("presence").unwrap(), None);
+    }
+
+    #[test]
+    fn memory_storage() {
+        let mut storage = MemoryStorage::new();
+        check_storage(&mut storage);
+        assert_eq!(storage.len(), 3);
+    }
+}
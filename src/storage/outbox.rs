@@ -0,0 +1,263 @@
+use super::{Storage, StorageError};
+use crate::{commands::with_nonce, messages::Commands, Encodable};
+use std::collections::BTreeMap;
+
+/// A message queued in an [Outbox]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxEntry {
+    /// The id of the message, increasing in the order they were queued
+    pub id: u64,
+    /// The `client-nonce` the message is sent with
+    pub nonce: String,
+    /// How many times the message was (about to be) sent
+    pub attempts: u32,
+    /// The encoded message, with its nonce and `\r\n`
+    pub line: Vec<u8>,
+}
+
+impl OutboxEntry {
+    fn encode(&self) -> Vec<u8> {
+        let mut data = format!("{} {}\n", self.attempts, self.nonce).into_bytes();
+        data.extend_from_slice(&self.line);
+        data
+    }
+
+    fn decode(id: u64, data: &[u8]) -> Result<Self, StorageError> {
+        let invalid = || StorageError::Encoding(format!("invalid outbox entry: {}", id).into());
+
+        let pos = data.iter().position(|&b| b == b'\n').ok_or_else(invalid)?;
+        let (head, line) = (&data[..pos], &data[pos + 1..]);
+        let head = std::str::from_utf8(head).map_err(|_| invalid())?;
+        let (attempts, nonce) = head.split_once(' ').ok_or_else(invalid)?;
+
+        Ok(Self {
+            id,
+            nonce: nonce.to_string(),
+            attempts: attempts.parse().map_err(|_| invalid())?,
+            line: line.to_vec(),
+        })
+    }
+}
+
+/// A queue of outgoing messages kept in a [Storage], so they survive restarts.
+///
+/// Every message is queued with a unique `client-nonce`. Delivering one is:
+/// 1. take the [next](Outbox::next) message
+/// 1. [mark it as sent](Outbox::mark_sent), then write its line to the connection
+/// 1. remove it when Twitch [echoes](Outbox::observe) it, or [acknowledge](Outbox::ack) it yourself
+///
+/// A message is marked before it is written, so after a crash a message
+/// with `attempts > 0` may or may not have been sent. Sending it again uses
+/// the same nonce, so the copies can be told apart (e.g. with a
+/// [NonceMap](crate::runner::NonceMap)). Messages are never lost, but may be
+/// sent more than once.
+///
+/// ```
+/// # use twitchchat::{commands, storage::{MemoryStorage, Outbox}};
+/// let mut outbox = Outbox::open(MemoryStorage::new(), "outbox/")?;
+/// outbox.push(commands::privmsg("#museun", "hello"))?;
+///
+/// let entry = outbox.next()?.unwrap();
+/// outbox.mark_sent(entry.id)?;
+///
+/// // e.g. the bot crashed before the message was acknowledged
+/// let mut outbox = Outbox::open(outbox.into_inner(), "outbox/")?;
+/// let entry = outbox.next()?.unwrap();
+/// assert_eq!(entry.attempts, 1);
+/// assert!(entry.line.ends_with(b" PRIVMSG #museun :hello\r\n"));
+///
+/// outbox.ack(entry.id)?;
+/// assert!(outbox.is_empty());
+/// # Ok::<_, twitchchat::storage::StorageError>(())
+/// ```
+#[derive(Debug)]
+pub struct Outbox<S> {
+    storage: S,
+    prefix: String,
+    next_id: u64,
+    // the nonces of the queued messages, by id
+    nonces: BTreeMap<u64, String>,
+}
+
+impl<S: Storage> Outbox<S> {
+    /// Open the outbox stored under this key prefix, with the messages queued before
+    pub fn open(storage: S, prefix: &str) -> Result<Self, StorageError> {
+        let mut this = Self {
+            storage,
+            prefix: prefix.to_string(),
+            next_id: 0,
+            nonces: BTreeMap::new(),
+        };
+
+        for entry in this.pending()? {
+            this.next_id = entry.id + 1;
+            this.nonces.insert(entry.id, entry.nonce);
+        }
+        Ok(this)
+    }
+
+    /// Queue this message, with a new nonce
+    pub fn push<E: Encodable>(&mut self, cmd: E) -> Result<OutboxEntry, StorageError> {
+        let nonce = crate::util::nonce();
+        let mut line = vec![];
+        with_nonce(&nonce, cmd).encode(&mut line)?;
+
+        let entry = OutboxEntry {
+            id: self.next_id,
+            nonce,
+            attempts: 0,
+            line,
+        };
+        self.storage.put(&self.key(entry.id), &entry.encode())?;
+        self.next_id += 1;
+        self.nonces.insert(entry.id, entry.nonce.clone());
+        Ok(entry)
+    }
+
+    /// The oldest queued message
+    pub fn next(&self) -> Result<Option<OutboxEntry>, StorageError> {
+        match self.nonces.keys().next() {
+            Some(&id) => self.get(id),
+            None => Ok(None),
+        }
+    }
+
+    /// All of the queued messages, oldest first
+    pub fn pending(&self) -> Result<Vec<OutboxEntry>, StorageError> {
+        self.storage
+            .scan(&self.prefix)?
+            .into_iter()
+            .filter_map(|(key, data)| {
+                let id = u64::from_str_radix(&key[self.prefix.len()..], 16).ok()?;
+                Some(OutboxEntry::decode(id, &data))
+            })
+            .collect()
+    }
+
+    /// Record that this message is about to be sent
+    ///
+    /// This returns the entry with its new number of attempts
+    pub fn mark_sent(&mut self, id: u64) -> Result<Option<OutboxEntry>, StorageError> {
+        let mut entry = match self.get(id)? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        entry.attempts += 1;
+        self.storage.put(&self.key(id), &entry.encode())?;
+        Ok(Some(entry))
+    }
+
+    /// Remove this message, because it was delivered (or should be dropped)
+    pub fn ack(&mut self, id: u64) -> Result<Option<OutboxEntry>, StorageError> {
+        if self.nonces.remove(&id).is_none() {
+            return Ok(None);
+        }
+        match self.storage.remove(&self.key(id))? {
+            Some(data) => OutboxEntry::decode(id, &data).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Remove the message this is an echo of, by its `client-nonce`.
+    ///
+    /// This returns the delivered message
+    pub fn observe(&mut self, msg: &Commands<'_>) -> Result<Option<OutboxEntry>, StorageError> {
+        let nonce = match msg {
+            Commands::Privmsg(msg) => msg.client_nonce(),
+            _ => None,
+        };
+        let id = nonce.and_then(|nonce| {
+            self.nonces
+                .iter()
+                .find_map(|(&id, queued)| (queued == nonce).then(|| id))
+        });
+        match id {
+            Some(id) => self.ack(id),
+            None => Ok(None),
+        }
+    }
+
+    /// The number of queued messages
+    pub fn len(&self) -> usize {
+        self.nonces.len()
+    }
+
+    /// Whether no messages are queued
+    pub fn is_empty(&self) -> bool {
+        self.nonces.is_empty()
+    }
+
+    /// Get the storage back
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    fn get(&self, id: u64) -> Result<Option<OutboxEntry>, StorageError> {
+        match self.storage.get(&self.key(id))? {
+            Some(data) => OutboxEntry::decode(id, &data).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // ids are fixed-width so the keys sort in the order they were queued
+    fn key(&self, id: u64) -> String {
+        format!("{}{:016x}", self.prefix, id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{commands, irc::parse, storage::MemoryStorage, FromIrcMessage as _};
+
+    #[test]
+    fn resume() {
+        let mut outbox = Outbox::open(MemoryStorage::new(), "outbox/").unwrap();
+        let first = outbox.push(commands::privmsg("#museun", "first")).unwrap();
+        let second = outbox.push(commands::privmsg("#museun", "second")).unwrap();
+        assert_ne!(first.nonce, second.nonce);
+        assert_eq!(
+            first.line,
+            format!("@client-nonce={} PRIVMSG #museun :first\r\n", first.nonce).into_bytes()
+        );
+
+        assert_eq!(outbox.next().unwrap().unwrap().id, first.id);
+        assert_eq!(outbox.mark_sent(first.id).unwrap().unwrap().attempts, 1);
+
+        let mut storage = outbox.into_inner();
+        storage.put("other", b"not an entry").unwrap();
+
+        let mut outbox = Outbox::open(storage, "outbox/").unwrap();
+        let pending = outbox.pending().unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].nonce, first.nonce);
+        assert_eq!(pending[0].attempts, 1);
+        assert_eq!(pending[1], second);
+
+        // ids continue after the queued ones
+        let third = outbox.push(commands::privmsg("#museun", "third")).unwrap();
+        assert_eq!(third.id, second.id + 1);
+
+        let echo = format!(
+            "@client-nonce={} :bot!bot@bot PRIVMSG #museun :first\r\n",
+            first.nonce
+        );
+        let echo = Commands::from_irc(parse(&echo).next().unwrap().unwrap()).unwrap();
+        assert_eq!(outbox.observe(&echo).unwrap().unwrap().id, first.id);
+        assert!(outbox.observe(&echo).unwrap().is_none());
+
+        assert_eq!(outbox.ack(second.id).unwrap(), Some(second));
+        assert_eq!(outbox.len(), 1);
+        assert!(outbox.mark_sent(first.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn invalid_entry() {
+        let mut storage = MemoryStorage::new();
+        storage.put("outbox/0000000000000000", b"garbage").unwrap();
+        assert!(matches!(
+            Outbox::open(storage, "outbox/"),
+            Err(StorageError::Encoding(..))
+        ));
+    }
+}
@@ -0,0 +1,70 @@
+use super::{Storage, StorageError};
+
+/// A [Storage] in a [sled](https://docs.rs/sled) tree
+#[derive(Debug, Clone)]
+pub struct SledStorage {
+    tree: sled::Tree,
+}
+
+impl SledStorage {
+    /// Open (or create) the sled database at this path, and store everything in its default tree
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, StorageError> {
+        let db = sled::open(path)?;
+        Ok(Self::new((*db).clone()))
+    }
+
+    /// Store everything in this tree, e.g. from `Db::open_tree` to share a database with other data
+    pub fn new(tree: sled::Tree) -> Self {
+        Self { tree }
+    }
+
+    /// Get the tree of this storage
+    pub fn tree(&self) -> &sled::Tree {
+        &self.tree
+    }
+}
+
+impl From<sled::Error> for StorageError {
+    fn from(err: sled::Error) -> Self {
+        Self::Backend(Box::new(err))
+    }
+}
+
+impl Storage for SledStorage {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        Ok(self.tree.get(key)?.map(|value| value.to_vec()))
+    }
+
+    fn put(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        self.tree.insert(key, value)?;
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        Ok(self.tree.remove(key)?.map(|value| value.to_vec()))
+    }
+
+    fn scan(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>, StorageError> {
+        self.tree
+            .scan_prefix(prefix)
+            .map(|item| {
+                let (key, value) = item?;
+                let key = String::from_utf8(key.to_vec())
+                    .map_err(|err| StorageError::Encoding(err.into()))?;
+                Ok((key, value.to_vec()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sled_storage() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let mut storage = SledStorage::new(db.open_tree("twitchchat").unwrap());
+        crate::storage::tests::check_storage(&mut storage);
+    }
+}
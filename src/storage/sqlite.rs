@@ -0,0 +1,101 @@
+use super::{Storage, StorageError};
+use rusqlite::{params, Connection, OptionalExtension as _};
+
+/// A [Storage] in a table of a [SQLite](https://docs.rs/rusqlite) database
+///
+/// The table has a `key TEXT PRIMARY KEY` and a `value BLOB` column, and is
+/// created if it doesn't exist.
+#[derive(Debug)]
+pub struct SqliteStorage {
+    conn: Connection,
+    table: String,
+}
+
+impl SqliteStorage {
+    /// The name of the table used by [SqliteStorage::open]
+    pub const DEFAULT_TABLE: &'static str = "twitchchat_storage";
+
+    /// Open (or create) the database at this path, and store everything in the default table
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, StorageError> {
+        Self::new(Connection::open(path)?, Self::DEFAULT_TABLE)
+    }
+
+    /// Store everything in this table of the database.
+    ///
+    /// The table name is quoted, but it should still not come from untrusted input
+    pub fn new(conn: Connection, table: &str) -> Result<Self, StorageError> {
+        let this = Self {
+            conn,
+            table: format!("\"{}\"", table.replace('"', "\"\"")),
+        };
+        this.conn.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} (key TEXT PRIMARY KEY, value BLOB NOT NULL)",
+                this.table
+            ),
+            [],
+        )?;
+        Ok(this)
+    }
+
+    /// Get the connection of this storage
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(err: rusqlite::Error) -> Self {
+        Self::Backend(Box::new(err))
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        let query = format!("SELECT value FROM {} WHERE key = ?1", self.table);
+        let value = self
+            .conn
+            .query_row(&query, params![key], |row| row.get(0))
+            .optional()?;
+        Ok(value)
+    }
+
+    fn put(&mut self, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        let query = format!(
+            "INSERT OR REPLACE INTO {} (key, value) VALUES (?1, ?2)",
+            self.table
+        );
+        self.conn.execute(&query, params![key, value])?;
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        let value = self.get(key)?;
+        let query = format!("DELETE FROM {} WHERE key = ?1", self.table);
+        self.conn.execute(&query, params![key])?;
+        Ok(value)
+    }
+
+    fn scan(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>, StorageError> {
+        // `LIKE` would need the prefix to be escaped, and isn't case sensitive
+        let query = format!(
+            "SELECT key, value FROM {} WHERE substr(key, 1, length(?1)) = ?1 ORDER BY key",
+            self.table
+        );
+        let mut stmt = self.conn.prepare(&query)?;
+        let rows = stmt.query_map(params![prefix], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_, _>>().map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqlite_storage() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut storage = SqliteStorage::new(conn, "my \"storage\"").unwrap();
+        crate::storage::tests::check_storage(&mut storage);
+    }
+}
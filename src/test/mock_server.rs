@@ -0,0 +1,519 @@
+use std::{
+    collections::HashSet,
+    io::{BufRead, BufReader, Result, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::rate_limit::RateLimit;
+
+/// A local server that speaks enough of Twitch's IRC dialect to run end-to-end
+/// tests against, without touching Twitch.
+///
+/// It listens on a random port of `127.0.0.1` and handles every connection on
+/// its own thread:
+/// * `CAP REQ` is acknowledged, and `NICK` is answered with the welcome
+///   messages (and a `GLOBALUSERSTATE` for non-anonymous users with the tags
+///   and commands capabilities)
+/// * `JOIN` and `PART` are echoed back, with the `NAMES` list (and a
+///   `ROOMSTATE` with the tags capability) after joining
+/// * `PING` is answered with a `PONG`
+/// * `PRIVMSG`s over the optional [rate limit](MockServer::with_rate_limit)
+///   are answered with a `msg_ratelimit` `NOTICE`
+///
+/// Every line it receives is recorded, and lines can be sent to every
+/// connected client, e.g. to test the handling of `RECONNECT`.
+///
+/// Connect to [MockServer::addr] with a plain TCP connector, e.g.
+/// `connector::async_io::Connector::custom(server.addr())`, or with a
+/// `SyncRunner`:
+///
+/// ```
+/// # use twitchchat::{runner::{Status, SyncRunner}, messages::Commands, test::MockServer, UserConfig};
+/// use std::net::TcpStream;
+///
+/// let server = MockServer::start().unwrap();
+///
+/// let config = UserConfig::builder().anonymous().enable_all_capabilities().build().unwrap();
+/// let stream = TcpStream::connect(server.addr()).unwrap();
+/// let mut runner = SyncRunner::connect(stream.try_clone().unwrap(), stream, &config).unwrap();
+/// runner.join("#museun").unwrap();
+///
+/// server.send(":museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n");
+/// loop {
+///     match runner.next_message().unwrap() {
+///         Status::Message(Commands::Privmsg(msg)) => break assert_eq!(msg.data(), "hello"),
+///         _ => continue,
+///     }
+/// }
+///
+/// assert!(server.received().contains(&"JOIN #museun\r\n".to_string()));
+/// ```
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    received: Vec<String>,
+    clients: Vec<Arc<Mutex<TcpStream>>>,
+    connections: usize,
+    rate_limit: Option<(u64, Duration)>,
+    shutdown: bool,
+}
+
+impl MockServer {
+    /// Start a server on a random local port
+    pub fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let accept_state = Arc::clone(&state);
+        std::thread::spawn(move || accept(listener, accept_state));
+
+        Ok(Self { addr, state })
+    }
+
+    /// Answer `PRIVMSG`s over `cap` per `period` with a `msg_ratelimit` `NOTICE`.
+    ///
+    /// This applies to the connections made after calling it
+    pub fn with_rate_limit(self, cap: u64, period: Duration) -> Self {
+        self.state().rate_limit.replace((cap, period));
+        self
+    }
+
+    /// The address the server is listening on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// How many connections have been accepted so far
+    pub fn connections(&self) -> usize {
+        self.state().connections
+    }
+
+    /// Every line the server has received, from every connection, in order
+    pub fn received(&self) -> Vec<String> {
+        self.state().received.clone()
+    }
+
+    /// Wait until a received line matches `pred`, returning whether one did before the `timeout`
+    pub fn wait_for<F>(&self, timeout: Duration, pred: F) -> bool
+    where
+        F: Fn(&str) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.state().received.iter().any(|line| pred(line)) {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    /// Send this raw line to every connected client
+    pub fn send(&self, line: &str) {
+        let clients = self.state().clients.clone();
+        for client in clients {
+            let _ = client.lock().unwrap().write_all(line.as_bytes());
+        }
+    }
+
+    /// Tell every connected client to reconnect, and close their connections
+    pub fn reconnect(&self) {
+        self.send(":tmi.twitch.tv RECONNECT\r\n");
+        self.disconnect();
+    }
+
+    /// Close every connection, without telling the clients anything
+    pub fn disconnect(&self) {
+        for client in std::mem::take(&mut self.state().clients) {
+            let _ = client.lock().unwrap().shutdown(Shutdown::Both);
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.state().shutdown = true;
+        self.disconnect();
+        // wake up the accept loop so it can see the shutdown
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+fn accept(listener: TcpListener, state: Arc<Mutex<State>>) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(..) => continue,
+        };
+
+        let (reader, writer, rate_limit) = {
+            let mut state = state.lock().unwrap();
+            if state.shutdown {
+                return;
+            }
+            let writer = match stream.try_clone() {
+                Ok(writer) => Arc::new(Mutex::new(writer)),
+                Err(..) => continue,
+            };
+            state.connections += 1;
+            state.clients.push(Arc::clone(&writer));
+            let rate_limit = state
+                .rate_limit
+                .map(|(cap, period)| RateLimit::full(cap, period));
+            (stream, writer, rate_limit)
+        };
+
+        let mut client = Client {
+            state: Arc::clone(&state),
+            writer,
+            nick: String::new(),
+            caps: HashSet::new(),
+            rate_limit,
+        };
+        std::thread::spawn(move || client.run(reader));
+    }
+}
+
+struct Client {
+    state: Arc<Mutex<State>>,
+    writer: Arc<Mutex<TcpStream>>,
+    nick: String,
+    caps: HashSet<String>,
+    rate_limit: Option<RateLimit>,
+}
+
+impl Client {
+    fn run(&mut self, reader: TcpStream) {
+        let mut reader = BufReader::new(reader);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match reader.read_line(&mut line) {
+                Ok(0) | Err(..) => return,
+                Ok(..) => {}
+            }
+
+            self.state.lock().unwrap().received.push(line.clone());
+            if self.handle(line.trim_end()).is_err() {
+                return;
+            }
+        }
+    }
+
+    fn handle(&mut self, line: &str) -> Result<()> {
+        let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            "CAP" => {
+                let caps = args.strip_prefix("REQ :").unwrap_or_default();
+                for cap in caps.split_whitespace() {
+                    self.caps.insert(cap.to_string());
+                    self.send(&format!(":tmi.twitch.tv CAP * ACK :{}", cap))?;
+                }
+            }
+
+            "NICK" => {
+                self.nick = args.to_string();
+                self.welcome()?;
+            }
+
+            "JOIN" => {
+                for channel in args.split(',') {
+                    self.join(channel)?;
+                }
+            }
+
+            "PART" => {
+                let nick = &self.nick;
+                self.send(&format!(
+                    ":{}!{}@{}.tmi.twitch.tv PART {}",
+                    nick, nick, nick, args
+                ))?;
+            }
+
+            "PING" => {
+                let token = args.trim_start_matches(':');
+                self.send(&format!(":tmi.twitch.tv PONG tmi.twitch.tv :{}", token))?;
+            }
+
+            "PRIVMSG" => {
+                let channel = args.split(' ').next().unwrap_or_default();
+                if let Some(Err(..)) = self.rate_limit.as_mut().map(|limit| limit.consume(1)) {
+                    self.send(&format!(
+                        "@msg-id=msg_ratelimit :tmi.twitch.tv NOTICE {} :Your message was not sent because you are sending messages too quickly.",
+                        channel
+                    ))?;
+                }
+            }
+
+            "QUIT" => {
+                self.writer.lock().unwrap().shutdown(Shutdown::Both)?;
+            }
+
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn welcome(&mut self) -> Result<()> {
+        let nick = self.nick.clone();
+        self.send(&format!(":tmi.twitch.tv 001 {} :Welcome, GLHF!", nick))?;
+        self.send(&format!(
+            ":tmi.twitch.tv 002 {} :Your host is tmi.twitch.tv",
+            nick
+        ))?;
+        self.send(&format!(
+            ":tmi.twitch.tv 003 {} :This server is rather new",
+            nick
+        ))?;
+        self.send(&format!(":tmi.twitch.tv 004 {} :-", nick))?;
+        self.send(&format!(":tmi.twitch.tv 375 {} :-", nick))?;
+        self.send(&format!(
+            ":tmi.twitch.tv 372 {} :You are in a maze of twisty passages.",
+            nick
+        ))?;
+        self.send(&format!(":tmi.twitch.tv 376 {} :>", nick))?;
+
+        let anonymous = nick.starts_with("justinfan");
+        if !anonymous && self.has_cap("tags") && self.has_cap("commands") {
+            self.send(&format!(
+                "@badge-info=;badges=;color=;display-name={};emote-sets=0;user-id=1234;user-type= :tmi.twitch.tv GLOBALUSERSTATE",
+                nick
+            ))?;
+        }
+        Ok(())
+    }
+
+    fn join(&mut self, channel: &str) -> Result<()> {
+        let nick = self.nick.clone();
+        self.send(&format!(
+            ":{}!{}@{}.tmi.twitch.tv JOIN {}",
+            nick, nick, nick, channel
+        ))?;
+        self.send(&format!(
+            ":{}.tmi.twitch.tv 353 {} = {} :{}",
+            nick, nick, channel, nick
+        ))?;
+        self.send(&format!(
+            ":{}.tmi.twitch.tv 366 {} {} :End of /NAMES list",
+            nick, nick, channel
+        ))?;
+
+        if self.has_cap("tags") {
+            self.send(&format!(
+                "@emote-only=0;followers-only=-1;r9k=0;room-id=1;slow=0;subs-only=0 :tmi.twitch.tv ROOMSTATE {}",
+                channel
+            ))?;
+        }
+        Ok(())
+    }
+
+    fn has_cap(&self, name: &str) -> bool {
+        self.caps.contains(&format!("twitch.tv/{}", name))
+    }
+
+    fn send(&self, line: &str) -> Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(line.as_bytes())?;
+        writer.write_all(b"\r\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commands,
+        messages::{Commands, MessageId, Notice},
+        runner::{Error, Identity, Status, SyncRunner},
+        UserConfig,
+    };
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn connect(server: &MockServer, config: &UserConfig) -> SyncRunner<TcpStream, TcpStream> {
+        let stream = TcpStream::connect(server.addr()).unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        SyncRunner::connect(stream.try_clone().unwrap(), stream, config).unwrap()
+    }
+
+    fn user_config() -> UserConfig {
+        UserConfig::builder()
+            .name("museun")
+            .token(format!("oauth:{}", "a".repeat(30)))
+            .enable_all_capabilities()
+            .build()
+            .unwrap()
+    }
+
+    fn next_notice(runner: &mut SyncRunner<TcpStream, TcpStream>) -> Notice<'static> {
+        loop {
+            match runner.next_message().unwrap() {
+                Status::Message(Commands::Notice(msg)) => break msg,
+                Status::Message(..) => continue,
+                status => panic!("unexpected status: {:?}", status),
+            }
+        }
+    }
+
+    #[test]
+    fn register_and_join() {
+        let server = MockServer::start().unwrap();
+        let mut runner = connect(&server, &user_config());
+
+        match runner.identity() {
+            Identity::Full { name, user_id, .. } => {
+                assert_eq!(name, "museun");
+                assert_eq!(*user_id, 1234);
+            }
+            identity => panic!("unexpected identity: {:?}", identity),
+        }
+
+        runner.join("#museun").unwrap();
+        assert!(runner.is_on_channel("#museun"));
+
+        runner.writer().encode(commands::ping("1234")).unwrap();
+        loop {
+            match runner.next_message().unwrap() {
+                Status::Message(Commands::Pong(msg)) => break assert_eq!(msg.token(), "1234"),
+                Status::Message(..) => continue,
+                status => panic!("unexpected status: {:?}", status),
+            }
+        }
+
+        runner.part("#museun").unwrap();
+        assert!(!runner.is_on_channel("#museun"));
+
+        runner.quit().unwrap();
+        assert!(server.wait_for(TIMEOUT, |line| line == "QUIT\r\n"));
+
+        let received = server.received();
+        assert!(received[0].starts_with("CAP REQ :"));
+        assert!(received.contains(&"NICK museun\r\n".to_string()));
+    }
+
+    #[test]
+    fn rate_limit() {
+        let server = MockServer::start()
+            .unwrap()
+            .with_rate_limit(1, Duration::from_secs(30));
+        let mut runner = connect(&server, &user_config());
+        runner.join("#museun").unwrap();
+
+        let writer = runner.writer();
+        writer
+            .encode(commands::privmsg("#museun", "hello"))
+            .unwrap();
+        writer
+            .encode(commands::privmsg("#museun", "world"))
+            .unwrap();
+
+        assert!(matches!(
+            next_notice(&mut runner).msg_id(),
+            Some(MessageId::MsgRatelimit)
+        ));
+    }
+
+    #[test]
+    fn reconnect() {
+        let server = MockServer::start().unwrap();
+        let config = UserConfig::builder().anonymous().build().unwrap();
+
+        let mut runner = connect(&server, &config);
+        server.reconnect();
+        let err = loop {
+            match runner.next_message() {
+                Ok(Status::Message(..)) => continue,
+                result => break result,
+            }
+        };
+        assert!(matches!(err, Err(Error::ShouldReconnect)));
+
+        let runner = connect(&server, &config);
+        assert_eq!(server.connections(), 2);
+        assert!(matches!(runner.identity(), Identity::Anonymous { .. }));
+    }
+
+    #[test]
+    #[cfg(feature = "async-io")]
+    fn async_runner() {
+        use crate::{connector::async_io::Connector, AsyncRunner};
+
+        let server = MockServer::start().unwrap();
+        let connector = Connector::custom(server.addr()).unwrap();
+
+        futures_lite::future::block_on(async {
+            let mut runner = AsyncRunner::connect(connector, &user_config())
+                .await
+                .unwrap();
+            assert_eq!(runner.identity.username(), "museun");
+
+            runner.join("#museun").await.unwrap();
+            server.send(":museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n");
+            loop {
+                match runner.next_message().await.unwrap() {
+                    Status::Message(Commands::Privmsg(msg)) => {
+                        break assert_eq!(msg.data(), "hello")
+                    }
+                    Status::Message(..) => continue,
+                    status => panic!("unexpected status: {:?}", status),
+                }
+            }
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "async-io")]
+    fn async_send_rejected() {
+        use crate::{connector::async_io::Connector, runner::RejectReason, AsyncRunner};
+
+        let server = MockServer::start().unwrap();
+        let connector = Connector::custom(server.addr()).unwrap();
+
+        futures_lite::future::block_on(async {
+            let mut runner = AsyncRunner::connect(connector, &user_config())
+                .await
+                .unwrap();
+            runner.join("#museun").await.unwrap();
+            let rejections = runner.send_rejections();
+
+            runner
+                .writer()
+                .encode(commands::privmsg("#museun", "hello"))
+                .await
+                .unwrap();
+            let sent = |line: &str| line.starts_with("PRIVMSG");
+            while !server.wait_for(Duration::from_millis(100), sent) {
+                runner.step().await.unwrap();
+            }
+
+            server.send(
+                "@msg-id=msg_emoteonly :tmi.twitch.tv NOTICE #museun :This room is in emote-only mode.\r\n",
+            );
+            loop {
+                match runner.next_message().await.unwrap() {
+                    Status::Message(Commands::Notice(..)) => break,
+                    Status::Message(..) => continue,
+                    status => panic!("unexpected status: {:?}", status),
+                }
+            }
+
+            let rejected = rejections.try_recv().unwrap();
+            assert_eq!(rejected.reason, RejectReason::EmoteOnly);
+            assert_eq!(rejected.message.as_deref(), Some("PRIVMSG #museun :hello"));
+        });
+    }
+}
@@ -1,5 +1,4 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
 
 use crate::irc::{TagIndices, Tags};
 use crate::MaybeOwned;
@@ -59,11 +58,14 @@ impl std::error::Error for BuilderError {}
 /// // you can get the unescaped value with `get_unescaped`
 /// assert_eq!(tags.get_unescaped("my-message").unwrap(), "my\nmessage\nspans\nmultiple\nlines");
 /// ```
+///
+/// The tags are rendered in the order they were first added, so the output is
+/// stable between runs.
 #[derive(Default, Debug, Clone)]
 pub struct TagsBuilder<'a> {
     // the spec says 'last' key wins, and the order is irrelevant.
-    // so lets just use a hashmap
-    tags: HashMap<Cow<'a, str>, Cow<'a, str>>,
+    // but we keep the insertion order so the rendered string is deterministic
+    tags: Vec<(Cow<'a, str>, Cow<'a, str>)>,
 }
 
 impl<'a> TagsBuilder<'a> {
@@ -76,14 +78,14 @@ impl<'a> TagsBuilder<'a> {
     ///
     /// # NOTE
     /// `value` can be empty.
-    /// `key` will replace any previous keys
+    /// `key` will replace any previous keys, keeping its original position
     ///
     pub fn add<K, V>(mut self, key: K, value: V) -> Self
     where
         K: Into<Cow<'a, str>>,
         V: Into<Cow<'a, str>>,
     {
-        self.tags.insert(key.into(), value.into());
+        self.insert(key.into(), value.into());
         self
     }
 
@@ -92,13 +94,19 @@ impl<'a> TagsBuilder<'a> {
     /// # NOTE
     /// This'll override any previously set keys.
     pub fn merge(mut self, tags: &Tags<'_>) -> Self {
-        self.tags.extend(
-            tags.iter()
-                .map(|(k, v)| (Cow::Owned(k.to_owned()), Cow::Owned(v.to_owned()))),
-        );
+        for (k, v) in tags.iter() {
+            self.insert(Cow::Owned(k.to_owned()), Cow::Owned(v.to_owned()));
+        }
         self
     }
 
+    fn insert(&mut self, key: Cow<'a, str>, value: Cow<'a, str>) {
+        match self.tags.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.tags.push((key, value)),
+        }
+    }
+
     /// Build the tags reference string and its indices.
     ///
     /// # Errors
@@ -236,7 +244,7 @@ mod tests {
         use crate::FromIrcMessage as _;
 
         let msg = "@badge-info=;badges=broadcaster/1;color=#FF69B4;display-name=museun;emote-only=1;emotes=25:0-4,6-10/81274:12-17;flags=;id=4e160a53-5482-4764-ba28-f224cd59a51f;mod=0;room-id=23196011;subscriber=0;tmi-sent-ts=1601079032426;turbo=0;user-id=23196011;user-type= :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :Kappa Kappa VoHiYo\r\n";
-        let msg = crate::IrcMessage::parse(crate::MaybeOwned::Borrowed(msg)).unwrap();
+        let msg = crate::IrcMessage::parse(MaybeOwned::Borrowed(msg)).unwrap();
         let pm = crate::messages::Privmsg::from_irc(msg).unwrap();
         let tags = pm.tags();
 
@@ -282,4 +290,24 @@ mod tests {
             assert_eq!(tags.get_unescaped("color").unwrap(), "#FF0000");
         }
     }
+
+    #[test]
+    fn insertion_order() {
+        let user_tags = TagsBuilder::new()
+            .add("color", "#FF00FF")
+            .add("display-name", "museun")
+            .add("badges", "broadcaster/1")
+            .add("color", "#FF0000")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            &*user_tags.data,
+            "@color=#FF0000;display-name=museun;badges=broadcaster/1"
+        );
+
+        let tags = user_tags.as_tags();
+        let keys = tags.iter().map(|(k, _)| k).collect::<Vec<_>>();
+        assert_eq!(keys, vec!["color", "display-name", "badges"]);
+    }
 }
@@ -1,8 +1,7 @@
 //! Traits and Structs that can be used to process tags that themselves contain lists of information.
 //! Those lists add information or interpretation to messages and senders, and are expressed in somewhat consistent formats.
 
-use derive_more::{Constructor, Deref, From};
-use parse_display::{Display, FromStr};
+use crate::twitch::ParseError;
 use std::default::Default;
 use std::iter::FilterMap;
 use std::ops::Range;
@@ -23,11 +22,56 @@ pub struct SeparatorInfo {
 
 /// Like range, but implements FromStr.
 /// Indicates character ranges in Twitch messages.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Deref, From, Display, FromStr, Default)]
-#[from(forward)]
-#[display("{0.start}-{0.end}")] // this also defines from_str
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
-pub struct MsgRange(#[from_str(default)] Range<u16>);
+pub struct MsgRange(Range<u16>);
+
+impl std::ops::Deref for MsgRange {
+    type Target = Range<u16>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> From<T> for MsgRange
+where
+    T: Into<Range<u16>>,
+{
+    fn from(range: T) -> Self {
+        Self(range.into())
+    }
+}
+
+impl std::fmt::Display for MsgRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.0.start, self.0.end)
+    }
+}
+
+impl FromStr for MsgRange {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s.split_once('-').ok_or(ParseError::InvalidValue)?;
+        let start = start.parse().map_err(|_| ParseError::InvalidValue)?;
+        let end = end.parse().map_err(|_| ParseError::InvalidValue)?;
+        Ok(Self(start..end))
+    }
+}
+
+// `Range` isn't ordered, so ranges are ordered by their start, then their end
+impl PartialOrd for MsgRange {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MsgRange {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.0.start, self.0.end).cmp(&(other.0.start, other.0.end))
+    }
+}
 
 /// Trait that applies information stored in a tag that adds attribute information to specific parts of a message. (like emote interpretation)
 pub trait Attribution<Ref, Attr>: Sized
@@ -64,6 +108,30 @@ where
             .filter_map(|x| Attr::from_str(x).ok())
     }
 
+    /// Parses a single attribution, reporting why it could not be parsed.
+    ///
+    /// This rejects anything [Attribution::parse] would drop, including the
+    /// attributes it skips.
+    fn parse_strict(item: &str) -> Result<Self, AttributionErrorKind> {
+        let (left, right) = split_pair(item, Self::get_range_attribute_separator())
+            .ok_or(AttributionErrorKind::Invalid)?;
+        let reference = <Ref as FromStr>::from_str(&left)
+            .map_err(|_| AttributionErrorKind::InvalidReference)?;
+
+        let attributes = match right.as_str() {
+            "" => vec![],
+            right => right
+                .split(Self::get_attribute_separator())
+                .map(|attr| {
+                    Attr::from_str(attr).map_err(|_| AttributionErrorKind::InvalidAttribute {
+                        attribute: attr.to_string(),
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+        };
+        Ok(Self::new(reference, attributes.into_iter()))
+    }
+
     /// Parses a single attribution.
     fn parse(item: &str) -> Option<Self> {
         split_pair(item, Self::get_range_attribute_separator()).and_then(|(left, right)| {
@@ -87,16 +155,127 @@ pub(crate) fn split_pair<'a>(input: impl AsRef<str> + 'a, sep: char) -> Option<(
         .into()
 }
 
+/// Why an element of an [AttributionVec] could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AttributionErrorKind {
+    /// The element could not be parsed as a whole
+    Invalid,
+    /// The part before the range/attribute separator could not be parsed
+    InvalidReference,
+    /// One of the attributes could not be parsed
+    InvalidAttribute {
+        /// The attribute, as it appeared in the tag
+        attribute: String,
+    },
+}
+
+/// An element of an [AttributionVec] that could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributionError {
+    /// The position of the element in the tag
+    pub index: usize,
+    /// The element, as it appeared in the tag
+    pub raw: String,
+    /// Why it could not be parsed
+    pub kind: AttributionErrorKind,
+}
+
+impl std::fmt::Display for AttributionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid element #{} '{}': ", self.index, self.raw)?;
+        match &self.kind {
+            AttributionErrorKind::Invalid => f.write_str("unknown format"),
+            AttributionErrorKind::InvalidReference => f.write_str("invalid reference"),
+            AttributionErrorKind::InvalidAttribute { attribute } => {
+                write!(f, "invalid attribute '{}'", attribute)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AttributionError {}
+
+/// The elements of a tag that [AttributionVec::parse_strict] could not parse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributionErrors {
+    /// Every element that could not be parsed, in the order they appeared in the tag
+    pub errors: Vec<AttributionError>,
+}
+
+impl std::fmt::Display for AttributionErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} invalid element(s)", self.errors.len())?;
+        if let Some(first) = self.errors.first() {
+            write!(f, ", first: {}", first)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AttributionErrors {}
+
+/// An element of a tag, with where it appeared in the value of the tag.
+///
+/// This maps e.g. a clickable badge back to the wire data, for tooltips or debugging.
+/// It derefs to the element
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Spanned<T> {
+    value: T,
+    raw: String,
+    span: Range<usize>,
+}
+
+impl<T> Spanned<T> {
+    /// The element as it appeared in the tag, and its byte range in the value of the tag
+    pub fn raw_span(&self) -> (&str, Range<usize>) {
+        (&self.raw, self.span.clone())
+    }
+
+    /// The parsed element
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Get the parsed element, without its span
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
+
+impl<T> std::ops::Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
 /// We wrap the iterator of attributions so that we can define from_str on it.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Deref, Constructor)]
+///
+/// The elements are kept in the order they appeared in the tag.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct AttributionVec<Ref: FromStr, Attr: FromStr, T: Attribution<Ref, Attr>> {
-    #[deref]
     element: Vec<T>,
     _phantom_data: std::marker::PhantomData<Ref>,
     _phantom_data_2: std::marker::PhantomData<Attr>,
 }
 
+impl<Ref, Attr, T> std::ops::Deref for AttributionVec<Ref, Attr, T>
+where
+    Ref: FromStr,
+    Attr: FromStr,
+    T: Attribution<Ref, Attr>,
+{
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.element
+    }
+}
+
 impl<Ref, Attr, T> From<Vec<T>> for AttributionVec<Ref, Attr, T>
 where
     Ref: FromStr,
@@ -108,6 +287,101 @@ where
     }
 }
 
+impl<Ref, Attr, T> AttributionVec<Ref, Attr, T>
+where
+    Ref: FromStr,
+    Attr: FromStr,
+    T: Attribution<Ref, Attr>,
+{
+    /// Wrap these elements
+    pub fn new(
+        element: Vec<T>,
+        _phantom_data: std::marker::PhantomData<Ref>,
+        _phantom_data_2: std::marker::PhantomData<Attr>,
+    ) -> Self {
+        Self {
+            element,
+            _phantom_data,
+            _phantom_data_2,
+        }
+    }
+
+    /// Parses every element of the tag, also returning the ones that could not be parsed.
+    ///
+    /// The elements that could be parsed are the same ones [FromStr] keeps.
+    pub fn parse_with_errors(s: &str) -> (Self, Vec<AttributionError>) {
+        if s.is_empty() {
+            return (vec![].into(), vec![]);
+        }
+
+        let split_char = <T as Attribution<Ref, Attr>>::get_attribution_separator();
+        let (mut elements, mut errors) = (vec![], vec![]);
+        for (index, raw) in s.split(split_char).enumerate() {
+            match <T as Attribution<Ref, Attr>>::parse_strict(raw) {
+                Ok(element) => elements.push(element),
+                Err(kind) => {
+                    errors.push(AttributionError {
+                        index,
+                        raw: raw.to_string(),
+                        kind,
+                    });
+                    // it may still be partially parsed, e.g. without its invalid attributes
+                    elements.extend(<T as Attribution<Ref, Attr>>::parse(raw));
+                }
+            }
+        }
+        (elements.into(), errors)
+    }
+
+    /// Parses every element of the tag, keeping where each one is in the tag.
+    ///
+    /// The elements that could be parsed are the same ones [FromStr] keeps.
+    ///
+    /// ```
+    /// # use twitchchat::twitch::{Badge, BadgeVec};
+    /// let badges = BadgeVec::parse_spanned("moderator/1,oops,bits/100");
+    /// assert_eq!(*badges[1], Badge::Bits(100));
+    /// assert_eq!(badges[1].raw_span(), ("bits/100", 17..25));
+    /// ```
+    pub fn parse_spanned(s: &str) -> Vec<Spanned<T>> {
+        let split_char = <T as Attribution<Ref, Attr>>::get_attribution_separator();
+        let mut start = 0;
+        s.split(split_char)
+            .filter_map(|raw| {
+                let span = start..start + raw.len();
+                start = span.end + split_char.len_utf8();
+                let value = <T as Attribution<Ref, Attr>>::parse(raw)?;
+                Some(Spanned {
+                    value,
+                    raw: raw.to_string(),
+                    span,
+                })
+            })
+            .collect()
+    }
+
+    /// Parses every element of the tag, failing if any of them could not be parsed.
+    ///
+    /// Parsing with [FromStr] instead skips those elements.
+    ///
+    /// ```
+    /// # use twitchchat::twitch::{attributes::AttributionErrorKind, EmoteVec};
+    /// let err = EmoteVec::parse_strict("25:0-4/1902:6-x").unwrap_err();
+    /// assert_eq!(err.errors[0].index, 1);
+    /// assert_eq!(err.errors[0].raw, "1902:6-x");
+    /// assert!(matches!(
+    ///     &err.errors[0].kind,
+    ///     AttributionErrorKind::InvalidAttribute { attribute } if attribute == "6-x"
+    /// ));
+    /// ```
+    pub fn parse_strict(s: &str) -> Result<Self, AttributionErrors> {
+        match Self::parse_with_errors(s) {
+            (this, errors) if errors.is_empty() => Ok(this),
+            (_, errors) => Err(AttributionErrors { errors }),
+        }
+    }
+}
+
 impl<Ref, Attr, T> FromStr for AttributionVec<Ref, Attr, T>
 where
     Ref: FromStr,
@@ -1,20 +1,20 @@
 #![allow(missing_docs)]
-use crate::twitch::attributes::{Attribution, AttributionVec, SeparatorInfo};
+use crate::twitch::attributes::{Attribution, AttributionErrorKind, AttributionVec, SeparatorInfo};
 /// The kind of the [badges] that are associated with messages.
 ///
 /// Any unknown (e.g. custom badges/sub events, etc) are placed into the [Unknown] variant.
 ///
 /// [badges]: Badge
 /// [Unknown]: BadgeKind::Unknown
-use derive_more::IsVariant;
-use parse_display::{Display, FromStr};
+use crate::twitch::ParseError;
+use std::fmt;
 use std::str::FromStr;
 
 /// Describes the kind of badge owned by the user.
+///
+/// A badge is written as `name/version` in the `badges` and `badge-info` tags.
 #[non_exhaustive]
-#[derive(Display, FromStr, Debug, Clone, PartialEq, Eq, Hash, IsVariant)]
-#[display(style = "kebab-case")] // this also defines the FromStr style
-#[display("{}/1")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub enum Badge {
     /// Admin badge
@@ -35,34 +35,212 @@ pub enum Badge {
     Partner,
 
     /// Bits badge
-    #[display("bits/{0}")]
     Bits(u64), //u64: number of bits
 
     /// GlobalMod badge
-    #[display("global_mod/1")] // legacy badge in snake_case
-    GlobalMod,
+    GlobalMod, // legacy badge in snake_case
 
     /// Subscriber badge with tier info
-    /// This is being parsed if the data number matches the format [num]0[num]
-    #[display("subscriber/{0}0{1:>02}")]
-    TierSubscriber(u8, #[from_str(regex = "[0-9]{2,}")] u32), //u8: Subscription tier, u32: Subscription months (at least two characters)
+    /// This is being parsed if the version is a tier digit (`1` to `9`), a `0`
+    /// and at least two digits of months, e.g. `3012`
+    TierSubscriber(u8, u32), //u8: Subscription tier, u32: Subscription months
 
     /// Subscriber badge without tier info.
-    /// This is being parsed if the data number didn't match the pattern in TierSubscriber.
-    #[display("subscriber/{0}")]
+    /// This is being parsed if the version didn't match the pattern in TierSubscriber.
     NoTierSubscriber(u32), //u32: Subscription months
 
     /// Unknown badge. Likely a custom badge
-    #[display("{0}/{1}")] // displays only the inside data
     Unknown(String, u64),
 }
 
+impl fmt::Display for Badge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Admin => f.write_str("admin/1"),
+            Self::Broadcaster => f.write_str("broadcaster/1"),
+            Self::Moderator => f.write_str("moderator/1"),
+            Self::Staff => f.write_str("staff/1"),
+            Self::Turbo => f.write_str("turbo/1"),
+            Self::Premium => f.write_str("premium/1"),
+            Self::Vip => f.write_str("vip/1"),
+            Self::Partner => f.write_str("partner/1"),
+            Self::Bits(bits) => write!(f, "bits/{}", bits),
+            Self::GlobalMod => f.write_str("global_mod/1"),
+            Self::TierSubscriber(tier, months) => write!(f, "subscriber/{}0{:>02}", tier, months),
+            Self::NoTierSubscriber(months) => write!(f, "subscriber/{}", months),
+            Self::Unknown(name, version) => write!(f, "{}/{}", name, version),
+        }
+    }
+}
+
+impl FromStr for Badge {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (name, version) = input.split_once('/').ok_or(ParseError::InvalidValue)?;
+
+        let badge = match (name, version) {
+            ("admin", "1") => Self::Admin,
+            ("broadcaster", "1") => Self::Broadcaster,
+            ("moderator", "1") => Self::Moderator,
+            ("staff", "1") => Self::Staff,
+            ("turbo", "1") => Self::Turbo,
+            ("premium", "1") => Self::Premium,
+            ("vip", "1") => Self::Vip,
+            ("partner", "1") => Self::Partner,
+            ("global_mod", "1") => Self::GlobalMod,
+            ("bits", bits) => Self::Bits(parse_number(bits)?),
+            ("subscriber", months) => parse_subscriber(months)?,
+            // the known badges with another version end up here, e.g. `moderator/0`
+            (name, version) => Self::Unknown(name.to_string(), parse_number(version)?),
+        };
+        Ok(badge)
+    }
+}
+
+// `subscriber/3012` is a tier 3 subscription for 12 months, `subscriber/12` is just 12 months
+fn parse_subscriber(version: &str) -> Result<Badge, ParseError> {
+    let bytes = version.as_bytes();
+    if let [tier @ b'1'..=b'9', b'0', months @ ..] = bytes {
+        if months.len() >= 2 && months.iter().all(u8::is_ascii_digit) {
+            let months = version
+                .get(2..)
+                .ok_or(ParseError::InvalidValue)
+                .and_then(parse_number)?;
+            return Ok(Badge::TierSubscriber(*tier - b'0', months));
+        }
+    }
+    parse_number(version).map(Badge::NoTierSubscriber)
+}
+
+// only plain digits, `u64::from_str` would also take a leading `+`
+fn parse_number<T: FromStr>(input: &str) -> Result<T, ParseError> {
+    if input.is_empty() || !input.bytes().all(|c| c.is_ascii_digit()) {
+        return Err(ParseError::InvalidValue);
+    }
+    input.parse().map_err(|_| ParseError::InvalidValue)
+}
+
+// `is_admin`, `is_bits` etc for each variant
+macro_rules! is_variant {
+    ($($name:ident => $variant:ident $(($($field:tt),*))?;)*) => {
+        impl Badge {
+            $(
+                pub fn $name(&self) -> bool {
+                    matches!(self, Self::$variant $(($($field),*))?)
+                }
+            )*
+        }
+    };
+}
+
+is_variant! {
+    is_admin => Admin;
+    is_broadcaster => Broadcaster;
+    is_moderator => Moderator;
+    is_staff => Staff;
+    is_turbo => Turbo;
+    is_premium => Premium;
+    is_vip => Vip;
+    is_partner => Partner;
+    is_bits => Bits(..);
+    is_global_mod => GlobalMod;
+    is_tier_subscriber => TierSubscriber(..);
+    is_no_tier_subscriber => NoTierSubscriber(..);
+    is_unknown => Unknown(..);
+}
+
 impl Badge {
+    /// A subscriber badge of this tier (`1` to `3`), for this many months.
+    ///
+    /// Returns `None` for other tiers, because the tier and the months share
+    /// one number in the badge (e.g. `subscriber/3012`).
+    ///
+    /// ```
+    /// # use twitchchat::twitch::Badge;
+    /// let badge = Badge::subscriber(3, 12).unwrap();
+    /// assert_eq!(badge, Badge::TierSubscriber(3, 12));
+    /// assert_eq!(badge.to_tag_value(), "subscriber/3012");
+    ///
+    /// assert!(Badge::subscriber(0, 12).is_none());
+    /// ```
+    pub fn subscriber(tier: u8, months: u32) -> Option<Self> {
+        match tier {
+            1..=3 => Some(Self::TierSubscriber(tier, months)),
+            _ => None,
+        }
+    }
+
+    /// A bits badge for this many bits cheered, e.g. `bits/1000`
+    pub fn bits(bits: u64) -> Self {
+        Self::Bits(bits)
+    }
+
+    /// Any other badge with this version, e.g. `founder/0`.
+    ///
+    /// Returns `None` if the name is one of the known badges, or if the badge
+    /// can't be written into a tag (it is empty, or has a `/`, `,`, `;`, `=` or
+    /// a whitespace).
+    ///
+    /// ```
+    /// # use twitchchat::twitch::Badge;
+    /// let badge = Badge::custom("founder", 0).unwrap();
+    /// assert_eq!(badge.to_tag_value(), "founder/0");
+    ///
+    /// assert!(Badge::custom("moderator", 1).is_none());
+    /// assert!(Badge::custom("a,b", 1).is_none());
+    /// ```
+    pub fn custom(name: impl Into<String>, version: u64) -> Option<Self> {
+        let name = name.into();
+        let invalid = |c: char| matches!(c, '/' | ',' | ';' | '=') || c.is_whitespace();
+        if name.is_empty() || name.contains(invalid) {
+            return None;
+        }
+
+        let badge = Self::Unknown(name, version);
+        match badge.to_tag_value().parse::<Self>() {
+            Ok(parsed) if parsed == badge => Some(badge),
+            _ => None,
+        }
+    }
+
+    /// This badge as it is written in the `badges` or `badge-info` tag.
+    ///
+    /// Parsing the value gives back this badge, for every badge the parser
+    /// returns and every badge made with [subscriber](Self::subscriber),
+    /// [bits](Self::bits) or [custom](Self::custom). Badges built from the
+    /// variants directly may not: `NoTierSubscriber(3012)` is written as
+    /// `subscriber/3012`, which is read as a tier 3 subscription.
+    pub fn to_tag_value(&self) -> String {
+        self.to_string()
+    }
+
     // all other is_variant() functions are derived automatically
     /// Returns whether this badge is any kind of subscriber badge.
     pub(crate) fn is_subscriber(&self) -> bool {
         self.is_tier_subscriber() || self.is_no_tier_subscriber()
     }
+
+    /// The position of this badge in the order the official client shows them, lower is first.
+    ///
+    /// Channel roles come first, then subscriptions, then bits, then global badges.
+    pub fn display_rank(&self) -> u8 {
+        match self {
+            Self::Broadcaster => 0,
+            Self::Staff => 1,
+            Self::Admin => 2,
+            Self::GlobalMod => 3,
+            Self::Moderator => 4,
+            Self::Vip => 5,
+            Self::Partner => 6,
+            Self::TierSubscriber(..) | Self::NoTierSubscriber(..) => 7,
+            Self::Unknown(name, _) if name == "founder" => 7,
+            Self::Bits(..) => 8,
+            Self::Premium => 9,
+            Self::Turbo => 10,
+            Self::Unknown(..) => 11,
+        }
+    }
 }
 /// Metadata to the chat badges
 pub type BadgeInfo = Badge;
@@ -85,11 +263,40 @@ impl Attribution<Badge, u64> for Badge {
     fn parse(item: &str) -> Option<Self> {
         <Badge as FromStr>::from_str(item).ok()
     }
+
+    fn parse_strict(item: &str) -> Result<Self, AttributionErrorKind> {
+        <Badge as FromStr>::from_str(item).map_err(|_| AttributionErrorKind::Invalid)
+    }
+}
+
+/// The months of a subscription from the raw `badge-info` tag.
+///
+/// This reads the number directly, because it is always months in this tag
+/// (e.g. `subscriber/1012` isn't a tier 1 subscription).
+pub(crate) fn subscriber_months(badge_info: &str) -> Option<u32> {
+    badge_info.split(',').find_map(|badge| {
+        let (name, months) = badge.split_once('/')?;
+        match name {
+            "subscriber" | "founder" => months.parse().ok(),
+            _ => None,
+        }
+    })
 }
 
 /// Vector containing user badges
 pub type BadgeVec = AttributionVec<Badge, u64, Badge>;
 
+impl BadgeVec {
+    /// The badges in the order the official client shows them.
+    ///
+    /// Badges of the same [rank](Badge::display_rank) keep their order from the tag.
+    pub fn display_order(&self) -> Vec<&Badge> {
+        let mut badges = self.iter().collect::<Vec<_>>();
+        badges.sort_by_key(|badge| badge.display_rank());
+        badges
+    }
+}
+
 /*
  *//*
 /// An iterator over badges
@@ -147,10 +354,166 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_edge_cases() {
+        let badge_set: &[(&str, Badge)] = &[
+            // a known badge with another version is unknown
+            ("admin/2", Badge::Unknown("admin".into(), 2)),
+            ("moderator/0", Badge::Unknown("moderator".into(), 0)),
+            ("moderator/01", Badge::Unknown("moderator".into(), 1)),
+            ("Admin/1", Badge::Unknown("Admin".into(), 1)),
+            ("global-mod/1", Badge::Unknown("global-mod".into(), 1)),
+            ("/1", Badge::Unknown("".into(), 1)),
+            ("bits/0", Badge::Bits(0)),
+            // the tier is one digit, followed by a 0 and at least two digits
+            ("subscriber/0", Badge::NoTierSubscriber(0)),
+            ("subscriber/10", Badge::NoTierSubscriber(10)),
+            ("subscriber/100", Badge::NoTierSubscriber(100)),
+            ("subscriber/0012", Badge::NoTierSubscriber(12)),
+            ("subscriber/12012", Badge::NoTierSubscriber(12012)),
+            ("subscriber/1000", Badge::TierSubscriber(1, 0)),
+            ("subscriber/2005", Badge::TierSubscriber(2, 5)),
+            ("subscriber/30012", Badge::TierSubscriber(3, 12)),
+            ("subscriber/10123", Badge::TierSubscriber(1, 123)),
+        ];
+
+        for (raw, badge) in badge_set {
+            assert_eq!(Badge::from_str(raw).unwrap(), *badge, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn display() {
+        let badge_set: &[(Badge, &str)] = &[
+            (Badge::Admin, "admin/1"),
+            (Badge::Broadcaster, "broadcaster/1"),
+            (Badge::Moderator, "moderator/1"),
+            (Badge::Staff, "staff/1"),
+            (Badge::Turbo, "turbo/1"),
+            (Badge::Premium, "premium/1"),
+            (Badge::Vip, "vip/1"),
+            (Badge::Partner, "partner/1"),
+            (Badge::Bits(100), "bits/100"),
+            (Badge::GlobalMod, "global_mod/1"),
+            (Badge::TierSubscriber(1, 5), "subscriber/1005"),
+            (Badge::TierSubscriber(2, 123), "subscriber/20123"),
+            (Badge::NoTierSubscriber(3), "subscriber/3"),
+            (Badge::Unknown("founder".into(), 0), "founder/0"),
+        ];
+
+        for (badge, raw) in badge_set {
+            assert_eq!(badge.to_string(), *raw);
+            assert_eq!(Badge::from_str(raw).unwrap(), *badge);
+        }
+    }
+
+    #[test]
+    fn badge_vec_wire_order() {
+        let badges = BadgeVec::from_str("subscriber/6,broadcaster/1,bits/100,moderator/1").unwrap();
+        assert_eq!(
+            *badges,
+            vec![
+                Badge::NoTierSubscriber(6),
+                Badge::Broadcaster,
+                Badge::Bits(100),
+                Badge::Moderator
+            ]
+        );
+    }
+
+    #[test]
+    fn badge_vec_display_order() {
+        let badges = BadgeVec::from_str(
+            "premium/1,subscriber/6,bits/100,founder/0,moderator/1,glhf-pledge/1",
+        )
+        .unwrap();
+        assert_eq!(
+            badges.display_order(),
+            vec![
+                &Badge::Moderator,
+                &Badge::NoTierSubscriber(6),
+                &Badge::Unknown("founder".into(), 0),
+                &Badge::Bits(100),
+                &Badge::Premium,
+                &Badge::Unknown("glhf-pledge".into(), 1),
+            ]
+        );
+
+        // the wire order is untouched
+        assert_eq!(badges[0], Badge::Premium);
+    }
+
+    #[test]
+    fn badge_info_subscriber_months() {
+        assert_eq!(subscriber_months("subscriber/21"), Some(21));
+        assert_eq!(
+            subscriber_months("predictions/blue-1,subscriber/1012"),
+            Some(1012)
+        );
+        assert_eq!(subscriber_months("founder/3"), Some(3));
+        assert_eq!(subscriber_months("predictions/blue-1"), None);
+        assert_eq!(subscriber_months(""), None);
+    }
+
+    #[test]
+    fn constructors_round_trip() {
+        let mut badges = vec![
+            Badge::bits(0),
+            Badge::bits(100_000),
+            Badge::custom("founder", 0).unwrap(),
+            Badge::custom("glhf-pledge", 1).unwrap(),
+            Badge::custom("predictions", 12).unwrap(),
+        ];
+        for tier in 1..=3 {
+            for &months in &[0, 1, 9, 10, 99, 100, 1012] {
+                badges.push(Badge::subscriber(tier, months).unwrap());
+            }
+        }
+
+        for badge in badges {
+            let value = badge.to_tag_value();
+            assert_eq!(Badge::from_str(&value).unwrap(), badge, "{}", value);
+        }
+    }
+
+    #[test]
+    fn constructors_invalid() {
+        assert!(Badge::subscriber(0, 1).is_none());
+        assert!(Badge::subscriber(4, 1).is_none());
+        assert!(Badge::subscriber(10, 1).is_none());
+
+        for name in &[
+            "",
+            "admin",
+            "bits",
+            "subscriber",
+            "global_mod",
+            "a/b",
+            "a,b",
+            "a b",
+            "a;b",
+        ] {
+            assert!(Badge::custom(*name, 1).is_none(), "{}", name);
+        }
+    }
+
     #[test]
     fn parse_invalid() {
-        let badge_str = "this_badge_is_invalid";
-        let badge_result = Badge::from_str(badge_str);
-        assert!(badge_result.is_err())
+        for badge_str in &[
+            "this_badge_is_invalid",
+            "",
+            "/",
+            "bits/",
+            "bits/x",
+            "bits/+5",
+            "subscriber/x",
+            "subscriber/30x1",
+            "moderator/-1",
+            "staff/1 ",
+            "a/b/1",
+            "predictions/blue-1",
+        ] {
+            assert!(Badge::from_str(badge_str).is_err(), "{}", badge_str);
+        }
     }
 }
@@ -0,0 +1,200 @@
+use super::Badge;
+use std::collections::HashMap;
+
+/// The size of a badge image
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum BadgeScale {
+    /// 18x18 pixels
+    X1,
+    /// 36x36 pixels
+    X2,
+    /// 72x72 pixels
+    X4,
+}
+
+impl Default for BadgeScale {
+    fn default() -> Self {
+        Self::X1
+    }
+}
+
+impl BadgeScale {
+    // Twitch numbers the scales, rather than naming them
+    fn as_path(self) -> &'static str {
+        match self {
+            Self::X1 => "1",
+            Self::X2 => "2",
+            Self::X4 => "3",
+        }
+    }
+}
+
+// the image ids of the global badges that don't change
+const GLOBAL_BADGES: &[(&str, &str, &str)] = &[
+    ("admin", "1", "9ef7e029-4cdf-4d4d-a0d5-e2b3fb2583fe"),
+    ("bits", "1", "73b5c3fb-24f9-4a82-a852-2f475b59411c"),
+    ("bits", "100", "09d93036-e7ce-431c-9a9e-7044297133f2"),
+    ("broadcaster", "1", "5527c58c-fb7d-422d-b71b-f309dcb85cc1"),
+    ("global_mod", "1", "9384c43e-4ce7-4e94-b2a1-b93656896eba"),
+    ("moderator", "1", "3267646d-33f0-4b17-b3df-f923a41db1d0"),
+    ("partner", "1", "d12a2e27-16f6-41d0-ab77-b780518f00a3"),
+    ("premium", "1", "bbbe0db0-a598-423e-86d0-f9fb98ca1933"),
+    ("staff", "1", "d97c37bd-a6f5-4c38-8f57-4e4bef88af34"),
+    ("turbo", "1", "bd444ec6-8f34-4bf9-91f4-af1e3428d80f"),
+    ("vip", "1", "b817aba4-fad8-49e2-b88a-7cc744dfa6ec"),
+];
+
+/// The URL of a badge image on Twitch's CDN, from its image id
+pub fn badge_image_url(image_id: &str, scale: BadgeScale) -> String {
+    format!(
+        "https://static-cdn.jtvnw.net/badges/v1/{}/{}",
+        image_id,
+        scale.as_path()
+    )
+}
+
+impl Badge {
+    /// The name of the set of this badge, e.g. `subscriber`
+    pub fn set_id(&self) -> String {
+        let badge = self.to_string();
+        match badge.split_once('/') {
+            Some((set_id, _)) => set_id.to_string(),
+            None => badge,
+        }
+    }
+
+    /// The version of this badge in its set, e.g. `100` for `bits/100`
+    pub fn version(&self) -> String {
+        let badge = self.to_string();
+        match badge.split_once('/') {
+            Some((_, version)) => version.to_string(),
+            None => String::new(),
+        }
+    }
+
+    /// The image URL of this badge, if it is a global badge with a known image.
+    ///
+    /// Channels have their own subscriber and bits badges (and some global
+    /// badges change over time), use a [BadgeResolver] for those.
+    ///
+    /// ```
+    /// # use twitchchat::twitch::{Badge, BadgeScale};
+    /// assert_eq!(
+    ///     Badge::Moderator.image_url(BadgeScale::X2).unwrap(),
+    ///     "https://static-cdn.jtvnw.net/badges/v1/3267646d-33f0-4b17-b3df-f923a41db1d0/2"
+    /// );
+    /// assert!(Badge::NoTierSubscriber(12).image_url(BadgeScale::X1).is_none());
+    /// ```
+    pub fn image_url(&self, scale: BadgeScale) -> Option<String> {
+        let (set_id, version) = (self.set_id(), self.version());
+        GLOBAL_BADGES
+            .iter()
+            .find(|(set, ver, _)| *set == set_id && *ver == version)
+            .map(|(_, _, image_id)| badge_image_url(image_id, scale))
+    }
+}
+
+/// Something that can find the image URL of a badge, e.g. from the badges of a
+/// channel fetched with the Helix API.
+///
+/// This is implemented for closures.
+pub trait BadgeResolver {
+    /// The image URL of this badge at this scale, if it is known
+    fn image_url(&self, badge: &Badge, scale: BadgeScale) -> Option<String>;
+}
+
+impl<F> BadgeResolver for F
+where
+    F: Fn(&Badge, BadgeScale) -> Option<String>,
+{
+    fn image_url(&self, badge: &Badge, scale: BadgeScale) -> Option<String> {
+        (self)(badge, scale)
+    }
+}
+
+/// A [BadgeResolver] for the badges of a channel, falling back to the global badges.
+///
+/// ```
+/// # use twitchchat::twitch::{Badge, BadgeResolver as _, BadgeScale, ChannelBadges};
+/// let mut badges = ChannelBadges::default();
+/// badges.insert("subscriber", "12", "some-image-id");
+///
+/// assert_eq!(
+///     badges.image_url(&Badge::NoTierSubscriber(12), BadgeScale::X4).unwrap(),
+///     "https://static-cdn.jtvnw.net/badges/v1/some-image-id/3"
+/// );
+/// assert!(badges.image_url(&Badge::Vip, BadgeScale::X1).is_some());
+/// assert!(badges.image_url(&Badge::NoTierSubscriber(24), BadgeScale::X1).is_none());
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChannelBadges {
+    images: HashMap<(String, String), String>,
+}
+
+impl ChannelBadges {
+    /// Use this image id for the `version` of the `set_id` badge, replacing any previous one
+    pub fn insert(
+        &mut self,
+        set_id: impl Into<String>,
+        version: impl Into<String>,
+        image_id: impl Into<String>,
+    ) {
+        self.images
+            .insert((set_id.into(), version.into()), image_id.into());
+    }
+
+    /// Get the image id of this badge, if it was inserted
+    pub fn image_id(&self, badge: &Badge) -> Option<&str> {
+        self.images
+            .get(&(badge.set_id(), badge.version()))
+            .map(String::as_str)
+    }
+}
+
+impl BadgeResolver for ChannelBadges {
+    fn image_url(&self, badge: &Badge, scale: BadgeScale) -> Option<String> {
+        match self.image_id(badge) {
+            Some(image_id) => Some(badge_image_url(image_id, scale)),
+            None => badge.image_url(scale),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_id_and_version() {
+        let badges = [
+            (Badge::GlobalMod, "global_mod", "1"),
+            (Badge::Bits(1000), "bits", "1000"),
+            (Badge::TierSubscriber(2, 6), "subscriber", "2006"),
+            (Badge::NoTierSubscriber(3), "subscriber", "3"),
+            (Badge::Unknown("founder".into(), 0), "founder", "0"),
+        ];
+        for (badge, set_id, version) in &badges {
+            assert_eq!(badge.set_id(), *set_id);
+            assert_eq!(badge.version(), *version);
+        }
+    }
+
+    #[test]
+    fn resolvers() {
+        assert!(Badge::Bits(100).image_url(BadgeScale::X1).is_some());
+        assert!(Badge::Bits(1000).image_url(BadgeScale::X1).is_none());
+
+        let resolver = |badge: &Badge, scale| match badge {
+            Badge::Bits(..) => Some(badge_image_url("cheer", scale)),
+            badge => badge.image_url(scale),
+        };
+        assert_eq!(
+            resolver.image_url(&Badge::Bits(1000), BadgeScale::X2),
+            Some("https://static-cdn.jtvnw.net/badges/v1/cheer/2".to_string())
+        );
+        assert!(resolver
+            .image_url(&Badge::Broadcaster, BadgeScale::X1)
+            .is_some());
+    }
+}
@@ -0,0 +1,208 @@
+/*!
+Cheermotes are words in a message, like `Cheer100`, that carry the bits attached to it.
+
+The amount decides the tier of the cheermote, and the tier decides its color:
+
+| tier      | amount     | color     |
+| --------- | ---------- | --------- |
+| `Gray`    | `1+`       | `#979797` |
+| `Purple`  | `100+`     | `#9C3EE8` |
+| `Green`   | `1000+`    | `#1DB2A5` |
+| `Blue`    | `5000+`    | `#0099FE` |
+| `Red`     | `10000+`   | `#F43021` |
+
+Only the global, built-in prefixes are known here. Custom channel cheermotes need the Helix API.
+
+```
+# use twitchchat::twitch::{Cheer, CheerTier};
+let cheers = twitchchat::twitch::parse_cheers("great stream Cheer100 Kappa1000").collect::<Vec<_>>();
+assert_eq!(cheers, vec![
+    Cheer { prefix: "Cheer".into(), amount: 100, tier: CheerTier::Purple },
+    Cheer { prefix: "Kappa".into(), amount: 1000, tier: CheerTier::Green },
+]);
+```
+*/
+
+use crate::twitch::color::RGB;
+use std::str::FromStr;
+
+/// The global cheermote prefixes Twitch provides to every channel
+pub const CHEERMOTE_PREFIXES: &[&str] = &[
+    "Cheer",
+    "DoodleCheer",
+    "BibleThump",
+    "cheerwhal",
+    "Corgo",
+    "Scoops",
+    "uni",
+    "ShowLove",
+    "Party",
+    "SeemsGood",
+    "Pride",
+    "Kappa",
+    "FrankerZ",
+    "HeyGuys",
+    "DansGame",
+    "EleGiggle",
+    "TriHard",
+    "Kreygasm",
+    "4Head",
+    "SwiftRage",
+    "NotLikeThis",
+    "FailFish",
+    "VoHiYo",
+    "PJSalt",
+    "MrDestructoid",
+    "bday",
+    "RIPCheer",
+    "Shamrock",
+    "BitBoss",
+    "Streamlabs",
+    "Muxy",
+    "HolidayCheer",
+    "Goal",
+    "Anon",
+    "Charity",
+];
+
+/// The tier of a cheermote, decided by the amount of bits
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum CheerTier {
+    /// `1` to `99` bits
+    Gray,
+    /// `100` to `999` bits
+    Purple,
+    /// `1000` to `4999` bits
+    Green,
+    /// `5000` to `9999` bits
+    Blue,
+    /// `10000` or more bits
+    Red,
+}
+
+impl CheerTier {
+    /// Get the tier for this amount of bits
+    pub fn from_amount(amount: u64) -> Self {
+        match amount {
+            0..=99 => Self::Gray,
+            100..=999 => Self::Purple,
+            1000..=4999 => Self::Green,
+            5000..=9999 => Self::Blue,
+            _ => Self::Red,
+        }
+    }
+
+    /// The smallest amount of bits for this tier
+    pub fn min_amount(self) -> u64 {
+        match self {
+            Self::Gray => 1,
+            Self::Purple => 100,
+            Self::Green => 1000,
+            Self::Blue => 5000,
+            Self::Red => 10000,
+        }
+    }
+
+    /// The color Twitch uses for this tier
+    pub fn rgb(self) -> RGB {
+        match self {
+            Self::Gray => RGB(0x97, 0x97, 0x97),
+            Self::Purple => RGB(0x9C, 0x3E, 0xE8),
+            Self::Green => RGB(0x1D, 0xB2, 0xA5),
+            Self::Blue => RGB(0x00, 0x99, 0xFE),
+            Self::Red => RGB(0xF4, 0x30, 0x21),
+        }
+    }
+}
+
+/// A cheermote found in a message, e.g. `Cheer100`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Cheer {
+    /// The prefix, as it was written in the message
+    pub prefix: String,
+    /// The amount of bits
+    pub amount: u64,
+    /// The tier for the amount of bits
+    pub tier: CheerTier,
+}
+
+impl FromStr for Cheer {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // some prefixes (4Head) contain digits, so the amount is only the trailing digits
+        let prefix = s.trim_end_matches(|c: char| c.is_ascii_digit());
+        let amount = s.get(prefix.len()..).ok_or(())?;
+
+        if !CHEERMOTE_PREFIXES
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(prefix))
+        {
+            return Err(());
+        }
+
+        let amount = amount.parse().map_err(|_| ())?;
+        if amount == 0 {
+            return Err(());
+        }
+
+        Ok(Self {
+            prefix: prefix.to_string(),
+            amount,
+            tier: CheerTier::from_amount(amount),
+        })
+    }
+}
+
+/// Parse the cheermotes out of a message, in the order they appear
+pub fn parse_cheers(data: &str) -> impl Iterator<Item = Cheer> + '_ {
+    data.split_whitespace().filter_map(|word| word.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let tests = &[
+            ("Cheer1", "Cheer", 1, CheerTier::Gray),
+            ("cheer99", "cheer", 99, CheerTier::Gray),
+            ("Kappa100", "Kappa", 100, CheerTier::Purple),
+            ("4Head1000", "4Head", 1000, CheerTier::Green),
+            ("ShowLove4999", "ShowLove", 4999, CheerTier::Green),
+            ("uni5000", "uni", 5000, CheerTier::Blue),
+            ("Party10000", "Party", 10000, CheerTier::Red),
+            ("Cheer250000", "Cheer", 250_000, CheerTier::Red),
+        ];
+
+        for (input, prefix, amount, tier) in tests {
+            let cheer = input.parse::<Cheer>().unwrap();
+            assert_eq!(cheer.prefix, *prefix);
+            assert_eq!(cheer.amount, *amount);
+            assert_eq!(cheer.tier, *tier);
+        }
+
+        for input in &["Cheer", "Cheer0", "100", "Cheer10a", "NotACheer100", "4Head"] {
+            assert!(input.parse::<Cheer>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn tiers() {
+        for tier in &[
+            CheerTier::Gray,
+            CheerTier::Purple,
+            CheerTier::Green,
+            CheerTier::Blue,
+            CheerTier::Red,
+        ] {
+            assert_eq!(CheerTier::from_amount(tier.min_amount()), *tier);
+        }
+        assert_eq!(CheerTier::from_amount(99), CheerTier::Gray);
+        assert_eq!(CheerTier::Blue.rgb().to_string(), "#0099FE");
+    }
+}
@@ -59,14 +59,20 @@ let rgb: RGB = twitch_color.into();
 
 use std::{convert::TryFrom, str::FromStr};
 
-/// An error returned when trying to parse a string as an RGB triplet
+/// An error returned when trying to parse a string as an RGB triplet, or as
+/// one of the other Twitch types, e.g. a [Badge](super::Badge)
+///
+/// ***NOTE*** This is a breaking change from `0.14.8`, where the other types
+/// returned a `parse_display::ParseError`.
 #[non_exhaustive]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum ParseError {
     /// An invalid hex string for `RGB`
     InvalidHexString,
     /// Unknown color name
     UnknownColor,
+    /// The string isn't a valid value of the type
+    InvalidValue,
 }
 
 impl std::fmt::Display for ParseError {
@@ -74,6 +80,7 @@ impl std::fmt::Display for ParseError {
         match self {
             Self::InvalidHexString => f.write_str("invalid hex string"),
             Self::UnknownColor => f.write_str("unknown color"),
+            Self::InvalidValue => f.write_str("invalid value"),
         }
     }
 }
@@ -84,9 +91,8 @@ impl FromStr for RGB {
     type Err = ParseError;
     fn from_str(input: &str) -> Result<Self, Self::Err> {
         let input = input.trim();
-        let input = match (input.chars().next(), input.len()) {
-            (Some('#'), 7) => &input[1..],
-            (.., 6) => input,
+        let input = match input.strip_prefix('#').unwrap_or(input) {
+            hex if hex.len() == 6 => hex,
             _ => return Err(ParseError::InvalidHexString),
         };
 
@@ -214,17 +220,19 @@ impl FromStr for Color {
     fn from_str(input: &str) -> Result<Self, Self::Err> {
         use TwitchColor::*;
         let find = |color| {
-            let colors = twitch_colors();
-            colors[colors.iter().position(|(d, _)| *d == color).unwrap()]
+            twitch_colors()
+                .iter()
+                .copied()
+                .find(|&(d, _)| d == color)
+                .ok_or(ParseError::UnknownColor)
         };
 
         let mut s = input.replace(' ', "_");
         if !s.contains('_') {
-            if let Some(pos) = s
-                .chars()
+            if let Some((pos, _)) = s
+                .char_indices()
                 .skip(1)
-                .position(|d| d.is_ascii_uppercase())
-                .map(|d| d + 1)
+                .find(|(_, d)| d.is_ascii_uppercase())
             {
                 s.insert(pos, '_');
             }
@@ -233,21 +241,21 @@ impl FromStr for Color {
         // TODO this isn't correct, its always parsing stuff as 'Turbo'
         let s = s.to_ascii_lowercase();
         let (kind, rgb) = match s.as_str() {
-            "blue" => find(Blue),
-            "blue_violet" => find(BlueViolet),
-            "cadet_blue" => find(CadetBlue),
-            "chocolate" => find(Chocolate),
-            "coral" => find(Coral),
-            "dodger_blue" => find(DodgerBlue),
-            "firebrick" => find(Firebrick),
-            "golden_rod" => find(GoldenRod),
-            "green" => find(Green),
-            "hot_pink" => find(HotPink),
-            "orange_red" => find(OrangeRed),
-            "red" => find(Red),
-            "sea_green" => find(SeaGreen),
-            "spring_green" => find(SpringGreen),
-            "yellow_green" => find(YellowGreen),
+            "blue" => find(Blue)?,
+            "blue_violet" => find(BlueViolet)?,
+            "cadet_blue" => find(CadetBlue)?,
+            "chocolate" => find(Chocolate)?,
+            "coral" => find(Coral)?,
+            "dodger_blue" => find(DodgerBlue)?,
+            "firebrick" => find(Firebrick)?,
+            "golden_rod" => find(GoldenRod)?,
+            "green" => find(Green)?,
+            "hot_pink" => find(HotPink)?,
+            "orange_red" => find(OrangeRed)?,
+            "red" => find(Red)?,
+            "sea_green" => find(SeaGreen)?,
+            "spring_green" => find(SpringGreen)?,
+            "yellow_green" => find(YellowGreen)?,
             _ => (Turbo, input.parse()?),
         };
 
@@ -474,6 +482,13 @@ mod tests {
         assert_eq!(color.to_string(), "#FAFAFA");
     }
 
+    #[test]
+    fn parse_invalid_color() {
+        for input in &["éééA", "#ééé", "#", "", "_", "Blue_", "#FAFAFAFA"] {
+            assert!(input.parse::<Color>().is_err(), "{}", input);
+        }
+    }
+
     #[test]
     fn fields() {
         let rgb = RGB(0x27, 255, 82);
@@ -0,0 +1,209 @@
+/*!
+Detection of Twitch features that are deprecated, or have already been removed.
+
+Twitch has removed host mode and several chat commands. Checking the messages a
+bot receives and sends against these lists helps with finding dead code paths.
+
+```
+# use twitchchat::twitch::Deprecation;
+let input = ":tmi.twitch.tv HOSTTARGET #museun :shaken_bot 10\r\n";
+let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+assert_eq!(
+    Deprecation::check(&msg),
+    vec![Deprecation::Command("HOSTTARGET".into())]
+);
+
+let input = "PRIVMSG #museun :/host shaken_bot\r\n";
+let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+assert_eq!(
+    Deprecation::check(&msg),
+    vec![Deprecation::ChatCommand("/host".into())]
+);
+```
+*/
+
+use crate::irc::{IrcMessage, Tags};
+use crate::Validator as _;
+
+/// IRC commands Twitch no longer sends
+pub const DEPRECATED_COMMANDS: &[&str] = &[IrcMessage::HOST_TARGET];
+
+/// Chat commands (sent as a `PRIVMSG`) Twitch no longer supports
+pub const DEPRECATED_CHAT_COMMANDS: &[&str] = &["/host", "/unhost"];
+
+/// Tags Twitch documents as deprecated, in favor of the `badges` tag
+pub const DEPRECATED_TAGS: &[&str] = &["subscriber", "turbo"];
+
+/// A deprecated feature that was used by a message
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Deprecation {
+    /// A deprecated IRC command, e.g. `HOSTTARGET`
+    Command(String),
+    /// A deprecated chat command, e.g. `/host`
+    ChatCommand(String),
+    /// A deprecated tag
+    Tag {
+        /// The IRC command of the message
+        command: String,
+        /// The name of the tag
+        tag: String,
+    },
+}
+
+impl Deprecation {
+    /// Check a message (either received, or about to be sent) for deprecated features
+    pub fn check(msg: &IrcMessage<'_>) -> Vec<Self> {
+        let mut found = vec![];
+
+        let command = msg.get_command();
+        if DEPRECATED_COMMANDS.contains(&command) {
+            found.push(Self::Command(command.to_string()));
+        }
+
+        if command == IrcMessage::PRIVMSG {
+            if let Some(cmd) = msg.get_data().and_then(|data| data.split(' ').next()) {
+                if let Some(cmd) = DEPRECATED_CHAT_COMMANDS
+                    .iter()
+                    .find(|known| known.eq_ignore_ascii_case(cmd))
+                {
+                    found.push(Self::ChatCommand(cmd.to_string()));
+                }
+            }
+        }
+
+        if msg.get_tags().is_some() {
+            let indices = msg.parse_tags();
+            let tags = Tags::from_data_indices(&msg.raw, &indices);
+            found.extend(
+                DEPRECATED_TAGS
+                    .iter()
+                    .filter(|&&tag| tags.get(tag).is_some())
+                    .map(|tag| Self::Tag {
+                        command: command.to_string(),
+                        tag: tag.to_string(),
+                    }),
+            );
+        }
+
+        found
+    }
+}
+
+impl std::fmt::Display for Deprecation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Command(command) => write!(f, "the '{}' command is deprecated", command),
+            Self::ChatCommand(command) => {
+                write!(f, "the '{}' chat command is deprecated", command)
+            }
+            Self::Tag { command, tag } => {
+                write!(f, "the '{}' tag (on '{}') is deprecated", tag, command)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Encodable as _;
+
+    fn check(input: &str) -> Vec<Deprecation> {
+        let msg = crate::irc::parse(input).next().unwrap().unwrap();
+        Deprecation::check(&msg)
+    }
+
+    #[test]
+    fn received() {
+        assert_eq!(
+            check("@badges=turbo/1;subscriber=1;turbo=1 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n"),
+            vec![
+                Deprecation::Tag {
+                    command: "PRIVMSG".into(),
+                    tag: "subscriber".into()
+                },
+                Deprecation::Tag {
+                    command: "PRIVMSG".into(),
+                    tag: "turbo".into()
+                },
+            ]
+        );
+
+        assert!(check(
+            "@badges=turbo/1 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :/hostile\r\n"
+        )
+        .is_empty());
+        assert!(check("PING :1234567890\r\n").is_empty());
+    }
+
+    #[test]
+    fn encoded() {
+        let mut buf = vec![];
+        crate::commands::host("#museun", "shaken_bot")
+            .encode(&mut buf)
+            .unwrap();
+        crate::commands::unhost("#museun").encode(&mut buf).unwrap();
+        let buf = std::str::from_utf8(&buf).unwrap();
+
+        let found = crate::irc::parse(buf)
+            .map(|msg| msg.unwrap())
+            .flat_map(|msg| Deprecation::check(&msg))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            found,
+            vec![
+                Deprecation::ChatCommand("/host".into()),
+                Deprecation::ChatCommand("/unhost".into()),
+            ]
+        );
+        assert_eq!(
+            found[0].to_string(),
+            "the '/host' chat command is deprecated"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "testing")]
+    fn runner_reports_tags_once() {
+        use crate::{runner::Status, test::TestConnector, AsyncRunner, UserConfig};
+
+        let config = UserConfig::builder().anonymous().build().unwrap();
+        futures_lite::future::block_on(async {
+            let connector = TestConnector::default();
+            connector
+                .conn
+                .write_data(
+                    ":tmi.twitch.tv 001 justinfan1234 :Welcome, GLHF!\r\n\
+                     :tmi.twitch.tv 376 justinfan1234 :>\r\n\
+                     @subscriber=1 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n\
+                     @subscriber=1 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :world\r\n\
+                     @subscriber=1 :tmi.twitch.tv USERSTATE #museun\r\n\
+                     :tmi.twitch.tv HOSTTARGET #museun :shaken_bot 10\r\n\
+                     :tmi.twitch.tv HOSTTARGET #museun :shaken_bot 10\r\n",
+                )
+                .await;
+
+            let mut runner = AsyncRunner::connect(connector, &config).await.unwrap();
+            let deprecations = runner.deprecations();
+            while let Status::Message(..) = runner.next_message().await.unwrap() {}
+
+            let found = std::iter::from_fn(|| deprecations.try_recv()).collect::<Vec<_>>();
+            let tag = |command: &str| Deprecation::Tag {
+                command: command.into(),
+                tag: "subscriber".into(),
+            };
+            assert_eq!(
+                found,
+                vec![
+                    tag("PRIVMSG"),
+                    tag("USERSTATE"),
+                    Deprecation::Command("HOSTTARGET".into()),
+                    Deprecation::Command("HOSTTARGET".into()),
+                ]
+            );
+        });
+    }
+}
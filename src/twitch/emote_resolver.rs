@@ -0,0 +1,333 @@
+use super::{emotes::split_modifiers, Emote};
+use crate::{messages::Privmsg, BoxedFuture};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// What an [EmoteResolver] knows about an emote
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct EmoteInfo {
+    /// The id of the emote, without modifiers
+    pub id: String,
+    /// The name of the emote, e.g. `Kappa`
+    pub name: String,
+    /// The URL of an image of the emote, if it has one
+    pub image_url: Option<String>,
+}
+
+/// Something that can look up emotes by their id, e.g. with the Helix API or a
+/// third-party emote service.
+///
+/// The channel is given, because channels have their own emotes. The id is the
+/// [base id](Emote::base_id) of the emote, without its modifiers.
+///
+/// This is implemented for closures. Wrap it in an [EmoteCache] to not look up
+/// an emote for every message it is in.
+pub trait EmoteResolver: Send + Sync {
+    /// Look up the emote with this id, seen on this channel
+    fn resolve(&self, channel: &str, id: &str) -> BoxedFuture<Option<EmoteInfo>>;
+}
+
+impl<F> EmoteResolver for F
+where
+    F: Fn(&str, &str) -> BoxedFuture<Option<EmoteInfo>> + Send + Sync,
+{
+    fn resolve(&self, channel: &str, id: &str) -> BoxedFuture<Option<EmoteInfo>> {
+        (self)(channel, id)
+    }
+}
+
+/// An emote of a message, with what the resolver knows about it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResolvedEmote {
+    /// The emote, and where it is in the message
+    pub emote: Emote,
+    /// The emote that was looked up, if the resolver knows it
+    pub info: Option<EmoteInfo>,
+}
+
+/// An [EmoteResolver] with a cache of the emotes it looked up.
+///
+/// The last `capacity` emotes that were looked up are kept, per channel. The
+/// emotes the resolver didn't know are kept too, so they aren't looked up
+/// again for every message.
+///
+/// ```
+/// # use twitchchat::twitch::{EmoteCache, EmoteInfo};
+/// # use twitchchat::BoxedFuture;
+/// let mut cache = EmoteCache::new(
+///     |_channel: &str, id: &str| -> BoxedFuture<Option<EmoteInfo>> {
+///         let info = match id {
+///             "25" => Some(EmoteInfo {
+///                 id: id.to_string(),
+///                 name: "Kappa".to_string(),
+///                 image_url: None,
+///             }),
+///             _ => None,
+///         };
+///         Box::pin(async move { info })
+///     },
+///     100,
+/// );
+///
+/// futures_lite::future::block_on(async {
+///     let info = cache.resolve("#museun", "25_HF").await.unwrap();
+///     assert_eq!(info.name, "Kappa");
+///     assert!(cache.resolve("#museun", "1902").await.is_none());
+/// });
+/// assert_eq!(cache.len(), 2);
+/// ```
+pub struct EmoteCache<R> {
+    resolver: R,
+    cache: Lru<(String, String), Option<EmoteInfo>>,
+}
+
+impl<R> std::fmt::Debug for EmoteCache<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmoteCache")
+            .field("len", &self.cache.len())
+            .field("capacity", &self.cache.capacity)
+            .finish()
+    }
+}
+
+impl<R: EmoteResolver> EmoteCache<R> {
+    /// Cache the last `capacity` emotes this resolver looked up
+    pub fn new(resolver: R, capacity: usize) -> Self {
+        Self {
+            resolver,
+            cache: Lru::new(capacity),
+        }
+    }
+
+    /// Look up the emote with this id (modifiers are ignored), seen on this channel.
+    ///
+    /// The resolver is only asked if the emote isn't in the cache.
+    pub async fn resolve(&mut self, channel: &str, id: &str) -> Option<EmoteInfo> {
+        let id = split_modifiers(id).0;
+        let key = (channel.to_string(), id.to_string());
+        if let Some(info) = self.cache.get(&key) {
+            return info.clone();
+        }
+
+        let info = self.resolver.resolve(&key.0, &key.1).await;
+        self.cache.insert(key, info.clone());
+        info
+    }
+
+    /// Look up the emotes of this message, in the order of the `emotes` tag
+    pub async fn resolve_message(&mut self, msg: &Privmsg<'_>) -> Vec<ResolvedEmote> {
+        let mut resolved = vec![];
+        for emote in msg.emotes().iter() {
+            let info = self.resolve(msg.channel(), emote.base_id()).await;
+            resolved.push(ResolvedEmote {
+                emote: emote.clone(),
+                info,
+            });
+        }
+        resolved
+    }
+
+    /// Forget the emotes of this channel, e.g. after it changed its emotes
+    pub fn forget_channel(&mut self, channel: &str) {
+        self.cache.retain(|(ch, _)| ch != channel)
+    }
+
+    /// Forget all of the emotes
+    pub fn clear(&mut self) {
+        self.cache.retain(|_| false)
+    }
+
+    /// The number of emotes in the cache
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.cache.len() == 0
+    }
+
+    /// Get the resolver back
+    pub fn into_inner(self) -> R {
+        self.resolver
+    }
+}
+
+// a least-recently-used cache, the entries are ordered by when they were last used
+struct Lru<K, V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<K, (V, u64)>,
+    order: BTreeMap<u64, K>,
+}
+
+impl<K: Hash + Eq + Clone, V> Lru<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        let tick = self.next_tick();
+        let (value, used) = self.entries.get_mut(key)?;
+        let key = self.order.remove(used)?;
+        *used = tick;
+        self.order.insert(tick, key);
+        Some(value)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let tick = self.next_tick();
+        if let Some((_, used)) = self.entries.insert(key.clone(), (value, tick)) {
+            self.order.remove(&used);
+        }
+        self.order.insert(tick, key);
+
+        while self.entries.len() > self.capacity {
+            let oldest = match self.order.keys().next() {
+                Some(&oldest) => oldest,
+                None => break,
+            };
+            if let Some(key) = self.order.remove(&oldest) {
+                self.entries.remove(&key);
+            }
+        }
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(&K) -> bool) {
+        let entries = &mut self.entries;
+        self.order.retain(|_, key| {
+            let kept = keep(key);
+            if !kept {
+                entries.remove(key);
+            }
+            kept
+        });
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irc::parse;
+    use crate::FromIrcMessage as _;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    fn counting(lookups: Arc<AtomicUsize>) -> impl EmoteResolver {
+        move |channel: &str, id: &str| -> BoxedFuture<Option<EmoteInfo>> {
+            lookups.fetch_add(1, Ordering::SeqCst);
+            let info = match (channel, id) {
+                (_, "25") => Some(("Kappa", "https://example.com/25")),
+                ("#museun", "1902") => Some(("Keepo", "https://example.com/1902")),
+                _ => None,
+            }
+            .map(|(name, url)| EmoteInfo {
+                id: id.to_string(),
+                name: name.to_string(),
+                image_url: Some(url.to_string()),
+            });
+            Box::pin(async move { info })
+        }
+    }
+
+    #[test]
+    fn lru() {
+        let mut lru = Lru::new(2);
+        lru.insert("a", 1);
+        lru.insert("b", 2);
+        assert_eq!(lru.get(&"a"), Some(&1));
+
+        // "b" was used the longest ago
+        lru.insert("c", 3);
+        assert_eq!(lru.len(), 2);
+        assert_eq!(lru.get(&"b"), None);
+        assert_eq!(lru.get(&"a"), Some(&1));
+        assert_eq!(lru.get(&"c"), Some(&3));
+
+        lru.insert("c", 4);
+        assert_eq!(lru.get(&"c"), Some(&4));
+        assert_eq!(lru.len(), 2);
+
+        lru.retain(|key| *key != "a");
+        assert_eq!(lru.len(), 1);
+        assert_eq!(lru.order.len(), 1);
+
+        let mut lru = Lru::new(0);
+        lru.insert("a", 1);
+        assert_eq!(lru.len(), 0);
+    }
+
+    #[test]
+    fn cache() {
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let mut cache = EmoteCache::new(counting(lookups.clone()), 10);
+
+        futures_lite::future::block_on(async {
+            assert_eq!(cache.resolve("#museun", "25").await.unwrap().name, "Kappa");
+            assert_eq!(cache.resolve("#museun", "25_HF").await.unwrap().name, "Kappa");
+            assert!(cache.resolve("#other", "1902").await.is_none());
+            assert!(cache.resolve("#other", "1902").await.is_none());
+            assert_eq!(lookups.load(Ordering::SeqCst), 2);
+
+            // channels have their own emotes
+            assert_eq!(cache.resolve("#museun", "1902").await.unwrap().name, "Keepo");
+            assert_eq!(lookups.load(Ordering::SeqCst), 3);
+
+            cache.forget_channel("#museun");
+            assert_eq!(cache.len(), 1);
+            cache.resolve("#museun", "25").await;
+            assert_eq!(lookups.load(Ordering::SeqCst), 4);
+
+            cache.clear();
+            assert!(cache.is_empty());
+        });
+    }
+
+    #[test]
+    fn resolve_message() {
+        let input = "@emotes=25:0-4,12-16/1902:6-10/33:18-25 :test!test@test.tmi.twitch.tv PRIVMSG #museun :Kappa Keepo Kappa DansGame\r\n";
+        let msg = parse(input).next().unwrap().unwrap();
+        let msg = Privmsg::from_irc(msg).unwrap();
+
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let mut cache = EmoteCache::new(counting(lookups), 10);
+        let resolved = futures_lite::future::block_on(cache.resolve_message(&msg));
+
+        let names = resolved
+            .iter()
+            .map(|resolved| {
+                let name = resolved.info.as_ref().map(|info| info.name.as_str());
+                (resolved.emote.id.as_str(), resolved.emote.ranges.len(), name)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("25", 2, Some("Kappa")),
+                ("1902", 1, Some("Keepo")),
+                ("33", 1, None)
+            ]
+        );
+    }
+}
@@ -7,43 +7,178 @@ They are presented (to the irc connection) in a `id:range1,range2/id2:range1,..`
 `"testing Kappa"` would be `25:8-13`
 
 `"Kappa testing Kappa"` would be `25:0-5,14-19`
+
+Newer emotes use ids like `emotesv2_dc24652ada1e4c84a5e3ceebae4de709`, and modified
+emotes (e.g. `Kappa_HF`) append the modifier to the id of the base emote: `25_HF:0-7`.
 */
 
-use crate::twitch::attributes::{Attribution, AttributionVec, MsgRange, SeparatorInfo};
-use derive_more::From;
-use shrinkwraprs::Shrinkwrap;
-use std::collections::HashSet;
+use crate::twitch::attributes::{
+    Attribution, AttributionErrorKind, AttributionVec, MsgRange, SeparatorInfo,
+};
+use crate::twitch::ParseError;
+use std::collections::BTreeSet;
 use std::str::FromStr;
 
 /// Newtype that describes the emote sets available to a user.
 /// Those emotes are described as a simple list of numbers, like `0,33,50,237,793,2126,3517,4578,5569,9400,10337,12239`.
-#[derive(Shrinkwrap, From, Debug, PartialEq, Clone)]
-pub struct EmoteSet(HashSet<u32>);
+///
+/// The set is ordered, so iterating over it always yields the ids in ascending order.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EmoteSet(BTreeSet<u32>);
+
+impl From<BTreeSet<u32>> for EmoteSet {
+    fn from(set: BTreeSet<u32>) -> Self {
+        Self(set)
+    }
+}
+
+impl std::ops::Deref for EmoteSet {
+    type Target = BTreeSet<u32>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<BTreeSet<u32>> for EmoteSet {
+    fn as_ref(&self) -> &BTreeSet<u32> {
+        &self.0
+    }
+}
+
+impl std::borrow::Borrow<BTreeSet<u32>> for EmoteSet {
+    fn borrow(&self) -> &BTreeSet<u32> {
+        &self.0
+    }
+}
 
 impl FromStr for EmoteSet {
     type Err = <u32 as FromStr>::Err;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         s.split(',')
             .map(u32::from_str)
-            .collect::<Result<HashSet<u32>, Self::Err>>()
+            .collect::<Result<BTreeSet<u32>, Self::Err>>()
             .map(EmoteSet::from)
     }
 }
 
+/// A modifier that can be applied to an emote, e.g. `Kappa_HF`
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum EmoteModifier {
+    /// Black & white
+    Bw,
+    /// Horizontal flip
+    Hf,
+    /// Sunglasses
+    Sg,
+    /// Squished
+    Sq,
+    /// Thinking
+    Tk,
+}
+
+impl std::fmt::Display for EmoteModifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let suffix = match self {
+            Self::Bw => "BW",
+            Self::Hf => "HF",
+            Self::Sg => "SG",
+            Self::Sq => "SQ",
+            Self::Tk => "TK",
+        };
+        f.write_str(suffix)
+    }
+}
+
+impl FromStr for EmoteModifier {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let modifier = match s {
+            "BW" => Self::Bw,
+            "HF" => Self::Hf,
+            "SG" => Self::Sg,
+            "SQ" => Self::Sq,
+            "TK" => Self::Tk,
+            _ => return Err(ParseError::InvalidValue),
+        };
+        Ok(modifier)
+    }
+}
+
 /// Emotes.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Emote {
-    /// This emote id, e.g. `Kappa = 25`    
-    pub id: usize,
+    /// This emote id, e.g. `Kappa = 25`, `Kappa_HF = 25_HF` or `emotesv2_dc24652ada1e4c84a5e3ceebae4de709`
+    ///
+    /// ***NOTE*** This is a breaking change from `0.14.8`, where this was a
+    /// `usize`. The newer emote ids (and the ones with modifiers) aren't
+    /// numbers, so they couldn't be parsed at all. Use [Emote::numeric_id]
+    /// where you need the number.
+    pub id: String,
     /// A list of [Range] in the message where this emote is found
     ///
     /// [Range]: https://doc.rust-lang.org/std/ops/struct.Range.html
     pub ranges: Vec<MsgRange>,
 }
 
-impl Attribution<usize, MsgRange> for Emote {
-    fn new(reference: usize, attributes: impl Iterator<Item = MsgRange>) -> Self {
+impl Emote {
+    /// The id as a number, e.g. `25` for `25` or `25_HF`
+    ///
+    /// This is None for the ids that aren't numbers, e.g. `emotesv2_..`
+    ///
+    /// ```
+    /// # use twitchchat::twitch::Emote;
+    /// let emote: Emote = "25_HF:0-7".parse().unwrap();
+    /// assert_eq!(emote.numeric_id(), Some(25));
+    ///
+    /// let emote: Emote = "emotesv2_dc24652ada1e4c84a5e3ceebae4de709:0-4".parse().unwrap();
+    /// assert_eq!(emote.numeric_id(), None);
+    /// ```
+    pub fn numeric_id(&self) -> Option<usize> {
+        self.base_id().parse().ok()
+    }
+
+    /// The id of the unmodified emote, e.g. `25` for `25_HF`
+    ///
+    /// This is the full id if the emote has no modifiers.
+    pub fn base_id(&self) -> &str {
+        split_modifiers(&self.id).0
+    }
+
+    /// The modifiers applied to this emote, in the order they appear in the id
+    pub fn modifiers(&self) -> Vec<EmoteModifier> {
+        split_modifiers(&self.id).1
+    }
+
+    /// Determines whether any modifiers are applied to this emote
+    pub fn is_modified(&self) -> bool {
+        !self.modifiers().is_empty()
+    }
+}
+
+/// Split an emote id into the id of the unmodified emote, and its modifiers
+pub(crate) fn split_modifiers(id: &str) -> (&str, Vec<EmoteModifier>) {
+    let mut base = id;
+    let mut modifiers = vec![];
+
+    // only strip known modifiers, the `emotesv2_` prefix also uses an underscore
+    while let Some((rest, suffix)) = base.rsplit_once('_') {
+        match suffix.parse() {
+            Ok(modifier) => modifiers.push(modifier),
+            Err(..) => break,
+        }
+        base = rest;
+    }
+
+    modifiers.reverse();
+    (base, modifiers)
+}
+
+impl Attribution<String, MsgRange> for Emote {
+    fn new(reference: String, attributes: impl Iterator<Item = MsgRange>) -> Self {
         Self {
             id: reference,
             ranges: attributes.collect(),
@@ -57,18 +192,47 @@ impl Attribution<usize, MsgRange> for Emote {
             attribute_separator: ',',
         }
     }
+
+    // the id is a string, so an empty tag would otherwise produce an emote with an empty id
+    fn parse(item: &str) -> Option<Self> {
+        let (id, ranges) = item.split_once(Self::get_range_attribute_separator())?;
+        if id.is_empty() {
+            return None;
+        }
+        Self::new(id.to_string(), Self::parse_attributes(ranges)).into()
+    }
+
+    fn parse_strict(item: &str) -> Result<Self, AttributionErrorKind> {
+        let (id, ranges) = item
+            .split_once(Self::get_range_attribute_separator())
+            .ok_or(AttributionErrorKind::Invalid)?;
+        if id.is_empty() {
+            return Err(AttributionErrorKind::InvalidReference);
+        }
+        let ranges = ranges
+            .split(Self::get_attribute_separator())
+            .map(|range| {
+                range
+                    .parse()
+                    .map_err(|_| AttributionErrorKind::InvalidAttribute {
+                        attribute: range.to_string(),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::new(id.to_string(), ranges.into_iter()))
+    }
 }
 
 impl FromStr for Emote {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        <Emote as Attribution<usize, MsgRange>>::parse(s).ok_or(())
+        <Emote as Attribution<String, MsgRange>>::parse(s).ok_or(())
     }
 }
 
 /// Vector containing emote attribution data.
-pub type EmoteVec = AttributionVec<usize, MsgRange, Emote>;
+pub type EmoteVec = AttributionVec<String, MsgRange, Emote>;
 
 /// An iterator over emotes
 // #[derive(Debug, Constructor)]
@@ -105,7 +269,7 @@ mod tests {
         macro_rules! emote {
             ($id:expr, $($r:expr),* $(,)?) => {
                 Emote {
-                    id: $id,
+                    id: $id.to_string(),
                     ranges: vec![$($r.into()),*]
                 }
             };
@@ -136,6 +300,13 @@ mod tests {
                 "33:0-7/25:9-13,15-19",
                 vec![emote!(33, (0..7)), emote!(25, (9..13), (15..19))],
             ),
+            (
+                "emotesv2_dc24652ada1e4c84a5e3ceebae4de709:0-4/25_HF:6-13",
+                vec![
+                    emote!("emotesv2_dc24652ada1e4c84a5e3ceebae4de709", (0..4)),
+                    emote!("25_HF", (6..13)),
+                ],
+            ),
         ];
 
         for (input, expect) in inputs {
@@ -144,4 +315,53 @@ mod tests {
             assert_eq!(*emotes, *expect);
         }
     }
+
+    #[test]
+    fn empty() {
+        assert_eq!(*EmoteVec::from_str("").unwrap(), vec![]);
+        assert!(Emote::from_str(":0-4").is_err());
+    }
+
+    #[test]
+    fn modifiers() {
+        let tests: &[(&str, &str, &[EmoteModifier])] = &[
+            ("25:0-4", "25", &[]),
+            ("25_HF:0-7", "25", &[EmoteModifier::Hf]),
+            (
+                "25_SG_TK:0-10",
+                "25",
+                &[EmoteModifier::Sg, EmoteModifier::Tk],
+            ),
+            (
+                "emotesv2_dc24652ada1e4c84a5e3ceebae4de709:0-4",
+                "emotesv2_dc24652ada1e4c84a5e3ceebae4de709",
+                &[],
+            ),
+            (
+                "emotesv2_dc24652ada1e4c84a5e3ceebae4de709_BW:0-7",
+                "emotesv2_dc24652ada1e4c84a5e3ceebae4de709",
+                &[EmoteModifier::Bw],
+            ),
+            // unknown suffixes are left as part of the id
+            ("25_XX:0-7", "25_XX", &[]),
+        ];
+
+        for (input, base_id, modifiers) in tests {
+            let emote = Emote::from_str(input).unwrap();
+            assert_eq!(emote.base_id(), *base_id);
+            assert_eq!(emote.modifiers(), *modifiers);
+            assert_eq!(emote.is_modified(), !modifiers.is_empty());
+        }
+
+        assert_eq!(EmoteModifier::Sq.to_string(), "SQ");
+    }
+
+    #[test]
+    fn emote_set_order() {
+        let set = EmoteSet::from_str("793,0,12239,33,50").unwrap();
+        assert_eq!(
+            set.iter().copied().collect::<Vec<_>>(),
+            vec![0, 33, 50, 793, 12239]
+        );
+    }
 }
@@ -20,32 +20,64 @@
 //! Message: "LMAO Poki wtf" -- Flags: "0-3:P.6,10-12:P.6"
 
 use crate::twitch::attributes::{split_pair, Attribution, AttributionVec, MsgRange, SeparatorInfo};
-use derive_more::Constructor;
 use std::str::FromStr;
 
 /// The four possible types of offensive terms recognized by Twitch
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub enum ScoreType {
+    /// Aggression, `A`
     Aggressive,
+    /// Identity language, `I`
     Identity,
+    /// Profanity, `P`
     Profanity,
+    /// Sexual language, `S`
     Sexual,
 }
 
 /// A score that was assigned to a term by automod. Like A.6, S.3, etc.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Score(ScoreType, u8);
 
+impl Score {
+    /// The type of the term
+    pub fn score_type(&self) -> ScoreType {
+        self.0
+    }
+
+    /// How severe the term is, the `6` in `A.6`
+    pub fn severity(&self) -> u8 {
+        self.1
+    }
+}
+
 /// Contains information about a flagged term.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Constructor)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Flag {
     range: MsgRange,
     scores: Vec<Score>,
 }
 
+impl Flag {
+    /// A flagged term at this range, with these scores
+    pub fn new(range: MsgRange, scores: Vec<Score>) -> Self {
+        Self { range, scores }
+    }
+
+    /// The range of the flagged term in the message
+    pub fn range(&self) -> &MsgRange {
+        &self.range
+    }
+
+    /// The scores automod assigned to the term, if any
+    pub fn scores(&self) -> &[Score] {
+        &self.scores
+    }
+}
+
 impl FromStr for Score {
     type Err = ();
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -142,4 +174,37 @@ mod tests {
             assert_eq!(*flags, *expect);
         }
     }
+
+    #[test]
+    fn parse_strict() {
+        use crate::twitch::attributes::{AttributionError, AttributionErrorKind};
+
+        let input = "0-3:P.6,x-4:P.6,10-12:P.6/Q.1";
+        let lenient = FlagVec::from_str(input).unwrap();
+        let (flags, errors) = FlagVec::parse_with_errors(input);
+        assert_eq!(flags, lenient);
+        assert_eq!(
+            errors,
+            vec![
+                AttributionError {
+                    index: 1,
+                    raw: "x-4:P.6".to_string(),
+                    kind: AttributionErrorKind::InvalidReference,
+                },
+                AttributionError {
+                    index: 2,
+                    raw: "10-12:P.6/Q.1".to_string(),
+                    kind: AttributionErrorKind::InvalidAttribute {
+                        attribute: "Q.1".to_string()
+                    },
+                },
+            ]
+        );
+
+        // the lenient parser keeps the flag, but drops the invalid score
+        assert_eq!(lenient.len(), 2);
+        assert_eq!(FlagVec::parse_strict(input).unwrap_err().errors, errors);
+        assert!(FlagVec::parse_strict("").unwrap().is_empty());
+        assert_eq!(FlagVec::parse_strict("0-3").unwrap().len(), 1);
+    }
 }
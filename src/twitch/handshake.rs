@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+/// The order the registration commands are sent in, see [HandshakeOptions]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RegisterOrder {
+    /// `CAP REQ`s, then `PASS` and `NICK`.
+    ///
+    /// Twitch only sends `GLOBALUSERSTATE` if the capabilities were requested
+    /// before registering.
+    CapabilitiesFirst,
+    /// `PASS` and `NICK`, then `CAP REQ`s.
+    ///
+    /// Twitch doesn't send `GLOBALUSERSTATE` in this order, so the identity is
+    /// never [Identity::Full](crate::runner::Identity::Full).
+    CredentialsFirst,
+}
+
+impl Default for RegisterOrder {
+    fn default() -> Self {
+        Self::CapabilitiesFirst
+    }
+}
+
+/// Which message the handshake waits for before the connection is ready, see [HandshakeOptions]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReadyOn {
+    /// The end of the MOTD (`376`), then every requested capability, then
+    /// `GLOBALUSERSTATE` if Twitch will send one for the requested capabilities
+    Auto,
+    /// The welcome message (`001`).
+    ///
+    /// This is the fastest, e.g. for anonymous read-only connections. The
+    /// identity only has the capabilities acknowledged before it.
+    Welcome,
+    /// `GLOBALUSERSTATE`, even if the requested capabilities don't look like
+    /// Twitch will send one.
+    ///
+    /// Anonymous logins never get one, so they are ready at the end of the MOTD.
+    ///
+    /// With [RegisterOrder::CredentialsFirst] Twitch doesn't send one either,
+    /// so [UserConfigBuilder::build](crate::twitch::UserConfigBuilder::build)
+    /// rejects that without a `global_user_state_timeout`.
+    GlobalUserState,
+}
+
+impl Default for ReadyOn {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+
+/// A phase of the handshake, see [HandshakeOptions]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HandshakePhase {
+    /// Waiting for Twitch to accept the registration
+    Welcome,
+    /// Waiting for the requested capabilities to be acknowledged
+    Capabilities,
+    /// Waiting for `GLOBALUSERSTATE`
+    GlobalUserState,
+}
+
+impl std::fmt::Display for HandshakePhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Welcome => f.write_str("welcome"),
+            Self::Capabilities => f.write_str("capabilities"),
+            Self::GlobalUserState => f.write_str("global user state"),
+        }
+    }
+}
+
+/// How a runner registers, and when it considers the connection ready.
+///
+/// A read-only bot can be ready as soon as Twitch welcomes it, while a bot
+/// that needs its user id should wait for `GLOBALUSERSTATE`.
+///
+/// ```
+/// # use twitchchat::twitch::{HandshakeOptions, ReadyOn, UserConfig};
+/// # use std::time::Duration;
+/// let config = UserConfig::builder()
+///     .anonymous()
+///     .handshake(HandshakeOptions {
+///         ready_on: ReadyOn::Welcome,
+///         welcome_timeout: Some(Duration::from_secs(5)),
+///         ..HandshakeOptions::default()
+///     })
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HandshakeOptions {
+    /// The order the registration commands are sent in
+    pub order: RegisterOrder,
+    /// Which message the connection is ready after
+    pub ready_on: ReadyOn,
+    /// How long to wait for Twitch to accept the registration
+    pub welcome_timeout: Option<Duration>,
+    /// How long to wait for the capabilities after the registration was accepted
+    pub capabilities_timeout: Option<Duration>,
+    /// How long to wait for `GLOBALUSERSTATE` after the capabilities were acknowledged
+    pub global_user_state_timeout: Option<Duration>,
+    /// Request all of the capabilities with a single `CAP REQ`.
+    ///
+    /// If Twitch refuses the batch, each capability is requested again on its
+    /// own. The ones refused then are listed in
+    /// [Capabilities::refused](crate::runner::Capabilities::refused) instead of
+    /// failing the handshake.
+    pub batch_capabilities: bool,
+}
+
+impl HandshakeOptions {
+    /// The timeout of this phase, if there is one
+    pub fn timeout(&self, phase: HandshakePhase) -> Option<Duration> {
+        match phase {
+            HandshakePhase::Welcome => self.welcome_timeout,
+            HandshakePhase::Capabilities => self.capabilities_timeout,
+            HandshakePhase::GlobalUserState => self.global_user_state_timeout,
+        }
+    }
+}
@@ -4,21 +4,51 @@ mod capability;
 pub use capability::Capability;
 
 mod userconfig;
-pub use userconfig::{UserConfig, UserConfigBuilder, UserConfigError};
+pub use userconfig::{anonymous_nick, UserConfig, UserConfigBuilder, UserConfigError};
+
+mod handshake;
+pub use handshake::{HandshakeOptions, HandshakePhase, ReadyOn, RegisterOrder};
 
 pub mod attributes;
-pub use attributes::MsgRange;
 pub(crate) use attributes::{Attribution, AttributionVec};
+pub use attributes::{MsgRange, Spanned};
 
 mod emotes;
-pub use emotes::{Emote, EmoteSet, EmoteVec};
+pub use emotes::{Emote, EmoteModifier, EmoteSet, EmoteVec};
+
+cfg_async! {
+    mod emote_resolver;
+    pub use emote_resolver::{EmoteCache, EmoteInfo, EmoteResolver, ResolvedEmote};
+}
+
+pub mod cheer;
+pub use cheer::{parse_cheers, Cheer, CheerTier};
+
+pub mod value;
+pub use value::{ConversionRates, MonetaryValue};
+
+pub mod deprecation;
+pub use deprecation::Deprecation;
+
+pub mod schema;
+pub use schema::{SchemaDrift, TagSchema, TagType};
+
+pub mod sender;
+pub use sender::{SpecialSender, SpecialSenderPolicy};
 
 mod flags;
-pub use flags::{Flag, FlagVec};
+pub use flags::{Flag, FlagVec, Score, ScoreType};
+
+mod user_type;
+pub use user_type::UserType;
 
 mod badge;
+pub(crate) use badge::subscriber_months;
 pub use badge::{Badge, BadgeInfo, BadgeVec};
 
+mod badge_url;
+pub use badge_url::{badge_image_url, BadgeResolver, BadgeScale, ChannelBadges};
+
 pub mod color;
 #[doc(inline)]
-pub use color::Color;
+pub use color::{Color, ParseError};
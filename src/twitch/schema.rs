@@ -0,0 +1,395 @@
+/*!
+The tags this crate knows about, as data.
+
+This is meant for tooling: a GUI can show what a tag holds, a code generator
+can emit accessors in another language, and a bot can [check] the messages it
+receives to find tags Twitch started sending (or sends on other messages)
+before this table knows about them.
+
+```
+# use twitchchat::{irc::IrcMessage, twitch::schema::{self, TagType, SchemaDrift}};
+let bits = schema::tag("bits").unwrap();
+assert_eq!(bits.ty, TagType::U64);
+assert!(bits.is_sent_with(IrcMessage::PRIVMSG));
+
+let input = "@bits=100;pinned-chat-paid-amount=500 :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :cheer100\r\n";
+let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+assert_eq!(
+    schema::check(&msg),
+    vec![SchemaDrift::UnknownTag {
+        command: "PRIVMSG".into(),
+        tag: "pinned-chat-paid-amount".into(),
+    }]
+);
+```
+*/
+
+use crate::irc::{IrcMessage, Tags};
+use crate::Validator as _;
+
+/// How the value of a tag is parsed
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum TagType {
+    /// Free text, possibly escaped
+    String,
+    /// `0` or `1`
+    Bool,
+    /// An unsigned number, e.g. an id or a count
+    U64,
+    /// A list of badges, see [BadgeVec](crate::twitch::BadgeVec)
+    Badges,
+    /// A color like `#FF4500`, see [Color](crate::twitch::Color)
+    Color,
+    /// A list of emote set ids, see [EmoteSet](crate::twitch::EmoteSet)
+    EmoteSet,
+    /// Emotes and their ranges, see [EmoteVec](crate::twitch::EmoteVec)
+    Emotes,
+    /// Automod flags and their ranges, see [FlagVec](crate::twitch::FlagVec)
+    Flags,
+    /// Whether followers-only mode is on, and for how long you have to follow
+    FollowersOnly,
+    /// The kind of a `NOTICE` or `USERNOTICE`
+    MsgId,
+    /// A subscription plan, like `1000` or `Prime`
+    SubPlan,
+    /// The `user-type`, see [UserType](crate::twitch::UserType)
+    UserType,
+}
+
+impl TagType {
+    /// The Rust type this crate parses the tag as
+    pub fn rust_type(&self) -> &'static str {
+        match self {
+            Self::String => "&str",
+            Self::Bool => "bool",
+            Self::U64 => "u64",
+            Self::Badges => "BadgeVec",
+            Self::Color => "Color",
+            Self::EmoteSet => "EmoteSet",
+            Self::Emotes => "EmoteVec",
+            Self::Flags => "FlagVec",
+            Self::FollowersOnly => "FollowersOnly",
+            Self::MsgId => "NoticeType",
+            Self::SubPlan => "SubPlan",
+            Self::UserType => "UserType",
+        }
+    }
+}
+
+/// A tag, what it holds and which messages it is sent with
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct TagSchema {
+    /// The name of the tag, e.g. `display-name`
+    pub name: &'static str,
+    /// How its value is parsed
+    pub ty: TagType,
+    /// The IRC commands it is sent with, e.g. `PRIVMSG`
+    pub commands: &'static [&'static str],
+    /// Whether Twitch documents it as deprecated
+    pub deprecated: bool,
+}
+
+impl TagSchema {
+    /// Whether this tag is sent with this IRC command
+    pub fn is_sent_with(&self, command: &str) -> bool {
+        self.commands.contains(&command)
+    }
+}
+
+const fn known(name: &'static str, ty: TagType, commands: &'static [&'static str]) -> TagSchema {
+    TagSchema {
+        name,
+        ty,
+        commands,
+        deprecated: false,
+    }
+}
+
+const fn deprecated(
+    name: &'static str,
+    ty: TagType,
+    commands: &'static [&'static str],
+) -> TagSchema {
+    TagSchema {
+        name,
+        ty,
+        commands,
+        deprecated: true,
+    }
+}
+
+// the messages sent on behalf of a user carry most of the same tags
+const USER: &[&str] = &[
+    IrcMessage::PRIVMSG,
+    IrcMessage::USER_NOTICE,
+    IrcMessage::USER_STATE,
+    IrcMessage::GLOBAL_USER_STATE,
+    IrcMessage::WHISPER,
+];
+const CHAT: &[&str] = &[
+    IrcMessage::PRIVMSG,
+    IrcMessage::USER_NOTICE,
+    IrcMessage::USER_STATE,
+];
+const SENT: &[&str] = &[IrcMessage::PRIVMSG, IrcMessage::USER_NOTICE];
+const PRIVMSG: &[&str] = &[IrcMessage::PRIVMSG];
+const USER_NOTICE: &[&str] = &[IrcMessage::USER_NOTICE];
+const ROOM_STATE: &[&str] = &[IrcMessage::ROOM_STATE];
+const WHISPER: &[&str] = &[IrcMessage::WHISPER];
+
+/// Every tag this crate knows about
+pub const TAGS: &[TagSchema] = {
+    use TagType::*;
+    &[
+        known(
+            "badge-info",
+            Badges,
+            &[
+                IrcMessage::PRIVMSG,
+                IrcMessage::USER_NOTICE,
+                IrcMessage::USER_STATE,
+                IrcMessage::GLOBAL_USER_STATE,
+            ],
+        ),
+        known("badges", Badges, USER),
+        known("ban-duration", U64, &[IrcMessage::CLEAR_CHAT]),
+        known("bits", U64, PRIVMSG),
+        known("client-nonce", String, PRIVMSG),
+        known("color", Color, USER),
+        known("custom-reward-id", String, PRIVMSG),
+        known("display-name", String, USER),
+        known(
+            "emote-only",
+            Bool,
+            &[IrcMessage::PRIVMSG, IrcMessage::ROOM_STATE],
+        ),
+        known(
+            "emote-sets",
+            EmoteSet,
+            &[IrcMessage::USER_STATE, IrcMessage::GLOBAL_USER_STATE],
+        ),
+        known(
+            "emotes",
+            Emotes,
+            &[
+                IrcMessage::PRIVMSG,
+                IrcMessage::USER_NOTICE,
+                IrcMessage::WHISPER,
+            ],
+        ),
+        known("first-msg", Bool, PRIVMSG),
+        known("flags", Flags, SENT),
+        known("followers-only", FollowersOnly, ROOM_STATE),
+        known("id", String, CHAT),
+        known(
+            "login",
+            String,
+            &[IrcMessage::USER_NOTICE, IrcMessage::CLEAR_MSG],
+        ),
+        known("message-id", String, WHISPER),
+        known("mod", Bool, CHAT),
+        known(
+            "msg-id",
+            MsgId,
+            &[IrcMessage::NOTICE, IrcMessage::USER_NOTICE],
+        ),
+        known("msg-param-category", String, USER_NOTICE),
+        known("msg-param-copoReward", U64, USER_NOTICE),
+        known("msg-param-cumulative-months", U64, USER_NOTICE),
+        known("msg-param-displayName", String, USER_NOTICE),
+        known("msg-param-gift-months", U64, USER_NOTICE),
+        known("msg-param-id", String, USER_NOTICE),
+        known("msg-param-login", String, USER_NOTICE),
+        known("msg-param-months", U64, USER_NOTICE),
+        known("msg-param-promo-gift-total", U64, USER_NOTICE),
+        known("msg-param-promo-name", String, USER_NOTICE),
+        known("msg-param-recipient-display-name", String, USER_NOTICE),
+        known("msg-param-recipient-id", U64, USER_NOTICE),
+        known("msg-param-recipient-user-name", String, USER_NOTICE),
+        known("msg-param-ritual-name", String, USER_NOTICE),
+        known("msg-param-sender-login", String, USER_NOTICE),
+        known("msg-param-sender-name", String, USER_NOTICE),
+        known("msg-param-should-share-streak", Bool, USER_NOTICE),
+        known("msg-param-streak-months", U64, USER_NOTICE),
+        known("msg-param-sub-plan", SubPlan, USER_NOTICE),
+        known("msg-param-sub-plan-name", String, USER_NOTICE),
+        known("msg-param-threshold", U64, USER_NOTICE),
+        known("msg-param-value", U64, USER_NOTICE),
+        known("msg-param-viewerCount", U64, USER_NOTICE),
+        known("r9k", Bool, ROOM_STATE),
+        known("reply-parent-display-name", String, PRIVMSG),
+        known("reply-parent-msg-body", String, PRIVMSG),
+        known("reply-parent-msg-id", String, PRIVMSG),
+        known("reply-parent-user-id", U64, PRIVMSG),
+        known("reply-parent-user-login", String, PRIVMSG),
+        known("returning-chatter", Bool, PRIVMSG),
+        known(
+            "room-id",
+            U64,
+            &[
+                IrcMessage::PRIVMSG,
+                IrcMessage::USER_NOTICE,
+                IrcMessage::ROOM_STATE,
+                IrcMessage::CLEAR_CHAT,
+                IrcMessage::CLEAR_MSG,
+            ],
+        ),
+        known("slow", U64, ROOM_STATE),
+        known("subs-only", Bool, ROOM_STATE),
+        deprecated("subscriber", Bool, CHAT),
+        known("system-msg", String, USER_NOTICE),
+        known("target-msg-id", String, &[IrcMessage::CLEAR_MSG]),
+        known("target-user-id", U64, &[IrcMessage::CLEAR_CHAT]),
+        known("thread-id", String, WHISPER),
+        known(
+            "tmi-sent-ts",
+            U64,
+            &[
+                IrcMessage::PRIVMSG,
+                IrcMessage::USER_NOTICE,
+                IrcMessage::CLEAR_CHAT,
+                IrcMessage::CLEAR_MSG,
+            ],
+        ),
+        deprecated("turbo", Bool, USER),
+        known(
+            "user-id",
+            U64,
+            &[
+                IrcMessage::PRIVMSG,
+                IrcMessage::USER_NOTICE,
+                IrcMessage::GLOBAL_USER_STATE,
+                IrcMessage::WHISPER,
+            ],
+        ),
+        known("user-type", UserType, USER),
+        known("vip", Bool, PRIVMSG),
+    ]
+};
+
+/// Every tag this crate knows about, see [TAGS]
+pub fn tag_schema() -> &'static [TagSchema] {
+    TAGS
+}
+
+/// Look up a tag by its name
+pub fn tag(name: &str) -> Option<&'static TagSchema> {
+    TAGS.iter().find(|schema| schema.name == name)
+}
+
+/// The tags that are sent with this IRC command, e.g. `PRIVMSG`
+pub fn tags_for(command: &str) -> impl Iterator<Item = &'static TagSchema> + '_ {
+    TAGS.iter()
+        .filter(move |schema| schema.is_sent_with(command))
+}
+
+/// A difference between a message and the [TAGS], see [check]
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum SchemaDrift {
+    /// The tag isn't known at all
+    UnknownTag {
+        /// The IRC command of the message
+        command: String,
+        /// The name of the tag
+        tag: String,
+    },
+    /// The tag is known, but not on this command
+    UnexpectedTag {
+        /// The IRC command of the message
+        command: String,
+        /// The name of the tag
+        tag: String,
+    },
+}
+
+/// Check the tags of a message against the [TAGS]
+pub fn check(msg: &IrcMessage<'_>) -> Vec<SchemaDrift> {
+    let command = msg.get_command();
+    let mut found = vec![];
+    if msg.get_tags().is_none() {
+        return found;
+    }
+
+    let indices = msg.parse_tags();
+    let tags = Tags::from_data_indices(&msg.raw, &indices);
+    for (name, _) in tags.iter() {
+        match tag(name) {
+            None => found.push(SchemaDrift::UnknownTag {
+                command: command.to_string(),
+                tag: name.to_string(),
+            }),
+            Some(schema) if !schema.is_sent_with(command) => {
+                found.push(SchemaDrift::UnexpectedTag {
+                    command: command.to_string(),
+                    tag: name.to_string(),
+                })
+            }
+            Some(..) => {}
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::twitch::deprecation::DEPRECATED_TAGS;
+
+    fn parse(input: &str) -> IrcMessage<'_> {
+        crate::irc::parse(input).next().unwrap().unwrap()
+    }
+
+    #[test]
+    fn unique() {
+        for (i, schema) in TAGS.iter().enumerate() {
+            assert!(
+                TAGS.iter()
+                    .skip(i + 1)
+                    .all(|other| other.name != schema.name),
+                "'{}' is in the table twice",
+                schema.name
+            );
+            assert!(
+                !schema.commands.is_empty(),
+                "'{}' has no commands",
+                schema.name
+            );
+        }
+
+        let deprecated = TAGS
+            .iter()
+            .filter(|schema| schema.deprecated)
+            .map(|schema| schema.name)
+            .collect::<Vec<_>>();
+        assert_eq!(deprecated, DEPRECATED_TAGS);
+    }
+
+    #[test]
+    fn drift() {
+        let msg = parse("@badge-info=;badges=broadcaster/1;color=#FF69B4;display-name=museun;emotes=;flags=;id=6a6a6a;mod=0;room-id=23196011;subscriber=0;tmi-sent-ts=1601079032426;turbo=0;user-id=23196011;user-type= :museun!museun@museun.tmi.twitch.tv PRIVMSG #museun :hello\r\n");
+        assert!(check(&msg).is_empty());
+
+        let msg = parse("@bits=100;x-future-tag=1 :tmi.twitch.tv USERSTATE #museun\r\n");
+        assert_eq!(
+            check(&msg),
+            vec![
+                SchemaDrift::UnexpectedTag {
+                    command: "USERSTATE".into(),
+                    tag: "bits".into()
+                },
+                SchemaDrift::UnknownTag {
+                    command: "USERSTATE".into(),
+                    tag: "x-future-tag".into()
+                },
+            ]
+        );
+
+        assert!(tags_for(IrcMessage::ROOM_STATE).any(|schema| schema.name == "slow"));
+        assert_eq!(tag("slow").unwrap().ty.rust_type(), "u64");
+    }
+}
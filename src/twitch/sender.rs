@@ -0,0 +1,134 @@
+/*!
+Detection of the pseudo-users Twitch sends messages as.
+
+Most server messages come from `tmi.twitch.tv`, but some legacy lines (e.g.
+`MODE` and old `NOTICE`s) come from the `jtv` user. Neither of them is a real
+chatter, so anything tracking chatters should route them elsewhere.
+
+```
+# use twitchchat::twitch::SpecialSender;
+let input = ":jtv!jtv@jtv.tmi.twitch.tv MODE #museun +o shaken_bot\r\n";
+let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+assert_eq!(SpecialSender::check(&msg), Some(SpecialSender::Jtv));
+
+let input = ":museun!museun@museun.tmi.twitch.tv JOIN #museun\r\n";
+let msg = twitchchat::irc::parse(input).next().unwrap().unwrap();
+assert_eq!(SpecialSender::check(&msg), None);
+```
+*/
+
+use crate::irc::IrcMessage;
+
+/// A pseudo-user that Twitch sends messages as
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum SpecialSender {
+    /// The server itself, `tmi.twitch.tv`
+    Server,
+    /// The legacy `jtv` user
+    Jtv,
+}
+
+impl SpecialSender {
+    /// The name of the server
+    pub const SERVER: &'static str = "tmi.twitch.tv";
+
+    /// The name of the legacy user
+    pub const JTV: &'static str = "jtv";
+
+    /// The name this sender uses
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Server => Self::SERVER,
+            Self::Jtv => Self::JTV,
+        }
+    }
+
+    /// Is this name one of the special senders?
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Server, Self::Jtv]
+            .iter()
+            .copied()
+            .find(|sender| sender.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Is this raw prefix (e.g. `jtv!jtv@jtv.tmi.twitch.tv`) one of the special senders?
+    pub fn from_prefix(prefix: &str) -> Option<Self> {
+        let name = prefix.split('!').next().unwrap_or(prefix);
+        Self::from_name(name)
+    }
+
+    /// Check whether a message was sent by one of the special senders
+    pub fn check(msg: &IrcMessage<'_>) -> Option<Self> {
+        msg.get_prefix().and_then(Self::from_prefix)
+    }
+}
+
+impl std::fmt::Display for SpecialSender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// What to do with the messages of the special senders
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SpecialSenderPolicy {
+    /// Skip them, this is the default
+    Ignore,
+    /// Treat them like any other user
+    Include,
+}
+
+impl Default for SpecialSenderPolicy {
+    fn default() -> Self {
+        Self::Ignore
+    }
+}
+
+impl SpecialSenderPolicy {
+    /// Should something from this name be kept?
+    pub fn allows(self, name: &str) -> bool {
+        match self {
+            Self::Ignore => SpecialSender::from_name(name).is_none(),
+            Self::Include => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(input: &str) -> Option<SpecialSender> {
+        let msg = crate::irc::parse(input).next().unwrap().unwrap();
+        SpecialSender::check(&msg)
+    }
+
+    #[test]
+    fn detect() {
+        assert_eq!(
+            check(":tmi.twitch.tv CLEARCHAT #museun\r\n"),
+            Some(SpecialSender::Server)
+        );
+        assert_eq!(
+            check(":jtv MODE #museun +o museun\r\n"),
+            Some(SpecialSender::Jtv)
+        );
+        assert_eq!(
+            check(":JTV!jtv@jtv.tmi.twitch.tv PRIVMSG #museun :hello\r\n"),
+            Some(SpecialSender::Jtv)
+        );
+        assert_eq!(
+            check(":jtvfan!jtvfan@jtvfan.tmi.twitch.tv JOIN #museun\r\n"),
+            None
+        );
+        assert_eq!(check("PING :tmi.twitch.tv\r\n"), None);
+    }
+
+    #[test]
+    fn policy() {
+        assert!(!SpecialSenderPolicy::default().allows("jtv"));
+        assert!(SpecialSenderPolicy::default().allows("museun"));
+        assert!(SpecialSenderPolicy::Include.allows("tmi.twitch.tv"));
+    }
+}
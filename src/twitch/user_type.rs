@@ -0,0 +1,42 @@
+/// The `user-type` tag of a user
+///
+/// Twitch sends this tag as empty (`user-type=`) for ordinary users, so an empty
+/// tag is [UserType::Normal], while a missing tag means the type is unknown.
+///
+/// ```
+/// # use twitchchat::twitch::UserType;
+/// assert_eq!(UserType::from_tag(""), UserType::Normal);
+/// assert_eq!(UserType::from_tag("global_mod"), UserType::GlobalModerator);
+/// assert_eq!(UserType::from_tag("something_new"), UserType::Unknown);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[non_exhaustive]
+pub enum UserType {
+    /// An ordinary user, sent as an empty tag
+    Normal,
+    /// A moderator of the channel, `mod`
+    Moderator,
+    /// A global moderator, `global_mod`
+    GlobalModerator,
+    /// A Twitch admin, `admin`
+    Admin,
+    /// A Twitch staff member, `staff`
+    Staff,
+    /// A type this crate doesn't know about
+    Unknown,
+}
+
+impl UserType {
+    /// Get the type from the value of a `user-type` tag
+    pub fn from_tag(value: &str) -> Self {
+        match value {
+            "" => Self::Normal,
+            "mod" => Self::Moderator,
+            "global_mod" => Self::GlobalModerator,
+            "admin" => Self::Admin,
+            "staff" => Self::Staff,
+            _ => Self::Unknown,
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::twitch::Capability;
+use crate::twitch::{Capability, HandshakeOptions, ReadyOn, RegisterOrder};
 use std::collections::BTreeSet;
 
 /**
@@ -35,6 +35,9 @@ pub struct UserConfig {
     pub token: String,
     /// Capabilities to be requested from the server
     pub capabilities: Vec<Capability>,
+    /// How to register, and when the connection is ready
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub handshake: HandshakeOptions,
 }
 
 impl UserConfig {
@@ -45,10 +48,39 @@ impl UserConfig {
 
     /// Determines whether this config was requested as anonymous
     pub fn is_anonymous(&self) -> bool {
-        self.name == crate::JUSTINFAN1234 && self.token == crate::JUSTINFAN1234
+        is_anonymous_nick(&self.name) && self.token == crate::JUSTINFAN1234
     }
 }
 
+/// A `justinfan` nick for an anonymous login, derived from a seed.
+///
+/// The same `machine_id` and `shard` always give the same nick, so a fleet of
+/// read-only connections can pick distinct nicks without coordinating (or
+/// rolling dice at startup). The shards of one machine never collide, as long
+/// as they're below 10000. Two machines collide with a chance of about one in
+/// a million. It is never the shared `justinfan1234`.
+///
+/// ```
+/// # use twitchchat::twitch::anonymous_nick;
+/// let nick = anonymous_nick("worker-eu-1", 0);
+/// assert!(nick.starts_with("justinfan"));
+/// assert_eq!(nick, anonymous_nick("worker-eu-1", 0));
+/// assert_ne!(nick, anonymous_nick("worker-eu-1", 1));
+/// ```
+pub fn anonymous_nick(machine_id: &str, shard: u32) -> String {
+    let hash = crate::messages::Fnv::new().write(machine_id.bytes()).0;
+    // starting the machine part at 1 keeps the nick clear of `justinfan1234`
+    let machine = hash % 1_000_000 + 1;
+    format!("justinfan{}", machine * 10_000 + u64::from(shard % 10_000))
+}
+
+// `justinfan` followed by digits, which Twitch accepts without a token
+fn is_anonymous_nick(name: &str) -> bool {
+    name.strip_prefix("justinfan")
+        .filter(|digits| !digits.is_empty() && digits.bytes().all(|c| c.is_ascii_digit()))
+        .is_some()
+}
+
 /// User config error returned by the [UserConfigBuilder]
 #[non_exhaustive]
 #[derive(Debug, Copy, Clone)]
@@ -59,6 +91,9 @@ pub enum UserConfigError {
     InvalidToken,
     /// Anonymous login was requested with a user-provided name or token
     PartialAnonymous,
+    /// The handshake would wait for a `GLOBALUSERSTATE` that Twitch doesn't
+    /// send, without a timeout. See [ReadyOn::GlobalUserState]
+    NeverReady,
 }
 
 impl std::fmt::Display for UserConfigError {
@@ -71,6 +106,9 @@ impl std::fmt::Display for UserConfigError {
             Self::PartialAnonymous => f.write_str(
                 "user provided name or token provided when an anonymous login was requested",
             ),
+            Self::NeverReady => f.write_str(
+                "waiting for GLOBALUSERSTATE without a timeout, but it isn't sent when the credentials are sent first",
+            ),
         }
     }
 }
@@ -84,6 +122,8 @@ pub struct UserConfigBuilder {
     capabilities: BTreeSet<Capability>,
     name: Option<String>,
     token: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    handshake: HandshakeOptions,
 }
 
 impl UserConfigBuilder {
@@ -112,6 +152,15 @@ impl UserConfigBuilder {
         self.name(name).token(token)
     }
 
+    /// Uses an anonymous login, with a nick derived from `machine_id` and `shard`
+    ///
+    /// See [anonymous_nick] for how the nick is picked. The connected nick is
+    /// available from [Identity::username](crate::runner::Identity::username).
+    pub fn anonymous_seeded(self, machine_id: &str, shard: u32) -> Self {
+        self.name(anonymous_nick(machine_id, shard))
+            .token(crate::JUSTINFAN1234)
+    }
+
     /// Capabilities to enable
     ///
     pub fn capabilities(mut self, caps: &[Capability]) -> Self {
@@ -132,6 +181,12 @@ impl UserConfigBuilder {
         ])
     }
 
+    /// How to register, and when the connection is ready
+    pub fn handshake(mut self, handshake: HandshakeOptions) -> Self {
+        self.handshake = handshake;
+        self
+    }
+
     /// Tries to build the UserConfig
     ///
     /// This returns an error if the name or token are invalid
@@ -150,20 +205,26 @@ impl UserConfigBuilder {
             .filter(|s| validate_token(s))
             .ok_or(UserConfigError::InvalidToken)?;
 
-        match (name.as_str(), token.as_str()) {
-            (crate::JUSTINFAN1234, crate::JUSTINFAN1234) => {
-                // both are allowed
-            }
-            (crate::JUSTINFAN1234, ..) | (.., crate::JUSTINFAN1234) => {
-                return Err(UserConfigError::PartialAnonymous)
-            }
-            _ => {}
+        // an anonymous nick is only allowed with the anonymous token, and vice versa
+        if is_anonymous_nick(&name) != (token == crate::JUSTINFAN1234) {
+            return Err(UserConfigError::PartialAnonymous);
+        }
+
+        // anonymous logins are ready at the end of the MOTD instead
+        let handshake = self.handshake;
+        if handshake.ready_on == ReadyOn::GlobalUserState
+            && handshake.order == RegisterOrder::CredentialsFirst
+            && handshake.global_user_state_timeout.is_none()
+            && token != crate::JUSTINFAN1234
+        {
+            return Err(UserConfigError::NeverReady);
         }
 
         Ok(UserConfig {
             name,
             token,
             capabilities: self.capabilities.into_iter().collect(),
+            handshake: self.handshake,
         })
     }
 }
@@ -178,7 +239,7 @@ fn validate_token(s: &str) -> bool {
     if s == crate::JUSTINFAN1234 {
         return true;
     }
-    !s.is_empty() && s.len() == 36 && &s[..6] == "oauth:"
+    s.len() == 36 && s.starts_with("oauth:")
 }
 
 #[cfg(test)]
@@ -199,6 +260,7 @@ mod tests {
                 name: "foo".to_string(),
                 token: format!("oauth:{}", "a".repeat(30)),
                 capabilities: vec![],
+                handshake: HandshakeOptions::default(),
             }
         )
     }
@@ -219,6 +281,7 @@ mod tests {
                 name: "foo".to_string(),
                 token: format!("oauth:{}", "a".repeat(30)),
                 capabilities: vec![Capability::Membership, Capability::Tags,],
+                handshake: HandshakeOptions::default(),
             }
         )
     }
@@ -233,12 +296,71 @@ mod tests {
                 name: crate::JUSTINFAN1234.to_string(),
                 token: crate::JUSTINFAN1234.to_string(),
                 capabilities: vec![],
+                handshake: HandshakeOptions::default(),
             }
         );
 
         assert!(config.is_anonymous());
     }
 
+    #[test]
+    fn valid_user_config_anonymous_seeded() {
+        let config = UserConfig::builder()
+            .anonymous_seeded("worker-eu-1", 3)
+            .build()
+            .unwrap();
+        assert!(config.is_anonymous());
+        assert_eq!(config.name, anonymous_nick("worker-eu-1", 3));
+        assert_eq!(config.token, crate::JUSTINFAN1234);
+
+        let nicks = (0..100)
+            .map(|shard| anonymous_nick("worker-eu-1", shard))
+            .chain((0..100).map(|shard| anonymous_nick("worker-eu-2", shard)))
+            .collect::<BTreeSet<_>>();
+        assert_eq!(nicks.len(), 200);
+        assert!(!nicks.contains(crate::JUSTINFAN1234));
+
+        let err = UserConfig::builder()
+            .name(anonymous_nick("worker-eu-1", 3))
+            .token(format!("oauth:{}", "a".repeat(30)))
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, UserConfigError::PartialAnonymous));
+    }
+
+    #[test]
+    fn never_ready() {
+        let handshake = HandshakeOptions {
+            order: RegisterOrder::CredentialsFirst,
+            ready_on: ReadyOn::GlobalUserState,
+            ..HandshakeOptions::default()
+        };
+        let builder = || {
+            UserConfig::builder()
+                .name("foo")
+                .token(format!("oauth:{}", "a".repeat(30)))
+        };
+
+        let err = builder().handshake(handshake).build().unwrap_err();
+        assert!(matches!(err, UserConfigError::NeverReady));
+
+        let handshake = HandshakeOptions {
+            global_user_state_timeout: Some(std::time::Duration::from_secs(5)),
+            ..handshake
+        };
+        assert!(builder().handshake(handshake).build().is_ok());
+
+        // anonymous logins don't wait for it
+        let config = UserConfig::builder()
+            .anonymous()
+            .handshake(HandshakeOptions {
+                global_user_state_timeout: None,
+                ..handshake
+            })
+            .build();
+        assert!(config.is_ok());
+    }
+
     #[test]
     fn invalid_name_missing() {
         let err = UserConfig::builder().build().unwrap_err();
@@ -300,4 +422,14 @@ mod tests {
             .unwrap_err();
         matches!(err, UserConfigError::InvalidToken);
     }
+
+    #[test]
+    fn invalid_token_multibyte() {
+        let err = UserConfig::builder()
+            .name("foobar")
+            .token(format!("oauthé{}", "a".repeat(29)))
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, UserConfigError::InvalidToken));
+    }
 }
@@ -0,0 +1,192 @@
+/*!
+Normalized monetary values for bits, subscriptions and Hype Chats.
+
+These are estimates for analytics -- not what the broadcaster is paid. Bits are
+valued at one US cent each, and subscriptions at their US list price.
+
+```
+# use twitchchat::twitch::{ConversionRates, MonetaryValue};
+let rates = ConversionRates::new("USD").rate("EUR", 1.10);
+
+let values = vec![
+    MonetaryValue::from_bits(500),
+    MonetaryValue::new(1000, 2, "EUR"),
+];
+
+let total = rates.total(&values).unwrap();
+assert_eq!(total, MonetaryValue::new(1600, 2, "USD"));
+assert_eq!(total.to_string(), "16.00 USD");
+```
+*/
+
+#[cfg(feature = "usernotice")]
+use crate::messages::SubPlan;
+use std::collections::HashMap;
+
+/// An amount of money in some currency
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MonetaryValue {
+    /// The amount, in minor units (e.g. cents)
+    pub amount: u64,
+    /// The number of decimal places in `amount`, e.g. `2` for cents
+    pub exponent: u32,
+    /// The ISO 4217 currency code, e.g. `USD`
+    pub currency: String,
+}
+
+impl MonetaryValue {
+    /// Create a new value of `amount` minor units with `exponent` decimal places
+    pub fn new(amount: u64, exponent: u32, currency: impl Into<String>) -> Self {
+        Self {
+            amount,
+            exponent,
+            currency: currency.into(),
+        }
+    }
+
+    /// The value of this many bits, at one US cent per bit
+    pub fn from_bits(bits: u64) -> Self {
+        Self::new(bits, 2, "USD")
+    }
+
+    /// The US list price of this subscription plan.
+    ///
+    /// `Prime` is valued as a Tier 1 subscription. Returns None for an unknown plan
+    #[cfg(feature = "usernotice")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "usernotice")))]
+    pub fn from_sub_plan(plan: &SubPlan) -> Option<Self> {
+        let amount = match plan {
+            SubPlan::Prime | SubPlan::Tier1 => 499,
+            SubPlan::Tier2 => 999,
+            SubPlan::Tier3 => 2499,
+            _ => return None,
+        };
+        Some(Self::new(amount, 2, "USD"))
+    }
+
+    /// The amount in major units (e.g. dollars)
+    pub fn as_f64(&self) -> f64 {
+        self.amount as f64 / 10_f64.powi(self.exponent as i32)
+    }
+}
+
+impl std::fmt::Display for MonetaryValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.*} {}",
+            self.exponent as usize,
+            self.as_f64(),
+            self.currency
+        )
+    }
+}
+
+/// User-supplied conversion rates, for normalizing [MonetaryValue]s into a single currency
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionRates {
+    target: String,
+    rates: HashMap<String, f64>,
+}
+
+impl ConversionRates {
+    /// The number of decimal places of converted values
+    pub const EXPONENT: u32 = 2;
+
+    /// Create rates that convert into the `target` currency
+    pub fn new(target: impl Into<String>) -> Self {
+        let target = target.into();
+        let mut rates = HashMap::new();
+        rates.insert(target.clone(), 1.0);
+        Self { target, rates }
+    }
+
+    /// Set the rate for `currency`: how much of the target currency one unit of it is worth
+    pub fn rate(mut self, currency: impl Into<String>, rate: f64) -> Self {
+        self.rates.insert(currency.into(), rate);
+        self
+    }
+
+    /// The target currency
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Convert `value` into the target currency, rounded to [ConversionRates::EXPONENT] decimal places.
+    ///
+    /// Returns None if there is no rate for the currency of `value`
+    pub fn convert(&self, value: &MonetaryValue) -> Option<MonetaryValue> {
+        let rate = self.rates.get(&value.currency)?;
+        let amount = value.as_f64() * rate * 10_f64.powi(Self::EXPONENT as i32);
+        Some(MonetaryValue::new(
+            amount.round() as u64,
+            Self::EXPONENT,
+            self.target.clone(),
+        ))
+    }
+
+    /// Convert all of `values` into the target currency and add them up
+    ///
+    /// Returns None if there is no rate for any of the currencies
+    pub fn total<'a, I>(&self, values: I) -> Option<MonetaryValue>
+    where
+        I: IntoIterator<Item = &'a MonetaryValue>,
+    {
+        let mut total = MonetaryValue::new(0, Self::EXPONENT, self.target.clone());
+        for value in values {
+            total.amount += self.convert(value)?.amount;
+        }
+        Some(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources() {
+        assert_eq!(MonetaryValue::from_bits(100).to_string(), "1.00 USD");
+        assert_eq!(MonetaryValue::new(1500, 0, "JPY").to_string(), "1500 JPY");
+    }
+
+    #[test]
+    #[cfg(feature = "usernotice")]
+    fn sub_plans() {
+        assert_eq!(
+            MonetaryValue::from_sub_plan(&SubPlan::Tier3).unwrap(),
+            MonetaryValue::new(2499, 2, "USD")
+        );
+        assert_eq!(
+            MonetaryValue::from_sub_plan(&SubPlan::Prime),
+            MonetaryValue::from_sub_plan(&SubPlan::Tier1)
+        );
+        assert!(MonetaryValue::from_sub_plan(&SubPlan::Unknown("4000".into())).is_none());
+    }
+
+    #[test]
+    fn convert() {
+        let rates = ConversionRates::new("EUR")
+            .rate("USD", 0.9)
+            .rate("JPY", 0.0062);
+
+        let value = rates.convert(&MonetaryValue::from_bits(1000)).unwrap();
+        assert_eq!(value, MonetaryValue::new(900, 2, "EUR"));
+
+        let value = rates.convert(&MonetaryValue::new(1500, 0, "JPY")).unwrap();
+        assert_eq!(value, MonetaryValue::new(930, 2, "EUR"));
+
+        let value = rates.convert(&MonetaryValue::new(1234, 3, "EUR")).unwrap();
+        assert_eq!(value, MonetaryValue::new(123, 2, "EUR"));
+
+        assert!(rates.convert(&MonetaryValue::new(100, 2, "GBP")).is_none());
+        assert!(rates
+            .total(&[
+                MonetaryValue::from_bits(1),
+                MonetaryValue::new(100, 2, "GBP")
+            ])
+            .is_none());
+        assert_eq!(rates.total(&[]).unwrap(), MonetaryValue::new(0, 2, "EUR"));
+    }
+}
@@ -6,6 +6,20 @@ pub fn timestamp() -> u64 {
         .as_secs()
 }
 
+// a `client-nonce` that is unique in this process, and unlikely to be used by another one
+pub(crate) fn nonce() -> String {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    format!(
+        "{:x}{:08x}",
+        now.as_millis(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
 cfg_async! {
 use futures_lite::future::{ready, Ready};
 use std::{
@@ -24,7 +24,8 @@ pub trait Validator {
 impl<'a> Validator for IrcMessage<'a> {
     fn parse_tags(&self) -> TagIndices {
         self.tags
-            .and_then(|index| TagIndices::build_indices(&self.raw[index]).ok())
+            .and_then(|index| index.get(&self.raw))
+            .and_then(|tags| TagIndices::build_indices(tags).ok())
             .unwrap_or_default()
     }
 
@@ -41,7 +42,8 @@ impl<'a> Validator for IrcMessage<'a> {
     fn expect_nick(&self) -> Result<MaybeOwnedIndex, MessageError> {
         self.prefix
             .and_then(|p| p.nick_index())
-            .ok_or(MessageError::ExpectedNick)
+            .ok_or(MessageError::ExpectedNick)?
+            .check(&self.raw)
     }
 
     fn expect_arg(&self, nth: usize) -> Result<&str, MessageError> {
@@ -51,7 +53,8 @@ impl<'a> Validator for IrcMessage<'a> {
 
     fn expect_arg_index(&self, nth: usize) -> Result<MaybeOwnedIndex, MessageError> {
         self.nth_arg_index(nth)
-            .ok_or(MessageError::ExpectedArg { pos: nth })
+            .ok_or(MessageError::ExpectedArg { pos: nth })?
+            .check(&self.raw)
     }
 
     fn expect_data(&self) -> Result<&str, MessageError> {
@@ -59,6 +62,8 @@ impl<'a> Validator for IrcMessage<'a> {
     }
 
     fn expect_data_index(&self) -> Result<MaybeOwnedIndex, MessageError> {
-        self.data.ok_or(MessageError::ExpectedData)
+        self.data
+            .ok_or(MessageError::ExpectedData)?
+            .check(&self.raw)
     }
 }
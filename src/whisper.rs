@@ -0,0 +1,156 @@
+/*!
+Sending whispers without caring about how they are sent.
+
+Twitch heavily restricts whispers sent over IRC (as `/w`) for unverified bots,
+and silently drops most of them. With the `helix` feature they can be sent with
+the [Helix](crate::helix) API instead.
+
+Both ways implement [SendWhisper], and a [WhisperConfig] picks one of them, so
+the rest of a bot doesn't need to know which one is used.
+
+```no_run
+# use twitchchat::{connector::Connector, whisper::{SendWhisper as _, WhisperConfig}};
+# async fn demo(runner: &twitchchat::AsyncRunner, connector: impl Connector + 'static) {
+let mut sender = WhisperConfig::Irc.into_sender(runner.writer(), connector);
+sender.send_whisper("museun", "hello world").await.unwrap();
+# }
+```
+*/
+
+use crate::{
+    writer::{AsyncWriter, SendError},
+    BoxedFuture,
+};
+use futures_lite::AsyncWrite;
+
+/// An error returned when sending a whisper
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An I/O error occurred while writing to the connection
+    Io(std::io::Error),
+    /// The writer refused to send the whisper, e.g. because of its rate limit
+    Send(SendError),
+    /// The Helix API returned an error
+    #[cfg(feature = "helix")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "helix")))]
+    Helix(crate::helix::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io error: {}", err),
+            Self::Send(err) => write!(f, "cannot send whisper: {}", err),
+            #[cfg(feature = "helix")]
+            Self::Helix(err) => write!(f, "helix error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Send(err) => Some(err),
+            #[cfg(feature = "helix")]
+            Self::Helix(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<SendError> for Error {
+    fn from(err: SendError) -> Self {
+        match err {
+            SendError::Io(err) => Self::Io(err),
+            err => Self::Send(err),
+        }
+    }
+}
+
+#[cfg(feature = "helix")]
+impl From<crate::helix::Error> for Error {
+    fn from(err: crate::helix::Error) -> Self {
+        Self::Helix(err)
+    }
+}
+
+/// Something that can send a whisper
+pub trait SendWhisper {
+    /// Whisper this `message` to `username`
+    fn send_whisper(&mut self, username: &str, message: &str) -> BoxedFuture<Result<(), Error>>;
+}
+
+impl<W> SendWhisper for AsyncWriter<W>
+where
+    W: AsyncWrite + Clone + Unpin + Send + Sync + 'static,
+{
+    fn send_whisper(&mut self, username: &str, message: &str) -> BoxedFuture<Result<(), Error>> {
+        let mut this = self.clone();
+        let (username, message) = (username.to_string(), message.to_string());
+        Box::pin(async move {
+            let cmd = crate::commands::whisper(&username, &message);
+            this.encode(cmd).await.map_err(Into::into)
+        })
+    }
+}
+
+#[cfg(feature = "helix")]
+impl<C> SendWhisper for crate::helix::HelixWhisper<C>
+where
+    C: crate::connector::Connector + 'static,
+{
+    fn send_whisper(&mut self, username: &str, message: &str) -> BoxedFuture<Result<(), Error>> {
+        let mut this = self.clone();
+        let (username, message) = (username.to_string(), message.to_string());
+        Box::pin(async move { this.whisper(&username, &message).await.map_err(Into::into) })
+    }
+}
+
+/// Which way whispers are sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WhisperConfig {
+    /// As a `/w` command over the IRC connection, this is the default
+    Irc,
+    /// With the Helix API
+    #[cfg(feature = "helix")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "helix")))]
+    Helix(crate::helix::HelixConfig),
+}
+
+impl Default for WhisperConfig {
+    fn default() -> Self {
+        Self::Irc
+    }
+}
+
+impl WhisperConfig {
+    /// Create the sender this configures.
+    ///
+    /// The `writer` is used for IRC, and the `connector` for Helix.
+    pub fn into_sender<W, C>(
+        self,
+        writer: AsyncWriter<W>,
+        connector: C,
+    ) -> Box<dyn SendWhisper + Send + Sync>
+    where
+        W: AsyncWrite + Clone + Unpin + Send + Sync + 'static,
+        C: crate::connector::Connector + 'static,
+    {
+        match self {
+            Self::Irc => {
+                let _ = connector;
+                Box::new(writer)
+            }
+            #[cfg(feature = "helix")]
+            Self::Helix(config) => Box::new(crate::helix::HelixWhisper::new(connector, config)),
+        }
+    }
+}
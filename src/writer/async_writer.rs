@@ -1,16 +1,39 @@
+use super::SendError;
 use crate::channel::Sender;
 use crate::encoder::AsyncEncoder;
-use crate::Encodable;
+use crate::irc::{IrcMessage, Limits};
+use crate::{rate_limit::RateLimit, Encodable, MaybeOwned, MaybeOwnedIndex, MessageError};
 
 use futures_lite::AsyncWrite;
 use io::Write;
-use std::io::{self};
+use std::{
+    io::{self},
+    sync::{Arc, Mutex},
+};
+#[cfg(feature = "sink")]
+use {
+    futures_sink::Sink,
+    std::{
+        pin::Pin,
+        task::{Context, Poll},
+    },
+};
 
 /// An asynchronous writer.
+///
+/// Before anything is written, every line of a message is checked against the
+/// optional [Limits] of the writer, the channel of `JOIN`, `PART` and `PRIVMSG`
+/// lines is checked, and a token is taken from the optional [RateLimit] for
+/// each line. If any of these fail nothing is written, and the reason is
+/// returned as a [SendError].
+///
+/// Clones of a writer share its rate limit.
 #[derive(Clone)]
 pub struct AsyncWriter<W> {
     inner: AsyncEncoder<W>,
     activity_tx: Sender<()>,
+    limits: Limits,
+    rate_limit: Option<Arc<Mutex<RateLimit>>>,
 }
 
 impl<W> std::fmt::Debug for AsyncWriter<W> {
@@ -24,11 +47,13 @@ where
     W: Write + Send + Sync,
 {
     /// If the wrapped writer is synchronous, you can use this method to encode the message to it.
-    pub fn encode_sync<M>(&mut self, msg: M) -> io::Result<()>
+    pub fn encode_sync<M>(&mut self, msg: M) -> Result<(), SendError>
     where
         M: Encodable + Send + Sync,
     {
-        self.inner.encode_sync(msg)
+        let data = self.prepare(msg)?;
+        self.inner.encode_sync(&*data)?;
+        Ok(())
     }
 }
 
@@ -53,26 +78,28 @@ where
         Self {
             inner: AsyncEncoder::new(inner),
             activity_tx,
+            limits: Limits::NONE,
+            rate_limit: None,
         }
     }
 
     /// Encode this [Encodable] message to the writer.
-    pub async fn encode<M>(&mut self, msg: M) -> io::Result<()>
+    pub async fn encode<M>(&mut self, msg: M) -> Result<(), SendError>
     where
         M: Encodable + Send + Sync,
     {
-        self.inner.encode(msg).await?;
+        let data = self.prepare(msg)?;
+        self.inner.encode(&*data).await?;
         if self.activity_tx.send(()).await.is_err() {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::UnexpectedEof,
-                "Runner has closed its receiver",
-            ));
+            return Err(SendError::NotConnected);
         }
         Ok(())
     }
 
     /// Encode a slice of [Encodable] messages to the writer.
-    pub async fn encode_many<'a, I, M>(&mut self, msgs: I) -> io::Result<()>
+    ///
+    /// This stops at the first message that couldn't be sent.
+    pub async fn encode_many<'a, I, M>(&mut self, msgs: I) -> Result<(), SendError>
     where
         I: IntoIterator<Item = &'a M> + Send + Sync + 'a,
         I::IntoIter: Send + Sync,
@@ -84,3 +111,267 @@ where
         Ok(())
     }
 }
+
+/// Write messages with [futures::SinkExt](https://docs.rs/futures/0.3/futures/sink/trait.SinkExt.html),
+/// e.g. `writer.send(commands::join("#museun")).await`, or forward a stream of messages with `send_all`.
+///
+/// Messages are checked and buffered by `start_send`, and written by the next `poll_ready` or `poll_flush`.
+/// A [SendError] is returned as an `io::Error` wrapping it.
+#[cfg(feature = "sink")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sink")))]
+impl<W, M> Sink<M> for AsyncWriter<W>
+where
+    W: AsyncWrite + Unpin,
+    M: Encodable,
+{
+    type Error = io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Sink::<M>::poll_ready(Pin::new(&mut self.get_mut().inner), ctx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: M) -> io::Result<()> {
+        let this = self.get_mut();
+        let data = this.prepare(item)?;
+        Pin::new(&mut this.inner).start_send(data)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        futures_lite::ready!(Sink::<M>::poll_flush(Pin::new(&mut this.inner), ctx))?;
+        this.notify_activity()?;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Sink::<M>::poll_close(Pin::new(&mut self.get_mut().inner), ctx)
+    }
+}
+
+impl<W> AsyncWriter<W> {
+    /// Check the lines of messages against these limits.
+    ///
+    /// By default, no limits are checked. Note that [Limits::TWITCH] counts
+    /// bytes, while Twitch allows 500 characters in the message itself, so it
+    /// rejects some messages with multibyte characters that Twitch would take.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Take a token from this rate limit for every line written.
+    ///
+    /// The runner already queues messages for each channel to stay under
+    /// Twitch's limits, this lets you find out before a message is queued.
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(Arc::new(Mutex::new(rate_limit)));
+        self
+    }
+
+    // encode the message and check it, so nothing is written if it can't be sent
+    fn prepare<M>(&self, msg: M) -> Result<Vec<u8>, SendError>
+    where
+        M: Encodable,
+    {
+        let mut data = vec![];
+        msg.encode(&mut data)?;
+        let lines = check_lines(&data, &self.limits)?;
+
+        if let (Some(rate_limit), true) = (&self.rate_limit, lines > 0) {
+            // a token is only taken once it's available, so a poisoned lock is still consistent
+            let mut rate_limit = rate_limit.lock().unwrap_or_else(|err| err.into_inner());
+            if let Err(retry_after) = rate_limit.consume(lines) {
+                return Err(SendError::RateLimited { retry_after });
+            }
+        }
+        Ok(data)
+    }
+
+    // the runner only needs to know that something was written, so a full channel is fine
+    #[cfg(feature = "sink")]
+    fn notify_activity(&self) -> io::Result<()> {
+        match self.activity_tx.try_send(()) {
+            Err(crate::channel::TrySendError::Closed(..)) => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "Runner has closed its receiver",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+// check the lines of `data` that can be parsed, returning how many lines there are
+fn check_lines(data: &[u8], limits: &Limits) -> Result<u64, SendError> {
+    let mut lines = 0;
+    for line in data.split_inclusive(|&c| c == b'\n') {
+        lines += 1;
+
+        let line = match std::str::from_utf8(line) {
+            Ok(line) => line,
+            Err(..) => continue,
+        };
+        let msg = match IrcMessage::parse(MaybeOwned::Borrowed(line)) {
+            Ok(msg) => msg,
+            Err(MessageError::MessageTooLong { len }) => {
+                return Err(SendError::TooLong {
+                    len,
+                    max: limits.max_line.min(MaybeOwnedIndex::MAX),
+                })
+            }
+            Err(..) => continue,
+        };
+
+        match limits.check(&msg) {
+            Err(MessageError::LineTooLong { len, max })
+            | Err(MessageError::TagSectionTooLong { len, max }) => {
+                return Err(SendError::TooLong { len, max })
+            }
+            _ => {}
+        }
+
+        let command = msg.get_command();
+        if command != IrcMessage::JOIN
+            && command != IrcMessage::PART
+            && command != IrcMessage::PRIVMSG
+        {
+            continue;
+        }
+
+        let channels = msg.nth_arg(0).unwrap_or_default();
+        for channel in channels.split(',') {
+            if !is_valid_channel(channel) {
+                return Err(SendError::InvalidChannel {
+                    channel: channel.to_string(),
+                });
+            }
+        }
+    }
+    Ok(lines)
+}
+
+// a '#' followed by a login, or by the name of a chat room (`#chatrooms:<id>:<uuid>`)
+fn is_valid_channel(channel: &str) -> bool {
+    match channel.strip_prefix('#') {
+        Some(name) if !name.is_empty() => name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | ':' | '-')),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{commands, rate_limit::RateClass};
+    use std::time::Duration;
+
+    fn writer() -> (AsyncWriter<Vec<u8>>, crate::channel::Receiver<()>) {
+        let (tx, rx) = crate::channel::bounded(16);
+        (AsyncWriter::new(vec![], tx), rx)
+    }
+
+    #[test]
+    fn send_errors() {
+        let (writer, _rx) = writer();
+        let mut writer = writer
+            .with_limits(Limits::TWITCH)
+            .with_rate_limit(RateLimit::full(2, RateClass::period()));
+
+        futures_lite::future::block_on(async {
+            writer.encode(commands::join("#museun")).await.unwrap();
+
+            let long = "a".repeat(600);
+            let err = writer
+                .encode(commands::privmsg("#museun", &long))
+                .await
+                .unwrap_err();
+            assert!(matches!(err, SendError::TooLong { max: 512, .. }));
+
+            let err = writer.encode("JOIN #foo,#\r\n").await.unwrap_err();
+            assert!(matches!(err, SendError::InvalidChannel { channel } if channel == "#"));
+
+            // rejected messages don't take from the rate limit
+            writer.encode(commands::part("#museun")).await.unwrap();
+            let err = writer.encode(commands::join("#museun")).await.unwrap_err();
+            assert!(
+                matches!(err, SendError::RateLimited { retry_after } if retry_after > Duration::ZERO)
+            );
+        });
+
+        assert_eq!(
+            std::str::from_utf8(&writer.inner.writer).unwrap(),
+            "JOIN #museun\r\nPART #museun\r\n"
+        );
+    }
+
+    #[test]
+    fn multibyte() {
+        // 300 characters, but 600 bytes
+        let data = "é".repeat(300);
+        let msg = commands::privmsg("#museun", &data);
+
+        futures_lite::future::block_on(async {
+            let (mut unchecked, _rx) = writer();
+            unchecked.encode(msg).await.unwrap();
+
+            let (checked, _rx) = writer();
+            let mut checked = checked.with_limits(Limits::TWITCH);
+            let err = checked.encode(msg).await.unwrap_err();
+            assert!(matches!(err, SendError::TooLong { max: 512, .. }));
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "sink")]
+    fn sink_send_errors() {
+        let (writer, _rx) = writer();
+        let mut writer = writer
+            .with_limits(Limits::TWITCH)
+            .with_rate_limit(RateLimit::full(1, RateClass::period()));
+        let waker = futures_lite::future::block_on(futures_lite::future::poll_fn(|ctx| {
+            Poll::Ready(ctx.waker().clone())
+        }));
+        let mut ctx = Context::from_waker(&waker);
+
+        let send_error =
+            |err: io::Error| *err.into_inner().unwrap().downcast::<SendError>().unwrap();
+
+        let long = "a".repeat(600);
+        let err = Pin::new(&mut writer)
+            .start_send(commands::privmsg("#museun", &long))
+            .unwrap_err();
+        assert!(matches!(
+            send_error(err),
+            SendError::TooLong { max: 512, .. }
+        ));
+
+        Pin::new(&mut writer)
+            .start_send(commands::join("#museun"))
+            .unwrap();
+        let err = Pin::new(&mut writer)
+            .start_send(commands::join("#museun"))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        assert!(matches!(send_error(err), SendError::RateLimited { .. }));
+
+        assert!(matches!(
+            Sink::<commands::types::Join<'_>>::poll_flush(Pin::new(&mut writer), &mut ctx),
+            Poll::Ready(Ok(()))
+        ));
+        assert_eq!(
+            std::str::from_utf8(&writer.inner.writer).unwrap(),
+            "JOIN #museun\r\n"
+        );
+    }
+
+    #[test]
+    fn not_connected() {
+        let (mut writer, rx) = writer();
+        drop(rx);
+        let err = futures_lite::future::block_on(writer.encode(commands::ping("1234")));
+        assert!(matches!(err, Err(SendError::NotConnected)));
+
+        let err = io::Error::from(SendError::NotConnected);
+        assert_eq!(err.kind(), io::ErrorKind::NotConnected);
+    }
+}
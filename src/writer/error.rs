@@ -0,0 +1,83 @@
+use std::{io, time::Duration};
+
+/// An error returned when a writer refuses, or fails, to send a message
+///
+/// Apart from [SendError::Io], nothing was written when this is returned, so the
+/// message can be queued, trimmed or dropped.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SendError {
+    /// The rate limit of the writer was reached
+    RateLimited {
+        /// How long until enough of the rate limit is available again
+        retry_after: Duration,
+    },
+    /// A line of the message was longer than the limits of the writer
+    TooLong {
+        /// The length of the line, or of its tag section
+        len: usize,
+        /// The maximum length allowed
+        max: usize,
+    },
+    /// The message was for a channel with an invalid name
+    InvalidChannel {
+        /// The channel, as it was encoded
+        channel: String,
+    },
+    /// The connection, or the runner the writer belongs to, has closed
+    NotConnected,
+    /// An I/O error occurred while writing the message
+    Io(io::Error),
+}
+
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RateLimited { retry_after } => {
+                write!(f, "rate limited, retry after {:?}", retry_after)
+            }
+            Self::TooLong { len, max } => {
+                write!(f, "message too long: {} bytes (max {})", len, max)
+            }
+            Self::InvalidChannel { channel } => write!(f, "invalid channel: '{}'", channel),
+            Self::NotConnected => f.write_str("not connected"),
+            Self::Io(err) => write!(f, "io error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SendError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SendError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotConnected => Self::NotConnected,
+            _ => Self::Io(err),
+        }
+    }
+}
+
+impl From<SendError> for io::Error {
+    fn from(err: SendError) -> Self {
+        if let SendError::Io(err) = err {
+            return err;
+        }
+
+        let kind = match &err {
+            SendError::Io(..) => unreachable!(),
+            SendError::RateLimited { .. } => io::ErrorKind::WouldBlock,
+            SendError::TooLong { .. } | SendError::InvalidChannel { .. } => {
+                io::ErrorKind::InvalidInput
+            }
+            SendError::NotConnected => io::ErrorKind::NotConnected,
+        };
+        io::Error::new(kind, err)
+    }
+}
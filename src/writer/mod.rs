@@ -1,7 +1,20 @@
 //! A set of writers
 
-mod async_writer;
-pub use async_writer::AsyncWriter;
+cfg_async! {
+    mod async_writer;
+    pub use async_writer::AsyncWriter;
+}
 
-mod mpsc_writer;
-pub use mpsc_writer::MpscWriter;
+cfg_async! {
+    mod mpsc_writer;
+    pub use mpsc_writer::MpscWriter;
+}
+
+mod error;
+pub use error::SendError;
+
+mod sink;
+pub use sink::{BroadcastSink, MemorySink, MessageSink};
+
+mod tee;
+pub use tee::{RouteStrategy, TeeWriter};
@@ -62,7 +62,7 @@ impl MpscWriter {
         Some(tail.into_boxed_slice())
     }
 
-    fn inner_flush(&mut self) -> std::io::Result<()> {
+    fn inner_flush(&mut self) -> io::Result<()> {
         use crate::channel::TrySendError;
 
         let tail = match self.split_buf() {
@@ -76,7 +76,7 @@ impl MpscWriter {
         match self.channel.try_send(tail) {
             Ok(..) => Ok(()),
             Err(TrySendError::Closed(..)) => Err(io::Error::new(
-                io::ErrorKind::UnexpectedEof,
+                io::ErrorKind::NotConnected,
                 "writer was closed",
             )),
             Err(TrySendError::Full(..)) => unreachable!(),
@@ -0,0 +1,285 @@
+use crate::Encodable;
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+/// A destination for outgoing messages.
+///
+/// This doesn't depend on any particular IO model, so it can be implemented for
+/// the connection writer, for logs, or for test doubles alike.
+///
+/// ```
+/// use twitchchat::{commands, writer::{BroadcastSink, MemorySink, MessageSink}};
+///
+/// let log = MemorySink::default();
+/// let other = MemorySink::default();
+///
+/// // mirror everything written to both sinks
+/// let mut sink = BroadcastSink::new().with(log.clone()).with(other.clone());
+/// sink.send_encoded(commands::join("museun")).unwrap();
+/// sink.flush().unwrap();
+///
+/// assert_eq!(log.lines(), vec!["JOIN #museun"]);
+/// assert_eq!(other.lines(), vec!["JOIN #museun"]);
+/// ```
+pub trait MessageSink {
+    /// Send this raw data, it should already be `\r\n` terminated.
+    fn send_raw(&mut self, data: &[u8]) -> io::Result<()>;
+
+    /// Encode this [Encodable] message and send it.
+    fn send_encoded<M>(&mut self, msg: M) -> io::Result<()>
+    where
+        M: Encodable,
+        Self: Sized,
+    {
+        let mut buf = Vec::new();
+        msg.encode(&mut buf)?;
+        self.send_raw(&buf)
+    }
+
+    /// Flush any buffered data
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+impl<S> MessageSink for &mut S
+where
+    S: MessageSink + ?Sized,
+{
+    fn send_raw(&mut self, data: &[u8]) -> io::Result<()> {
+        (**self).send_raw(data)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+}
+
+impl<S> MessageSink for Box<S>
+where
+    S: MessageSink + ?Sized,
+{
+    fn send_raw(&mut self, data: &[u8]) -> io::Result<()> {
+        (**self).send_raw(data)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+}
+
+cfg_async! {
+impl<W> MessageSink for crate::writer::AsyncWriter<W>
+where
+    W: io::Write + Send + Sync,
+{
+    fn send_raw(&mut self, data: &[u8]) -> io::Result<()> {
+        self.encode_sync(data).map_err(Into::into)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::Write::flush(self)
+    }
+}
+}
+
+/// An in-memory sink, useful for tests and for capturing outgoing traffic.
+///
+/// Clones of this share the same buffer.
+#[derive(Default, Clone)]
+pub struct MemorySink {
+    buf: Arc<Mutex<Vec<u8>>>,
+}
+
+impl std::fmt::Debug for MemorySink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemorySink").finish()
+    }
+}
+
+impl MemorySink {
+    /// Create a new, empty sink
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a copy of everything that was sent to this sink
+    pub fn data(&self) -> Vec<u8> {
+        self.lock().clone()
+    }
+
+    /// Get everything that was sent to this sink, split into lines without the trailing `\r\n`
+    pub fn lines(&self) -> Vec<String> {
+        String::from_utf8_lossy(&self.lock())
+            .split_terminator("\r\n")
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// Clear the buffer
+    pub fn clear(&self) {
+        self.lock().clear()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<u8>> {
+        // the buffer is only ever appended to, so a poisoned lock still has valid data
+        self.buf.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl MessageSink for MemorySink {
+    fn send_raw(&mut self, data: &[u8]) -> io::Result<()> {
+        self.lock().extend_from_slice(data);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A sink that mirrors everything sent to it to multiple sinks.
+///
+/// Every sink is always written to. If any of them fail, the first error is returned.
+///
+/// Use `Box<dyn MessageSink + Send>` as `S` to mix different kinds of sinks.
+#[derive(Clone)]
+pub struct BroadcastSink<S> {
+    sinks: Vec<S>,
+}
+
+impl<S> Default for BroadcastSink<S> {
+    fn default() -> Self {
+        Self { sinks: Vec::new() }
+    }
+}
+
+impl<S> std::fmt::Debug for BroadcastSink<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BroadcastSink")
+            .field("sinks", &self.sinks.len())
+            .finish()
+    }
+}
+
+impl<S> BroadcastSink<S>
+where
+    S: MessageSink,
+{
+    /// Create a new sink without any outputs
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add this sink as an output
+    pub fn with(mut self, sink: S) -> Self {
+        self.push(sink);
+        self
+    }
+
+    /// Add this sink as an output
+    pub fn push(&mut self, sink: S) {
+        self.sinks.push(sink)
+    }
+
+    /// Get the outputs of this sink
+    pub fn sinks(&self) -> &[S] {
+        &self.sinks
+    }
+
+    /// Consumes this sink, returning its outputs
+    pub fn into_inner(self) -> Vec<S> {
+        self.sinks
+    }
+
+    fn for_each(&mut self, mut func: impl FnMut(&mut S) -> io::Result<()>) -> io::Result<()> {
+        let mut result = Ok(());
+        for sink in &mut self.sinks {
+            let res = func(sink);
+            if result.is_ok() {
+                result = res;
+            }
+        }
+        result
+    }
+}
+
+impl<S> MessageSink for BroadcastSink<S>
+where
+    S: MessageSink,
+{
+    fn send_raw(&mut self, data: &[u8]) -> io::Result<()> {
+        self.for_each(|sink| sink.send_raw(data))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.for_each(MessageSink::flush)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands;
+
+    struct FailingSink;
+
+    impl MessageSink for FailingSink {
+        fn send_raw(&mut self, _data: &[u8]) -> io::Result<()> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn memory_sink() {
+        let mut sink = MemorySink::new();
+        sink.send_encoded(commands::join("museun")).unwrap();
+        sink.send_raw(b"PING :foo\r\n").unwrap();
+
+        assert_eq!(sink.lines(), vec!["JOIN #museun", "PING :foo"]);
+        assert_eq!(sink.data(), b"JOIN #museun\r\nPING :foo\r\n".to_vec());
+
+        sink.clear();
+        assert!(sink.lines().is_empty());
+    }
+
+    #[test]
+    fn broadcast_mixed_sinks() {
+        let log = MemorySink::new();
+        let mut sink = BroadcastSink::<Box<dyn MessageSink + Send>>::new()
+            .with(Box::new(FailingSink))
+            .with(Box::new(log.clone()));
+
+        let err = sink.send_encoded(commands::part("#museun")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+
+        // the other sinks still get the message
+        assert_eq!(log.lines(), vec!["PART #museun"]);
+        assert_eq!(sink.sinks().len(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn async_writer_sink() {
+        let (tx, rx) = crate::channel::unbounded();
+        let (activity_tx, _activity_rx) = crate::channel::bounded(1);
+        let mut writer =
+            crate::writer::AsyncWriter::new(crate::writer::MpscWriter::new(tx), activity_tx);
+
+        let log = MemorySink::new();
+        let mut sink = BroadcastSink::<Box<dyn MessageSink + '_>>::new()
+            .with(Box::new(&mut writer))
+            .with(Box::new(log.clone()));
+
+        sink.send_encoded(commands::join("museun")).unwrap();
+        sink.flush().unwrap();
+
+        assert_eq!(&*rx.try_recv().unwrap(), b"JOIN #museun\r\n");
+        assert_eq!(log.lines(), vec!["JOIN #museun"]);
+    }
+}
@@ -0,0 +1,370 @@
+use super::{MessageSink, SendError};
+use crate::{rate_limit::RateLimit, Encodable};
+
+use std::{io, time::Duration};
+
+/// How a [TeeWriter] picks the account for a message
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RouteStrategy {
+    /// Use the first account that can send the message, in the order they were added.
+    ///
+    /// The other accounts are only used when it is out of budget or has failed.
+    Primary,
+    /// Take turns between the accounts, skipping the ones that can't send the message
+    RoundRobin,
+}
+
+impl Default for RouteStrategy {
+    fn default() -> Self {
+        Self::Primary
+    }
+}
+
+struct Account<S> {
+    name: String,
+    sink: S,
+    budget: Option<RateLimit>,
+    failed: bool,
+}
+
+/// A writer that sends each message with one of several accounts.
+///
+/// Each account has its own [MessageSink] (usually the writer of its own
+/// runner) and an optional budget, which a line is taken from for every line
+/// sent with it. When an account is out of budget the message is sent with
+/// another one, and when its sink fails it is marked as failed and skipped
+/// until it is [restored](TeeWriter::restore).
+///
+/// Use [BroadcastSink](super::BroadcastSink) instead to send every message
+/// with every sink.
+///
+/// ```
+/// use twitchchat::{commands, rate_limit::RateLimit, writer::{MemorySink, TeeWriter}};
+/// use std::time::Duration;
+///
+/// let (main, backup) = (MemorySink::new(), MemorySink::new());
+///
+/// let mut writer = TeeWriter::new()
+///     .with_budget("main", main.clone(), RateLimit::full(1, Duration::from_secs(30)))
+///     .with("backup", backup.clone());
+///
+/// assert_eq!(writer.send(commands::privmsg("#museun", "hello")).unwrap(), "main");
+/// // main is out of budget, so this is sent by backup
+/// assert_eq!(writer.send(commands::privmsg("#museun", "world")).unwrap(), "backup");
+///
+/// assert_eq!(main.lines(), vec!["PRIVMSG #museun :hello"]);
+/// assert_eq!(backup.lines(), vec!["PRIVMSG #museun :world"]);
+/// ```
+pub struct TeeWriter<S> {
+    accounts: Vec<Account<S>>,
+    strategy: RouteStrategy,
+    next: usize,
+}
+
+impl<S> Default for TeeWriter<S> {
+    fn default() -> Self {
+        Self {
+            accounts: Vec::new(),
+            strategy: RouteStrategy::default(),
+            next: 0,
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for TeeWriter<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TeeWriter")
+            .field("accounts", &self.names().collect::<Vec<_>>())
+            .field("strategy", &self.strategy)
+            .finish()
+    }
+}
+
+impl<S> TeeWriter<S>
+where
+    S: MessageSink,
+{
+    /// Create a new writer without any accounts
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pick the account for each message with this strategy
+    pub fn with_strategy(mut self, strategy: RouteStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Add an account without a budget
+    pub fn with(mut self, name: impl Into<String>, sink: S) -> Self {
+        self.push(name, sink, None);
+        self
+    }
+
+    /// Add an account that can send as many lines as this rate limit allows
+    pub fn with_budget(mut self, name: impl Into<String>, sink: S, budget: RateLimit) -> Self {
+        self.push(name, sink, Some(budget));
+        self
+    }
+
+    /// Add an account, with an optional budget
+    pub fn push(&mut self, name: impl Into<String>, sink: S, budget: Option<RateLimit>) {
+        self.accounts.push(Account {
+            name: name.into(),
+            sink,
+            budget,
+            failed: false,
+        })
+    }
+
+    /// Send this message with one of the accounts, returning the name of the account.
+    ///
+    /// If no account could send it, this is [SendError::RateLimited] with the
+    /// shortest wait if any account was only out of budget, otherwise the error
+    /// of the last account that failed (or [SendError::NotConnected] if every
+    /// account has already failed).
+    pub fn send<M>(&mut self, msg: M) -> Result<&str, SendError>
+    where
+        M: Encodable,
+    {
+        let mut data = Vec::new();
+        msg.encode(&mut data)?;
+        self.route(&data)
+    }
+
+    /// Send this raw data with one of the accounts, returning the name of the account.
+    ///
+    /// See [TeeWriter::send]
+    pub fn route(&mut self, data: &[u8]) -> Result<&str, SendError> {
+        let lines = data.split_inclusive(|&c| c == b'\n').count() as u64;
+        let len = self.accounts.len();
+
+        let mut retry_after: Option<Duration> = None;
+        let mut error = None;
+
+        for offset in 0..len {
+            let index = match self.strategy {
+                RouteStrategy::Primary => offset,
+                RouteStrategy::RoundRobin => (self.next + offset) % len,
+            };
+            let account = &mut self.accounts[index];
+            if account.failed {
+                continue;
+            }
+
+            if let Some(budget) = &mut account.budget {
+                if let Err(wait) = budget.consume(lines) {
+                    retry_after = Some(retry_after.map_or(wait, |d| d.min(wait)));
+                    continue;
+                }
+            }
+
+            match account.sink.send_raw(data).map_err(SendError::from) {
+                Ok(()) => {
+                    self.next = (index + 1) % len;
+                    return Ok(&self.accounts[index].name);
+                }
+                // the sink has its own rate limit
+                Err(SendError::RateLimited { retry_after: wait }) => {
+                    retry_after = Some(retry_after.map_or(wait, |d| d.min(wait)));
+                }
+                // every account would reject these
+                Err(err @ SendError::TooLong { .. })
+                | Err(err @ SendError::InvalidChannel { .. }) => return Err(err),
+                Err(err) => {
+                    account.failed = true;
+                    error = Some(err);
+                }
+            }
+        }
+
+        match (retry_after, error) {
+            (Some(retry_after), _) => Err(SendError::RateLimited { retry_after }),
+            (None, Some(err)) => Err(err),
+            (None, None) => Err(SendError::NotConnected),
+        }
+    }
+
+    /// Mark the account with this name as working again, returning whether it had failed
+    pub fn restore(&mut self, name: &str) -> bool {
+        match self.accounts.iter_mut().find(|a| a.name == name) {
+            Some(account) => std::mem::replace(&mut account.failed, false),
+            None => false,
+        }
+    }
+
+    /// Stop using the account with this name, returning its sink
+    pub fn remove(&mut self, name: &str) -> Option<S> {
+        let index = self.accounts.iter().position(|a| a.name == name)?;
+        let account = self.accounts.remove(index);
+        if self.next > index {
+            self.next -= 1;
+        }
+        Some(account.sink)
+    }
+
+    /// The names of the accounts whose sinks have failed
+    pub fn failed(&self) -> impl Iterator<Item = &str> + '_ {
+        self.accounts
+            .iter()
+            .filter(|a| a.failed)
+            .map(|a| a.name.as_str())
+    }
+
+    /// How many lines the account with this name can send right now, if it has a budget
+    pub fn available(&self, name: &str) -> Option<u64> {
+        self.accounts
+            .iter()
+            .find(|a| a.name == name)?
+            .budget
+            .as_ref()
+            .map(RateLimit::get_available_tokens)
+    }
+
+    /// Get the sink of the account with this name
+    pub fn sink(&self, name: &str) -> Option<&S> {
+        self.accounts
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| &a.sink)
+    }
+
+    /// Get the sink of the account with this name, mutably
+    pub fn sink_mut(&mut self, name: &str) -> Option<&mut S> {
+        self.accounts
+            .iter_mut()
+            .find(|a| a.name == name)
+            .map(|a| &mut a.sink)
+    }
+}
+
+impl<S> TeeWriter<S> {
+    /// The names of the accounts, in the order they were added
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.accounts.iter().map(|a| a.name.as_str())
+    }
+}
+
+impl<S> MessageSink for TeeWriter<S>
+where
+    S: MessageSink,
+{
+    fn send_raw(&mut self, data: &[u8]) -> io::Result<()> {
+        self.route(data).map(drop).map_err(Into::into)
+    }
+
+    // flush every account that hasn't failed, returning the first error
+    fn flush(&mut self) -> io::Result<()> {
+        let mut result = Ok(());
+        for account in self.accounts.iter_mut().filter(|a| !a.failed) {
+            let res = account.sink.flush();
+            if result.is_ok() {
+                result = res;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{commands, writer::MemorySink};
+
+    // a sink that fails once it is closed
+    #[derive(Default, Clone)]
+    struct Closable {
+        sink: MemorySink,
+        closed: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl Closable {
+        fn close(&self) {
+            self.closed.store(true, std::sync::atomic::Ordering::SeqCst)
+        }
+    }
+
+    impl MessageSink for Closable {
+        fn send_raw(&mut self, data: &[u8]) -> io::Result<()> {
+            if self.closed.load(std::sync::atomic::Ordering::SeqCst) {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"));
+            }
+            self.sink.send_raw(data)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn budget(lines: u64) -> RateLimit {
+        RateLimit::full(lines, Duration::from_secs(30))
+    }
+
+    #[test]
+    fn budgets() {
+        let mut writer = TeeWriter::new()
+            .with_budget("main", MemorySink::new(), budget(2))
+            .with_budget("backup", MemorySink::new(), budget(1));
+
+        let mut sent = vec![];
+        for _ in 0..3 {
+            sent.push(writer.send(commands::ping("1")).unwrap().to_string());
+        }
+        assert_eq!(sent, vec!["main", "main", "backup"]);
+        assert_eq!(writer.available("main"), Some(0));
+
+        let err = writer.send(commands::ping("1")).unwrap_err();
+        assert!(
+            matches!(err, SendError::RateLimited { retry_after } if retry_after > Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn failover() {
+        let (main, backup) = (Closable::default(), Closable::default());
+        let mut writer = TeeWriter::new()
+            .with("main", main.clone())
+            .with("backup", backup.clone());
+
+        assert_eq!(writer.send(commands::join("#museun")).unwrap(), "main");
+        main.close();
+        assert_eq!(writer.send(commands::part("#museun")).unwrap(), "backup");
+        assert_eq!(writer.failed().collect::<Vec<_>>(), vec!["main"]);
+
+        backup.close();
+        let err = writer.send(commands::ping("1")).unwrap_err();
+        assert!(matches!(err, SendError::Io(err) if err.kind() == io::ErrorKind::BrokenPipe));
+        assert!(matches!(
+            writer.send(commands::ping("1")),
+            Err(SendError::NotConnected)
+        ));
+
+        assert!(writer.restore("backup"));
+        assert!(!writer.restore("backup"));
+        assert!(writer.send(commands::ping("1")).is_err());
+
+        assert_eq!(main.sink.lines(), vec!["JOIN #museun"]);
+        assert_eq!(backup.sink.lines(), vec!["PART #museun"]);
+    }
+
+    #[test]
+    fn round_robin() {
+        let mut writer = TeeWriter::new()
+            .with_strategy(RouteStrategy::RoundRobin)
+            .with("a", MemorySink::new())
+            .with("b", MemorySink::new())
+            .with_budget("c", MemorySink::new(), budget(1));
+
+        let mut sent = vec![];
+        for _ in 0..5 {
+            sent.push(writer.send(commands::ping("1")).unwrap().to_string());
+        }
+        assert_eq!(sent, vec!["a", "b", "c", "a", "b"]);
+
+        writer.remove("a").unwrap();
+        assert_eq!(writer.names().collect::<Vec<_>>(), vec!["b", "c"]);
+        assert_eq!(writer.send(commands::ping("1")).unwrap(), "b");
+        assert_eq!(writer.sink("b").unwrap().lines().len(), 3);
+    }
+}